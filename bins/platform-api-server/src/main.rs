@@ -1,5 +1,6 @@
 use anyhow::Result;
 use clap::Parser;
+use platform_api::log_redaction::{RedactingMakeWriter, Redactor};
 use platform_api::{create_router, AppConfig, AppState};
 use std::env;
 use std::net::SocketAddr;
@@ -50,7 +51,15 @@ async fn main() -> Result<()> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| args.log_level.into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(
+            // Colors would be stripped by the redacting writer anyway
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(RedactingMakeWriter::new(
+                    std::io::stdout,
+                    Redactor::from_env(),
+                )),
+        )
         .init();
 
     info!("Starting Platform API Server");
@@ -88,8 +97,29 @@ async fn main() -> Result<()> {
     let state_arc = Arc::new(state);
     platform_api::background::start_challenge_sync_task(state_arc.clone());
 
+    // Start background task to fail jobs that exceeded their timeout
+    platform_api::background::start_job_timeout_enforcer_task(state_arc.clone());
+
+    // Start background task to re-queue failed jobs that have retries left
+    platform_api::background::start_job_retry_task(state_arc.clone());
+
+    // Re-queue jobs left claimed by validators connected before the restart
+    platform_api::background::start_stale_job_cleanup_task(state_arc.clone());
+
+    // Start background task to re-queue jobs of validators that disconnected
+    platform_api::background::start_orphaned_job_task(state_arc.clone());
+
+    // Start background task to apply runtime setting changes
+    platform_api::background::start_settings_refresh_task(state_arc.clone());
+
     // Start background task to sync metagraph hotkeys from Bittensor chain
-    platform_api::background::start_metagraph_sync_task();
+    platform_api::background::start_metagraph_sync_task(state_arc.clone());
+
+    // Start background task to score validators for job routing
+    platform_api::background::start_trust_score_task(state_arc.clone());
+
+    // Start background task to start emission epochs as blocks are produced
+    platform_api::background::start_emission_epoch_task(state_arc.clone());
 
     // Create router
     let app = create_router((*state_arc).clone());
@@ -109,7 +139,12 @@ async fn main() -> Result<()> {
     } else {
         info!("Starting HTTP server on {}", addr);
         let listener = TcpListener::bind(addr).await?;
-        axum::serve(listener, app).await?;
+        // Connection info carries the peer address checked by the WebSocket CIDR filter
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await?;
     }
 
     Ok(())
@@ -131,6 +166,39 @@ fn load_config(_path: &str) -> Result<AppConfig> {
         hex::encode(key)
     };
 
+    // Comma-separated list from the environment
+    let parse_list = |var: &str| -> Option<Vec<String>> {
+        env::var(var).ok().map(|value| {
+            value
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+    };
+
+    // Largest job payload accepted from challenges, shared by the scheduler
+    // and the job distributor
+    let max_job_payload_bytes = env::var("MAX_JOB_PAYLOAD_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(platform_api_scheduler::DEFAULT_MAX_JOB_PAYLOAD_BYTES);
+
+    // Largest resources a challenge may require
+    let parse_max = |var: &str| env::var(var).ok().and_then(|s| s.parse::<u64>().ok());
+    let default_max = platform_api_builder::BuilderConfig::default().max_resources;
+    let max_resources = platform_api_models::ResourceRequirements {
+        vcpu: parse_max("MAX_CHALLENGE_VCPU")
+            .and_then(|vcpu| u32::try_from(vcpu).ok())
+            .or(default_max.vcpu),
+        memory_gb: parse_max("MAX_CHALLENGE_MEMORY_GB").or(default_max.memory_gb),
+        gpu_count: parse_max("MAX_CHALLENGE_GPUS")
+            .and_then(|gpus| u32::try_from(gpus).ok())
+            .or(default_max.gpu_count),
+        gpu_type: None,
+        disk_gb: parse_max("MAX_CHALLENGE_DISK_GB").or(default_max.disk_gb),
+    };
+
     // Encryption disabled - no longer using STORAGE_ENCRYPTION_KEY or KBS_ENCRYPTION_KEY
     tracing::info!("Storage and KBS encryption disabled");
 
@@ -148,6 +216,16 @@ fn load_config(_path: &str) -> Result<AppConfig> {
             s3_region: Some("us-east-1".to_string()),
             minio_endpoint: None,
             encryption_key: "disabled".to_string(),
+            circuit_breaker: platform_api_storage::CircuitBreakerConfig {
+                failure_threshold: env::var("DB_CIRCUIT_FAILURE_THRESHOLD")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(5),
+                recovery_window_secs: env::var("DB_CIRCUIT_RECOVERY_WINDOW_SECS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(30),
+            },
         },
         attestation_config: platform_api_attestation::TdxConfig::from_env(),
         kbs_config: platform_api_kbs::KbsConfig {
@@ -159,16 +237,95 @@ fn load_config(_path: &str) -> Result<AppConfig> {
         scheduler_config: platform_api_scheduler::SchedulerConfig {
             max_concurrent_jobs: 100,
             job_timeout: 1800,
+            runtime_timeouts: env::var("SCHEDULER_RUNTIME_TIMEOUTS")
+                .map(|s| {
+                    platform_api_scheduler::parse_runtime_timeouts(&s)
+                        .expect("Invalid SCHEDULER_RUNTIME_TIMEOUTS")
+                })
+                .unwrap_or_default(),
+            max_job_timeout: env::var("SCHEDULER_MAX_JOB_TIMEOUT_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(24 * 3600),
             retry_attempts: 3,
             retry_delay: 60,
             cleanup_interval: 300,
+            max_batch_size: env::var("SCHEDULER_MAX_BATCH_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(100),
+            priority_aging_secs: env::var("SCHEDULER_PRIORITY_AGING_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(600),
+            max_aging_boost: env::var("SCHEDULER_MAX_AGING_BOOST")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(3.0),
+            deadline_window_secs: env::var("SCHEDULER_DEADLINE_WINDOW_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(900),
+            default_challenge_quota: platform_api_scheduler::ChallengeJobQuota {
+                max_pending: env::var("SCHEDULER_MAX_PENDING_JOBS_PER_CHALLENGE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1000),
+                max_in_flight: env::var("SCHEDULER_MAX_IN_FLIGHT_JOBS_PER_CHALLENGE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(100),
+            },
+            challenge_quotas: std::collections::HashMap::new(),
+            challenge_webhooks: env::var("SCHEDULER_CHALLENGE_WEBHOOKS")
+                .map(|s| {
+                    platform_api_scheduler::parse_challenge_webhooks(&s)
+                        .expect("Invalid SCHEDULER_CHALLENGE_WEBHOOKS")
+                })
+                .unwrap_or_default(),
+            challenge_scorers: env::var("SCHEDULER_CHALLENGE_SCORERS")
+                .map(|s| {
+                    platform_api_scheduler::parse_challenge_scorers(&s)
+                        .expect("Invalid SCHEDULER_CHALLENGE_SCORERS")
+                })
+                .unwrap_or_default(),
+            reliability_half_life_secs: env::var("SCHEDULER_RELIABILITY_HALF_LIFE_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(7 * 24 * 3600),
+            max_job_payload_bytes,
         },
         builder_config: platform_api_builder::BuilderConfig {
-            build_timeout: 1800,
-            max_concurrent_builds: 10,
-            docker_registry: "localhost:5000".to_string(),
-            github_token: None,
-            build_cache_size: 1024 * 1024 * 1024, // 1GB
+            build_timeout: env::var("BUILD_TIMEOUT_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1800),
+            max_concurrent_builds: env::var("MAX_CONCURRENT_BUILDS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(10),
+            max_builds_per_owner: env::var("MAX_BUILDS_PER_OWNER")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(3),
+            docker_registry: env::var("DOCKER_REGISTRY")
+                .unwrap_or_else(|_| "localhost:5000".to_string()),
+            github_token: env::var("GITHUB_TOKEN").ok().filter(|s| !s.is_empty()),
+            github_api_url: env::var("GITHUB_API_URL").unwrap_or_else(|_| {
+                platform_api_builder::github_import::DEFAULT_GITHUB_API_URL.to_string()
+            }),
+            build_cache_size: env::var("BUILD_CACHE_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1024 * 1024 * 1024), // 1GB
+            prune_evicted_images: env::var("PRUNE_EVICTED_IMAGES").as_deref() == Ok("true"),
+            compose_hash_algorithm: env::var("COMPOSE_HASH_ALGORITHM")
+                .map(|s| s.parse().expect("Invalid COMPOSE_HASH_ALGORITHM"))
+                .unwrap_or_default(),
+            allowed_registries: parse_list("ALLOWED_IMAGE_REGISTRIES").unwrap_or_default(),
+            allowed_bind_mounts: parse_list("ALLOWED_BIND_MOUNTS")
+                .unwrap_or(platform_api_builder::BuilderConfig::default().allowed_bind_mounts),
+            max_resources,
         },
         metrics_config: platform_api::MetricsConfig {
             enabled: true,
@@ -176,5 +333,18 @@ fn load_config(_path: &str) -> Result<AppConfig> {
             path: "/metrics".to_string(),
             collect_interval: 60,
         },
+        cors_config: platform_api::middleware::cors::CorsConfig::from_env(),
+        rate_limit_config: platform_api::middleware::rate_limit::RateLimitConfig::from_env(),
+        auth_config: platform_api::middleware::auth::AuthConfig::from_env(),
+        ws_allowed_cidr_ranges: parse_list("WS_ALLOWED_CIDR_RANGES").unwrap_or_default(),
+        response_envelope_enabled: env::var("RESPONSE_ENVELOPE_ENABLED").as_deref() == Ok("true"),
+        max_job_payload_bytes,
+        // Unset or 0 sends jobs to every active validator
+        max_job_fanout: env::var("MAX_JOB_FANOUT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&fanout| fanout > 0),
+        validator_channel: platform_api::message_channel::ChannelConfig::from_env("VALIDATOR"),
+        challenge_proxy: platform_api::routes::challenge_proxy::ChallengeProxyConfig::from_env(),
     })
 }
@@ -31,7 +31,7 @@ pub async fn serve_https(
 
     // Serve with axum-server
     axum_server::bind_rustls(addr, config)
-        .serve(router.into_make_service())
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .context("Failed to serve HTTPS")?;
 
@@ -1,3 +1,4 @@
+use crate::challenge_runner::AutoStart;
 use crate::state::AppState;
 use base64::{engine::general_purpose, Engine as _};
 use chrono::{DateTime, Utc};
@@ -99,6 +100,7 @@ async fn sync_challenges_from_db(state: &AppState, pool: &PgPool) -> anyhow::Res
             resources, ports, env, emission_share, mechanism_id, weight,
             description, mermaid_chart, github_repo, dstack_image, created_at, updated_at
         FROM challenges
+        WHERE status <> 'draft'
         "#,
     )
     .persistent(false)
@@ -261,102 +263,107 @@ async fn sync_challenges_from_db(state: &AppState, pool: &PgPool) -> anyhow::Res
 
     // Auto-start new or changed challenges if ChallengeRunner is available
     // Only start challenges that we confirmed exist in the database
+    //
+    // A challenge registered twice is started once: the runner returns the
+    // instance already started. One re-registered under a new compose hash is
+    // started, and the runner stops its previous version
     if let Some(runner) = &state.challenge_runner {
-        // Filter out challenges that are already running
-        let mut challenges_to_start = Vec::new();
-        let running_challenges = runner.list_running_challenges().await;
-        let running_compose_hashes: std::collections::HashSet<String> = running_challenges
-            .iter()
-            .map(|c| c.compose_hash.clone())
-            .collect();
-
         for compose_hash in new_or_changed {
-            if !running_compose_hashes.contains(&compose_hash) {
-                challenges_to_start.push(compose_hash);
-            } else {
-                info!(
+            // Verify the challenge still exists in new_challenges (which came from DB)
+            let Some(challenge_spec) = new_challenges.get(&compose_hash) else {
+                warn!(
                     compose_hash = &compose_hash,
-                    "Challenge already running, skipping auto-start"
+                    "Challenge compose_hash not in database, skipping auto-start"
                 );
-            }
-        }
+                continue;
+            };
 
-        for compose_hash in challenges_to_start {
-            // Verify the challenge still exists in new_challenges (which came from DB)
-            if let Some(challenge_spec) = new_challenges.get(&compose_hash) {
+            if let Some(running) = runner
+                .running_instance(&challenge_spec.id.to_string(), &compose_hash)
+                .await
+            {
                 info!(
                     compose_hash = &compose_hash,
-                    "Auto-starting challenge with compose_hash"
+                    running_compose_hash = &running.compose_hash,
+                    "Challenge already running, skipping auto-start"
                 );
-                // Use the challenge_spec we already have to avoid re-querying
-                // Note: Errors here are logged but don't prevent the challenge from being marked as failed
-                // Some errors (like migration timeouts, schema check failures) are non-fatal
-
-                // Load environment variables for this challenge
-                let env_vars = match state.load_challenge_env_vars(&compose_hash).await {
-                    Ok(vars) => {
-                        if !vars.is_empty() {
-                            info!(
-                                compose_hash = &compose_hash,
-                                count = vars.len(),
-                                "Loaded {} environment variables for challenge",
-                                vars.len()
-                            );
-                        }
-                        Some(vars)
+                continue;
+            }
+
+            info!(
+                compose_hash = &compose_hash,
+                "Auto-starting challenge with compose_hash"
+            );
+            // Use the challenge_spec we already have to avoid re-querying
+            // Note: Errors here are logged but don't prevent the challenge from being marked as failed
+            // Some errors (like migration timeouts, schema check failures) are non-fatal
+
+            // Load environment variables for this challenge
+            let env_vars = match state.load_challenge_env_vars(&compose_hash).await {
+                Ok(vars) => {
+                    if !vars.is_empty() {
+                        info!(
+                            compose_hash = &compose_hash,
+                            count = vars.len(),
+                            "Loaded {} environment variables for challenge",
+                            vars.len()
+                        );
                     }
-                    Err(e) => {
+                    Some(vars)
+                }
+                Err(e) => {
+                    warn!(
+                        compose_hash = &compose_hash,
+                        error = %e,
+                        "Failed to load environment variables for challenge (continuing without them)"
+                    );
+                    None
+                }
+            };
+
+            match runner.auto_start(challenge_spec, env_vars).await {
+                Ok(AutoStart::Started(_)) => {
+                    info!(
+                        compose_hash = &compose_hash,
+                        "Challenge auto-started successfully"
+                    );
+                }
+                Ok(AutoStart::Upgraded { superseded, .. }) => {
+                    info!(
+                        compose_hash = &compose_hash,
+                        superseded_compose_hash = &superseded.compose_hash,
+                        "Challenge auto-started, previous version stopped"
+                    );
+                }
+                Ok(AutoStart::Running(running)) => {
+                    info!(
+                        compose_hash = &compose_hash,
+                        running_compose_hash = &running.compose_hash,
+                        "Challenge already running, skipping auto-start"
+                    );
+                }
+                Err(e) => {
+                    let error_str = e.to_string();
+                    // Only log as error if it's a fatal error (CVM deployment failure, etc.)
+                    // Non-fatal errors (migrations timeout, schema check) are logged as warnings
+                    if error_str.contains("Failed to check existing schema")
+                        || error_str.contains("Timeout waiting for db_version")
+                        || error_str.contains("Failed to get migrations")
+                        || error_str.contains("Failed to apply migrations")
+                    {
                         warn!(
                             compose_hash = &compose_hash,
                             error = %e,
-                            "Failed to load environment variables for challenge (continuing without them)"
+                            "Non-fatal error during challenge start (challenge will continue)"
                         );
-                        None
-                    }
-                };
-
-                match runner
-                    .run_challenge_by_compose_hash_with_spec(
-                        &compose_hash,
-                        Some(challenge_spec),
-                        env_vars,
-                    )
-                    .await
-                {
-                    Ok(_) => {
-                        info!(
+                    } else {
+                        error!(
                             compose_hash = &compose_hash,
-                            "Challenge auto-started successfully"
+                            error = %e,
+                            "Failed to auto-start challenge"
                         );
                     }
-                    Err(e) => {
-                        let error_str = e.to_string();
-                        // Only log as error if it's a fatal error (CVM deployment failure, etc.)
-                        // Non-fatal errors (migrations timeout, schema check) are logged as warnings
-                        if error_str.contains("Failed to check existing schema")
-                            || error_str.contains("Timeout waiting for db_version")
-                            || error_str.contains("Failed to get migrations")
-                            || error_str.contains("Failed to apply migrations")
-                        {
-                            warn!(
-                                compose_hash = &compose_hash,
-                                error = %e,
-                                "Non-fatal error during challenge start (challenge will continue)"
-                            );
-                        } else {
-                            error!(
-                                compose_hash = &compose_hash,
-                                error = %e,
-                                "Failed to auto-start challenge"
-                            );
-                        }
-                    }
                 }
-            } else {
-                warn!(
-                    compose_hash = &compose_hash,
-                    "Challenge compose_hash not in database, skipping auto-start"
-                );
             }
         }
     }
@@ -364,23 +371,272 @@ async fn sync_challenges_from_db(state: &AppState, pool: &PgPool) -> anyhow::Res
     Ok(())
 }
 
+/// Start background task that fails jobs which exceeded their timeout
+pub fn start_job_timeout_enforcer_task(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        info!("Starting job timeout enforcer task - checking every 30 seconds");
+
+        let mut interval = interval(Duration::from_secs(30));
+
+        loop {
+            interval.tick().await;
+
+            match state.scheduler.enforce_timeouts().await {
+                Ok(0) => {}
+                Ok(count) => info!("Timeout enforcer failed {} expired jobs", count),
+                Err(e) => error!("Failed to enforce job timeouts: {}", e),
+            }
+        }
+    });
+}
+
+/// Start background task to re-queue failed jobs that have retries left
+pub fn start_job_retry_task(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        info!("Starting job retry task - checking every 30 seconds");
+
+        let mut interval = interval(Duration::from_secs(30));
+
+        loop {
+            interval.tick().await;
+
+            match state.scheduler.retry_failed_jobs().await {
+                Ok(0) => {}
+                Ok(count) => info!("Job retry task re-queued {} failed jobs", count),
+                Err(e) => error!("Failed to retry failed jobs: {}", e),
+            }
+        }
+    });
+}
+
+/// Start background task to re-queue the jobs left claimed by validators that
+/// were connected when the platform last stopped
+///
+/// Runs once, at startup; see `SchedulerService::cleanup_stale_jobs`.
+pub fn start_stale_job_cleanup_task(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        match state.scheduler.cleanup_stale_jobs().await {
+            Ok(0) => {}
+            Ok(count) => info!("Stale job cleanup re-queued {} claimed jobs", count),
+            Err(e) => error!("Failed to clean up stale jobs: {}", e),
+        }
+    });
+}
+
+/// Start background task to re-queue the jobs of validators that disconnected
+/// and did not resume their session within its grace period
+pub fn start_orphaned_job_task(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        info!("Starting orphaned job task - checking every 30 seconds");
+
+        let mut interval = interval(Duration::from_secs(30));
+
+        loop {
+            interval.tick().await;
+
+            for hotkey in state.expire_suspended_sessions().await {
+                match state.requeue_orphaned_jobs(&hotkey).await {
+                    Ok(0) => {}
+                    Ok(count) => info!(
+                        "Re-queued {} jobs of disconnected validator {}",
+                        count, hotkey
+                    ),
+                    Err(e) => error!(
+                        "Failed to re-queue jobs of disconnected validator {}: {}",
+                        hotkey, e
+                    ),
+                }
+            }
+        }
+    });
+}
+
+/// Start background task to recompute validator trust scores
+///
+/// Scores cover the last `TRUST_SCORE_WINDOW_DAYS` days (default 7) and are
+/// refreshed every 10 minutes with the latest metagraph stakes.
+pub fn start_trust_score_task(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        use crate::routes::metagraph::get_metagraph_stakes;
+
+        let window_days = std::env::var("TRUST_SCORE_WINDOW_DAYS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(7);
+
+        info!(
+            "Starting trust score task - scoring validators over {} days every 10 minutes",
+            window_days
+        );
+
+        let mut interval = interval(Duration::from_secs(600));
+
+        loop {
+            interval.tick().await;
+
+            let stakes = get_metagraph_stakes().read().await.clone();
+            state.scheduler.update_validator_stakes(stakes).await;
+
+            match state.scheduler.refresh_trust_scores(window_days).await {
+                Ok(count) => debug!("Computed trust scores for {} validators", count),
+                Err(e) => error!("Failed to compute validator trust scores: {}", e),
+            }
+        }
+    });
+}
+
+/// Start background tasks that keep runtime settings in sync
+///
+/// Changes made on other instances arrive through `LISTEN` on
+/// `platform_settings_changed`; every change is pushed into the scheduler.
+pub fn start_settings_refresh_task(state: Arc<AppState>) {
+    let settings = state.settings.clone();
+    let base = state.config.scheduler_config.clone();
+    let scheduler = state.scheduler.clone();
+    tokio::spawn(async move {
+        settings.watch_scheduler(base, scheduler).await;
+    });
+
+    let Some(pool) = state.database_pool.clone() else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        use crate::services::settings::SETTINGS_CHANGED_CHANNEL;
+        use sqlx::postgres::PgListener;
+
+        info!("Starting settings refresh task - listening on {}", SETTINGS_CHANGED_CHANNEL);
+
+        loop {
+            let mut listener = match PgListener::connect_with(&pool).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    error!("Failed to connect settings listener: {}", e);
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    continue;
+                }
+            };
+
+            if let Err(e) = listener.listen(SETTINGS_CHANGED_CHANNEL).await {
+                error!("Failed to listen for settings changes: {}", e);
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                continue;
+            }
+
+            // Catch up on anything missed while disconnected
+            if let Err(e) = state.settings.refresh().await {
+                warn!("Failed to refresh platform settings: {}", e);
+            }
+
+            loop {
+                match listener.recv().await {
+                    Ok(notification) => {
+                        debug!("Platform setting changed: {}", notification.payload());
+                        if let Err(e) = state.settings.refresh().await {
+                            warn!("Failed to refresh platform settings: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Settings listener disconnected: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
 /// Start background task to sync metagraph hotkeys from Bittensor chain
-pub fn start_metagraph_sync_task() {
+///
+/// The neurons that changed since the previous sync are written to the
+/// `validators` table, see `refresh_metagraph_cache`.
+pub fn start_metagraph_sync_task(state: Arc<AppState>) {
     tokio::spawn(async move {
         use crate::routes::metagraph::refresh_metagraph_cache;
 
         info!("Starting metagraph sync task - refreshing from Bittensor chain every 60 seconds");
+        let pool = state.database_pool.as_deref();
 
         // Initial sync at startup
         info!("🔄 Initial metagraph sync: Loading hotkeys from Bittensor chain...");
-        refresh_metagraph_cache().await;
+        refresh_metagraph_cache(pool).await;
 
         // Refresh every 60 seconds (matching METAGRAPH_CACHE_TTL_SEC from terminal-challenge)
         let mut interval = interval(Duration::from_secs(60));
 
         loop {
             interval.tick().await;
-            refresh_metagraph_cache().await;
+            refresh_metagraph_cache(pool).await;
+        }
+    });
+}
+
+/// Start background task that starts emission epochs as the chain advances
+///
+/// Epochs last `EMISSION_EPOCH_BLOCKS` blocks (default one tempo, 360). The
+/// latest block is polled every 12 seconds, the block time; a new epoch is
+/// started once it reaches the end of the current one, with the subnet block
+/// emission of its duration as total emissions.
+pub fn start_emission_epoch_task(state: Arc<AppState>) {
+    use crate::services::emission::DEFAULT_EPOCH_BLOCKS;
+
+    let (Some(emissions), Some(bittensor)) = (state.emissions.clone(), state.bittensor.clone())
+    else {
+        warn!("Emission epochs disabled - PostgreSQL or Bittensor service not available");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let epoch_blocks = std::env::var("EMISSION_EPOCH_BLOCKS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&blocks: &u64| blocks > 0)
+            .unwrap_or(DEFAULT_EPOCH_BLOCKS);
+
+        info!(
+            "Starting emission epoch task - {} blocks per epoch",
+            epoch_blocks
+        );
+
+        let mut interval = interval(Duration::from_secs(12));
+
+        loop {
+            interval.tick().await;
+
+            let current_block = match bittensor.current_block().await {
+                Ok(block) => block,
+                Err(e) => {
+                    warn!("Failed to get latest block for emission epochs: {}", e);
+                    continue;
+                }
+            };
+
+            let due = match emissions.latest_epoch().await {
+                Ok(last) => current_block >= last.map_or(0, |epoch| epoch.end_block),
+                Err(e) => {
+                    error!("Failed to load latest emission epoch: {}", e);
+                    continue;
+                }
+            };
+            if !due {
+                continue;
+            }
+
+            let registry = state.challenge_registry.read().await.clone();
+            let total_emissions = match bittensor.calculate_subnet_emissions(&registry).await {
+                Ok(subnet) => subnet.block_emission_rao as f64 / 1e9 * epoch_blocks as f64,
+                Err(e) => {
+                    warn!("Failed to get subnet emissions for the next epoch: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = emissions
+                .advance(current_block, epoch_blocks, total_emissions)
+                .await
+            {
+                error!("Failed to start emission epoch: {}", e);
+            }
         }
     });
 }
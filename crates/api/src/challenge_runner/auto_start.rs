@@ -0,0 +1,272 @@
+//! Deduplication of challenge auto-starts
+//!
+//! The challenge sync starts the challenges it finds in the database. A
+//! challenge registered twice must not be started again: validators would be
+//! expected to run it twice. [`AutoStarts`] records the instance started for
+//! each version of a challenge, keyed by challenge id and compose hash, and
+//! the CVM it runs on once active. A challenge re-registered under a new
+//! compose hash is a new version: it is started, and the instance of the
+//! version it supersedes is handed back to be stopped.
+
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+use super::runner::types::ChallengeInstance;
+
+/// Outcome of [`AutoStarts::start`]
+#[derive(Debug, Clone)]
+pub enum AutoStart {
+    /// The instance was recorded and is to be started
+    Started(ChallengeInstance),
+    /// The instance was recorded and is to be started, in place of the
+    /// instance of a previous version of the challenge, which is to be stopped
+    Upgraded {
+        instance: ChallengeInstance,
+        superseded: ChallengeInstance,
+    },
+    /// An instance of the same challenge version was already started
+    Running(ChallengeInstance),
+}
+
+impl AutoStart {
+    pub fn instance(&self) -> &ChallengeInstance {
+        match self {
+            AutoStart::Started(instance)
+            | AutoStart::Upgraded { instance, .. }
+            | AutoStart::Running(instance) => instance,
+        }
+    }
+}
+
+/// Instances started by auto-start
+#[derive(Debug, Default)]
+pub struct AutoStarts {
+    instances: RwLock<HashMap<(String, String), ChallengeInstance>>, // Key: (challenge_id, compose_hash)
+}
+
+impl AutoStarts {
+    /// Record `instance` as started, unless an instance with its challenge id
+    /// and compose hash already is, or one with its compose hash under another
+    /// challenge id: that one is returned instead
+    ///
+    /// An instance of the challenge under another compose hash is superseded
+    /// and forgotten.
+    pub async fn start(&self, instance: ChallengeInstance) -> AutoStart {
+        let mut instances = self.instances.write().await;
+        if let Some(existing) = find(&instances, &instance.challenge_id, &instance.compose_hash) {
+            return AutoStart::Running(existing.clone());
+        }
+
+        let superseded = instances
+            .keys()
+            .find(|(challenge_id, _)| *challenge_id == instance.challenge_id)
+            .cloned()
+            .and_then(|key| instances.remove(&key));
+        instances.insert(key(&instance), instance.clone());
+        match superseded {
+            Some(superseded) => AutoStart::Upgraded {
+                instance,
+                superseded,
+            },
+            None => AutoStart::Started(instance),
+        }
+    }
+
+    /// Instance started with the challenge id and compose hash, or with the
+    /// compose hash under another challenge id, if any
+    pub async fn get(&self, challenge_id: &str, compose_hash: &str) -> Option<ChallengeInstance> {
+        find(&*self.instances.read().await, challenge_id, compose_hash).cloned()
+    }
+
+    /// Record the CVM the instance of `challenge_id` with `compose_hash` runs
+    /// on, once its lifecycle made it active
+    ///
+    /// False when no such instance was started, or it was superseded since.
+    pub async fn set_running(
+        &self,
+        challenge_id: &str,
+        compose_hash: &str,
+        cvm_instance_id: String,
+        cvm_api_url: String,
+    ) -> bool {
+        let mut instances = self.instances.write().await;
+        let key = (challenge_id.to_string(), compose_hash.to_string());
+        let Some(instance) = instances.get_mut(&key) else {
+            return false;
+        };
+        instance.cvm_instance_id = Some(cvm_instance_id);
+        instance.cvm_api_url = Some(cvm_api_url);
+        instance.is_running = true;
+        true
+    }
+
+    /// Forget the instance with `compose_hash`, so that it can be started again
+    pub async fn finish(&self, compose_hash: &str) -> Option<ChallengeInstance> {
+        let mut instances = self.instances.write().await;
+        let key = instances
+            .keys()
+            .find(|(_, hash)| hash == compose_hash)?
+            .clone();
+        instances.remove(&key)
+    }
+
+    pub async fn running(&self) -> Vec<ChallengeInstance> {
+        self.instances.read().await.values().cloned().collect()
+    }
+}
+
+fn key(instance: &ChallengeInstance) -> (String, String) {
+    (instance.challenge_id.clone(), instance.compose_hash.clone())
+}
+
+fn find<'a>(
+    instances: &'a HashMap<(String, String), ChallengeInstance>,
+    challenge_id: &str,
+    compose_hash: &str,
+) -> Option<&'a ChallengeInstance> {
+    instances
+        .get(&(challenge_id.to_string(), compose_hash.to_string()))
+        .or_else(|| {
+            instances
+                .values()
+                .find(|instance| instance.compose_hash == compose_hash)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn instance(challenge_id: &str, compose_hash: &str) -> ChallengeInstance {
+        ChallengeInstance {
+            challenge_id: challenge_id.to_string(),
+            name: "term-challenge".to_string(),
+            version: "1.0.0".to_string(),
+            compose_hash: compose_hash.to_string(),
+            cvm_instance_id: None,
+            cvm_api_url: None,
+            schema_name: format!("challenge_{}", challenge_id),
+            db_version: None,
+            is_running: false,
+            ws_started: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_same_challenge_started_once() {
+        let auto_starts = Arc::new(AutoStarts::default());
+
+        // The same challenge registered twice, synced concurrently
+        let starts = futures::future::join_all((0..2).map(|_| {
+            let auto_starts = auto_starts.clone();
+            async move { auto_starts.start(instance("c1", "hash-a")).await }
+        }))
+        .await;
+        let started = starts
+            .iter()
+            .filter(|start| matches!(start, AutoStart::Started(_)))
+            .count();
+        assert_eq!(started, 1);
+        assert_eq!(auto_starts.running().await.len(), 1);
+
+        // Under another id with the same compose hash
+        let start = auto_starts.start(instance("c2", "hash-a")).await;
+        assert!(matches!(&start, AutoStart::Running(existing) if existing.challenge_id == "c1"));
+        assert_eq!(auto_starts.running().await.len(), 1);
+
+        // Other challenges are not affected
+        let start = auto_starts.start(instance("c3", "hash-c")).await;
+        assert!(matches!(start, AutoStart::Started(_)));
+    }
+
+    #[tokio::test]
+    async fn test_new_version_supersedes_the_old_one() {
+        let auto_starts = AutoStarts::default();
+        auto_starts.start(instance("c1", "hash-a")).await;
+        auto_starts
+            .set_running(
+                "c1",
+                "hash-a",
+                "vm-1".to_string(),
+                "http://10.0.0.2:8000".to_string(),
+            )
+            .await;
+
+        // Re-registered under a new compose hash
+        let start = auto_starts.start(instance("c1", "hash-b")).await;
+        let AutoStart::Upgraded {
+            instance: started,
+            superseded,
+        } = start
+        else {
+            panic!("expected an upgrade, got {:?}", start);
+        };
+        assert_eq!(started.compose_hash, "hash-b");
+        assert_eq!(superseded.compose_hash, "hash-a");
+        assert_eq!(superseded.cvm_instance_id.as_deref(), Some("vm-1"));
+
+        // Only the new version is recorded, and the old one can no longer
+        // be marked as running
+        let running = auto_starts.running().await;
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].compose_hash, "hash-b");
+        assert!(auto_starts.get("c1", "hash-a").await.is_none());
+        assert!(
+            !auto_starts
+                .set_running(
+                    "c1",
+                    "hash-a",
+                    "vm-1".to_string(),
+                    "http://10.0.0.2:8000".to_string()
+                )
+                .await
+        );
+
+        // The new version is started once
+        let start = auto_starts.start(instance("c1", "hash-b")).await;
+        assert!(matches!(start, AutoStart::Running(_)));
+    }
+
+    #[tokio::test]
+    async fn test_finished_challenge_can_start_again() {
+        let auto_starts = AutoStarts::default();
+        auto_starts.start(instance("c1", "hash-a")).await;
+
+        assert!(auto_starts.finish("hash-b").await.is_none());
+        assert_eq!(
+            auto_starts.finish("hash-a").await.unwrap().challenge_id,
+            "c1"
+        );
+        assert!(auto_starts.get("c1", "hash-a").await.is_none());
+
+        let start = auto_starts.start(instance("c1", "hash-b")).await;
+        assert!(matches!(start, AutoStart::Started(_)));
+        assert_eq!(start.instance().compose_hash, "hash-b");
+    }
+
+    #[tokio::test]
+    async fn test_running_instance_records_its_cvm() {
+        let auto_starts = AutoStarts::default();
+        auto_starts.start(instance("c1", "hash-a")).await;
+
+        // Not for another compose hash of the challenge
+        let api_url = "http://10.0.0.2:8000".to_string();
+        assert!(
+            !auto_starts
+                .set_running("c1", "hash-b", "vm-1".to_string(), api_url.clone())
+                .await
+        );
+        assert!(!auto_starts.get("c1", "hash-a").await.unwrap().is_running);
+
+        assert!(
+            auto_starts
+                .set_running("c1", "hash-a", "vm-1".to_string(), api_url.clone())
+                .await
+        );
+        let running = auto_starts.get("c1", "hash-a").await.unwrap();
+        assert!(running.is_running);
+        assert_eq!(running.cvm_instance_id.as_deref(), Some("vm-1"));
+        assert_eq!(running.cvm_api_url, Some(api_url));
+    }
+}
@@ -130,6 +130,11 @@ impl CvmManager {
         super::info::list_cvm_instances(&self.vmm_url).await
     }
 
+    /// Stop CVM instance
+    pub async fn stop_cvm(&self, instance_id: &str) -> Result<()> {
+        super::deployment::stop_cvm(&self.vmm_url, instance_id).await
+    }
+
     /// Restart CVM instance
     pub async fn restart_cvm(&self, instance_id: &str) -> Result<()> {
         info!(instance_id = instance_id, "Restarting CVM instance");
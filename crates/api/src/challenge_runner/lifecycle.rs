@@ -0,0 +1,93 @@
+//! Challenge lifecycle driven against the dstack VMM
+//!
+//! [`VmmChallengeDriver`] provisions challenge CVMs with the [`CvmManager`]
+//! and attests them over the challenge SDK WebSocket, for the
+//! `platform_api_challenge_runner` lifecycle orchestrator.
+
+use futures_util::SinkExt;
+use platform_api_challenge_runner::cvm::{ChallengeDriver, ProvisionedCvm, StepError};
+use platform_api_models::ChallengeSpec;
+
+use super::cvm_manager::{CvmInfo, CvmManager};
+use super::runner::types::ChallengeInstance;
+use super::ws::{self, ChallengeWsClient};
+
+/// Drives challenges against the dstack VMM
+pub struct VmmChallengeDriver {
+    cvm_manager: CvmManager,
+}
+
+impl VmmChallengeDriver {
+    pub fn new(cvm_manager: CvmManager) -> Self {
+        Self { cvm_manager }
+    }
+}
+
+#[async_trait::async_trait]
+impl ChallengeDriver for VmmChallengeDriver {
+    /// VMM and gateway failures are all deemed transient: a deployment the VMM
+    /// refuses for good only costs the retries
+    async fn provision(&self, challenge: &ChallengeSpec) -> Result<ProvisionedCvm, StepError> {
+        let cvm_info = self
+            .cvm_manager
+            .setup_cvm(&ChallengeInstance::from_spec(challenge))
+            .await
+            .map_err(|e| StepError::transient(format!("{:#}", e)))?;
+
+        Ok(ProvisionedCvm {
+            instance_id: cvm_info.instance_id,
+            ip_address: cvm_info.ip_address,
+            api_port: cvm_info.api_port,
+            sdk_port: cvm_info.sdk_port,
+        })
+    }
+
+    /// Failing to reach the challenge is transient, a failed handshake is not
+    async fn attest(
+        &self,
+        challenge: &ChallengeSpec,
+        cvm: &ProvisionedCvm,
+    ) -> Result<(), StepError> {
+        let mut client = ChallengeWsClient::new(
+            format!("ws://{}:{}/sdk/ws", cvm.ip_address, cvm.sdk_port),
+            "platform-api".to_string(),
+        );
+        client.compose_hash = Some(challenge.compose_hash.clone());
+
+        let (mut read, write_handle) = ws::connect(&client)
+            .await
+            .map_err(|e| StepError::transient(format!("{:#}", e)))?;
+        ws::attest(&mut read, &write_handle)
+            .await
+            .map_err(|e| StepError::permanent(format!("{:#}", e)))?;
+
+        // The session itself is opened by the runner once the challenge is active
+        let _ = write_handle.lock().await.close().await;
+        Ok(())
+    }
+
+    async fn stop(&self, cvm: &ProvisionedCvm) -> Result<(), StepError> {
+        self.cvm_manager
+            .stop_cvm(&cvm.instance_id)
+            .await
+            .map_err(|e| StepError::transient(format!("{:#}", e)))
+    }
+}
+
+/// Base URL of the challenge API on `cvm`
+pub fn api_url(cvm: &ProvisionedCvm) -> String {
+    format!("http://{}:{}", cvm.ip_address, cvm.api_port)
+}
+
+/// CVM info of `cvm`, for running the challenge on it
+pub fn cvm_info(cvm: &ProvisionedCvm) -> CvmInfo {
+    CvmInfo {
+        instance_id: cvm.instance_id.clone(),
+        ip_address: cvm.ip_address.clone(),
+        api_port: cvm.api_port,
+        sdk_port: cvm.sdk_port,
+        status: "running".to_string(),
+        created_at: chrono::Utc::now(),
+        metadata: Default::default(),
+    }
+}
@@ -1,6 +1,8 @@
 //! Refactored challenge runner module with organized components
 
+pub mod auto_start;
 pub mod challenge_ws;
+pub mod lifecycle;
 pub mod ws; // WebSocket client module (split from challenge_ws.rs)
 pub mod cvm_manager;
 pub mod migrations;
@@ -13,9 +15,15 @@ pub mod monitoring;
 
 use std::sync::Arc;
 use anyhow::Result;
+use tracing::{error, info};
+use platform_api_challenge_runner::lifecycle::{
+    ChallengeLifecycle, ChallengeState, LifecycleOrchestrator, RetryPolicy,
+};
 
+pub use auto_start::{AutoStart, AutoStarts};
 pub use challenge_ws::ChallengeWsClient;
 pub use cvm_manager::CvmManager;
+pub use lifecycle::VmmChallengeDriver;
 pub use migrations::MigrationRunner;
 pub use runner::{
     ChallengeInstance, ChallengeRunnerConfig, ChallengeMetadata, ChallengeStatus,
@@ -35,6 +43,8 @@ pub struct ChallengeRunner {
     core: Arc<ChallengeRunnerCore>,
     orchestrator: Arc<ChallengeOrchestrator>,
     monitor: Arc<ChallengeMonitor>,
+    auto_starts: Arc<AutoStarts>,
+    lifecycle: Arc<LifecycleOrchestrator>,
 }
 
 impl ChallengeRunner {
@@ -97,10 +107,18 @@ impl ChallengeRunner {
             monitoring_config,
         ));
 
+        // Create lifecycle orchestrator for auto-started challenges
+        let lifecycle = Arc::new(LifecycleOrchestrator::new(
+            Arc::new(VmmChallengeDriver::new(core.cvm_manager.clone())),
+            RetryPolicy::default(),
+        ));
+
         Self {
             core,
             orchestrator,
             monitor,
+            auto_starts: Arc::new(AutoStarts::default()),
+            lifecycle,
         }
     }
 
@@ -163,8 +181,83 @@ impl ChallengeRunner {
         ).await
     }
 
+    /// Start the challenge of `spec` unless it is already running
+    ///
+    /// A challenge version runs once: when an instance with the same challenge
+    /// id and compose hash, or the same compose hash, was started, that
+    /// instance is returned as `AutoStart::Running` and nothing is started.
+    /// The instance of a previous version of the challenge is stopped, and
+    /// returned in `AutoStart::Upgraded`.
+    ///
+    /// A started challenge goes through its lifecycle, see
+    /// [`Self::challenge_state`], and runs on its CVM once active, reachable
+    /// at the `cvm_api_url` of [`Self::list_running_challenges`]. A challenge
+    /// that fails to start, or whose run ends, is stopped and started again
+    /// by a later call.
+    pub async fn auto_start(
+        &self,
+        spec: &platform_api_models::ChallengeSpec,
+        env_vars: Option<std::collections::HashMap<String, String>>,
+    ) -> Result<AutoStart> {
+        let start = self.auto_starts.start(ChallengeInstance::from_spec(spec)).await;
+        if let AutoStart::Upgraded { superseded, .. } = &start {
+            info!(
+                challenge_id = %spec.id,
+                compose_hash = &superseded.compose_hash,
+                new_compose_hash = &spec.compose_hash,
+                "Stopping superseded challenge version"
+            );
+            self.lifecycle
+                .stop_version(spec.id, &superseded.compose_hash)
+                .await;
+            if let Err(e) = self.orchestrator.stop_challenge(&superseded.compose_hash).await {
+                error!(
+                    "Failed to stop superseded challenge {}: {}",
+                    superseded.compose_hash, e
+                );
+            }
+        }
+        if !matches!(start, AutoStart::Running(_)) {
+            let lifecycle = self.lifecycle.start(spec.clone()).await;
+            tokio::spawn(run_when_active(
+                self.core.clone(),
+                self.lifecycle.clone(),
+                self.auto_starts.clone(),
+                spec.clone(),
+                env_vars,
+                lifecycle,
+            ));
+        }
+        Ok(start)
+    }
+
+    /// Lifecycle of the auto-started challenge `challenge_id`, if it was started
+    pub async fn challenge_state(&self, challenge_id: uuid::Uuid) -> Option<ChallengeLifecycle> {
+        self.lifecycle.state(challenge_id).await
+    }
+
+    /// Instance started for the challenge id or compose hash, if any
+    pub async fn running_instance(
+        &self,
+        challenge_id: &str,
+        compose_hash: &str,
+    ) -> Option<ChallengeInstance> {
+        self.auto_starts.get(challenge_id, compose_hash).await
+    }
+
+    /// Challenges started by auto-start, with the endpoint of their CVM once
+    /// it is active
+    pub async fn list_running_challenges(&self) -> Vec<ChallengeInstance> {
+        self.auto_starts.running().await
+    }
+
     /// Stop a running challenge
     pub async fn stop_challenge(&self, compose_hash: &str) -> Result<()> {
+        if let Some(instance) = self.auto_starts.finish(compose_hash).await {
+            if let Ok(challenge_id) = instance.challenge_id.parse() {
+                self.lifecycle.stop_version(challenge_id, compose_hash).await;
+            }
+        }
         self.orchestrator.stop_challenge(compose_hash).await
     }
 
@@ -287,6 +380,50 @@ impl ChallengeRunner {
     }
 }
 
+/// Run the challenge of `spec` once its lifecycle made it active, then stop it
+///
+/// Its auto-start is finished once stopped, so that the challenge can be
+/// started again. Only this version of the challenge is stopped: a newer one
+/// that superseded it keeps running.
+async fn run_when_active(
+    core: Arc<ChallengeRunnerCore>,
+    lifecycle: Arc<LifecycleOrchestrator>,
+    auto_starts: Arc<AutoStarts>,
+    spec: platform_api_models::ChallengeSpec,
+    env_vars: Option<std::collections::HashMap<String, String>>,
+    mut state: tokio::sync::watch::Receiver<ChallengeLifecycle>,
+) {
+    let settled = state
+        .wait_for(|l| matches!(l.state, ChallengeState::Active | ChallengeState::Stopped))
+        .await
+        .map(|l| l.clone());
+
+    if let Ok(ChallengeLifecycle {
+        state: ChallengeState::Active,
+        cvm: Some(cvm),
+        ..
+    }) = settled
+    {
+        auto_starts
+            .set_running(
+                &spec.id.to_string(),
+                &spec.compose_hash,
+                cvm.instance_id.clone(),
+                lifecycle::api_url(&cvm),
+            )
+            .await;
+        if let Err(e) = core
+            .run_provisioned(&spec, env_vars, lifecycle::cvm_info(&cvm))
+            .await
+        {
+            error!("Challenge {} failed: {}", spec.compose_hash, e);
+        }
+        lifecycle.stop_version(spec.id, &spec.compose_hash).await;
+    }
+
+    auto_starts.finish(&spec.compose_hash).await;
+}
+
 /// Get system start time (placeholder)
 fn get_start_time() -> i64 {
     // In a real implementation, this would be stored when the system starts
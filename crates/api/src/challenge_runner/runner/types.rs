@@ -17,6 +17,25 @@ pub struct ChallengeInstance {
     pub ws_started: bool, // WebSocket connection to challenge CVM started
 }
 
+impl ChallengeInstance {
+    /// Instance of `spec`, not started yet
+    pub fn from_spec(spec: &platform_api_models::ChallengeSpec) -> Self {
+        let challenge_id = spec.id.to_string();
+        Self {
+            schema_name: format!("challenge_{}", challenge_id.replace('-', "_")),
+            challenge_id,
+            name: spec.name.clone(),
+            version: spec.version.clone(),
+            compose_hash: spec.compose_hash.clone(),
+            cvm_instance_id: None,
+            cvm_api_url: None,
+            db_version: None,
+            is_running: false,
+            ws_started: false,
+        }
+    }
+}
+
 /// Configuration for challenge runner
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChallengeRunnerConfig {
@@ -8,7 +8,7 @@ use std::sync::Arc;
 use tracing::{error, info, warn};
 
 use super::runner::types::{ChallengeRunnerConfig, ChallengeInstance, ChallengeMetadata, ChallengeStatus};
-use super::cvm_manager::CvmManager;
+use super::cvm_manager::{CvmInfo, CvmManager};
 use super::migrations::MigrationRunner;
 use super::challenge_ws::ChallengeWsClient;
 
@@ -100,15 +100,6 @@ impl ChallengeRunnerCore {
     ) -> Result<()> {
         info!("Starting challenge execution for compose_hash: {}", compose_hash);
 
-        // Validate challenge is not already running
-        {
-            let challenges = self.challenges.read().await;
-            if challenges.contains_key(compose_hash) {
-                warn!("Challenge {} is already running", compose_hash);
-                return Err(anyhow::anyhow!("Challenge is already running"));
-            }
-        }
-
         // Get or use provided challenge metadata
         let challenge_metadata = if let Some(spec) = challenge_spec {
             ChallengeMetadata::from_spec(spec, compose_hash.to_string())?
@@ -123,19 +114,62 @@ impl ChallengeRunnerCore {
             env_vars.unwrap_or_default(),
         );
 
-        // Store challenge instance
+        self.run_instance(instance, None).await
+    }
+
+    /// Run the challenge of `spec` on the CVM its lifecycle provisioned and
+    /// attested
+    ///
+    /// The CVM is left running: the lifecycle stops it.
+    pub async fn run_provisioned(
+        &self,
+        spec: &platform_api_models::ChallengeSpec,
+        env_vars: Option<HashMap<String, String>>,
+        cvm_info: CvmInfo,
+    ) -> Result<()> {
+        let compose_hash = spec.compose_hash.clone();
+        info!("Starting provisioned challenge for compose_hash: {}", compose_hash);
+
+        let challenge_metadata =
+            ChallengeMetadata::from_spec(serde_json::to_value(spec)?, compose_hash)?;
+        let instance = ChallengeInstance::new(
+            challenge_metadata,
+            self.config.clone(),
+            env_vars.unwrap_or_default(),
+        );
+
+        self.run_instance(instance, Some(cvm_info)).await
+    }
+
+    /// Run `instance` until its challenge completes, on `cvm_info` if given,
+    /// else on a CVM set up and cleaned up for it
+    async fn run_instance(
+        &self,
+        instance: ChallengeInstance,
+        cvm_info: Option<CvmInfo>,
+    ) -> Result<()> {
+        let compose_hash = instance.metadata.compose_hash.clone();
+
+        // Validate challenge is not already running, and store its instance
         {
             let mut challenges = self.challenges.write().await;
-            challenges.insert(compose_hash.to_string(), instance.clone());
+            if challenges.contains_key(&compose_hash) {
+                warn!("Challenge {} is already running", compose_hash);
+                return Err(anyhow::anyhow!("Challenge is already running"));
+            }
+            challenges.insert(compose_hash.clone(), instance.clone());
         }
 
         // Execute challenge
-        let result = self.execute_challenge(instance).await;
+        let result = match cvm_info {
+            Some(cvm_info) => self.run_on_cvm(&instance, &cvm_info).await,
+            None => self.execute_challenge(instance).await,
+        };
 
         // Clean up challenge instance
         {
             let mut challenges = self.challenges.write().await;
-            challenges.remove(compose_hash);
+            challenges.remove(&compose_hash);
         }
 
         match result {
@@ -159,25 +193,34 @@ impl ChallengeRunnerCore {
         let cvm_info = self.cvm_manager.setup_cvm(&instance).await
             .context("Failed to setup CVM environment")?;
 
+        self.run_on_cvm(&instance, &cvm_info).await?;
+
+        // Step 5: Cleanup CVM environment
+        info!("Cleaning up CVM environment for challenge: {}", compose_hash);
+        self.cvm_manager.cleanup_cvm(&cvm_info).await
+            .context("Failed to cleanup CVM environment")?;
+
+        Ok(())
+    }
+
+    /// Run `instance` on its CVM: migrations, then the challenge over WebSocket
+    async fn run_on_cvm(&self, instance: &ChallengeInstance, cvm_info: &CvmInfo) -> Result<()> {
+        let compose_hash = &instance.metadata.compose_hash;
+
         // Step 2: Run migrations if needed
         info!("Running migrations for challenge: {}", compose_hash);
-        self.migration_runner.run_migrations(&instance, &cvm_info).await
+        self.migration_runner.run_migrations(instance, cvm_info).await
             .context("Failed to run migrations")?;
 
         // Step 3: Establish WebSocket connection to challenge
         info!("Establishing WebSocket connection to challenge: {}", compose_hash);
-        let ws_client = self.create_websocket_client(&instance, &cvm_info).await?;
+        let ws_client = self.create_websocket_client(instance, cvm_info).await?;
         
         // Step 4: Run challenge via WebSocket
         info!("Running challenge via WebSocket: {}", compose_hash);
-        self.run_challenge_via_websocket(ws_client, &instance).await
+        self.run_challenge_via_websocket(ws_client, instance).await
             .context("Failed to run challenge via WebSocket")?;
 
-        // Step 5: Cleanup CVM environment
-        info!("Cleaning up CVM environment for challenge: {}", compose_hash);
-        self.cvm_manager.cleanup_cvm(&cvm_info).await
-            .context("Failed to cleanup CVM environment")?;
-
         Ok(())
     }
 
@@ -185,7 +228,7 @@ impl ChallengeRunnerCore {
     async fn create_websocket_client(
         &self,
         instance: &ChallengeInstance,
-        cvm_info: &super::cvm_manager::CvmInfo,
+        cvm_info: &CvmInfo,
     ) -> Result<ChallengeWsClient> {
         let ws_url = format!("ws://{}:{}/sdk/ws", 
                            cvm_info.ip_address, 
@@ -272,6 +315,7 @@ impl ChallengeRunnerCore {
             updated_at: row.updated_at,
             is_active: row.is_active,
             sdk_port: None,
+            score_bounds: Default::default(),
         })
     }
 
@@ -300,6 +344,7 @@ impl ChallengeRunnerCore {
             updated_at: row.updated_at,
             is_active: row.is_active,
             sdk_port: None,
+            score_bounds: Default::default(),
         })
     }
 
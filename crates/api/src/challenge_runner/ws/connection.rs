@@ -27,23 +27,71 @@ pub async fn connect_with_reconnect<F>(client: &ChallengeWsClient, callback: F)
 where
     F: Fn(Value, mpsc::Sender<Value>) + Send + Sync + 'static,
 {
+    let (mut read, write_handle) = connect(client).await?;
+    let key_bytes = attest(&mut read, &write_handle).await?;
+
+    // Update connection state to verified
+    let mut conn_state = ConnectionState::Verified {
+        key: ChaCha20Poly1305::new_from_slice(&key_bytes)
+            .map_err(|_| anyhow!("Failed to create encryption key"))?,
+        compose_hash: client.compose_hash.clone(),
+    };
+
+    info!("✅ TDX attestation completed, connection verified");
+
+    // Handle migrations
+    handle_migrations(&mut read, &write_handle, &mut conn_state, client).await?;
+    info!("✅ Migration process completed");
+
+    // Send initial validator list
+    send_initial_validator_list(&write_handle, &conn_state, &client.compose_hash).await?;
+
+    // Spawn validator update task
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+    spawn_validator_update_task(
+        write_handle.clone(),
+        conn_state.clone(),
+        client.compose_hash.clone(),
+        shutdown_rx,
+    ).await?;
+
+    // Enter main message loop
+    let result = handle_message_loop(read, write_handle, conn_state, client.clone(), callback).await;
+
+    // Shutdown validator update task
+    let _ = shutdown_tx.send(());
+
+    result
+}
+
+type WsStream = tokio_tungstenite::WebSocketStream<
+    tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+>;
+pub type WsRead = futures_util::stream::SplitStream<WsStream>;
+pub type WsWrite = Arc<tokio::sync::Mutex<futures_util::stream::SplitSink<WsStream, Message>>>;
+
+/// Connect the WebSocket of the challenge
+pub async fn connect(client: &ChallengeWsClient) -> Result<(WsRead, WsWrite)> {
     info!("Connecting WebSocket to {}", client.url);
 
-    // Establish WebSocket connection
     let (ws_stream, _) = connect_async(&client.url)
         .await
         .with_context(|| format!("Failed to connect WebSocket to {}", client.url))?;
-    let (write, mut read) = ws_stream.split();
-
-    // Wrap write in Arc<Mutex> to share between tasks
-    let write_handle = Arc::new(tokio::sync::Mutex::new(write));
+    let (write, read) = ws_stream.split();
 
     info!(
         "✅ Connected WebSocket to {}, starting TDX attestation",
         client.url
     );
 
-    // Begin attestation handshake
+    // Wrap write in Arc<Mutex> to share between tasks
+    Ok((read, Arc::new(tokio::sync::Mutex::new(write))))
+}
+
+/// Run the TDX attestation handshake on a new connection
+///
+/// Returns the key encrypting the rest of the session.
+pub async fn attest(read: &mut WsRead, write_handle: &WsWrite) -> Result<[u8; 32]> {
     let mut nonce_bytes = [0u8; 32];
     rand::thread_rng().fill_bytes(&mut nonce_bytes);
     let nonce_hex = hex::encode(nonce_bytes);
@@ -53,8 +101,7 @@ where
     let api_pub_bytes = api_secret.public_key().as_bytes().to_vec();
     let api_pub_b64 = base64_engine.encode(&api_pub_bytes);
 
-    // Initial connection state
-    let mut conn_state = ConnectionState::Unverified {
+    let conn_state = ConnectionState::Unverified {
         started: Instant::now(),
         nonce: nonce_bytes,
     };
@@ -74,41 +121,7 @@ where
             .context("Failed to send attestation request")?;
     }
 
-    // Perform attestation handshake
-    let key_bytes = perform_attestation(&mut read, &conn_state, api_secret, &write_handle).await?;
-    
-    // Update connection state to verified
-    conn_state = ConnectionState::Verified {
-        key: ChaCha20Poly1305::new_from_slice(&key_bytes)
-            .map_err(|_| anyhow!("Failed to create encryption key"))?,
-        compose_hash: client.compose_hash.clone(),
-    };
-
-    info!("✅ TDX attestation completed, connection verified");
-
-    // Handle migrations
-    handle_migrations(&mut read, &write_handle, &mut conn_state, client).await?;
-    info!("✅ Migration process completed");
-
-    // Send initial validator list
-    send_initial_validator_list(&write_handle, &conn_state, &client.compose_hash).await?;
-
-    // Spawn validator update task
-    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
-    spawn_validator_update_task(
-        write_handle.clone(),
-        conn_state.clone(),
-        client.compose_hash.clone(),
-        shutdown_rx,
-    ).await?;
-
-    // Enter main message loop
-    let result = handle_message_loop(read, write_handle, conn_state, client.clone(), callback).await;
-
-    // Shutdown validator update task
-    let _ = shutdown_tx.send(());
-
-    result
+    perform_attestation(read, &conn_state, api_secret, write_handle).await
 }
 
 /// Reconnection logic with exponential backoff
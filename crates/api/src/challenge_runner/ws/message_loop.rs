@@ -10,6 +10,7 @@ use tracing::{debug, error, info, warn};
 use super::encryption::{decrypt_envelope, encrypt_message};
 use super::messages::{
     handle_benchmark_progress, handle_get_validator_count, handle_orm_permissions, handle_orm_query,
+    handle_orm_bulk_insert, handle_orm_transaction,
 };
 use super::types::{ChallengeWsClient, ConnectionState, EncryptedEnvelope};
 
@@ -107,6 +108,12 @@ async fn handle_incoming_message(
                 "orm_query" => {
                     handle_orm_query(message, callback_tx.clone()).await?;
                 }
+                "orm_transaction" => {
+                    handle_orm_transaction(message, callback_tx.clone()).await?;
+                }
+                "orm_bulk_insert" => {
+                    handle_orm_bulk_insert(message, callback_tx.clone()).await?;
+                }
                 "orm_permissions" => {
                     handle_orm_permissions(message, callback_tx.clone()).await?;
                 }
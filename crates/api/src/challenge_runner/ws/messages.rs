@@ -4,6 +4,7 @@ use anyhow::{anyhow, Result};
 use base64::{engine::general_purpose::STANDARD as base64_engine, Engine as _};
 use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit};
 use futures_util::SinkExt;
+use platform_api_orm_gateway::ChallengeSchema;
 use rand::RngCore;
 use serde_json::Value;
 use std::sync::Arc;
@@ -12,7 +13,7 @@ use tokio_tungstenite::tungstenite::Message;
 use tracing::{error, info, warn};
 
 use super::encryption::{decrypt_envelope, encrypt_message};
-use super::types::{ChallengeWsClient, EncryptedEnvelope};
+use super::types::EncryptedEnvelope;
 use crate::redis_client::{create_job_log, create_job_progress};
 
 /// Handle benchmark_progress messages for Redis logging
@@ -234,7 +235,6 @@ pub async fn handle_get_validator_count(
 /// Handle ORM queries via bridge/proxy
 pub async fn handle_orm_query(
     plain_msg: &Value,
-    client: &ChallengeWsClient,
     challenge_id: &str,
     orm_gateway: &Arc<tokio::sync::RwLock<platform_api_orm_gateway::SecureORMGateway>>,
     write_handle: &Arc<Mutex<futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>, Message>>>,
@@ -251,21 +251,7 @@ pub async fn handle_orm_query(
     // Extract ORM query from payload
     if let Some(query_json) = plain_msg.get("payload").and_then(|p| p.get("query")) {
         match serde_json::from_value::<platform_api_orm_gateway::ORMQuery>(query_json.clone()) {
-            Ok(mut orm_query) => {
-                // ALWAYS set schema to challenge schema - platform-api controls schemas
-                let schema = if let Some(schema_arc) = &client.schema_name {
-                    schema_arc.read().await.clone()
-                } else {
-                    format!("challenge_{}", challenge_id.replace('-', "_"))
-                };
-                let schema_for_log = schema.clone();
-                orm_query.schema = Some(schema);
-                info!(
-                    challenge_id = challenge_id,
-                    schema = &schema_for_log,
-                    "Platform-api set schema for ORM query (overriding any SDK-provided schema)"
-                );
-
+            Ok(orm_query) => {
                 info!(
                     challenge_id = challenge_id,
                     operation = &orm_query.operation,
@@ -273,9 +259,13 @@ pub async fn handle_orm_query(
                     "Executing ORM query via bridge"
                 );
 
-                // Execute query via ORM Gateway
+                // Platform-api controls schemas: an SDK-provided schema must be the challenge's own
                 let orm_gateway_guard = orm_gateway.read().await;
-                match orm_gateway_guard.execute_query(orm_query).await {
+                let outcome = match challenge_query_scope(challenge_id) {
+                    Ok(scope) => orm_gateway_guard.execute_scoped_query(&scope, orm_query).await,
+                    Err(e) => Err(e),
+                };
+                match outcome {
                     Ok(result) => {
                         // Include query_id if present in request
                         let mut response_msg = serde_json::json!({
@@ -322,6 +312,9 @@ pub async fn handle_orm_query(
                             "error": e.to_string(),
                             "message": e.to_string()
                         });
+                        if let Some(code) = orm_error_code(&e) {
+                            error_msg["code"] = serde_json::json!(code);
+                        }
                         
                         // Extract query_id from multiple possible locations
                         let query_id_opt = plain_msg
@@ -367,6 +360,177 @@ pub async fn handle_orm_query(
     }
 }
 
+/// Handle an ORM transaction: statements executed in one transaction, all
+/// committed or all rolled back
+pub async fn handle_orm_transaction(
+    plain_msg: &Value,
+    challenge_id: &str,
+    orm_gateway: &Arc<tokio::sync::RwLock<platform_api_orm_gateway::SecureORMGateway>>,
+    write_handle: &Arc<Mutex<futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>, Message>>>,
+    use_encryption: bool,
+    aead_key: &[u8; 32],
+) -> Result<bool> {
+    let Some(payload) = plain_msg.get("payload") else {
+        return Ok(false);
+    };
+    let transaction = match serde_json::from_value::<platform_api_orm_gateway::ORMTransaction>(
+        payload.clone(),
+    ) {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            warn!("Failed to parse ORM transaction: {}", e);
+            return Ok(false);
+        }
+    };
+
+    info!(
+        challenge_id = challenge_id,
+        statement_count = transaction.statements.len(),
+        "Executing ORM transaction via bridge"
+    );
+
+    // Platform-api controls schemas, as for single queries
+    let orm_gateway_guard = orm_gateway.read().await;
+    let outcome = match challenge_query_scope(challenge_id) {
+        Ok(scope) => {
+            orm_gateway_guard
+                .execute_scoped_transaction(&scope, transaction.statements)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    let mut response_msg = match outcome {
+        Ok(result) => serde_json::json!({
+            "type": "orm_transaction_result",
+            "committed": result.committed,
+            "results": result.results,
+            "rows_affected": result.rows_affected,
+        }),
+        Err(e) => {
+            warn!(challenge_id = challenge_id, error = %e, "ORM transaction rolled back");
+            let mut error_msg = serde_json::json!({
+                "type": "error",
+                "committed": false,
+                "error": e.to_string(),
+                "message": e.to_string()
+            });
+            if let Some(code) = orm_error_code(&e) {
+                error_msg["code"] = serde_json::json!(code);
+            }
+            error_msg
+        }
+    };
+
+    let query_id = payload
+        .get("query_id")
+        .or_else(|| plain_msg.get("message_id"))
+        .and_then(|v| v.as_str());
+    if let Some(query_id) = query_id {
+        response_msg["query_id"] = serde_json::Value::String(query_id.to_string());
+        response_msg["message_id"] = serde_json::Value::String(query_id.to_string());
+    } else {
+        warn!("ORM transaction missing query_id/message_id, response won't be matched");
+    }
+
+    send_message(write_handle, &response_msg, use_encryption, aead_key).await?;
+    Ok(true)
+}
+
+/// Handle an ORM bulk insert: rows inserted in a single statement, with
+/// conflicts failing it, skipped or updating the existing rows
+pub async fn handle_orm_bulk_insert(
+    plain_msg: &Value,
+    challenge_id: &str,
+    orm_gateway: &Arc<tokio::sync::RwLock<platform_api_orm_gateway::SecureORMGateway>>,
+    write_handle: &Arc<Mutex<futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>, Message>>>,
+    use_encryption: bool,
+    aead_key: &[u8; 32],
+) -> Result<bool> {
+    let Some(payload) = plain_msg.get("payload") else {
+        return Ok(false);
+    };
+    let bulk_insert = match serde_json::from_value::<platform_api_orm_gateway::BulkInsertQuery>(
+        payload.clone(),
+    ) {
+        Ok(bulk_insert) => bulk_insert,
+        Err(e) => {
+            warn!("Failed to parse ORM bulk insert: {}", e);
+            return Ok(false);
+        }
+    };
+
+    info!(
+        challenge_id = challenge_id,
+        table = &bulk_insert.table,
+        row_count = bulk_insert.rows.len(),
+        "Executing ORM bulk insert via bridge"
+    );
+
+    // Platform-api controls schemas, as for single queries
+    let orm_gateway_guard = orm_gateway.read().await;
+    let outcome = match challenge_query_scope(challenge_id) {
+        Ok(scope) => {
+            orm_gateway_guard
+                .execute_scoped_bulk_insert(&scope, bulk_insert)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    let mut response_msg = match outcome {
+        Ok(inserted) => serde_json::json!({
+            "type": "orm_bulk_insert_result",
+            "inserted": inserted,
+        }),
+        Err(e) => {
+            warn!(challenge_id = challenge_id, error = %e, "ORM bulk insert failed");
+            let mut error_msg = serde_json::json!({
+                "type": "error",
+                "error": e.to_string(),
+                "message": e.to_string()
+            });
+            if let Some(code) = orm_error_code(&e) {
+                error_msg["code"] = serde_json::json!(code);
+            }
+            error_msg
+        }
+    };
+
+    let query_id = payload
+        .get("query_id")
+        .or_else(|| plain_msg.get("message_id"))
+        .and_then(|v| v.as_str());
+    if let Some(query_id) = query_id {
+        response_msg["query_id"] = serde_json::Value::String(query_id.to_string());
+        response_msg["message_id"] = serde_json::Value::String(query_id.to_string());
+    } else {
+        warn!("ORM bulk insert missing query_id/message_id, response won't be matched");
+    }
+
+    send_message(write_handle, &response_msg, use_encryption, aead_key).await?;
+    Ok(true)
+}
+
+/// Schema and role the queries of a challenge are confined to, derived from
+/// its ID rather than from anything the challenge may change
+fn challenge_query_scope(challenge_id: &str) -> Result<ChallengeSchema> {
+    let challenge_id = uuid::Uuid::parse_str(challenge_id)
+        .map_err(|e| anyhow!("Invalid challenge ID '{}': {}", challenge_id, e))?;
+    Ok(ChallengeSchema::for_challenge(challenge_id))
+}
+
+/// Error code letting the SDK tell a retryable conflict or timeout from a refused query
+fn orm_error_code(e: &anyhow::Error) -> Option<&'static str> {
+    match e.downcast_ref() {
+        Some(platform_api_orm_gateway::ORMError::VersionConflict { .. }) => {
+            Some("version_conflict")
+        }
+        Some(platform_api_orm_gateway::ORMError::Timeout { .. }) => Some("timeout"),
+        Some(platform_api_orm_gateway::ORMError::Rejected { .. }) => Some("rejected"),
+        Some(platform_api_orm_gateway::ORMError::SchemaDenied { .. }) => Some("schema_denied"),
+        None => None,
+    }
+}
+
 /// Send a message (encrypted or plain text)
 pub async fn send_message(
     write_handle: &Arc<Mutex<futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>, Message>>>,
@@ -12,7 +12,7 @@ pub mod message_loop;
 pub mod validator_manager;
 
 pub use types::{ChallengeWsClient, ConnectionState, EncryptedEnvelope};
-pub use connection::connect_with_reconnect;
+pub use connection::{attest, connect, connect_with_reconnect};
 pub use tdx::verify_tdx_quote;
 pub use attestation::perform_attestation;
 pub use migration_handler::{handle_migrations, wait_for_migrations_applied, send_orm_ready};
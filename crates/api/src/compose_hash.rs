@@ -1,28 +1,27 @@
 use anyhow::{Context, Result};
+use platform_api_models::HashAlgorithm;
 use serde_yaml;
-use sha2::{Digest, Sha256};
 
-/// Calculate SHA256 hash from docker-compose file content
+/// Calculate the compose hash of docker-compose file content with `algorithm`
 ///
 /// This function calculates the hash using the dstack-specific normalization:
 /// - Parses the content as JSON
 /// - Sets docker_config to an empty object
 /// - Serializes to compact JSON
-/// - Calculates SHA256 hash
+/// - Hashes it with `algorithm`, SHA256 unless configured otherwise
 ///
 /// This hash is used to verify that validators are running the expected
 /// docker-compose configuration stored in vm_compose_configs.
-pub fn calculate_compose_hash_from_content(content: &str) -> Result<String> {
+pub fn calculate_compose_hash_from_content(
+    content: &str,
+    algorithm: HashAlgorithm,
+) -> Result<String> {
     // Normalize the content for consistent hashing
     let normalized = normalize_compose_content(content)?;
 
-    // Calculate SHA256 hash
-    let mut hasher = Sha256::new();
-    hasher.update(normalized.as_bytes());
-    let hash = hasher.finalize();
-    let hash_hex = hex::encode(hash);
+    let hash_hex = algorithm.hex_digest(normalized.as_bytes());
 
-    tracing::info!("Calculated compose hash: {}", hash_hex);
+    tracing::info!("Calculated {} compose hash: {}", algorithm, hash_hex);
 
     Ok(hash_hex)
 }
@@ -96,13 +95,13 @@ mod tests {
         let content1 = r#"{"services":{"api":{"image":"nginx"}},"version":"3.8"}"#;
         let content2 = r#"{"version":"3.8","services":{"api":{"image":"nginx"}},"docker_config":{"auths":{}}}"#;
 
-        let hash1 = calculate_compose_hash_from_content(content1).unwrap();
-        let hash2 = calculate_compose_hash_from_content(content2).unwrap();
+        let hash1 = calculate_compose_hash_from_content(content1, HashAlgorithm::Sha256).unwrap();
+        let hash2 = calculate_compose_hash_from_content(content2, HashAlgorithm::Sha256).unwrap();
 
         // Different content but should produce same hash after normalization of docker_config
         let content3 =
             r#"{"version":"3.8","services":{"api":{"image":"nginx"}},"docker_config":{}}"#;
-        let hash3 = calculate_compose_hash_from_content(content3).unwrap();
+        let hash3 = calculate_compose_hash_from_content(content3, HashAlgorithm::Sha256).unwrap();
 
         // hash1 and hash2 should be different because content1 does not have docker_config, so it is added.
         // content2 has docker_config, which is replaced.
@@ -110,11 +109,33 @@ mod tests {
         assert_ne!(hash1, hash2);
 
         let content4 = r#"{"version":"3.8","services":{"api":{"image":"nginx"}},"docker_config":{"some":"value"}}"#;
-        let hash4 = calculate_compose_hash_from_content(content4).unwrap();
+        let hash4 = calculate_compose_hash_from_content(content4, HashAlgorithm::Sha256).unwrap();
         assert_eq!(hash2, hash3);
         assert_eq!(hash3, hash4);
     }
 
+    #[test]
+    fn test_compose_hash_algorithm() {
+        let content = r#"{"version":"3.8","services":{"api":{"image":"nginx"}}}"#;
+        let normalized = normalize_compose_content(content).unwrap();
+
+        // SHA256 by default, over the normalized content
+        let sha256 =
+            calculate_compose_hash_from_content(content, HashAlgorithm::default()).unwrap();
+        assert_eq!(
+            sha256,
+            HashAlgorithm::Sha256.hex_digest(normalized.as_bytes())
+        );
+        assert_eq!(sha256.len(), 64);
+
+        let sha384 = calculate_compose_hash_from_content(content, HashAlgorithm::Sha384).unwrap();
+        assert_eq!(
+            HashAlgorithm::of_hex_digest(&sha384),
+            Some(HashAlgorithm::Sha384)
+        );
+        assert_ne!(&sha384[..64], sha256);
+    }
+
     #[test]
     fn test_normalize_compose_content_yaml() {
         // Test YAML format (as stored in vm_compose_configs)
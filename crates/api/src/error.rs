@@ -0,0 +1,87 @@
+//! Error responses of the API
+//!
+//! Services fail with a `PlatformError`; handlers returning [`ApiResult`]
+//! answer it with the status of its variant and an `ErrorResponse` body
+//! carrying the id of the request. Internal errors are logged and answered
+//! without their cause.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use platform_api_models::{ErrorResponse, PlatformError};
+
+use crate::middleware::request_id::current_request_id;
+
+/// A `PlatformError` answered as an HTTP response
+#[derive(Debug)]
+pub struct ApiError(pub PlatformError);
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+impl<E: Into<PlatformError>> From<E> for ApiError {
+    fn from(err: E) -> Self {
+        ApiError(err.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let err = self.0;
+        let status =
+            StatusCode::from_u16(err.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        match &err {
+            PlatformError::Internal(cause) => tracing::error!("Request failed: {:#}", cause),
+            err => tracing::warn!("Request refused: {}", err),
+        }
+
+        let mut body = ErrorResponse::from(err);
+        body.request_id = current_request_id();
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Lets handlers that answer other errors with a plain `Response` raise an
+/// `ApiError` with `?`
+impl From<ApiError> for Response {
+    fn from(err: ApiError) -> Self {
+        err.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platform_api_scheduler::{SchedulerConfig, SchedulerService};
+    use uuid::Uuid;
+
+    async fn error_body(response: Response) -> ErrorResponse {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_not_found_is_404() {
+        let scheduler = SchedulerService::new(&SchedulerConfig::default()).unwrap();
+        let err = scheduler.get_job(Uuid::new_v4()).await.unwrap_err();
+
+        let response = ApiError::from(err).into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = error_body(response).await;
+        assert_eq!(body.error, "not_found");
+        assert_eq!(body.code, 404);
+        assert_eq!(body.resource.as_deref(), Some("job"));
+        assert!(!body.retryable);
+    }
+
+    #[tokio::test]
+    async fn test_internal_error_hides_cause() {
+        let response = ApiError::from(anyhow::anyhow!("connection refused")).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = error_body(response).await;
+        assert_eq!(body.error, "internal");
+        assert!(!body.message.contains("connection refused"));
+    }
+}
@@ -1,30 +1,39 @@
 use axum::{
     extract::{Path, State},
+    http::HeaderMap,
     response::Json,
 };
 use uuid::Uuid;
 
+use crate::error::ApiResult;
 use crate::state::AppState;
 use platform_api_models::{
     AttestationRequest, AttestationResponse, AttestationSession, KeyReleaseRequest,
-    KeyReleaseResponse, PlatformResult,
+    KeyReleaseResponse, SessionToken,
 };
 
 /// Perform attestation handler
 pub async fn attest_handler(
     state: State<AppState>,
     request: Json<AttestationRequest>,
-) -> PlatformResult<Json<AttestationResponse>> {
+) -> ApiResult<Json<AttestationResponse>> {
     let response = state.attestation.verify_attestation(request.0).await?;
     Ok(Json(response))
 }
 
-/// Get attestation session handler
+/// Get attestation session handler, for the validator the `X-Attestation-Token` was issued to
 pub async fn get_attestation_session_handler(
     state: State<AppState>,
+    headers: HeaderMap,
     id: Path<Uuid>,
-) -> PlatformResult<Json<AttestationSession>> {
-    let session = state.attestation.get_session(*id).await?;
+) -> ApiResult<Json<AttestationSession>> {
+    let token = headers
+        .get("X-Attestation-Token")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let claims = state.attestation.verify_token_async(token).await?;
+    let validator_hotkey = claims["session_namespace"].as_str().unwrap_or_default();
+    let session = state.attestation.get_session(*id, validator_hotkey).await?;
     Ok(Json(session))
 }
 
@@ -32,7 +41,7 @@ pub async fn get_attestation_session_handler(
 pub async fn release_key_handler(
     state: State<AppState>,
     request: Json<KeyReleaseRequest>,
-) -> PlatformResult<Json<KeyReleaseResponse>> {
+) -> ApiResult<Json<KeyReleaseResponse>> {
     let response = state.kbs.release_key(request.0).await?;
     Ok(Json(response))
 }
@@ -41,7 +50,7 @@ pub async fn release_key_handler(
 pub async fn verify_key_handler(
     state: State<AppState>,
     request: Json<VerifyKeyRequest>,
-) -> PlatformResult<Json<platform_api_kbs::VerifyKeyResponse>> {
+) -> ApiResult<Json<platform_api_kbs::VerifyKeyResponse>> {
     let kbs_request = platform_api_kbs::VerifyKeyRequest {
         key_id: request.key_id.clone(),
         session_token: request.session_token.clone(),
@@ -54,7 +63,7 @@ pub async fn verify_key_handler(
 /// List policies handler
 pub async fn list_policies_handler(
     state: State<AppState>,
-) -> PlatformResult<Json<Vec<platform_api_models::AttestationPolicy>>> {
+) -> ApiResult<Json<Vec<platform_api_models::AttestationPolicy>>> {
     let policies = state.attestation.list_policies().await?;
     Ok(Json(policies))
 }
@@ -63,7 +72,7 @@ pub async fn list_policies_handler(
 pub async fn get_policy_handler(
     state: State<AppState>,
     id: Path<String>,
-) -> PlatformResult<Json<platform_api_models::AttestationPolicy>> {
+) -> ApiResult<Json<platform_api_models::AttestationPolicy>> {
     let policy = state.attestation.get_policy(&id).await?;
     Ok(Json(policy))
 }
@@ -72,7 +81,7 @@ pub async fn get_policy_handler(
 #[derive(Debug, serde::Deserialize)]
 pub struct VerifyKeyRequest {
     pub key_id: String,
-    pub session_token: String,
+    pub session_token: SessionToken,
     pub policy: String,
 }
 
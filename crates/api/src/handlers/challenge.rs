@@ -5,17 +5,18 @@ use axum::{
 };
 use uuid::Uuid;
 
+use crate::error::ApiResult;
 use crate::state::AppState;
 use platform_api_models::{
     ChallengeDetailResponse, ChallengeListResponse, ChallengeMetadata, CreateChallengeRequest,
-    PlatformResult, UpdateChallengeRequest,
+    PlatformError, UpdateChallengeRequest,
 };
 
 /// List challenges handler
 pub async fn list_challenges_handler(
     state: State<AppState>,
     params: Query<ListChallengesParams>,
-) -> PlatformResult<Json<ChallengeListResponse>> {
+) -> ApiResult<Json<ChallengeListResponse>> {
     let challenges = state
         .storage
         .list_challenges(
@@ -23,6 +24,7 @@ pub async fn list_challenges_handler(
             params.per_page.unwrap_or(20),
             params.status.clone(),
             params.visibility.clone(),
+            None,
         )
         .await?;
 
@@ -33,16 +35,24 @@ pub async fn list_challenges_handler(
 pub async fn get_challenge_handler(
     state: State<AppState>,
     id: Path<Uuid>,
-) -> PlatformResult<Json<ChallengeDetailResponse>> {
-    let challenge = state.storage.get_challenge(*id).await?;
-    Ok(Json(challenge))
+) -> ApiResult<Json<ChallengeDetailResponse>> {
+    let challenge = state
+        .storage
+        .get_challenge(*id)
+        .await?
+        .ok_or_else(|| PlatformError::not_found("challenge", *id))?;
+    Ok(Json(ChallengeDetailResponse {
+        metadata: challenge.metadata,
+        emissions: None,
+        stats: None,
+    }))
 }
 
 /// Create challenge handler
 pub async fn create_challenge_handler(
     state: State<AppState>,
     request: Json<CreateChallengeRequest>,
-) -> PlatformResult<Json<ChallengeMetadata>> {
+) -> ApiResult<Json<ChallengeMetadata>> {
     let challenge = state.builder.create_challenge(request.0).await?;
     Ok(Json(challenge))
 }
@@ -52,7 +62,7 @@ pub async fn update_challenge_handler(
     state: State<AppState>,
     id: Path<Uuid>,
     request: Json<UpdateChallengeRequest>,
-) -> PlatformResult<Json<ChallengeMetadata>> {
+) -> ApiResult<Json<ChallengeMetadata>> {
     let challenge = state.builder.update_challenge(*id, request.0).await?;
     Ok(Json(challenge))
 }
@@ -61,7 +71,7 @@ pub async fn update_challenge_handler(
 pub async fn delete_challenge_handler(
     state: State<AppState>,
     id: Path<Uuid>,
-) -> PlatformResult<StatusCode> {
+) -> ApiResult<StatusCode> {
     state.builder.delete_challenge(*id).await?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -70,7 +80,7 @@ pub async fn delete_challenge_handler(
 pub async fn get_challenge_emissions_handler(
     state: State<AppState>,
     id: Path<Uuid>,
-) -> PlatformResult<Json<platform_api_models::EmissionsSchedule>> {
+) -> ApiResult<Json<platform_api_models::EmissionsSchedule>> {
     let emissions = state.storage.get_challenge_emissions(*id).await?;
     Ok(Json(emissions))
 }
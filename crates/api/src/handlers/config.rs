@@ -5,14 +5,15 @@ use axum::{
 };
 use uuid::Uuid;
 
+use crate::error::ApiResult;
 use crate::state::AppState;
 use platform_api_models::{
-    ConfigBackup, ConfigResponse, ConfigValidationResult, PlatformResult, RestoreConfigRequest,
-    SubnetConfig, UpdateConfigRequest,
+    ConfigBackup, ConfigResponse, ConfigValidationResult, RestoreConfigRequest, SubnetConfig,
+    UpdateConfigRequest,
 };
 
 /// Get subnet configuration handler
-pub async fn get_config_handler(state: State<AppState>) -> PlatformResult<Json<ConfigResponse>> {
+pub async fn get_config_handler(state: State<AppState>) -> ApiResult<Json<ConfigResponse>> {
     let config = state.storage.get_subnet_config().await?;
     Ok(Json(ConfigResponse {
         config: config.clone(),
@@ -39,7 +40,7 @@ pub async fn get_config_handler(state: State<AppState>) -> PlatformResult<Json<C
 pub async fn update_config_handler(
     state: State<AppState>,
     request: Json<UpdateConfigRequest>,
-) -> PlatformResult<Json<SubnetConfig>> {
+) -> ApiResult<Json<SubnetConfig>> {
     let current = state.storage.get_subnet_config().await.unwrap_or_default();
     let config = platform_api_models::SubnetConfig {
         owner_hotkey: request.owner_hotkey.clone().unwrap_or(current.owner_hotkey),
@@ -67,7 +68,7 @@ pub async fn update_config_handler(
 pub async fn validate_config_handler(
     state: State<AppState>,
     request: Json<UpdateConfigRequest>,
-) -> PlatformResult<Json<ConfigValidationResult>> {
+) -> ApiResult<Json<ConfigValidationResult>> {
     let result = state.storage.validate_config(&request).await?;
     Ok(Json(result))
 }
@@ -76,7 +77,7 @@ pub async fn validate_config_handler(
 pub async fn create_backup_handler(
     state: State<AppState>,
     request: Json<CreateBackupRequest>,
-) -> PlatformResult<Json<ConfigBackup>> {
+) -> ApiResult<Json<ConfigBackup>> {
     let backup = state
         .storage
         .create_config_backup(platform_api_storage::CreateBackupRequest {
@@ -91,15 +92,13 @@ pub async fn create_backup_handler(
 pub async fn restore_config_handler(
     state: State<AppState>,
     request: Json<RestoreConfigRequest>,
-) -> PlatformResult<StatusCode> {
+) -> ApiResult<StatusCode> {
     state.storage.restore_config(request.0).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
 /// List configuration backups handler
-pub async fn list_backups_handler(
-    state: State<AppState>,
-) -> PlatformResult<Json<Vec<ConfigBackup>>> {
+pub async fn list_backups_handler(state: State<AppState>) -> ApiResult<Json<Vec<ConfigBackup>>> {
     let backups = state.storage.list_config_backups().await?;
     Ok(Json(backups))
 }
@@ -108,7 +107,7 @@ pub async fn list_backups_handler(
 pub async fn get_backup_handler(
     state: State<AppState>,
     id: Path<Uuid>,
-) -> PlatformResult<Json<ConfigBackup>> {
+) -> ApiResult<Json<ConfigBackup>> {
     let backup = state.storage.get_config_backup(*id).await?;
     Ok(Json(backup))
 }
@@ -116,7 +115,7 @@ pub async fn get_backup_handler(
 /// Get configuration change history handler
 pub async fn get_config_history_handler(
     state: State<AppState>,
-) -> PlatformResult<Json<Vec<platform_api_models::ConfigChangeLog>>> {
+) -> ApiResult<Json<Vec<platform_api_models::ConfigChangeLog>>> {
     let history = state.storage.get_config_history().await?;
     Ok(Json(history))
 }
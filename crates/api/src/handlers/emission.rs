@@ -5,11 +5,12 @@ use axum::{
 };
 use uuid::Uuid;
 
+use crate::error::ApiResult;
 use crate::state::AppState;
 use platform_api_models::{
     CalculateEmissionRequest, CalculateEmissionResponse, ChallengeEmissionMetrics,
     CreateEmissionScheduleRequest, DistributeEmissionRequest, EmissionAggregate, EmissionReport,
-    EmissionSchedule, MinerEmissionMetrics, PlatformResult, UpdateEmissionScheduleRequest,
+    EmissionSchedule, MinerEmissionMetrics, UpdateEmissionScheduleRequest,
     ValidatorEmissionMetrics,
 };
 
@@ -17,7 +18,7 @@ use platform_api_models::{
 pub async fn list_emissions_handler(
     state: State<AppState>,
     params: Query<ListEmissionsParams>,
-) -> PlatformResult<Json<Vec<EmissionSchedule>>> {
+) -> ApiResult<Json<Vec<EmissionSchedule>>> {
     let emissions = state
         .storage
         .list_emission_schedules(
@@ -34,7 +35,7 @@ pub async fn list_emissions_handler(
 pub async fn get_emission_schedule_handler(
     state: State<AppState>,
     id: Path<Uuid>,
-) -> PlatformResult<Json<EmissionSchedule>> {
+) -> ApiResult<Json<EmissionSchedule>> {
     let schedule = state.storage.get_emission_schedule(*id).await?;
     Ok(Json(schedule))
 }
@@ -43,7 +44,7 @@ pub async fn get_emission_schedule_handler(
 pub async fn create_emission_schedule_handler(
     state: State<AppState>,
     request: Json<CreateEmissionScheduleRequest>,
-) -> PlatformResult<Json<EmissionSchedule>> {
+) -> ApiResult<Json<EmissionSchedule>> {
     let schedule = state.storage.create_emission_schedule(request.0).await?;
     Ok(Json(schedule))
 }
@@ -53,7 +54,7 @@ pub async fn update_emission_schedule_handler(
     state: State<AppState>,
     id: Path<Uuid>,
     request: Json<UpdateEmissionScheduleRequest>,
-) -> PlatformResult<Json<EmissionSchedule>> {
+) -> ApiResult<Json<EmissionSchedule>> {
     let schedule = state
         .storage
         .update_emission_schedule(*id, request.0)
@@ -66,7 +67,7 @@ pub async fn distribute_emission_handler(
     state: State<AppState>,
     id: Path<Uuid>,
     request: Json<DistributeEmissionRequest>,
-) -> PlatformResult<StatusCode> {
+) -> ApiResult<StatusCode> {
     state.storage.distribute_emission(*id, request.0).await?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -75,7 +76,7 @@ pub async fn distribute_emission_handler(
 pub async fn calculate_emission_handler(
     state: State<AppState>,
     request: Json<CalculateEmissionRequest>,
-) -> PlatformResult<Json<CalculateEmissionResponse>> {
+) -> ApiResult<Json<CalculateEmissionResponse>> {
     let response = state.storage.calculate_emission(request.0).await?;
     Ok(Json(response))
 }
@@ -84,7 +85,7 @@ pub async fn calculate_emission_handler(
 pub async fn get_emission_aggregate_handler(
     state: State<AppState>,
     params: Query<GetEmissionAggregateParams>,
-) -> PlatformResult<Json<EmissionAggregate>> {
+) -> ApiResult<Json<EmissionAggregate>> {
     let aggregate = state
         .storage
         .get_emission_aggregate(params.period_start, params.period_end)
@@ -97,7 +98,7 @@ pub async fn get_emission_aggregate_handler(
 pub async fn get_challenge_emission_metrics_handler(
     state: State<AppState>,
     id: Path<Uuid>,
-) -> PlatformResult<Json<ChallengeEmissionMetrics>> {
+) -> ApiResult<Json<ChallengeEmissionMetrics>> {
     let metrics = state.storage.get_challenge_emission_metrics(*id).await?;
     Ok(Json(metrics))
 }
@@ -106,7 +107,7 @@ pub async fn get_challenge_emission_metrics_handler(
 pub async fn get_validator_emission_metrics_handler(
     state: State<AppState>,
     hotkey: Path<String>,
-) -> PlatformResult<Json<ValidatorEmissionMetrics>> {
+) -> ApiResult<Json<ValidatorEmissionMetrics>> {
     let metrics = state
         .storage
         .get_validator_emission_metrics(&hotkey)
@@ -118,7 +119,7 @@ pub async fn get_validator_emission_metrics_handler(
 pub async fn get_miner_emission_metrics_handler(
     state: State<AppState>,
     hotkey: Path<String>,
-) -> PlatformResult<Json<MinerEmissionMetrics>> {
+) -> ApiResult<Json<MinerEmissionMetrics>> {
     let metrics = state.storage.get_miner_emission_metrics(&hotkey).await?;
     Ok(Json(metrics))
 }
@@ -127,7 +128,7 @@ pub async fn get_miner_emission_metrics_handler(
 pub async fn get_emission_report_handler(
     state: State<AppState>,
     params: Query<GetEmissionReportParams>,
-) -> PlatformResult<Json<EmissionReport>> {
+) -> ApiResult<Json<EmissionReport>> {
     let report = state
         .storage
         .get_emission_report(params.period_start, params.period_end)
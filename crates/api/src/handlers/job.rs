@@ -5,18 +5,18 @@ use axum::{
 };
 use uuid::Uuid;
 
+use crate::error::ApiResult;
 use crate::routes::jobs::{FailJobRequest, GetNextJobParams, ListJobsParams};
 use crate::state::AppState;
 use platform_api_models::{
-    ClaimJobRequest, ClaimJobResponse, JobListResponse, JobMetadata, JobStats, PlatformResult,
-    SubmitResultRequest,
+    ClaimJobRequest, ClaimJobResponse, JobListResponse, JobMetadata, JobStats, SubmitResultRequest,
 };
 
 /// List jobs handler
 pub async fn list_jobs_handler(
     state: State<AppState>,
     params: Query<ListJobsParams>,
-) -> PlatformResult<Json<JobListResponse>> {
+) -> ApiResult<Json<JobListResponse>> {
     let jobs = state
         .scheduler
         .list_jobs(
@@ -24,6 +24,7 @@ pub async fn list_jobs_handler(
             params.per_page.unwrap_or(20),
             params.status.clone(),
             params.challenge_id,
+            &Default::default(),
         )
         .await?;
 
@@ -34,7 +35,7 @@ pub async fn list_jobs_handler(
 pub async fn get_job_handler(
     state: State<AppState>,
     id: Path<Uuid>,
-) -> PlatformResult<Json<JobMetadata>> {
+) -> ApiResult<Json<JobMetadata>> {
     let job = state.scheduler.get_job(*id).await?;
     Ok(Json(job))
 }
@@ -43,7 +44,7 @@ pub async fn get_job_handler(
 pub async fn claim_job_handler(
     state: State<AppState>,
     request: Json<ClaimJobRequest>,
-) -> PlatformResult<Json<ClaimJobResponse>> {
+) -> ApiResult<Json<ClaimJobResponse>> {
     let response = state.scheduler.claim_job(request.0).await?;
     Ok(Json(response))
 }
@@ -53,7 +54,7 @@ pub async fn claim_specific_job_handler(
     state: State<AppState>,
     id: Path<Uuid>,
     request: Json<ClaimJobRequest>,
-) -> PlatformResult<Json<ClaimJobResponse>> {
+) -> ApiResult<Json<ClaimJobResponse>> {
     let response = state.scheduler.claim_specific_job(*id, request.0).await?;
     Ok(Json(response))
 }
@@ -63,7 +64,7 @@ pub async fn complete_job_handler(
     state: State<AppState>,
     id: Path<Uuid>,
     request: Json<SubmitResultRequest>,
-) -> PlatformResult<StatusCode> {
+) -> ApiResult<StatusCode> {
     state.scheduler.complete_job(*id, request.0).await?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -73,10 +74,12 @@ pub async fn fail_job_handler(
     state: State<AppState>,
     id: Path<Uuid>,
     request: Json<FailJobRequest>,
-) -> PlatformResult<StatusCode> {
+) -> ApiResult<StatusCode> {
     let fail_request = platform_api_models::FailJobRequest {
         reason: request.reason.clone(),
         error_details: request.error_details.clone(),
+        failure_category: request.failure_category.clone(),
+        expected_version: request.expected_version,
     };
     state.scheduler.fail_job(*id, fail_request).await?;
     Ok(StatusCode::NO_CONTENT)
@@ -86,7 +89,14 @@ pub async fn fail_job_handler(
 pub async fn get_next_job_handler(
     state: State<AppState>,
     params: Query<GetNextJobParams>,
-) -> PlatformResult<Json<Option<ClaimJobResponse>>> {
+) -> ApiResult<Json<Option<ClaimJobResponse>>> {
+    if let Some(capacity) = params.capacity {
+        state
+            .scheduler
+            .report_validator_capacity(&params.validator_hotkey, capacity)
+            .await;
+    }
+
     let job = state
         .scheduler
         .get_next_job(params.validator_hotkey.clone(), params.runtime.clone())
@@ -96,7 +106,7 @@ pub async fn get_next_job_handler(
 }
 
 /// Get job stats handler
-pub async fn get_job_stats_handler(state: State<AppState>) -> PlatformResult<Json<JobStats>> {
+pub async fn get_job_stats_handler(state: State<AppState>) -> ApiResult<Json<JobStats>> {
     let stats = state.scheduler.get_job_stats().await?;
     Ok(Json(stats))
 }
@@ -1,12 +1,17 @@
 use anyhow::{anyhow, Context, Result};
+use futures_util::future::join_all;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use tracing::{info, warn};
 
-use crate::models::JobCache;
+use crate::challenge_runner::ChallengeInstance;
+use crate::middleware::request_id::current_request_id;
+use crate::models::{JobCache, JobStatus};
 use crate::redis_client::{create_job_log, create_job_progress};
-use crate::state::AppState;
-use platform_api_models::ValidatorChallengeState;
+use crate::state::{AppState, ValidatorConnection};
+use platform_api_models::{Hotkey, ValidatorChallengeState, ValidatorChallengeStatus};
+use platform_api_scheduler::{check_payload_size, PayloadTooLarge};
 
 /// Request to send a job to validators
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,7 +30,7 @@ pub struct DistributeJobResponse {
     pub job_id: String,
     pub distributed: bool,
     pub validator_count: usize,
-    pub assigned_validators: Vec<String>,
+    pub assigned_validators: Vec<Hotkey>,
 }
 
 /// Job result from validator to forward to challenge
@@ -37,6 +42,139 @@ pub struct JobResult {
     pub validator_hotkey: Option<String>, // Validator hotkey that executed the job
 }
 
+/// Reason a job was not distributed
+#[derive(Debug, thiserror::Error)]
+pub enum DistributeError {
+    /// The payload is larger than `AppConfig::max_job_payload_bytes`
+    #[error("Job payload of {size} bytes exceeds the limit of {limit} bytes")]
+    PayloadTooLarge { size: usize, limit: usize },
+}
+
+/// Hotkeys of the validators whose state for `compose_hash` in `status_map`
+/// is Active, those of `registered` first
+///
+/// Registering with a challenge among the preferred ones is a routing hint
+/// only: a registered validator that is not connected, or whose state is not
+/// Active, e.g. suspended or draining, gets no work. Hotkeys that are not
+/// valid SS58 addresses are left out.
+fn active_validators(
+    status_map: &HashMap<String, HashMap<String, ValidatorChallengeStatus>>,
+    compose_hash: &str,
+    registered: &[String],
+) -> Vec<Hotkey> {
+    let is_active = |hotkey: &str| {
+        status_map
+            .get(hotkey)
+            .and_then(|statuses| statuses.get(compose_hash))
+            .is_some_and(|status| status.state == ValidatorChallengeState::Active)
+    };
+    let mut hotkeys: Vec<&String> = registered
+        .iter()
+        .filter(|hotkey| is_active(hotkey.as_str()))
+        .collect();
+    let mut others: Vec<&String> = status_map
+        .keys()
+        .filter(|hotkey| !registered.contains(hotkey) && is_active(hotkey.as_str()))
+        .collect();
+    others.sort();
+    hotkeys.extend(others);
+    hotkeys
+        .into_iter()
+        .filter_map(|hotkey| hotkey.parse::<Hotkey>().ok())
+        .collect()
+}
+
+/// Order `validators` by the number of running jobs of `jobs` each is
+/// assigned, fewest first, keeping their order among equals
+fn least_loaded_first(validators: &mut [Hotkey], jobs: &HashMap<String, JobCache>) {
+    let mut running: HashMap<&str, usize> = HashMap::new();
+    for job in jobs.values().filter(|job| job.status == JobStatus::Running) {
+        for hotkey in &job.assigned_validators {
+            *running.entry(hotkey.as_str()).or_default() += 1;
+        }
+    }
+    validators.sort_by_key(|hotkey| running.get(hotkey.as_str()).copied().unwrap_or(0));
+}
+
+/// Send `message` to the connected `validators` until `max_fanout` of them
+/// received it, and return those that did, in the order of `validators`
+///
+/// The message is sent to as many validators as are still needed at once, so
+/// that channels staying full under the blocking overflow policy are waited
+/// for together rather than in turn. A validator the message cannot be sent
+/// to is skipped, so the next one takes its place.
+async fn send_to_validators(
+    validators: &[Hotkey],
+    connections: &HashMap<String, ValidatorConnection>,
+    message: &str,
+    max_fanout: Option<usize>,
+) -> Vec<Hotkey> {
+    let max_fanout = max_fanout.unwrap_or(usize::MAX);
+    let mut reachable = validators.iter().filter_map(|validator_hotkey| {
+        let Some(conn) = connections.get(validator_hotkey.as_str()) else {
+            warn!(
+                validator_hotkey = %validator_hotkey,
+                "Validator connection not found"
+            );
+            return None;
+        };
+        let Some(sender) = &conn.message_sender else {
+            warn!(
+                validator_hotkey = %validator_hotkey,
+                "Validator connection has no message sender"
+            );
+            return None;
+        };
+        Some((validator_hotkey, sender))
+    });
+
+    let mut sent = Vec::new();
+    while sent.len() < max_fanout {
+        let batch: Vec<_> = reachable.by_ref().take(max_fanout - sent.len()).collect();
+        if batch.is_empty() {
+            break;
+        }
+        // Send job message via WebSocket channels
+        let results = join_all(
+            batch
+                .iter()
+                .map(|(_, sender)| sender.send(message.to_string())),
+        )
+        .await;
+        for ((validator_hotkey, _), result) in batch.into_iter().zip(results) {
+            match result {
+                Ok(()) => sent.push(validator_hotkey.clone()),
+                Err(e) => warn!(
+                    validator_hotkey = %validator_hotkey,
+                    error = %e,
+                    "Failed to send job to validator"
+                ),
+            }
+        }
+    }
+    sent
+}
+
+/// Challenge `challenge_id`, by ID or name, among the `running` challenges
+fn find_challenge<'a>(
+    running: &'a [ChallengeInstance],
+    challenge_id: &str,
+) -> Option<&'a ChallengeInstance> {
+    running
+        .iter()
+        .find(|inst| inst.challenge_id == challenge_id || inst.name == challenge_id)
+}
+
+/// URL of the `receive_job_result` endpoint of `instance`, once its CVM is
+/// active
+fn receive_job_result_url(instance: &ChallengeInstance) -> Option<String> {
+    let cvm_api_url = instance.cvm_api_url.as_ref()?;
+    Some(format!(
+        "{}/sdk/public/receive_job_result",
+        cvm_api_url.trim_end_matches('/')
+    ))
+}
+
 /// Job distributor manages distribution of jobs from challenge SDK to validators
 pub struct JobDistributor {
     state: AppState,
@@ -48,10 +186,29 @@ impl JobDistributor {
     }
 
     /// Distribute a job to active validators for a specific compose_hash
+    ///
+    /// The job goes to at most `max_job_fanout` validators, those running the
+    /// fewest jobs first. Fails with `DistributeError::PayloadTooLarge`,
+    /// before anything is sent, if the payload exceeds `max_job_payload_bytes`.
+    #[tracing::instrument(
+        name = "job_distribution",
+        skip_all,
+        fields(job_id = %request.job_id, compose_hash = %request.compose_hash)
+    )]
     pub async fn distribute_job_to_validators(
         &self,
         request: DistributeJobRequest,
     ) -> Result<DistributeJobResponse> {
+        if let Err(PayloadTooLarge { size, limit }) =
+            check_payload_size(&request.payload, self.state.config.max_job_payload_bytes)
+        {
+            warn!(
+                job_id = &request.job_id,
+                size, limit, "Refused to distribute job with an oversized payload"
+            );
+            return Err(DistributeError::PayloadTooLarge { size, limit }.into());
+        }
+
         info!(
             job_id = &request.job_id,
             compose_hash = &request.compose_hash,
@@ -76,7 +233,7 @@ impl JobDistributor {
         }
 
         // Find active validators for this compose_hash
-        let active_validators = self
+        let mut active_validators = self
             .get_active_validators_for_compose_hash(&request.compose_hash)
             .await;
 
@@ -131,6 +288,7 @@ impl JobDistributor {
                     "compose_hash": request.compose_hash,
                     "challenge_id": request.challenge_id,
                     "job_name": request.job_name,
+                    "request_id": current_request_id(),
                 })),
             );
             if let Err(e) = redis.append_job_log(&request.job_id, &log_entry).await {
@@ -146,52 +304,40 @@ impl JobDistributor {
             "payload": request.payload,
             "challenge_id": request.challenge_id,
             "compose_hash": request.compose_hash,
+            "request_id": current_request_id(),
         });
 
         let job_message_str =
             serde_json::to_string(&job_message).context("Failed to serialize job message")?;
 
-        // Send job to each active validator via WebSocket
-        let mut assigned_validators = Vec::new();
-        let validator_connections = self.state.validator_connections.read().await;
-
-        for validator_hotkey in &active_validators {
-            if let Some(conn) = validator_connections.get(validator_hotkey) {
-                if let Some(sender) = &conn.message_sender {
-                    // Send job message via WebSocket channel
-                    if let Err(e) = sender.try_send(job_message_str.clone()) {
-                        warn!(
-                            validator_hotkey = validator_hotkey,
-                            error = %e,
-                            "Failed to send job to validator"
-                        );
-                        continue;
-                    }
-
-                    job_cache.assigned_validators.push(validator_hotkey.clone());
-                    assigned_validators.push(validator_hotkey.clone());
-                    info!(
-                        job_id = &request.job_id,
-                        validator_hotkey = validator_hotkey,
-                        "Sent job to validator"
-                    );
-                } else {
-                    warn!(
-                        validator_hotkey = validator_hotkey,
-                        "Validator connection has no message sender"
-                    );
-                }
-            } else {
-                warn!(
-                    validator_hotkey = validator_hotkey,
-                    "Validator connection not found"
-                );
-            }
+        // Send job to the least loaded validators via WebSocket
+        least_loaded_first(&mut active_validators, &*self.state.job_cache.read().await);
+        // Sends may wait for room in the channels, so not under the lock
+        let validator_connections = self.state.validator_connections.read().await.clone();
+        let assigned_validators = send_to_validators(
+            &active_validators,
+            &validator_connections,
+            &job_message_str,
+            self.state.config.max_job_fanout,
+        )
+        .await;
+        for validator_hotkey in &assigned_validators {
+            job_cache
+                .assigned_validators
+                .push(validator_hotkey.to_string());
+            self.state
+                .add_assigned_job(validator_hotkey.as_str(), &request.job_id)
+                .await;
+            info!(
+                job_id = &request.job_id,
+                validator_hotkey = %validator_hotkey,
+                "Sent job to validator"
+            );
         }
 
         // Update job cache status
         if !assigned_validators.is_empty() {
-            job_cache.mark_running(assigned_validators[0].clone());
+            job_cache.mark_running(assigned_validators[0].to_string());
 
             let mut cache = self.state.job_cache.write().await;
             cache.insert(request.job_id.clone(), job_cache.clone());
@@ -270,28 +416,44 @@ impl JobDistributor {
     }
 
     /// Get list of active validator hotkeys for a specific compose_hash
-    async fn get_active_validators_for_compose_hash(&self, compose_hash: &str) -> Vec<String> {
-        let status_map = self.state.validator_challenge_status.read().await;
-        let mut validators = Vec::new();
-
-        for (hotkey, challenge_statuses) in status_map.iter() {
-            if let Some(status) = challenge_statuses.get(compose_hash) {
-                if matches!(status.state, ValidatorChallengeState::Active) {
-                    validators.push(hotkey.clone());
-                }
+    ///
+    /// Validators registered with the challenge among their preferred ones
+    /// come first, see [`active_validators`].
+    async fn get_active_validators_for_compose_hash(&self, compose_hash: &str) -> Vec<Hotkey> {
+        let mut registered = Vec::new();
+        if let Some(challenge) = self.state.get_challenge(compose_hash).await {
+            match self
+                .state
+                .registered_validators_for_challenge(challenge.id)
+                .await
+            {
+                Ok(hotkeys) => registered = hotkeys,
+                Err(e) => warn!(
+                    compose_hash = compose_hash,
+                    error = %e,
+                    "Failed to load registered validators"
+                ),
             }
         }
 
-        validators
+        let status_map = self.state.validator_challenge_status.read().await;
+        active_validators(&status_map, compose_hash, &registered)
     }
 
     /// Forward job result from validator to challenge CVM
+    #[tracing::instrument(name = "job_result_forwarding", skip_all, fields(job_id = %result.job_id))]
     pub async fn forward_job_result(&self, result: JobResult) -> Result<()> {
         info!(
             job_id = &result.job_id,
             "Forwarding job result to challenge CVM"
         );
 
+        if let Some(validator_hotkey) = &result.validator_hotkey {
+            self.state
+                .remove_assigned_job(validator_hotkey, &result.job_id)
+                .await;
+        }
+
         // Find job cache entry
         let job_cache = {
             let cache = self.state.job_cache.read().await;
@@ -361,19 +523,11 @@ impl JobDistributor {
                 let running_challenges = challenge_runner.list_running_challenges().await;
 
                 // Find challenge by challenge_id from job_cache
-                let challenge_instance = running_challenges.iter().find(|inst| {
-                    inst.challenge_id == job_cache.challenge_id
-                        || inst.name == job_cache.challenge_id
-                });
+                let challenge_instance =
+                    find_challenge(&running_challenges, &job_cache.challenge_id);
 
                 if let Some(instance) = challenge_instance {
-                    if let Some(cvm_api_url) = &instance.cvm_api_url {
-                        // Build target URL for receive_job_result endpoint
-                        let target_url = format!(
-                            "{}/sdk/public/receive_job_result",
-                            cvm_api_url.trim_end_matches('/')
-                        );
-
+                    if let Some(target_url) = receive_job_result_url(instance) {
                         // Prepare payload for receive_job_result
                         // Use validator_hotkey from result if available, otherwise use first assigned validator
                         let validator_hotkey = result
@@ -453,3 +607,227 @@ impl JobDistributor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message_channel::{message_channel, ChannelConfig, MessageSender, OverflowPolicy};
+    use chrono::Utc;
+    use platform_api_models::SessionToken;
+    use std::time::Duration;
+
+    fn connection(hotkey: &Hotkey, sender: MessageSender) -> ValidatorConnection {
+        ValidatorConnection {
+            validator_hotkey: hotkey.clone(),
+            app_id: None,
+            instance_id: None,
+            compose_hash: Some("compose-hash".to_string()),
+            connected_at: Utc::now(),
+            session_token: SessionToken::generate(),
+            last_ping: Utc::now(),
+            message_sender: Some(sender),
+            subscriptions: vec!["compose-hash".to_string()],
+            assigned_jobs: vec![],
+        }
+    }
+
+    fn running_job(job_id: &str, validators: &[&Hotkey]) -> JobCache {
+        let mut job = JobCache::new(
+            job_id.to_string(),
+            "challenge".to_string(),
+            "compose-hash".to_string(),
+            None,
+        );
+        for hotkey in validators {
+            job.mark_running(hotkey.to_string());
+        }
+        job
+    }
+
+    #[tokio::test]
+    async fn test_fanout_caps_validators_sent_the_job() {
+        let config = ChannelConfig {
+            capacity: 1,
+            overflow: OverflowPolicy::Block {
+                timeout: Duration::from_millis(10),
+            },
+        };
+        let hotkeys: Vec<Hotkey> = (0..20).map(|i| Hotkey::from_public_key(&[i; 32])).collect();
+        let mut connections = HashMap::new();
+        let mut receivers = HashMap::new();
+        for hotkey in &hotkeys {
+            let (sender, receiver) = message_channel("validator", hotkey.as_str(), config);
+            connections.insert(hotkey.to_string(), connection(hotkey, sender));
+            receivers.insert(hotkey.clone(), receiver);
+        }
+        // Validators 0 and 1 run jobs already, validator 2 cannot take more
+        // messages and validator 3 is gone
+        let jobs: HashMap<String, JobCache> = [
+            running_job("a", &[&hotkeys[0], &hotkeys[1]]),
+            running_job("b", &[&hotkeys[0]]),
+        ]
+        .into_iter()
+        .map(|job| (job.job_id.clone(), job))
+        .collect();
+        connections[hotkeys[2].as_str()]
+            .message_sender
+            .as_ref()
+            .unwrap()
+            .send("busy".to_string())
+            .await
+            .unwrap();
+        connections.remove(hotkeys[3].as_str());
+
+        let mut validators = hotkeys.clone();
+        least_loaded_first(&mut validators, &jobs);
+        assert_eq!(validators[18..], [hotkeys[1].clone(), hotkeys[0].clone()]);
+
+        let sent = send_to_validators(&validators, &connections, "job", Some(5)).await;
+        assert_eq!(sent, hotkeys[4..9]);
+        for hotkey in &sent {
+            let receiver = receivers.get_mut(hotkey).unwrap();
+            assert_eq!(receiver.recv().await.as_deref(), Some("job"));
+        }
+
+        // Without a limit every reachable validator gets the job
+        let sent = send_to_validators(&validators, &connections, "job", None).await;
+        assert_eq!(sent.len(), 18);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_full_channels_are_waited_for_together() {
+        let timeout = Duration::from_secs(1);
+        let config = ChannelConfig {
+            capacity: 1,
+            overflow: OverflowPolicy::Block { timeout },
+        };
+        let hotkeys: Vec<Hotkey> = (0..4).map(|i| Hotkey::from_public_key(&[i; 32])).collect();
+        let mut connections = HashMap::new();
+        let mut receivers = Vec::new();
+        for hotkey in &hotkeys {
+            let (sender, receiver) = message_channel("validator", hotkey.as_str(), config);
+            connections.insert(hotkey.to_string(), connection(hotkey, sender));
+            receivers.push(receiver);
+        }
+        // Validators 0 to 2 cannot take more messages
+        for hotkey in &hotkeys[..3] {
+            connections[hotkey.as_str()]
+                .message_sender
+                .as_ref()
+                .unwrap()
+                .send("busy".to_string())
+                .await
+                .unwrap();
+        }
+
+        let start = tokio::time::Instant::now();
+        let sent = send_to_validators(&hotkeys, &connections, "job", None).await;
+        assert_eq!(sent, hotkeys[3..]);
+        assert!(start.elapsed() < 2 * timeout);
+    }
+
+    #[test]
+    fn test_only_active_validators_get_work() {
+        let hotkeys: Vec<Hotkey> = (0..5).map(|i| Hotkey::from_public_key(&[i; 32])).collect();
+        let status = |hotkey: &Hotkey, compose_hash: &str, state: ValidatorChallengeState| {
+            let status = ValidatorChallengeStatus {
+                validator_hotkey: hotkey.to_string(),
+                compose_hash: compose_hash.to_string(),
+                state,
+                last_heartbeat: Utc::now(),
+                penalty_reason: None,
+            };
+            (
+                hotkey.to_string(),
+                HashMap::from([(compose_hash.to_string(), status)]),
+            )
+        };
+        // Validator 4 is registered but not connected
+        let status_map: HashMap<String, HashMap<String, ValidatorChallengeStatus>> = [
+            status(&hotkeys[0], "compose-hash", ValidatorChallengeState::Active),
+            status(&hotkeys[1], "compose-hash", ValidatorChallengeState::Active),
+            status(
+                &hotkeys[2],
+                "compose-hash",
+                ValidatorChallengeState::Recycling,
+            ),
+            status(&hotkeys[3], "other-hash", ValidatorChallengeState::Active),
+        ]
+        .into_iter()
+        .collect();
+        let registered: Vec<String> = [&hotkeys[1], &hotkeys[2], &hotkeys[3], &hotkeys[4]]
+            .iter()
+            .map(|hotkey| hotkey.to_string())
+            .collect();
+
+        assert_eq!(
+            active_validators(&status_map, "compose-hash", &registered),
+            [hotkeys[1].clone(), hotkeys[0].clone()]
+        );
+        assert_eq!(
+            active_validators(&status_map, "other-hash", &registered),
+            [hotkeys[3].clone()]
+        );
+        assert!(active_validators(&status_map, "unknown-hash", &registered).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_results_forwarded_to_the_active_challenge_version() {
+        let auto_starts = crate::challenge_runner::AutoStarts::default();
+        let instance = |compose_hash: &str| ChallengeInstance {
+            challenge_id: "c1".to_string(),
+            name: "term-challenge".to_string(),
+            version: "1.0.0".to_string(),
+            compose_hash: compose_hash.to_string(),
+            cvm_instance_id: None,
+            cvm_api_url: None,
+            schema_name: "challenge_c1".to_string(),
+            db_version: None,
+            is_running: false,
+            ws_started: false,
+        };
+        let target_url = |running: &[ChallengeInstance], challenge_id: &str| {
+            find_challenge(running, challenge_id).map(receive_job_result_url)
+        };
+
+        // Started, its CVM not active yet
+        auto_starts.start(instance("hash-a")).await;
+        assert_eq!(target_url(&auto_starts.running().await, "c1"), Some(None));
+        assert_eq!(target_url(&auto_starts.running().await, "c2"), None);
+
+        auto_starts
+            .set_running(
+                "c1",
+                "hash-a",
+                "vm-1".to_string(),
+                "http://10.0.0.2:8000/".to_string(),
+            )
+            .await;
+        for challenge_id in ["c1", "term-challenge"] {
+            assert_eq!(
+                target_url(&auto_starts.running().await, challenge_id),
+                Some(Some(
+                    "http://10.0.0.2:8000/sdk/public/receive_job_result".to_string()
+                ))
+            );
+        }
+
+        // A new version receives the results once its CVM is active
+        auto_starts.start(instance("hash-b")).await;
+        assert_eq!(target_url(&auto_starts.running().await, "c1"), Some(None));
+        auto_starts
+            .set_running(
+                "c1",
+                "hash-b",
+                "vm-2".to_string(),
+                "http://10.0.0.3:8000".to_string(),
+            )
+            .await;
+        assert_eq!(
+            target_url(&auto_starts.running().await, "c1"),
+            Some(Some(
+                "http://10.0.0.3:8000/sdk/public/receive_job_result".to_string()
+            ))
+        );
+    }
+}
@@ -1,16 +1,19 @@
 use axum::{extract::State, http::StatusCode, response::Json, Router};
 use serde_json::Value;
-use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 
 pub mod background;
 pub mod challenge_migrations;
 pub mod challenge_runner;
 pub mod compose_hash;
+pub mod error;
 pub mod handlers;
 pub mod job_distributor;
+pub mod log_redaction;
+pub mod message_channel;
 pub mod middleware;
 pub mod models;
+pub mod policy;
 // ORM Gateway moved to platform-api-orm-gateway crate
 pub mod redis_client;
 pub mod routes;
@@ -27,6 +30,7 @@ pub use state::*;
 pub fn create_router(state: AppState) -> Router {
     let router = Router::new()
         .merge(routes::challenges::create_router())
+        .merge(routes::builds::create_router())
         .merge(routes::jobs::create_router())
         .merge(routes::attestation::create_router())
         .merge(routes::results::create_router())
@@ -37,19 +41,47 @@ pub fn create_router(state: AppState) -> Router {
         // .merge(routes::pools::create_router())
         // .merge(routes::nodes::create_router())
         .merge(routes::ui::create_router())
-        .merge(routes::websocket::create_router())
+        .merge(
+            routes::websocket::create_router().layer(middleware::cidr_filter::CidrFilter::new(
+                &state.config.ws_allowed_cidr_ranges,
+            )),
+        )
         .merge(routes::challenge_credentials::create_router())
         .merge(routes::orm::create_router())
         .merge(routes::metagraph::create_router())
         .merge(routes::challenge_proxy::create_router())
         .merge(routes::mechanisms::create_router())
         .merge(routes::network::create_router())
-        .merge(routes::validators::create_router());
+        .merge(routes::validators::create_router())
+        .merge(routes::admin::create_router());
+
+    // The envelope reads the request id, so it sits inside its middleware
+    let router = if state.config.response_envelope_enabled {
+        router.layer(axum::middleware::from_fn(
+            middleware::envelope::response_envelope_middleware,
+        ))
+    } else {
+        router
+    };
 
     // Apply CORS and tracing to all environments
     router
-        .layer(CorsLayer::permissive())
+        .layer(axum::middleware::from_fn_with_state(
+            state.maintenance.clone(),
+            middleware::maintenance::maintenance_middleware,
+        ))
+        .layer(axum::middleware::from_fn(
+            middleware::auth::content_digest_middleware,
+        ))
+        .layer(middleware::rate_limit::RateLimitLayer::new(
+            state.config.rate_limit_config.clone(),
+            state.config.auth_config.clone(),
+        ))
+        .layer(state.config.cors_config.layer())
         .layer(TraceLayer::new_for_http())
+        .layer(axum::middleware::from_fn(
+            middleware::request_id::request_id_middleware,
+        ))
         .fallback(handle_404)
         .with_state(state)
 }
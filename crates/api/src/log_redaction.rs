@@ -0,0 +1,392 @@
+//! Redaction of secrets in log output
+//!
+//! [`RedactingMakeWriter`] wraps the writer of the fmt layer and masks the
+//! values of sensitive keys in each formatted event before it is written:
+//! structured fields (`session_token=...`), `Debug` output of structs
+//! (`jwt_secret: "..."`) and JSON embedded in messages (`"api_key":"..."`),
+//! escaped or not. A key is sensitive when it contains one of the configured
+//! keys, ignoring case, so `passphrase` also masks `HOTKEY_PASSPHRASE`.
+//!
+//! Values after a colon are only masked when quoted, so prose like
+//! `Invalid token: expired` is left alone.
+//!
+//! ANSI escape sequences are stripped from events before they are redacted:
+//! with colors on, the fmt layer writes `key=value` fields as
+//! `\x1b[3mkey\x1b[0m\x1b[2m=\x1b[0mvalue`, hiding the keys.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::sync::Arc;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Keys redacted by default
+pub const DEFAULT_REDACTED_KEYS: &[&str] = &[
+    "secret",
+    "password",
+    "passphrase",
+    "token",
+    "api_key",
+    "private_key",
+];
+
+/// Replacement of redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Masks the values of sensitive keys in text
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    /// Lowercase keys a sensitive key contains
+    keys: Vec<String>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(DEFAULT_REDACTED_KEYS)
+    }
+}
+
+impl Redactor {
+    pub fn new<K: AsRef<str>>(keys: impl IntoIterator<Item = K>) -> Self {
+        Self {
+            keys: keys
+                .into_iter()
+                .map(|key| key.as_ref().trim().to_ascii_lowercase())
+                .filter(|key| !key.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Create a redactor from environment variables
+    ///
+    /// `LOG_REDACTED_KEYS` is a comma separated list of keys redacted on top
+    /// of the defaults.
+    pub fn from_env() -> Self {
+        let extra = std::env::var("LOG_REDACTED_KEYS").unwrap_or_default();
+        Self::new(
+            DEFAULT_REDACTED_KEYS
+                .iter()
+                .copied()
+                .chain(extra.split(',')),
+        )
+    }
+
+    fn is_sensitive(&self, key: &str) -> bool {
+        let key = key.to_ascii_lowercase();
+        self.keys.iter().any(|sensitive| key.contains(sensitive))
+    }
+
+    /// `text` with the values of sensitive keys replaced by [`REDACTED`]
+    pub fn redact<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let bytes = text.as_bytes();
+        let mut redacted = String::new();
+        let mut copied = 0;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            if !is_key_byte(bytes[i]) {
+                i += 1;
+                continue;
+            }
+            let key_start = i;
+            while i < bytes.len() && is_key_byte(bytes[i]) {
+                i += 1;
+            }
+            // Keys start at a word boundary
+            if key_start > 0 && is_key_byte(bytes[key_start - 1]) {
+                continue;
+            }
+
+            let Some((start, end)) = value_after_key(bytes, i) else {
+                continue;
+            };
+            if !self.is_sensitive(&text[key_start..i]) {
+                continue;
+            }
+            redacted.push_str(&text[copied..start]);
+            redacted.push_str(REDACTED);
+            copied = end;
+            i = end;
+        }
+
+        if copied == 0 {
+            return Cow::Borrowed(text);
+        }
+        redacted.push_str(&text[copied..]);
+        Cow::Owned(redacted)
+    }
+}
+
+fn is_key_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.')
+}
+
+/// Span of the value following a key ending at `i`, quotes excluded
+fn value_after_key(bytes: &[u8], mut i: usize) -> Option<(usize, usize)> {
+    // Closing quote of a JSON key, possibly escaped
+    if bytes[i..].starts_with(b"\\\"") {
+        i += 2;
+    } else if bytes.get(i) == Some(&b'"') {
+        i += 1;
+    }
+
+    let separator = *bytes.get(i)?;
+    if separator != b'=' && separator != b':' {
+        return None;
+    }
+    i += 1;
+    while bytes.get(i) == Some(&b' ') {
+        i += 1;
+    }
+
+    if bytes[i..].starts_with(b"\\\"") {
+        let start = i + 2;
+        let len = bytes[start..].windows(2).position(|w| w == b"\\\"")?;
+        return Some((start, start + len));
+    }
+    if bytes.get(i) == Some(&b'"') {
+        let start = i + 1;
+        let mut end = start;
+        while end < bytes.len() {
+            match bytes[end] {
+                b'\\' => end += 2,
+                b'"' => return Some((start, end)),
+                _ => end += 1,
+            }
+        }
+        return None;
+    }
+    if separator == b':' {
+        return None;
+    }
+
+    let start = i;
+    let end = bytes[start..]
+        .iter()
+        .position(|&b| b.is_ascii_whitespace() || matches!(b, b',' | b';' | b'}' | b']' | b')'))
+        .map_or(bytes.len(), |len| start + len);
+    (end > start).then_some((start, end))
+}
+
+/// `text` without its ANSI escape sequences, such as the color codes of the
+/// fmt layer
+fn strip_ansi_escapes(text: &str) -> Cow<'_, str> {
+    if !text.contains('\x1b') {
+        return Cow::Borrowed(text);
+    }
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+        // A control sequence ends with a byte in @..=~, other escapes are
+        // two characters long
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    Cow::Owned(stripped)
+}
+
+/// `MakeWriter` redacting what is written through the writers of `inner`
+///
+/// Colors are stripped from the output, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+    redactor: Arc<Redactor>,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M, redactor: Redactor) -> Self {
+        Self {
+            inner,
+            redactor: Arc::new(redactor),
+        }
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<'a, M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter::new(self.inner.make_writer(), &self.redactor)
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        RedactingWriter::new(self.inner.make_writer_for(meta), &self.redactor)
+    }
+}
+
+/// Writer buffering an event and writing it redacted when flushed or dropped
+pub struct RedactingWriter<'a, W: Write> {
+    inner: W,
+    redactor: &'a Redactor,
+    buffer: Vec<u8>,
+}
+
+impl<'a, W: Write> RedactingWriter<'a, W> {
+    fn new(inner: W, redactor: &'a Redactor) -> Self {
+        Self {
+            inner,
+            redactor,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            let text = String::from_utf8_lossy(&self.buffer);
+            let text = strip_ansi_escapes(&text);
+            self.inner
+                .write_all(self.redactor.redact(&text).as_bytes())?;
+            self.buffer.clear();
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for RedactingWriter<'_, W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const SECRET: &str = "s3cr3t-value";
+
+    /// Log sink shared with the fmt subscriber
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Config {
+        server_port: u16,
+        jwt_secret: String,
+    }
+
+    #[test]
+    fn test_redact() {
+        let redactor = Redactor::default();
+        let cases = [
+            (
+                "session_token=abc other=1",
+                "session_token=[REDACTED] other=1",
+            ),
+            (
+                "Config { port: 1, jwt_secret: \"abc\" }",
+                "Config { port: 1, jwt_secret: \"[REDACTED]\" }",
+            ),
+            (
+                r#"{"HOTKEY_PASSPHRASE": "abc", "name": "x"}"#,
+                r#"{"HOTKEY_PASSPHRASE": "[REDACTED]", "name": "x"}"#,
+            ),
+            (
+                r#"app_compose="{\"api_key\":\"abc\"}""#,
+                r#"app_compose="{\"api_key\":\"[REDACTED]\"}""#,
+            ),
+            ("Invalid token: expired", "Invalid token: expired"),
+            ("compose_hash=abc", "compose_hash=abc"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(redactor.redact(text), expected);
+        }
+
+        let redactor = Redactor::new(["compose_hash"]);
+        assert_eq!(
+            redactor.redact("compose_hash=abc"),
+            "compose_hash=[REDACTED]"
+        );
+        assert_eq!(redactor.redact("session_token=abc"), "session_token=abc");
+    }
+
+    #[test]
+    fn test_strip_ansi_escapes() {
+        assert_eq!(
+            strip_ansi_escapes("\x1b[3msession_token\x1b[0m\x1b[2m=\x1b[0mabc"),
+            "session_token=abc"
+        );
+        assert_eq!(
+            strip_ansi_escapes("\x1b[32m INFO\x1b[0m started"),
+            " INFO started"
+        );
+        assert!(matches!(
+            strip_ansi_escapes("plain"),
+            Cow::Borrowed("plain")
+        ));
+    }
+
+    /// Output of a fmt subscriber writing through the redactor events
+    /// carrying [`SECRET`], with colors or not
+    fn log_secrets(ansi: bool) -> String {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(RedactingMakeWriter::new(
+                move || writer.clone(),
+                Redactor::default(),
+            ))
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(ansi)
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let config = Config {
+                server_port: 3000,
+                jwt_secret: SECRET.to_string(),
+            };
+            tracing::info!("Loaded configuration: {:?}", config);
+            tracing::info!(session_token = SECRET, "Validator authenticated");
+            let app_compose = serde_json::json!({
+                "allowed_envs": ["HOTKEY_PASSPHRASE"],
+                "env": { "HOTKEY_PASSPHRASE": SECRET },
+            });
+            tracing::debug!("Expected app_compose: {}", app_compose);
+            tracing::warn!(app_compose = %app_compose, "Compose hash mismatch");
+            tracing::warn!(app_compose = ?app_compose.to_string(), "Compose hash mismatch");
+        });
+
+        let output = logs.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_secret_never_logged() {
+        for ansi in [false, true] {
+            let output = log_secrets(ansi);
+            assert_eq!(output.lines().count(), 5);
+            assert!(output.contains("server_port: 3000"));
+            assert!(output.contains(r#"session_token="[REDACTED]""#));
+            assert!(!output.contains(SECRET), "secret logged: {}", output);
+            assert!(!output.contains('\x1b'));
+        }
+    }
+}
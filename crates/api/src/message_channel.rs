@@ -0,0 +1,462 @@
+//! Bounded channels of the messages sent to WebSocket peers
+//!
+//! Messages for a peer are queued in a [`message_channel`] and written to its
+//! socket by a forwarding task. When a peer reads slower than messages are
+//! queued for it, the channel fills up and its [`OverflowPolicy`] applies:
+//! senders wait for room up to a timeout and then fail, or the oldest queued
+//! message is dropped to make room. Either way the loss is logged and counted
+//! in [`CHANNEL_DROPPED_METRIC`], and the number of queued messages is
+//! exported as [`CHANNEL_DEPTH_METRIC`], so that peers falling behind show up
+//! before messages are lost. Both are labelled by connection type only, as
+//! peers come and go: the peer a message was lost for is in the log.
+//!
+//! Each connection type has its own [`ChannelConfig`], e.g. validator
+//! connections read theirs from the `VALIDATOR_CHANNEL_*` variables.
+
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::warn;
+
+/// Messages queued for the peers of a `connection` type
+pub const CHANNEL_DEPTH_METRIC: &str = "ws_channel_depth";
+
+/// Messages dropped because the channel of a peer was full, by `connection`
+/// type and `policy`: `block` for messages whose sender timed out and
+/// `drop_oldest` for queued messages dropped to make room
+pub const CHANNEL_DROPPED_METRIC: &str = "ws_channel_dropped_total";
+
+/// Default time blocking senders wait for room, in milliseconds
+pub const DEFAULT_SEND_TIMEOUT_MS: u64 = 1000;
+
+/// What happens to a message sent to a full channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait up to `timeout` for room, then fail the send
+    Block { timeout: Duration },
+    /// Drop the oldest queued message to make room
+    DropOldest,
+}
+
+impl OverflowPolicy {
+    fn name(&self) -> &'static str {
+        match self {
+            OverflowPolicy::Block { .. } => "block",
+            OverflowPolicy::DropOldest => "drop_oldest",
+        }
+    }
+}
+
+/// Capacity and overflow policy of the channels of a connection type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelConfig {
+    /// Most messages queued for a peer
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 100,
+            overflow: OverflowPolicy::Block {
+                timeout: Duration::from_millis(DEFAULT_SEND_TIMEOUT_MS),
+            },
+        }
+    }
+}
+
+impl ChannelConfig {
+    /// Load the configuration of a connection type from the variables named
+    /// after `prefix`
+    ///
+    /// `{prefix}_CHANNEL_CAPACITY` is the capacity (default: 100),
+    /// `{prefix}_CHANNEL_OVERFLOW` the policy, `block` (default) or
+    /// `drop_oldest`, and `{prefix}_CHANNEL_SEND_TIMEOUT_MS` how long senders
+    /// block (default: 1000).
+    pub fn from_env(prefix: &str) -> Self {
+        let defaults = Self::default();
+        let var = |name: &str| std::env::var(format!("{}_CHANNEL_{}", prefix, name)).ok();
+
+        let timeout = var("SEND_TIMEOUT_MS").and_then(|s| s.parse().ok()).map_or(
+            Duration::from_millis(DEFAULT_SEND_TIMEOUT_MS),
+            Duration::from_millis,
+        );
+        let overflow = match var("OVERFLOW").as_deref().map(OverflowKind::from_str) {
+            None => defaults.overflow,
+            Some(Ok(OverflowKind::Block)) => OverflowPolicy::Block { timeout },
+            Some(Ok(OverflowKind::DropOldest)) => OverflowPolicy::DropOldest,
+            Some(Err(e)) => {
+                warn!("Ignoring {}_CHANNEL_OVERFLOW: {}", prefix, e);
+                defaults.overflow
+            }
+        };
+
+        Self {
+            capacity: var("CAPACITY")
+                .and_then(|s| s.parse().ok())
+                .filter(|&capacity| capacity > 0)
+                .unwrap_or(defaults.capacity),
+            overflow,
+        }
+    }
+}
+
+/// Name of an [`OverflowPolicy`] in the configuration
+enum OverflowKind {
+    Block,
+    DropOldest,
+}
+
+impl FromStr for OverflowKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "block" => Ok(OverflowKind::Block),
+            "drop_oldest" => Ok(OverflowKind::DropOldest),
+            other => Err(format!(
+                "unknown policy '{}', expected 'block' or 'drop_oldest'",
+                other
+            )),
+        }
+    }
+}
+
+/// Reason a message was not queued
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum SendError {
+    #[error("The channel stayed full for {0:?}")]
+    Timeout(Duration),
+    #[error("The channel is closed")]
+    Closed,
+}
+
+struct Channel {
+    connection: &'static str,
+    peer: String,
+    config: ChannelConfig,
+    queue: Mutex<Queue>,
+    senders: AtomicUsize,
+    /// Notified when a message is queued or the last sender dropped
+    queued: Notify,
+    /// Notified when a message is taken or the receiver dropped
+    taken: Notify,
+}
+
+#[derive(Default)]
+struct Queue {
+    messages: VecDeque<String>,
+    closed: bool,
+}
+
+impl Channel {
+    fn record_queued(&self, count: usize) {
+        metrics::gauge!(CHANNEL_DEPTH_METRIC, "connection" => self.connection)
+            .increment(count as f64);
+    }
+
+    fn record_taken(&self, count: usize) {
+        metrics::gauge!(CHANNEL_DEPTH_METRIC, "connection" => self.connection)
+            .decrement(count as f64);
+    }
+
+    fn record_dropped(&self) {
+        let labels = [
+            ("connection", self.connection),
+            ("policy", self.config.overflow.name()),
+        ];
+        metrics::counter!(CHANNEL_DROPPED_METRIC, &labels).increment(1);
+    }
+}
+
+/// Create a channel of messages to `peer`, a peer of a `connection` type
+pub fn message_channel(
+    connection: &'static str,
+    peer: impl Into<String>,
+    config: ChannelConfig,
+) -> (MessageSender, MessageReceiver) {
+    let channel = Arc::new(Channel {
+        connection,
+        peer: peer.into(),
+        config: ChannelConfig {
+            capacity: config.capacity.max(1),
+            ..config
+        },
+        queue: Mutex::new(Queue::default()),
+        senders: AtomicUsize::new(1),
+        queued: Notify::new(),
+        taken: Notify::new(),
+    });
+    channel.record_queued(0);
+    (
+        MessageSender {
+            channel: channel.clone(),
+        },
+        MessageReceiver { channel },
+    )
+}
+
+/// Sending half of a [`message_channel`]
+pub struct MessageSender {
+    channel: Arc<Channel>,
+}
+
+impl std::fmt::Debug for MessageSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageSender")
+            .field("connection", &self.channel.connection)
+            .field("peer", &self.channel.peer)
+            .field("depth", &self.depth())
+            .finish()
+    }
+}
+
+impl Clone for MessageSender {
+    fn clone(&self) -> Self {
+        self.channel.senders.fetch_add(1, Ordering::Relaxed);
+        Self {
+            channel: self.channel.clone(),
+        }
+    }
+}
+
+impl Drop for MessageSender {
+    fn drop(&mut self) {
+        if self.channel.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.channel.queue.lock().unwrap().closed = true;
+            self.channel.queued.notify_one();
+        }
+    }
+}
+
+impl MessageSender {
+    /// Queue `message`, applying the overflow policy if the channel is full
+    pub async fn send(&self, message: String) -> Result<(), SendError> {
+        let channel = &self.channel;
+        let deadline = match channel.config.overflow {
+            OverflowPolicy::Block { timeout } => {
+                Some((tokio::time::Instant::now() + timeout, timeout))
+            }
+            OverflowPolicy::DropOldest => None,
+        };
+
+        loop {
+            // Registered before checking for room, so that no take is missed
+            let taken = channel.taken.notified();
+            {
+                let mut queue = channel.queue.lock().unwrap();
+                if queue.closed {
+                    return Err(SendError::Closed);
+                }
+                if queue.messages.len() >= channel.config.capacity && deadline.is_none() {
+                    queue.messages.pop_front();
+                    channel.record_taken(1);
+                    channel.record_dropped();
+                    warn!(
+                        connection = channel.connection,
+                        peer = %channel.peer,
+                        "Channel full, dropped the oldest message"
+                    );
+                }
+                if queue.messages.len() < channel.config.capacity {
+                    queue.messages.push_back(message);
+                    channel.record_queued(1);
+                    channel.queued.notify_one();
+                    return Ok(());
+                }
+            }
+
+            let (deadline, timeout) = deadline.expect("only blocking sends wait");
+            if tokio::time::timeout_at(deadline, taken).await.is_err() {
+                channel.record_dropped();
+                warn!(
+                    connection = channel.connection,
+                    peer = %channel.peer,
+                    timeout_ms = timeout.as_millis() as u64,
+                    "Channel stayed full, message not sent"
+                );
+                return Err(SendError::Timeout(timeout));
+            }
+        }
+    }
+
+    /// Number of queued messages
+    pub fn depth(&self) -> usize {
+        self.channel.queue.lock().unwrap().messages.len()
+    }
+}
+
+/// Receiving half of a [`message_channel`]
+pub struct MessageReceiver {
+    channel: Arc<Channel>,
+}
+
+impl MessageReceiver {
+    /// Take the oldest queued message, waiting for one, or `None` once the
+    /// channel is empty and every sender dropped
+    pub async fn recv(&mut self) -> Option<String> {
+        let channel = &self.channel;
+        loop {
+            let queued = channel.queued.notified();
+            {
+                let mut queue = channel.queue.lock().unwrap();
+                if let Some(message) = queue.messages.pop_front() {
+                    channel.record_taken(1);
+                    channel.taken.notify_one();
+                    return Some(message);
+                }
+                if queue.closed {
+                    return None;
+                }
+            }
+            queued.await;
+        }
+    }
+}
+
+impl Drop for MessageReceiver {
+    fn drop(&mut self) {
+        let mut queue = self.channel.queue.lock().unwrap();
+        queue.closed = true;
+        self.channel.record_taken(queue.messages.len());
+        queue.messages.clear();
+        self.channel.taken.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(overflow: OverflowPolicy) -> ChannelConfig {
+        ChannelConfig {
+            capacity: 2,
+            overflow,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest() {
+        let (sender, mut receiver) =
+            message_channel("validator", "5F...", config(OverflowPolicy::DropOldest));
+        for message in ["a", "b", "c"] {
+            sender.send(message.to_string()).await.unwrap();
+        }
+        assert_eq!(sender.depth(), 2);
+        assert_eq!(receiver.recv().await.as_deref(), Some("b"));
+        assert_eq!(receiver.recv().await.as_deref(), Some("c"));
+
+        drop(sender);
+        assert_eq!(receiver.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_block_with_timeout() {
+        let timeout = Duration::from_millis(100);
+        let (sender, mut receiver) = message_channel(
+            "validator",
+            "5F...",
+            config(OverflowPolicy::Block { timeout }),
+        );
+        sender.send("a".to_string()).await.unwrap();
+        sender.send("b".to_string()).await.unwrap();
+        assert_eq!(
+            sender.send("c".to_string()).await,
+            Err(SendError::Timeout(timeout))
+        );
+
+        // A blocked send goes through once the receiver takes a message
+        let blocked = tokio::spawn({
+            let sender = sender.clone();
+            async move { sender.send("c".to_string()).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(receiver.recv().await.as_deref(), Some("a"));
+        blocked.await.unwrap().unwrap();
+        assert_eq!(receiver.recv().await.as_deref(), Some("b"));
+        assert_eq!(receiver.recv().await.as_deref(), Some("c"));
+
+        drop(receiver);
+        assert_eq!(sender.send("d".to_string()).await, Err(SendError::Closed));
+    }
+
+    #[test]
+    fn test_depth_and_drops_recorded() {
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        // Kept until the metrics are rendered, as dropping it empties the channel
+        let channels = metrics::with_local_recorder(&recorder, || {
+            runtime.block_on(async {
+                let (sender, receiver) =
+                    message_channel("validator", "5F...", config(OverflowPolicy::DropOldest));
+                for message in ["a", "b", "c"] {
+                    sender.send(message.to_string()).await.unwrap();
+                }
+                // Another validator, counted in the same series
+                let (other, mut other_receiver) =
+                    message_channel("validator", "5G...", config(OverflowPolicy::DropOldest));
+                other.send("d".to_string()).await.unwrap();
+                other.send("e".to_string()).await.unwrap();
+                other_receiver.recv().await.unwrap();
+                (sender, receiver, other, other_receiver)
+            })
+        });
+
+        let rendered = handle.render();
+        for expected in [
+            r#"ws_channel_depth{connection="validator"} 3"#,
+            r#"ws_channel_dropped_total{connection="validator",policy="drop_oldest"} 1"#,
+        ] {
+            assert!(
+                rendered.contains(expected),
+                "{} missing from:\n{}",
+                expected,
+                rendered
+            );
+        }
+        assert!(!rendered.contains("peer="), "{}", rendered);
+
+        // A disconnected validator leaves no queued messages behind
+        metrics::with_local_recorder(&recorder, || drop(channels));
+        assert!(handle
+            .render()
+            .contains(r#"ws_channel_depth{connection="validator"} 0"#));
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        std::env::set_var("TEST_PEER_CHANNEL_CAPACITY", "16");
+        std::env::set_var("TEST_PEER_CHANNEL_OVERFLOW", "Drop_Oldest");
+        assert_eq!(
+            ChannelConfig::from_env("TEST_PEER"),
+            ChannelConfig {
+                capacity: 16,
+                overflow: OverflowPolicy::DropOldest,
+            }
+        );
+
+        std::env::set_var("TEST_PEER_CHANNEL_OVERFLOW", "block");
+        std::env::set_var("TEST_PEER_CHANNEL_SEND_TIMEOUT_MS", "250");
+        assert_eq!(
+            ChannelConfig::from_env("TEST_PEER").overflow,
+            OverflowPolicy::Block {
+                timeout: Duration::from_millis(250)
+            }
+        );
+
+        std::env::set_var("TEST_PEER_CHANNEL_CAPACITY", "0");
+        std::env::set_var("TEST_PEER_CHANNEL_OVERFLOW", "spill");
+        assert_eq!(
+            ChannelConfig::from_env("TEST_PEER"),
+            ChannelConfig::default()
+        );
+    }
+}
@@ -0,0 +1,484 @@
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use sha2::{Digest, Sha256};
+use sp_core::{
+    crypto::{Pair, Ss58Codec},
+    sr25519,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use super::rate_limit::{API_KEY_HEADER, HOTKEY_HEADER};
+use super::security::AdminActor;
+use crate::state::AppState;
+
+/// Header carrying the hex sr25519 signature of a signed request
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Header carrying the unix timestamp of a signed request
+pub const TIMESTAMP_HEADER: &str = "x-timestamp";
+
+/// Header carrying the single-use nonce of a signed request
+pub const NONCE_HEADER: &str = "x-nonce";
+
+/// Header carrying the hex SHA-256 of the body of a signed request
+pub const CONTENT_DIGEST_HEADER: &str = "x-content-sha256";
+
+/// Largest body whose digest is checked, larger ones are refused with 413
+const MAX_DIGESTED_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Nonces of signed requests and validator registrations, remembered for as
+/// long as they are accepted so that none can be replayed
+#[derive(Debug, Default)]
+pub struct SeenNonces {
+    /// Expiry of each hotkey's nonces
+    seen: Mutex<HashMap<(String, String), u64>>,
+}
+
+impl SeenNonces {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `nonce` of `hotkey` until `expires_at`, false if it was
+    /// already seen
+    ///
+    /// Expired nonces are forgotten on the way.
+    pub(crate) fn insert(&self, hotkey: &str, nonce: &str, now_secs: u64, expires_at: u64) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, expiry| *expiry >= now_secs);
+        seen.insert((hotkey.to_string(), nonce.to_string()), expires_at)
+            .is_none()
+    }
+}
+
+/// Caller authentication configuration
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    /// Owner identity of each API key
+    pub api_keys: HashMap<String, String>,
+    /// Owners granted the admin role
+    pub admin_owners: HashSet<String>,
+    /// Maximum age of a signed request
+    pub signature_max_age_secs: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            api_keys: HashMap::new(),
+            admin_owners: HashSet::new(),
+            signature_max_age_secs: 30,
+        }
+    }
+}
+
+impl AuthConfig {
+    /// Create configuration from environment variables
+    ///
+    /// `PLATFORM_API_KEYS` is a comma separated list of `owner:key` pairs and
+    /// `PLATFORM_ADMIN_OWNERS` a comma separated list of owners with the admin role.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        let api_keys = std::env::var("PLATFORM_API_KEYS")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .filter_map(|entry| match entry.split_once(':') {
+                        Some((owner, key)) if !owner.trim().is_empty() && !key.trim().is_empty() => {
+                            Some((key.trim().to_string(), owner.trim().to_string()))
+                        }
+                        _ => {
+                            tracing::warn!("Ignoring invalid API key entry");
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or(defaults.api_keys);
+
+        let admin_owners = std::env::var("PLATFORM_ADMIN_OWNERS")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or(defaults.admin_owners);
+
+        Self {
+            api_keys,
+            admin_owners,
+            signature_max_age_secs: std::env::var("SIGNATURE_MAX_AGE_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.signature_max_age_secs),
+        }
+    }
+
+    /// Identify the caller of a request
+    ///
+    /// An `X-API-Key` is resolved to its configured owner. Otherwise the
+    /// request must be signed: `X-Signature` is the sr25519 signature by
+    /// `X-Hotkey` of `"{timestamp}:{nonce}:{METHOD}:{path}:{body digest}"`,
+    /// with the timestamp sent in `X-Timestamp`, a nonce used once in
+    /// `X-Nonce` and the hex SHA-256 of the body in `X-Content-SHA256`, which
+    /// [`content_digest_middleware`] checks against the body. The hotkey is
+    /// then the owner.
+    pub fn identify(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        now_secs: u64,
+        nonces: &SeenNonces,
+    ) -> Result<Caller, StatusCode> {
+        let (owner, signed_nonce) = self.verify_credentials(method, path, headers, now_secs)?;
+
+        // Only checked once signed, so that others cannot burn nonces
+        if let Some((nonce, expires_at)) = signed_nonce {
+            if !nonces.insert(&owner, &nonce, now_secs, expires_at) {
+                tracing::warn!(hotkey = %owner, "Rejected replayed signed request");
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+        }
+
+        Ok(Caller {
+            admin: self.admin_owners.contains(&owner),
+            owner,
+        })
+    }
+
+    /// Owner the credentials of a request authenticate, without using up
+    /// the nonce of a signed request
+    ///
+    /// For the rate limiter, which keys requests before they are identified.
+    pub fn authenticated_owner(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        now_secs: u64,
+    ) -> Option<String> {
+        self.verify_credentials(method, path, headers, now_secs)
+            .ok()
+            .map(|(owner, _)| owner)
+    }
+
+    /// Owner the credentials of a request authenticate and, for signed
+    /// requests, their nonce and when it expires
+    fn verify_credentials(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        now_secs: u64,
+    ) -> Result<(String, Option<(String, u64)>), StatusCode> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+        if let Some(key) = header(API_KEY_HEADER) {
+            let owner = self.api_keys.get(key).cloned().ok_or_else(|| {
+                tracing::warn!("Rejected unknown API key");
+                StatusCode::UNAUTHORIZED
+            })?;
+            return Ok((owner, None));
+        }
+
+        let (Some(hotkey), Some(signature), Some(timestamp), Some(nonce), Some(digest)) = (
+            header(HOTKEY_HEADER),
+            header(SIGNATURE_HEADER),
+            header(TIMESTAMP_HEADER),
+            header(NONCE_HEADER),
+            header(CONTENT_DIGEST_HEADER),
+        ) else {
+            return Err(StatusCode::UNAUTHORIZED);
+        };
+
+        let sent_at: u64 = timestamp.parse().map_err(|_| StatusCode::UNAUTHORIZED)?;
+        if now_secs.abs_diff(sent_at) > self.signature_max_age_secs || nonce.is_empty() {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+
+        let message = format!("{}:{}:{}:{}:{}", timestamp, nonce, method, path, digest);
+        if !verify_hotkey_signature(hotkey, signature, message.as_bytes()) {
+            tracing::warn!(hotkey = hotkey, "Rejected request with invalid signature");
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+
+        let expires_at = sent_at.saturating_add(self.signature_max_age_secs);
+        Ok((hotkey.to_string(), Some((nonce.to_string(), expires_at))))
+    }
+}
+
+/// Reject requests whose body does not match their `X-Content-SHA256`
+///
+/// Requests without the header pass through untouched; signed requests
+/// cannot omit it, as it is part of what [`AuthConfig::identify`] checks the
+/// signature of.
+pub async fn content_digest_middleware(
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let Some(digest) = request.headers().get(CONTENT_DIGEST_HEADER) else {
+        return Ok(next.run(request).await);
+    };
+    let expected = hex::decode(digest.as_bytes()).map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    let (parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_DIGESTED_BODY_BYTES)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+    if Sha256::digest(&bytes).as_slice() != expected.as_slice() {
+        tracing::warn!(
+            path = %parts.uri.path(),
+            "Rejected request whose body does not match its digest"
+        );
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await)
+}
+
+/// Whether `signature_hex` is the sr25519 signature of `message` by `hotkey`, in SS58
+pub(crate) fn verify_hotkey_signature(hotkey: &str, signature_hex: &str, message: &[u8]) -> bool {
+    let Ok(public_key) = sr25519::Public::from_ss58check(hotkey) else {
+        return false;
+    };
+    let Some(signature) = hex::decode(signature_hex)
+        .ok()
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+    else {
+        return false;
+    };
+
+    sr25519::Pair::verify(&sr25519::Signature::from(signature), message, &public_key)
+}
+
+/// Authenticated caller of a request
+///
+/// Extracting a `Caller` rejects unauthenticated requests with 401. Requests
+/// that went through the admin IP whitelist are admins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caller {
+    pub owner: String,
+    pub admin: bool,
+}
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for Caller {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if let Some(AdminActor(actor)) = parts.extensions.get::<AdminActor>() {
+            return Ok(Caller {
+                owner: actor.clone(),
+                admin: true,
+            });
+        }
+
+        let now_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        state.config.auth_config.identify(
+            &parts.method,
+            parts.uri.path(),
+            &parts.headers,
+            now_secs,
+            &state.seen_request_nonces,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn config() -> AuthConfig {
+        AuthConfig {
+            api_keys: HashMap::from([
+                ("alice-key".to_string(), "alice".to_string()),
+                ("root-key".to_string(), "root".to_string()),
+            ]),
+            admin_owners: HashSet::from(["root".to_string()]),
+            ..AuthConfig::default()
+        }
+    }
+
+    fn headers(pairs: &[(&'static str, String)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn identify(headers: &HeaderMap) -> Result<Caller, StatusCode> {
+        config().identify(
+            &Method::PUT,
+            "/challenges/1",
+            headers,
+            NOW,
+            &SeenNonces::new(),
+        )
+    }
+
+    fn empty_digest() -> String {
+        hex::encode(Sha256::digest(b""))
+    }
+
+    #[test]
+    fn test_api_key_identifies_owner() {
+        let alice = identify(&headers(&[(API_KEY_HEADER, "alice-key".to_string())])).unwrap();
+        assert_eq!(alice.owner, "alice");
+        assert!(!alice.admin);
+
+        let root = identify(&headers(&[(API_KEY_HEADER, "root-key".to_string())])).unwrap();
+        assert!(root.admin);
+
+        assert_eq!(
+            identify(&headers(&[(API_KEY_HEADER, "stolen".to_string())])),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(identify(&HeaderMap::new()), Err(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn test_signed_request_identifies_hotkey() {
+        let pair = sr25519::Pair::from_seed(&[7u8; 32]);
+        let hotkey = pair.public().to_ss58check();
+        let sign = |timestamp: u64, path: &str| {
+            let message = format!("{}:n1:PUT:{}:{}", timestamp, path, empty_digest());
+            hex::encode(pair.sign(message.as_bytes()))
+        };
+
+        let signed = |signature: String, timestamp: u64| {
+            headers(&[
+                (HOTKEY_HEADER, hotkey.clone()),
+                (SIGNATURE_HEADER, signature),
+                (TIMESTAMP_HEADER, timestamp.to_string()),
+                (NONCE_HEADER, "n1".to_string()),
+                (CONTENT_DIGEST_HEADER, empty_digest()),
+            ])
+        };
+
+        let caller = identify(&signed(sign(NOW, "/challenges/1"), NOW)).unwrap();
+        assert_eq!(caller.owner, hotkey);
+
+        // Signed for another route
+        assert!(identify(&signed(sign(NOW, "/challenges/2"), NOW)).is_err());
+        // Replayed too late
+        assert!(identify(&signed(sign(NOW - 60, "/challenges/1"), NOW - 60)).is_err());
+    }
+
+    #[test]
+    fn test_signed_request_bound_to_nonce_and_body() {
+        let pair = sr25519::Pair::from_seed(&[7u8; 32]);
+        let hotkey = pair.public().to_ss58check();
+        let body_digest = hex::encode(Sha256::digest(br#"{"name":"a"}"#));
+        let signed = |nonce: &str, digest: &str, sent_digest: &str| {
+            let message = format!("{}:{}:PUT:/challenges/1:{}", NOW, nonce, digest);
+            headers(&[
+                (HOTKEY_HEADER, hotkey.clone()),
+                (SIGNATURE_HEADER, hex::encode(pair.sign(message.as_bytes()))),
+                (TIMESTAMP_HEADER, NOW.to_string()),
+                (NONCE_HEADER, nonce.to_string()),
+                (CONTENT_DIGEST_HEADER, sent_digest.to_string()),
+            ])
+        };
+        let nonces = SeenNonces::new();
+        let identify = |headers: &HeaderMap, now: u64| {
+            config().identify(&Method::PUT, "/challenges/1", headers, now, &nonces)
+        };
+
+        let request = signed("n1", &body_digest, &body_digest);
+        identify(&request, NOW).unwrap();
+        // The same request replayed within its max age
+        assert_eq!(identify(&request, NOW + 1), Err(StatusCode::UNAUTHORIZED));
+        // Another nonce is a new request
+        identify(&signed("n2", &body_digest, &body_digest), NOW).unwrap();
+
+        // The digest is signed, so it cannot be swapped for another body's
+        let swapped = signed("n3", &body_digest, &empty_digest());
+        assert_eq!(identify(&swapped, NOW), Err(StatusCode::UNAUTHORIZED));
+
+        // Unsigned nonces or digests are refused
+        let mut unsigned = signed("n4", &body_digest, &body_digest);
+        unsigned.remove(NONCE_HEADER);
+        assert_eq!(identify(&unsigned, NOW), Err(StatusCode::UNAUTHORIZED));
+        let mut undigested = signed("n5", &body_digest, &body_digest);
+        undigested.remove(CONTENT_DIGEST_HEADER);
+        assert_eq!(identify(&undigested, NOW), Err(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn test_seen_nonces_expire() {
+        let nonces = SeenNonces::new();
+        assert!(nonces.insert("alice", "n1", NOW, NOW + 30));
+        assert!(!nonces.insert("alice", "n1", NOW + 30, NOW + 60));
+        // Nonces of other hotkeys are their own
+        assert!(nonces.insert("bob", "n1", NOW, NOW + 30));
+        // and forgotten once requests carrying them are too old anyway
+        assert!(nonces.insert("alice", "n1", NOW + 61, NOW + 91));
+    }
+
+    #[tokio::test]
+    async fn test_body_checked_against_digest() {
+        use axum::{routing::put, Router};
+        use tower::ServiceExt;
+
+        let router = Router::new()
+            .route("/challenges/1", put(|body: String| async move { body }))
+            .layer(axum::middleware::from_fn(content_digest_middleware));
+        let request = |body: &'static str, digest: Option<String>| {
+            let mut request = axum::http::Request::builder()
+                .method(Method::PUT)
+                .uri("/challenges/1");
+            if let Some(digest) = digest {
+                request = request.header(CONTENT_DIGEST_HEADER, digest);
+            }
+            request.body(Body::from(body)).unwrap()
+        };
+        let digest = hex::encode(Sha256::digest(b"signed body"));
+
+        let response = router
+            .clone()
+            .oneshot(request("signed body", Some(digest.clone())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"signed body");
+
+        let response = router
+            .clone()
+            .oneshot(request("tampered body", Some(digest)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = router.oneshot(request("unsigned", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,205 @@
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Tower layer only letting through connections from allowed CIDR ranges
+///
+/// The remote address is the peer of the TCP connection, taken from
+/// `ConnectInfo<SocketAddr>`. An empty list of ranges allows every address.
+/// Ranges that fail to parse are logged and match nothing, so a list of only
+/// invalid ranges rejects every connection rather than allowing them all.
+#[derive(Debug, Clone)]
+pub struct CidrFilter {
+    ranges: Arc<Option<Vec<IpNet>>>,
+}
+
+impl CidrFilter {
+    pub fn new(ranges: &[String]) -> Self {
+        if ranges.is_empty() {
+            return Self {
+                ranges: Arc::new(None),
+            };
+        }
+        let parsed = ranges
+            .iter()
+            .filter_map(|range| match parse_range(range) {
+                Some(net) => Some(net),
+                None => {
+                    tracing::error!("Ignoring invalid allowed CIDR range '{}'", range);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            ranges: Arc::new(Some(parsed)),
+        }
+    }
+
+    /// Whether connections from `ip` are allowed
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        match self.ranges.as_ref() {
+            None => true,
+            // IPv4 clients of a dual-stack listener show up as IPv4-mapped IPv6
+            Some(ranges) => {
+                let ip = ip.to_canonical();
+                ranges.iter().any(|net| net.contains(&ip))
+            }
+        }
+    }
+}
+
+/// A range, or a single address as a /32 or /128 range
+pub(crate) fn parse_range(range: &str) -> Option<IpNet> {
+    let range = range.trim();
+    range
+        .parse::<IpNet>()
+        .ok()
+        .or_else(|| range.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+impl<S> Layer<S> for CidrFilter {
+    type Service = CidrFilterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CidrFilterService {
+            inner,
+            filter: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`CidrFilter`]
+#[derive(Debug, Clone)]
+pub struct CidrFilterService<S> {
+    inner: S,
+    filter: CidrFilter,
+}
+
+impl<S> Service<Request> for CidrFilterService<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        if self.filter.ranges.is_some() {
+            let remote_ip = req
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip());
+            let allowed = remote_ip.is_some_and(|ip| self.filter.allows(ip));
+            if !allowed {
+                tracing::warn!(
+                    ip = %remote_ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string()),
+                    timestamp = %Utc::now().to_rfc3339(),
+                    path = %req.uri().path(),
+                    "Rejected connection from outside the allowed CIDR ranges"
+                );
+                return Box::pin(async { Ok(forbidden()) });
+            }
+        }
+        Box::pin(self.inner.call(req))
+    }
+}
+
+fn forbidden() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({
+            "error": "Connections from this address are not allowed"
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn ranges(ranges: &[&str]) -> Vec<String> {
+        ranges.iter().map(|r| r.to_string()).collect()
+    }
+
+    async fn status(filter: CidrFilter, remote: Option<&str>) -> StatusCode {
+        let router = Router::new()
+            .route("/validators/:hotkey/ws", get(|| async { "upgraded" }))
+            .layer(filter);
+        let mut request = Request::builder()
+            .uri("/validators/5F/ws")
+            .body(Body::empty())
+            .unwrap();
+        if let Some(addr) = remote {
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(addr.parse::<SocketAddr>().unwrap()));
+        }
+        router.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_connections_outside_ranges_rejected() {
+        let filter = CidrFilter::new(&ranges(&["10.20.0.0/16", "2001:db8::/32", "192.0.2.7"]));
+
+        assert_eq!(
+            status(filter.clone(), Some("10.20.3.4:5000")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(filter.clone(), Some("[2001:db8::1]:5000")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(filter.clone(), Some("192.0.2.7:5000")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(filter.clone(), Some("[::ffff:10.20.0.1]:5000")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(filter.clone(), Some("10.21.0.1:5000")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(filter.clone(), Some("192.0.2.8:5000")).await,
+            StatusCode::FORBIDDEN
+        );
+        // Without the remote address the connection cannot be checked
+        assert_eq!(status(filter, None).await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_empty_list_allows_all() {
+        let filter = CidrFilter::new(&[]);
+        assert_eq!(
+            status(filter.clone(), Some("203.0.113.9:443")).await,
+            StatusCode::OK
+        );
+        assert_eq!(status(filter, None).await, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_invalid_ranges_match_nothing() {
+        let filter = CidrFilter::new(&ranges(&["10.0.0.0/33", "not-a-range"]));
+        assert!(!filter.allows("10.0.0.1".parse().unwrap()));
+    }
+}
@@ -0,0 +1,210 @@
+use axum::http::{HeaderName, HeaderValue, Method};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// CORS configuration for the API router
+///
+/// The default allows no cross-origin requests; origins must be listed explicitly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorsConfig {
+    /// Allowed origins (e.g. `https://dashboard.platform.network`), `*` for any
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub allow_credentials: bool,
+    /// How long browsers may cache preflight responses
+    pub max_age_secs: Option<u64>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: ["GET", "POST", "PUT", "DELETE", "OPTIONS"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            allowed_headers: ["authorization", "content-type", "x-attestation-token"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            allow_credentials: false,
+            max_age_secs: Some(3600),
+        }
+    }
+}
+
+impl CorsConfig {
+    /// Create configuration from environment variables
+    ///
+    /// Lists are comma separated: `ALLOWED_ORIGINS`, `CORS_ALLOWED_METHODS`,
+    /// `CORS_ALLOWED_HEADERS`. `CORS_ALLOW_CREDENTIALS` and `CORS_MAX_AGE` are scalars.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        let parse_list = |var: &str| -> Option<Vec<String>> {
+            std::env::var(var).ok().map(|value| {
+                value
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+        };
+
+        Self {
+            allowed_origins: parse_list("ALLOWED_ORIGINS").unwrap_or(defaults.allowed_origins),
+            allowed_methods: parse_list("CORS_ALLOWED_METHODS")
+                .unwrap_or(defaults.allowed_methods),
+            allowed_headers: parse_list("CORS_ALLOWED_HEADERS")
+                .unwrap_or(defaults.allowed_headers),
+            allow_credentials: std::env::var("CORS_ALLOW_CREDENTIALS")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(defaults.allow_credentials),
+            max_age_secs: std::env::var("CORS_MAX_AGE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .or(defaults.max_age_secs),
+        }
+    }
+
+    /// Build the tower-http CORS layer for this configuration
+    pub fn layer(&self) -> CorsLayer {
+        let allow_any_origin = self.allowed_origins.iter().any(|o| o == "*");
+
+        let origin = if allow_any_origin {
+            AllowOrigin::any()
+        } else {
+            let origins: Vec<HeaderValue> = self
+                .allowed_origins
+                .iter()
+                .filter_map(|o| match o.parse::<HeaderValue>() {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        tracing::warn!("Ignoring invalid CORS origin '{}': {}", o, e);
+                        None
+                    }
+                })
+                .collect();
+            AllowOrigin::list(origins)
+        };
+
+        let methods: Vec<Method> = self
+            .allowed_methods
+            .iter()
+            .filter_map(|m| Method::from_bytes(m.to_uppercase().as_bytes()).ok())
+            .collect();
+
+        let headers: Vec<HeaderName> = self
+            .allowed_headers
+            .iter()
+            .filter_map(|h| HeaderName::from_bytes(h.to_lowercase().as_bytes()).ok())
+            .collect();
+
+        let mut layer = CorsLayer::new()
+            .allow_origin(origin)
+            .allow_methods(methods)
+            .allow_headers(headers);
+
+        // Credentials cannot be combined with a wildcard origin
+        if self.allow_credentials && !allow_any_origin {
+            layer = layer.allow_credentials(true);
+        } else if self.allow_credentials {
+            tracing::warn!("CORS credentials ignored because ALLOWED_ORIGINS contains '*'");
+        }
+
+        if let Some(max_age) = self.max_age_secs {
+            layer = layer.max_age(Duration::from_secs(max_age));
+        }
+
+        layer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn test_router(config: &CorsConfig) -> Router {
+        Router::new()
+            .route("/api/jobs", get(|| async { "ok" }))
+            .layer(config.layer())
+    }
+
+    fn preflight(origin: &str) -> Request<Body> {
+        Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/api/jobs")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_preflight_returns_configured_headers() {
+        let config = CorsConfig {
+            allowed_origins: vec!["https://dashboard.example.com".to_string()],
+            allowed_methods: vec!["GET".to_string(), "POST".to_string()],
+            allowed_headers: vec!["content-type".to_string()],
+            allow_credentials: true,
+            max_age_secs: Some(600),
+        };
+
+        let response = test_router(&config)
+            .oneshot(preflight("https://dashboard.example.com"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://dashboard.example.com"
+        );
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_METHODS], "GET,POST");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_HEADERS], "content-type");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "600");
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_origin_is_rejected() {
+        let config = CorsConfig {
+            allowed_origins: vec!["https://dashboard.example.com".to_string()],
+            ..CorsConfig::default()
+        };
+
+        let response = test_router(&config)
+            .oneshot(preflight("https://evil.example.com"))
+            .await
+            .unwrap();
+
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_default_allows_no_cross_origin() {
+        let response = test_router(&CorsConfig::default())
+            .oneshot(preflight("http://localhost:3000"))
+            .await
+            .unwrap();
+
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+}
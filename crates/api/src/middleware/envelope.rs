@@ -0,0 +1,142 @@
+use axum::{
+    body::{Body, HttpBody},
+    extract::Request,
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+
+use super::request_id::RequestId;
+
+/// Version reported in the `meta` of enveloped responses
+pub const API_VERSION: &str = "1.0";
+
+/// Bodies larger than this are passed through without an envelope
+const MAX_ENVELOPED_BODY_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Wrap successful JSON responses in `{ "data": ..., "meta": ... }`
+///
+/// `meta` carries the id of the request, as set by the request id middleware,
+/// the time of the response and [`API_VERSION`]. Errors, non-JSON responses
+/// and bodies whose size is unknown, such as event streams, are left as they
+/// are. Enabled by `AppConfig::response_envelope_enabled`.
+pub async fn response_envelope_middleware(
+    request_id: RequestId,
+    req: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(req).await;
+
+    let is_success = response.status().is_success();
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let small_enough = response
+        .body()
+        .size_hint()
+        .exact()
+        .is_some_and(|len| len <= MAX_ENVELOPED_BODY_BYTES);
+
+    if !(is_success && is_json && small_enough) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_ENVELOPED_BODY_BYTES as usize).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("Failed to buffer response body: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(data) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Body::from(
+                serde_json::json!({
+                    "data": data,
+                    "meta": {
+                        "request_id": request_id.as_str(),
+                        "timestamp": Utc::now(),
+                        "api_version": API_VERSION,
+                    },
+                })
+                .to_string(),
+            )
+        }
+        Err(_) => Body::from(bytes),
+    };
+
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::request_id::{request_id_middleware, REQUEST_ID_HEADER};
+    use axum::{http::StatusCode, routing::get, Json, Router};
+    use tower::ServiceExt;
+
+    fn test_router() -> Router {
+        Router::new()
+            .route(
+                "/api/jobs",
+                get(|| async { Json(serde_json::json!([{ "id": "job-1" }])) }),
+            )
+            .route("/api/text", get(|| async { "ok" }))
+            .route(
+                "/api/fail",
+                get(|| async {
+                    (
+                        StatusCode::NOT_FOUND,
+                        Json(serde_json::json!({ "error": "Not Found" })),
+                    )
+                }),
+            )
+            .layer(axum::middleware::from_fn(response_envelope_middleware))
+            .layer(axum::middleware::from_fn(request_id_middleware))
+    }
+
+    async fn get_body(uri: &str) -> (Response, Vec<u8>) {
+        let request = Request::builder()
+            .uri(uri)
+            .header(REQUEST_ID_HEADER, "envelope-1")
+            .body(Body::empty())
+            .unwrap();
+        let response = test_router().oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        (Response::from_parts(parts, Body::empty()), bytes.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_json_response_enveloped() {
+        let (response, body) = get_body("/api/jobs").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "envelope-1");
+
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["data"], serde_json::json!([{ "id": "job-1" }]));
+        assert_eq!(body["meta"]["request_id"], "envelope-1");
+        assert_eq!(body["meta"]["api_version"], API_VERSION);
+        let timestamp = body["meta"]["timestamp"].as_str().unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(timestamp).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_other_responses_not_enveloped() {
+        let (_, body) = get_body("/api/text").await;
+        assert_eq!(body, b"ok");
+
+        let (response, body) = get_body("/api/fail").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "Not Found");
+        assert!(body.get("data").is_none());
+        assert_eq!(body["request_id"], "envelope-1");
+    }
+}
@@ -0,0 +1,186 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Default `Retry-After` value (seconds) returned while maintenance is active
+pub const DEFAULT_MAINTENANCE_RETRY_AFTER: u64 = 300;
+
+/// Path prefix that stays writable during maintenance so the flag can be cleared
+const MAINTENANCE_EXEMPT_PREFIX: &str = "/admin/maintenance";
+
+/// Snapshot of the maintenance flag
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    pub reason: Option<String>,
+    pub retry_after_secs: u64,
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl Default for MaintenanceStatus {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reason: None,
+            retry_after_secs: DEFAULT_MAINTENANCE_RETRY_AFTER,
+            since: None,
+        }
+    }
+}
+
+/// Maintenance mode flag shared across handlers
+///
+/// While enabled, mutating requests are rejected with 503 and reads keep serving.
+#[derive(Debug, Default)]
+pub struct MaintenanceMode {
+    status: RwLock<MaintenanceStatus>,
+}
+
+impl MaintenanceMode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable maintenance mode
+    pub async fn enable(&self, reason: Option<String>, retry_after_secs: Option<u64>) {
+        let mut status = self.status.write().await;
+        status.enabled = true;
+        status.reason = reason;
+        status.retry_after_secs = retry_after_secs.unwrap_or(DEFAULT_MAINTENANCE_RETRY_AFTER);
+        status.since = Some(Utc::now());
+
+        warn!(
+            reason = ?status.reason,
+            retry_after_secs = status.retry_after_secs,
+            "Maintenance mode ENABLED - mutating requests will be rejected"
+        );
+    }
+
+    /// Disable maintenance mode
+    pub async fn disable(&self) {
+        let mut status = self.status.write().await;
+        *status = MaintenanceStatus::default();
+
+        info!("Maintenance mode DISABLED - mutating requests accepted again");
+    }
+
+    /// Get the current maintenance status
+    pub async fn status(&self) -> MaintenanceStatus {
+        self.status.read().await.clone()
+    }
+
+    /// Check whether maintenance mode is active
+    pub async fn is_enabled(&self) -> bool {
+        self.status.read().await.enabled
+    }
+}
+
+/// Reject mutating requests with 503 while maintenance mode is enabled
+pub async fn maintenance_middleware(
+    State(maintenance): State<Arc<MaintenanceMode>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let is_read = matches!(
+        *req.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+
+    if is_read || req.uri().path().starts_with(MAINTENANCE_EXEMPT_PREFIX) {
+        return next.run(req).await;
+    }
+
+    let status = maintenance.status().await;
+    if !status.enabled {
+        return next.run(req).await;
+    }
+
+    tracing::debug!(
+        method = %req.method(),
+        path = req.uri().path(),
+        "Rejecting mutating request during maintenance"
+    );
+
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::RETRY_AFTER, status.retry_after_secs.to_string())],
+        Json(serde_json::json!({
+            "error": "Service Unavailable",
+            "message": "Platform is in maintenance mode; writes are temporarily disabled",
+            "reason": status.reason,
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn test_router(maintenance: Arc<MaintenanceMode>) -> Router {
+        Router::new()
+            .route("/api/jobs", get(|| async { "ok" }).post(|| async { "ok" }))
+            .route("/admin/maintenance", axum::routing::post(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                maintenance,
+                maintenance_middleware,
+            ))
+    }
+
+    fn request(method: Method, uri: &str) -> Request {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_blocks_mutations_but_serves_reads() {
+        let maintenance = Arc::new(MaintenanceMode::new());
+        maintenance
+            .enable(Some("schema migration".to_string()), Some(120))
+            .await;
+        let router = test_router(maintenance.clone());
+
+        let response = router
+            .clone()
+            .oneshot(request(Method::POST, "/api/jobs"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "120");
+
+        let response = router
+            .clone()
+            .oneshot(request(Method::GET, "/api/jobs"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The toggle endpoint stays reachable so maintenance can be lifted
+        let response = router
+            .clone()
+            .oneshot(request(Method::POST, "/admin/maintenance"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        maintenance.disable().await;
+        let response = router
+            .oneshot(request(Method::POST, "/api/jobs"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
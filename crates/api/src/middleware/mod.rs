@@ -1,2 +1,9 @@
+pub mod auth;
+pub mod cidr_filter;
+pub mod cors;
+pub mod envelope;
+pub mod maintenance;
+pub mod rate_limit;
+pub mod request_id;
 pub mod security;
 pub mod tls;
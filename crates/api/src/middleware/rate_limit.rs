@@ -0,0 +1,587 @@
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::future::BoxFuture;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+use super::auth::AuthConfig;
+use super::cidr_filter::parse_range;
+
+/// Header identifying the calling hotkey
+pub const HOTKEY_HEADER: &str = "x-hotkey";
+
+/// Header carrying an API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Idle buckets are dropped after this long
+const FLUSH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Requests per minute for one endpoint category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryLimit {
+    pub name: String,
+    /// Requests whose path starts with this prefix belong to the category
+    pub path_prefix: String,
+    pub unauthenticated_per_minute: u32,
+    pub authenticated_per_minute: u32,
+}
+
+/// Rate limiting configuration
+///
+/// Requests whose API key or hotkey signature checks out are limited per
+/// owner at the authenticated rate; other requests are limited per client IP.
+/// The client IP is the peer address of the connection, or the address a
+/// trusted proxy forwarded the request for in `X-Forwarded-For`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub unauthenticated_per_minute: u32,
+    pub authenticated_per_minute: u32,
+    /// Overrides for specific endpoint categories; the longest matching prefix wins
+    pub categories: Vec<CategoryLimit>,
+    /// Addresses or CIDR ranges of the proxies whose `X-Forwarded-For` is trusted
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            unauthenticated_per_minute: 60,
+            authenticated_per_minute: 600,
+            categories: Vec::new(),
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Create configuration from environment variables
+    ///
+    /// `RATE_LIMIT_ENABLED`, `RATE_LIMIT_UNAUTHENTICATED_PER_MINUTE` and
+    /// `RATE_LIMIT_AUTHENTICATED_PER_MINUTE` are scalars. `RATE_LIMIT_CATEGORIES`
+    /// is a comma separated list of `name=path_prefix:unauthenticated:authenticated`,
+    /// e.g. `jobs=/jobs:30:300,orm=/orm:120:1200`. `RATE_LIMIT_TRUSTED_PROXIES`
+    /// is a comma separated list of proxy addresses or CIDR ranges.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        let parse_u32 = |var: &str, default: u32| -> u32 {
+            std::env::var(var)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default)
+        };
+
+        let categories = std::env::var("RATE_LIMIT_CATEGORIES")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .filter_map(|entry| match parse_category(entry) {
+                        Some(category) => Some(category),
+                        None => {
+                            tracing::warn!("Ignoring invalid rate limit category '{}'", entry);
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or(defaults.categories);
+
+        let trusted_proxies = std::env::var("RATE_LIMIT_TRUSTED_PROXIES")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or(defaults.trusted_proxies);
+
+        Self {
+            enabled: std::env::var("RATE_LIMIT_ENABLED")
+                .map(|v| v.to_lowercase() != "false")
+                .unwrap_or(defaults.enabled),
+            unauthenticated_per_minute: parse_u32(
+                "RATE_LIMIT_UNAUTHENTICATED_PER_MINUTE",
+                defaults.unauthenticated_per_minute,
+            ),
+            authenticated_per_minute: parse_u32(
+                "RATE_LIMIT_AUTHENTICATED_PER_MINUTE",
+                defaults.authenticated_per_minute,
+            ),
+            categories,
+            trusted_proxies,
+        }
+    }
+
+    /// Category name and limit for a request
+    fn limit_for(&self, path: &str, authenticated: bool) -> (&str, u32) {
+        let category = self
+            .categories
+            .iter()
+            .filter(|c| path.starts_with(&c.path_prefix))
+            .max_by_key(|c| c.path_prefix.len());
+
+        match (category, authenticated) {
+            (Some(c), true) => (&c.name, c.authenticated_per_minute),
+            (Some(c), false) => (&c.name, c.unauthenticated_per_minute),
+            (None, true) => ("default", self.authenticated_per_minute),
+            (None, false) => ("default", self.unauthenticated_per_minute),
+        }
+    }
+}
+
+fn parse_category(entry: &str) -> Option<CategoryLimit> {
+    let (name, rest) = entry.split_once('=')?;
+    let mut parts = rest.rsplitn(3, ':');
+    let authenticated_per_minute = parts.next()?.trim().parse().ok()?;
+    let unauthenticated_per_minute = parts.next()?.trim().parse().ok()?;
+    let path_prefix = parts.next()?.trim().to_string();
+
+    Some(CategoryLimit {
+        name: name.trim().to_string(),
+        path_prefix,
+        unauthenticated_per_minute,
+        authenticated_per_minute,
+    })
+}
+
+/// Token bucket refilled continuously at `per_minute / 60` tokens per second
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(per_minute: u32, now: Instant) -> Self {
+        let capacity = per_minute as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: capacity / 60.0,
+            last_refill: now,
+        }
+    }
+
+    /// Take one token, or return how long until one is available
+    pub fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else if self.refill_per_sec > 0.0 {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.refill_per_sec,
+            ))
+        } else {
+            Err(Duration::from_secs(60))
+        }
+    }
+}
+
+/// Shared bucket map behind a [`RateLimitLayer`]
+#[derive(Debug)]
+struct RateLimiter {
+    config: RateLimitConfig,
+    /// Checks the credentials requests are keyed by
+    auth: AuthConfig,
+    trusted_proxies: Vec<IpNet>,
+    buckets: Mutex<Buckets>,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    map: HashMap<String, TokenBucket>,
+    last_flush: Instant,
+}
+
+impl RateLimiter {
+    fn new(config: RateLimitConfig, auth: AuthConfig, now: Instant) -> Self {
+        let trusted_proxies = config
+            .trusted_proxies
+            .iter()
+            .filter_map(|proxy| match parse_range(proxy) {
+                Some(net) => Some(net),
+                None => {
+                    tracing::error!("Ignoring invalid trusted proxy '{}'", proxy);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            config,
+            auth,
+            trusted_proxies,
+            buckets: Mutex::new(Buckets {
+                map: HashMap::new(),
+                last_flush: now,
+            }),
+        }
+    }
+
+    fn check(&self, req: &Request, now: Instant) -> Result<(), Duration> {
+        let (client, authenticated) = self.client_key(req);
+        let (category, per_minute) = self.config.limit_for(req.uri().path(), authenticated);
+        let key = format!("{}:{}", category, client);
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Drop idle buckets so the map does not grow without bound
+        if now.saturating_duration_since(buckets.last_flush) >= FLUSH_INTERVAL {
+            buckets.map.retain(|_, bucket| {
+                now.saturating_duration_since(bucket.last_refill) < FLUSH_INTERVAL
+            });
+            buckets.last_flush = now;
+        }
+
+        buckets
+            .map
+            .entry(key)
+            .or_insert_with(|| TokenBucket::new(per_minute, now))
+            .try_acquire(now)
+    }
+
+    /// Rate limiting key of a request and whether it is authenticated
+    ///
+    /// Unverified `X-Hotkey` and `X-API-Key` values are never keys, so that
+    /// callers cannot spread their requests over made-up identities.
+    fn client_key(&self, req: &Request) -> (String, bool) {
+        let now_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if let Some(owner) =
+            self.auth
+                .authenticated_owner(req.method(), req.uri().path(), req.headers(), now_secs)
+        {
+            return (format!("owner:{}", owner), true);
+        }
+
+        let client_ip = self
+            .client_ip(req)
+            .map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+        (format!("ip:{}", client_ip), false)
+    }
+
+    /// Peer address of the connection or, when the peer is a trusted proxy,
+    /// the last address in `X-Forwarded-For` that is not a trusted proxy
+    fn client_ip(&self, req: &Request) -> Option<IpAddr> {
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_canonical())?;
+        if !self.is_trusted_proxy(peer) {
+            return Some(peer);
+        }
+
+        // Each proxy appends the address it received the request from
+        let forwarded_for = req
+            .headers()
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let mut client = peer;
+        for hop in forwarded_for.rsplit(',') {
+            let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+                break;
+            };
+            client = ip.to_canonical();
+            if !self.is_trusted_proxy(client) {
+                break;
+            }
+        }
+        Some(client)
+    }
+
+    fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+}
+
+/// Tower layer enforcing per-key token bucket limits
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitLayer {
+    /// Limit requests as configured, keying those whose credentials `auth`
+    /// accepts by their owner
+    pub fn new(config: RateLimitConfig, auth: AuthConfig) -> Self {
+        Self {
+            limiter: Arc::new(RateLimiter::new(config, auth, Instant::now())),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+/// Service produced by [`RateLimitLayer`]
+#[derive(Debug, Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S> Service<Request> for RateLimitService<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        if self.limiter.config.enabled {
+            if let Err(wait) = self.limiter.check(&req, Instant::now()) {
+                return Box::pin(async move { Ok(too_many_requests(wait)) });
+            }
+        }
+
+        Box::pin(self.inner.call(req))
+    }
+}
+
+fn too_many_requests(wait: Duration) -> Response {
+    let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(serde_json::json!({
+            "error": "Too Many Requests",
+            "retry_after_secs": retry_after
+        })),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::post, Router};
+    use tower::ServiceExt;
+
+    fn auth() -> AuthConfig {
+        AuthConfig {
+            api_keys: HashMap::from([
+                ("key-a".to_string(), "alice".to_string()),
+                ("key-b".to_string(), "bob".to_string()),
+            ]),
+            ..AuthConfig::default()
+        }
+    }
+
+    fn router(config: RateLimitConfig) -> Router {
+        Router::new()
+            .route("/jobs", post(|| async { "created" }))
+            .route("/orm/query", post(|| async { "ok" }))
+            .layer(RateLimitLayer::new(config, auth()))
+    }
+
+    fn request(uri: &str, api_key: Option<&str>) -> Request {
+        request_from("10.0.0.1", uri, &[(API_KEY_HEADER, api_key)])
+    }
+
+    fn request_from(peer: &str, uri: &str, headers: &[(&str, Option<&str>)]) -> Request {
+        let mut builder = Request::builder().method("POST").uri(uri);
+        for (name, value) in headers {
+            if let Some(value) = value {
+                builder = builder.header(*name, *value);
+            }
+        }
+        let mut req = builder.body(Body::empty()).unwrap();
+        let peer = SocketAddr::new(peer.parse().unwrap(), 40000);
+        req.extensions_mut().insert(ConnectInfo(peer));
+        req
+    }
+
+    async fn allowed(app: &Router, uri: &str, api_key: Option<&str>) -> usize {
+        let mut count = 0;
+        for _ in 0..1000 {
+            let response = app.clone().oneshot(request(uri, api_key)).await.unwrap();
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                break;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    fn limiter(trusted_proxies: &[&str]) -> RateLimiter {
+        let config = RateLimitConfig {
+            trusted_proxies: trusted_proxies.iter().map(|p| p.to_string()).collect(),
+            ..RateLimitConfig::default()
+        };
+        RateLimiter::new(config, auth(), Instant::now())
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_bucket_returns_429_with_retry_after() {
+        let app = router(RateLimitConfig::default());
+
+        assert_eq!(allowed(&app, "/jobs", None).await, 60);
+
+        let response = app.oneshot(request("/jobs", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    }
+
+    #[tokio::test]
+    async fn test_limits_are_per_key_and_authenticated_rate() {
+        let config = RateLimitConfig::default();
+        assert_eq!(config.limit_for("/jobs", true), ("default", 600));
+        assert_eq!(config.limit_for("/jobs", false), ("default", 60));
+
+        let app = router(RateLimitConfig {
+            unauthenticated_per_minute: 2,
+            authenticated_per_minute: 20,
+            ..config
+        });
+
+        assert_eq!(allowed(&app, "/jobs", Some("key-a")).await, 20);
+        // Another owner and the unauthenticated caller have their own buckets
+        assert_eq!(allowed(&app, "/jobs", Some("key-b")).await, 20);
+        assert_eq!(allowed(&app, "/jobs", None).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_unverified_identities_share_the_peer_bucket() {
+        let app = router(RateLimitConfig {
+            unauthenticated_per_minute: 2,
+            ..RateLimitConfig::default()
+        });
+
+        assert_eq!(allowed(&app, "/jobs", Some("made-up-key")).await, 2);
+        // Neither another unknown key nor a bare hotkey opens a new bucket
+        assert_eq!(allowed(&app, "/jobs", Some("another-key")).await, 0);
+        let spoofed = request_from("10.0.0.1", "/jobs", &[(HOTKEY_HEADER, Some("5Grw"))]);
+        let response = app.clone().oneshot(spoofed).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Another peer still has its own
+        let other = request_from("10.0.0.2", "/jobs", &[]);
+        let response = app.oneshot(other).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_forwarded_for_only_trusted_from_proxies() {
+        let forwarded = [("x-forwarded-for", Some("203.0.113.7, 10.1.0.5"))];
+
+        // Without trusted proxies the header is ignored
+        let (key, authenticated) =
+            limiter(&[]).client_key(&request_from("198.51.100.1", "/jobs", &forwarded));
+        assert_eq!(key, "ip:198.51.100.1");
+        assert!(!authenticated);
+
+        // A trusted proxy is skipped, along with proxies it forwarded for
+        let limiter = limiter(&["198.51.100.1", "10.1.0.0/16"]);
+        let (key, _) = limiter.client_key(&request_from("198.51.100.1", "/jobs", &forwarded));
+        assert_eq!(key, "ip:203.0.113.7");
+
+        // Other peers cannot claim to be forwarding
+        let (key, _) = limiter.client_key(&request_from("192.0.2.9", "/jobs", &forwarded));
+        assert_eq!(key, "ip:192.0.2.9");
+
+        let (key, authenticated) = limiter.client_key(&request_from(
+            "198.51.100.1",
+            "/jobs",
+            &[(API_KEY_HEADER, Some("key-a")), forwarded[0]],
+        ));
+        assert_eq!(key, "owner:alice");
+        assert!(authenticated);
+    }
+
+    #[tokio::test]
+    async fn test_category_limits() {
+        let app = router(RateLimitConfig {
+            categories: vec![CategoryLimit {
+                name: "jobs".to_string(),
+                path_prefix: "/jobs".to_string(),
+                unauthenticated_per_minute: 5,
+                authenticated_per_minute: 10,
+            }],
+            ..RateLimitConfig::default()
+        });
+
+        assert_eq!(allowed(&app, "/jobs", None).await, 5);
+        assert_eq!(allowed(&app, "/jobs", Some("key-a")).await, 10);
+        // Other endpoints keep the default limit
+        assert_eq!(allowed(&app, "/orm/query", None).await, 60);
+    }
+
+    #[test]
+    fn test_bucket_refills_and_idle_buckets_are_flushed() {
+        let start = Instant::now();
+        let limiter = RateLimiter::new(RateLimitConfig::default(), auth(), start);
+        let req = request("/jobs", None);
+
+        for _ in 0..60 {
+            assert!(limiter.check(&req, start).is_ok());
+        }
+        let wait = limiter.check(&req, start).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(1));
+        assert!(limiter.check(&req, start + Duration::from_secs(1)).is_ok());
+
+        let later = start + FLUSH_INTERVAL + Duration::from_secs(1);
+        limiter
+            .check(&request("/jobs", Some("key-b")), later)
+            .unwrap();
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.map.len(), 1);
+        assert!(buckets.map.contains_key("default:owner:bob"));
+    }
+
+    #[test]
+    fn test_parse_category() {
+        let category = parse_category("jobs=/jobs:30:300").unwrap();
+        assert_eq!(category.name, "jobs");
+        assert_eq!(category.path_prefix, "/jobs");
+        assert_eq!(category.unauthenticated_per_minute, 30);
+        assert_eq!(category.authenticated_per_minute, 300);
+
+        assert!(parse_category("jobs=/jobs:thirty:300").is_none());
+        assert!(parse_category("/jobs:30:300").is_none());
+    }
+}
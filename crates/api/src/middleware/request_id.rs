@@ -0,0 +1,308 @@
+use axum::{
+    body::{Body, HttpBody},
+    extract::{FromRequestParts, Request},
+    http::{header, request::Parts, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::convert::Infallible;
+use std::future::Future;
+use tracing::Instrument;
+
+/// Header carrying the request correlation id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Maximum accepted length of a client supplied request id
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Error bodies larger than this are passed through without the request id
+const MAX_ERROR_BODY_BYTES: u64 = 64 * 1024;
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// Correlation id of the current request, available as a request extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Generate a new random request id
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Accept a client supplied id if it is short and made of safe characters
+    pub fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?;
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        valid.then(|| Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Extracting a `RequestId` gives the id set by [`request_id_middleware`],
+/// or a new one for requests that did not go through it
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequestId {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(RequestId::generate))
+    }
+}
+
+/// Request id of the task currently being served, if any
+///
+/// Set for HTTP requests by [`request_id_middleware`] and for WebSocket
+/// connections by [`scope_request_id`].
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Run a future with `request_id` as the current request id
+pub async fn scope_request_id<F: Future>(request_id: String, future: F) -> F::Output {
+    CURRENT_REQUEST_ID.scope(request_id, future).await
+}
+
+/// Forward the current request id on outbound HTTP calls
+pub trait RequestIdExt {
+    fn with_request_id(self) -> Self;
+}
+
+impl RequestIdExt for reqwest::RequestBuilder {
+    fn with_request_id(self) -> Self {
+        match current_request_id() {
+            Some(request_id) => self.header(REQUEST_ID_HEADER, request_id),
+            None => self,
+        }
+    }
+}
+
+/// Extract or generate an `X-Request-Id` and wrap the request in a tracing span
+///
+/// The id is echoed in the response header and added to JSON error bodies.
+pub async fn request_id_middleware(mut req: Request, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(RequestId::from_header)
+        .unwrap_or_else(RequestId::generate);
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = req.uri().path(),
+    );
+
+    req.extensions_mut().insert(request_id.clone());
+
+    let response = scope_request_id(request_id.0.clone(), next.run(req).instrument(span)).await;
+    let mut response = attach_request_id_to_error(response, &request_id).await;
+
+    if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
+
+/// Add `request_id` to JSON error bodies so clients can quote it in reports
+async fn attach_request_id_to_error(response: Response, request_id: &RequestId) -> Response {
+    let is_error = response.status().is_client_error() || response.status().is_server_error();
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let small_enough = response
+        .body()
+        .size_hint()
+        .exact()
+        .is_some_and(|len| len <= MAX_ERROR_BODY_BYTES);
+
+    if !(is_error && is_json && small_enough) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_ERROR_BODY_BYTES as usize).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("Failed to buffer error body: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(mut map)) => {
+            map.entry("request_id")
+                .or_insert_with(|| serde_json::Value::String(request_id.0.clone()));
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Body::from(serde_json::Value::Object(map).to_string())
+        }
+        _ => Body::from(bytes),
+    };
+
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get, Json, Router};
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    /// Log sink shared with the fmt subscriber
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture_logs() -> (CapturedLogs, tracing::subscriber::DefaultGuard) {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        (logs, tracing::subscriber::set_default(subscriber))
+    }
+
+    fn log_line(logs: &CapturedLogs, message: &str) -> String {
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        output
+            .lines()
+            .find(|line| line.contains(message))
+            .unwrap_or_else(|| panic!("no log line containing '{}'", message))
+            .to_string()
+    }
+
+    #[tracing::instrument(name = "job_distribution", skip_all)]
+    async fn distribute_job() {
+        tracing::info!("distributing job");
+        tokio::spawn(async { tracing::info!("job sent to validator") }.in_current_span())
+            .await
+            .unwrap();
+    }
+
+    fn test_router() -> Router {
+        Router::new()
+            .route(
+                "/api/jobs",
+                get(|| async {
+                    tracing::info!("listing jobs");
+                    "ok"
+                }),
+            )
+            .route(
+                "/api/jobs/distribute",
+                axum::routing::post(|| async {
+                    distribute_job().await;
+                    "ok"
+                }),
+            )
+            .route(
+                "/api/fail",
+                get(|| async {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({ "error": "Bad Request" })),
+                    )
+                }),
+            )
+            .layer(axum::middleware::from_fn(request_id_middleware))
+    }
+
+    fn request(uri: &str, request_id: Option<&str>) -> Request {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(id) = request_id {
+            builder = builder.header(REQUEST_ID_HEADER, id);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_id_round_trip_and_logged() {
+        let (logs, _guard) = capture_logs();
+
+        let response = test_router()
+            .oneshot(request("/api/jobs", Some("claim-1402")))
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "claim-1402");
+
+        let line = log_line(&logs, "listing jobs");
+        assert!(line.contains("request_id=claim-1402"), "{}", line);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_reaches_downstream_spans() {
+        let (logs, _guard) = capture_logs();
+
+        let mut req = request("/api/jobs/distribute", Some("dist-7"));
+        *req.method_mut() = axum::http::Method::POST;
+        let response = test_router().oneshot(req).await.unwrap();
+
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "dist-7");
+
+        // Component spans and spawned tasks stay nested under the request span
+        let line = log_line(&logs, "distributing job");
+        assert!(line.contains("request_id=dist-7"), "{}", line);
+        assert!(line.contains("job_distribution"), "{}", line);
+
+        let line = log_line(&logs, "job sent to validator");
+        assert!(line.contains("request_id=dist-7"), "{}", line);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_generated_and_added_to_error_body() {
+        let response = test_router()
+            .oneshot(request("/api/fail", Some("bad id with spaces")))
+            .await
+            .unwrap();
+
+        let request_id = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_ne!(request_id, "bad id with spaces");
+        assert!(uuid::Uuid::parse_str(&request_id).is_ok());
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "Bad Request");
+        assert_eq!(body["request_id"], request_id);
+    }
+}
@@ -97,44 +97,9 @@ pub async fn attestation_middleware(
     }
 }
 
-/// CORS configuration for production
+/// CORS layer built from environment configuration (see `CorsConfig::from_env`)
 pub fn cors_layer() -> tower_http::cors::CorsLayer {
-    use tower_http::cors::CorsLayer;
-
-    let allowed_origins_str = std::env::var("ALLOWED_ORIGINS")
-        .unwrap_or_else(|_| "https://api.platform.network,http://localhost:3000".to_string());
-
-    let origins: Result<Vec<_>, _> = allowed_origins_str
-        .split(',')
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.parse::<axum::http::HeaderValue>())
-        .collect();
-
-    match origins {
-        Ok(valid_origins) => CorsLayer::new()
-            .allow_origin(valid_origins)
-            .allow_methods([
-                axum::http::Method::GET,
-                axum::http::Method::POST,
-                axum::http::Method::PUT,
-                axum::http::Method::DELETE,
-                axum::http::Method::OPTIONS,
-            ])
-            .allow_headers([
-                header::AUTHORIZATION,
-                header::CONTENT_TYPE,
-                header::HeaderName::from_static("x-attestation-token"),
-            ])
-            .allow_credentials(true),
-        Err(e) => {
-            tracing::warn!(
-                "Failed to parse ALLOWED_ORIGINS, using permissive CORS: {}",
-                e
-            );
-            CorsLayer::permissive()
-        }
-    }
+    super::cors::CorsConfig::from_env().layer()
 }
 
 /// Security headers middleware
@@ -199,6 +164,17 @@ pub async fn ip_whitelist_middleware(req: Request, next: Next) -> Result<Respons
         return Ok(next.run(req).await);
     }
 
+    admin_only_middleware(req, next).await
+}
+
+/// Identity of the whitelisted admin making the request
+#[derive(Debug, Clone)]
+pub struct AdminActor(pub String);
+
+/// Restrict a router to `ADMIN_IP_WHITELIST` regardless of path
+///
+/// The client IP is made available to handlers as an [`AdminActor`] extension.
+pub async fn admin_only_middleware(mut req: Request, next: Next) -> Result<Response, StatusCode> {
     let whitelisted_ips = std::env::var("ADMIN_IP_WHITELIST")
         .unwrap_or_else(|_| "127.0.0.1,::1".to_string())
         .split(',')
@@ -211,9 +187,11 @@ pub async fn ip_whitelist_middleware(req: Request, next: Next) -> Result<Respons
         .get("x-forwarded-for")
         .and_then(|v| v.to_str().ok())
         .and_then(|s| s.split(',').next())
-        .unwrap_or("unknown");
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
 
-    if whitelisted_ips.contains(&client_ip.to_string()) {
+    if whitelisted_ips.contains(&client_ip) {
+        req.extensions_mut().insert(AdminActor(client_ip));
         Ok(next.run(req).await)
     } else {
         tracing::warn!("Admin access denied from IP: {}", client_ip);
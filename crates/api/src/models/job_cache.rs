@@ -57,6 +57,24 @@ impl JobCache {
         self.updated_at = Utc::now();
     }
 
+    /// Drop a disconnected validator from the running job, which is back to
+    /// pending once none of its validators is left
+    pub fn release_validator(&mut self, validator_hotkey: &str) {
+        if self.status != JobStatus::Running {
+            return;
+        }
+        let assigned = self.assigned_validators.len();
+        self.assigned_validators
+            .retain(|hotkey| hotkey != validator_hotkey);
+        if self.assigned_validators.len() == assigned {
+            return;
+        }
+        if self.assigned_validators.is_empty() {
+            self.status = JobStatus::Pending;
+        }
+        self.updated_at = Utc::now();
+    }
+
     pub fn mark_completed(&mut self) {
         self.status = JobStatus::Completed;
         self.updated_at = Utc::now();
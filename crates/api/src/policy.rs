@@ -0,0 +1,128 @@
+//! Authorization policy for owned resources
+
+use axum::http::StatusCode;
+use uuid::Uuid;
+
+use crate::middleware::auth::Caller;
+use crate::state::AppState;
+
+/// Operations on a challenge that are restricted to its owner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeAction {
+    Update,
+    Delete,
+    Clone,
+    ViewEmissions,
+    RequestCredentials,
+    Build,
+    ViewBuilds,
+    ViewReceipts,
+    CheckHealth,
+    ViewState,
+}
+
+/// Decide whether `caller` may perform `action` on a resource owned by `owner`
+///
+/// Owners may act on their own resources; admins may act on any.
+pub fn authorize(caller: &Caller, owner: &str, action: ChallengeAction) -> Result<(), StatusCode> {
+    if caller.admin || caller.owner == owner {
+        Ok(())
+    } else {
+        tracing::warn!(
+            caller = %caller.owner,
+            owner = owner,
+            action = ?action,
+            "Denied access to resource of another owner"
+        );
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+/// Authorize `action` on challenge `challenge_id` and return its owner
+///
+/// Without a database only admins are authorized, since ownership is unknown.
+pub async fn authorize_challenge(
+    state: &AppState,
+    caller: &Caller,
+    challenge_id: Uuid,
+    action: ChallengeAction,
+) -> Result<String, StatusCode> {
+    let Some(pool) = &state.database_pool else {
+        return authorize(caller, "", action).map(|_| caller.owner.clone());
+    };
+
+    let owner: String = sqlx::query_scalar("SELECT owner FROM challenges WHERE id = $1")
+        .bind(challenge_id)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load owner of challenge {}: {}", challenge_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    authorize(caller, &owner, action)?;
+    Ok(owner)
+}
+
+/// Owner to filter a listing by, for `mine=true`
+pub fn owner_filter(mine: bool, caller: Option<&Caller>) -> Result<Option<String>, StatusCode> {
+    match (mine, caller) {
+        (false, _) => Ok(None),
+        (true, Some(caller)) => Ok(Some(caller.owner.clone())),
+        (true, None) => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caller(owner: &str, admin: bool) -> Caller {
+        Caller {
+            owner: owner.to_string(),
+            admin,
+        }
+    }
+
+    #[test]
+    fn test_cross_owner_update_rejected() {
+        let alice = caller("alice", false);
+        assert_eq!(authorize(&alice, "alice", ChallengeAction::Update), Ok(()));
+        assert_eq!(
+            authorize(&alice, "bob", ChallengeAction::Update),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            authorize(&alice, "bob", ChallengeAction::Delete),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[test]
+    fn test_admin_override() {
+        let admin = caller("root", true);
+        for action in [
+            ChallengeAction::Update,
+            ChallengeAction::Delete,
+            ChallengeAction::Clone,
+            ChallengeAction::ViewEmissions,
+            ChallengeAction::RequestCredentials,
+            ChallengeAction::Build,
+            ChallengeAction::ViewBuilds,
+            ChallengeAction::ViewReceipts,
+            ChallengeAction::CheckHealth,
+        ] {
+            assert_eq!(authorize(&admin, "bob", action), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_mine_filter() {
+        let alice = caller("alice", false);
+        assert_eq!(owner_filter(false, Some(&alice)), Ok(None));
+        assert_eq!(owner_filter(false, None), Ok(None));
+        assert_eq!(owner_filter(true, Some(&alice)), Ok(Some("alice".to_string())));
+        assert_eq!(owner_filter(true, None), Err(StatusCode::UNAUTHORIZED));
+    }
+}
@@ -1,8 +1,11 @@
 use anyhow::{Context, Result};
+use futures::{Stream, StreamExt};
+use platform_api_models::BuildLogEvent;
 use redis::aio::ConnectionManager;
 use redis::{AsyncCommands, Client};
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
+use uuid::Uuid;
 
 /// Redis client for job progress logging
 #[derive(Clone)]
@@ -158,6 +161,71 @@ impl RedisClient {
         Ok(())
     }
 
+    /// Record a build log event and publish it to live subscribers
+    pub async fn publish_build_log(&self, event: &BuildLogEvent) -> Result<()> {
+        let mut conn = self.get_connection().await?;
+        let key = format!("build:{}:logs", event.build_id);
+
+        let json = serde_json::to_string(event).context("Failed to serialize build log event")?;
+
+        // Keep the backlog for late subscribers, with TTL of 24 hours
+        conn.rpush::<_, _, ()>(&key, &json)
+            .await
+            .context("Failed to append build log in Redis")?;
+        conn.expire::<_, ()>(&key, 86400)
+            .await
+            .context("Failed to set TTL on build logs")?;
+
+        conn.publish::<_, _, ()>(build_log_channel(event.build_id), json)
+            .await
+            .context("Failed to publish build log event")?;
+
+        Ok(())
+    }
+
+    /// Get the build log events recorded so far
+    pub async fn get_build_logs(&self, build_id: Uuid) -> Result<Vec<BuildLogEvent>> {
+        let mut conn = self.get_connection().await?;
+        let key = format!("build:{}:logs", build_id);
+
+        let json_strings: Vec<String> = conn
+            .lrange(&key, 0, -1)
+            .await
+            .context("Failed to get build logs from Redis")?;
+
+        Ok(json_strings
+            .iter()
+            .filter_map(|json_str| match serde_json::from_str(json_str) {
+                Ok(event) => Some(event),
+                Err(e) => {
+                    warn!("Failed to deserialize build log event: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Subscribe to build log events published from now on
+    pub async fn subscribe_build_logs(
+        &self,
+        build_id: Uuid,
+    ) -> Result<impl Stream<Item = BuildLogEvent>> {
+        let mut pubsub = self
+            .client
+            .get_async_pubsub()
+            .await
+            .context("Failed to open Redis pub/sub connection")?;
+        pubsub
+            .subscribe(build_log_channel(build_id))
+            .await
+            .context("Failed to subscribe to build logs")?;
+
+        Ok(pubsub.into_on_message().filter_map(|msg| async move {
+            let payload: String = msg.get_payload().ok()?;
+            serde_json::from_str(&payload).ok()
+        }))
+    }
+
     /// Test Redis connection
     pub async fn test_connection(&self) -> Result<()> {
         let mut conn = self.get_connection().await?;
@@ -170,6 +238,11 @@ impl RedisClient {
     }
 }
 
+/// Pub/sub channel carrying the log events of a build
+pub fn build_log_channel(build_id: Uuid) -> String {
+    format!("build:{}:events", build_id)
+}
+
 /// Helper function to create a job progress update
 pub fn create_job_progress(
     job_id: String,
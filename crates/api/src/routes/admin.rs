@@ -0,0 +1,79 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::middleware::maintenance::MaintenanceStatus;
+use crate::middleware::security::ip_whitelist_middleware;
+use crate::state::AppState;
+
+/// Create admin router
+///
+/// All routes are restricted to `ADMIN_IP_WHITELIST`.
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/admin/maintenance",
+            get(get_maintenance).post(set_maintenance),
+        )
+        .route(
+            "/admin/validators/:hotkey/revoke-sessions",
+            post(revoke_validator_sessions),
+        )
+        .route_layer(axum::middleware::from_fn(ip_whitelist_middleware))
+}
+
+/// Request to toggle maintenance mode
+#[derive(Debug, Deserialize)]
+pub struct SetMaintenanceRequest {
+    pub enabled: bool,
+    pub reason: Option<String>,
+    pub retry_after_secs: Option<u64>,
+}
+
+/// Get current maintenance mode status
+pub async fn get_maintenance(State(state): State<AppState>) -> Json<MaintenanceStatus> {
+    Json(state.maintenance.status().await)
+}
+
+/// Enable or disable maintenance mode
+pub async fn set_maintenance(
+    State(state): State<AppState>,
+    Json(request): Json<SetMaintenanceRequest>,
+) -> Result<Json<MaintenanceStatus>, StatusCode> {
+    if request.enabled {
+        state
+            .maintenance
+            .enable(request.reason, request.retry_after_secs)
+            .await;
+    } else {
+        state.maintenance.disable().await;
+    }
+
+    Ok(Json(state.maintenance.status().await))
+}
+
+/// Sessions revoked for a validator
+#[derive(Debug, Serialize)]
+pub struct RevokeSessionsResponse {
+    pub hotkey: String,
+    pub revoked: usize,
+}
+
+/// Revoke the sessions of a validator, connected or suspended, so that it has
+/// to attest again
+pub async fn revoke_validator_sessions(
+    State(state): State<AppState>,
+    Path(hotkey): Path<String>,
+) -> Result<Json<RevokeSessionsResponse>, StatusCode> {
+    let revoked = state.revoke_validator_sessions(&hotkey).await;
+    if revoked == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(RevokeSessionsResponse { hotkey, revoked }))
+}
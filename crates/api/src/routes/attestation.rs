@@ -1,6 +1,6 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::Json,
     routing::{get, post},
     Router,
@@ -11,10 +11,11 @@ use ring::rand::{SecureRandom, SystemRandom};
 use serde::Serialize;
 use uuid::Uuid;
 
+use crate::error::ApiResult;
 use crate::state::AppState;
 use platform_api_models::{
     AttestationRequest, AttestationResponse, AttestationSession, KeyReleaseRequest,
-    KeyReleaseResponse,
+    KeyReleaseResponse, PlatformError, RefreshSessionRequest,
 };
 
 /// Create attestation router
@@ -22,6 +23,7 @@ pub fn create_router() -> Router<AppState> {
     Router::new()
         .route("/attestation/challenge", post(create_challenge))
         .route("/attestation/verify", post(verify_attestation))
+        .route("/attestation/refresh", post(refresh_session))
         .route("/attest", post(attest))
         .route("/attest/sessions/:id", get(get_attestation_session))
         .route("/keys/release", post(release_key))
@@ -31,15 +33,13 @@ pub fn create_router() -> Router<AppState> {
 }
 
 /// Perform attestation
+///
+/// Attestations that do not verify are refused with 401.
 pub async fn attest(
     State(state): State<AppState>,
     Json(request): Json<AttestationRequest>,
-) -> Result<Json<AttestationResponse>, StatusCode> {
-    let response = state
-        .attestation
-        .verify_attestation(request)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+) -> ApiResult<Json<AttestationResponse>> {
+    let response = state.attestation.verify_attestation(request).await?;
 
     Ok(Json(response))
 }
@@ -69,25 +69,44 @@ pub async fn create_challenge(
 pub async fn verify_attestation(
     State(state): State<AppState>,
     Json(request): Json<AttestationRequest>,
-) -> Result<Json<AttestationResponse>, StatusCode> {
+) -> ApiResult<Json<AttestationResponse>> {
+    let response = state.attestation.verify_attestation(request).await?;
+    Ok(Json(response))
+}
+
+/// Exchange the refresh token of an attestation for a new session token
+///
+/// Refresh tokens that are invalid or expired are refused with 401, after
+/// which the validator has to attest again.
+pub async fn refresh_session(
+    State(state): State<AppState>,
+    Json(request): Json<RefreshSessionRequest>,
+) -> ApiResult<Json<AttestationResponse>> {
     let response = state
         .attestation
-        .verify_attestation(request)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .refresh_session(&request.refresh_token)
+        .await?;
     Ok(Json(response))
 }
 
-/// Get attestation session
+/// Get attestation session of the validator the `X-Attestation-Token` was issued to
+///
+/// Sessions of other validators are reported as not found.
 pub async fn get_attestation_session(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
-) -> Result<Json<AttestationSession>, StatusCode> {
-    let session = state
-        .attestation
-        .get_session(id)
-        .await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+) -> ApiResult<Json<AttestationSession>> {
+    let token = headers
+        .get("X-Attestation-Token")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| PlatformError::unauthorized("Missing X-Attestation-Token header"))?;
+    let claims = state.attestation.verify_token_async(token).await?;
+    let validator_hotkey = claims["session_namespace"]
+        .as_str()
+        .ok_or_else(|| PlatformError::unauthorized("Token names no session namespace"))?;
+
+    let session = state.attestation.get_session(id, validator_hotkey).await?;
 
     Ok(Json(session))
 }
@@ -128,12 +147,8 @@ pub async fn verify_key(
 /// List attestation policies
 pub async fn list_policies(
     State(state): State<AppState>,
-) -> Result<Json<Vec<platform_api_models::AttestationPolicy>>, StatusCode> {
-    let policies = state
-        .attestation
-        .list_policies()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+) -> ApiResult<Json<Vec<platform_api_models::AttestationPolicy>>> {
+    let policies = state.attestation.list_policies().await?;
 
     Ok(Json(policies))
 }
@@ -142,12 +157,8 @@ pub async fn list_policies(
 pub async fn get_policy(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<platform_api_models::AttestationPolicy>, StatusCode> {
-    let policy = state
-        .attestation
-        .get_policy(&id)
-        .await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+) -> ApiResult<Json<platform_api_models::AttestationPolicy>> {
+    let policy = state.attestation.get_policy(&id).await?;
 
     Ok(Json(policy))
 }
@@ -0,0 +1,242 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+    routing::get,
+    Router,
+};
+use futures::{future, stream, Stream, StreamExt};
+use platform_api_models::{BuildLogEvent, ImageBuild, PlatformError};
+use tokio::sync::mpsc;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::middleware::auth::Caller;
+use crate::policy::{authorize_challenge, ChallengeAction};
+use crate::state::AppState;
+
+/// Create builds router
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/builds/:build_id", get(get_build).delete(cancel_build))
+        .route("/builds/:build_id/logs", get(stream_build_logs))
+}
+
+/// Forward the log events of build `build_id` to Redis, in order, for
+/// `GET /builds/:build_id/logs`
+pub(crate) fn forward_build_log(
+    state: &AppState,
+    build_id: Uuid,
+    mut events: mpsc::UnboundedReceiver<BuildLogEvent>,
+) {
+    let redis = state.redis_client.clone();
+    tokio::spawn(
+        async move {
+            if redis.is_none() {
+                tracing::warn!(
+                    "Redis unavailable, build {} logs will not be streamed",
+                    build_id
+                );
+            }
+            while let Some(event) = events.recv().await {
+                if let Some(redis) = &redis {
+                    if let Err(e) = redis.publish_build_log(&event).await {
+                        tracing::warn!("Failed to publish build {} log: {}", build_id, e);
+                    }
+                }
+            }
+        }
+        .in_current_span(),
+    );
+}
+
+/// Get the status and log tail of an image build (owner of its challenge or admin only)
+pub async fn get_build(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(build_id): Path<Uuid>,
+) -> Result<Json<ImageBuild>, Response> {
+    let build = state
+        .builder
+        .image_builds()
+        .get(build_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::from(PlatformError::not_found("build", build_id)))?;
+    authorize_challenge(
+        &state,
+        &caller,
+        build.challenge_id,
+        ChallengeAction::ViewBuilds,
+    )
+    .await
+    .map_err(IntoResponse::into_response)?;
+
+    Ok(Json(build))
+}
+
+/// Cancel a queued image build (owner of its challenge or admin only)
+///
+/// A build that already started, or is over, is not cancelled and is refused
+/// with 409.
+pub async fn cancel_build(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(build_id): Path<Uuid>,
+) -> Result<Json<ImageBuild>, Response> {
+    let not_found = || ApiError::from(PlatformError::not_found("build", build_id));
+    let builds = state.builder.image_builds();
+    let build = builds
+        .get(build_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(not_found)?;
+    authorize_challenge(&state, &caller, build.challenge_id, ChallengeAction::Build)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let build = builds
+        .cancel(build_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(build))
+}
+
+/// Stream the log of a build as server-sent events (owner of its challenge
+/// or admin only)
+///
+/// Events recorded before the client connected are replayed first. The
+/// stream ends after the final event of the build.
+pub async fn stream_build_logs(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(build_id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, Response> {
+    let build = state
+        .builder
+        .image_builds()
+        .get(build_id)
+        .await
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::from(PlatformError::not_found("build", build_id)))?;
+    authorize_challenge(
+        &state,
+        &caller,
+        build.challenge_id,
+        ChallengeAction::ViewBuilds,
+    )
+    .await
+    .map_err(IntoResponse::into_response)?;
+
+    let redis = state
+        .redis_client
+        .clone()
+        .ok_or_else(|| StatusCode::SERVICE_UNAVAILABLE.into_response())?;
+
+    // Subscribe before reading the backlog so no event falls in between
+    let live = redis.subscribe_build_logs(build_id).await.map_err(|e| {
+        tracing::error!("Failed to subscribe to build {} logs: {}", build_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    let backlog = redis.get_build_logs(build_id).await.map_err(|e| {
+        tracing::error!("Failed to get build {} logs: {}", build_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let events = build_log_events(backlog, live)
+        .map(|event| Event::default().event("build_log").json_data(event));
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Replay `backlog`, then follow `live` until the build is done
+fn build_log_events(
+    backlog: Vec<BuildLogEvent>,
+    live: impl Stream<Item = BuildLogEvent>,
+) -> impl Stream<Item = BuildLogEvent> {
+    // Live events already present in the backlog are dropped
+    let replayed_until = backlog.last().map(|event| event.timestamp);
+    let live = live.filter(move |event| {
+        future::ready(replayed_until.map_or(true, |until| event.timestamp > until))
+    });
+
+    let events = Box::pin(stream::iter(backlog).chain(live));
+    stream::unfold((events, false), |(mut events, finished)| async move {
+        if finished {
+            return None;
+        }
+        let event = events.next().await?;
+        let finished = event.done;
+        Some((event, (events, finished)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use platform_api_models::BuildLogLevel;
+
+    fn event(build_id: Uuid, offset_ms: i64, message: &str, done: bool) -> BuildLogEvent {
+        BuildLogEvent {
+            build_id,
+            timestamp: Utc::now() + Duration::milliseconds(offset_ms),
+            level: BuildLogLevel::Info,
+            message: message.to_string(),
+            done,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backlog_replayed_before_live_events() {
+        let build_id = Uuid::new_v4();
+        let first = event(build_id, 0, "Starting build", false);
+        let second = event(build_id, 10, "Compose hash: abc", false);
+        let live = vec![
+            // Published before the backlog was read
+            second.clone(),
+            event(build_id, 20, "Resources validated", false),
+            event(build_id, 30, "Challenge created", true),
+            event(build_id, 40, "never delivered", false),
+        ];
+
+        let messages: Vec<String> =
+            build_log_events(vec![first, second], stream::iter(live))
+                .map(|event| event.message)
+                .collect()
+                .await;
+
+        assert_eq!(
+            messages,
+            vec![
+                "Starting build",
+                "Compose hash: abc",
+                "Resources validated",
+                "Challenge created"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_finished_build_ends_stream() {
+        let build_id = Uuid::new_v4();
+        let backlog = vec![
+            event(build_id, 0, "Starting build", false),
+            event(build_id, 10, "Build failed", true),
+        ];
+
+        // A pending live stream must not keep the response open
+        let events: Vec<BuildLogEvent> = build_log_events(backlog, stream::pending())
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 2);
+        assert!(events[1].done);
+    }
+}
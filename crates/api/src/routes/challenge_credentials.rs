@@ -6,7 +6,10 @@ use tracing::{error, info};
 use x25519_dalek::PublicKey;
 
 use crate::challenge_migrations::{MigrationOrchestrator, MigrationRequest};
+use crate::middleware::auth::Caller;
+use crate::policy::{authorize_challenge, ChallengeAction};
 use crate::state::AppState;
+use platform_api_orm_gateway::provision_challenge_schema;
 
 #[derive(Debug, Deserialize)]
 pub struct CredentialRequest {
@@ -39,6 +42,7 @@ pub struct EncryptedCredentials {
 /// Handle credential requests from TDX-verified challenges via validators
 pub async fn request_credentials(
     State(state): State<AppState>,
+    caller: Caller,
     Json(request): Json<CredentialRequest>,
 ) -> Result<Json<CredentialResponse>, StatusCode> {
     info!(
@@ -52,6 +56,14 @@ pub async fn request_credentials(
         StatusCode::BAD_REQUEST
     })?;
 
+    authorize_challenge(
+        &state,
+        &caller,
+        challenge_uuid,
+        ChallengeAction::RequestCredentials,
+    )
+    .await?;
+
     // Verify the challenge exists and is authorized
     let _challenge = state
         .storage
@@ -62,10 +74,6 @@ pub async fn request_credentials(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    // Generate schema name from challenge ID
-    let schema_name = format!("challenge_{}", request.challenge_id.replace('-', "_"));
-
-    // Create migration orchestrator
     let db_pool = state
         .database_pool
         .as_ref()
@@ -75,6 +83,16 @@ pub async fn request_credentials(
         })?
         .clone();
 
+    // Provision the challenge schema and role, if not done at registration
+    let schema_name = provision_challenge_schema(&db_pool, challenge_uuid)
+        .await
+        .map_err(|e| {
+            error!("Failed to provision challenge schema: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .schema;
+
+    // Create migration orchestrator
     let orchestrator = MigrationOrchestrator::new((*db_pool).clone());
 
     // Create challenge database schema
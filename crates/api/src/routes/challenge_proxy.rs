@@ -13,9 +13,12 @@ use sp_core::{
     crypto::{Pair, Ss58Codec},
     sr25519,
 };
+use std::time::Duration;
 use tracing::{error, info, warn};
 
+use crate::challenge_runner::ChallengeInstance;
 use crate::metagraph::get_metagraph_cache;
+use crate::middleware::request_id::RequestIdExt;
 use crate::state::AppState;
 
 /// Serialize JSON with sorted keys to match Python's json.dumps(..., sort_keys=True)
@@ -56,6 +59,56 @@ fn sort_json_keys(value: &Value) -> Value {
     }
 }
 
+/// Default of `ChallengeProxyConfig::max_proxy_body_bytes`, 10 MiB
+pub const DEFAULT_MAX_PROXY_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Limits of the requests proxied to challenge CVMs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChallengeProxyConfig {
+    /// Largest response body buffered from a challenge CVM
+    pub max_proxy_body_bytes: usize,
+    /// Longest wait for the connection to a challenge CVM
+    pub connect_timeout: Duration,
+    /// Longest a proxied request may take, reading the response included
+    pub request_timeout: Duration,
+}
+
+impl Default for ChallengeProxyConfig {
+    fn default() -> Self {
+        Self {
+            max_proxy_body_bytes: DEFAULT_MAX_PROXY_BODY_BYTES,
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ChallengeProxyConfig {
+    /// Load the configuration from `CHALLENGE_PROXY_MAX_BODY_BYTES`,
+    /// `CHALLENGE_PROXY_CONNECT_TIMEOUT_SECS` and `CHALLENGE_PROXY_TIMEOUT_SECS`
+    ///
+    /// Unset, invalid or zero values keep the defaults.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .filter(|&value| value > 0)
+        };
+
+        Self {
+            max_proxy_body_bytes: var("CHALLENGE_PROXY_MAX_BODY_BYTES")
+                .and_then(|bytes| usize::try_from(bytes).ok())
+                .unwrap_or(defaults.max_proxy_body_bytes),
+            connect_timeout: var("CHALLENGE_PROXY_CONNECT_TIMEOUT_SECS")
+                .map_or(defaults.connect_timeout, Duration::from_secs),
+            request_timeout: var("CHALLENGE_PROXY_TIMEOUT_SECS")
+                .map_or(defaults.request_timeout, Duration::from_secs),
+        }
+    }
+}
+
 /// Signature verification error
 #[derive(Debug)]
 pub enum SignatureError {
@@ -66,6 +119,10 @@ pub enum SignatureError {
     HotkeyNotInMetagraph,
     ChallengeNotFound,
     CvmUnavailable,
+    /// The challenge CVM answered with a body over `max_proxy_body_bytes`
+    ResponseTooLarge,
+    /// The challenge CVM did not answer within `request_timeout`
+    UpstreamTimeout,
 }
 
 impl IntoResponse for SignatureError {
@@ -96,6 +153,14 @@ impl IntoResponse for SignatureError {
                 StatusCode::BAD_GATEWAY,
                 "Challenge CVM is not available".to_string(),
             ),
+            SignatureError::ResponseTooLarge => (
+                StatusCode::BAD_GATEWAY,
+                "Challenge response is too large".to_string(),
+            ),
+            SignatureError::UpstreamTimeout => (
+                StatusCode::GATEWAY_TIMEOUT,
+                "Challenge CVM did not answer in time".to_string(),
+            ),
         };
 
         let body = serde_json::json!({
@@ -208,6 +273,31 @@ fn is_public_readonly_endpoint(route_name: &str) -> bool {
     )
 }
 
+/// API URL of the CVM of challenge `challenge_name`, by name or ID, among the
+/// `running` challenges
+///
+/// `CvmUnavailable` while the challenge is started but its CVM not active yet.
+fn challenge_cvm_url(
+    running: &[ChallengeInstance],
+    challenge_name: &str,
+) -> Result<String, SignatureError> {
+    let Some(instance) = running
+        .iter()
+        .find(|inst| inst.name == challenge_name || inst.challenge_id == challenge_name)
+    else {
+        warn!(
+            challenge_name = challenge_name,
+            "Challenge not found or not running"
+        );
+        return Err(SignatureError::ChallengeNotFound);
+    };
+
+    instance
+        .cvm_api_url
+        .clone()
+        .ok_or(SignatureError::CvmUnavailable)
+}
+
 /// Proxy GET request to challenge CVM
 async fn proxy_get_to_challenge(
     state: &AppState,
@@ -224,27 +314,7 @@ async fn proxy_get_to_challenge(
 
     // Find challenge by name or ID using public method
     let running_challenges = challenge_runner.list_running_challenges().await;
-
-    // Search for challenge by name or challenge_id
-    let challenge_instance = running_challenges
-        .iter()
-        .find(|inst| inst.name == challenge_name || inst.challenge_id == challenge_name);
-
-    if challenge_instance.is_none() {
-        warn!(
-            challenge_name = challenge_name,
-            "Challenge not found or not running"
-        );
-        return Err(SignatureError::ChallengeNotFound);
-    }
-
-    let instance = challenge_instance.unwrap();
-
-    // Get CVM API URL
-    let cvm_api_url = instance
-        .cvm_api_url
-        .as_ref()
-        .ok_or(SignatureError::CvmUnavailable)?;
+    let cvm_api_url = challenge_cvm_url(&running_challenges, challenge_name)?;
 
     // Build target URL: {cvm_api_url}/sdk/public/{route_name}?{query_params}
     let target_url = if query_params.is_empty() {
@@ -269,18 +339,10 @@ async fn proxy_get_to_challenge(
         "Proxying GET request to challenge CVM"
     );
 
-    // Create HTTP client
-    let client = reqwest::Client::builder()
-        .danger_accept_invalid_certs(true) // Accept self-signed certs from CVMs
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| {
-            error!("Failed to create HTTP client: {}", e);
-            SignatureError::CvmUnavailable
-        })?;
+    let client = upstream_client(&state.config.challenge_proxy)?;
 
     // Forward GET request to challenge CVM
-    let mut request_builder = client.get(&target_url);
+    let mut request_builder = client.get(&target_url).with_request_id();
 
     // Add verified hotkey header if available (for signed requests)
     if let Some(hotkey) = verified_hotkey {
@@ -292,36 +354,7 @@ async fn proxy_get_to_challenge(
         request_builder = request_builder.header("X-CHUTES-API-TOKEN", chutes_token);
     }
 
-    let response = request_builder.send().await.map_err(|e| {
-        error!(
-            target_url = &target_url,
-            error = %e,
-            "Failed to proxy GET request to challenge CVM"
-        );
-        SignatureError::CvmUnavailable
-    })?;
-
-    // Get response status and body
-    let status = response.status();
-    let body = response.text().await.map_err(|e| {
-        error!("Failed to read response body: {}", e);
-        SignatureError::CvmUnavailable
-    })?;
-
-    // Parse JSON body if possible, otherwise return as-is
-    let json_body: Value =
-        serde_json::from_str(&body).unwrap_or_else(|_| serde_json::json!({ "raw_response": body }));
-
-    // Convert to axum response
-    let axum_response = axum::response::Response::builder()
-        .status(status.as_u16())
-        .header("Content-Type", "application/json")
-        .body(Body::from(
-            serde_json::to_string(&json_body).unwrap_or_default(),
-        ))
-        .map_err(|_| SignatureError::CvmUnavailable)?;
-
-    Ok(axum_response)
+    forward(request_builder, &target_url, &state.config.challenge_proxy).await
 }
 
 /// Proxy request to challenge CVM
@@ -340,27 +373,7 @@ async fn proxy_to_challenge(
 
     // Find challenge by name or ID using public method
     let running_challenges = challenge_runner.list_running_challenges().await;
-
-    // Search for challenge by name or challenge_id
-    let challenge_instance = running_challenges
-        .iter()
-        .find(|inst| inst.name == challenge_name || inst.challenge_id == challenge_name);
-
-    if challenge_instance.is_none() {
-        warn!(
-            challenge_name = challenge_name,
-            "Challenge not found or not running"
-        );
-        return Err(SignatureError::ChallengeNotFound);
-    }
-
-    let instance = challenge_instance.unwrap();
-
-    // Get CVM API URL
-    let cvm_api_url = instance
-        .cvm_api_url
-        .as_ref()
-        .ok_or(SignatureError::CvmUnavailable)?;
+    let cvm_api_url = challenge_cvm_url(&running_challenges, challenge_name)?;
 
     // Build target URL: {cvm_api_url}/sdk/public/{route_name}
     let target_url = format!(
@@ -376,20 +389,13 @@ async fn proxy_to_challenge(
         "Proxying request to challenge CVM"
     );
 
-    // Create HTTP client
-    let client = reqwest::Client::builder()
-        .danger_accept_invalid_certs(true) // Accept self-signed certs from CVMs
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| {
-            error!("Failed to create HTTP client: {}", e);
-            SignatureError::CvmUnavailable
-        })?;
+    let client = upstream_client(&state.config.challenge_proxy)?;
 
     // Forward request to challenge CVM with verified hotkey in header
     // Also include CHUTES API token from platform-api if available
     let mut request_builder = client
         .post(&target_url)
+        .with_request_id()
         .header("X-Verified-Miner-Hotkey", verified_hotkey)
         .header("Content-Type", "application/json");
 
@@ -401,25 +407,63 @@ async fn proxy_to_challenge(
         warn!("CHUTES API token not available - LLM validation may fail during agent upload");
     }
 
-    let response = request_builder.json(&body_json).send().await.map_err(|e| {
-        error!(
-            target_url = &target_url,
-            error = %e,
-            "Failed to proxy request to challenge CVM"
-        );
-        SignatureError::CvmUnavailable
-    })?;
+    forward(
+        request_builder.json(&body_json),
+        &target_url,
+        &state.config.challenge_proxy,
+    )
+    .await
+}
 
-    // Get response status and body
-    let status = response.status();
-    let body = response.text().await.map_err(|e| {
-        error!("Failed to read response body: {}", e);
-        SignatureError::CvmUnavailable
-    })?;
+/// HTTP client of the requests proxied to challenge CVMs
+fn upstream_client(config: &ChallengeProxyConfig) -> Result<reqwest::Client, SignatureError> {
+    reqwest::Client::builder()
+        .danger_accept_invalid_certs(true) // Accept self-signed certs from CVMs
+        .connect_timeout(config.connect_timeout)
+        .build()
+        .map_err(|e| {
+            error!("Failed to create HTTP client: {}", e);
+            SignatureError::CvmUnavailable
+        })
+}
+
+/// Send a proxied request to the challenge CVM and relay its response
+///
+/// The request fails with `UpstreamTimeout` unless the response is read
+/// within `request_timeout`, and with `ResponseTooLarge` as soon as its body
+/// exceeds `max_proxy_body_bytes`.
+async fn forward(
+    request: reqwest::RequestBuilder,
+    target_url: &str,
+    config: &ChallengeProxyConfig,
+) -> Result<Response, SignatureError> {
+    let exchange = async {
+        let response = request.send().await.map_err(|e| {
+            error!(
+                target_url = target_url,
+                error = %e,
+                "Failed to proxy request to challenge CVM"
+            );
+            SignatureError::CvmUnavailable
+        })?;
+        let status = response.status();
+        let body = read_body(response, config.max_proxy_body_bytes).await?;
+        Ok((status, body))
+    };
+    let (status, body) = tokio::time::timeout(config.request_timeout, exchange)
+        .await
+        .map_err(|_| {
+            warn!(
+                target_url = target_url,
+                timeout = ?config.request_timeout,
+                "Challenge CVM did not answer proxied request in time"
+            );
+            SignatureError::UpstreamTimeout
+        })??;
 
     // Parse JSON body if possible, otherwise return as-is
-    let json_body: Value =
-        serde_json::from_str(&body).unwrap_or_else(|_| serde_json::json!({ "raw_response": body }));
+    let json_body: Value = serde_json::from_slice(&body)
+        .unwrap_or_else(|_| serde_json::json!({ "raw_response": String::from_utf8_lossy(&body) }));
 
     // Convert to axum response
     let axum_response = axum::response::Response::builder()
@@ -433,6 +477,44 @@ async fn proxy_to_challenge(
     Ok(axum_response)
 }
 
+/// Read the body of `response`, giving up once it exceeds `max_bytes`
+///
+/// The body is read chunk by chunk, so an oversized body is never buffered
+/// whole, whether or not its length is announced.
+async fn read_body(
+    mut response: reqwest::Response,
+    max_bytes: usize,
+) -> Result<Vec<u8>, SignatureError> {
+    let url = response.url().clone();
+    let too_large = |received: u64| {
+        warn!(
+            url = %url,
+            received,
+            max_bytes,
+            "Challenge CVM response body too large, aborting proxied request"
+        );
+        SignatureError::ResponseTooLarge
+    };
+    if let Some(length) = response
+        .content_length()
+        .filter(|&len| len > max_bytes as u64)
+    {
+        return Err(too_large(length));
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| {
+        error!("Failed to read response body: {}", e);
+        SignatureError::CvmUnavailable
+    })? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(too_large((body.len() + chunk.len()) as u64));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
 /// Handle challenge public route GET request
 async fn handle_challenge_public_route_get(
     State(state): State<AppState>,
@@ -506,3 +588,212 @@ pub fn create_router() -> Router<AppState> {
         get(handle_challenge_public_route_get).post(handle_challenge_public_route),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge_runner::AutoStarts;
+    use axum::body::Bytes;
+    use axum::routing::post;
+
+    async fn serve(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    /// Mock challenge CVM answering with bodies of 1 KiB chunks
+    async fn upstream() -> String {
+        let chunks = |count: usize| {
+            futures::stream::iter(
+                (0..count)
+                    .map(|_| Ok::<_, std::convert::Infallible>(Bytes::from(vec![b'a'; 1024]))),
+            )
+        };
+        serve(
+            Router::new()
+                .route("/small", post(|| async { r#"{"ok":true}"# }))
+                .route("/sized", get(|| async { vec![b'a'; 8 * 1024] }))
+                .route(
+                    "/chunked",
+                    get(move || async move { Body::from_stream(chunks(8)) }),
+                )
+                .route(
+                    "/slow",
+                    get(|| async {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        "{}"
+                    }),
+                ),
+        )
+        .await
+    }
+
+    fn config() -> ChallengeProxyConfig {
+        ChallengeProxyConfig {
+            max_proxy_body_bytes: 4 * 1024,
+            connect_timeout: Duration::from_secs(1),
+            request_timeout: Duration::from_millis(500),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward_relays_response() {
+        let url = upstream().await;
+        let config = config();
+        let client = upstream_client(&config).unwrap();
+
+        let target_url = format!("{}/small", url);
+        let response = forward(
+            client.post(&target_url).json(&serde_json::json!({})),
+            &target_url,
+            &config,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            serde_json::json!({ "ok": true })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forward_caps_response_body() {
+        let url = upstream().await;
+        let config = config();
+        let client = upstream_client(&config).unwrap();
+
+        // Announced, and streamed without a length
+        for route in ["sized", "chunked"] {
+            let target_url = format!("{}/{}", url, route);
+            let error = forward(client.get(&target_url), &target_url, &config)
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(error, SignatureError::ResponseTooLarge),
+                "{}",
+                route
+            );
+            assert_eq!(error.into_response().status(), StatusCode::BAD_GATEWAY);
+        }
+
+        // Bodies up to the cap are relayed
+        let config = ChallengeProxyConfig {
+            max_proxy_body_bytes: 8 * 1024,
+            ..config
+        };
+        for route in ["sized", "chunked"] {
+            let target_url = format!("{}/{}", url, route);
+            let response = forward(client.get(&target_url), &target_url, &config)
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", route);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward_deadline() {
+        let url = upstream().await;
+        let config = config();
+        let client = upstream_client(&config).unwrap();
+
+        let target_url = format!("{}/slow", url);
+        let error = forward(client.get(&target_url), &target_url, &config)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SignatureError::UpstreamTimeout));
+        assert_eq!(error.into_response().status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    fn instance(compose_hash: &str) -> ChallengeInstance {
+        ChallengeInstance {
+            challenge_id: "c1".to_string(),
+            name: "term-challenge".to_string(),
+            version: "1.0.0".to_string(),
+            compose_hash: compose_hash.to_string(),
+            cvm_instance_id: None,
+            cvm_api_url: None,
+            schema_name: "challenge_c1".to_string(),
+            db_version: None,
+            is_running: false,
+            ws_started: false,
+        }
+    }
+
+    /// Mock challenge CVM answering its public `get_agent_status` route with
+    /// its `version`
+    async fn challenge_cvm(version: &'static str) -> String {
+        serve(Router::new().route(
+            "/sdk/public/get_agent_status",
+            get(move || async move { axum::Json(serde_json::json!({ "version": version })) }),
+        ))
+        .await
+    }
+
+    /// Version answering `get_agent_status` of `challenge`, proxied as
+    /// `proxy_get_to_challenge` does
+    async fn agent_status(
+        auto_starts: &AutoStarts,
+        challenge: &str,
+    ) -> Result<Value, SignatureError> {
+        let cvm_api_url = challenge_cvm_url(&auto_starts.running().await, challenge)?;
+        let config = config();
+        let target_url = format!("{}/sdk/public/get_agent_status", cvm_api_url);
+        let response = forward(
+            upstream_client(&config)?.get(&target_url),
+            &target_url,
+            &config,
+        )
+        .await?;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        Ok(body["version"].clone())
+    }
+
+    #[tokio::test]
+    async fn test_proxies_to_the_active_challenge_version() {
+        let auto_starts = AutoStarts::default();
+        assert!(matches!(
+            agent_status(&auto_starts, "term-challenge").await,
+            Err(SignatureError::ChallengeNotFound)
+        ));
+
+        // Started, its CVM not active yet
+        auto_starts.start(instance("hash-a")).await;
+        assert!(matches!(
+            agent_status(&auto_starts, "term-challenge").await,
+            Err(SignatureError::CvmUnavailable)
+        ));
+
+        // Active, by name or ID
+        let url = challenge_cvm("v1").await;
+        auto_starts
+            .set_running("c1", "hash-a", "vm-1".to_string(), url)
+            .await;
+        for challenge in ["term-challenge", "c1"] {
+            assert_eq!(agent_status(&auto_starts, challenge).await.unwrap(), "v1");
+        }
+
+        // A new version replaces the old one once its CVM is active
+        auto_starts.start(instance("hash-b")).await;
+        assert!(matches!(
+            agent_status(&auto_starts, "term-challenge").await,
+            Err(SignatureError::CvmUnavailable)
+        ));
+        let url = challenge_cvm("v2").await;
+        auto_starts
+            .set_running("c1", "hash-b", "vm-2".to_string(), url)
+            .await;
+        assert_eq!(
+            agent_status(&auto_starts, "term-challenge").await.unwrap(),
+            "v2"
+        );
+    }
+}
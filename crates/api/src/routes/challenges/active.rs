@@ -1,23 +1,58 @@
 //! Active challenges handlers
 
 use axum::{
-    extract::State,
+    extract::{Query, State},
     http::StatusCode,
     response::Json,
 };
 use crate::state::AppState;
+use serde::Deserialize;
 use serde_json::Value as JsonValue;
-use sqlx::Row;
 
-/// Get active challenges only
+/// Default page size for active challenges
+const DEFAULT_ACTIVE_PER_PAGE: u32 = 100;
+
+/// Maximum page size for active challenges
+const MAX_ACTIVE_PER_PAGE: u32 = 500;
+
+#[derive(Deserialize)]
+pub struct ActiveChallengesParams {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    pub compose_hash: Option<String>,
+}
+
+/// Get active challenges with pagination
+///
+/// Status is derived from the states validators report for each challenge.
 pub async fn get_active_challenges(
     State(state): State<AppState>,
+    Query(params): Query<ActiveChallengesParams>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     let pool = state
         .database_pool
         .as_ref()
         .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
 
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params
+        .per_page
+        .unwrap_or(DEFAULT_ACTIVE_PER_PAGE)
+        .clamp(1, MAX_ACTIVE_PER_PAGE);
+    let offset = (page - 1) as i64 * per_page as i64;
+
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM challenges WHERE status <> 'draft' AND ($1::TEXT IS NULL OR compose_hash = $1)",
+    )
+    .persistent(false)
+    .bind(params.compose_hash.as_deref())
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count active challenges: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
     #[derive(sqlx::FromRow)]
     struct ChallengeRow {
         id: uuid::Uuid,
@@ -33,10 +68,15 @@ pub async fn get_active_challenges(
         r#"
         SELECT id, name, compose_hash, github_repo, mechanism_id, emission_share, resources
         FROM challenges
+        WHERE status <> 'draft' AND ($1::TEXT IS NULL OR compose_hash = $1)
         ORDER BY created_at DESC
+        LIMIT $2 OFFSET $3
         "#,
     )
     .persistent(false)
+    .bind(params.compose_hash.as_deref())
+    .bind(per_page as i64)
+    .bind(offset)
     .fetch_all(pool.as_ref())
     .await
     .map_err(|e| {
@@ -45,22 +85,32 @@ pub async fn get_active_challenges(
     })?;
 
     tracing::info!(
-        "get_active_challenges: returning {} challenges from database",
-        rows.len()
+        "get_active_challenges: returning {} of {} challenges from database (page {})",
+        rows.len(),
+        total,
+        page
     );
 
-    Ok(Json(serde_json::json!({
-        "challenges": rows.iter().map(|row| serde_json::json!({
+    let mut challenges = Vec::with_capacity(rows.len());
+    for row in rows {
+        let status = state.get_challenge_state(&row.compose_hash).await;
+        challenges.push(serde_json::json!({
             "id": row.id.to_string(),
-            "name": row.name.clone(),
-            "status": "Active", // All challenges in database are considered active
-            "github_repo": row.github_repo.clone().unwrap_or_default(),
+            "name": row.name,
+            "status": status,
+            "github_repo": row.github_repo.unwrap_or_default(),
             "github_commit": "", // Not stored in DB, derived from compose_hash
-            "resource_requirements": row.resources.clone(),
-            "compose_hash": row.compose_hash.clone(),
+            "resource_requirements": row.resources,
+            "compose_hash": row.compose_hash,
             "mechanism_id": row.mechanism_id as u8,
             "emission_share": row.emission_share,
-        })).collect::<Vec<_>>()
+        }));
+    }
+
+    Ok(Json(serde_json::json!({
+        "challenges": challenges,
+        "total": total,
+        "page": page,
+        "per_page": per_page,
     })))
 }
-
@@ -0,0 +1,66 @@
+//! Challenge image build handlers
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use crate::error::ApiError;
+use crate::middleware::auth::Caller;
+use crate::policy::{authorize_challenge, ChallengeAction};
+use crate::routes::builds::forward_build_log;
+use crate::state::AppState;
+use platform_api_builder::BuildLog;
+use platform_api_models::{CreateImageBuildRequest, ImageBuild};
+use uuid::Uuid;
+
+/// Queue a build of the challenge's image (owner or admin only)
+///
+/// The build runs in the background; its status is on `GET /builds/:build_id`
+/// and its output can be tailed on `GET /builds/:build_id/logs`. A source that
+/// is not a GitHub repository and ref or an HTTPS artifact URL is refused with 400.
+/// Builds of a challenge owner share the build slots with other owners' in
+/// turns, and can be cancelled until they start with `DELETE /builds/:build_id`.
+pub async fn build_challenge_image(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CreateImageBuildRequest>,
+) -> Result<(StatusCode, Json<ImageBuild>), Response> {
+    let owner = authorize_challenge(&state, &caller, id, ChallengeAction::Build)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let build_id = Uuid::new_v4();
+    let (log, events) = BuildLog::new(build_id);
+    forward_build_log(&state, build_id, events);
+
+    let build = state
+        .builder
+        .image_builds()
+        .enqueue(id, &owner, request.source, log)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok((StatusCode::ACCEPTED, Json(build)))
+}
+
+/// List the image builds of a challenge, most recent first (owner or admin only)
+pub async fn list_challenge_builds(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<ImageBuild>>, Response> {
+    authorize_challenge(&state, &caller, id, ChallengeAction::ViewBuilds)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let builds = state
+        .builder
+        .image_builds()
+        .list(id)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(builds))
+}
@@ -3,52 +3,321 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
 };
+use crate::error::ApiError;
+use crate::middleware::auth::Caller;
+use crate::policy::{authorize_challenge, ChallengeAction};
+use crate::routes::builds::forward_build_log;
 use crate::state::AppState;
+use sqlx::PgPool;
+use tracing::Instrument;
 use uuid::Uuid;
-use platform_api_models::{ChallengeMetadata, CreateChallengeRequest, UpdateChallengeRequest};
+use platform_api_builder::{validate_healthcheck, BuildLog, BuilderService};
+use platform_api_orm_gateway::provision_challenge_schema;
+use platform_api_scheduler::{
+    check_payload_schema, validate_job_defaults, validate_score_bounds, validate_score_policy,
+};
+use platform_api_models::{
+    ChallengeMetadata, ComposeHashDrift, CreateChallengeRequest, CreateChallengeResponse,
+    ImportChallengeRequest, ImportChallengeResponse, UpdateChallengeRequest,
+};
 
-/// Create new challenge
+/// Create new challenge owned by the caller
+///
+/// The challenge is built before the response, answered with 200 and the
+/// challenge, then given its own database schema. The build log is still
+/// published, under a build id of its own; clients that want to tail it use
+/// `POST /v2/challenges`, see [`start_challenge_creation`].
+/// Names are unique, ignoring case: a name already taken by another challenge
+/// is refused with 409, and so is the name a challenge was renamed from. A compose file failing validation is refused with 400 and a
+/// field error per finding, and resource requirements above the configured
+/// maximums or an invalid health check with 400. A challenge with a
+/// `healthcheck_url` is checked once built, the result appearing in the build log.
 pub async fn create_challenge(
     State(state): State<AppState>,
+    caller: Caller,
     Json(request): Json<CreateChallengeRequest>,
-) -> Result<Json<ChallengeMetadata>, StatusCode> {
+) -> Result<Json<ChallengeMetadata>, Response> {
+    let build_id = Uuid::new_v4();
+    check_new_challenge(&state, &request).await?;
+    let (log, events) = BuildLog::new(build_id);
+    forward_build_log(&state, build_id, events);
+
     let challenge = state
         .builder
-        .create_challenge(request)
+        .create_challenge_with_log(request, &caller.owner, &log)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(ApiError::from)?;
+    provision_schema(state.database_pool.as_deref(), challenge.id).await;
 
     Ok(Json(challenge))
 }
 
-/// Update challenge
+/// Start creating a challenge owned by the caller, `POST /v2/challenges`
+///
+/// Checked like [`create_challenge`], but answered with 202 as soon as the
+/// challenge is accepted: it is built in the background, and the returned
+/// `build_id` can be tailed on `GET /builds/:build_id/logs`.
+pub async fn start_challenge_creation(
+    State(state): State<AppState>,
+    caller: Caller,
+    Json(request): Json<CreateChallengeRequest>,
+) -> Result<(StatusCode, Json<CreateChallengeResponse>), Response> {
+    let build_id = Uuid::new_v4();
+    let challenge_id = BuilderService::challenge_id(&request);
+    check_new_challenge(&state, &request).await?;
+    let (log, events) = BuildLog::new(build_id);
+    forward_build_log(&state, build_id, events);
+
+    let builder = state.builder.clone();
+    let pool = state.database_pool.clone();
+    tokio::spawn(
+        async move {
+            match builder
+                .create_challenge_with_log(request, &caller.owner, &log)
+                .await
+            {
+                Ok(challenge) => provision_schema(pool.as_deref(), challenge.id).await,
+                Err(e) => tracing::error!("Build {} failed: {}", build_id, e),
+            }
+        }
+        .in_current_span(),
+    );
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(CreateChallengeResponse {
+            build_id,
+            challenge_id,
+        }),
+    ))
+}
+
+/// Checks a challenge must pass before it is built
+async fn check_new_challenge(
+    state: &AppState,
+    request: &CreateChallengeRequest,
+) -> Result<(), Response> {
+    let challenge_id = BuilderService::challenge_id(request);
+    state
+        .builder
+        .ensure_creatable(&request.name, challenge_id)
+        .await
+        .map_err(ApiError::from)?;
+    if let Some(resources) = &request.resources {
+        state
+            .builder
+            .check_resources(resources)
+            .map_err(ApiError::from)?;
+    }
+    validate_healthcheck(
+        request.healthcheck_url.as_deref(),
+        request.healthcheck_timeout_secs,
+    )
+    .map_err(ApiError::from)?;
+    state
+        .builder
+        .check_compose(request)
+        .map_err(ApiError::from)?;
+    Ok(())
+}
+
+/// Give a new challenge its own schema for ORM queries
+async fn provision_schema(pool: Option<&PgPool>, challenge_id: Uuid) {
+    if let Some(pool) = pool {
+        if let Err(e) = provision_challenge_schema(pool, challenge_id).await {
+            tracing::error!(
+                "Failed to provision schema of challenge {}: {}",
+                challenge_id,
+                e
+            );
+        }
+    }
+}
+
+/// Import a challenge from the manifest of a GitHub repository
+///
+/// The manifest, `challenge.toml` or `challenge.yaml` in `path`, and the
+/// files it names are read at the commit `ref` points to, see
+/// `platform_api_builder::github_import`. A manifest without an `id` creates
+/// a challenge owned by the caller, answered with 201; one with the `id` of
+/// an existing challenge updates it (owner or admin only), answered with 200.
+/// Challenges are validated like those created and updated through the API.
+/// Invalid manifests are refused with 400 and failed GitHub requests, rate
+/// limits included, with 502, both detailing the failure in the error body.
+pub async fn import_challenge(
+    State(state): State<AppState>,
+    caller: Caller,
+    Json(request): Json<ImportChallengeRequest>,
+) -> Result<(StatusCode, Json<ImportChallengeResponse>), Response> {
+    let source = state
+        .builder
+        .fetch_challenge_source(&request)
+        .await
+        .map_err(ApiError::from)?;
+    let id = source.challenge_id();
+    if source.manifest.id.is_some() {
+        authorize_challenge(&state, &caller, id, ChallengeAction::Update)
+            .await
+            .map_err(IntoResponse::into_response)?;
+    }
+    if let Some(schema) = &source.job_payload_schema {
+        if let Err(e) = check_payload_schema(schema) {
+            tracing::warn!("Rejected job payload schema of challenge {}: {}", id, e);
+            return Err(StatusCode::BAD_REQUEST.into_response());
+        }
+    }
+    if let Some(defaults) = &source.manifest.job_defaults {
+        if let Err(e) = validate_job_defaults(defaults) {
+            tracing::warn!("Rejected job defaults of challenge {}: {}", id, e);
+            return Err(StatusCode::BAD_REQUEST.into_response());
+        }
+    }
+    if let Some(bounds) = &source.manifest.score_bounds {
+        if let Err(e) = validate_score_bounds(bounds) {
+            tracing::warn!("Rejected score bounds of challenge {}: {}", id, e);
+            return Err(StatusCode::BAD_REQUEST.into_response());
+        }
+    }
+    if let Some(policy) = &source.manifest.score_policy {
+        if let Err(e) = validate_score_policy(policy) {
+            tracing::warn!("Rejected score policy of challenge {}: {}", id, e);
+            return Err(StatusCode::BAD_REQUEST.into_response());
+        }
+    }
+
+    let imported = state
+        .builder
+        .import_challenge(source, &caller.owner)
+        .await
+        .map_err(ApiError::from)?;
+
+    if !imported.created {
+        return Ok((StatusCode::OK, Json(imported)));
+    }
+    provision_schema(state.database_pool.as_deref(), id).await;
+    Ok((StatusCode::CREATED, Json(imported)))
+}
+
+/// Update challenge (owner or admin only)
+///
+/// A `job_payload_schema` must be a JSON Schema the scheduler supports, see
+/// `platform_api_scheduler::check_payload_schema`, `job_defaults` must be
+/// valid job parameters, `score_bounds` and the ranges of `score_policy`
+/// finite with `min` not above `max` and `resources` within the configured
+/// maximums. A
+/// harness config whose environment lacks variables the compose file needs
+/// is refused with 400 and a field error per finding.
 pub async fn update_challenge(
     State(state): State<AppState>,
+    caller: Caller,
     Path(id): Path<Uuid>,
     Json(request): Json<UpdateChallengeRequest>,
-) -> Result<Json<ChallengeMetadata>, StatusCode> {
-    let challenge = state
+) -> Result<Json<ChallengeMetadata>, Response> {
+    let owner = authorize_challenge(&state, &caller, id, ChallengeAction::Update)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    if let Some(schema) = &request.job_payload_schema {
+        if let Err(e) = check_payload_schema(schema) {
+            tracing::warn!("Rejected job payload schema of challenge {}: {}", id, e);
+            return Err(StatusCode::BAD_REQUEST.into_response());
+        }
+    }
+    if let Some(defaults) = &request.job_defaults {
+        if let Err(e) = validate_job_defaults(defaults) {
+            tracing::warn!("Rejected job defaults of challenge {}: {}", id, e);
+            return Err(StatusCode::BAD_REQUEST.into_response());
+        }
+    }
+    if let Some(bounds) = &request.score_bounds {
+        if let Err(e) = validate_score_bounds(bounds) {
+            tracing::warn!("Rejected score bounds of challenge {}: {}", id, e);
+            return Err(StatusCode::BAD_REQUEST.into_response());
+        }
+    }
+    if let Some(policy) = &request.score_policy {
+        if let Err(e) = validate_score_policy(policy) {
+            tracing::warn!("Rejected score policy of challenge {}: {}", id, e);
+            return Err(StatusCode::BAD_REQUEST.into_response());
+        }
+    }
+
+    let mut challenge = state
         .builder
         .update_challenge(id, request)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(ApiError::from)?;
+    challenge.owner = owner;
 
     Ok(Json(challenge))
 }
 
-/// Delete challenge
+/// Clone a challenge into a new draft owned by the caller (owner of the source or admin only)
+///
+/// The request must name and describe the clone; its other fields override
+/// the source's only when set.
+pub async fn clone_challenge(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CreateChallengeRequest>,
+) -> Result<(StatusCode, Json<ChallengeMetadata>), Response> {
+    authorize_challenge(&state, &caller, id, ChallengeAction::Clone)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let challenge = state
+        .builder
+        .clone_challenge_as(id, request, &caller.owner)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok((StatusCode::CREATED, Json(challenge)))
+}
+
+/// Delete challenge (owner or admin only)
 pub async fn delete_challenge(
     State(state): State<AppState>,
+    caller: Caller,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, Response> {
+    authorize_challenge(&state, &caller, id, ChallengeAction::Delete)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
     state
         .builder
         .delete_challenge(id)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(ApiError::from)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Recompute the compose hash of a challenge and report whether the stored
+/// one drifted from it (admin only)
+///
+/// A drifted hash is replaced with the recomputed one.
+pub async fn recompute_compose_hash(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ComposeHashDrift>, Response> {
+    if !caller.admin {
+        tracing::warn!(
+            caller = %caller.owner,
+            challenge_id = %id,
+            "Denied compose hash recomputation to non-admin"
+        );
+        return Err(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let drift = state
+        .builder
+        .recompute_compose_hash(id)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(drift))
+}
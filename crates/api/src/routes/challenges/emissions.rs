@@ -1,18 +1,37 @@
 //! Challenge emissions handlers
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
 };
+use crate::middleware::auth::Caller;
+use crate::policy::{authorize_challenge, ChallengeAction};
 use crate::state::AppState;
+use platform_api_models::EmissionAllocation;
+use serde::Deserialize;
 use uuid::Uuid;
 
-/// Get challenge emissions
+/// Epochs projected unless `epochs` is given
+const DEFAULT_PROJECTED_EPOCHS: u32 = 24;
+
+/// Most epochs a projection may cover
+const MAX_PROJECTED_EPOCHS: u32 = 1000;
+
+/// Query parameters for emission projections
+#[derive(Debug, Deserialize)]
+pub struct EmissionProjectionParams {
+    pub epochs: Option<u32>,
+}
+
+/// Get challenge emissions (owner or admin only)
 pub async fn get_challenge_emissions(
     State(state): State<AppState>,
+    caller: Caller,
     Path(id): Path<Uuid>,
 ) -> Result<Json<platform_api_models::EmissionsSchedule>, StatusCode> {
+    authorize_challenge(&state, &caller, id, ChallengeAction::ViewEmissions).await?;
+
     let emissions = state
         .storage
         .get_challenge_emissions(id)
@@ -22,3 +41,26 @@ pub async fn get_challenge_emissions(
     Ok(Json(emissions))
 }
 
+
+/// Project the allocations of the next epochs of a challenge's emissions
+/// (owner or admin only)
+pub async fn get_challenge_emissions_projection(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<Uuid>,
+    Query(params): Query<EmissionProjectionParams>,
+) -> Result<Json<Vec<EmissionAllocation>>, StatusCode> {
+    let epochs = params.epochs.unwrap_or(DEFAULT_PROJECTED_EPOCHS);
+    if epochs == 0 || epochs > MAX_PROJECTED_EPOCHS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    authorize_challenge(&state, &caller, id, ChallengeAction::ViewEmissions).await?;
+
+    let emissions = state
+        .storage
+        .get_challenge_emissions(id)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(emissions.project(epochs)))
+}
@@ -1,91 +1,61 @@
 //! Get challenge handlers
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
 };
 use crate::state::AppState;
+use serde::Deserialize;
 use uuid::Uuid;
-use platform_api_models::{
-    ChallengeDetailResponse, ChallengeMetadata, ChallengeStatus, ChallengeVisibility, Hotkey, Id,
-};
+use platform_api_models::{ChallengeDetailResponse, ChallengeLiveStats};
+
+/// Query parameters for challenge details
+#[derive(Debug, Default, Deserialize)]
+pub struct ChallengeDetailParams {
+    /// Include live job counts and the latest compose hash
+    #[serde(default)]
+    pub include_stats: bool,
+}
 
 /// Get challenge details
 pub async fn get_challenge(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
+    Query(params): Query<ChallengeDetailParams>,
 ) -> Result<Json<ChallengeDetailResponse>, StatusCode> {
-    let pool = state
-        .database_pool
-        .as_ref()
-        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
-
-    #[derive(sqlx::FromRow)]
-    struct ChallengeRow {
-        id: uuid::Uuid,
-        name: String,
-        compose_hash: String,
-        compose_yaml: String,
-        version: String,
-        images: Vec<String>,
-        resources: serde_json::Value,
-        ports: serde_json::Value,
-        env: serde_json::Value,
-        emission_share: f64,
-        mechanism_id: i16,
-        weight: Option<f64>,
-        description: Option<String>,
-        mermaid_chart: Option<String>,
-        github_repo: Option<String>,
-        dstack_image: Option<String>,
-        created_at: chrono::DateTime<chrono::Utc>,
-        updated_at: chrono::DateTime<chrono::Utc>,
-    }
-
-    let row = sqlx::query_as::<_, ChallengeRow>(
-        r#"
-        SELECT 
-            id, name, compose_hash, compose_yaml, version, images,
-            resources, ports, env, emission_share, mechanism_id, weight,
-            description, mermaid_chart, github_repo, dstack_image,
-            created_at, updated_at
-        FROM challenges
-        WHERE id = $1
-        "#,
-    )
-    .persistent(false)
-    .bind(id)
-    .fetch_optional(pool.as_ref())
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to query challenge: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    if let Some(row) = row {
-        let metadata = ChallengeMetadata {
-            id: Id::from(row.id),
-            name: row.name,
-            description: row.description.unwrap_or_default(),
-            version: row.version,
-            visibility: ChallengeVisibility::Public,
-            status: ChallengeStatus::Active,
-            owner: Hotkey::from("platform"),
-            created_at: row.created_at,
-            updated_at: row.updated_at,
-            tags: vec![],
-        };
-
-        let response = ChallengeDetailResponse {
-            metadata,
-            emissions: None,
-        };
-
-        Ok(Json(response))
+    let challenge = state
+        .storage
+        .get_challenge(id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to query challenge: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let stats = if params.include_stats {
+        let jobs = state
+            .scheduler
+            .get_challenge_job_counts(id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to count jobs for challenge {}: {}", id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        Some(ChallengeLiveStats {
+            jobs,
+            latest_compose_hash: challenge.compose_hash,
+        })
     } else {
-        Err(StatusCode::NOT_FOUND)
-    }
+        None
+    };
+
+    Ok(Json(ChallengeDetailResponse {
+        metadata: challenge.metadata,
+        emissions: None,
+        stats,
+    }))
 }
 
 use super::list::{PublicChallengeResponse, ChallengeStats};
@@ -0,0 +1,36 @@
+//! Challenge health check handlers
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Json, Response},
+};
+use crate::error::ApiError;
+use crate::middleware::auth::Caller;
+use crate::policy::{authorize_challenge, ChallengeAction};
+use crate::state::AppState;
+use platform_api_models::HealthCheckResult;
+use uuid::Uuid;
+
+/// Check that the challenge's container responds (owner or admin only)
+///
+/// Sends a GET request to the challenge's `healthcheck_url`, see
+/// `platform_api_builder::health_check`. An unhealthy challenge is answered
+/// with 200 and `healthy: false`; a challenge without a health check URL is
+/// refused with 409.
+pub async fn check_challenge_health(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<Uuid>,
+) -> Result<Json<HealthCheckResult>, Response> {
+    authorize_challenge(&state, &caller, id, ChallengeAction::CheckHealth)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let result = state
+        .builder
+        .check_challenge_health(id)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(result))
+}
@@ -31,7 +31,13 @@ pub async fn get_challenge_jobs(
 
     let jobs = state
         .scheduler
-        .list_jobs(page, per_page, params.status, Some(id))
+        .list_jobs(page, per_page, params.status, Some(id), &Default::default())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let usage = state
+        .scheduler
+        .challenge_job_usage(id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -54,6 +60,7 @@ pub async fn get_challenge_jobs(
         "total": jobs.total,
         "page": jobs.page,
         "per_page": jobs.per_page,
+        "usage": usage,
     })))
 }
 
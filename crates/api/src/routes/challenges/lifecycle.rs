@@ -0,0 +1,36 @@
+//! Challenge lifecycle handlers
+
+use crate::middleware::auth::Caller;
+use crate::policy::{authorize_challenge, ChallengeAction};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use platform_api_challenge_runner::lifecycle::ChallengeLifecycle;
+use uuid::Uuid;
+
+/// Get where an auto-started challenge is in its lifecycle (owner or admin
+/// only, as it exposes the CVM and the errors of failed steps)
+///
+/// Challenges the runner never started, and all challenges when the runner is
+/// disabled, are answered with 404.
+pub async fn get_challenge_state(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ChallengeLifecycle>, StatusCode> {
+    authorize_challenge(&state, &caller, id, ChallengeAction::ViewState).await?;
+
+    let runner = state
+        .challenge_runner
+        .as_ref()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    runner
+        .challenge_state(id)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
@@ -5,120 +5,46 @@ use axum::{
     http::StatusCode,
     response::Json,
 };
+use crate::middleware::auth::Caller;
+use crate::policy::owner_filter;
 use crate::state::AppState;
 use serde::Deserialize;
-use serde_json::Value as JsonValue;
 use sqlx::Row;
-use platform_api_models::{ChallengeListResponse, ChallengeMetadata, ChallengeStatus, ChallengeVisibility, Hotkey, Id};
+use platform_api_models::ChallengeListResponse;
 
 #[derive(Deserialize)]
 pub struct ListChallengesParams {
     pub page: Option<u32>,
     pub per_page: Option<u32>,
+    /// Only list challenges owned by the caller
+    #[serde(default)]
+    pub mine: bool,
 }
 
 /// List challenges with pagination
 pub async fn list_challenges(
     State(state): State<AppState>,
+    caller: Option<Caller>,
     Query(params): Query<ListChallengesParams>,
 ) -> Result<Json<ChallengeListResponse>, StatusCode> {
     tracing::debug!("Starting challenge list query");
 
-    let pool = state.database_pool.as_ref().ok_or_else(|| {
-        tracing::error!("Database pool not available");
-        StatusCode::SERVICE_UNAVAILABLE
-    })?;
+    let owner = owner_filter(params.mine, caller.as_ref())?;
 
-    let page = params.page.unwrap_or(1);
-    let per_page = params.per_page.unwrap_or(20);
-    let offset = (page - 1) * per_page;
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(20).clamp(1, 100);
 
-    tracing::debug!(
-        "Query parameters: page={}, per_page={}, offset={}",
-        page, per_page, offset
-    );
+    tracing::debug!("Query parameters: page={}, per_page={}", page, per_page);
 
-    // First, get total count
-    tracing::debug!("Executing COUNT query");
-    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM challenges")
-        .persistent(false)
-        .fetch_one(pool.as_ref())
+    let response = state
+        .storage
+        .list_challenges(page, per_page, None, None, owner)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to count challenges: {}", e);
-            tracing::error!("   Error details: {:?}", e);
+            tracing::error!("Failed to list challenges: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
-
-    tracing::debug!("Total challenges in database: {}", total);
-
-    // Query challenges from database with pagination
-    #[derive(sqlx::FromRow)]
-    struct ChallengeRow {
-        id: uuid::Uuid,
-        name: String,
-        compose_hash: String,
-        compose_yaml: String,
-        version: String,
-        images: Vec<String>,
-        resources: JsonValue,
-        ports: JsonValue,
-        env: JsonValue,
-        emission_share: f64,
-        mechanism_id: i16,
-        weight: Option<f64>,
-        description: Option<String>,
-        mermaid_chart: Option<String>,
-        github_repo: Option<String>,
-        dstack_image: Option<String>,
-        created_at: chrono::DateTime<chrono::Utc>,
-        updated_at: chrono::DateTime<chrono::Utc>,
-    }
-
-    let rows = sqlx::query_as::<_, ChallengeRow>(
-        r#"
-        SELECT 
-            id, name, compose_hash, compose_yaml, version, images,
-            resources, ports, env, emission_share, mechanism_id, weight,
-            description, mermaid_chart, github_repo, dstack_image,
-            created_at, updated_at
-        FROM challenges
-        ORDER BY created_at DESC
-        LIMIT $1 OFFSET $2
-        "#,
-    )
-    .persistent(false)
-    .bind(per_page as i64)
-    .bind(offset as i64)
-    .fetch_all(pool.as_ref())
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to query challenges: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let challenges: Vec<ChallengeMetadata> = rows
-        .into_iter()
-        .map(|row| ChallengeMetadata {
-            id: Id::from(row.id),
-            name: row.name,
-            description: row.description.unwrap_or_default(),
-            version: row.version,
-            visibility: ChallengeVisibility::Public,
-            status: ChallengeStatus::Active,
-            owner: Hotkey::from("platform"),
-            created_at: row.created_at,
-            updated_at: row.updated_at,
-            tags: vec![],
-        })
-        .collect();
-
-    let response = ChallengeListResponse {
-        challenges,
-        total: total as u64,
-        page,
-        per_page,
-    };
+    let total = response.total;
 
     tracing::debug!(
         "Returning {} challenges (page {}, per_page {}, total {})",
@@ -197,7 +123,7 @@ pub async fn list_challenges_public(
             c.created_at, c.updated_at
         FROM challenges c
         INNER JOIN jobs j ON j.challenge_id = c.id
-        WHERE j.created_at > NOW() - INTERVAL '30 days'
+        WHERE j.created_at > NOW() - INTERVAL '30 days' AND c.status <> 'draft'
         ORDER BY c.created_at DESC
         "#,
     )
@@ -298,3 +224,76 @@ pub async fn list_challenges_public(
     }))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platform_api_models::{
+        ChallengeMetadata, ChallengeStatus, ChallengeVisibility, Id, JobPriority,
+        DEFAULT_HEALTHCHECK_TIMEOUT_SECS,
+    };
+    use platform_api_storage::{MemoryStorageBackend, StorageConfig, StoredChallenge};
+    use std::sync::Arc;
+
+    fn challenge(name: &str, created_at: chrono::DateTime<chrono::Utc>) -> StoredChallenge {
+        StoredChallenge {
+            metadata: ChallengeMetadata {
+                id: Id::from(uuid::Uuid::new_v4()),
+                name: name.to_string(),
+                description: String::new(),
+                version: "1.0.0".to_string(),
+                visibility: ChallengeVisibility::Public,
+                status: ChallengeStatus::Active,
+                owner: "alice".to_string(),
+                created_at,
+                updated_at: created_at,
+                tags: vec![],
+                default_job_priority: JobPriority::Normal,
+                job_payload_schema: None,
+                job_defaults: Default::default(),
+                resources: Default::default(),
+                score_bounds: Default::default(),
+                score_policy: Default::default(),
+                healthcheck_url: None,
+                healthcheck_timeout_secs: DEFAULT_HEALTHCHECK_TIMEOUT_SECS,
+            },
+            compose_hash: format!("{}-hash", name),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_challenges_pages_through_storage() {
+        let storage = MemoryStorageBackend::new(&StorageConfig::default()).unwrap();
+        let now = chrono::Utc::now();
+        for (i, name) in ["oldest", "older", "newest"].into_iter().enumerate() {
+            let created_at = now - chrono::Duration::hours(3 - i as i64);
+            storage.insert_challenge(challenge(name, created_at)).await;
+        }
+        let state = AppState::for_tests(Arc::new(storage)).unwrap();
+        let list = |page, per_page| {
+            list_challenges(
+                State(state.clone()),
+                None,
+                Query(ListChallengesParams {
+                    page,
+                    per_page,
+                    mine: false,
+                }),
+            )
+        };
+
+        let Json(first) = list(None, Some(2)).await.unwrap();
+        assert_eq!(first.total, 3);
+        let names: Vec<_> = first.challenges.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["newest", "older"]);
+        let Json(second) = list(Some(2), Some(2)).await.unwrap();
+        assert_eq!(second.challenges[0].name, "oldest");
+
+        // Out of range parameters are clamped rather than overflowing
+        let Json(past) = list(Some(u32::MAX), Some(u32::MAX)).await.unwrap();
+        assert!(past.challenges.is_empty());
+        assert_eq!(past.per_page, 100);
+        let Json(zero) = list(Some(0), Some(0)).await.unwrap();
+        assert_eq!((zero.page, zero.per_page), (1, 1));
+        assert_eq!(zero.challenges[0].name, "newest");
+    }
+}
@@ -9,6 +9,9 @@ pub mod specs;
 pub mod debug;
 pub mod jobs;
 pub mod env_vars;
+pub mod builds;
+pub mod health;
+pub mod lifecycle;
 
 use axum::{routing::{get, post}, Router};
 use crate::state::AppState;
@@ -17,6 +20,8 @@ use crate::state::AppState;
 pub fn create_router() -> Router<AppState> {
     Router::new()
         .route("/challenges", get(list::list_challenges).post(crud::create_challenge))
+        .route("/v2/challenges", post(crud::start_challenge_creation))
+        .route("/challenges/import", post(crud::import_challenge))
         .route("/challenges/active", get(active::get_active_challenges))
         .route("/challenges/specs", get(specs::get_challenge_specs))
         .route("/challenges/public", get(list::list_challenges_public))
@@ -27,8 +32,21 @@ pub fn create_router() -> Router<AppState> {
                 .put(crud::update_challenge)
                 .delete(crud::delete_challenge),
         )
+        .route("/challenges/:id/clone", post(crud::clone_challenge))
+        .route(
+            "/challenges/:id/recompute-hash",
+            post(crud::recompute_compose_hash),
+        )
+        .route("/challenges/:id/build", post(builds::build_challenge_image))
+        .route("/challenges/:id/builds", get(builds::list_challenge_builds))
+        .route("/challenges/:id/health", get(health::check_challenge_health))
+        .route("/challenges/:id/state", get(lifecycle::get_challenge_state))
         .route("/challenges/:id/public", get(get::get_challenge_public))
         .route("/challenges/:id/emissions", get(emissions::get_challenge_emissions))
+        .route(
+            "/challenges/:id/emissions/projection",
+            get(emissions::get_challenge_emissions_projection),
+        )
         .route("/challenges/:id/jobs", get(jobs::get_challenge_jobs))
         .route(
             "/challenges/:compose_hash/env-vars",
@@ -1,18 +1,24 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
     routing::{get, post},
     Router,
 };
+use serde_json::Value;
 use std::env;
 use tracing::warn;
 use uuid::Uuid;
 
+use crate::middleware::auth::Caller;
+use crate::services::settings::{
+    SettingChange, SettingDefinition, SettingValue, SettingsError, SETTING_DEFINITIONS,
+};
 use crate::state::AppState;
 use platform_api_models::{
-    ConfigBackup, ConfigValidationResult, RestoreConfigRequest, TSubnetConfig, UpdateConfigRequest,
-    VmComposeResponse, VmHardwareSpec, VmManifestDefaults, VmProvisioningBundle,
+    provisioning_env_keys, ConfigBackup, ConfigValidationResult, RestoreConfigRequest,
+    TSubnetConfig, UpdateConfigRequest, VmComposeResponse, VmHardwareSpec, VmManifestDefaults,
+    VmProvisioningBundle,
 };
 
 /// Create config router
@@ -29,6 +35,96 @@ pub fn create_router() -> Router<AppState> {
             "/config/compose/validator_vm",
             get(get_validator_vm_compose),
         )
+        .route("/config/settings", get(get_settings).put(update_setting))
+        .route("/config/settings/history", get(get_settings_history))
+}
+
+/// Reject callers without the admin role from the runtime settings routes
+fn require_admin(caller: &Caller) -> Result<(), (StatusCode, Json<Value>)> {
+    if caller.admin {
+        return Ok(());
+    }
+    warn!(caller = %caller.owner, "Denied runtime settings access to non-admin");
+    Err((
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({ "error": "Admin role required" })),
+    ))
+}
+
+/// Tunable settings and their current overrides
+#[derive(Debug, serde::Serialize)]
+pub struct SettingsResponse {
+    pub definitions: &'static [SettingDefinition],
+    pub values: Vec<SettingValue>,
+}
+
+/// Request to change a runtime setting
+#[derive(Debug, serde::Deserialize)]
+pub struct UpdateSettingRequest {
+    pub key: String,
+    pub value: Value,
+}
+
+/// Query parameters for the settings audit trail
+#[derive(Debug, serde::Deserialize)]
+pub struct SettingsHistoryParams {
+    pub key: String,
+    pub limit: Option<i64>,
+}
+
+fn settings_error(err: SettingsError) -> (StatusCode, Json<Value>) {
+    let status = match &err {
+        SettingsError::UnknownSetting(_) => StatusCode::NOT_FOUND,
+        SettingsError::InvalidValue { .. } => StatusCode::BAD_REQUEST,
+        SettingsError::Storage(e) => {
+            warn!("Settings storage error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(serde_json::json!({ "error": err.to_string() })))
+}
+
+/// List tunable settings with their current overrides
+pub async fn get_settings(
+    State(state): State<AppState>,
+    caller: Caller,
+) -> Result<Json<SettingsResponse>, (StatusCode, Json<Value>)> {
+    require_admin(&caller)?;
+    Ok(Json(SettingsResponse {
+        definitions: SETTING_DEFINITIONS,
+        values: state.settings.list().await,
+    }))
+}
+
+/// Change a runtime setting
+pub async fn update_setting(
+    State(state): State<AppState>,
+    caller: Caller,
+    Json(request): Json<UpdateSettingRequest>,
+) -> Result<Json<SettingValue>, (StatusCode, Json<Value>)> {
+    require_admin(&caller)?;
+    state
+        .settings
+        .set(&request.key, request.value, &caller.owner)
+        .await
+        .map(Json)
+        .map_err(settings_error)
+}
+
+/// Get the audit trail of a runtime setting
+pub async fn get_settings_history(
+    State(state): State<AppState>,
+    caller: Caller,
+    Query(params): Query<SettingsHistoryParams>,
+) -> Result<Json<Vec<SettingChange>>, (StatusCode, Json<Value>)> {
+    require_admin(&caller)?;
+    let limit = params.limit.unwrap_or(50).clamp(1, 500);
+    state
+        .settings
+        .history(&params.key, limit)
+        .await
+        .map(Json)
+        .map_err(settings_error)
 }
 
 /// Get subnet configuration
@@ -217,7 +313,6 @@ pub async fn get_validator_vm_compose(
     }))
 }
 
-const DEFAULT_ENV_KEYS: &[&str] = &["DSTACK_VMM_URL", "HOTKEY_PASSPHRASE", "VALIDATOR_BASE_URL"];
 const DEFAULT_VM_IMAGE: &str = "dstack-0.5.2";
 const DEFAULT_VM_VCPU: u32 = 16;
 const DEFAULT_VM_MEMORY_MB: u32 = 16 * 1024;
@@ -230,18 +325,9 @@ const ENV_VALIDATOR_VM_DISK_GB: &str = "VALIDATOR_VM_DISK_GB";
 fn build_validator_provisioning_bundle(
     config: &platform_api_models::VmComposeConfig,
 ) -> VmProvisioningBundle {
-    let mut env_keys: Vec<String> = DEFAULT_ENV_KEYS.iter().map(|k| k.to_string()).collect();
-    for key in &config.required_env {
-        if !env_keys.iter().any(|existing| existing == key) {
-            env_keys.push(key.clone());
-        }
-    }
-    // Sort and deduplicate env_keys for consistent hash calculation
-    env_keys.sort();
-    env_keys.dedup();
-
     VmProvisioningBundle {
-        env_keys,
+        // The variables the attestation expects the manifest to allow
+        env_keys: provisioning_env_keys(&config.required_env),
         manifest_defaults: VmManifestDefaults {
             manifest_version: 2,
             name: Some(config.vm_type.to_string()),
@@ -1,6 +1,8 @@
 use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
+use platform_api_storage::CircuitState;
 use serde_json::Value;
 
+use crate::services::VerifierMode;
 use crate::state::AppState;
 
 /// Create health router
@@ -9,6 +11,7 @@ pub fn create_router() -> Router<AppState> {
         .route("/", get(version_info))
         .route("/health", get(health_check))
         .route("/health/ready", get(readiness_check))
+        .route("/health/readiness", get(readiness_check))
         .route("/health/live", get(liveness_check))
         .route("/metrics", get(metrics))
         .route("/version", get(version_info))
@@ -57,10 +60,31 @@ pub async fn liveness_check() -> Result<Json<Value>, StatusCode> {
 
 /// Metrics endpoint
 pub async fn metrics(State(state): State<AppState>) -> Result<String, StatusCode> {
-    state
+    let mut metrics = state
         .metrics
         .get_metrics()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Prepared statement reuse of each ORM gateway
+    let gateways = [
+        ("read_write", &state.orm_gateway),
+        ("read_only", &state.orm_gateway_readonly),
+    ];
+    for (gateway, orm_gateway) in gateways {
+        let Some(orm_gateway) = orm_gateway else {
+            continue;
+        };
+        let stats = orm_gateway.read().await.statement_cache_stats();
+        for (name, value) in [
+            ("orm_statement_cache_hits_total", stats.hits),
+            ("orm_statement_cache_misses_total", stats.misses),
+            ("orm_statement_cache_entries", stats.entries as u64),
+        ] {
+            metrics.push_str(&format!("{}{{gateway=\"{}\"}} {}\n", name, gateway, value));
+        }
+    }
+
+    Ok(metrics)
 }
 
 /// Version info endpoint - Returns Docker commit SHA and public key for verification
@@ -110,6 +134,12 @@ struct ServiceStatus {
 #[derive(Debug, serde::Serialize)]
 struct ReadinessStatus {
     is_ready: bool,
+    maintenance_mode: bool,
+    verifier_mode: VerifierMode,
+    /// State of the database circuit breaker, `None` without a database.
+    /// An open circuit does not make the instance unready: requests needing
+    /// the database fail fast while the others are still served.
+    database_circuit: Option<CircuitState>,
     timestamp: chrono::DateTime<chrono::Utc>,
     services: std::collections::BTreeMap<String, ServiceStatus>,
     errors: Vec<String>,
@@ -213,6 +243,9 @@ async fn check_readiness(state: &AppState) -> ReadinessStatus {
 
     ReadinessStatus {
         is_ready,
+        maintenance_mode: state.maintenance.is_enabled().await,
+        verifier_mode: state.verifier_mode(),
+        database_circuit: state.storage.circuit_state(),
         timestamp: chrono::Utc::now(),
         services,
         errors,
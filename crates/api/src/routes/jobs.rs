@@ -5,7 +5,7 @@ use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
-    routing::{get, post},
+    routing::{get, post, put},
     Router,
 };
 use uuid::Uuid;
@@ -23,19 +23,26 @@ pub fn create_router() -> Router<AppState> {
     Router::new()
         // Core job management routes
         .route("/api/jobs", post(create_job).get(list_jobs))
+        .route("/api/jobs/batch", post(create_jobs_batch))
         .route("/api/jobs/pending", get(get_pending_jobs))
         .route("/api/jobs/claim", post(claim_job))
         .route("/api/jobs/next", get(get_next_job))
         .route("/api/jobs/stats", get(get_job_stats))
+        .route("/api/jobs/dead-letter", get(list_dead_letter_jobs))
         
         // Specific job operations
         .route("/api/jobs/:id", get(get_job))
         .route("/api/jobs/:id/claim", post(claim_specific_job))
         .route("/api/jobs/:id/complete", post(complete_job))
         .route("/api/jobs/:id/fail", post(fail_job))
+        .route("/api/jobs/:id/requeue", post(requeue_job))
+        .route("/api/jobs/:id/priority", put(set_job_priority))
+        .route("/api/jobs/:id/reset", post(reset_job))
+        .route("/api/jobs/:id/clear-retries", post(clear_job_retries))
         
         // Job results and progress
         .route("/api/jobs/:id/results", post(submit_results))
+        .route("/api/jobs/:id/receipts", get(get_job_receipts))
         .route("/api/jobs/:id/progress", get(get_job_progress))
         .route("/api/jobs/:id/test-results", get(get_job_test_results))
         .route("/api/jobs/:id/current-test", get(get_current_test))
@@ -46,6 +53,7 @@ pub fn create_router() -> Router<AppState> {
         .route("/api/jobs/:id/metrics", get(get_job_metrics))
         .route("/api/jobs/:id/status-stream", get(get_job_status_stream))
         .route("/api/jobs/:id/timeline", get(get_job_timeline))
+        .route("/api/jobs/:id/history", get(get_job_history))
         
         // Challenge-specific routes
         .route(
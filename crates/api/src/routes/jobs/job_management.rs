@@ -3,24 +3,30 @@
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
 };
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use sqlx::Row;
 use uuid::Uuid;
 
+use crate::error::{ApiError, ApiResult};
 use crate::job_distributor::{DistributeJobRequest, JobDistributor};
 use crate::state::AppState;
+use crate::middleware::auth::Caller;
 use platform_api_models::{
-    ClaimJobRequest, ClaimJobResponse, JobListResponse, JobMetadata, JobStats,
+    ClaimJobRequest, ClaimJobResponse, DeadLetteredJobListResponse, Hotkey, JobListResponse,
+    JobMetadata, JobPriority, JobStats,
 };
-use platform_api_scheduler::CreateJobRequest;
+use platform_api_scheduler::{BatchCreateJobsResponse, CreateJobRequest, JobSearch};
 
 /// Create a new job
+///
+/// Payloads above the scheduler's `max_job_payload_bytes` are refused with 413.
 pub async fn create_job(
     State(state): State<AppState>,
     Json(request): Json<CreateJobRequest>,
-) -> Result<Json<JobMetadata>, StatusCode> {
+) -> Result<Json<JobMetadata>, Response> {
     // Clone the request data we need before moving it
     let compose_hash = request.compose_hash.clone();
     let challenge_id = request.challenge_id.clone();
@@ -28,7 +34,7 @@ pub async fn create_job(
     // Validate request
     if let Err(e) = validate_create_job_request(&request).await {
         error!("Invalid job creation request: {}", e);
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
 
     // Create job through scheduler
@@ -36,10 +42,7 @@ pub async fn create_job(
         .scheduler
         .create_job(request)
         .await
-        .map_err(|e| {
-            error!("Failed to create job: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .map_err(ApiError::from)?;
 
     // Distribute job to validators if needed
     if job.requires_distribution {
@@ -63,31 +66,66 @@ pub async fn create_job(
     Ok(Json(job))
 }
 
+/// Create several jobs atomically
+///
+/// Returns 422 with per-item errors if any request is invalid, in which case
+/// no job is created.
+pub async fn create_jobs_batch(
+    State(state): State<AppState>,
+    Json(requests): Json<Vec<CreateJobRequest>>,
+) -> Result<(StatusCode, Json<BatchCreateJobsResponse>), Response> {
+    let max_batch_size = state.scheduler.config().await.max_batch_size;
+    if requests.is_empty() {
+        error!("Rejected empty job batch");
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    if requests.len() > max_batch_size {
+        error!(
+            "Rejected job batch of {} items (max {})",
+            requests.len(),
+            max_batch_size
+        );
+        return Err(StatusCode::PAYLOAD_TOO_LARGE.into_response());
+    }
+
+    let response = state
+        .scheduler
+        .create_jobs_batch(requests)
+        .await
+        .map_err(ApiError::from)?;
+
+    let status = if response.created {
+        info!("Created batch of {} jobs", response.results.len());
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+
+    Ok((status, Json(response)))
+}
+
 /// List jobs with optional filtering
 pub async fn list_jobs(
     State(state): State<AppState>,
     Query(params): Query<ListJobsQuery>,
-) -> Result<Json<JobListResponse>, StatusCode> {
+) -> ApiResult<Json<JobListResponse>> {
     let limit = params.limit.unwrap_or(50).min(100); // Max 100 jobs
     let offset = params.offset.unwrap_or(0);
+    let search = JobSearch {
+        search: params.search,
+        created_after: params.created_after,
+        created_before: params.created_before,
+    };
 
     let jobs = state
         .scheduler
-        .list_jobs(limit, offset, params.status, params.challenge_id)
-        .await
-        .map_err(|e| {
-            error!("Failed to list jobs: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .list_jobs(limit, offset, params.status, params.challenge_id, &search)
+        .await?;
 
     let total = state
         .scheduler
         .count_jobs(params.status, params.challenge_id)
-        .await
-        .map_err(|e| {
-            error!("Failed to count jobs: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .await?;
 
     Ok(Json(JobListResponse {
         jobs,
@@ -101,15 +139,8 @@ pub async fn list_jobs(
 pub async fn get_job(
     State(state): State<AppState>,
     Path(job_id): Path<Uuid>,
-) -> Result<Json<JobMetadata>, StatusCode> {
-    let job = state
-        .scheduler
-        .get_job(job_id)
-        .await
-        .map_err(|e| {
-            error!("Failed to get job {}: {}", job_id, e);
-            StatusCode::NOT_FOUND
-        })?;
+) -> ApiResult<Json<JobMetadata>> {
+    let job = state.scheduler.get_job(job_id).await?;
 
     Ok(Json(job))
 }
@@ -118,25 +149,18 @@ pub async fn get_job(
 pub async fn claim_job(
     State(state): State<AppState>,
     Json(request): Json<ClaimJobRequest>,
-) -> Result<Json<ClaimJobResponse>, StatusCode> {
+) -> Result<Json<ClaimJobResponse>, Response> {
     // Validate validator
     if let Err(e) = validate_validator_for_claim(&request.validator_hotkey, &state).await {
         error!("Validator validation failed for job claim: {}", e);
-        return Err(StatusCode::FORBIDDEN);
+        return Err(StatusCode::FORBIDDEN.into_response());
     }
 
     let claim_response = state
         .scheduler
         .claim_job(request)
         .await
-        .map_err(|e| {
-            error!("Failed to claim job: {}", e);
-            match e.downcast_ref::<platform_api_scheduler::SchedulerError>() {
-                Some(platform_api_scheduler::SchedulerError::JobNotFound) => StatusCode::NOT_FOUND,
-                Some(platform_api_scheduler::SchedulerError::JobAlreadyClaimed) => StatusCode::CONFLICT,
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
-            }
-        })?;
+        .map_err(ApiError::from)?;
 
     info!("Job {} claimed by validator {}", 
           claim_response.job_id, claim_response.validator_hotkey);
@@ -149,11 +173,11 @@ pub async fn claim_specific_job(
     State(state): State<AppState>,
     Path(job_id): Path<Uuid>,
     Json(request): Json<ClaimJobRequest>,
-) -> Result<Json<ClaimJobResponse>, StatusCode> {
+) -> Result<Json<ClaimJobResponse>, Response> {
     // Validate validator
     if let Err(e) = validate_validator_for_claim(&request.validator_hotkey, &state).await {
         error!("Validator validation failed for specific job claim: {}", e);
-        return Err(StatusCode::FORBIDDEN);
+        return Err(StatusCode::FORBIDDEN.into_response());
     }
 
     let mut request = request;
@@ -163,10 +187,7 @@ pub async fn claim_specific_job(
         .scheduler
         .claim_job(request)
         .await
-        .map_err(|e| {
-            error!("Failed to claim specific job {}: {}", job_id, e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .map_err(ApiError::from)?;
 
     info!("Specific job {} claimed by validator {}", 
           job_id, claim_response.validator_hotkey);
@@ -179,21 +200,18 @@ pub async fn complete_job(
     State(state): State<AppState>,
     Path(job_id): Path<Uuid>,
     Json(request): Json<CompleteJobRequest>,
-) -> Result<(), StatusCode> {
+) -> Result<(), Response> {
     // Validate job completion request
     if let Err(e) = validate_complete_job_request(&request).await {
         error!("Invalid job completion request: {}", e);
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
 
     state
         .scheduler
         .complete_job(job_id, request.validator_hotkey, request.results)
         .await
-        .map_err(|e| {
-            error!("Failed to complete job {}: {}", job_id, e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .map_err(ApiError::from)?;
 
     info!("Job {} completed successfully", job_id);
     Ok(())
@@ -204,21 +222,18 @@ pub async fn fail_job(
     State(state): State<AppState>,
     Path(job_id): Path<Uuid>,
     Json(request): Json<FailJobRequest>,
-) -> Result<(), StatusCode> {
+) -> Result<(), Response> {
     // Validate job failure request
     if let Err(e) = validate_fail_job_request(&request).await {
         error!("Invalid job failure request: {}", e);
-        return Err(StatusCode::BAD_REQUEST);
+        return Err(StatusCode::BAD_REQUEST.into_response());
     }
 
     state
         .scheduler
         .fail_job(job_id, request.validator_hotkey, request.error_message)
         .await
-        .map_err(|e| {
-            error!("Failed to mark job {} as failed: {}", job_id, e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .map_err(ApiError::from)?;
 
     warn!("Job {} failed: {}", job_id, request.error_message);
     Ok(())
@@ -228,21 +243,25 @@ pub async fn fail_job(
 pub async fn get_next_job(
     State(state): State<AppState>,
     Query(params): Query<GetNextJobQuery>,
-) -> Result<Json<Option<JobMetadata>>, StatusCode> {
+) -> Result<Json<Option<JobMetadata>>, Response> {
     // Validate validator
     if let Err(e) = validate_validator_for_claim(&params.validator_hotkey, &state).await {
         error!("Validator validation failed for next job request: {}", e);
-        return Err(StatusCode::FORBIDDEN);
+        return Err(StatusCode::FORBIDDEN.into_response());
+    }
+
+    if let Some(capacity) = params.capacity {
+        state
+            .scheduler
+            .report_validator_capacity(&params.validator_hotkey, capacity)
+            .await;
     }
 
     let job = state
         .scheduler
         .get_next_job(params.validator_hotkey, params.challenge_id)
         .await
-        .map_err(|e| {
-            error!("Failed to get next job: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .map_err(ApiError::from)?;
 
     Ok(Json(job))
 }
@@ -251,15 +270,11 @@ pub async fn get_next_job(
 pub async fn get_job_stats(
     State(state): State<AppState>,
     Query(params): Query<JobStatsQuery>,
-) -> Result<Json<JobStats>, StatusCode> {
+) -> ApiResult<Json<JobStats>> {
     let stats = state
         .scheduler
         .get_job_stats(params.challenge_id, params.time_range)
-        .await
-        .map_err(|e| {
-            error!("Failed to get job stats: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .await?;
 
     Ok(Json(stats))
 }
@@ -268,34 +283,146 @@ pub async fn get_job_stats(
 pub async fn get_pending_jobs(
     State(state): State<AppState>,
     Query(params): Query<PendingJobsQuery>,
-) -> Result<Json<Vec<JobMetadata>>, StatusCode> {
+) -> ApiResult<Json<Vec<JobMetadata>>> {
     let limit = params.limit.unwrap_or(20).min(50); // Max 50 pending jobs
 
     let jobs = state
         .scheduler
         .get_pending_jobs(limit, params.challenge_id)
-        .await
-        .map_err(|e| {
-            error!("Failed to get pending jobs: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        .await?;
 
     Ok(Json(jobs))
 }
 
+/// List jobs that exhausted their retries, with their retry history
+pub async fn list_dead_letter_jobs(
+    State(state): State<AppState>,
+    Query(params): Query<DeadLetterJobsQuery>,
+) -> ApiResult<Json<DeadLetteredJobListResponse>> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(50).clamp(1, 100);
+
+    let jobs = state
+        .scheduler
+        .list_dead_lettered_jobs(page, per_page)
+        .await?;
+
+    Ok(Json(jobs))
+}
+
+/// Re-queue a dead-lettered job with its retry count reset (admin only)
+pub async fn requeue_job(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<JobMetadata>, Response> {
+    if !caller.admin {
+        warn!(caller = %caller.owner, job_id = %job_id, "Denied requeue of job to non-admin");
+        return Err(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let job = state
+        .scheduler
+        .requeue_job(job_id, &caller.owner)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(job))
+}
+
+/// Set the priority of a job (admin only)
+///
+/// Takes effect from the next claim.
+pub async fn set_job_priority(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(job_id): Path<Uuid>,
+    Json(request): Json<SetJobPriorityRequest>,
+) -> Result<Json<JobMetadata>, Response> {
+    require_admin(&caller, job_id, "reprioritize")?;
+
+    let job = state
+        .scheduler
+        .set_job_priority(job_id, request.priority, &caller.owner)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(job))
+}
+
+/// Reset a claimed, running, failed, timed out or dead-lettered job to
+/// pending (admin only)
+pub async fn reset_job(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<JobMetadata>, Response> {
+    require_admin(&caller, job_id, "reset")?;
+
+    let job = state
+        .scheduler
+        .reset_job(job_id, &caller.owner)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(job))
+}
+
+/// Set the retry count of a job back to zero (admin only)
+pub async fn clear_job_retries(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<JobMetadata>, Response> {
+    require_admin(&caller, job_id, "clear retries of")?;
+
+    let job = state
+        .scheduler
+        .clear_job_retries(job_id, &caller.owner)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(job))
+}
+
+fn require_admin(caller: &Caller, job_id: Uuid, action: &str) -> Result<(), Response> {
+    if caller.admin {
+        return Ok(());
+    }
+    warn!(caller = %caller.owner, job_id = %job_id, "Denied {} job to non-admin", action);
+    Err(StatusCode::FORBIDDEN.into_response())
+}
+
 // Request/Response types
+#[derive(Deserialize)]
+pub struct DeadLetterJobsQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct SetJobPriorityRequest {
+    pub priority: JobPriority,
+}
+
 #[derive(Deserialize)]
 pub struct ListJobsQuery {
     pub limit: Option<u32>,
     pub offset: Option<u32>,
     pub status: Option<String>,
     pub challenge_id: Option<Uuid>,
+    /// Text matched against the top-level keys and values of the job payload
+    pub search: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize)]
 pub struct GetNextJobQuery {
-    pub validator_hotkey: String,
+    pub validator_hotkey: Hotkey,
     pub challenge_id: Option<Uuid>,
+    /// Number of jobs the validator can run at once; it is not handed more
+    pub capacity: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -320,6 +447,12 @@ pub struct CompleteJobRequest {
 pub struct FailJobRequest {
     pub validator_hotkey: String,
     pub error_message: String,
+    #[serde(default)]
+    pub failure_category: Option<platform_api_models::FailureCategory>,
+    /// Version of the job the failure is for, see
+    /// `SubmitResultRequest::expected_version`
+    #[serde(default)]
+    pub expected_version: Option<u64>,
 }
 
 // Validation functions
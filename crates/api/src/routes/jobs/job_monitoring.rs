@@ -10,6 +10,7 @@ use uuid::Uuid;
 use futures_util::StreamExt;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
+use tracing::Instrument;
 use axum::response::sse::{Event, Sse};
 
 use crate::state::AppState;
@@ -40,11 +41,14 @@ pub async fn stream_logs(
 
     // Spawn task to stream logs
     let state_clone = state.clone();
-    tokio::spawn(async move {
-        if let Err(e) = stream_job_logs_task(job_id, params, tx, state_clone).await {
-            error!("Log streaming task failed for job {}: {}", job_id, e);
+    tokio::spawn(
+        async move {
+            if let Err(e) = stream_job_logs_task(job_id, params, tx, state_clone).await {
+                error!("Log streaming task failed for job {}: {}", job_id, e);
+            }
         }
-    });
+        .in_current_span(),
+    );
 
     // Convert receiver to SSE stream
     let stream = ReceiverStream::new(rx)
@@ -150,11 +154,14 @@ pub async fn get_job_status_stream(
 
     // Spawn task to stream status updates
     let state_clone = state.clone();
-    tokio::spawn(async move {
-        if let Err(e) = stream_job_status_task(job_id, tx, state_clone).await {
-            error!("Status streaming task failed for job {}: {}", job_id, e);
+    tokio::spawn(
+        async move {
+            if let Err(e) = stream_job_status_task(job_id, tx, state_clone).await {
+                error!("Status streaming task failed for job {}: {}", job_id, e);
+            }
         }
-    });
+        .in_current_span(),
+    );
 
     // Convert receiver to SSE stream
     let stream = ReceiverStream::new(rx)
@@ -296,6 +303,19 @@ pub async fn get_job_timeline(
     Ok(Json(timeline))
 }
 
+/// Get the status transitions of a job, oldest first
+pub async fn get_job_history(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<Vec<platform_api_models::JobEvent>>, StatusCode> {
+    let history = state.storage.get_job_history(job_id).await.map_err(|e| {
+        error!("Failed to get job history {}: {}", job_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(history))
+}
+
 // Request/Response types
 #[derive(Deserialize)]
 pub struct LogStreamQuery {
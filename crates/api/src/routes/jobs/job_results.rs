@@ -8,8 +8,10 @@ use axum::{
 use serde::Deserialize;
 use uuid::Uuid;
 
+use crate::middleware::auth::Caller;
+use crate::policy::{authorize_challenge, ChallengeAction};
 use crate::state::AppState;
-use platform_api_models::{JobMetadata, JobTestResult, SubmitResultRequest};
+use platform_api_models::{JobMetadata, JobReceipts, JobTestResult, SubmitResultRequest};
 use serde_json::Value as JsonValue;
 
 /// Submit job results
@@ -160,6 +162,49 @@ pub async fn get_current_test(
     Ok(Json(current_test))
 }
 
+/// Get the receipts a validator submitted with the result of a job
+///
+/// Restricted to the validator that ran the job, the owner of its challenge
+/// and admins. The attestation receipt is checked against the attestation
+/// service, which only verifies the grant tokens it issued itself.
+pub async fn get_job_receipts(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<JobReceiptsResponse>, StatusCode> {
+    let job = state.scheduler.get_job(job_id).await.map_err(|e| {
+        warn!("Failed to get job {} for receipts: {}", job_id, e);
+        StatusCode::NOT_FOUND
+    })?;
+
+    let ran_job = job.validator_hotkey.as_deref() == Some(caller.owner.as_str());
+    if !(caller.admin || ran_job) {
+        authorize_challenge(&state, &caller, job.challenge_id, ChallengeAction::ViewReceipts)
+            .await?;
+    }
+
+    let receipts = state.scheduler.get_job_receipts(job_id).await.map_err(|e| {
+        error!("Failed to get receipts of job {}: {}", job_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let attestation_verified = match &receipts.attestation_receipt {
+        Some(receipt) => match state.attestation.verify_receipt(receipt) {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Attestation receipt of job {} did not verify: {}", job_id, e);
+                false
+            }
+        },
+        None => false,
+    };
+
+    Ok(Json(JobReceiptsResponse {
+        receipts,
+        attestation_verified,
+    }))
+}
+
 /// Process test results
 async fn process_test_results(
     job_id: Uuid,
@@ -306,6 +351,14 @@ pub struct JobProgressInfo {
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+#[derive(serde::Serialize)]
+pub struct JobReceiptsResponse {
+    #[serde(flatten)]
+    pub receipts: JobReceipts,
+    /// Whether the attestation receipt was issued by this platform's attestation service
+    pub attestation_verified: bool,
+}
+
 #[derive(serde::Serialize)]
 pub struct CurrentTestInfo {
     pub test_name: String,
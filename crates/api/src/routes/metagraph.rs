@@ -1,19 +1,82 @@
-use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use bittensor_rs::chain::BittensorClient;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::collections::HashSet;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::OnceLock;
 use tokio::sync::RwLock;
 use tracing::{error, info};
 
+use crate::services::bittensor::get_subnet_params;
 use crate::state::AppState;
+use platform_api_models::{Hotkey, MetagraphDiff, NeuronInfo, SubnetParams};
+
+/// Channel notified, with the diff counts, of each metagraph diff written to
+/// the `validators` table
+pub const METAGRAPH_DIFF_APPLIED_CHANNEL: &str = "metagraph_diff_applied";
+
+/// Number of applied diffs kept for `GET /api/metagraph/diff`
+const RECENT_DIFFS: usize = 100;
 
 /// Metagraph cache (in-memory)
 static METAGRAPH_CACHE: OnceLock<RwLock<HashSet<String>>> = OnceLock::new();
 
+/// Stake of each hotkey relative to the largest stake on the subnet
+static METAGRAPH_STAKES: OnceLock<RwLock<HashMap<String, f64>>> = OnceLock::new();
+
+/// Hyperparameters of the subnet, read on each metagraph sync
+static METAGRAPH_PARAMS: OnceLock<RwLock<Option<SubnetParams>>> = OnceLock::new();
+
+/// Neurons of the last metagraph sync whose changes were written, by hotkey
+static METAGRAPH_NEURONS: OnceLock<RwLock<HashMap<Hotkey, NeuronInfo>>> = OnceLock::new();
+
+/// Block of the last metagraph sync, 0 before the first one
+static LAST_SYNC_BLOCK: AtomicU64 = AtomicU64::new(0);
+
+/// Latest applied diffs, oldest first
+static METAGRAPH_DIFFS: OnceLock<RwLock<VecDeque<AppliedMetagraphDiff>>> = OnceLock::new();
+
+/// A metagraph diff and the block of the sync that applied it
+#[derive(Debug, Clone, Serialize)]
+pub struct AppliedMetagraphDiff {
+    pub block: u64,
+    #[serde(flatten)]
+    pub diff: MetagraphDiff,
+}
+
 pub fn get_metagraph_cache() -> &'static RwLock<HashSet<String>> {
     METAGRAPH_CACHE.get_or_init(|| RwLock::new(HashSet::new()))
 }
 
+pub fn get_metagraph_stakes() -> &'static RwLock<HashMap<String, f64>> {
+    METAGRAPH_STAKES.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+pub fn get_subnet_params_cache() -> &'static RwLock<Option<SubnetParams>> {
+    METAGRAPH_PARAMS.get_or_init(|| RwLock::new(None))
+}
+
+pub fn get_metagraph_neurons() -> &'static RwLock<HashMap<Hotkey, NeuronInfo>> {
+    METAGRAPH_NEURONS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn get_metagraph_diffs() -> &'static RwLock<VecDeque<AppliedMetagraphDiff>> {
+    METAGRAPH_DIFFS.get_or_init(|| RwLock::new(VecDeque::new()))
+}
+
+/// Block of the last metagraph sync, 0 before the first one
+pub fn last_sync_block() -> u64 {
+    LAST_SYNC_BLOCK.load(Ordering::Relaxed)
+}
+
 /// Get netuid from environment or use default subnet (100)
 fn get_netuid() -> u16 {
     std::env::var("BT_NETUID")
@@ -24,7 +87,10 @@ fn get_netuid() -> u16 {
 
 /// Create metagraph router
 pub fn create_router() -> Router<AppState> {
-    Router::new().route("/api/metagraph/hotkeys", get(get_metagraph_hotkeys))
+    Router::new()
+        .route("/api/metagraph/hotkeys", get(get_metagraph_hotkeys))
+        .route("/api/metagraph/params", get(get_metagraph_params))
+        .route("/api/metagraph/diff", get(get_metagraph_diff))
 }
 
 /// Get list of valid hotkeys from metagraph cache
@@ -45,8 +111,54 @@ pub async fn get_metagraph_hotkeys(
     })))
 }
 
+/// Get the hyperparameters of the subnet from the last metagraph sync
+///
+/// Lets validators check they meet the minimum stake before connecting.
+/// Returns 503 until the parameters have been read once.
+pub async fn get_metagraph_params(
+    State(_state): State<AppState>,
+) -> Result<Json<SubnetParams>, StatusCode> {
+    get_subnet_params_cache()
+        .read()
+        .await
+        .clone()
+        .map(Json)
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetagraphDiffParams {
+    #[serde(default)]
+    pub since_block: u64,
+}
+
+/// Get the metagraph diffs applied after block `since_block`, oldest first
+///
+/// For debugging the sync: only the latest diffs are kept.
+pub async fn get_metagraph_diff(
+    State(_state): State<AppState>,
+    Query(params): Query<MetagraphDiffParams>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let diffs: Vec<AppliedMetagraphDiff> = get_metagraph_diffs()
+        .read()
+        .await
+        .iter()
+        .filter(|applied| applied.block > params.since_block)
+        .cloned()
+        .collect();
+
+    Ok(Json(json!({
+        "last_sync_block": last_sync_block(),
+        "diffs": diffs,
+    })))
+}
+
 /// Initialize metagraph cache by syncing from Bittensor chain/subtensor
-pub async fn refresh_metagraph_cache() {
+///
+/// Only the neurons that changed since the previous sync are written to the
+/// `validators` table, when there is a database; the first sync writes them
+/// all. A diff that fails to be written is retried on the next sync.
+pub async fn refresh_metagraph_cache(pool: Option<&PgPool>) {
     let cache = get_metagraph_cache();
     let netuid = get_netuid();
 
@@ -55,15 +167,38 @@ pub async fn refresh_metagraph_cache() {
         "Starting metagraph cache refresh from Bittensor chain"
     );
 
-    match sync_metagraph_from_chain(netuid).await {
-        Ok(hotkeys) => {
-            let mut cache_guard = cache.write().await;
-            *cache_guard = hotkeys;
-            info!(
+    // Create Bittensor client with default connection (connects to mainnet finney)
+    // Can be overridden via BT_ENDPOINT env var in BittensorClient::with_default()
+    let client = match BittensorClient::with_default().await {
+        Ok(client) => client,
+        Err(e) => {
+            error!(
                 netuid = netuid,
-                hotkey_count = cache_guard.len(),
-                "Metagraph cache refreshed successfully"
+                error = %e,
+                "Failed to create Bittensor client for metagraph refresh"
             );
+            return;
+        }
+    };
+
+    match sync_metagraph_from_chain(&client, netuid).await {
+        Ok((block, neurons)) => {
+            let stakes: HashMap<String, f64> = neurons
+                .iter()
+                .map(|neuron| (neuron.hotkey.to_string(), neuron.stake))
+                .collect();
+            {
+                let mut cache_guard = cache.write().await;
+                *cache_guard = stakes.keys().cloned().collect();
+                *get_metagraph_stakes().write().await = relative_stakes(&stakes);
+                info!(
+                    netuid = netuid,
+                    hotkey_count = cache_guard.len(),
+                    "Metagraph cache refreshed successfully"
+                );
+            }
+
+            record_metagraph_sync(pool, netuid, block, neurons).await;
         }
         Err(e) => {
             error!(
@@ -74,39 +209,175 @@ pub async fn refresh_metagraph_cache() {
             // Don't clear cache on error, keep existing data
         }
     }
+
+    match get_subnet_params(&client, netuid).await {
+        Ok(params) => *get_subnet_params_cache().write().await = Some(params),
+        Err(e) => {
+            error!(
+                netuid = netuid,
+                error = %e,
+                "Failed to refresh subnet parameters from chain"
+            );
+        }
+    }
 }
 
-/// Sync metagraph from Bittensor chain and extract all hotkeys
-async fn sync_metagraph_from_chain(netuid: u16) -> anyhow::Result<HashSet<String>> {
-    use bittensor_rs::chain::BittensorClient;
+/// Diff the neurons of a sync at `block` against the cached ones, write the
+/// diff and cache the neurons
+///
+/// The cache is left as it is if writing the diff fails, so that it is
+/// written with the next one.
+async fn record_metagraph_sync(
+    pool: Option<&PgPool>,
+    netuid: u16,
+    block: u64,
+    neurons: Vec<NeuronInfo>,
+) {
+    let mut cached = get_metagraph_neurons().write().await;
+    let diff = MetagraphDiff::between(&cached, &neurons);
+    if !diff.is_empty() {
+        if let Some(pool) = pool {
+            if let Err(e) = apply_metagraph_diff(pool, block, &diff).await {
+                error!(
+                    netuid = netuid,
+                    error = %e,
+                    "Failed to write metagraph diff to validators"
+                );
+                return;
+            }
+        }
+        info!(
+            netuid = netuid,
+            block = block,
+            added = diff.added.len(),
+            removed = diff.removed.len(),
+            updated = diff.updated.len(),
+            "Applied metagraph diff"
+        );
+        let mut diffs = get_metagraph_diffs().write().await;
+        if diffs.len() == RECENT_DIFFS {
+            diffs.pop_front();
+        }
+        diffs.push_back(AppliedMetagraphDiff { block, diff });
+    }
+    *cached = neurons
+        .into_iter()
+        .map(|neuron| (neuron.hotkey.clone(), neuron))
+        .collect();
+    LAST_SYNC_BLOCK.store(block, Ordering::Relaxed);
+}
+
+/// Scale stakes so that the largest stake on the subnet is 1.0
+fn relative_stakes(stakes: &HashMap<String, f64>) -> HashMap<String, f64> {
+    let max_stake = stakes.values().copied().fold(0.0, f64::max);
+    stakes
+        .iter()
+        .map(|(hotkey, stake)| {
+            let share = if max_stake > 0.0 { stake / max_stake } else { 0.0 };
+            (hotkey.clone(), share)
+        })
+        .collect()
+}
+
+/// Write the uid and stake of the registered validators that changed in
+/// `diff` to the `validators` table, and notify
+/// `METAGRAPH_DIFF_APPLIED_CHANNEL` with the diff counts
+async fn apply_metagraph_diff(
+    pool: &PgPool,
+    block: u64,
+    diff: &MetagraphDiff,
+) -> anyhow::Result<()> {
+    let (hotkeys, (uids, stakes)): (Vec<&str>, (Vec<i32>, Vec<f64>)) = diff
+        .changed()
+        .map(|neuron| (neuron.hotkey.as_str(), (neuron.uid as i32, neuron.stake)))
+        .unzip();
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        UPDATE validators AS v
+        SET uid = n.uid, stake = n.stake, metagraph_block = $4, updated_at = NOW()
+        FROM UNNEST($1::TEXT[], $2::INTEGER[], $3::DOUBLE PRECISION[]) AS n(hotkey, uid, stake)
+        WHERE v.hotkey = n.hotkey
+        "#,
+    )
+    .bind(&hotkeys)
+    .bind(&uids)
+    .bind(&stakes)
+    .bind(block as i64)
+    .execute(&mut *tx)
+    .await?;
+
+    let removed: Vec<&str> = diff.removed.iter().map(|hotkey| hotkey.as_str()).collect();
+    sqlx::query(
+        r#"
+        UPDATE validators
+        SET uid = NULL, stake = NULL, metagraph_block = $2, updated_at = NOW()
+        WHERE hotkey = ANY($1)
+        "#,
+    )
+    .bind(&removed)
+    .bind(block as i64)
+    .execute(&mut *tx)
+    .await?;
+
+    // Delivered to listeners on commit only
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(METAGRAPH_DIFF_APPLIED_CHANNEL)
+        .bind(
+            json!({
+                "block": block,
+                "added": diff.added.len(),
+                "removed": diff.removed.len(),
+                "updated": diff.updated.len(),
+            })
+            .to_string(),
+        )
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Sync metagraph from Bittensor chain, returning the latest block and all
+/// neurons of the subnet
+async fn sync_metagraph_from_chain(
+    client: &BittensorClient,
+    netuid: u16,
+) -> anyhow::Result<(u64, Vec<NeuronInfo>)> {
     use bittensor_rs::queries::neurons;
     use bittensor_rs::utils::ss58::encode_ss58;
 
-    // Create Bittensor client with default connection (connects to mainnet finney)
-    // Can be overridden via BT_ENDPOINT env var in BittensorClient::with_default()
-    let client = BittensorClient::with_default()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to create Bittensor client: {}", e))?;
-
     info!(netuid = netuid, "Querying neurons from Bittensor chain");
 
+    let block = client
+        .block_number()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to query latest block number: {}", e))?;
+
     // Get all neurons for the subnet
-    let neurons_list = neurons::neurons(&client, netuid, None)
+    let neurons_list = neurons::neurons(client, netuid, None)
         .await
         .map_err(|e| anyhow::anyhow!("Failed to query neurons: {}", e))?;
 
     info!(
         netuid = netuid,
+        block = block,
         neuron_count = neurons_list.len(),
         "Retrieved neurons from chain"
     );
 
-    // Extract hotkeys and convert to ss58 format
-    let mut hotkeys = HashSet::new();
-    for neuron in neurons_list {
-        let hotkey_ss58 = encode_ss58(&neuron.hotkey);
-        hotkeys.insert(hotkey_ss58);
-    }
-
-    Ok(hotkeys)
+    // Convert hotkeys to ss58 format
+    let neurons = neurons_list
+        .into_iter()
+        .map(|neuron| -> anyhow::Result<NeuronInfo> {
+            Ok(NeuronInfo {
+                hotkey: encode_ss58(&neuron.hotkey).parse()?,
+                uid: neuron.uid as u16,
+                stake: neuron.total_stake as f64,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok((block, neurons))
 }
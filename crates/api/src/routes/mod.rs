@@ -1,4 +1,6 @@
+pub mod admin;
 pub mod attestation;
+pub mod builds;
 pub mod challenge_credentials;
 pub mod challenge_proxy;
 pub mod challenges;
@@ -1,14 +1,17 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
     response::Json,
-    routing::post,
+    routing::{get, post},
     Router,
 };
 use serde_json::Value;
 use tracing::{error, info, warn};
 
-use platform_api_orm_gateway::ORMQuery;
+use platform_api_orm_gateway::{
+    audit::list_audit_entries, AuditEntry, AuditFilter, ChallengeSchema, ORMError, ORMQuery,
+};
+use crate::middleware::auth::Caller;
 use crate::state::AppState;
 
 /// Create ORM router for validator routes (read-only)
@@ -19,6 +22,34 @@ pub fn create_router() -> Router<AppState> {
             "/challenges/:challenge_id/orm/query",
             post(execute_orm_query_with_challenge),
         )
+        .route("/orm/audit", get(get_orm_audit))
+}
+
+/// List audited ORM queries, newest first (admin only)
+///
+/// Filtered by `challenge` and an executed-at range (`from`, `to`).
+async fn get_orm_audit(
+    State(state): State<AppState>,
+    caller: Caller,
+    Query(filter): Query<AuditFilter>,
+) -> Result<Json<Vec<AuditEntry>>, StatusCode> {
+    if !caller.admin {
+        warn!(caller = %caller.owner, "Denied ORM query audit to non-admin");
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let pool = state
+        .database_pool
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    list_audit_entries(pool, &filter)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!(error = %e, "Failed to list ORM query audit");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
 }
 
 /// Execute ORM query (read-only for validator)
@@ -70,7 +101,7 @@ async fn execute_orm_query(
                 error = %e,
                 "ORM query failed"
             );
-            Err(StatusCode::BAD_REQUEST)
+            Err(orm_error_status(&e, StatusCode::BAD_REQUEST))
         }
     }
 }
@@ -84,7 +115,7 @@ async fn execute_orm_query_with_challenge(
     State(state): State<AppState>,
     Path(challenge_id): Path<String>,
     headers: HeaderMap,
-    Json(mut query): Json<ORMQuery>,
+    Json(query): Json<ORMQuery>,
 ) -> Result<Json<Value>, StatusCode> {
     // Get validator hotkey from header
     let validator_hotkey = extract_validator_hotkey(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
@@ -95,83 +126,36 @@ async fn execute_orm_query_with_challenge(
         "DEPRECATED: ORM query via HTTP - please use WebSocket connection instead"
     );
 
-    // Resolve schema: {challenge_name}_v{db_version}
-    if query.schema.is_none() {
-        // Get challenge_name from database using challenge_id
-        let challenge_name = if let Some(pool) = &state.database_pool {
-            // Try to parse challenge_id as UUID first
-            let challenge_uuid = if let Ok(uuid) = uuid::Uuid::parse_str(&challenge_id) {
-                uuid
-            } else {
-                // If not a UUID, treat it as challenge name directly
-                // Normalize challenge name (replace hyphens with underscores for schema)
-                let schema_name = challenge_id.replace('-', "_");
-                let db_version = query.db_version.unwrap_or(1);
-                query.schema = Some(format!("{}_v{}", schema_name, db_version));
-                info!(
-                    challenge_id = &challenge_id,
-                    schema = &query.schema.as_ref().unwrap(),
-                    "Resolved schema from challenge_id (non-UUID) and db_version"
-                );
-                // Use read-only ORM gateway
-                let gateway = state
-                    .orm_gateway
-                    .as_ref()
-                    .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
-                let gateway = gateway.read().await;
-                let result = gateway.execute_read_query(query).await.map_err(|e| {
-                    error!("Failed to execute ORM query: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
-                return Ok(Json(serde_json::to_value(result).map_err(|e| {
-                    error!("Failed to serialize ORM result: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?));
-            };
-
-            // Query database for challenge name
-            let name_result: Option<String> =
-                sqlx::query_scalar("SELECT name FROM challenges WHERE id = $1 LIMIT 1")
-                    .bind(challenge_uuid)
-                    .fetch_optional(pool.as_ref())
-                    .await
-                    .map_err(|e| {
-                        error!("Failed to query challenge name from database: {}", e);
-                        StatusCode::INTERNAL_SERVER_ERROR
-                    })?;
-
-            match name_result {
-                Some(name) => name,
-                None => {
-                    error!(
-                        challenge_id = &challenge_id,
-                        "Challenge not found in database"
-                    );
-                    return Err(StatusCode::NOT_FOUND);
-                }
-            }
-        } else {
-            error!(challenge_id = &challenge_id, "Database pool not available");
-            return Err(StatusCode::SERVICE_UNAVAILABLE);
-        };
-
-        // Get db_version from query, default to 1 if not provided
-        let db_version = query.db_version.unwrap_or(1);
-
-        // Normalize challenge name (replace hyphens with underscores for schema)
-        let normalized_name = challenge_name.replace('-', "_");
-
-        // Construct schema: {challenge_name}_v{db_version}
-        query.schema = Some(format!("{}_v{}", normalized_name, db_version));
-
-        info!(
+    // Queries are confined to the schema of the challenge and run as its
+    // role, both derived from its immutable ID; a schema named by the query
+    // is checked against it rather than trusted
+    let challenge_uuid = uuid::Uuid::parse_str(&challenge_id).map_err(|_| {
+        warn!(
+            challenge_id = &challenge_id,
+            "ORM query for an invalid challenge ID"
+        );
+        StatusCode::BAD_REQUEST
+    })?;
+    let pool = state.database_pool.as_ref().ok_or_else(|| {
+        error!(challenge_id = &challenge_id, "Database pool not available");
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM challenges WHERE id = $1)")
+        .bind(challenge_uuid)
+        .fetch_one(pool.as_ref())
+        .await
+        .map_err(|e| {
+            error!("Failed to look up challenge: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !exists {
+        error!(
             challenge_id = &challenge_id,
-            challenge_name = &challenge_name,
-            db_version = db_version,
-            schema = &query.schema.as_ref().unwrap(),
-            "Resolved schema from challenge_name and db_version"
+            "Challenge not found in database"
         );
+        return Err(StatusCode::NOT_FOUND);
     }
+    let scope = ChallengeSchema::for_challenge(challenge_uuid);
 
     info!(
         validator_hotkey = &validator_hotkey,
@@ -188,7 +172,7 @@ async fn execute_orm_query_with_challenge(
 
     // Execute query (read-only gateway will reject write operations)
     let orm_gateway_guard = orm_gateway.read().await;
-    match orm_gateway_guard.execute_query(query).await {
+    match orm_gateway_guard.execute_scoped_query(&scope, query).await {
         Ok(result) => Ok(Json(serde_json::json!({
             "success": true,
             "result": result
@@ -200,11 +184,24 @@ async fn execute_orm_query_with_challenge(
                 error = %e,
                 "ORM query failed"
             );
-            Err(StatusCode::BAD_REQUEST)
+            Err(orm_error_status(&e, StatusCode::BAD_REQUEST))
         }
     }
 }
 
+/// Status of a failed ORM query: 409 for an optimistic lock conflict, 504 for
+/// a statement timeout, 422 for a query refused by a guardrail, 403 for a
+/// query outside its challenge schema, else `fallback`
+fn orm_error_status(e: &anyhow::Error, fallback: StatusCode) -> StatusCode {
+    match e.downcast_ref::<ORMError>() {
+        Some(ORMError::VersionConflict { .. }) => StatusCode::CONFLICT,
+        Some(ORMError::Timeout { .. }) => StatusCode::GATEWAY_TIMEOUT,
+        Some(ORMError::Rejected { .. }) => StatusCode::UNPROCESSABLE_ENTITY,
+        Some(ORMError::SchemaDenied { .. }) => StatusCode::FORBIDDEN,
+        None => fallback,
+    }
+}
+
 /// Extract validator hotkey from header
 fn extract_validator_hotkey(header_map: &HeaderMap) -> Option<String> {
     header_map
@@ -354,6 +354,7 @@ pub async fn list_jobs_for_ui(
             100,  // Get up to 100 jobs for UI
             None, // No status filter
             params.challenge_id,
+            &Default::default(),
         )
         .await
         .map_err(|e| {
@@ -370,6 +371,8 @@ pub async fn list_jobs_for_ui(
             platform_api_models::JobStatus::Completed => "completed".to_string(),
             platform_api_models::JobStatus::Failed => "failed".to_string(),
             platform_api_models::JobStatus::Timeout => "timeout".to_string(),
+            platform_api_models::JobStatus::DeadLettered => "dead_lettered".to_string(),
+            platform_api_models::JobStatus::Blocked => "blocked".to_string(),
         }
     }
 
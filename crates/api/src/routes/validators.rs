@@ -1,11 +1,26 @@
-use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use platform_api_models::{
+    NeuronInfo, RegisterValidatorRequest, ResourceRequirements, ValidatorRegistration,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
 
+use crate::middleware::auth::verify_hotkey_signature;
 use crate::state::AppState;
 
 /// Create validators router
 pub fn create_router() -> Router<AppState> {
-    Router::new().route("/validators/public", get(list_validators_public))
+    Router::new()
+        .route("/validators/public", get(list_validators_public))
+        .route("/validators/register", post(register_validator))
+        .route("/validators/capacity", get(get_challenge_capacity))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -203,3 +218,350 @@ pub async fn list_validators_public(
         validators,
     }))
 }
+
+/// Register a validator and the capabilities it declares
+///
+/// The registration must be signed by its hotkey within the signed request
+/// max age, with a nonce used once, see
+/// [`ValidatorRegistration::signing_payload`]. Only validators of the
+/// metagraph can register. Registering again replaces the declared
+/// capabilities.
+pub async fn register_validator(
+    State(state): State<AppState>,
+    Json(request): Json<RegisterValidatorRequest>,
+) -> Result<Json<ValidatorRegistration>, StatusCode> {
+    let pool = state.database_pool.as_ref().ok_or_else(|| {
+        tracing::error!("Database pool not available");
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+
+    let neuron = verify_registration(&state, &request).await?;
+    let registration = request.registration;
+
+    let runtimes: Vec<String> = registration
+        .runtimes
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    // Later changes are written by the metagraph sync
+    sqlx::query(
+        r#"
+        INSERT INTO validators (
+            hotkey, runtimes, gpu, memory_gb, cpu_cores, preferred_challenges,
+            gpu_count, gpu_type, disk_gb, uid, stake, metagraph_block
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        ON CONFLICT (hotkey) DO UPDATE
+        SET runtimes = EXCLUDED.runtimes,
+            gpu = EXCLUDED.gpu,
+            memory_gb = EXCLUDED.memory_gb,
+            cpu_cores = EXCLUDED.cpu_cores,
+            preferred_challenges = EXCLUDED.preferred_challenges,
+            gpu_count = EXCLUDED.gpu_count,
+            gpu_type = EXCLUDED.gpu_type,
+            disk_gb = EXCLUDED.disk_gb,
+            uid = EXCLUDED.uid,
+            stake = EXCLUDED.stake,
+            metagraph_block = EXCLUDED.metagraph_block,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(registration.hotkey.as_str())
+    .bind(&runtimes)
+    .bind(registration.gpu)
+    .bind(i64::try_from(registration.memory_gb).map_err(|_| StatusCode::BAD_REQUEST)?)
+    .bind(i32::try_from(registration.cpu_cores).map_err(|_| StatusCode::BAD_REQUEST)?)
+    .bind(&registration.preferred_challenges)
+    .bind(i32::try_from(registration.gpu_count).map_err(|_| StatusCode::BAD_REQUEST)?)
+    .bind(registration.gpu_type.as_deref())
+    .bind(
+        registration
+            .disk_gb
+            .map(i64::try_from)
+            .transpose()
+            .map_err(|_| StatusCode::BAD_REQUEST)?,
+    )
+    .bind(neuron.uid as i32)
+    .bind(neuron.stake)
+    .bind(Some(crate::routes::metagraph::last_sync_block() as i64).filter(|block| *block > 0))
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to store validator registration: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tracing::info!(
+        hotkey = registration.hotkey.as_str(),
+        runtimes = ?runtimes,
+        preferred_challenges = registration.preferred_challenges.len(),
+        "Validator registered"
+    );
+    state
+        .scheduler
+        .report_validator_registration(registration.clone())
+        .await;
+    Ok(Json(registration))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChallengeCapacityQuery {
+    pub challenge_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChallengeCapacityResponse {
+    pub challenge_id: Uuid,
+    pub resources: ResourceRequirements,
+    pub connected_validators: usize,
+    /// Connected validators whose registration declares at least `resources`
+    pub satisfying_validators: usize,
+}
+
+/// Count the connected validators able to run the jobs of a challenge
+///
+/// Validators that never registered only count for challenges without
+/// resource requirements, see [`ResourceRequirements::satisfied_by`].
+pub async fn get_challenge_capacity(
+    State(state): State<AppState>,
+    Query(query): Query<ChallengeCapacityQuery>,
+) -> Result<Json<ChallengeCapacityResponse>, StatusCode> {
+    let pool = state.database_pool.as_ref().ok_or_else(|| {
+        tracing::error!("Database pool not available");
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+
+    let resources: serde_json::Value =
+        sqlx::query_scalar("SELECT resource_requirements FROM challenges WHERE id = $1")
+            .bind(query.challenge_id)
+            .fetch_optional(pool.as_ref())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to load challenge {}: {}", query.challenge_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+    let resources: ResourceRequirements = serde_json::from_value(resources).unwrap_or_default();
+
+    let connected: Vec<String> = state
+        .list_validator_connections()
+        .await
+        .into_iter()
+        .map(|conn| conn.validator_hotkey)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let registrations = state
+        .validator_registrations(&connected)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load validator registrations: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let satisfying_validators = if resources.is_empty() {
+        connected.len()
+    } else {
+        registrations
+            .iter()
+            .filter(|registration| resources.satisfied_by(registration))
+            .count()
+    };
+
+    Ok(Json(ChallengeCapacityResponse {
+        challenge_id: query.challenge_id,
+        resources,
+        connected_validators: connected.len(),
+        satisfying_validators,
+    }))
+}
+
+/// Check the signature and nonce of a registration and that its hotkey is a
+/// validator of the metagraph, returning its neuron
+///
+/// The nonce is only used up once the signature checks out, so that others
+/// cannot burn it.
+async fn verify_registration(
+    state: &AppState,
+    request: &RegisterValidatorRequest,
+) -> Result<NeuronInfo, StatusCode> {
+    let hotkey = &request.registration.hotkey;
+    let max_age_secs = state.config.auth_config.signature_max_age_secs;
+    let now_secs = chrono::Utc::now().timestamp().max(0) as u64;
+    if now_secs.abs_diff(request.timestamp) > max_age_secs || request.nonce.is_empty() {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let payload = request
+        .registration
+        .signing_payload(request.timestamp, &request.nonce);
+    if !verify_hotkey_signature(hotkey, &request.signature, &payload) {
+        tracing::warn!(
+            hotkey = hotkey.as_str(),
+            "Rejected validator registration with invalid signature"
+        );
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let expires_at = request.timestamp.saturating_add(max_age_secs);
+    if !state
+        .seen_request_nonces
+        .insert(hotkey.as_str(), &request.nonce, now_secs, expires_at)
+    {
+        tracing::warn!(
+            hotkey = hotkey.as_str(),
+            "Rejected replayed validator registration"
+        );
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let neuron = crate::routes::metagraph::get_metagraph_neurons()
+        .read()
+        .await
+        .get(hotkey)
+        .cloned();
+    let min_stake = crate::routes::metagraph::get_subnet_params_cache()
+        .read()
+        .await
+        .as_ref()
+        .and_then(|params| params.min_stake);
+    match neuron {
+        Some(neuron) if holds_validator_stake(&neuron, min_stake) => Ok(neuron),
+        _ => {
+            tracing::warn!(
+                hotkey = hotkey.as_str(),
+                "Rejected registration of a hotkey that is not a metagraph validator"
+            );
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+/// Whether `neuron` stakes at least `min_stake`, the minimum stake to hold a
+/// validator permit, when it is known
+fn holds_validator_stake(neuron: &NeuronInfo, min_stake: Option<u64>) -> bool {
+    min_stake.is_none_or(|min_stake| neuron.stake >= min_stake as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platform_api_models::RuntimeType;
+    use platform_api_storage::{MemoryStorageBackend, StorageConfig};
+    use sp_core::{
+        crypto::{Pair, Ss58Codec},
+        sr25519,
+    };
+    use std::sync::Arc;
+
+    fn registration(pair: &sr25519::Pair) -> ValidatorRegistration {
+        ValidatorRegistration {
+            hotkey: pair.public().to_ss58check().parse().unwrap(),
+            runtimes: vec![RuntimeType::Docker],
+            gpu: false,
+            memory_gb: 32,
+            cpu_cores: 8,
+            preferred_challenges: vec![],
+            gpu_count: 0,
+            gpu_type: None,
+            disk_gb: None,
+        }
+    }
+
+    fn signed_request(pair: &sr25519::Pair, nonce: &str) -> RegisterValidatorRequest {
+        let registration = registration(pair);
+        let timestamp = chrono::Utc::now().timestamp() as u64;
+        let signature = hex::encode(pair.sign(&registration.signing_payload(timestamp, nonce)));
+        RegisterValidatorRequest {
+            registration,
+            timestamp,
+            nonce: nonce.to_string(),
+            signature,
+        }
+    }
+
+    #[test]
+    fn test_registration_signature_covers_capabilities() {
+        let pair = sr25519::Pair::from_seed(&[3u8; 32]);
+        let mut registration = ValidatorRegistration {
+            hotkey: pair.public().to_ss58check().parse().unwrap(),
+            runtimes: vec![RuntimeType::Docker, RuntimeType::Sgx],
+            gpu: true,
+            memory_gb: 64,
+            cpu_cores: 16,
+            preferred_challenges: vec![uuid::Uuid::new_v4()],
+            gpu_count: 0,
+            gpu_type: None,
+            disk_gb: None,
+        };
+        let timestamp = 1_700_000_000;
+        let signature = hex::encode(pair.sign(&registration.signing_payload(timestamp, "n1")));
+
+        let verify = |registration: &ValidatorRegistration, timestamp: u64, nonce: &str| {
+            verify_hotkey_signature(
+                &registration.hotkey,
+                &signature,
+                &registration.signing_payload(timestamp, nonce),
+            )
+        };
+        assert!(verify(&registration, timestamp, "n1"));
+        // Neither the timestamp, the nonce nor a capability can be changed
+        assert!(!verify(&registration, timestamp + 1, "n1"));
+        assert!(!verify(&registration, timestamp, "n2"));
+        registration.memory_gb = 128;
+        assert!(!verify(&registration, timestamp, "n1"));
+    }
+
+    #[tokio::test]
+    async fn test_registration_accepted_once_from_metagraph_validators() {
+        let state = AppState::for_tests(Arc::new(
+            MemoryStorageBackend::new(&StorageConfig::default()).unwrap(),
+        ))
+        .unwrap();
+        let pair = sr25519::Pair::from_seed(&[4u8; 32]);
+        let hotkey = registration(&pair).hotkey;
+        crate::routes::metagraph::get_metagraph_neurons()
+            .write()
+            .await
+            .insert(
+                hotkey.clone(),
+                NeuronInfo {
+                    hotkey,
+                    uid: 4,
+                    stake: 1_000.0,
+                },
+            );
+
+        let request = signed_request(&pair, "n1");
+        let neuron = verify_registration(&state, &request).await.unwrap();
+        assert_eq!(neuron.uid, 4);
+        // The same registration replayed within its max age
+        assert_eq!(
+            verify_registration(&state, &request).await.unwrap_err(),
+            StatusCode::UNAUTHORIZED
+        );
+        // Another nonce is a new registration
+        verify_registration(&state, &signed_request(&pair, "n2"))
+            .await
+            .unwrap();
+
+        // Hotkeys outside the metagraph cannot register
+        let outsider = sr25519::Pair::from_seed(&[5u8; 32]);
+        assert_eq!(
+            verify_registration(&state, &signed_request(&outsider, "n1"))
+                .await
+                .unwrap_err(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[test]
+    fn test_validator_stake_checked_against_min_stake() {
+        let neuron = NeuronInfo {
+            hotkey: platform_api_models::Hotkey::from_public_key(&[1; 32]),
+            uid: 1,
+            stake: 1_000.0,
+        };
+        assert!(holds_validator_stake(&neuron, None));
+        assert!(holds_validator_stake(&neuron, Some(1_000)));
+        assert!(!holds_validator_stake(&neuron, Some(1_001)));
+    }
+}
@@ -1,16 +1,20 @@
 use anyhow::Context;
 use base64::{engine::general_purpose::STANDARD as base64_engine, Engine as _};
 use hex;
-use serde_json::json;
-use sha2::{Digest, Sha256};
 use sp_core::{crypto::Ss58Codec, sr25519};
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
+use crate::services::dstack_verifier::record_verification;
 use crate::services::DstackVerifierClient;
 use crate::state::AppState;
 use dstack_types::VmConfig;
-use platform_api_models::{AttestationRequest, AttestationType};
+use platform_api_attestation::with_verification_timeout;
+use platform_api_models::{
+    expected_compose_hash, AttestationRequest, AttestationType, ContentHash, HashAlgorithm,
+    VmComposeConfig,
+};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use super::messages::{AttestationMessage, SecureMessage};
 use super::utils::extract_compose_hash_from_event_log;
@@ -46,12 +50,7 @@ pub async fn verify_secure_message(
     let public_key = sr25519::Public::from_ss58check(&msg.public_key)
         .map_err(|e| anyhow::anyhow!("Invalid public key: {}", e))?;
 
-    // Recreate message to verify
-    let mut message = Vec::new();
-    message.extend_from_slice(msg.message_type.as_bytes());
-    message.extend_from_slice(msg.timestamp.to_string().as_bytes());
-    message.extend_from_slice(msg.nonce.as_bytes());
-    message.extend_from_slice(msg.data.to_string().as_bytes());
+    let message = msg.signing_bytes();
 
     // Decode signature
     let signature_bytes =
@@ -123,11 +122,14 @@ pub async fn verify_validator_attestation(
 
     // Verify attestation with event log
     let event_log = msg.event_log.as_deref();
-    let result = state
-        .attestation
-        .verify_attestation_with_event_log(attest_request, event_log)
-        .await
-        .context("Failed to verify attestation")?;
+    let result = with_verification_timeout(
+        verification_timeout(state),
+        state
+            .attestation
+            .verify_attestation_with_event_log(attest_request, event_log),
+    )
+    .await
+    .context("Failed to verify attestation")?;
 
     if !matches!(
         result.status,
@@ -158,6 +160,11 @@ pub async fn verify_validator_attestation(
     Ok(())
 }
 
+/// Bound on each attestation verification, see `TdxConfig::verification_timeout`
+fn verification_timeout(state: &AppState) -> Duration {
+    Duration::from_secs(state.config.attestation_config.verification_timeout)
+}
+
 /// Verify challenge binding in report data
 pub fn verify_challenge_binding(
     report_data_hex: &str,
@@ -171,11 +178,9 @@ pub fn verify_challenge_binding(
         || (report_data_hex.len() >= 64 && &report_data_hex[..64] == challenge_hash)
 }
 
-/// Compute challenge hash
-pub fn compute_challenge_hash(challenge: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(challenge.as_bytes());
-    hex::encode(hasher.finalize())
+/// Compute challenge hash with `algorithm`, see `TdxConfig::hash_algorithm`
+pub fn compute_challenge_hash(challenge: &str, algorithm: HashAlgorithm) -> String {
+    algorithm.hex_digest(challenge.as_bytes())
 }
 
 /// Verify validator using full TDX verification with dstack-verifier
@@ -184,6 +189,15 @@ pub fn compute_challenge_hash(challenge: &str) -> String {
 /// 2. MRTD/RTMR measurements match expected values
 /// 3. Compose hash matches expected value from DB
 /// 4. Challenge binding (nonce) is correct
+///
+/// The span records the reported compose hash and whether it matched the
+/// expected one; the verifier call is timed and its outcome counted, see
+/// `dstack_verifier::record_verification`.
+#[tracing::instrument(
+    name = "dstack_verifier",
+    skip_all,
+    fields(compose_hash = tracing::field::Empty, compose_hash_match = tracing::field::Empty)
+)]
 async fn verify_validator_with_dstack_verifier(
     state: &AppState,
     msg: &AttestationMessage,
@@ -206,6 +220,8 @@ async fn verify_validator_with_dstack_verifier(
         "Validator reported compose hash: {}",
         validator_compose_hash
     );
+    let span = tracing::Span::current();
+    span.record("compose_hash", validator_compose_hash.as_str());
 
     // Get expected compose config from DB
     let db_compose_config = state
@@ -219,64 +235,28 @@ async fn verify_validator_with_dstack_verifier(
         db_compose_config.vm_type
     );
 
-    // Build provisioning bundle (same logic as config.rs)
-    let mut env_keys: Vec<String> = ["DSTACK_VMM_URL", "HOTKEY_PASSPHRASE", "VALIDATOR_BASE_URL"]
-        .iter()
-        .map(|k| k.to_string())
-        .collect();
-    for key in &db_compose_config.required_env {
-        if !env_keys.iter().any(|existing| existing == key) {
-            env_keys.push(key.clone());
-        }
-    }
-    env_keys.sort();
-    env_keys.dedup();
-
-    // Build app_compose manifest (same structure as deploy.rs)
-    let app_compose = json!({
-        "manifest_version": 2,
-        "name": db_compose_config.vm_type,
-        "runner": "docker-compose",
-        "docker_compose_file": db_compose_config.compose_content,
-        "kms_enabled": true,
-        "gateway_enabled": true,
-        "local_key_provider_enabled": false,
-        "key_provider_id": "",
-        "public_logs": true,
-        "public_sysinfo": true,
-        "public_tcbinfo": true,
-        "allowed_envs": env_keys,
-        "no_instance_id": false,
-        "secure_time": false,
-    });
-
-    // Calculate expected compose hash (same method as deploy.rs)
-    let app_compose_str =
-        serde_json::to_string(&app_compose).context("Failed to serialize app_compose")?;
-
-    info!("📋 PLATFORM-API EXPECTED app_compose (raw JSON):\n{}", app_compose_str);
-    info!("📋 PLATFORM-API env_keys used: {:?}", env_keys);
-
-    // Normalize JSON to ensure consistent key ordering before hashing
-    let normalized_compose = normalize_json_for_hashing(&app_compose_str)
-        .unwrap_or_else(|_| app_compose_str.clone());
-    
-    info!("📋 PLATFORM-API normalized JSON:\n{}", normalized_compose);
+    let hash_algorithm = state.config.attestation_config.hash_algorithm;
+    let expected_compose_hash = expected_validator_compose_hash(&db_compose_config, hash_algorithm);
 
-    let mut hasher = Sha256::new();
-    hasher.update(normalized_compose.as_bytes());
-    let expected_compose_hash = hex::encode(hasher.finalize());
-
-    info!("Expected compose hash from DB: {}", expected_compose_hash);
+    info!(
+        "Expected {} compose hash from DB: {}",
+        hash_algorithm, expected_compose_hash.hex
+    );
 
-    // Compare compose hashes
-    if validator_compose_hash != expected_compose_hash {
-        return Err(anyhow::anyhow!(
-            "Compose hash mismatch: validator reported {}, expected {}",
-            validator_compose_hash,
-            expected_compose_hash
-        ));
-    }
+    // Compare compose hashes; the algorithm of the reported one is told by its length
+    let reported_algorithm =
+        HashAlgorithm::of_hex_digest(&validator_compose_hash).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Validator reported a compose hash of unknown algorithm: {}",
+                validator_compose_hash
+            )
+        })?;
+    let compose_hash_check = expected_compose_hash.verify(&ContentHash {
+        algorithm: reported_algorithm,
+        hex: validator_compose_hash.clone(),
+    });
+    span.record("compose_hash_match", compose_hash_check.is_ok());
+    compose_hash_check.context("Compose hash verification failed")?;
     
     info!("✅ Compose hash verification successful");
 
@@ -342,10 +322,15 @@ async fn verify_validator_with_dstack_verifier(
 
         info!("Calling dstack-verifier for full TDX verification");
         
-        let verification_result = verifier
-            .verify(verification_request)
-            .await
-            .context("Failed to verify TDX quote with dstack-verifier")?;
+        let started = Instant::now();
+        let verification_result = with_verification_timeout(
+            verification_timeout(state),
+            verifier.verify(verification_request),
+        )
+        .await;
+        record_verification(&verification_result, started.elapsed());
+        let verification_result =
+            verification_result.context("Failed to verify TDX quote with dstack-verifier")?;
 
         if !verification_result.is_valid {
             return Err(anyhow::anyhow!(
@@ -379,17 +364,17 @@ async fn verify_validator_with_dstack_verifier(
             Err(_) => hex::decode(quote).context("Failed to decode quote as base64 or hex")?,
         };
 
-        // Calculate expected SHA256 of challenge
-        let mut hasher = Sha256::new();
-        hasher.update(challenge_bytes);
-        let expected_hash = hasher.finalize();
+        // Calculate expected hash of challenge, with the algorithm validators use too
+        let hash_algorithm = state.config.attestation_config.hash_algorithm;
+        let expected_hash = hash_algorithm.digest(challenge_bytes);
 
         // Check if report_data in quote matches challenge (report_data is at offset 568-632)
         if quote_bytes.len() >= 632 {
             let report_data_slice = &quote_bytes[568..632];
-            if report_data_slice[..32] != expected_hash[..] {
+            if report_data_slice[..expected_hash.len()] != expected_hash[..] {
                 return Err(anyhow::anyhow!(
-                    "Challenge verification failed: report_data in quote does not match SHA256(challenge)"
+                    "Challenge verification failed: report_data in quote does not match {}(challenge)",
+                    hash_algorithm
                 ));
             }
             info!("✅ Challenge nonce binding verified");
@@ -401,6 +386,26 @@ async fn verify_validator_with_dstack_verifier(
     Ok(())
 }
 
+/// Compose hash expected from a VM deployed with the compose config `config`
+///
+/// This is the hash of the `app_compose` manifest of the VM (same structure as
+/// deploy.rs), allowed the provisioning variables and those the config
+/// requires, computed by [`expected_compose_hash`] like the builder does for
+/// challenges.
+pub fn expected_validator_compose_hash(
+    config: &VmComposeConfig,
+    algorithm: HashAlgorithm,
+) -> ContentHash {
+    let hash = expected_compose_hash(
+        &config.vm_type,
+        &config.compose_content,
+        &config.required_env,
+        algorithm,
+    );
+    debug!("Expected compose hash of {}: {}", config.vm_type, hash.hex);
+    hash
+}
+
 fn resolve_vm_config_from_msg(
     msg: &AttestationMessage,
     os_image_hash: &str,
@@ -452,34 +457,108 @@ fn build_fallback_vm_config(os_image_hash: &str) -> anyhow::Result<(String, VmCo
     Ok((vm_config, parsed))
 }
 
-/// Normalize JSON by sorting all object keys alphabetically
-/// This ensures consistent hashing regardless of key insertion order
-fn normalize_json_for_hashing(json_str: &str) -> anyhow::Result<String> {
-    let value: serde_json::Value = serde_json::from_str(json_str)
-        .context("Failed to parse JSON for normalization")?;
-    
-    let normalized = sort_json_keys(&value);
-    
-    serde_json::to_string(&normalized)
-        .context("Failed to serialize normalized JSON")
-}
 
-/// Recursively sort all object keys in a JSON value
-fn sort_json_keys(value: &serde_json::Value) -> serde_json::Value {
-    use serde_json::Value;
-    
-    match value {
-        Value::Object(map) => {
-            let mut sorted: std::collections::BTreeMap<String, Value> = std::collections::BTreeMap::new();
-            for (k, v) in map {
-                sorted.insert(k.clone(), sort_json_keys(v));
-            }
-            Value::Object(sorted.into_iter().collect())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platform_api_builder::BuilderService;
+    use sp_core::crypto::Pair;
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn test_signed_message_verifies() {
+        let (pair, _) = sr25519::Pair::generate();
+        let hotkey = pair.public().to_ss58check();
+        let msg = SecureMessage::new("heartbeat", serde_json::json!({ "n": 1 })).sign(&pair);
+        verify_secure_message(&msg, &hotkey).await.unwrap();
+
+        // As the API receives it
+        let received: SecureMessage =
+            serde_json::from_str(&serde_json::to_string(&msg).unwrap()).unwrap();
+        verify_secure_message(&received, &hotkey).await.unwrap();
+
+        let tamperings: [fn(&mut SecureMessage); 4] = [
+            |msg| msg.message_type.push('s'),
+            |msg| msg.timestamp -= 1,
+            |msg| msg.nonce.push('0'),
+            |msg| msg.data = serde_json::json!({ "n": 2 }),
+        ];
+        for tamper in tamperings {
+            let mut tampered = msg.clone();
+            tamper(&mut tampered);
+            assert!(verify_secure_message(&tampered, &hotkey).await.is_err());
         }
-        Value::Array(arr) => {
-            Value::Array(arr.iter().map(|v| sort_json_keys(v)).collect())
+
+        // Signed by another hotkey
+        let (other, _) = sr25519::Pair::generate();
+        let forged = SecureMessage {
+            public_key: hotkey.clone(),
+            ..msg.clone().sign(&other)
+        };
+        assert!(verify_secure_message(&forged, &hotkey).await.is_err());
+        let other_hotkey = msg.clone().sign(&other);
+        assert!(verify_secure_message(&other_hotkey, &hotkey).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reordered_object_payload_verifies() {
+        let (pair, _) = sr25519::Pair::generate();
+        let hotkey = pair.public().to_ss58check();
+        let data = serde_json::json!({
+            "job_id": "job-1",
+            "scores": { "recall": 0.5, "accuracy": 0.9 },
+            "metrics": [{ "step": 1, "loss": 0.25 }],
+        });
+        let msg = SecureMessage::new("job_result", data).sign(&pair);
+        let signed = String::from_utf8(msg.signing_bytes()).unwrap();
+        assert!(signed.ends_with(
+            r#"{"job_id":"job-1","metrics":[{"loss":0.25,"step":1}],"scores":{"accuracy":0.9,"recall":0.5}}"#
+        ));
+
+        // Sent by a client whose JSON keeps keys in another order
+        let wire = format!(
+            r#"{{"seq":0,"public_key":"{}","signature":"{}","nonce":"{}","timestamp":{},"data":{{"scores":{{"recall":0.5,"accuracy":0.9}},"metrics":[{{"step":1,"loss":0.25}}],"job_id":"job-1"}},"message_type":"job_result"}}"#,
+            msg.public_key, msg.signature, msg.nonce, msg.timestamp
+        );
+        let received: SecureMessage = serde_json::from_str(&wire).unwrap();
+        assert_eq!(received.signing_bytes(), msg.signing_bytes());
+        verify_secure_message(&received, &hotkey).await.unwrap();
+    }
+
+    #[test]
+    fn test_builder_hash_matches_verification() {
+        let now = chrono::Utc::now();
+        let config = VmComposeConfig {
+            id: uuid::Uuid::new_v4(),
+            vm_type: "demo-challenge".to_string(),
+            compose_content: "services:\n  agent:\n    image: registry.platform.network/agent\n"
+                .to_string(),
+            description: None,
+            required_env: vec!["API_KEY".to_string(), "MODE".to_string()],
+            os_image_hash: None,
+            vcpu: None,
+            memory_mb: None,
+            disk_gb: None,
+            image_version: None,
+            created_at: now,
+            updated_at: now,
+        };
+        // The challenge's own variables, the provisioning ones are implied
+        let env: BTreeMap<String, String> = [("API_KEY", "secret"), ("MODE", "full")]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Sha384] {
+            assert_eq!(
+                BuilderService::expected_compose_hash(
+                    &config.vm_type,
+                    &config.compose_content,
+                    &env,
+                    algorithm,
+                ),
+                expected_validator_compose_hash(&config, algorithm).hex
+            );
         }
-        _ => value.clone()
     }
 }
-
@@ -14,35 +14,77 @@ use tokio::sync::Mutex;
 use tracing::{debug, error, info, warn};
 use x25519_dalek::{EphemeralSecret, PublicKey};
 
-use crate::state::AppState;
+use crate::message_channel::MessageSender;
+use crate::state::{AppState, ValidatorConnection};
+use platform_api_models::{Hotkey, SessionToken};
 
+use super::message_handler::ConnectionEnd;
 use super::messages::{AttestationMessage, HandshakeMessage, SecureMessage};
 use super::utils::{
     extract_app_id_from_event_log, extract_compose_hash_from_event_log,
     extract_instance_id_from_event_log,
 };
 
+/// Outcome of the attestation phase
+pub enum AuthenticatedSession {
+    /// Fresh attestation, with the identity read from its event log
+    Attested {
+        cipher: ChaCha20Poly1305,
+        app_id: Option<String>,
+        instance_id: Option<String>,
+        compose_hash: Option<String>,
+    },
+    /// Previous session resumed from its token, skipping attestation
+    Resumed {
+        cipher: ChaCha20Poly1305,
+        connection: ValidatorConnection,
+    },
+}
+
 /// Handle unauthenticated WebSocket messages during attestation phase
+///
+/// A `handshake` carrying a `resume_session_token` restores the session the
+/// validator had before disconnecting, if it is still within the resume grace
+/// period. Otherwise the validator must send an `attestation_request`.
 pub async fn handle_unauthenticated_message(
     msg: String,
     sender: &Arc<Mutex<futures_util::stream::SplitSink<WebSocket, axum::extract::ws::Message>>>,
-    hotkey: &str,
+    hotkey: &Hotkey,
     state: &AppState,
-) -> Result<Option<ChaCha20Poly1305>> {
+) -> Result<Option<AuthenticatedSession>> {
     let msg_json: Value = serde_json::from_str(&msg)
         .context("Failed to parse unauthenticated message")?;
 
     if let Some(msg_type) = msg_json.get("type").and_then(|t| t.as_str()) {
         match msg_type {
+            "handshake" => {
+                let handshake: HandshakeMessage = serde_json::from_value(msg_json)
+                    .context("Failed to parse handshake")?;
+
+                if let Some(token) = &handshake.resume_session_token {
+                    return resume_session(&handshake, token, sender, hotkey, state).await;
+                }
+            }
             "attestation_request" => {
                 let attestation: AttestationMessage = serde_json::from_value(msg_json)
                     .context("Failed to parse attestation request")?;
 
-                if is_dev_mode() {
-                    return handle_dev_mode_attestation(attestation, sender, hotkey).await;
+                let event_log = attestation.event_log.clone().unwrap_or_default();
+                let app_id = extract_app_id_from_event_log(&event_log);
+                let instance_id = extract_instance_id_from_event_log(&event_log);
+                let compose_hash = extract_compose_hash_from_event_log(&event_log);
+
+                let cipher = if is_dev_mode() {
+                    handle_dev_mode_attestation(attestation, sender, hotkey).await?
                 } else {
-                    return handle_production_attestation(attestation, sender, hotkey, state).await;
-                }
+                    handle_production_attestation(attestation, sender, hotkey, state).await?
+                };
+                return Ok(cipher.map(|cipher| AuthenticatedSession::Attested {
+                    cipher,
+                    app_id,
+                    instance_id,
+                    compose_hash,
+                }));
             }
             _ => {
                 warn!("Received unexpected message type during attestation: {}", msg_type);
@@ -54,11 +96,67 @@ pub async fn handle_unauthenticated_message(
     Ok(None)
 }
 
+/// Resume the suspended session of `token`
+///
+/// A rejected resumption is not fatal: the validator is told why and can
+/// still authenticate with an `attestation_request`.
+async fn resume_session(
+    handshake: &HandshakeMessage,
+    token: &SessionToken,
+    sender: &Arc<Mutex<futures_util::stream::SplitSink<WebSocket, axum::extract::ws::Message>>>,
+    hotkey: &Hotkey,
+    state: &AppState,
+) -> Result<Option<AuthenticatedSession>> {
+    let (response, resumed) = match state
+        .resume_validator_session(hotkey, token, handshake.instance_id.as_deref())
+        .await
+    {
+        Ok(session) => {
+            info!(
+                validator_hotkey = %hotkey,
+                instance_id = ?session.connection.instance_id,
+                subscriptions = session.connection.subscriptions.len(),
+                assigned_jobs = session.connection.assigned_jobs.len(),
+                disconnected_at = %session.disconnected_at,
+                "Validator resumed its session without attestation"
+            );
+            (
+                serde_json::json!({ "type": "resume_response", "status": "success" }),
+                Some(AuthenticatedSession::Resumed {
+                    cipher: session.cipher,
+                    connection: session.connection,
+                }),
+            )
+        }
+        Err(e) => {
+            warn!("Rejected session resumption for {}: {}", hotkey, e);
+            (
+                serde_json::json!({
+                    "type": "resume_response",
+                    "status": "rejected",
+                    "reason": e.to_string(),
+                }),
+                None,
+            )
+        }
+    };
+
+    {
+        let mut sender = sender.lock().await;
+        sender
+            .send(axum::extract::ws::Message::Text(response.to_string()))
+            .await
+            .context("Failed to send resume response")?;
+    }
+
+    Ok(resumed)
+}
+
 /// Handle development mode attestation (simplified)
 async fn handle_dev_mode_attestation(
     attestation: AttestationMessage,
     sender: &Arc<Mutex<futures_util::stream::SplitSink<WebSocket, axum::extract::ws::Message>>>,
-    hotkey: &str,
+    hotkey: &Hotkey,
 ) -> Result<Option<ChaCha20Poly1305>> {
     info!("DEV MODE: Skipping attestation for validator: {}", hotkey);
 
@@ -91,7 +189,7 @@ async fn handle_dev_mode_attestation(
 async fn handle_production_attestation(
     attestation: AttestationMessage,
     sender: &Arc<Mutex<futures_util::stream::SplitSink<WebSocket, axum::extract::ws::Message>>>,
-    hotkey: &str,
+    hotkey: &Hotkey,
     state: &AppState,
 ) -> Result<Option<ChaCha20Poly1305>> {
     info!("Starting production attestation for validator: {}", hotkey);
@@ -156,36 +254,87 @@ async fn handle_production_attestation(
 }
 
 /// Complete authentication process after successful attestation
+///
+/// When the connection is lost it is suspended rather than dropped, so that
+/// the validator can resume it with the session token sent here. Closing the
+/// connection ends the session and revokes its token.
 pub async fn complete_authentication(
-    hotkey: String,
-    cipher: ChaCha20Poly1305,
-    sender: futures_util::stream::SplitSink<WebSocket, axum::extract::ws::Message>,
+    hotkey: Hotkey,
+    session: AuthenticatedSession,
+    message_sender: MessageSender,
     receiver: futures_util::stream::SplitStream<WebSocket>,
     state: AppState,
 ) -> Result<()> {
     info!("Authentication completed for validator: {}", hotkey);
 
-    // Register validator connection
-    let connection = crate::state::ValidatorConnection {
-        hotkey: hotkey.clone(),
-        sender: Arc::new(Mutex::new(sender)),
-        last_heartbeat: std::time::Instant::now(),
+    let now = chrono::Utc::now();
+    let (cipher, connection) = match session {
+        AuthenticatedSession::Attested {
+            cipher,
+            app_id,
+            instance_id,
+            compose_hash,
+        } => (
+            cipher,
+            ValidatorConnection {
+                validator_hotkey: hotkey.clone(),
+                app_id,
+                instance_id,
+                compose_hash,
+                connected_at: now,
+                session_token: SessionToken::generate(),
+                last_ping: now,
+                message_sender: Some(message_sender.clone()),
+                subscriptions: Vec::new(),
+                assigned_jobs: Vec::new(),
+            },
+        ),
+        AuthenticatedSession::Resumed { cipher, connection } => (
+            cipher,
+            ValidatorConnection {
+                last_ping: now,
+                message_sender: Some(message_sender.clone()),
+                ..connection
+            },
+        ),
     };
 
-    state
-        .register_validator_connection(hotkey.clone(), connection)
+    let session_token = connection.session_token.clone();
+    let session_msg = serde_json::json!({
+        "type": "session",
+        "session_token": session_token,
+        "resume_grace_secs": state.session_resume_grace.num_seconds(),
+    });
+    if let Err(e) = state.add_validator_connection(connection).await {
+        warn!("Refused connection of {}: {}", hotkey, e);
+        return Err(e);
+    }
+    message_sender
+        .send(session_msg.to_string())
         .await
-        .context("Failed to register validator connection")?;
+        .context("Failed to send session token")?;
 
     // Start authenticated message handling
-    super::message_handler::handle_authenticated_messages(
-        hotkey,
+    let result = super::message_handler::handle_authenticated_messages(
+        hotkey.to_string(),
+        session_token.clone(),
         receiver,
-        cipher,
-        state,
-    ).await?;
-
-    Ok(())
+        cipher.clone(),
+        state.clone(),
+    )
+    .await;
+
+    match result {
+        Ok(ConnectionEnd::Closed) => state.end_validator_session(&hotkey, &session_token).await,
+        // Revoking the session already removed the connection
+        Ok(ConnectionEnd::Revoked) => {}
+        Ok(ConnectionEnd::Lost) | Err(_) => {
+            state
+                .suspend_validator_connection(&hotkey, &session_token, cipher)
+                .await
+        }
+    }
+    result.map(|_| ())
 }
 
 /// Verify attestation data against validator records
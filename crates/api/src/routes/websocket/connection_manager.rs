@@ -3,18 +3,45 @@
 use axum::extract::ws::WebSocket;
 use futures_util::{SinkExt, StreamExt};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
-use tracing::{debug, error, info, warn};
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn, Instrument};
 
+use crate::message_channel::message_channel;
+use crate::middleware::request_id::scope_request_id;
 use crate::state::AppState;
+use platform_api_models::Hotkey;
 
-use super::authentication::{handle_unauthenticated_message, complete_authentication};
+use super::authentication::{
+    complete_authentication, handle_unauthenticated_message, AuthenticatedSession,
+};
 use super::utils::extract_compose_hash_from_event_log;
 
 /// Main WebSocket connection handler
+///
+/// Each connection gets a connection id that is recorded on a span wrapping the
+/// whole connection and forwarded as `X-Request-Id` on outbound calls.
 pub async fn handle_validator_connection(
     socket: WebSocket,
-    hotkey: String,
+    hotkey: Hotkey,
+    state: AppState,
+) -> Result<(), anyhow::Error> {
+    let connection_id = uuid::Uuid::new_v4().to_string();
+    let span = tracing::info_span!(
+        "ws_connection",
+        connection_id = %connection_id,
+        hotkey = %hotkey,
+    );
+
+    scope_request_id(
+        connection_id,
+        run_validator_connection(socket, hotkey, state).instrument(span),
+    )
+    .await
+}
+
+async fn run_validator_connection(
+    socket: WebSocket,
+    hotkey: Hotkey,
     state: AppState,
 ) -> Result<(), anyhow::Error> {
     info!("Handling WebSocket connection for validator: {}", hotkey);
@@ -23,36 +50,30 @@ pub async fn handle_validator_connection(
     let sender = Arc::new(Mutex::new(sender));
 
     // Create channel for sending messages to this validator
-    let (tx, mut rx) = mpsc::channel::<String>(100);
-    let tx_clone = Arc::new(tx);
+    let (tx, mut rx) =
+        message_channel("validator", hotkey.as_str(), state.config.validator_channel);
 
     // Spawn task to forward messages from channel to WebSocket
     let sender_for_task = sender.clone();
-    tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            let mut sender = sender_for_task.lock().await;
-            if let Err(e) = sender.send(axum::extract::ws::Message::Text(msg)).await {
-                error!("Failed to send message to WebSocket: {}", e);
-                break;
+    tokio::spawn(
+        async move {
+            while let Some(msg) = rx.recv().await {
+                let mut sender = sender_for_task.lock().await;
+                if let Err(e) = sender.send(axum::extract::ws::Message::Text(msg)).await {
+                    error!("Failed to send message to WebSocket: {}", e);
+                    break;
+                }
             }
         }
-    });
+        .in_current_span(),
+    );
 
-    // Handle attestation phase
-    let cipher = handle_attestation_phase(&mut receiver, &sender, &hotkey, &state).await?;
+    // Handle attestation phase, or resumption of a previous session
+    let session = handle_attestation_phase(&mut receiver, &sender, &hotkey, &state).await?;
 
     // Complete authentication and switch to authenticated handling
-    if let Some(cipher) = cipher {
-        // Reconstruct WebSocket from parts for authenticated phase
-        // Note: This is a simplified approach - in practice you might want to
-        // keep the original split and pass the receiver directly
-        complete_authentication(
-            hotkey,
-            cipher,
-            sender.lock().await.clone(),
-            receiver,
-            state,
-        ).await?;
+    if let Some(session) = session {
+        complete_authentication(hotkey, session, tx, receiver, state).await?;
     } else {
         warn!("Authentication failed for validator: {}", hotkey);
     }
@@ -64,14 +85,15 @@ pub async fn handle_validator_connection(
 async fn handle_attestation_phase(
     receiver: &mut futures_util::stream::SplitStream<WebSocket>,
     sender: &Arc<Mutex<futures_util::stream::SplitSink<WebSocket, axum::extract::ws::Message>>>,
-    hotkey: &str,
+    hotkey: &Hotkey,
     state: &AppState,
-) -> Result<Option<chacha20poly1305::ChaCha20Poly1305>, anyhow::Error> {
+) -> Result<Option<AuthenticatedSession>, anyhow::Error> {
     info!("Starting attestation phase for validator: {}", hotkey);
 
     // Set timeout for attestation phase
     let timeout = tokio::time::Duration::from_secs(30);
     let attestation_start = std::time::Instant::now();
+    let mut message_seq: u64 = 0;
 
     loop {
         // Check for timeout
@@ -85,10 +107,15 @@ async fn handle_attestation_phase(
             msg = receiver.next() => {
                 match msg {
                     Some(Ok(axum::extract::ws::Message::Text(text))) => {
-                        match handle_unauthenticated_message(text, sender, hotkey, state).await {
-                            Ok(Some(cipher)) => {
+                        message_seq += 1;
+                        let message_span = tracing::debug_span!("ws_message", seq = message_seq);
+                        match handle_unauthenticated_message(text, sender, hotkey, state)
+                            .instrument(message_span)
+                            .await
+                        {
+                            Ok(Some(session)) => {
                                 info!("✅ Attestation completed for validator: {}", hotkey);
-                                return Ok(Some(cipher));
+                                return Ok(Some(session));
                             }
                             Ok(None) => {
                                 // Continue waiting for attestation
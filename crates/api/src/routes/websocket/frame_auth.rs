@@ -0,0 +1,338 @@
+//! Connection-level authentication of validator frames
+//!
+//! The sr25519 signature of a validator is verified once, on the first frame
+//! of its connection. The API then hands the validator a random frame key over
+//! the encrypted channel, and every later frame only carries an HMAC-SHA256
+//! under that key and a strictly increasing sequence number.
+//!
+//! While validators move to MAC'd frames, later frames still signed with the
+//! hotkey are accepted too, unless `WS_ACCEPT_SIGNED_FRAMES=false`. They are
+//! numbered and sequence-checked the same way, their signature covering the
+//! sequence number.
+
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+
+use super::messages::SecureMessage;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Length of the per-connection frame key, in bytes
+pub const FRAME_KEY_BYTES: usize = 32;
+
+/// Length of an sr25519 signature, in bytes
+const SR25519_SIGNATURE_BYTES: usize = 64;
+
+/// Whether frames after the first may still be signed with the hotkey
+/// instead of MAC'd, from `WS_ACCEPT_SIGNED_FRAMES` (default true)
+pub fn accept_signed_frames_from_env() -> bool {
+    match std::env::var("WS_ACCEPT_SIGNED_FRAMES") {
+        Ok(value) => match value.to_lowercase().as_str() {
+            "true" | "1" => true,
+            "false" | "0" => false,
+            _ => {
+                tracing::warn!(
+                    "Ignoring WS_ACCEPT_SIGNED_FRAMES={}: not a boolean, accepting signed frames",
+                    value
+                );
+                true
+            }
+        },
+        Err(_) => true,
+    }
+}
+
+/// Authentication state of one validator connection
+pub struct ConnectionAuth {
+    hotkey: String,
+    frame_key: Option<[u8; FRAME_KEY_BYTES]>,
+    last_seq: u64,
+    accept_signed_frames: bool,
+}
+
+impl ConnectionAuth {
+    pub fn new(hotkey: impl Into<String>) -> Self {
+        Self {
+            hotkey: hotkey.into(),
+            frame_key: None,
+            last_seq: 0,
+            accept_signed_frames: false,
+        }
+    }
+
+    /// Also accept frames after the first signed with the hotkey, for
+    /// validators that do not MAC their frames yet
+    pub fn with_signed_frames(mut self, accept: bool) -> Self {
+        self.accept_signed_frames = accept;
+        self
+    }
+
+    /// Whether the hotkey signature has been verified on this connection
+    pub fn is_established(&self) -> bool {
+        self.frame_key.is_some()
+    }
+
+    /// Verify the signed first frame and bind the connection to its hotkey
+    ///
+    /// Returns the frame key the validator must MAC its later frames with.
+    pub async fn establish(&mut self, msg: &SecureMessage) -> Result<[u8; FRAME_KEY_BYTES]> {
+        super::auth::verify_secure_message(msg, &self.hotkey).await?;
+
+        let mut frame_key = [0u8; FRAME_KEY_BYTES];
+        rand::rngs::OsRng.fill_bytes(&mut frame_key);
+        self.bind(frame_key, msg.seq);
+        Ok(frame_key)
+    }
+
+    fn bind(&mut self, frame_key: [u8; FRAME_KEY_BYTES], seq: u64) {
+        self.frame_key = Some(frame_key);
+        self.last_seq = seq;
+    }
+
+    /// Check a frame after the first one, by its MAC or, when signed frames
+    /// are accepted, by an sr25519 signature of the hotkey
+    ///
+    /// Signed frames are told apart by the length of their signature. Their
+    /// sequence number is signed too, and must increase like that of MAC'd
+    /// frames.
+    pub async fn verify(&mut self, msg: &SecureMessage) -> Result<()> {
+        let signed = msg.signature.len() == 2 * SR25519_SIGNATURE_BYTES;
+        if !(self.accept_signed_frames && signed) {
+            return self.verify_frame(msg);
+        }
+
+        if !self.is_established() {
+            return Err(anyhow!("Connection is not authenticated yet"));
+        }
+        self.check_seq(msg.seq)?;
+        super::auth::verify_secure_message(msg, &self.hotkey).await?;
+
+        self.last_seq = msg.seq;
+        Ok(())
+    }
+
+    /// Check the MAC and sequence number of a frame after the first one
+    pub fn verify_frame(&mut self, msg: &SecureMessage) -> Result<()> {
+        let frame_key = self
+            .frame_key
+            .ok_or_else(|| anyhow!("Connection is not authenticated yet"))?;
+
+        if msg.public_key != self.hotkey {
+            return Err(anyhow!(
+                "Public key mismatch: expected {}, got {}",
+                self.hotkey,
+                msg.public_key
+            ));
+        }
+        self.check_seq(msg.seq)?;
+
+        let tag =
+            hex::decode(&msg.signature).map_err(|e| anyhow!("Invalid frame MAC hex: {}", e))?;
+        frame_mac(&frame_key, msg)
+            .verify_slice(&tag)
+            .map_err(|_| anyhow!("Frame MAC verification failed"))?;
+
+        self.last_seq = msg.seq;
+        Ok(())
+    }
+
+    /// Refuse a sequence number that does not follow the last accepted one,
+    /// whether that frame was MAC'd or signed
+    fn check_seq(&self, seq: u64) -> Result<()> {
+        if seq <= self.last_seq {
+            return Err(anyhow!(
+                "Replayed or reordered frame: sequence {} after {}",
+                seq,
+                self.last_seq
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// HMAC-SHA256 over the signed fields of a frame, sequence number included,
+/// see [`SecureMessage::signing_bytes`]
+fn frame_mac(frame_key: &[u8; FRAME_KEY_BYTES], msg: &SecureMessage) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(frame_key).expect("HMAC accepts keys of any length");
+    mac.update(&msg.signing_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::{crypto::Ss58Codec, sr25519, Pair};
+
+    const HOTKEY: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    fn frame(frame_key: &[u8; FRAME_KEY_BYTES], seq: u64) -> SecureMessage {
+        let mut msg = SecureMessage {
+            message_type: "heartbeat".to_string(),
+            data: serde_json::json!({ "n": seq }),
+            timestamp: 1_700_000_000,
+            nonce: format!("nonce-{}", seq),
+            signature: String::new(),
+            public_key: HOTKEY.to_string(),
+            seq,
+        };
+        msg.signature = hex::encode(frame_mac(frame_key, &msg).finalize().into_bytes());
+        msg
+    }
+
+    fn established(frame_key: [u8; FRAME_KEY_BYTES]) -> ConnectionAuth {
+        let mut auth = ConnectionAuth::new(HOTKEY);
+        auth.bind(frame_key, 1);
+        auth
+    }
+
+    #[test]
+    fn test_frames_verified_by_mac_and_sequence() {
+        let key = [7u8; FRAME_KEY_BYTES];
+        let mut auth = established(key);
+
+        auth.verify_frame(&frame(&key, 2)).unwrap();
+        auth.verify_frame(&frame(&key, 5)).unwrap();
+        // Replays and frames older than the last one are refused
+        assert!(auth.verify_frame(&frame(&key, 5)).is_err());
+        assert!(auth.verify_frame(&frame(&key, 3)).is_err());
+        auth.verify_frame(&frame(&key, 6)).unwrap();
+    }
+
+    #[test]
+    fn test_forged_frames_rejected() {
+        let key = [7u8; FRAME_KEY_BYTES];
+        let mut auth = established(key);
+
+        assert!(auth
+            .verify_frame(&frame(&[8u8; FRAME_KEY_BYTES], 2))
+            .is_err());
+
+        let mut tampered = frame(&key, 2);
+        tampered.data = serde_json::json!({ "n": 3 });
+        assert!(auth.verify_frame(&tampered).is_err());
+
+        let mut other_hotkey = frame(&key, 2);
+        other_hotkey.public_key = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty".to_string();
+        assert!(auth.verify_frame(&other_hotkey).is_err());
+
+        // A rejected frame does not advance the sequence
+        auth.verify_frame(&frame(&key, 2)).unwrap();
+    }
+
+    fn signed_frame(pair: &sr25519::Pair, seq: u64) -> SecureMessage {
+        let mut msg = SecureMessage {
+            message_type: "heartbeat".to_string(),
+            data: serde_json::json!({ "n": seq }),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            nonce: format!("nonce-{}", seq),
+            signature: String::new(),
+            public_key: pair.public().to_ss58check(),
+            seq,
+        };
+        msg.signature = hex::encode(pair.sign(&msg.signing_bytes()));
+        msg
+    }
+
+    /// MAC'd frame of the hotkey of `pair`
+    fn frame_of(
+        frame_key: &[u8; FRAME_KEY_BYTES],
+        pair: &sr25519::Pair,
+        seq: u64,
+    ) -> SecureMessage {
+        SecureMessage {
+            public_key: pair.public().to_ss58check(),
+            ..frame(frame_key, seq)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_frames_accepted_during_transition() {
+        let pair = sr25519::Pair::from_seed(&[5u8; 32]);
+        let key = [7u8; FRAME_KEY_BYTES];
+        let mut auth = ConnectionAuth::new(pair.public().to_ss58check()).with_signed_frames(true);
+        auth.bind(key, 1);
+
+        // Signed frames of validators not MAC'ing yet
+        auth.verify(&signed_frame(&pair, 2)).await.unwrap();
+        // mixed with MAC'd ones
+        auth.verify(&frame_of(&key, &pair, 3)).await.unwrap();
+        // Signed frames do not go back
+        assert!(auth.verify(&signed_frame(&pair, 3)).await.is_err());
+        auth.verify(&signed_frame(&pair, 4)).await.unwrap();
+
+        let other = sr25519::Pair::from_seed(&[6u8; 32]);
+        let mut forged = signed_frame(&other, 5);
+        forged.public_key = pair.public().to_ss58check();
+        assert!(auth.verify(&forged).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replayed_signed_frames_rejected() {
+        let pair = sr25519::Pair::from_seed(&[5u8; 32]);
+        let mut auth = ConnectionAuth::new(pair.public().to_ss58check()).with_signed_frames(true);
+        auth.bind([7u8; FRAME_KEY_BYTES], 1);
+
+        let captured = signed_frame(&pair, 2);
+        auth.verify(&captured).await.unwrap();
+
+        // Replayed without a sequence number
+        let mut unnumbered = captured.clone();
+        unnumbered.seq = 0;
+        assert!(auth.verify(&unnumbered).await.is_err());
+        // or with one rewritten past the last accepted
+        let mut renumbered = captured.clone();
+        renumbered.seq = 3;
+        assert!(auth.verify(&renumbered).await.is_err());
+        assert!(auth.verify(&captured).await.is_err());
+
+        // Neither advanced the sequence
+        auth.verify(&signed_frame(&pair, 3)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_duplicated_and_reordered_signed_frames_rejected() {
+        let pair = sr25519::Pair::from_seed(&[5u8; 32]);
+        let key = [7u8; FRAME_KEY_BYTES];
+        let mut auth = ConnectionAuth::new(pair.public().to_ss58check()).with_signed_frames(true);
+        auth.bind(key, 1);
+
+        let first = signed_frame(&pair, 2);
+        let second = signed_frame(&pair, 3);
+        let third = signed_frame(&pair, 4);
+
+        // Reordered: the third arrives first, and the second after it
+        auth.verify(&third).await.unwrap();
+        assert!(auth.verify(&second).await.is_err());
+        assert!(auth.verify(&first).await.is_err());
+        // Duplicated
+        assert!(auth.verify(&third).await.is_err());
+
+        // MAC'd frames share the sequence
+        assert!(auth.verify(&frame_of(&key, &pair, 4)).await.is_err());
+        auth.verify(&frame_of(&key, &pair, 5)).await.unwrap();
+        assert!(auth.verify(&signed_frame(&pair, 5)).await.is_err());
+        auth.verify(&signed_frame(&pair, 6)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_signed_frames_refused_after_transition() {
+        let pair = sr25519::Pair::from_seed(&[5u8; 32]);
+        let mut auth = ConnectionAuth::new(pair.public().to_ss58check()).with_signed_frames(false);
+        auth.bind([7u8; FRAME_KEY_BYTES], 1);
+
+        assert!(auth.verify(&signed_frame(&pair, 2)).await.is_err());
+    }
+
+    #[test]
+    fn test_frames_rejected_before_establishment() {
+        let key = [7u8; FRAME_KEY_BYTES];
+        let mut auth = ConnectionAuth::new(HOTKEY);
+        assert!(!auth.is_established());
+        assert!(auth.verify_frame(&frame(&key, 1)).is_err());
+    }
+}
@@ -5,6 +5,7 @@ use axum::{
     extract::{ws::WebSocketUpgrade, Path, State},
     response::Response,
 };
+use platform_api_models::Hotkey;
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
@@ -23,14 +24,17 @@ pub async fn validator_websocket(
     info!("Validator WebSocket connection request from: {}", hotkey);
 
     // Validate hotkey format
-    if let Err(e) = validate_hotkey(&hotkey) {
-        error!("Invalid hotkey format: {} - {}", hotkey, e);
-        return axum::response::Json(serde_json::json!({
-            "error": "Invalid hotkey format",
-            "message": e.to_string()
-        }))
-        .into_response();
-    }
+    let hotkey: Hotkey = match hotkey.parse() {
+        Ok(hotkey) => hotkey,
+        Err(e) => {
+            error!("Invalid hotkey format: {} - {}", hotkey, e);
+            return axum::response::Json(serde_json::json!({
+                "error": "Invalid hotkey format",
+                "message": e.to_string()
+            }))
+            .into_response();
+        }
+    };
 
     // Check if validator is registered and active
     if let Err(e) = validate_validator(&hotkey, &state).await {
@@ -51,24 +55,6 @@ pub async fn validator_websocket(
         })
 }
 
-/// Validate hotkey format
-fn validate_hotkey(hotkey: &str) -> Result<(), anyhow::Error> {
-    if hotkey.is_empty() {
-        return Err(anyhow::anyhow!("Hotkey cannot be empty"));
-    }
-
-    if hotkey.len() > 64 {
-        return Err(anyhow::anyhow!("Hotkey too long (max 64 characters)"));
-    }
-
-    // Basic format validation - adjust according to your hotkey format
-    if !hotkey.chars().all(|c| c.is_alphanumeric() || c == '_') {
-        return Err(anyhow::anyhow!("Hotkey contains invalid characters"));
-    }
-
-    Ok(())
-}
-
 /// Validate that validator exists and is in good standing
 async fn validate_validator(hotkey: &str, state: &AppState) -> Result<(), anyhow::Error> {
     // Check if validator exists
@@ -177,6 +163,9 @@ pub async fn update_websocket_config(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::extract::ws::Message;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
 
     #[test]
     fn test_validate_hotkey() {
@@ -190,6 +179,53 @@ mod tests {
         assert!(validate_hotkey("invalid-hotkey!").is_err());
     }
 
+    /// Echo server upgrading like `validator_websocket`
+    async fn echo(ws: WebSocketUpgrade) -> Response {
+        ws.protocols(["platform-api-v1"])
+            .max_frame_size(1024 * 1024)
+            .on_upgrade(|mut socket| async move {
+                while let Some(Ok(Message::Text(text))) = socket.recv().await {
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+            })
+    }
+
+    #[tokio::test]
+    async fn test_permessage_deflate_is_not_negotiated() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = axum::Router::new().route("/ws", axum::routing::get(echo));
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        // Whether or not the client offers the extension, it gets a handshake
+        // response without it and sends uncompressed frames, even for large
+        // messages
+        for offer in [Some("permessage-deflate; client_max_window_bits"), None] {
+            let mut request = format!("ws://{}/ws", addr).into_client_request().unwrap();
+            let headers = request.headers_mut();
+            if let Some(offer) = offer {
+                headers.insert("Sec-WebSocket-Extensions", offer.parse().unwrap());
+            }
+            headers.insert("Sec-WebSocket-Protocol", "platform-api-v1".parse().unwrap());
+            let (mut socket, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+            assert!(response.headers().get("Sec-WebSocket-Extensions").is_none());
+            let message = serde_json::json!({
+                "type": "job_execute",
+                "payload": "x".repeat(512 * 1024),
+            })
+            .to_string();
+            socket
+                .send(tungstenite::Message::Text(message.clone()))
+                .await
+                .unwrap();
+            let echoed = socket.next().await.unwrap().unwrap();
+            assert_eq!(echoed.into_text().unwrap(), message);
+        }
+    }
+
     #[test]
     fn test_is_dev_mode() {
         // Test default behavior
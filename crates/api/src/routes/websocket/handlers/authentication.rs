@@ -39,7 +39,8 @@ pub async fn handle_unauthenticated_message(
         if handshake.msg_type == "handshake" && !*awaiting_attestation {
             info!("Validator {} completed handshake", hotkey);
             // Generate challenge
-            let challenge = compute_challenge_hash(hotkey);
+            let challenge =
+                compute_challenge_hash(hotkey, state.config.attestation_config.hash_algorithm);
             *expected_challenge = challenge.clone();
 
             let response = serde_json::json!({
@@ -7,38 +7,65 @@ use futures_util::{SinkExt, StreamExt};
 use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 
 use crate::state::AppState;
+use platform_api_models::SessionToken;
 
+use super::frame_auth::{accept_signed_frames_from_env, ConnectionAuth};
 use super::messages::SecureMessage;
 use super::encryption::{decrypt_message, encrypt_message};
 
+/// How the authenticated message handling of a connection ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionEnd {
+    /// The validator closed the connection, ending its session
+    Closed,
+    /// The connection was lost; the validator can resume its session
+    Lost,
+    /// The session was revoked, see `AppState::revoke_validator_sessions`
+    Revoked,
+}
+
 /// Handle authenticated WebSocket messages
 pub async fn handle_authenticated_messages(
     hotkey: String,
+    session_token: SessionToken,
     mut receiver: futures_util::stream::SplitStream<WebSocket>,
     cipher: ChaCha20Poly1305,
     state: AppState,
-) -> Result<()> {
+) -> Result<ConnectionEnd> {
     info!("Starting authenticated message handling for: {}", hotkey);
+    let mut message_seq: u64 = 0;
+    let mut connection_auth =
+        ConnectionAuth::new(hotkey.clone()).with_signed_frames(accept_signed_frames_from_env());
 
     loop {
         match receiver.next().await {
             Some(Ok(axum::extract::ws::Message::Text(text))) => {
+                if state.is_session_revoked(&session_token).await {
+                    warn!("Refusing messages of {}: its session was revoked", hotkey);
+                    return Ok(ConnectionEnd::Revoked);
+                }
+                message_seq += 1;
+                let message_span = tracing::debug_span!("ws_message", seq = message_seq);
                 if let Err(e) = handle_authenticated_message(
                     &text,
+                    &mut connection_auth,
                     &cipher,
                     &hotkey,
                     &state,
-                ).await {
+                )
+                .instrument(message_span)
+                .await
+                {
                     error!("Error handling authenticated message: {}", e);
                     // Continue processing other messages even if one fails
                 }
             }
             Some(Ok(axum::extract::ws::Message::Close(close_frame))) => {
                 info!("WebSocket closed for {}: {:?}", hotkey, close_frame);
-                break;
+                return Ok(ConnectionEnd::Closed);
             }
             Some(Ok(_)) => {
                 debug!("Ignoring non-text message from: {}", hotkey);
@@ -55,24 +82,43 @@ pub async fn handle_authenticated_messages(
         }
     }
 
-    // Unregister validator connection
-    state.unregister_validator_connection(&hotkey).await;
+    // The caller suspends the validator connection so that it can be resumed
     info!("Authenticated message handling ended for: {}", hotkey);
 
-    Ok(())
+    Ok(ConnectionEnd::Lost)
 }
 
 /// Handle individual authenticated message
+///
+/// Only the first frame of a connection is checked against the hotkey's
+/// sr25519 signature; the frames after it are checked by their MAC and
+/// sequence number, or by their signature during the transition to MAC'd
+/// frames.
 async fn handle_authenticated_message(
     text: &str,
+    connection_auth: &mut ConnectionAuth,
     cipher: &ChaCha20Poly1305,
     hotkey: &str,
     state: &AppState,
 ) -> Result<()> {
-    // Decrypt message
     let secure_msg: SecureMessage = serde_json::from_str(text)
         .context("Failed to parse secure message")?;
 
+    if connection_auth.is_established() {
+        connection_auth
+            .verify(&secure_msg)
+            .await
+            .context("Frame authentication failed")?;
+    } else {
+        let frame_key = connection_auth
+            .establish(&secure_msg)
+            .await
+            .context("Connection authentication failed")?;
+        send_frame_key(hotkey, &frame_key, secure_msg.seq, cipher, state).await?;
+    }
+
+    // Decrypt message
+
     let decrypted = decrypt_message(&secure_msg, cipher)
         .context("Failed to decrypt authenticated message")?;
 
@@ -85,31 +131,102 @@ async fn handle_authenticated_message(
 
     // Route message to appropriate handler
     if let Some(msg_type) = msg_json.get("type").and_then(|t| t.as_str()) {
-        match msg_type {
-            "challenge_status" => {
-                handle_challenge_status(hotkey, &msg_json, state).await;
-            }
-            "orm_query" => {
-                handle_orm_query(hotkey, &msg_json, cipher, state).await?;
-            }
-            "orm_permissions" => {
-                handle_orm_permissions_msg(hotkey, &msg_json, cipher, state).await?;
-            }
-            "job_result" => {
-                handle_job_result(hotkey, &msg_json, state).await?;
-            }
-            "heartbeat" => {
-                handle_heartbeat(hotkey, state).await;
-            }
-            _ => {
-                warn!("Unknown authenticated message type from {}: {}", hotkey, msg_type);
-            }
+        if let Some(response) = dispatch_message(msg_type, hotkey, &msg_json, state).await? {
+            send_response(hotkey, &response, cipher, state).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Route a decrypted message to its handler, returning the response to send
+/// back to the validator, if any
+async fn dispatch_message(
+    msg_type: &str,
+    hotkey: &str,
+    msg_json: &Value,
+    state: &AppState,
+) -> Result<Option<Value>> {
+    let response = match msg_type {
+        "challenge_status" => {
+            handle_challenge_status(hotkey, msg_json, state).await;
+            None
+        }
+        "orm_query" => Some(handle_orm_query(hotkey, msg_json, state).await),
+        "orm_permissions" => Some(handle_orm_permissions_msg(hotkey, msg_json, state).await),
+        "orm_transaction" => Some(handle_orm_transaction(hotkey, msg_json, state).await),
+        "orm_bulk_insert" => Some(handle_orm_bulk_insert(hotkey, msg_json, state).await),
+        "job_result" => {
+            handle_job_result(hotkey, msg_json, state).await?;
+            None
+        }
+        "heartbeat" => {
+            handle_heartbeat(hotkey, state).await;
+            None
+        }
+        _ => {
+            warn!(
+                "Unknown authenticated message type from {}: {}",
+                hotkey, msg_type
+            );
+            None
+        }
+    };
+
+    Ok(response)
+}
+
+/// Send a response to the validator over its connection
+async fn send_response(
+    hotkey: &str,
+    response: &Value,
+    cipher: &ChaCha20Poly1305,
+    state: &AppState,
+) -> Result<()> {
+    if let Some(connection) = state.get_validator_connection(hotkey).await {
+        let encrypted_response = encrypt_message(response, cipher)?;
+
+        if let Err(e) = connection.send_message(&encrypted_response).await {
+            error!(
+                "Failed to send {} to {}: {}",
+                response.get("type").unwrap_or(&Value::Null),
+                hotkey,
+                e
+            );
         }
     }
 
     Ok(())
 }
 
+/// Hand the validator the key to MAC its next frames with
+async fn send_frame_key(
+    hotkey: &str,
+    frame_key: &[u8],
+    seq: u64,
+    cipher: &ChaCha20Poly1305,
+    state: &AppState,
+) -> Result<()> {
+    let connection = state
+        .get_validator_connection(hotkey)
+        .await
+        .ok_or_else(|| anyhow!("No connection for validator {}", hotkey))?;
+
+    let message = serde_json::json!({
+        "type": "frame_auth",
+        "frame_key": hex::encode(frame_key),
+        "seq": seq,
+    });
+    let encrypted = encrypt_message(&message, cipher)?;
+    connection
+        .send_message(&encrypted)
+        .await
+        .context("Failed to send frame key")?;
+
+    debug!("Connection of {} authenticated, frames are now checked by MAC", hotkey);
+    Ok(())
+}
+
 /// Handle challenge status updates
 async fn handle_challenge_status(hotkey: &str, msg_json: &Value, state: &AppState) {
     debug!("Handling challenge status from {}: {:?}", hotkey, msg_json);
@@ -120,12 +237,7 @@ async fn handle_challenge_status(hotkey: &str, msg_json: &Value, state: &AppStat
 }
 
 /// Handle ORM query requests
-async fn handle_orm_query(
-    hotkey: &str,
-    msg_json: &Value,
-    cipher: &ChaCha20Poly1305,
-    state: &AppState,
-) -> Result<()> {
+async fn handle_orm_query(hotkey: &str, msg_json: &Value, state: &AppState) -> Value {
     debug!("Handling ORM query from {}: {:?}", hotkey, msg_json);
 
     let result = super::orm::handle_orm_query_with_challenge(
@@ -134,7 +246,7 @@ async fn handle_orm_query(
         state,
     ).await;
 
-    let response = match result {
+    match result {
         Ok(response) => response,
         Err(e) => {
             error!("ORM query failed for {}: {}", hotkey, e);
@@ -144,32 +256,16 @@ async fn handle_orm_query(
                 "error": e.to_string()
             })
         }
-    };
-
-    // Send response back to validator
-    if let Some(connection) = state.get_validator_connection(hotkey).await {
-        let encrypted_response = encrypt_message(&response, cipher)?;
-        
-        if let Err(e) = connection.send_message(&encrypted_response).await {
-            error!("Failed to send ORM query response to {}: {}", hotkey, e);
-        }
     }
-
-    Ok(())
 }
 
 /// Handle ORM permissions requests
-async fn handle_orm_permissions_msg(
-    hotkey: &str,
-    msg_json: &Value,
-    cipher: &ChaCha20Poly1305,
-    state: &AppState,
-) -> Result<()> {
+async fn handle_orm_permissions_msg(hotkey: &str, msg_json: &Value, state: &AppState) -> Value {
     debug!("Handling ORM permissions from {}: {:?}", hotkey, msg_json);
 
     let result = super::orm::handle_orm_permissions(msg_json, hotkey, state).await;
 
-    let response = match result {
+    match result {
         Ok(response) => response,
         Err(e) => {
             error!("ORM permissions check failed for {}: {}", hotkey, e);
@@ -179,18 +275,102 @@ async fn handle_orm_permissions_msg(
                 "error": e.to_string()
             })
         }
-    };
+    }
+}
 
-    // Send response back to validator
-    if let Some(connection) = state.get_validator_connection(hotkey).await {
-        let encrypted_response = encrypt_message(&response, cipher)?;
-        
-        if let Err(e) = connection.send_message(&encrypted_response).await {
-            error!("Failed to send ORM permissions response to {}: {}", hotkey, e);
-        }
+/// Handle ORM transaction requests
+///
+/// The statements of `transaction` run in a single transaction confined to
+/// the schema of `challenge_id`; the response says whether it committed.
+async fn handle_orm_transaction(hotkey: &str, msg_json: &Value, state: &AppState) -> Value {
+    debug!("Handling ORM transaction from {}: {:?}", hotkey, msg_json);
+
+    let result = async {
+        let orm_gateway = state
+            .orm_gateway
+            .as_ref()
+            .ok_or_else(|| anyhow!("ORM gateway not available"))?;
+        let challenge_id = msg_json
+            .get("challenge_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Missing challenge_id"))?;
+        let transaction = msg_json
+            .get("transaction")
+            .ok_or_else(|| anyhow!("Missing transaction"))?;
+
+        super::orm::handle_orm_transaction_with_challenge(
+            state,
+            orm_gateway,
+            transaction,
+            challenge_id,
+            hotkey,
+        )
+        .await
     }
+    .await;
 
-    Ok(())
+    if let Err(e) = &result {
+        error!("ORM transaction failed for {}: {}", hotkey, e);
+    }
+    orm_response("orm_transaction_response", msg_json, result)
+}
+
+/// Handle ORM bulk insert requests
+///
+/// The rows of `bulk_insert` are inserted in a single statement into a table
+/// of the schema of `challenge_id`; the response counts the inserted rows.
+async fn handle_orm_bulk_insert(hotkey: &str, msg_json: &Value, state: &AppState) -> Value {
+    debug!("Handling ORM bulk insert from {}: {:?}", hotkey, msg_json);
+
+    let result = async {
+        let orm_gateway = state
+            .orm_gateway
+            .as_ref()
+            .ok_or_else(|| anyhow!("ORM gateway not available"))?;
+        let challenge_id = msg_json
+            .get("challenge_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Missing challenge_id"))?;
+        let bulk_insert = msg_json
+            .get("bulk_insert")
+            .ok_or_else(|| anyhow!("Missing bulk_insert"))?;
+
+        super::orm::handle_orm_bulk_insert_with_challenge(
+            state,
+            orm_gateway,
+            bulk_insert,
+            challenge_id,
+            hotkey,
+        )
+        .await
+    }
+    .await;
+
+    if let Err(e) = &result {
+        error!("ORM bulk insert failed for {}: {}", hotkey, e);
+    }
+    orm_response("orm_bulk_insert_response", msg_json, result)
+}
+
+/// Response to an ORM request, echoing its `query_id` so the validator can
+/// match it to the request
+fn orm_response(response_type: &str, msg_json: &Value, result: Result<Value>) -> Value {
+    let mut response = match result {
+        Ok(result) => serde_json::json!({
+            "type": response_type,
+            "success": true,
+            "result": result
+        }),
+        Err(e) => serde_json::json!({
+            "type": response_type,
+            "success": false,
+            "error": e.to_string()
+        }),
+    };
+    if let Some(query_id) = msg_json.get("query_id") {
+        response["query_id"] = query_id.clone();
+    }
+    response
 }
 
 /// Handle job result submissions
@@ -218,3 +398,76 @@ async fn handle_heartbeat(hotkey: &str, state: &AppState) {
         error!("Failed to update heartbeat for {}: {}", hotkey, e);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platform_api_storage::{MemoryStorageBackend, StorageConfig};
+
+    fn test_state() -> AppState {
+        AppState::for_tests(Arc::new(
+            MemoryStorageBackend::new(&StorageConfig::default()).unwrap(),
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_orm_transaction_is_dispatched() {
+        let state = test_state();
+        let msg = serde_json::json!({
+            "type": "orm_transaction",
+            "query_id": "q-1",
+            "challenge_id": uuid::Uuid::new_v4().to_string(),
+            "transaction": { "statements": [] }
+        });
+
+        let response = dispatch_message("orm_transaction", "validator", &msg, &state)
+            .await
+            .unwrap()
+            .expect("ORM transactions are answered");
+
+        assert_eq!(response["type"], "orm_transaction_response");
+        assert_eq!(response["success"], false);
+        assert_eq!(response["query_id"], "q-1");
+        assert!(response["error"]
+            .as_str()
+            .unwrap()
+            .contains("ORM gateway not available"));
+    }
+
+    #[tokio::test]
+    async fn test_orm_bulk_insert_is_dispatched() {
+        let state = test_state();
+        let msg = serde_json::json!({
+            "type": "orm_bulk_insert",
+            "query_id": "q-2",
+            "challenge_id": uuid::Uuid::new_v4().to_string(),
+            "bulk_insert": { "table": "results", "rows": [] }
+        });
+
+        let response = dispatch_message("orm_bulk_insert", "validator", &msg, &state)
+            .await
+            .unwrap()
+            .expect("ORM bulk inserts are answered");
+
+        assert_eq!(response["type"], "orm_bulk_insert_response");
+        assert_eq!(response["success"], false);
+        assert_eq!(response["query_id"], "q-2");
+        assert!(response["error"]
+            .as_str()
+            .unwrap()
+            .contains("ORM gateway not available"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_message_is_not_answered() {
+        let state = test_state();
+        let msg = serde_json::json!({ "type": "orm_unknown" });
+
+        let response = dispatch_message("orm_unknown", "validator", &msg, &state)
+            .await
+            .unwrap();
+
+        assert!(response.is_none());
+    }
+}
@@ -1,4 +1,7 @@
+use platform_api_models::{sort_json_keys, SessionToken};
 use serde::{Deserialize, Serialize};
+use sp_core::crypto::{Pair, Ss58Codec};
+use sp_core::sr25519;
 use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
@@ -6,6 +9,11 @@ pub struct HandshakeMessage {
     #[serde(rename = "type")]
     pub msg_type: String,
     pub validator_hotkey: String,
+    /// Token of a previous session to resume instead of attesting again
+    #[serde(default)]
+    pub resume_session_token: Option<SessionToken>,
+    #[serde(default)]
+    pub instance_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -19,14 +27,74 @@ pub struct AttestationMessage {
     pub vm_config: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Message a validator signs with its hotkey
+///
+/// Clients build messages with [`SecureMessage::new`], sign them with
+/// [`SecureMessage::sign`] and send them as JSON. The signature covers
+/// [`SecureMessage::signing_bytes`], which verification checks it against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecureMessage {
     pub message_type: String,
     pub data: serde_json::Value,
     pub timestamp: u64,
     pub nonce: String,
+    /// sr25519 signature on the first frame of a connection, then the frame MAC
     pub signature: String,
     pub public_key: String,
+    /// Sequence number of the frame, strictly increasing on a connection and
+    /// covered by its signature or MAC
+    #[serde(default)]
+    pub seq: u64,
+}
+
+impl SecureMessage {
+    /// Unsigned message of `message_type` carrying `data`, timestamped now
+    /// with a random nonce
+    pub fn new(message_type: impl Into<String>, data: serde_json::Value) -> Self {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self {
+            message_type: message_type.into(),
+            data,
+            timestamp,
+            nonce: Uuid::new_v4().to_string(),
+            signature: String::new(),
+            public_key: String::new(),
+            seq: 0,
+        }
+    }
+
+    /// Set the sequence number of the frame
+    pub fn with_seq(mut self, seq: u64) -> Self {
+        self.seq = seq;
+        self
+    }
+
+    /// Canonical form of the signed fields: the sequence number as 8
+    /// big-endian bytes, the message type, the timestamp in decimal, the nonce
+    /// and the data as compact JSON with sorted keys, concatenated
+    ///
+    /// Keys are sorted so that signer and verifier agree on the bytes of
+    /// object payloads whatever order their JSON maps keep keys in.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.seq.to_be_bytes());
+        bytes.extend_from_slice(self.message_type.as_bytes());
+        bytes.extend_from_slice(self.timestamp.to_string().as_bytes());
+        bytes.extend_from_slice(self.nonce.as_bytes());
+        bytes.extend_from_slice(sort_json_keys(&self.data).to_string().as_bytes());
+        bytes
+    }
+
+    /// Sign the message with the hotkey `pair`, setting its public key and
+    /// signature
+    pub fn sign(mut self, pair: &sr25519::Pair) -> Self {
+        self.public_key = pair.public().to_ss58check();
+        self.signature = hex::encode(pair.sign(&self.signing_bytes()));
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -46,5 +114,14 @@ mod tests {
         let json = r#"{"type":"handshake","validator_hotkey":"5DD123..."}"#;
         let msg: HandshakeMessage = serde_json::from_str(json).unwrap();
         assert_eq!(msg.msg_type, "handshake");
+        assert!(msg.resume_session_token.is_none());
+    }
+
+    #[test]
+    fn test_resume_handshake_message() {
+        let json = r#"{"type":"handshake","validator_hotkey":"5DD123...","resume_session_token":"token","instance_id":"instance-1"}"#;
+        let msg: HandshakeMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(msg.resume_session_token.as_deref(), Some("token"));
+        assert_eq!(msg.instance_id.as_deref(), Some("instance-1"));
     }
 }
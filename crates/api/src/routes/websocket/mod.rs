@@ -4,16 +4,17 @@ mod messages;
 mod orm;
 mod utils;
 mod authentication;
+mod frame_auth;
 mod message_handler;
 mod connection_manager;
 
 use crate::state::AppState;
 use axum::Router;
 
-pub use messages::ValidatorNotification;
+pub use messages::{SecureMessage, ValidatorNotification};
 pub use handler::validator_websocket;
 pub use authentication::{handle_unauthenticated_message, complete_authentication};
-pub use message_handler::handle_authenticated_messages;
+pub use message_handler::{handle_authenticated_messages, ConnectionEnd};
 pub use connection_manager::{handle_validator_connection, spawn_health_check_task, shutdown_connections};
 
 /// Create WebSocket router
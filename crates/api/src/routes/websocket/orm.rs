@@ -4,7 +4,10 @@ use tokio::sync::RwLock;
 use tracing::{error, info};
 use uuid::Uuid;
 
-use platform_api_orm_gateway::{ORMQuery, SecureORMGateway, TablePermission};
+use platform_api_orm_gateway::{
+    BulkInsertQuery, ChallengeSchema, ORMQuery, ORMTransaction, SecureORMGateway,
+    TablePermission,
+};
 use crate::state::AppState;
 use std::collections::HashMap;
 
@@ -37,88 +40,21 @@ pub async fn handle_orm_query_with_challenge(
     validator_hotkey: &str,
 ) -> anyhow::Result<serde_json::Value> {
     // Parse the query
-    let mut query: ORMQuery =
+    let query: ORMQuery =
         serde_json::from_value(query_data.clone()).context("Failed to parse ORM query")?;
 
-    // Resolve schema: {challenge_name}_v{db_version}
-    if query.schema.is_none() {
-        // Get challenge_name from database using challenge_id
-        let challenge_name = if let Some(pool) = &state.database_pool {
-            // Try to parse challenge_id as UUID first
-            let challenge_uuid = if let Ok(uuid) = Uuid::parse_str(challenge_id) {
-                uuid
-            } else {
-                // If not a UUID, treat it as challenge name directly
-                // Normalize challenge name (replace hyphens with underscores for schema)
-                let schema_name = challenge_id.replace('-', "_");
-                let db_version = query.db_version.unwrap_or(1);
-                query.schema = Some(format!("{}_v{}", schema_name, db_version));
-                info!(
-                    validator_hotkey = validator_hotkey,
-                    challenge_id = challenge_id,
-                    schema = &query.schema.as_ref().unwrap(),
-                    "Resolved schema from challenge_id (non-UUID) and db_version"
-                );
-                // Execute the query using normal gateway (permissions control access)
-                let gateway = orm_gateway.read().await;
-                let result = gateway
-                    .execute_query(query)
-                    .await
-                    .context("Failed to execute ORM query")?;
-                return Ok(serde_json::to_value(result)?);
-            };
-
-            // Query database for challenge name
-            let name_result: Option<String> =
-                sqlx::query_scalar("SELECT name FROM challenges WHERE id = $1 LIMIT 1")
-                    .bind(challenge_uuid)
-                    .fetch_optional(pool.as_ref())
-                    .await
-                    .context("Failed to query challenge name from database")?;
-
-            match name_result {
-                Some(name) => name,
-                None => {
-                    error!(
-                        validator_hotkey = validator_hotkey,
-                        challenge_id = challenge_id,
-                        "Challenge not found in database"
-                    );
-                    return Err(anyhow::anyhow!("Challenge not found: {}", challenge_id));
-                }
-            }
-        } else {
-            error!(
-                validator_hotkey = validator_hotkey,
-                challenge_id = challenge_id,
-                "Database pool not available"
-            );
-            return Err(anyhow::anyhow!("Database pool not available"));
-        };
-
-        // Get db_version from query, default to 1 if not provided
-        let db_version = query.db_version.unwrap_or(1);
-
-        // Normalize challenge name (replace hyphens with underscores for schema)
-        let normalized_name = challenge_name.replace('-', "_");
-
-        // Construct schema: {challenge_name}_v{db_version}
-        query.schema = Some(format!("{}_v{}", normalized_name, db_version));
-
-        info!(
-            validator_hotkey = validator_hotkey,
-            challenge_id = challenge_id,
-            challenge_name = &challenge_name,
-            db_version = db_version,
-            schema = &query.schema.as_ref().unwrap(),
-            "Resolved schema from challenge_name and db_version"
-        );
-    }
+    let scope = challenge_scope(state, challenge_id, validator_hotkey).await?;
+    info!(
+        validator_hotkey = validator_hotkey,
+        challenge_id = challenge_id,
+        schema = &scope.schema,
+        "Executing ORM query for challenge"
+    );
 
     // Execute the query using normal gateway (permissions control access)
     let gateway = orm_gateway.read().await;
     let result = gateway
-        .execute_query(query)
+        .execute_scoped_query(&scope, query)
         .await
         .context("Failed to execute ORM query")?;
 
@@ -126,6 +62,105 @@ pub async fn handle_orm_query_with_challenge(
     Ok(serde_json::to_value(result)?)
 }
 
+/// Handle ORM transaction from validator with challenge schema resolution
+///
+/// The statements run in one transaction confined to the challenge's schema,
+/// like single queries, and are all committed or all rolled back.
+pub async fn handle_orm_transaction_with_challenge(
+    state: &AppState,
+    orm_gateway: &Arc<RwLock<SecureORMGateway>>,
+    transaction_data: &serde_json::Value,
+    challenge_id: &str,
+    validator_hotkey: &str,
+) -> anyhow::Result<serde_json::Value> {
+    let transaction: ORMTransaction = serde_json::from_value(transaction_data.clone())
+        .context("Failed to parse ORM transaction")?;
+
+    let scope = challenge_scope(state, challenge_id, validator_hotkey).await?;
+    info!(
+        validator_hotkey = validator_hotkey,
+        challenge_id = challenge_id,
+        schema = &scope.schema,
+        statement_count = transaction.statements.len(),
+        "Executing ORM transaction for challenge"
+    );
+
+    let gateway = orm_gateway.read().await;
+    let result = gateway
+        .execute_scoped_transaction(&scope, transaction.statements)
+        .await
+        .context("ORM transaction rolled back")?;
+
+    Ok(serde_json::to_value(result)?)
+}
+
+/// Handle ORM bulk insert from validator with challenge schema resolution
+pub async fn handle_orm_bulk_insert_with_challenge(
+    state: &AppState,
+    orm_gateway: &Arc<RwLock<SecureORMGateway>>,
+    bulk_insert_data: &serde_json::Value,
+    challenge_id: &str,
+    validator_hotkey: &str,
+) -> anyhow::Result<serde_json::Value> {
+    let bulk_insert: BulkInsertQuery = serde_json::from_value(bulk_insert_data.clone())
+        .context("Failed to parse ORM bulk insert")?;
+
+    let scope = challenge_scope(state, challenge_id, validator_hotkey).await?;
+    info!(
+        validator_hotkey = validator_hotkey,
+        challenge_id = challenge_id,
+        schema = &scope.schema,
+        table = &bulk_insert.table,
+        row_count = bulk_insert.rows.len(),
+        "Executing ORM bulk insert for challenge"
+    );
+
+    let gateway = orm_gateway.read().await;
+    let inserted = gateway
+        .execute_scoped_bulk_insert(&scope, bulk_insert)
+        .await
+        .context("Failed to execute ORM bulk insert")?;
+
+    Ok(serde_json::json!({ "inserted": inserted }))
+}
+
+/// Schema and role the ORM requests of a validator for `challenge_id` are
+/// confined to
+///
+/// Queries are confined to the schema of the challenge and run as its role,
+/// both derived from its immutable ID; a schema named by the query is checked
+/// against it rather than trusted.
+async fn challenge_scope(
+    state: &AppState,
+    challenge_id: &str,
+    validator_hotkey: &str,
+) -> anyhow::Result<ChallengeSchema> {
+    let challenge_uuid = Uuid::parse_str(challenge_id)
+        .with_context(|| format!("Invalid challenge ID: {}", challenge_id))?;
+    let pool = state.database_pool.as_ref().ok_or_else(|| {
+        error!(
+            validator_hotkey = validator_hotkey,
+            challenge_id = challenge_id,
+            "Database pool not available"
+        );
+        anyhow::anyhow!("Database pool not available")
+    })?;
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM challenges WHERE id = $1)")
+        .bind(challenge_uuid)
+        .fetch_one(pool.as_ref())
+        .await
+        .context("Failed to look up challenge")?;
+    if !exists {
+        error!(
+            validator_hotkey = validator_hotkey,
+            challenge_id = challenge_id,
+            "Challenge not found in database"
+        );
+        return Err(anyhow::anyhow!("Challenge not found: {}", challenge_id));
+    }
+    Ok(ChallengeSchema::for_challenge(challenge_uuid))
+}
+
 /// Handle ORM permissions update
 pub async fn handle_orm_permissions(
     orm_gateway: &Arc<RwLock<SecureORMGateway>>,
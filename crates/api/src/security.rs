@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use sha2::{Digest, Sha256};
 use std::path::PathBuf;
 use hex;
 use serde_json;
@@ -9,57 +10,149 @@ pub struct PlatformSecurity {
     verifying_key: VerifyingKey,
     compose_hash: String, // Hash of Docker Compose file (attested by TDX)
     key_file: PathBuf,
+    /// Rotation epoch the keys were derived at, 0 until the first rotation
+    key_epoch: u64,
+    /// Version of the keys, see `key_id_for`
+    key_id: String,
+    /// Bytes of entropy in generated nonces
+    nonce_len: usize,
+}
+
+/// Bytes of entropy in generated nonces, unless `PLATFORM_NONCE_BYTES` says otherwise
+pub const DEFAULT_NONCE_BYTES: usize = 16;
+
+/// Fewest bytes of entropy a generated nonce may have
+pub const MIN_NONCE_BYTES: usize = 8;
+
+/// Nonce length from `PLATFORM_NONCE_BYTES`, ignored when below `MIN_NONCE_BYTES`
+fn nonce_len_from_env() -> usize {
+    match std::env::var("PLATFORM_NONCE_BYTES") {
+        Ok(value) => match value.parse::<usize>() {
+            Ok(len) if len >= MIN_NONCE_BYTES => len,
+            _ => {
+                tracing::warn!(
+                    "Ignoring PLATFORM_NONCE_BYTES={}: expected at least {} bytes, using {}",
+                    value,
+                    MIN_NONCE_BYTES,
+                    DEFAULT_NONCE_BYTES
+                );
+                DEFAULT_NONCE_BYTES
+            }
+        },
+        Err(_) => DEFAULT_NONCE_BYTES,
+    }
+}
+
+/// Fewest bytes a key derivation secret may have
+pub const MIN_KEY_SECRET_BYTES: usize = 16;
+
+/// Current rotation epoch from `PLATFORM_KEY_EPOCH`, 0 when unset
+fn key_epoch_from_env() -> u64 {
+    match std::env::var("PLATFORM_KEY_EPOCH") {
+        Ok(value) => value.parse().unwrap_or_else(|_| {
+            tracing::warn!(
+                "Ignoring PLATFORM_KEY_EPOCH={}: not an epoch number, using 0",
+                value
+            );
+            0
+        }),
+        Err(_) => 0,
+    }
+}
+
+fn key_file_from_env() -> PathBuf {
+    std::env::var("PLATFORM_SECURITY_KEY_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./security.key"))
 }
 
 impl PlatformSecurity {
+    /// Load the keys of `PLATFORM_SECURITY_KEY_FILE` at the rotation epoch of
+    /// `PLATFORM_KEY_EPOCH`, see [`Self::load_or_generate`]
     pub fn new() -> Result<Self> {
-        // Get key file path from environment or use default
-        let key_file = std::env::var("PLATFORM_SECURITY_KEY_FILE")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| PathBuf::from("./security.key"));
+        Self::load_or_generate(key_file_from_env(), key_epoch_from_env())
+    }
+
+    /// Load the keys saved in `key_file`, rotating them if they are older
+    /// than `epoch`
+    ///
+    /// Keys of an earlier epoch, or no saved keys, are replaced with new
+    /// random keys of `epoch`, saved to `key_file`. Keys of a later epoch are
+    /// kept: rotation never goes back.
+    pub fn load_or_generate(key_file: PathBuf, epoch: u64) -> Result<Self> {
+        // Generate random compose hash (not verified, randomized)
+        let mut compose_hash = {
+            use rand::RngCore;
+            let mut random_bytes = [0u8; 16];
+            rand::thread_rng().fill_bytes(&mut random_bytes);
+            format!("random-{}", hex::encode(random_bytes))
+        };
 
         // Try to load existing keys
         if key_file.exists() {
             match Self::load_keys_from_file(&key_file) {
-                Ok((signing_key, verifying_key, compose_hash)) => {
+                Ok((signing_key, verifying_key, saved_compose_hash, key_epoch))
+                    if key_epoch >= epoch =>
+                {
                     tracing::info!("Loaded existing security keys from {}", key_file.display());
+                    if key_epoch > epoch {
+                        tracing::warn!(
+                            "Security keys of {} are of epoch {}, later than the configured epoch {}; keeping them",
+                            key_file.display(),
+                            key_epoch,
+                            epoch
+                        );
+                    }
+                    let key_id = Self::key_id_for(&verifying_key, key_epoch);
                     return Ok(Self {
                         signing_key,
                         verifying_key,
-                        compose_hash,
+                        compose_hash: saved_compose_hash,
                         key_file,
+                        key_epoch,
+                        key_id,
+                        nonce_len: nonce_len_from_env(),
                     });
                 }
+                Ok((_, _, saved_compose_hash, key_epoch)) => {
+                    tracing::info!(
+                        "Rotating security keys of {} from epoch {} to {}",
+                        key_file.display(),
+                        key_epoch,
+                        epoch
+                    );
+                    compose_hash = saved_compose_hash;
+                }
                 Err(e) => {
-                    tracing::warn!("Failed to load keys from {}: {}. Generating new keys.", key_file.display(), e);
+                    tracing::warn!(
+                        "Failed to load keys from {}: {}. Generating new keys.",
+                        key_file.display(),
+                        e
+                    );
                 }
             }
         }
 
-        // Generate random compose hash (not verified, randomized)
-        use rand::RngCore;
-        let mut random_bytes = [0u8; 16];
-        rand::thread_rng().fill_bytes(&mut random_bytes);
-        let compose_hash = format!("random-{}", hex::encode(random_bytes));
-
-        tracing::info!(
-            "Generated random compose hash (not verified): {}",
-            compose_hash
-        );
+        let mut security = Self::new_with_random_keys(&compose_hash)?;
+        security.key_file = key_file;
+        security.key_epoch = epoch;
+        security.key_id = Self::key_id_for(&security.verifying_key, epoch);
 
-        let security = Self::new_with_random_keys(&compose_hash)?;
-        
         // Save keys to file
         if let Err(e) = security.save_keys_to_file() {
-            tracing::warn!("Failed to save keys to {}: {}", key_file.display(), e);
+            tracing::warn!(
+                "Failed to save keys to {}: {}",
+                security.key_file.display(),
+                e
+            );
         } else {
-            tracing::info!("Saved security keys to {}", key_file.display());
+            tracing::info!("Saved security keys to {}", security.key_file.display());
         }
 
         Ok(security)
     }
 
-    fn load_keys_from_file(key_file: &PathBuf) -> Result<(SigningKey, VerifyingKey, String)> {
+    fn load_keys_from_file(key_file: &PathBuf) -> Result<(SigningKey, VerifyingKey, String, u64)> {
         let contents = std::fs::read_to_string(key_file)
             .context("Failed to read key file")?;
         
@@ -86,8 +179,9 @@ impl PlatformSecurity {
             .as_str()
             .unwrap_or("unknown")
             .to_string();
+        let key_epoch = key_data["key_epoch"].as_u64().unwrap_or(0);
         
-        Ok((signing_key, verifying_key, compose_hash))
+        Ok((signing_key, verifying_key, compose_hash, key_epoch))
     }
 
     fn save_keys_to_file(&self) -> Result<()> {
@@ -95,6 +189,8 @@ impl PlatformSecurity {
             "secret_key": hex::encode(self.signing_key.to_bytes()),
             "public_key": hex::encode(self.verifying_key.to_bytes()),
             "compose_hash": self.compose_hash,
+            "key_epoch": self.key_epoch,
+            "key_id": self.key_id,
         });
         
         let contents = serde_json::to_string_pretty(&key_data)
@@ -139,10 +235,7 @@ impl PlatformSecurity {
 
     /// Generate random keys (not deterministic, not verified)
     pub fn new_with_random_keys(compose_hash: &str) -> Result<Self> {
-        // Get key file path from environment or use default
-        let key_file = std::env::var("PLATFORM_SECURITY_KEY_FILE")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| PathBuf::from("./security.key"));
+        let key_file = key_file_from_env();
 
         // Generate random key pair (not deterministic)
         use rand::rngs::OsRng;
@@ -178,17 +271,80 @@ impl PlatformSecurity {
             public_key_bytes.len()
         );
 
+        let key_id = Self::key_id_for(&verifying_key, 0);
         Ok(Self {
             signing_key,
             verifying_key,
             compose_hash: compose_hash.to_string(),
             key_file,
+            key_epoch: 0,
+            key_id,
+            nonce_len: nonce_len_from_env(),
         })
     }
 
-    /// Legacy method - kept for compatibility but uses random keys
-    pub fn new_with_compose_hash(compose_hash: &str) -> Result<Self> {
-        Self::new_with_random_keys(compose_hash)
+    /// Derive the keys from `compose_hash` and `secret`, at rotation epoch 0
+    pub fn new_with_compose_hash(compose_hash: &str, secret: &[u8]) -> Result<Self> {
+        Self::new_with_compose_hash_at_epoch(compose_hash, secret, 0)
+    }
+
+    /// Derive the keys from `compose_hash`, `secret` and a rotation `epoch`
+    ///
+    /// The keys are the HMAC-SHA256, keyed by `secret`, of the compose hash
+    /// and epoch: the compose hash is public, so the secret, sealed to the
+    /// platform's CVM or configured and never exposed, is what keeps others
+    /// from deriving them. The same inputs always give the same keys, so a
+    /// compromised key is rotated by bumping the epoch, without changing the
+    /// compose hash. The epoch is part of the key ID.
+    pub fn new_with_compose_hash_at_epoch(
+        compose_hash: &str,
+        secret: &[u8],
+        epoch: u64,
+    ) -> Result<Self> {
+        use hmac::{Hmac, Mac};
+
+        if secret.len() < MIN_KEY_SECRET_BYTES {
+            return Err(anyhow::anyhow!(
+                "Key derivation secret must have at least {} bytes, got {}",
+                MIN_KEY_SECRET_BYTES,
+                secret.len()
+            ));
+        }
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+            .map_err(|e| anyhow::anyhow!("Invalid key derivation secret: {}", e))?;
+        mac.update(b"platform-api-security-key:");
+        mac.update(compose_hash.as_bytes());
+        mac.update(b":");
+        mac.update(&epoch.to_be_bytes());
+        let seed: [u8; 32] = mac.finalize().into_bytes().into();
+
+        let signing_key = SigningKey::from_bytes(&seed);
+        let verifying_key = signing_key.verifying_key();
+        let key_id = Self::key_id_for(&verifying_key, epoch);
+
+        tracing::info!(
+            "Derived security keys {} from compose hash {}",
+            key_id,
+            compose_hash
+        );
+
+        Ok(Self {
+            signing_key,
+            verifying_key,
+            compose_hash: compose_hash.to_string(),
+            key_file: key_file_from_env(),
+            key_epoch: epoch,
+            key_id,
+            nonce_len: nonce_len_from_env(),
+        })
+    }
+
+    /// Key ID of `verifying_key` at rotation `epoch`: `v{epoch}-` followed by
+    /// the first 8 bytes of the SHA256 of the public key, hex-encoded
+    pub fn key_id_for(verifying_key: &VerifyingKey, epoch: u64) -> String {
+        let fingerprint = Sha256::digest(verifying_key.to_bytes());
+        format!("v{}-{}", epoch, hex::encode(&fingerprint[..8]))
     }
 
     /// Get compose_hash from dstack TDX attestation
@@ -239,8 +395,6 @@ impl PlatformSecurity {
         Ok(compose_hash)
     }
 
-    // Removed derive_seed_from_compose_hash - keys are now random, not deterministic
-
     /// Sign a message with the private key
     pub fn sign(&self, message: &[u8]) -> Vec<u8> {
         let signature = self.signing_key.sign(message);
@@ -252,23 +406,87 @@ impl PlatformSecurity {
         self.verifying_key.to_bytes().to_vec()
     }
 
+    /// Get the key ID along with the public key, so verifiers can tell key versions apart
+    pub fn get_public_key_with_id(&self) -> (String, Vec<u8>) {
+        (self.key_id.clone(), self.get_public_key())
+    }
+
+    /// Get the key ID of the current keys
+    pub fn get_key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Get the rotation epoch of the current keys
+    pub fn get_key_epoch(&self) -> u64 {
+        self.key_epoch
+    }
+
     /// Get the compose hash (from TDX attestation)
     pub fn get_compose_hash(&self) -> &str {
         &self.compose_hash
     }
 
-    /// Create a signed response header value
+    /// Use nonces of `len` bytes of entropy, at least `MIN_NONCE_BYTES`
+    pub fn with_nonce_len(mut self, len: usize) -> Result<Self> {
+        if len < MIN_NONCE_BYTES {
+            return Err(anyhow::anyhow!(
+                "Nonces need at least {} bytes, got {}",
+                MIN_NONCE_BYTES,
+                len
+            ));
+        }
+        self.nonce_len = len;
+        Ok(self)
+    }
+
+    /// Get the bytes of entropy in generated nonces
+    pub fn get_nonce_len(&self) -> usize {
+        self.nonce_len
+    }
+
+    /// Generate a fresh nonce from the operating system's CSPRNG, hex-encoded
+    pub fn generate_nonce(&self) -> String {
+        self.generate_nonce_from(&mut rand::rngs::OsRng)
+    }
+
+    /// Generate a nonce from `rng`, hex-encoded
+    ///
+    /// The `CryptoRng` bound keeps non-cryptographic generators out.
+    pub fn generate_nonce_from<R: rand::RngCore + rand::CryptoRng>(&self, rng: &mut R) -> String {
+        let mut bytes = vec![0u8; self.nonce_len];
+        rng.fill_bytes(&mut bytes);
+        hex::encode(bytes)
+    }
+
+    /// Create a signed response header value: `signature:key_id:timestamp:nonce`
+    ///
+    /// The signature covers the key ID, so verifiers can pick the public key
+    /// of that version. The nonce must never be reused; prefer
+    /// `create_signed_header_auto`, which generates it.
     pub fn create_signed_header(&self, timestamp: i64, nonce: &str) -> String {
-        // Create message: timestamp + nonce
-        let message = format!("{}:{}", timestamp, nonce);
+        // Create message: key_id + timestamp + nonce
+        let message = format!("{}:{}:{}", self.key_id, timestamp, nonce);
         let signature = self.sign(message.as_bytes());
         format!("{}:{}", hex::encode(signature), message)
     }
+
+    /// Create a signed response header value with a generated nonce, see
+    /// `create_signed_header` and `generate_nonce`
+    pub fn create_signed_header_auto(&self, timestamp: i64) -> String {
+        self.create_signed_header(timestamp, &self.generate_nonce())
+    }
+}
+
+impl platform_api_scheduler::WebhookSigner for PlatformSecurity {
+    fn sign(&self, payload: &[u8]) -> Vec<u8> {
+        PlatformSecurity::sign(self, payload)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ed25519_dalek::Verifier;
 
     #[test]
     fn test_random_key_generation() {
@@ -295,4 +513,131 @@ mod tests {
 
         assert!(verifying_key.verify(message, &sig).is_ok());
     }
+
+    const SECRET: &[u8] = b"test-key-derivation-secret";
+
+    #[test]
+    fn test_key_rotation_epochs() {
+        let compose_hash = "test-compose-hash-rotation";
+        let epoch0 = PlatformSecurity::new_with_compose_hash(compose_hash, SECRET).unwrap();
+        let epoch1 =
+            PlatformSecurity::new_with_compose_hash_at_epoch(compose_hash, SECRET, 1).unwrap();
+        let epoch1_again =
+            PlatformSecurity::new_with_compose_hash_at_epoch(compose_hash, SECRET, 1).unwrap();
+
+        // The same epoch is deterministic
+        assert_eq!(
+            epoch1.get_public_key_with_id(),
+            epoch1_again.get_public_key_with_id()
+        );
+        assert_eq!(epoch1.sign(b"message"), epoch1_again.sign(b"message"));
+
+        // Another epoch rotates the keys and their ID
+        assert_ne!(epoch0.get_public_key(), epoch1.get_public_key());
+        assert_ne!(epoch0.get_key_id(), epoch1.get_key_id());
+        assert!(epoch0.get_key_id().starts_with("v0-"));
+        assert!(epoch1.get_key_id().starts_with("v1-"));
+        assert_eq!(epoch1.get_key_epoch(), 1);
+
+        // Another compose hash gives other keys at the same epoch
+        let other =
+            PlatformSecurity::new_with_compose_hash_at_epoch("other-compose-hash", SECRET, 1)
+                .unwrap();
+        assert_ne!(other.get_public_key(), epoch1.get_public_key());
+    }
+
+    #[test]
+    fn test_derived_keys_depend_on_secret() {
+        let compose_hash = "test-compose-hash-secret";
+        let sec = PlatformSecurity::new_with_compose_hash(compose_hash, SECRET).unwrap();
+        let other_secret =
+            PlatformSecurity::new_with_compose_hash(compose_hash, b"another-derivation-secret")
+                .unwrap();
+        assert_ne!(sec.get_public_key(), other_secret.get_public_key());
+        assert_ne!(sec.sign(b"message"), other_secret.sign(b"message"));
+
+        // Knowing the compose hash alone is not enough
+        assert!(PlatformSecurity::new_with_compose_hash(compose_hash, b"").is_err());
+        assert!(PlatformSecurity::new_with_compose_hash(compose_hash, b"short").is_err());
+    }
+
+    #[test]
+    fn test_saved_keys_rotated_by_epoch() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join("security.key");
+
+        let epoch0 = PlatformSecurity::load_or_generate(key_file.clone(), 0).unwrap();
+        let reloaded = PlatformSecurity::load_or_generate(key_file.clone(), 0).unwrap();
+        assert_eq!(reloaded.get_public_key_with_id(), epoch0.get_public_key_with_id());
+
+        // A later epoch rotates the saved keys
+        let epoch1 = PlatformSecurity::load_or_generate(key_file.clone(), 1).unwrap();
+        assert_ne!(epoch1.get_public_key(), epoch0.get_public_key());
+        assert!(epoch1.get_key_id().starts_with("v1-"));
+        assert_eq!(epoch1.get_compose_hash(), epoch0.get_compose_hash());
+
+        // and never goes back
+        let stale = PlatformSecurity::load_or_generate(key_file, 0).unwrap();
+        assert_eq!(stale.get_public_key_with_id(), epoch1.get_public_key_with_id());
+        assert_eq!(stale.get_key_epoch(), 1);
+    }
+
+    #[test]
+    fn test_signed_header_names_key() {
+        let sec = PlatformSecurity::new_with_compose_hash_at_epoch("test-compose-hash", SECRET, 3)
+            .unwrap();
+        let header = sec.create_signed_header(1234567890, "nonce");
+
+        let (signature, message) = header.split_once(':').unwrap();
+        assert_eq!(message, format!("{}:1234567890:nonce", sec.get_key_id()));
+
+        let (key_id, public_key) = sec.get_public_key_with_id();
+        assert_eq!(message.split(':').next(), Some(key_id.as_str()));
+        let verifying_key =
+            VerifyingKey::from_bytes(&public_key[..32].try_into().unwrap()).unwrap();
+        let sig_bytes: [u8; 64] = hex::decode(signature).unwrap().try_into().unwrap();
+        let sig = ed25519_dalek::Signature::from_bytes(&sig_bytes);
+        assert!(verifying_key.verify(message.as_bytes(), &sig).is_ok());
+    }
+
+    #[test]
+    fn test_generated_nonces_unique() {
+        let sec = PlatformSecurity::new_with_compose_hash("test-compose-hash", SECRET).unwrap();
+        let nonces: std::collections::HashSet<String> =
+            (0..1000).map(|_| sec.generate_nonce()).collect();
+        assert_eq!(nonces.len(), 1000);
+        for nonce in &nonces {
+            assert_eq!(nonce.len(), 2 * sec.get_nonce_len());
+            assert!(hex::decode(nonce).is_ok());
+        }
+
+        let header = sec.create_signed_header_auto(1234567890);
+        let nonce = header.rsplit(':').next().unwrap();
+        assert_eq!(nonce.len(), 2 * sec.get_nonce_len());
+        assert_ne!(sec.create_signed_header_auto(1234567890), header);
+    }
+
+    #[test]
+    fn test_nonce_length_configurable() {
+        use rand::SeedableRng;
+
+        let sec = PlatformSecurity::new_with_compose_hash("test-compose-hash", SECRET)
+            .unwrap()
+            .with_nonce_len(32)
+            .unwrap();
+        assert_eq!(sec.generate_nonce().len(), 64);
+
+        // The same entropy source gives the same nonces
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut same_rng = rand::rngs::StdRng::seed_from_u64(7);
+        assert_eq!(
+            sec.generate_nonce_from(&mut rng),
+            sec.generate_nonce_from(&mut same_rng)
+        );
+
+        assert!(PlatformSecurity::new_with_compose_hash("test-compose-hash", SECRET)
+            .unwrap()
+            .with_nonce_len(MIN_NONCE_BYTES - 1)
+            .is_err());
+    }
 }
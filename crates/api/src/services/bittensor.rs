@@ -3,7 +3,7 @@ use bittensor_rs::chain::BittensorClient;
 use bittensor_rs::queries::subnets;
 use platform_api_models::{
     ChallengeEmissionBreakdown, ChallengeEmissions, MechanismEmissionBreakdown, MechanismEmissions,
-    SubnetEmissions,
+    SubnetEmissions, SubnetParams,
 };
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
@@ -85,6 +85,19 @@ impl BittensorService {
         }
     }
 
+    /// Number of the latest block of the chain
+    pub async fn current_block(&self) -> Result<u64> {
+        self.client
+            .block_number()
+            .await
+            .map_err(|e| anyhow!("Failed to query latest block number: {}", e))
+    }
+
+    /// Hyperparameters of subnet `netuid`, see [`get_subnet_params`]
+    pub async fn get_subnet_params(&self, netuid: u16) -> Result<SubnetParams> {
+        get_subnet_params(&self.client, netuid).await
+    }
+
     /// Calculate total subnet emissions per day
     pub async fn calculate_subnet_emissions(
         &self,
@@ -371,6 +384,71 @@ impl BittensorService {
     }
 }
 
+/// Read the hyperparameters of subnet `netuid` from the chain
+///
+/// Only failing to reach the chain is an error: a parameter that cannot be
+/// read is logged and left unset, as the tempo of the emission calculation is.
+pub async fn get_subnet_params(client: &BittensorClient, netuid: u16) -> Result<SubnetParams> {
+    let block = client
+        .block_number()
+        .await
+        .map_err(|e| anyhow!("Failed to query latest block number: {}", e))?;
+
+    Ok(SubnetParams {
+        netuid,
+        tempo: subnet_param(netuid, "tempo", subnets::tempo(client, netuid).await),
+        immunity_period: subnet_param(
+            netuid,
+            "immunity_period",
+            subnets::immunity_period(client, netuid).await,
+        ),
+        min_allowed_weights: subnet_param(
+            netuid,
+            "min_allowed_weights",
+            subnets::min_allowed_weights(client, netuid).await,
+        ),
+        max_weights_limit: subnet_param(
+            netuid,
+            "max_weights_limit",
+            subnets::max_weight_limit(client, netuid).await,
+        ),
+        max_allowed_validators: subnet_param(
+            netuid,
+            "max_allowed_validators",
+            subnets::max_allowed_validators(client, netuid).await,
+        ),
+        kappa: subnet_param(netuid, "kappa", subnets::kappa(client, netuid).await),
+        rho: subnet_param(netuid, "rho", subnets::rho(client, netuid).await),
+        min_stake: subnet_param(
+            netuid,
+            "min_stake",
+            subnets::weights_min_stake(client).await,
+        ),
+        block,
+        fetched_at: chrono::Utc::now(),
+    })
+}
+
+/// Value of the subnet parameter `name`, unset if it could not be read
+fn subnet_param<T: Into<u64>, E: std::fmt::Display>(
+    netuid: u16,
+    name: &str,
+    result: std::result::Result<Option<T>, E>,
+) -> Option<u64> {
+    match result {
+        Ok(value) => value.map(Into::into),
+        Err(e) => {
+            warn!(
+                netuid = netuid,
+                parameter = name,
+                error = %e,
+                "Failed to query subnet parameter"
+            );
+            None
+        }
+    }
+}
+
 impl Clone for BittensorService {
     fn clone(&self) -> Self {
         Self {
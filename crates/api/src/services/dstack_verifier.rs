@@ -1,8 +1,21 @@
 use anyhow::{Context, Result};
 use dstack_types::VmConfig;
+use platform_api_attestation::AttestationConfig;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use tracing::{info, warn};
 
+use crate::middleware::request_id::RequestIdExt;
+
+/// Seconds validator verifications by dstack-verifier took, by `outcome`:
+/// `valid`, `invalid` or `error`, timeouts included
+pub const VERIFIER_LATENCY_METRIC: &str = "dstack_verifier_latency_seconds";
+
+/// Checks of validator verifications by dstack-verifier, by `check`
+/// (`quote_verified`, `event_log_verified` or `os_image_hash_verified`) and
+/// `result`: `passed` or `failed`
+pub const VERIFIER_CHECKS_METRIC: &str = "dstack_verifier_checks_total";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationRequest {
     pub quote: String,
@@ -44,6 +57,36 @@ pub struct KeyProvider {
     pub kms_name: String,
 }
 
+/// How validator attestations are verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerifierMode {
+    /// Full platform verification by the dstack verifier: quote, event log,
+    /// measurements and compose hash
+    FullDstack,
+    /// Built-in verification of the TDX quote only
+    BuiltInQuoteOnly,
+    /// Mock verification, in dev or TDX simulation mode
+    Mock,
+}
+
+impl VerifierMode {
+    /// Mode of a platform with the given dstack verifier, if any
+    ///
+    /// The dstack verifier is used whenever configured, even in dev mode.
+    pub fn resolve(
+        dstack_verifier: Option<&DstackVerifierClient>,
+        attestation: &AttestationConfig,
+    ) -> Self {
+        if dstack_verifier.is_some() {
+            VerifierMode::FullDstack
+        } else if attestation.is_dev_mode() || attestation.is_tdx_simulation_mode() {
+            VerifierMode::Mock
+        } else {
+            VerifierMode::BuiltInQuoteOnly
+        }
+    }
+}
+
 /// Client for dstack-verifier service
 #[derive(Clone)]
 pub struct DstackVerifierClient {
@@ -68,6 +111,7 @@ impl DstackVerifierClient {
         let response = self
             .client
             .post(format!("{}/verify", self.base_url))
+            .with_request_id()
             .json(&request)
             .send()
             .await
@@ -127,6 +171,32 @@ impl DstackVerifierClient {
     }
 }
 
+/// Record the latency and outcome of a validator verification by
+/// dstack-verifier, see [`VERIFIER_LATENCY_METRIC`] and [`VERIFIER_CHECKS_METRIC`]
+pub fn record_verification(result: &Result<VerificationResponse>, latency: Duration) {
+    let outcome = match result {
+        Ok(response) if response.is_valid => "valid",
+        Ok(_) => "invalid",
+        Err(_) => "error",
+    };
+    metrics::histogram!(VERIFIER_LATENCY_METRIC, "outcome" => outcome)
+        .record(latency.as_secs_f64());
+
+    let Ok(response) = result else {
+        return;
+    };
+    let details = &response.details;
+    for (check, verified) in [
+        ("quote_verified", details.quote_verified),
+        ("event_log_verified", details.event_log_verified),
+        ("os_image_hash_verified", details.os_image_hash_verified),
+    ] {
+        let result = if verified { "passed" } else { "failed" };
+        metrics::counter!(VERIFIER_CHECKS_METRIC, "check" => check, "result" => result)
+            .increment(1);
+    }
+}
+
 /// Parse VM configuration from validator data
 pub fn parse_validator_vm_config(validator_data: &serde_json::Value) -> Result<String> {
     // Extract VM configuration from validator attestation data
@@ -151,3 +221,84 @@ pub fn parse_validator_vm_config(validator_data: &serde_json::Value) -> Result<S
         os_image_hash,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attestation(dev_mode: bool) -> AttestationConfig {
+        AttestationConfig {
+            tee_enforced: true,
+            dev_mode,
+            session_timeout: 3600,
+            refresh_token_ttl: 86400,
+            pccs_url: None,
+            dcap_enabled: false,
+            verification_timeout: 30,
+            hash_algorithm: Default::default(),
+            token_audiences: vec!["platform-executor".to_string()],
+            allowed_app_ids: None,
+            token_signing_key: None,
+        }
+    }
+
+    #[test]
+    fn test_verifier_mode_matches_configuration() {
+        let verifier = DstackVerifierClient::new("http://localhost:8080".to_string()).unwrap();
+        assert_eq!(
+            VerifierMode::resolve(Some(&verifier), &attestation(false)),
+            VerifierMode::FullDstack
+        );
+        assert_eq!(
+            VerifierMode::resolve(Some(&verifier), &attestation(true)),
+            VerifierMode::FullDstack
+        );
+        assert_eq!(
+            VerifierMode::resolve(None, &attestation(false)),
+            VerifierMode::BuiltInQuoteOnly
+        );
+        assert_eq!(
+            VerifierMode::resolve(None, &attestation(true)),
+            VerifierMode::Mock
+        );
+    }
+
+    #[test]
+    fn test_verification_outcomes_recorded() {
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let response = VerificationResponse {
+            is_valid: false,
+            details: VerificationDetails {
+                quote_verified: true,
+                event_log_verified: true,
+                os_image_hash_verified: false,
+                report_data: None,
+                tcb_status: None,
+                advisory_ids: vec![],
+                app_info: None,
+            },
+            reason: Some("os image hash mismatch".to_string()),
+        };
+        metrics::with_local_recorder(&recorder, || {
+            record_verification(&Ok(response), Duration::from_millis(250));
+            record_verification(&Err(anyhow::anyhow!("timed out")), Duration::from_secs(30));
+        });
+
+        let rendered = handle.render();
+        for expected in [
+            r#"dstack_verifier_checks_total{check="quote_verified",result="passed"} 1"#,
+            r#"dstack_verifier_checks_total{check="event_log_verified",result="passed"} 1"#,
+            r#"dstack_verifier_checks_total{check="os_image_hash_verified",result="failed"} 1"#,
+            r#"dstack_verifier_latency_seconds_count{outcome="invalid"} 1"#,
+            r#"dstack_verifier_latency_seconds_count{outcome="error"} 1"#,
+        ] {
+            assert!(
+                rendered.contains(expected),
+                "{} missing from:\n{}",
+                expected,
+                rendered
+            );
+        }
+    }
+}
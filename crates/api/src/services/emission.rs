@@ -0,0 +1,269 @@
+//! Epoch-based emission periods
+//!
+//! Emissions are accounted over epochs, consecutive block ranges of a
+//! configurable length stored in `emission_epochs`. The epoch task started by
+//! `background::start_emission_epoch_task` follows the chain and starts a new
+//! epoch once the latest block reaches the end of the current one. Every
+//! epoch start is published to [`EmissionService::subscribe`] and, through
+//! `NOTIFY` on [`EPOCH_STARTED_CHANNEL`], to other instances.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use platform_api_models::EmissionEpoch;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::info;
+
+/// Postgres channel notified with the JSON epoch whenever an epoch starts
+pub const EPOCH_STARTED_CHANNEL: &str = "emission_epoch_started";
+
+pub use platform_api_models::DEFAULT_EPOCH_BLOCKS;
+
+#[derive(sqlx::FromRow)]
+struct EpochRow {
+    epoch_number: i64,
+    start_block: i64,
+    end_block: i64,
+    total_emissions: f64,
+    distributed_at: Option<DateTime<Utc>>,
+}
+
+impl From<EpochRow> for EmissionEpoch {
+    fn from(row: EpochRow) -> Self {
+        Self {
+            epoch_number: row.epoch_number as u64,
+            start_block: row.start_block as u64,
+            end_block: row.end_block as u64,
+            total_emissions: row.total_emissions,
+            distributed_at: row.distributed_at,
+        }
+    }
+}
+
+/// First block of the epoch to start at `current_block`, if one is due
+///
+/// The first epoch starts at `current_block`. Later ones follow the last epoch
+/// back to back; epochs missed while no instance was running are skipped, so
+/// the new epoch is the one containing `current_block`.
+pub fn next_epoch_start(
+    last: Option<&EmissionEpoch>,
+    current_block: u64,
+    duration_blocks: u64,
+) -> Option<u64> {
+    match last {
+        None => Some(current_block),
+        Some(last) if current_block >= last.end_block => {
+            let duration = duration_blocks.max(1);
+            Some(last.end_block + (current_block - last.end_block) / duration * duration)
+        }
+        Some(_) => None,
+    }
+}
+
+/// Starts and tracks emission epochs
+pub struct EmissionService {
+    database_pool: Arc<PgPool>,
+    events: broadcast::Sender<EmissionEpoch>,
+}
+
+impl EmissionService {
+    pub fn new(database_pool: Arc<PgPool>) -> Self {
+        let (events, _) = broadcast::channel(16);
+        Self {
+            database_pool,
+            events,
+        }
+    }
+
+    /// Subscribe to epoch starts of this instance
+    pub fn subscribe(&self) -> broadcast::Receiver<EmissionEpoch> {
+        self.events.subscribe()
+    }
+
+    /// Latest started epoch
+    pub async fn latest_epoch(&self) -> Result<Option<EmissionEpoch>> {
+        Self::fetch_latest_epoch(self.database_pool.as_ref()).await
+    }
+
+    /// Start the epoch following the latest one, spanning `duration_blocks`
+    /// blocks from `start_block`
+    ///
+    /// `start_block` must not fall inside the latest epoch.
+    pub async fn start_epoch(
+        &self,
+        start_block: u64,
+        duration_blocks: u64,
+        total_emissions: f64,
+    ) -> Result<EmissionEpoch> {
+        let mut tx = self.lock_epochs().await?;
+        let last = Self::fetch_latest_epoch(&mut *tx).await?;
+        let epoch = Self::insert_epoch(
+            &mut tx,
+            last.as_ref(),
+            start_block,
+            duration_blocks,
+            total_emissions,
+        )
+        .await?;
+        tx.commit().await?;
+
+        self.publish(&epoch);
+        Ok(epoch)
+    }
+
+    /// Start a new epoch if `current_block` reached the end of the latest one
+    ///
+    /// Returns the started epoch, if any. Safe to call from several instances
+    /// at once: only one of them starts the epoch.
+    pub async fn advance(
+        &self,
+        current_block: u64,
+        duration_blocks: u64,
+        total_emissions: f64,
+    ) -> Result<Option<EmissionEpoch>> {
+        let mut tx = self.lock_epochs().await?;
+        let last = Self::fetch_latest_epoch(&mut *tx).await?;
+        let Some(start_block) = next_epoch_start(last.as_ref(), current_block, duration_blocks)
+        else {
+            return Ok(None);
+        };
+        let epoch = Self::insert_epoch(
+            &mut tx,
+            last.as_ref(),
+            start_block,
+            duration_blocks,
+            total_emissions,
+        )
+        .await?;
+        tx.commit().await?;
+
+        self.publish(&epoch);
+        Ok(Some(epoch))
+    }
+
+    /// Begin a transaction holding the only right to start epochs
+    async fn lock_epochs(&self) -> Result<Transaction<'static, Postgres>> {
+        let mut tx = self.database_pool.begin().await?;
+        sqlx::query("LOCK TABLE emission_epochs IN SHARE ROW EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await?;
+        Ok(tx)
+    }
+
+    async fn fetch_latest_epoch<'e>(
+        executor: impl sqlx::PgExecutor<'e>,
+    ) -> Result<Option<EmissionEpoch>> {
+        let row = sqlx::query_as::<_, EpochRow>(
+            r#"
+            SELECT epoch_number, start_block, end_block, total_emissions, distributed_at
+            FROM emission_epochs
+            ORDER BY epoch_number DESC
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(executor)
+        .await?;
+
+        Ok(row.map(Into::into))
+    }
+
+    async fn insert_epoch(
+        tx: &mut Transaction<'static, Postgres>,
+        last: Option<&EmissionEpoch>,
+        start_block: u64,
+        duration_blocks: u64,
+        total_emissions: f64,
+    ) -> Result<EmissionEpoch> {
+        if duration_blocks == 0 {
+            anyhow::bail!("Epoch duration must be at least one block");
+        }
+        if !total_emissions.is_finite() || total_emissions < 0.0 {
+            anyhow::bail!(
+                "Epoch emissions must be a non-negative amount, got {}",
+                total_emissions
+            );
+        }
+        if let Some(last) = last {
+            if start_block < last.end_block {
+                anyhow::bail!(
+                    "Epoch cannot start at block {}, epoch {} runs until block {}",
+                    start_block,
+                    last.epoch_number,
+                    last.end_block
+                );
+            }
+        }
+
+        let epoch = EmissionEpoch {
+            epoch_number: last.map_or(1, |last| last.epoch_number + 1),
+            start_block,
+            end_block: start_block + duration_blocks,
+            total_emissions,
+            distributed_at: None,
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO emission_epochs (epoch_number, start_block, end_block, total_emissions)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(epoch.epoch_number as i64)
+        .bind(epoch.start_block as i64)
+        .bind(epoch.end_block as i64)
+        .bind(epoch.total_emissions)
+        .execute(&mut **tx)
+        .await?;
+
+        // Delivered to listeners on commit only
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(EPOCH_STARTED_CHANNEL)
+            .bind(serde_json::to_string(&epoch)?)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(epoch)
+    }
+
+    fn publish(&self, epoch: &EmissionEpoch) {
+        info!(
+            epoch_number = epoch.epoch_number,
+            start_block = epoch.start_block,
+            end_block = epoch.end_block,
+            total_emissions = epoch.total_emissions,
+            "Started emission epoch"
+        );
+        // No subscribers is fine
+        let _ = self.events.send(epoch.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn epoch(start_block: u64, end_block: u64) -> EmissionEpoch {
+        EmissionEpoch {
+            epoch_number: 1,
+            start_block,
+            end_block,
+            total_emissions: 0.0,
+            distributed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_next_epoch_start() {
+        // The first epoch starts right away
+        assert_eq!(next_epoch_start(None, 1_000, 360), Some(1_000));
+
+        let last = epoch(1_000, 1_360);
+        assert_eq!(next_epoch_start(Some(&last), 1_359, 360), None);
+        assert_eq!(next_epoch_start(Some(&last), 1_360, 360), Some(1_360));
+        assert_eq!(next_epoch_start(Some(&last), 1_500, 360), Some(1_360));
+
+        // Missed epochs are skipped, the new one stays on the epoch grid
+        assert_eq!(next_epoch_start(Some(&last), 2_100, 360), Some(2_080));
+    }
+}
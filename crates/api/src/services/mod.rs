@@ -1,5 +1,9 @@
 pub mod bittensor;
 pub mod dstack_verifier;
+pub mod emission;
+pub mod settings;
 
 pub use bittensor::BittensorService;
-pub use dstack_verifier::DstackVerifierClient;
+pub use dstack_verifier::{DstackVerifierClient, VerifierMode};
+pub use emission::EmissionService;
+pub use settings::SettingsHandle;
@@ -0,0 +1,524 @@
+//! Runtime-tunable platform settings
+//!
+//! Operators override a whitelisted set of settings through the admin API.
+//! Overrides are persisted in `platform_settings`, every change is recorded in
+//! `platform_settings_audit`, and services pick up new values through
+//! [`SettingsHandle::subscribe`] without a restart.
+
+use chrono::{DateTime, Utc};
+use platform_api_scheduler::{SchedulerConfig, SchedulerService};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{watch, RwLock};
+use tracing::{info, warn};
+
+/// Postgres channel notified whenever a setting changes
+pub const SETTINGS_CHANGED_CHANNEL: &str = "platform_settings_changed";
+
+pub const SCHEDULER_JOB_TIMEOUT: &str = "scheduler.job_timeout_secs";
+pub const SCHEDULER_RETRY_ATTEMPTS: &str = "scheduler.retry_attempts";
+pub const SCHEDULER_RETRY_DELAY: &str = "scheduler.retry_delay_secs";
+pub const SCHEDULER_MAX_CONCURRENT_JOBS: &str = "scheduler.max_concurrent_jobs";
+
+/// Value type and bounds of a setting
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SettingType {
+    Integer { min: i64, max: i64 },
+    Boolean,
+}
+
+/// A setting that may be tuned at runtime
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SettingDefinition {
+    pub key: &'static str,
+    pub description: &'static str,
+    #[serde(flatten)]
+    pub setting_type: SettingType,
+}
+
+impl SettingDefinition {
+    /// Check that `value` has the right type and lies within bounds
+    pub fn validate(&self, value: &JsonValue) -> Result<(), SettingsError> {
+        let invalid = |reason: String| SettingsError::InvalidValue {
+            key: self.key.to_string(),
+            reason,
+        };
+
+        match self.setting_type {
+            SettingType::Integer { min, max } => {
+                let n = value
+                    .as_i64()
+                    .ok_or_else(|| invalid("expected an integer".to_string()))?;
+                if n < min || n > max {
+                    return Err(invalid(format!(
+                        "{} is outside the allowed range {}..={}",
+                        n, min, max
+                    )));
+                }
+            }
+            SettingType::Boolean => {
+                if !value.is_boolean() {
+                    return Err(invalid("expected a boolean".to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whitelisted runtime-tunable settings
+pub const SETTING_DEFINITIONS: &[SettingDefinition] = &[
+    SettingDefinition {
+        key: SCHEDULER_JOB_TIMEOUT,
+        description: "Default job timeout in seconds",
+        setting_type: SettingType::Integer { min: 1, max: 7 * 24 * 3600 },
+    },
+    SettingDefinition {
+        key: SCHEDULER_RETRY_ATTEMPTS,
+        description: "Default number of retries for failed jobs",
+        setting_type: SettingType::Integer { min: 0, max: 20 },
+    },
+    SettingDefinition {
+        key: SCHEDULER_RETRY_DELAY,
+        description: "Delay in seconds before a failed job is retried",
+        setting_type: SettingType::Integer { min: 0, max: 24 * 3600 },
+    },
+    SettingDefinition {
+        key: SCHEDULER_MAX_CONCURRENT_JOBS,
+        description: "Maximum number of concurrently running jobs",
+        setting_type: SettingType::Integer { min: 1, max: 100_000 },
+    },
+];
+
+/// Look up the definition of a setting
+pub fn setting_definition(key: &str) -> Option<&'static SettingDefinition> {
+    SETTING_DEFINITIONS.iter().find(|d| d.key == key)
+}
+
+/// Settings errors
+#[derive(Debug)]
+pub enum SettingsError {
+    UnknownSetting(String),
+    InvalidValue { key: String, reason: String },
+    Storage(anyhow::Error),
+}
+
+impl std::fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::UnknownSetting(key) => write!(f, "Unknown setting: {}", key),
+            SettingsError::InvalidValue { key, reason } => {
+                write!(f, "Invalid value for {}: {}", key, reason)
+            }
+            SettingsError::Storage(e) => write!(f, "Settings storage error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<sqlx::Error> for SettingsError {
+    fn from(err: sqlx::Error) -> Self {
+        SettingsError::Storage(err.into())
+    }
+}
+
+/// Current override of a setting
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingValue {
+    pub key: String,
+    pub value: JsonValue,
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One entry of the settings audit trail
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SettingChange {
+    pub key: String,
+    pub old_value: Option<JsonValue>,
+    pub new_value: JsonValue,
+    pub changed_by: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Shared, cached view of the runtime settings
+///
+/// Reads hit the in-memory cache. Writes go to PostgreSQL when available and
+/// notify other instances through [`SETTINGS_CHANGED_CHANNEL`].
+#[derive(Clone)]
+pub struct SettingsHandle {
+    database_pool: Option<Arc<PgPool>>,
+    values: Arc<RwLock<HashMap<String, SettingValue>>>,
+    // Fallback audit trail if no database pool
+    audit: Arc<RwLock<Vec<SettingChange>>>,
+    version: Arc<watch::Sender<u64>>,
+}
+
+impl SettingsHandle {
+    pub fn new(database_pool: Option<Arc<PgPool>>) -> Self {
+        let (version, _) = watch::channel(0);
+        Self {
+            database_pool,
+            values: Arc::new(RwLock::new(HashMap::new())),
+            audit: Arc::new(RwLock::new(Vec::new())),
+            version: Arc::new(version),
+        }
+    }
+
+    /// Subscribe to change notifications; the value is bumped on every change
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.version.subscribe()
+    }
+
+    /// Get the current override of a setting
+    pub async fn get(&self, key: &str) -> Option<JsonValue> {
+        self.values.read().await.get(key).map(|v| v.value.clone())
+    }
+
+    /// Get an integer setting, if overridden
+    pub async fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get(key).await.and_then(|v| v.as_i64())
+    }
+
+    /// List all current overrides
+    pub async fn list(&self) -> Vec<SettingValue> {
+        let mut values: Vec<SettingValue> = self.values.read().await.values().cloned().collect();
+        values.sort_by(|a, b| a.key.cmp(&b.key));
+        values
+    }
+
+    /// Validate and store a new value, recording the change in the audit trail
+    pub async fn set(
+        &self,
+        key: &str,
+        value: JsonValue,
+        changed_by: &str,
+    ) -> Result<SettingValue, SettingsError> {
+        let definition = setting_definition(key)
+            .ok_or_else(|| SettingsError::UnknownSetting(key.to_string()))?;
+        definition.validate(&value)?;
+
+        let now = Utc::now();
+        let setting = SettingValue {
+            key: key.to_string(),
+            value,
+            updated_by: changed_by.to_string(),
+            updated_at: now,
+        };
+
+        // The value replaced is read under the same lock as the write, so that
+        // concurrent changes each record the value they actually replaced
+        let mut values = self.values.write().await;
+        let old_value = if let Some(pool) = &self.database_pool {
+            let mut tx = pool.begin().await?;
+
+            // Also serializes first-time writes of a key, which have no row to lock
+            sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+                .bind(key)
+                .execute(&mut *tx)
+                .await?;
+            let old_value: Option<JsonValue> =
+                sqlx::query_scalar("SELECT value FROM platform_settings WHERE key = $1")
+                    .bind(key)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO platform_settings (key, value, updated_by, updated_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (key) DO UPDATE
+                SET value = EXCLUDED.value, updated_by = EXCLUDED.updated_by, updated_at = EXCLUDED.updated_at
+                "#,
+            )
+            .bind(key)
+            .bind(&setting.value)
+            .bind(changed_by)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO platform_settings_audit (key, old_value, new_value, changed_by, changed_at)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(key)
+            .bind(&old_value)
+            .bind(&setting.value)
+            .bind(changed_by)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query("SELECT pg_notify($1, $2)")
+                .bind(SETTINGS_CHANGED_CHANNEL)
+                .bind(key)
+                .execute(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+            old_value
+        } else {
+            let old_value = values.get(key).map(|v| v.value.clone());
+            self.audit.write().await.push(SettingChange {
+                key: key.to_string(),
+                old_value: old_value.clone(),
+                new_value: setting.value.clone(),
+                changed_by: changed_by.to_string(),
+                changed_at: now,
+            });
+            old_value
+        };
+
+        values.insert(key.to_string(), setting.clone());
+        drop(values);
+        self.version.send_modify(|v| *v += 1);
+
+        info!(
+            key = key,
+            old_value = ?old_value,
+            new_value = %setting.value,
+            changed_by = changed_by,
+            "Platform setting changed"
+        );
+
+        Ok(setting)
+    }
+
+    /// Reload all overrides from the database
+    pub async fn refresh(&self) -> Result<(), SettingsError> {
+        let Some(pool) = &self.database_pool else {
+            return Ok(());
+        };
+
+        #[derive(sqlx::FromRow)]
+        struct SettingRow {
+            key: String,
+            value: JsonValue,
+            updated_by: String,
+            updated_at: DateTime<Utc>,
+        }
+
+        let rows = sqlx::query_as::<_, SettingRow>(
+            "SELECT key, value, updated_by, updated_at FROM platform_settings",
+        )
+        .fetch_all(pool.as_ref())
+        .await?;
+
+        let mut values = HashMap::new();
+        for row in rows {
+            // Ignore rows that were valid under an older whitelist
+            match setting_definition(&row.key).map(|d| d.validate(&row.value)) {
+                Some(Ok(())) => {
+                    values.insert(
+                        row.key.clone(),
+                        SettingValue {
+                            key: row.key,
+                            value: row.value,
+                            updated_by: row.updated_by,
+                            updated_at: row.updated_at,
+                        },
+                    );
+                }
+                Some(Err(e)) => warn!("Ignoring stored setting: {}", e),
+                None => warn!("Ignoring unknown stored setting: {}", row.key),
+            }
+        }
+
+        *self.values.write().await = values;
+        self.version.send_modify(|v| *v += 1);
+        Ok(())
+    }
+
+    /// Get the audit trail of a setting, most recent first
+    pub async fn history(&self, key: &str, limit: i64) -> Result<Vec<SettingChange>, SettingsError> {
+        if let Some(pool) = &self.database_pool {
+            let changes = sqlx::query_as::<_, SettingChange>(
+                r#"
+                SELECT key, old_value, new_value, changed_by, changed_at
+                FROM platform_settings_audit
+                WHERE key = $1
+                ORDER BY changed_at DESC
+                LIMIT $2
+                "#,
+            )
+            .bind(key)
+            .bind(limit)
+            .fetch_all(pool.as_ref())
+            .await?;
+            Ok(changes)
+        } else {
+            let audit = self.audit.read().await;
+            Ok(audit
+                .iter()
+                .rev()
+                .filter(|c| c.key == key)
+                .take(limit.max(0) as usize)
+                .cloned()
+                .collect())
+        }
+    }
+
+    /// Scheduler configuration with the current overrides applied on top of `base`
+    pub async fn scheduler_config(&self, base: &SchedulerConfig) -> SchedulerConfig {
+        let mut config = base.clone();
+        if let Some(v) = self.get_i64(SCHEDULER_JOB_TIMEOUT).await {
+            config.job_timeout = v as u64;
+        }
+        if let Some(v) = self.get_i64(SCHEDULER_RETRY_ATTEMPTS).await {
+            config.retry_attempts = v as u32;
+        }
+        if let Some(v) = self.get_i64(SCHEDULER_RETRY_DELAY).await {
+            config.retry_delay = v as u64;
+        }
+        if let Some(v) = self.get_i64(SCHEDULER_MAX_CONCURRENT_JOBS).await {
+            config.max_concurrent_jobs = v as u32;
+        }
+        config
+    }
+
+    /// Push setting changes into the scheduler until the handle is dropped
+    pub async fn watch_scheduler(&self, base: SchedulerConfig, scheduler: Arc<SchedulerService>) {
+        let mut changes = self.subscribe();
+        loop {
+            scheduler.update_config(self.scheduler_config(&base).await).await;
+            if changes.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_invalid_values_are_rejected() {
+        let settings = SettingsHandle::new(None);
+
+        let err = settings
+            .set(SCHEDULER_JOB_TIMEOUT, json!(-5), "127.0.0.1")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SettingsError::InvalidValue { .. }));
+
+        let err = settings
+            .set(SCHEDULER_RETRY_ATTEMPTS, json!("three"), "127.0.0.1")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SettingsError::InvalidValue { .. }));
+
+        let err = settings
+            .set("scheduler.unknown", json!(1), "127.0.0.1")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SettingsError::UnknownSetting(_)));
+
+        assert!(settings.get(SCHEDULER_JOB_TIMEOUT).await.is_none());
+        assert!(settings
+            .history(SCHEDULER_JOB_TIMEOUT, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_picks_up_changes_live() {
+        let settings = SettingsHandle::new(None);
+        let base = SchedulerConfig::default();
+        let scheduler = Arc::new(SchedulerService::new(&base).unwrap());
+
+        let watcher = tokio::spawn({
+            let settings = settings.clone();
+            let scheduler = scheduler.clone();
+            async move { settings.watch_scheduler(base, scheduler).await }
+        });
+
+        settings
+            .set(SCHEDULER_JOB_TIMEOUT, json!(120), "127.0.0.1")
+            .await
+            .unwrap();
+
+        let mut job_timeout = 0;
+        for _ in 0..50 {
+            job_timeout = scheduler.config().await.job_timeout;
+            if job_timeout == 120 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(job_timeout, 120);
+
+        watcher.abort();
+    }
+
+    #[tokio::test]
+    async fn test_changes_are_audited() {
+        let settings = SettingsHandle::new(None);
+
+        settings
+            .set(SCHEDULER_RETRY_ATTEMPTS, json!(5), "10.0.0.1")
+            .await
+            .unwrap();
+        settings
+            .set(SCHEDULER_RETRY_ATTEMPTS, json!(0), "10.0.0.2")
+            .await
+            .unwrap();
+
+        let history = settings.history(SCHEDULER_RETRY_ATTEMPTS, 10).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].changed_by, "10.0.0.2");
+        assert_eq!(history[0].old_value, Some(json!(5)));
+        assert_eq!(history[0].new_value, json!(0));
+        assert_eq!(history[1].changed_by, "10.0.0.1");
+        assert_eq!(history[1].old_value, None);
+
+        let current = settings.list().await;
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].updated_by, "10.0.0.2");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_changes_audit_the_value_they_replace() {
+        let settings = SettingsHandle::new(None);
+
+        let changes = (1..=20).map(|n| {
+            let settings = settings.clone();
+            tokio::spawn(async move {
+                settings
+                    .set(SCHEDULER_RETRY_ATTEMPTS, json!(n), "admin")
+                    .await
+                    .unwrap()
+            })
+        });
+        for change in changes.collect::<Vec<_>>() {
+            change.await.unwrap();
+        }
+
+        // Oldest first, every change replaced the one before it
+        let mut history = settings
+            .history(SCHEDULER_RETRY_ATTEMPTS, 50)
+            .await
+            .unwrap();
+        history.reverse();
+        assert_eq!(history.len(), 20);
+        assert_eq!(history[0].old_value, None);
+        for pair in history.windows(2) {
+            assert_eq!(pair[1].old_value.as_ref(), Some(&pair[0].new_value));
+        }
+        assert_eq!(
+            settings.get(SCHEDULER_RETRY_ATTEMPTS).await.as_ref(),
+            Some(&history[19].new_value)
+        );
+    }
+}
@@ -1,19 +1,30 @@
 use crate::challenge_runner::ChallengeRunner;
+use crate::message_channel::{ChannelConfig, MessageSender};
+use crate::middleware::auth::{AuthConfig, SeenNonces};
+use crate::middleware::cors::CorsConfig;
+use crate::middleware::maintenance::MaintenanceMode;
+use crate::middleware::rate_limit::RateLimitConfig;
 use crate::models::JobCache;
-use platform_api_orm_gateway::{ORMGatewayConfig, SecureORMGateway};
+use crate::routes::challenge_proxy::ChallengeProxyConfig;
+use platform_api_orm_gateway::{AuditConfig, ORMGatewayConfig, QueryAuditor, SecureORMGateway};
 use crate::redis_client::RedisClient;
 use crate::security::PlatformSecurity;
-use crate::services::{BittensorService, DstackVerifierClient};
-use chrono::{DateTime, Utc};
+use crate::services::{
+    BittensorService, DstackVerifierClient, EmissionService, SettingsHandle, VerifierMode,
+};
+use chacha20poly1305::ChaCha20Poly1305;
+use chrono::{DateTime, Duration, Utc};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
 use platform_api_attestation::AttestationService;
 use platform_api_builder::BuilderService;
 use platform_api_kbs::KeyBrokerService;
-use platform_api_models::{ChallengeSpec, ValidatorChallengeStatus};
-use platform_api_scheduler::SchedulerService;
+use platform_api_models::{ChallengeSpec, Hotkey, SessionToken, ValidatorChallengeStatus};
+use platform_api_scheduler::{SchedulerService, WebhookDispatcher};
 use platform_api_storage::{MemoryStorageBackend, StorageBackend};
 use sqlx::{PgPool, Row};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
 use tracing::{info, warn};
 
 /// Application state shared across all handlers
@@ -39,20 +50,63 @@ pub struct AppState {
     pub redis_client: Option<Arc<RedisClient>>,         // Redis client for job progress logging
     pub chutes_api_token: Arc<tokio::sync::RwLock<Option<String>>>, // CHUTES API token for platform-api (decrypted)
     pub bittensor: Option<Arc<BittensorService>>, // Bittensor service for blockchain queries
+    pub emissions: Option<Arc<EmissionService>>,  // Emission epochs, requires PostgreSQL
     pub dstack_verifier: Option<Arc<DstackVerifierClient>>, // DStack verifier for full platform verification
+    pub maintenance: Arc<MaintenanceMode>, // Maintenance flag that freezes mutating routes
+    pub settings: SettingsHandle, // Runtime-tunable settings overridden via the admin API
+    pub suspended_sessions: Arc<tokio::sync::RwLock<HashMap<SessionToken, SuspendedSession>>>, // Key: session_token
+    pub revoked_session_tokens: Arc<tokio::sync::RwLock<HashSet<SessionToken>>>,
+    pub seen_request_nonces: Arc<SeenNonces>, // Nonces of signed requests still within their max age
+    pub session_resume_grace: Duration, // How long a disconnected validator session can be resumed
 }
 
 /// Validator connection information
 #[derive(Debug, Clone)]
 pub struct ValidatorConnection {
-    pub validator_hotkey: String,
+    pub validator_hotkey: Hotkey,
     pub app_id: Option<String>,
     pub instance_id: Option<String>,
     pub compose_hash: Option<String>,
     pub connected_at: DateTime<Utc>,
-    pub session_token: String,
+    pub session_token: SessionToken,
     pub last_ping: DateTime<Utc>,
-    pub message_sender: Option<Arc<tokio::sync::mpsc::Sender<String>>>, // Channel to send messages to validator WebSocket (via mpsc channel)
+    pub message_sender: Option<MessageSender>, // Channel to send messages to validator WebSocket, see `AppConfig::validator_channel`
+    pub subscriptions: Vec<String>, // Compose hashes of the challenges the validator subscribed to
+    pub assigned_jobs: Vec<String>, // Jobs assigned to the validator over this connection
+}
+
+/// Default time a disconnected validator session can be resumed
+pub const DEFAULT_SESSION_RESUME_GRACE_SECS: i64 = 300;
+
+/// State of a disconnected validator, kept so that it can resume its session
+/// without a new attestation
+#[derive(Clone)]
+pub struct SuspendedSession {
+    pub connection: ValidatorConnection,
+    pub cipher: ChaCha20Poly1305,
+    pub disconnected_at: DateTime<Utc>,
+}
+
+impl SuspendedSession {
+    /// Check that the session can be resumed by `hotkey` from `instance_id` at `now`
+    pub fn check_resumable(
+        &self,
+        hotkey: &str,
+        instance_id: Option<&str>,
+        now: DateTime<Utc>,
+        grace: Duration,
+    ) -> anyhow::Result<()> {
+        if self.connection.validator_hotkey != hotkey {
+            return Err(anyhow::anyhow!("Session belongs to another validator"));
+        }
+        if now > self.disconnected_at + grace {
+            return Err(anyhow::anyhow!("Session expired"));
+        }
+        if self.connection.instance_id.as_deref() != instance_id {
+            return Err(anyhow::anyhow!("Session was opened from another instance"));
+        }
+        Ok(())
+    }
 }
 
 /// Application configuration
@@ -67,6 +121,24 @@ pub struct AppConfig {
     pub scheduler_config: SchedulerConfig,
     pub builder_config: BuilderConfig,
     pub metrics_config: MetricsConfig,
+    pub cors_config: CorsConfig,
+    pub rate_limit_config: RateLimitConfig,
+    pub auth_config: AuthConfig,
+    /// Ranges validator WebSocket connections may come from; empty allows any address
+    pub ws_allowed_cidr_ranges: Vec<String>,
+    /// Wrap successful JSON responses in an envelope with request metadata
+    pub response_envelope_enabled: bool,
+    /// Largest job payload distributed to validators, in bytes of its JSON
+    /// serialization
+    pub max_job_payload_bytes: usize,
+    /// Most validators a distributed job is sent to, the least loaded first;
+    /// `None` sends it to every active validator
+    pub max_job_fanout: Option<usize>,
+    /// Capacity and overflow policy of the channels of the messages sent to
+    /// validator connections
+    pub validator_channel: ChannelConfig,
+    /// Limits of the requests proxied to challenge CVMs
+    pub challenge_proxy: ChallengeProxyConfig,
 }
 
 // Config types are now imported from their respective crates
@@ -86,30 +158,63 @@ pub struct MetricsConfig {
 }
 
 /// Metrics service
+///
+/// When enabled, the metrics recorded with the `metrics` macros, such as
+/// those of dstack-verifier calls, are rendered after `metrics`.
 #[derive(Clone)]
 pub struct MetricsService {
     pub metrics: String,
+    prometheus: Option<PrometheusHandle>,
 }
 
 impl MetricsService {
-    pub fn new(_config: &MetricsConfig) -> anyhow::Result<Self> {
+    pub fn new(config: &MetricsConfig) -> anyhow::Result<Self> {
         Ok(Self {
             metrics: "# platform-api metrics\n".to_string(),
+            prometheus: config.enabled.then(prometheus_handle),
         })
     }
 
     pub fn get_metrics(&self) -> anyhow::Result<String> {
-        Ok(self.metrics.clone())
+        let mut metrics = self.metrics.clone();
+        if let Some(prometheus) = &self.prometheus {
+            metrics.push_str(&prometheus.render());
+        }
+        Ok(metrics)
     }
 }
 
+/// Buckets of the latency histograms, metrics named `*_seconds`
+const LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Handle of the Prometheus recorder of the `metrics` macros, installed as
+/// the global recorder the first time it is needed
+fn prometheus_handle() -> PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+    HANDLE
+        .get_or_init(|| {
+            let recorder = PrometheusBuilder::new()
+                .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), &LATENCY_BUCKETS)
+                .expect("latency buckets are not empty")
+                .build_recorder();
+            let handle = recorder.handle();
+            if let Err(e) = metrics::set_global_recorder(recorder) {
+                warn!("Failed to install the metrics recorder: {}", e);
+            }
+            handle
+        })
+        .clone()
+}
+
 impl AppState {
     pub async fn new(config: AppConfig) -> anyhow::Result<Self> {
         // Initialize storage backend based on configuration
         let (storage, database_pool) = if config.storage_config.backend_type == "postgres" {
             use platform_api_storage::PostgresStorageBackend;
             info!("Using PostgreSQL storage backend");
-            let pg_backend = PostgresStorageBackend::new(&config.database_url).await?;
+            let pg_backend = PostgresStorageBackend::new(&config.database_url)
+                .await?
+                .with_circuit_breaker(config.storage_config.circuit_breaker.clone());
             let pool = pg_backend.get_db_pool().clone();
             (
                 Arc::new(pg_backend) as Arc<dyn StorageBackend>,
@@ -127,29 +232,14 @@ impl AppState {
         let attestation = Arc::new(AttestationService::new(&config.attestation_config)?);
         let kbs = Arc::new(KeyBrokerService::new(&config.kbs_config)?);
 
-        // Initialize scheduler with database pool if available
-        let scheduler = if let Some(ref pool) = database_pool {
-            Arc::new(SchedulerService::with_database(
-                &config.scheduler_config,
-                pool.clone(),
-            )?)
-        } else {
-            Arc::new(SchedulerService::new(&config.scheduler_config)?)
-        };
-
-        let builder = Arc::new(BuilderService::new(
-            &config.builder_config,
-            database_pool.clone(),
-        )?);
-        let metrics = Arc::new(MetricsService::new(&config.metrics_config)?);
-
         // Initialize security
         tracing::info!("Initializing PlatformSecurity");
         let security = match PlatformSecurity::new() {
             Ok(sec) => {
                 let pub_key = sec.get_public_key();
                 tracing::info!(
-                    "✅ PlatformSecurity initialized successfully. Public key length: {} bytes",
+                    "✅ PlatformSecurity initialized successfully. Key ID: {}, public key length: {} bytes",
+                    sec.get_key_id(),
                     pub_key.len()
                 );
                 if pub_key.is_empty() {
@@ -165,10 +255,43 @@ impl AppState {
                 ));
             }
         };
+
+        // Initialize scheduler with database pool if available
+        let scheduler = if let Some(ref pool) = database_pool {
+            SchedulerService::with_database(&config.scheduler_config, pool.clone())?
+        } else {
+            SchedulerService::new(&config.scheduler_config)?
+        };
+        // Job events sent to challenge webhooks are signed with the platform key
+        let scheduler =
+            Arc::new(scheduler.with_webhooks(WebhookDispatcher::new(security.clone())));
+
+        let builder = Arc::new(BuilderService::new(
+            &config.builder_config,
+            database_pool.clone(),
+        )?);
+        match builder.image_builds().fail_interrupted().await {
+            Ok(0) => {}
+            Ok(count) => warn!(
+                "Marked {} image builds interrupted by a restart as failed",
+                count
+            ),
+            Err(e) => warn!("Failed to mark interrupted image builds as failed: {}", e),
+        }
+        if let Err(e) = builder.image_builds().cache().load().await {
+            warn!("Failed to load the image build cache: {}", e);
+        }
+        let metrics = Arc::new(MetricsService::new(&config.metrics_config)?);
         let validator_connections = Arc::new(tokio::sync::RwLock::new(HashMap::new()));
         let challenge_registry = Arc::new(tokio::sync::RwLock::new(HashMap::new()));
         let validator_challenge_status = Arc::new(tokio::sync::RwLock::new(HashMap::new()));
         let job_cache = Arc::new(tokio::sync::RwLock::new(HashMap::new()));
+        let session_resume_grace = Duration::seconds(
+            std::env::var("WS_SESSION_RESUME_GRACE_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_SESSION_RESUME_GRACE_SECS),
+        );
 
         // Initialize Redis client if REDIS_URL is set
         let redis_client = std::env::var("REDIS_URL")
@@ -187,23 +310,23 @@ impl AppState {
             });
 
         // Initialize ORM gateways if database pool is available
-        // Read-write gateway for direct SDK connections (public routes)
-        let orm_gateway = database_pool.as_ref().map(|pool| {
-            let orm_config = ORMGatewayConfig::read_write(); // Read-write for public routes
-            Arc::new(tokio::sync::RwLock::new(SecureORMGateway::new(
-                orm_config,
-                (**pool).clone(),
-            )))
-        });
-
-        // Read-only gateway for validator routes
-        let orm_gateway_readonly = database_pool.as_ref().map(|pool| {
-            let orm_config = ORMGatewayConfig::read_only(); // Read-only for validator
-            Arc::new(tokio::sync::RwLock::new(SecureORMGateway::new(
-                orm_config,
-                (**pool).clone(),
-            )))
+        let orm_gateways = database_pool.as_ref().map(|pool| {
+            // Both gateways record their queries in the same audit log
+            let auditor = QueryAuditor::spawn((**pool).clone(), AuditConfig::from_env());
+            let gateway = |orm_config| {
+                Arc::new(tokio::sync::RwLock::new(
+                    SecureORMGateway::new(orm_config, (**pool).clone())
+                        .with_auditor(auditor.clone()),
+                ))
+            };
+            (
+                // Read-write gateway for direct SDK connections (public routes)
+                gateway(ORMGatewayConfig::read_write()),
+                // Read-only gateway for validator routes
+                gateway(ORMGatewayConfig::read_only()),
+            )
         });
+        let (orm_gateway, orm_gateway_readonly) = orm_gateways.unzip();
 
         // Initialize CHUTES API token from database if available
         let chutes_api_token = Arc::new(tokio::sync::RwLock::new(None));
@@ -235,6 +358,10 @@ impl AppState {
             }
         };
 
+        let emissions = database_pool
+            .clone()
+            .map(|pool| Arc::new(EmissionService::new(pool)));
+
         // Initialize DStack verifier client if DSTACK_VERIFIER_URL is set
         let dstack_verifier = std::env::var("DSTACK_VERIFIER_URL")
             .ok()
@@ -250,6 +377,19 @@ impl AppState {
                     }
                 }
             });
+        if VerifierMode::resolve(dstack_verifier.as_deref(), &config.attestation_config)
+            == VerifierMode::BuiltInQuoteOnly
+        {
+            warn!(
+                "⚠️  DSTACK_VERIFIER_URL is not set: validator attestations are verified on their \
+                 TDX quote only, without event log, measurement or compose hash checks"
+            );
+        }
+
+        let settings = SettingsHandle::new(database_pool.clone());
+        if let Err(e) = settings.refresh().await {
+            warn!("Failed to load platform settings: {}", e);
+        }
 
         Ok(Self {
             storage,
@@ -271,14 +411,41 @@ impl AppState {
             redis_client,
             chutes_api_token,
             bittensor,
+            emissions,
             dstack_verifier,
+            maintenance: Arc::new(MaintenanceMode::new()),
+            settings,
+            suspended_sessions: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            revoked_session_tokens: Arc::new(tokio::sync::RwLock::new(HashSet::new())),
+            seen_request_nonces: Arc::new(SeenNonces::new()),
+            session_resume_grace,
         })
     }
 
-    /// Add a validator connection
-    pub async fn add_validator_connection(&self, conn: ValidatorConnection) {
+    /// How validator attestations are verified
+    pub fn verifier_mode(&self) -> VerifierMode {
+        VerifierMode::resolve(
+            self.dstack_verifier.as_deref(),
+            &self.config.attestation_config,
+        )
+    }
+
+    /// Add a validator connection, unless the validator is already connected
+    ///
+    /// The check and the insertion happen under a single lock, so of
+    /// concurrent connections of a validator only one is added.
+    pub async fn add_validator_connection(&self, conn: ValidatorConnection) -> anyhow::Result<()> {
         let mut connections = self.validator_connections.write().await;
-        connections.insert(conn.validator_hotkey.clone(), conn);
+        match connections.entry(conn.validator_hotkey.to_string()) {
+            Entry::Occupied(_) => Err(anyhow::anyhow!(
+                "Validator {} is already connected",
+                conn.validator_hotkey
+            )),
+            Entry::Vacant(entry) => {
+                entry.insert(conn);
+                Ok(())
+            }
+        }
     }
 
     /// Get a validator connection
@@ -299,6 +466,179 @@ impl AppState {
         connections.remove(hotkey);
     }
 
+    /// Remove the connection of validator `hotkey` if it is that of session
+    /// `token`, so that a connection replacing it is left in place
+    async fn take_validator_connection(
+        &self,
+        hotkey: &str,
+        token: &str,
+    ) -> Option<ValidatorConnection> {
+        let mut connections = self.validator_connections.write().await;
+        match connections.entry(hotkey.to_string()) {
+            Entry::Occupied(entry) if entry.get().session_token.as_str() == token => {
+                Some(entry.remove())
+            }
+            _ => None,
+        }
+    }
+
+    /// Remove the validator connection of session `token` and keep it
+    /// resumable for `session_resume_grace`
+    pub async fn suspend_validator_connection(
+        &self,
+        hotkey: &str,
+        token: &str,
+        cipher: ChaCha20Poly1305,
+    ) {
+        let Some(mut connection) = self.take_validator_connection(hotkey, token).await else {
+            return;
+        };
+        connection.message_sender = None;
+
+        let mut sessions = self.suspended_sessions.write().await;
+        sessions.insert(
+            connection.session_token.clone(),
+            SuspendedSession {
+                connection,
+                cipher,
+                disconnected_at: Utc::now(),
+            },
+        );
+    }
+
+    /// Record that job `job_id` was assigned to validator `hotkey` over its
+    /// current connection
+    pub async fn add_assigned_job(&self, hotkey: &str, job_id: &str) {
+        let mut connections = self.validator_connections.write().await;
+        if let Some(connection) = connections.get_mut(hotkey) {
+            if !connection.assigned_jobs.iter().any(|job| job == job_id) {
+                connection.assigned_jobs.push(job_id.to_string());
+            }
+        }
+    }
+
+    /// Forget the assignment of job `job_id` to validator `hotkey`, once it
+    /// returned its result
+    pub async fn remove_assigned_job(&self, hotkey: &str, job_id: &str) {
+        let mut connections = self.validator_connections.write().await;
+        if let Some(connection) = connections.get_mut(hotkey) {
+            connection.assigned_jobs.retain(|job| job != job_id);
+        }
+    }
+
+    /// Remove the connection of a validator that closed it, revoking its
+    /// session token so that the session cannot be resumed
+    pub async fn end_validator_session(&self, hotkey: &str, token: &str) {
+        let connection = self.take_validator_connection(hotkey, token).await;
+        if connection.is_some() {
+            self.revoke_session_token(token).await;
+        }
+    }
+
+    /// Revoke the sessions of validator `hotkey`, connected or suspended, and
+    /// return how many were revoked
+    ///
+    /// A connected validator is told its session was revoked and the
+    /// messages it sends afterwards are refused, see `is_session_revoked`.
+    pub async fn revoke_validator_sessions(&self, hotkey: &str) -> usize {
+        let mut tokens: Vec<SessionToken> = self
+            .suspended_sessions
+            .read()
+            .await
+            .iter()
+            .filter(|(_, session)| session.connection.validator_hotkey == hotkey)
+            .map(|(token, _)| token.clone())
+            .collect();
+
+        let connection = self.validator_connections.write().await.remove(hotkey);
+        if let Some(connection) = connection {
+            if let Some(sender) = &connection.message_sender {
+                let notice = serde_json::json!({ "type": "session_revoked" });
+                if let Err(e) = sender.send(notice.to_string()).await {
+                    warn!("Failed to notify {} of its revoked session: {}", hotkey, e);
+                }
+            }
+            tokens.push(connection.session_token);
+        }
+
+        for token in &tokens {
+            self.revoke_session_token(token).await;
+        }
+        tokens.len()
+    }
+
+    /// Check whether session token `token` was revoked
+    pub async fn is_session_revoked(&self, token: &str) -> bool {
+        self.revoked_session_tokens.read().await.contains(token)
+    }
+
+    /// Drop the suspended sessions whose resume grace has passed, returning
+    /// the hotkeys of their validators that have not reconnected since
+    pub async fn expire_suspended_sessions(&self) -> Vec<Hotkey> {
+        let now = Utc::now();
+        let mut expired = Vec::new();
+        self.suspended_sessions.write().await.retain(|_, session| {
+            let resumable = now <= session.disconnected_at + self.session_resume_grace;
+            if !resumable {
+                expired.push(session.connection.validator_hotkey.clone());
+            }
+            resumable
+        });
+
+        let connections = self.validator_connections.read().await;
+        expired.retain(|hotkey| !connections.contains_key(hotkey.as_str()));
+        expired.sort();
+        expired.dedup();
+        expired
+    }
+
+    /// Re-queue the jobs validator `hotkey` left unfinished when it
+    /// disconnected, and release it from the cached jobs it was running
+    ///
+    /// Returns the number of re-queued jobs, see
+    /// `SchedulerService::requeue_validator_jobs`.
+    pub async fn requeue_orphaned_jobs(&self, hotkey: &str) -> anyhow::Result<u64> {
+        let requeued = self.scheduler.requeue_validator_jobs(hotkey).await?;
+
+        let mut cache = self.job_cache.write().await;
+        for job in cache.values_mut() {
+            job.release_validator(hotkey);
+        }
+
+        Ok(requeued)
+    }
+
+    /// Take the suspended session of `token` if `hotkey` can resume it from `instance_id`
+    pub async fn resume_validator_session(
+        &self,
+        hotkey: &str,
+        token: &str,
+        instance_id: Option<&str>,
+    ) -> anyhow::Result<SuspendedSession> {
+        if self.revoked_session_tokens.read().await.contains(token) {
+            return Err(anyhow::anyhow!("Session was revoked"));
+        }
+
+        let mut sessions = self.suspended_sessions.write().await;
+        let session = sessions
+            .get(token)
+            .ok_or_else(|| anyhow::anyhow!("Unknown session"))?;
+        session.check_resumable(hotkey, instance_id, Utc::now(), self.session_resume_grace)?;
+
+        Ok(sessions.remove(token).expect("session exists"))
+    }
+
+    /// Revoke a session token so that it can no longer be resumed, verified
+    /// or refreshed
+    pub async fn revoke_session_token(&self, token: &str) {
+        self.attestation.revoke_session(token).await;
+        self.suspended_sessions.write().await.remove(token);
+        self.revoked_session_tokens
+            .write()
+            .await
+            .insert(SessionToken::from(token));
+    }
+
     /// List all connected validators
     pub async fn list_validator_connections(&self) -> Vec<ValidatorConnection> {
         let connections = self.validator_connections.read().await;
@@ -406,11 +746,22 @@ impl AppState {
         hotkey: &str,
         status: ValidatorChallengeStatus,
     ) {
-        let mut status_map = self.validator_challenge_status.write().await;
-        status_map
-            .entry(hotkey.to_string())
-            .or_insert_with(HashMap::new)
-            .insert(status.compose_hash.clone(), status);
+        let compose_hash = status.compose_hash.clone();
+        {
+            let mut status_map = self.validator_challenge_status.write().await;
+            status_map
+                .entry(hotkey.to_string())
+                .or_insert_with(HashMap::new)
+                .insert(compose_hash.clone(), status);
+        }
+
+        // Reporting on a challenge subscribes the connection to it
+        let mut connections = self.validator_connections.write().await;
+        if let Some(connection) = connections.get_mut(hotkey) {
+            if !connection.subscriptions.contains(&compose_hash) {
+                connection.subscriptions.push(compose_hash);
+            }
+        }
     }
 
     /// Get validator challenge status
@@ -451,6 +802,88 @@ impl AppState {
         count
     }
 
+    /// Hotkeys of the registered validators preferring `challenge_id`,
+    /// whether connected or not
+    pub async fn registered_validators_for_challenge(
+        &self,
+        challenge_id: uuid::Uuid,
+    ) -> anyhow::Result<Vec<String>> {
+        let Some(pool) = &self.database_pool else {
+            return Ok(Vec::new());
+        };
+        let hotkeys = sqlx::query_scalar(
+            "SELECT hotkey FROM validators WHERE $1 = ANY(preferred_challenges) ORDER BY hotkey",
+        )
+        .bind(challenge_id)
+        .fetch_all(pool.as_ref())
+        .await?;
+        Ok(hotkeys)
+    }
+
+    /// Capabilities declared by the registered validators among `hotkeys`
+    pub async fn validator_registrations(
+        &self,
+        hotkeys: &[String],
+    ) -> anyhow::Result<Vec<platform_api_models::ValidatorRegistration>> {
+        let Some(pool) = &self.database_pool else {
+            return Ok(Vec::new());
+        };
+
+        #[derive(sqlx::FromRow)]
+        struct RegistrationRow {
+            hotkey: String,
+            runtimes: Vec<String>,
+            gpu: bool,
+            memory_gb: i64,
+            cpu_cores: i32,
+            preferred_challenges: Vec<uuid::Uuid>,
+            gpu_count: i32,
+            gpu_type: Option<String>,
+            disk_gb: Option<i64>,
+        }
+
+        let rows = sqlx::query_as::<_, RegistrationRow>(
+            r#"
+            SELECT hotkey, runtimes, gpu, memory_gb, cpu_cores, preferred_challenges,
+                   gpu_count, gpu_type, disk_gb
+            FROM validators
+            WHERE hotkey = ANY($1)
+            "#,
+        )
+        .bind(hotkeys)
+        .fetch_all(pool.as_ref())
+        .await?;
+        rows.into_iter()
+            .map(|row| -> anyhow::Result<_> {
+                Ok(platform_api_models::ValidatorRegistration {
+                    hotkey: row.hotkey.parse()?,
+                    runtimes: row.runtimes.iter().map(|r| r.as_str().into()).collect(),
+                    gpu: row.gpu,
+                    memory_gb: row.memory_gb.max(0) as u64,
+                    cpu_cores: row.cpu_cores.max(0) as u32,
+                    preferred_challenges: row.preferred_challenges,
+                    gpu_count: row.gpu_count.max(0) as u32,
+                    gpu_type: row.gpu_type,
+                    disk_gb: row.disk_gb.map(|disk| disk.max(0) as u64),
+                })
+            })
+            .collect()
+    }
+
+    /// Get the challenge-wide state for a compose_hash from validator reports
+    pub async fn get_challenge_state(
+        &self,
+        compose_hash: &str,
+    ) -> platform_api_models::ValidatorChallengeState {
+        let status_map = self.validator_challenge_status.read().await;
+        platform_api_models::ValidatorChallengeState::aggregate(
+            status_map
+                .values()
+                .filter_map(|statuses| statuses.get(compose_hash))
+                .map(|status| &status.state),
+        )
+    }
+
     /// Initialize security with TDX attestation
     pub async fn init_security_from_tdx(self) -> anyhow::Result<Self> {
         let security = Arc::new(PlatformSecurity::init_from_tdx().await?);
@@ -568,3 +1001,255 @@ impl AppState {
         Ok(())
     }
 }
+
+#[cfg(test)]
+impl AppState {
+    /// State serving `storage`, without a database or external services, for
+    /// handler tests
+    pub(crate) fn for_tests(storage: Arc<dyn StorageBackend>) -> anyhow::Result<Self> {
+        let config = AppConfig {
+            server_port: 0,
+            server_host: "localhost".to_string(),
+            database_url: String::new(),
+            storage_config: StorageConfig::default(),
+            attestation_config: AttestationConfig::from_env(),
+            kbs_config: KbsConfig::default(),
+            scheduler_config: SchedulerConfig::default(),
+            builder_config: BuilderConfig::default(),
+            metrics_config: MetricsConfig {
+                enabled: false,
+                port: 0,
+                path: "/metrics".to_string(),
+                collect_interval: 60,
+            },
+            cors_config: CorsConfig::default(),
+            rate_limit_config: RateLimitConfig::default(),
+            auth_config: AuthConfig::default(),
+            ws_allowed_cidr_ranges: vec![],
+            response_envelope_enabled: false,
+            max_job_payload_bytes: platform_api_scheduler::DEFAULT_MAX_JOB_PAYLOAD_BYTES,
+            max_job_fanout: None,
+            validator_channel: ChannelConfig::default(),
+            challenge_proxy: ChallengeProxyConfig::default(),
+        };
+
+        Ok(Self {
+            storage,
+            attestation: Arc::new(AttestationService::new(&config.attestation_config)?),
+            kbs: Arc::new(KeyBrokerService::new(&config.kbs_config)?),
+            scheduler: Arc::new(SchedulerService::new(&config.scheduler_config)?),
+            builder: Arc::new(BuilderService::new(&config.builder_config, None)?),
+            metrics: Arc::new(MetricsService::new(&config.metrics_config)?),
+            security: Arc::new(PlatformSecurity::new_with_random_keys("test")?),
+            config: Arc::new(config),
+            validator_connections: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            challenge_registry: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            validator_challenge_status: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            database_pool: None,
+            orm_gateway: None,
+            orm_gateway_readonly: None,
+            challenge_runner: None,
+            job_cache: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            redis_client: None,
+            chutes_api_token: Arc::new(tokio::sync::RwLock::new(None)),
+            bittensor: None,
+            emissions: None,
+            dstack_verifier: None,
+            maintenance: Arc::new(MaintenanceMode::new()),
+            settings: SettingsHandle::new(None),
+            suspended_sessions: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            revoked_session_tokens: Arc::new(tokio::sync::RwLock::new(HashSet::new())),
+            seen_request_nonces: Arc::new(SeenNonces::new()),
+            session_resume_grace: Duration::seconds(DEFAULT_SESSION_RESUME_GRACE_SECS),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message_channel::message_channel;
+    use chacha20poly1305::KeyInit;
+    use platform_api_models::ValidatorChallengeState;
+
+    fn validator() -> Hotkey {
+        Hotkey::from_public_key(&[1; 32])
+    }
+
+    fn suspended(disconnected_at: DateTime<Utc>) -> SuspendedSession {
+        SuspendedSession {
+            connection: ValidatorConnection {
+                validator_hotkey: validator(),
+                app_id: None,
+                instance_id: Some("instance-1".to_string()),
+                compose_hash: None,
+                connected_at: disconnected_at - Duration::hours(1),
+                session_token: SessionToken::from("token"),
+                last_ping: disconnected_at,
+                message_sender: None,
+                subscriptions: vec!["compose-hash".to_string()],
+                assigned_jobs: vec![],
+            },
+            cipher: ChaCha20Poly1305::new_from_slice(&[0u8; 32]).unwrap(),
+            disconnected_at,
+        }
+    }
+
+    #[test]
+    fn test_session_resumable_within_grace() {
+        let now = Utc::now();
+        let grace = Duration::seconds(DEFAULT_SESSION_RESUME_GRACE_SECS);
+        let session = suspended(now - Duration::minutes(4));
+        let hotkey = validator();
+
+        assert!(session
+            .check_resumable(&hotkey, Some("instance-1"), now, grace)
+            .is_ok());
+        assert!(session
+            .check_resumable("other", Some("instance-1"), now, grace)
+            .is_err());
+        assert!(session
+            .check_resumable(&hotkey, Some("instance-2"), now, grace)
+            .is_err());
+        assert!(session
+            .check_resumable(&hotkey, Some("instance-1"), now + Duration::minutes(2), grace)
+            .is_err());
+    }
+
+    fn test_state() -> AppState {
+        AppState::for_tests(Arc::new(
+            MemoryStorageBackend::new(&StorageConfig::default()).unwrap(),
+        ))
+        .unwrap()
+    }
+
+    fn cipher() -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new_from_slice(&[0u8; 32]).unwrap()
+    }
+
+    async fn connect_attempt(state: &AppState) -> anyhow::Result<SessionToken> {
+        let hotkey = validator();
+        let (sender, _receiver) =
+            message_channel("validator", hotkey.as_str(), ChannelConfig::default());
+        let mut connection = suspended(Utc::now()).connection;
+        connection.session_token = SessionToken::generate();
+        connection.subscriptions = vec![];
+        connection.message_sender = Some(sender);
+        let token = connection.session_token.clone();
+        state.add_validator_connection(connection).await?;
+        Ok(token)
+    }
+
+    async fn connect(state: &AppState) -> SessionToken {
+        connect_attempt(state).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_connection_tracks_subscriptions_and_jobs_across_resumption() {
+        let state = test_state();
+        let hotkey = validator();
+        let token = connect(&state).await;
+
+        state
+            .update_validator_challenge_status(
+                &hotkey,
+                ValidatorChallengeStatus {
+                    validator_hotkey: hotkey.to_string(),
+                    compose_hash: "compose-hash".to_string(),
+                    state: ValidatorChallengeState::Active,
+                    last_heartbeat: Utc::now(),
+                    penalty_reason: None,
+                },
+            )
+            .await;
+        state.add_assigned_job(&hotkey, "job-1").await;
+        state.add_assigned_job(&hotkey, "job-2").await;
+        state.remove_assigned_job(&hotkey, "job-1").await;
+
+        state
+            .suspend_validator_connection(&hotkey, &token, cipher())
+            .await;
+        let session = state
+            .resume_validator_session(&hotkey, &token, Some("instance-1"))
+            .await
+            .unwrap();
+
+        assert_eq!(session.connection.subscriptions, vec!["compose-hash"]);
+        assert_eq!(session.connection.assigned_jobs, vec!["job-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_closed_session_cannot_be_resumed() {
+        let state = test_state();
+        let hotkey = validator();
+        let token = connect(&state).await;
+
+        state.end_validator_session(&hotkey, &token).await;
+
+        assert!(state.get_validator_connection(&hotkey).await.is_none());
+        assert!(state.is_session_revoked(&token).await);
+        let err = state
+            .resume_validator_session(&hotkey, &token, Some("instance-1"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Session was revoked");
+    }
+
+    #[tokio::test]
+    async fn test_revoking_validator_sessions() {
+        let state = test_state();
+        let hotkey = validator();
+        let suspended_token = connect(&state).await;
+        state
+            .suspend_validator_connection(&hotkey, &suspended_token, cipher())
+            .await;
+        let connected_token = connect(&state).await;
+
+        assert_eq!(state.revoke_validator_sessions(&hotkey).await, 2);
+
+        assert!(state.get_validator_connection(&hotkey).await.is_none());
+        assert!(state.is_session_revoked(&connected_token).await);
+        assert!(state
+            .resume_validator_session(&hotkey, &suspended_token, Some("instance-1"))
+            .await
+            .is_err());
+        assert_eq!(state.revoke_validator_sessions(&hotkey).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_validator_connects_once() {
+        let state = test_state();
+        let hotkey = validator();
+
+        let (first, second) = tokio::join!(connect_attempt(&state), connect_attempt(&state));
+
+        assert!(first.is_ok() != second.is_ok());
+        let token = first.or(second).unwrap();
+        assert_eq!(
+            state
+                .get_validator_connection(&hotkey)
+                .await
+                .unwrap()
+                .session_token,
+            token
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stale_session_leaves_new_connection() {
+        let state = test_state();
+        let hotkey = validator();
+        let old_token = connect(&state).await;
+        state.revoke_validator_sessions(&hotkey).await;
+        let new_token = connect(&state).await;
+
+        state
+            .suspend_validator_connection(&hotkey, &old_token, cipher())
+            .await;
+        state.end_validator_session(&hotkey, &old_token).await;
+
+        let connection = state.get_validator_connection(&hotkey).await.unwrap();
+        assert_eq!(connection.session_token, new_token);
+        assert!(!state.is_session_revoked(&new_token).await);
+    }
+}
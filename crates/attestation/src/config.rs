@@ -1,5 +1,27 @@
+use platform_api_models::HashAlgorithm;
 use serde::{Deserialize, Serialize};
 
+/// Default bound on a single attestation verification, in seconds
+pub const DEFAULT_VERIFICATION_TIMEOUT_SECS: u64 = 30;
+
+fn default_verification_timeout() -> u64 {
+    DEFAULT_VERIFICATION_TIMEOUT_SECS
+}
+
+/// Audience of grant tokens when none is configured
+pub const DEFAULT_TOKEN_AUDIENCE: &str = "platform-executor";
+
+fn default_token_audiences() -> Vec<String> {
+    vec![DEFAULT_TOKEN_AUDIENCE.to_string()]
+}
+
+/// Default lifetime of refresh tokens, in seconds
+pub const DEFAULT_REFRESH_TOKEN_TTL_SECS: u64 = 24 * 3600;
+
+fn default_refresh_token_ttl() -> u64 {
+    DEFAULT_REFRESH_TOKEN_TTL_SECS
+}
+
 /// TDX Configuration with production/dev mode support
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TdxConfig {
@@ -9,8 +31,32 @@ pub struct TdxConfig {
     pub dev_mode: bool,
     /// Session timeout in seconds
     pub session_timeout: u64,
+    /// How long, in seconds, the refresh token of an attestation can be
+    /// exchanged for new session tokens before the validator attests again
+    #[serde(default = "default_refresh_token_ttl")]
+    pub refresh_token_ttl: u64,
     /// PCCS URL for collateral retrieval
     pub pccs_url: Option<String>,
+    /// Whether SGX DCAP quotes are accepted
+    #[serde(default)]
+    pub dcap_enabled: bool,
+    /// Longest a single attestation verification may take, in seconds
+    #[serde(default = "default_verification_timeout")]
+    pub verification_timeout: u64,
+    /// Algorithm of compose hashes and challenge hashes, on both sides of an attestation
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    /// Audiences grant tokens are accepted for; minted tokens carry the first
+    #[serde(default = "default_token_audiences")]
+    pub token_audiences: Vec<String>,
+    /// App ids grant tokens may be issued to; any verified app id when unset
+    #[serde(default)]
+    pub allowed_app_ids: Option<Vec<String>>,
+    /// Hex-encoded key of at least 32 bytes signing session, refresh and
+    /// receipt tokens; a random key per process when unset, with which tokens
+    /// and receipts no longer verify after a restart
+    #[serde(default, skip_serializing)]
+    pub token_signing_key: Option<String>,
 }
 
 impl TdxConfig {
@@ -31,13 +77,80 @@ impl TdxConfig {
             .and_then(|s| s.parse().ok())
             .unwrap_or(86400); // 24 hours default
 
+        let refresh_token_ttl = std::env::var("REFRESH_TOKEN_TTL")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_REFRESH_TOKEN_TTL_SECS);
+
         let pccs_url = std::env::var("PCCS_URL").ok();
 
+        let dcap_enabled = std::env::var("DCAP_ENABLED")
+            .unwrap_or_else(|_| "false".to_string())
+            .to_lowercase()
+            == "true";
+
+        let verification_timeout = std::env::var("VERIFICATION_TIMEOUT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_VERIFICATION_TIMEOUT_SECS);
+
+        // A misspelled algorithm is reported, not silently replaced by the default
+        let hash_algorithm = std::env::var("COMPOSE_HASH_ALGORITHM")
+            .ok()
+            .and_then(|s| {
+                s.parse()
+                    .map_err(|e| {
+                        tracing::warn!(
+                            "Invalid COMPOSE_HASH_ALGORITHM: {}, using {}",
+                            e,
+                            HashAlgorithm::default()
+                        )
+                    })
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        // Comma-separated, e.g. TOKEN_AUDIENCES=executor-eu,executor-us
+        let token_audiences = std::env::var("TOKEN_AUDIENCES")
+            .ok()
+            .map(|s| {
+                s.split(',')
+                    .map(str::trim)
+                    .filter(|audience| !audience.is_empty())
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|audiences| !audiences.is_empty())
+            .unwrap_or_else(default_token_audiences);
+
+        // Comma-separated, e.g. ALLOWED_APP_IDS=<app id>,<app id>
+        let allowed_app_ids = std::env::var("ALLOWED_APP_IDS")
+            .ok()
+            .map(|s| {
+                s.split(',')
+                    .map(str::trim)
+                    .filter(|app_id| !app_id.is_empty())
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|app_ids| !app_ids.is_empty());
+
+        let token_signing_key = std::env::var("TOKEN_SIGNING_KEY")
+            .ok()
+            .filter(|key| !key.trim().is_empty());
+
         Self {
             tee_enforced,
             dev_mode,
             session_timeout,
+            refresh_token_ttl,
             pccs_url,
+            dcap_enabled,
+            verification_timeout,
+            hash_algorithm,
+            token_audiences,
+            allowed_app_ids,
+            token_signing_key,
         }
     }
 
@@ -128,4 +241,49 @@ mod tests {
         std::env::remove_var("TEE_ENFORCED");
         std::env::remove_var("DEV_MODE");
     }
+
+    #[test]
+    fn test_token_audiences() {
+        std::env::set_var("TOKEN_AUDIENCES", "executor-eu, executor-us,");
+        let config = TdxConfig::from_env();
+        assert_eq!(config.token_audiences, vec!["executor-eu", "executor-us"]);
+
+        std::env::remove_var("TOKEN_AUDIENCES");
+        let config = TdxConfig::from_env();
+        assert_eq!(config.token_audiences, vec![DEFAULT_TOKEN_AUDIENCE]);
+    }
+
+    #[test]
+    fn test_allowed_app_ids() {
+        std::env::set_var("ALLOWED_APP_IDS", "app-a, app-b,");
+        let config = TdxConfig::from_env();
+        assert_eq!(
+            config.allowed_app_ids,
+            Some(vec!["app-a".to_string(), "app-b".to_string()])
+        );
+
+        std::env::remove_var("ALLOWED_APP_IDS");
+        let config = TdxConfig::from_env();
+        assert_eq!(config.allowed_app_ids, None);
+    }
+
+    #[test]
+    fn test_hash_algorithm() {
+        std::env::set_var("COMPOSE_HASH_ALGORITHM", "SHA-384");
+        assert_eq!(TdxConfig::from_env().hash_algorithm, HashAlgorithm::Sha384);
+
+        std::env::set_var("COMPOSE_HASH_ALGORITHM", "md5");
+        assert_eq!(TdxConfig::from_env().hash_algorithm, HashAlgorithm::Sha256);
+
+        std::env::remove_var("COMPOSE_HASH_ALGORITHM");
+        assert_eq!(TdxConfig::from_env().hash_algorithm, HashAlgorithm::Sha256);
+    }
+
+    #[test]
+    fn test_token_signing_key_not_serialized() {
+        let mut config = TdxConfig::from_env();
+        config.token_signing_key = Some("00".repeat(32));
+        let json = serde_json::to_value(&config).unwrap();
+        assert!(json.get("token_signing_key").is_none());
+    }
 }
@@ -0,0 +1,199 @@
+use crate::config::TdxConfig;
+use crate::verifier::{ACCEPTED_TCB_STATUSES, DEFAULT_PCCS_URL};
+use crate::VerificationResult;
+use anyhow::{Context, Result};
+use dcap_qvl::quote::{Quote, Report};
+use dcap_qvl::QuoteCollateralV3;
+use platform_api_models::AttestationRequest;
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+/// SGX DCAP quote verifier
+///
+/// Verifies the quote of an SGX enclave against the Intel collateral of its
+/// platform. The verified measurements are MRENCLAVE and MRSIGNER; the
+/// enclave identity (MRENCLAVE) is reported as the app id and the second half
+/// of `report_data`, by convention a hash of the enclave's public key, as the
+/// instance id.
+#[derive(Debug)]
+pub struct DcapVerifier {
+    config: TdxConfig,
+}
+
+impl DcapVerifier {
+    pub fn new(config: TdxConfig) -> Self {
+        Self { config }
+    }
+
+    pub async fn verify(&self, request: &AttestationRequest) -> Result<VerificationResult> {
+        tracing::info!("Verifying SGX DCAP attestation with dcap-qvl");
+
+        let quote = match request.quote.as_deref() {
+            Some(quote) if !quote.is_empty() => quote,
+            _ => return Ok(rejected(request, "Quote is empty")),
+        };
+
+        let parsed =
+            Quote::parse(quote).map_err(|e| anyhow::anyhow!("Failed to parse quote: {:?}", e))?;
+        if !matches!(parsed.report, Report::SgxEnclave(_)) {
+            return Ok(rejected(request, "Quote is not an SGX enclave quote"));
+        }
+
+        let collateral = get_collateral(&self.config, quote).await?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+
+        self.verify_with_collateral(request, &collateral, now)
+    }
+
+    /// Verify the quote of `request` against `collateral` at time `now` (unix seconds)
+    pub fn verify_with_collateral(
+        &self,
+        request: &AttestationRequest,
+        collateral: &QuoteCollateralV3,
+        now: u64,
+    ) -> Result<VerificationResult> {
+        let quote = match request.quote.as_deref() {
+            Some(quote) if !quote.is_empty() => quote,
+            _ => return Ok(rejected(request, "Quote is empty")),
+        };
+
+        let verified_report = dcap_qvl::verify::verify(quote, collateral, now)
+            .context("Failed to verify SGX quote")?;
+
+        if !ACCEPTED_TCB_STATUSES.contains(&verified_report.status.as_str()) {
+            return Ok(rejected(
+                request,
+                &format!("Invalid TCB status: {}", verified_report.status),
+            ));
+        }
+
+        let parsed =
+            Quote::parse(quote).map_err(|e| anyhow::anyhow!("Failed to parse quote: {:?}", e))?;
+        let Report::SgxEnclave(enclave_report) = &parsed.report else {
+            return Ok(rejected(request, "Quote is not an SGX enclave quote"));
+        };
+
+        // SGX enclaves bind the nonce the same way as TDX: SHA256(nonce) in
+        // the first 32 bytes of report_data
+        if !request.nonce.is_empty() {
+            let expected_nonce_hash = Sha256::digest(&request.nonce);
+            if enclave_report.report_data[..32] != expected_nonce_hash[..] {
+                return Ok(rejected(
+                    request,
+                    "Nonce binding verification failed: report_data does not match SHA256(nonce)",
+                ));
+            }
+            info!("✅ Nonce binding verified successfully");
+        }
+
+        tracing::info!(
+            mr_enclave = %hex::encode(enclave_report.mr_enclave),
+            mr_signer = %hex::encode(enclave_report.mr_signer),
+            tcb_status = %verified_report.status,
+            "SGX quote verified successfully with dcap-qvl"
+        );
+
+        Ok(VerificationResult {
+            is_valid: true,
+            measurements: vec![
+                enclave_report.mr_enclave.to_vec(),
+                enclave_report.mr_signer.to_vec(),
+            ],
+            app_id: Some(enclave_report.mr_enclave.to_vec()),
+            instance_id: Some(enclave_report.report_data[32..].to_vec()),
+            device_id: None,
+            error: None,
+        })
+    }
+}
+
+/// Get the collateral of `quote` from the configured PCCS, or from Intel PCS
+pub(crate) async fn get_collateral(config: &TdxConfig, quote: &[u8]) -> Result<QuoteCollateralV3> {
+    let pccs_url = config.pccs_url.as_deref().unwrap_or(DEFAULT_PCCS_URL);
+
+    match dcap_qvl::collateral::get_collateral(pccs_url, quote).await {
+        Ok(collateral) => Ok(collateral),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to get collateral from PCCS, trying Intel PCS: {}",
+                e
+            );
+            dcap_qvl::collateral::get_collateral_from_pcs(quote)
+                .await
+                .context("Failed to get collateral from Intel PCS")
+        }
+    }
+}
+
+fn rejected(request: &AttestationRequest, error: &str) -> VerificationResult {
+    VerificationResult {
+        is_valid: false,
+        measurements: request.measurements.clone(),
+        app_id: None,
+        instance_id: None,
+        device_id: None,
+        error: Some(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platform_api_models::AttestationType;
+    use std::path::PathBuf;
+
+    fn request(quote: Vec<u8>) -> AttestationRequest {
+        AttestationRequest {
+            attestation_type: AttestationType::SgxDcap,
+            quote: Some(quote),
+            report: None,
+            nonce: vec![],
+            measurements: vec![],
+            capabilities: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_empty_quote_rejected() {
+        let verifier = DcapVerifier::new(TdxConfig::from_env());
+
+        let result = verifier.verify(&request(vec![])).await.unwrap();
+        assert!(!result.is_valid);
+        assert_eq!(result.error.as_deref(), Some("Quote is empty"));
+    }
+
+    /// Verifies the quote in `fixtures/sgx`, see `fixtures/README.md`
+    #[test]
+    #[ignore] // Requires the SGX quote fixture
+    fn test_verify_sgx_quote_fixture() {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/sgx");
+        let quote = std::fs::read(fixtures.join("quote.bin")).expect("Missing quote.bin");
+        let collateral: QuoteCollateralV3 = serde_json::from_slice(
+            &std::fs::read(fixtures.join("collateral.json")).expect("Missing collateral.json"),
+        )
+        .expect("Invalid collateral.json");
+        let verified_at: u64 = std::fs::read_to_string(fixtures.join("verified_at"))
+            .expect("Missing verified_at")
+            .trim()
+            .parse()
+            .expect("Invalid verified_at");
+
+        let verifier = DcapVerifier::new(TdxConfig::from_env());
+        let result = verifier
+            .verify_with_collateral(&request(quote.clone()), &collateral, verified_at)
+            .unwrap();
+        assert!(result.is_valid, "{:?}", result.error);
+        assert_eq!(result.measurements.len(), 2);
+        assert_eq!(result.app_id.as_ref(), Some(&result.measurements[0]));
+
+        // A nonce that the enclave did not bind is rejected
+        let mut unbound = request(quote);
+        unbound.nonce = b"a nonce the enclave never saw".to_vec();
+        let result = verifier
+            .verify_with_collateral(&unbound, &collateral, verified_at)
+            .unwrap();
+        assert!(!result.is_valid);
+    }
+}
@@ -3,6 +3,7 @@ use chrono::{DateTime, Duration, Utc};
 use hmac::{Hmac, Mac};
 use platform_api_models::{
     AttestationPolicy, AttestationRequest, AttestationResponse, AttestationSession,
+    AttestationType, PlatformError, PlatformResult, SessionToken,
 };
 use rand::RngCore;
 use sha2::Sha256;
@@ -12,15 +13,24 @@ use uuid::Uuid;
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// Type of refresh tokens, prefixed to what they sign
+const REFRESH_TOKEN_TYPE: &str = "refresh";
+
 mod verifier;
 pub use verifier::*;
 
+mod dcap;
+pub use dcap::*;
+
 mod config;
 pub use config::*;
 
 mod mock_tdx;
 pub use mock_tdx::*;
 
+mod timeout;
+pub use timeout::*;
+
 // Use TdxConfig as AttestationConfig for now
 pub type AttestationConfig = TdxConfig;
 
@@ -28,9 +38,11 @@ pub type AttestationConfig = TdxConfig;
 pub struct AttestationService {
     config: AttestationConfig,
     verifier: TdxVerifier,
+    dcap_verifier: DcapVerifier,
     sessions: Arc<tokio::sync::RwLock<HashMap<Uuid, AttestationSession>>>,
     nonces: Arc<tokio::sync::RwLock<HashMap<String, NonceInfo>>>,
-    random_key: [u8; 32], // Random cryptographic key for token signing
+    /// Key signing session, refresh and receipt tokens
+    token_key: Vec<u8>,
 }
 
 /// Nonce information
@@ -41,37 +53,65 @@ struct NonceInfo {
 }
 
 impl AttestationService {
+    /// Create the service, signing tokens with the configured
+    /// `token_signing_key`
+    ///
+    /// Without one, tokens are signed with a random key of this process: they,
+    /// and the receipts validators attached to job results, stop verifying
+    /// once the platform restarts.
     pub fn new(config: &AttestationConfig) -> Result<Self> {
-        // Generate random cryptographic key (32 bytes) for token signing
-        // This key is unique per instance and provides quantum-resistant security
-        let mut random_key = [0u8; 32];
-        rand::thread_rng().fill_bytes(&mut random_key);
-
-        tracing::info!("Generated random cryptographic key for token signing (32 bytes)");
+        let token_key = match &config.token_signing_key {
+            Some(key) => {
+                let key = hex::decode(key.trim()).context("Token signing key is not hex")?;
+                if key.len() < 32 {
+                    return Err(anyhow::anyhow!(
+                        "Token signing key must have at least 32 bytes, got {}",
+                        key.len()
+                    ));
+                }
+                key
+            }
+            None => {
+                let mut random_key = vec![0u8; 32];
+                rand::thread_rng().fill_bytes(&mut random_key);
+                tracing::warn!(
+                    "No TOKEN_SIGNING_KEY configured, signing tokens with a random key: \
+                     tokens and job result receipts will not verify after a restart"
+                );
+                random_key
+            }
+        };
 
         let verifier = TdxVerifier::new(config.clone());
+        let dcap_verifier = DcapVerifier::new(config.clone());
 
         Ok(Self {
             config: config.clone(),
             verifier,
+            dcap_verifier,
             sessions: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
             nonces: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
-            random_key,
+            token_key,
         })
     }
 
     pub async fn verify_attestation(
         &self,
         request: AttestationRequest,
-    ) -> Result<AttestationResponse> {
+    ) -> PlatformResult<AttestationResponse> {
         self.verify_attestation_with_event_log(request, None).await
     }
 
+    #[tracing::instrument(
+        name = "attestation.verify",
+        skip_all,
+        fields(has_event_log = event_log.is_some())
+    )]
     pub async fn verify_attestation_with_event_log(
         &self,
         request: AttestationRequest,
         event_log: Option<&str>,
-    ) -> Result<AttestationResponse> {
+    ) -> PlatformResult<AttestationResponse> {
         // Check if TEE verification is enforced
         let tee_enforced =
             std::env::var("TEE_ENFORCED").unwrap_or_else(|_| "true".to_string()) == "true";
@@ -91,12 +131,13 @@ impl AttestationService {
             // Validate request structure even in dev mode
             if request.quote.is_none() {
                 return Ok(AttestationResponse {
-                    session_token: String::new(),
+                    session_token: SessionToken::default(),
                     status: platform_api_models::AttestationStatus::Failed,
                     expires_at: Utc::now(),
                     verified_measurements: vec![],
                     policy: String::new(),
                     error: Some("Missing quote in attestation request".to_string()),
+                    refresh_token: String::new(),
                 });
             }
 
@@ -104,12 +145,13 @@ impl AttestationService {
             if !request.nonce.is_empty() {
                 if request.nonce.len() < 16 {
                     return Ok(AttestationResponse {
-                        session_token: String::new(),
+                        session_token: SessionToken::default(),
                         status: platform_api_models::AttestationStatus::Failed,
                         expires_at: Utc::now(),
                         verified_measurements: vec![],
                         policy: String::new(),
                         error: Some("Nonce too short (minimum 16 bytes)".to_string()),
+                        refresh_token: String::new(),
                     });
                 }
 
@@ -124,12 +166,13 @@ impl AttestationService {
                         // In enhanced simulation mode, we still allow but log the issue
                         if !tdx_simulation_mode {
                             return Ok(AttestationResponse {
-                                session_token: String::new(),
+                                session_token: SessionToken::default(),
                                 status: platform_api_models::AttestationStatus::Failed,
                                 expires_at: Utc::now(),
                                 verified_measurements: vec![],
                                 policy: String::new(),
                                 error: Some(format!("Nonce binding verification failed: {}", e)),
+                                refresh_token: String::new(),
                             });
                         }
                     }
@@ -138,7 +181,8 @@ impl AttestationService {
 
             // Extract app info from event log (same as real verifier)
             let (app_id, instance_id, compose_hash) =
-                Self::extract_app_info_from_event_log(event_log)?;
+                Self::extract_app_info_from_event_log(event_log)
+                    .map_err(|e| PlatformError::invalid("event_log", format!("{:#}", e)))?;
 
             // Use extracted values or fallback to defaults
             let app_id_bytes = app_id
@@ -166,31 +210,49 @@ impl AttestationService {
                 error: None,
             }
         } else {
-            tracing::info!("Verifying attestation request with TDX verifier");
+            let (kind, verified) = match request.attestation_type {
+                AttestationType::SgxDcap => {
+                    if !self.config.dcap_enabled {
+                        return Err(PlatformError::unauthorized(
+                            "SGX DCAP attestation is disabled. Attestation rejected.",
+                        ));
+                    }
+                    tracing::info!("Verifying attestation request with SGX DCAP verifier");
+                    ("SGX DCAP", self.dcap_verifier.verify(&request).await)
+                }
+                _ => {
+                    tracing::info!("Verifying attestation request with TDX verifier");
+                    (
+                        "TDX",
+                        TdxVerifier::verify_static(&self.verifier, &request, event_log).await,
+                    )
+                }
+            };
 
             // Verify the attestation with the real verifier (fail fast if invalid)
-            match TdxVerifier::verify_static(&self.verifier, &request, event_log).await {
+            match verified {
                 Ok(result) => {
                     if !result.is_valid {
-                        return Err(anyhow::anyhow!(
-                            "TDX attestation verification failed: {}",
+                        return Err(PlatformError::unauthorized(format!(
+                            "{} attestation verification failed: {}",
+                            kind,
                             result.error.as_deref().unwrap_or("Unknown error")
-                        ));
+                        )));
                     }
                     result
                 }
                 Err(e) => {
-                    return Err(anyhow::anyhow!(
-                        "TDX attestation verification error: {}. Attestation rejected.",
-                        e
-                    ));
+                    return Err(PlatformError::unauthorized(format!(
+                        "{} attestation verification error: {}. Attestation rejected.",
+                        kind, e
+                    )));
                 }
             }
         };
 
         if !verification_result.is_valid {
             return Ok(AttestationResponse {
-                session_token: String::new(),
+                session_token: SessionToken::default(),
                 status: platform_api_models::AttestationStatus::Failed,
                 expires_at: Utc::now(),
                 verified_measurements: vec![],
@@ -200,23 +262,22 @@ impl AttestationService {
                         .error
                         .unwrap_or_else(|| "Verification failed".to_string()),
                 ),
+                refresh_token: String::new(),
             });
         }
 
-        // Generate session token
-        let session_id = Uuid::new_v4();
-        let session_token = self.generate_grant_token(&session_id, &verification_result)?;
-        let expires_at = Utc::now() + Duration::seconds(self.config.session_timeout as i64);
-
-        // Store session
         // Derive validator_hotkey from verified TEE identity (app_id and instance_id)
         // Fail fast if app_id or instance_id is missing
         let validator_hotkey = {
             let app_id = verification_result.app_id.as_ref().ok_or_else(|| {
-                anyhow::anyhow!("Security error: app_id missing from verification result")
+                PlatformError::unauthorized(
+                    "Security error: app_id missing from verification result",
+                )
             })?;
             let instance_id = verification_result.instance_id.as_ref().ok_or_else(|| {
-                anyhow::anyhow!("Security error: instance_id missing from verification result")
+                PlatformError::unauthorized(
+                    "Security error: instance_id missing from verification result",
+                )
             })?;
 
             let app_id_str = hex::encode(app_id);
@@ -224,6 +285,17 @@ impl AttestationService {
             format!("validator-{}-{}", app_id_str, instance_id_str)
         };
 
+        // Generate session token, namespaced to the validator
+        let session_id = Uuid::new_v4();
+        let session_token = SessionToken::from(self.generate_grant_token(
+            &session_id,
+            &validator_hotkey,
+            &verification_result,
+        )?);
+        let expires_at = Utc::now() + Duration::seconds(self.config.session_timeout as i64);
+        let refresh_token = self.generate_refresh_token(&session_id, &validator_hotkey)?;
+
+        // Store session
         let session = AttestationSession {
             id: session_id,
             session_token: session_token.clone(),
@@ -247,113 +319,137 @@ impl AttestationService {
             verified_measurements: verification_result.measurements,
             policy: String::new(),
             error: None,
+            refresh_token,
+        })
+    }
+
+    /// Issue a new session token for the session `refresh_token` was issued
+    /// with, see [`TdxConfig::refresh_token_ttl`]
+    ///
+    /// The response carries the same refresh token, which is not extended:
+    /// once it expires the validator has to attest again. The session token
+    /// it replaces no longer verifies. Fails with
+    /// `PlatformError::Unauthorized` for a token that is not a refresh token
+    /// this service issued, has expired, or whose session is gone or was
+    /// revoked, see [`Self::revoke_session`].
+    pub async fn refresh_session(
+        &self,
+        refresh_token: &str,
+    ) -> PlatformResult<AttestationResponse> {
+        let token = self
+            .parse_unexpired_token(refresh_token, Some(REFRESH_TOKEN_TYPE))
+            .map_err(PlatformError::unauthorized)?;
+
+        let mut sessions = self.sessions.write().await;
+        let session = sessions
+            .get_mut(&token.session_id)
+            .filter(|session| session.validator_hotkey == token.session_namespace)
+            .ok_or_else(|| {
+                PlatformError::unauthorized("Session not found or revoked, attest again")
+            })?;
+        session.session_token = SessionToken::from(self.mint_token(
+            None,
+            &session.id,
+            &session.validator_hotkey,
+            self.config.session_timeout,
+        )?);
+        session.expires_at = Utc::now() + Duration::seconds(self.config.session_timeout as i64);
+
+        Ok(AttestationResponse {
+            session_token: session.session_token.clone(),
+            status: platform_api_models::AttestationStatus::Verified,
+            expires_at: session.expires_at,
+            verified_measurements: session.verified_measurements.clone(),
+            policy: session.policy.clone(),
+            error: None,
+            refresh_token: refresh_token.to_string(),
         })
     }
 
-    pub async fn get_session(&self, id: Uuid) -> Result<AttestationSession> {
+    /// Revoke the session `session_token` was issued for
+    ///
+    /// The session is dropped, so that neither its session token nor its
+    /// refresh token are accepted anymore. Returns whether a session was
+    /// revoked.
+    pub async fn revoke_session(&self, session_token: &str) -> bool {
+        let mut sessions = self.sessions.write().await;
+        let revoked = sessions
+            .iter()
+            .find(|(_, session)| session.session_token.as_str() == session_token)
+            .map(|(id, _)| *id);
+        match revoked {
+            Some(id) => {
+                sessions.remove(&id);
+                tracing::info!(session_id = %id, "Revoked attestation session");
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Session `id` of `validator_hotkey`, the validator the caller authenticated as
+    ///
+    /// Sessions of other validators are reported as not found, so that their
+    /// ids cannot be probed.
+    pub async fn get_session(
+        &self,
+        id: Uuid,
+        validator_hotkey: &str,
+    ) -> PlatformResult<AttestationSession> {
         let sessions = self.sessions.read().await;
         sessions
             .get(&id)
+            .filter(|session| session.validator_hotkey == validator_hotkey)
             .cloned()
-            .ok_or_else(|| anyhow::anyhow!("Session not found"))
+            .ok_or_else(|| PlatformError::not_found("attestation session", id))
     }
 
-    pub async fn list_policies(&self) -> Result<Vec<AttestationPolicy>> {
+    pub async fn list_policies(&self) -> PlatformResult<Vec<AttestationPolicy>> {
         Ok(vec![])
     }
 
-    pub async fn get_policy(&self, _id: &str) -> Result<AttestationPolicy> {
-        Err(anyhow::anyhow!("Policy not found"))
+    pub async fn get_policy(&self, id: &str) -> PlatformResult<AttestationPolicy> {
+        Err(PlatformError::not_found("attestation policy", id))
     }
 
-    pub fn verify_token(&self, token: &str) -> Result<serde_json::Value> {
-        // Token format: session_id.expiration.signature (base64)
-        let parts: Vec<&str> = token.split('.').collect();
-        if parts.len() != 3 {
-            return Err(anyhow::anyhow!("Invalid token format"));
-        }
-
-        let session_id_str = parts[0];
-        let expiration_str = parts[1];
-        let signature_str = parts[2];
-
-        // Verify signature
-        let message = format!("{}.{}", session_id_str, expiration_str);
-        let mut mac = HmacSha256::new_from_slice(&self.random_key)
-            .map_err(|e| anyhow::anyhow!("Failed to create HMAC: {}", e))?;
-        mac.update(message.as_bytes());
-        let expected_signature = hex::encode(mac.finalize().into_bytes());
-
-        if signature_str != expected_signature {
-            return Err(anyhow::anyhow!("Invalid token signature"));
-        }
-
-        // Verify expiration
-        let expiration = expiration_str
-            .parse::<i64>()
-            .map_err(|_| anyhow::anyhow!("Invalid expiration format"))?;
-
-        let now = Utc::now().timestamp();
-        if expiration < now {
-            return Err(anyhow::anyhow!("Token expired"));
-        }
-
-        // Get session to extract app_id and instance_id
-        let session_id = Uuid::parse_str(session_id_str)
-            .map_err(|_| anyhow::anyhow!("Invalid session ID format"))?;
+    /// Check a grant token, failing with `PlatformError::Unauthorized` if it
+    /// is not one this service issued or has expired
+    pub fn verify_token(&self, token: &str) -> PlatformResult<serde_json::Value> {
+        let token = self
+            .parse_grant_token(token)
+            .map_err(PlatformError::unauthorized)?;
 
         // We need async access to sessions, but this is a sync function
         // For now, return the session_id and expiration - the caller can look up the session
         Ok(serde_json::json!({
-            "session_id": session_id_str,
-            "exp": expiration,
+            "session_id": token.session_id.to_string(),
+            "exp": token.expiration,
+            "aud": token.audience,
+            "session_namespace": token.session_namespace,
             "app_id": "extracted-from-session", // Will be extracted from session in async context
             "instance_id": "extracted-from-session",
         }))
     }
 
     /// Verify token and return session claims (async version)
-    pub async fn verify_token_async(&self, token: &str) -> Result<serde_json::Value> {
-        // Token format: session_id.expiration.signature (base64)
-        let parts: Vec<&str> = token.split('.').collect();
-        if parts.len() != 3 {
-            return Err(anyhow::anyhow!("Invalid token format"));
-        }
-
-        let session_id_str = parts[0];
-        let expiration_str = parts[1];
-        let signature_str = parts[2];
-
-        // Verify signature
-        let message = format!("{}.{}", session_id_str, expiration_str);
-        let mut mac = HmacSha256::new_from_slice(&self.random_key)
-            .map_err(|e| anyhow::anyhow!("Failed to create HMAC: {}", e))?;
-        mac.update(message.as_bytes());
-        let expected_signature = hex::encode(mac.finalize().into_bytes());
-
-        if signature_str != expected_signature {
-            return Err(anyhow::anyhow!("Invalid token signature"));
-        }
-
-        // Verify expiration
-        let expiration = expiration_str
-            .parse::<i64>()
-            .map_err(|_| anyhow::anyhow!("Invalid expiration format"))?;
-
-        let now = Utc::now().timestamp();
-        if expiration < now {
-            return Err(anyhow::anyhow!("Token expired"));
+    ///
+    /// Unlike [`Self::verify_token`], tokens of revoked sessions and tokens a
+    /// refresh replaced are refused.
+    pub async fn verify_token_async(&self, token_str: &str) -> PlatformResult<serde_json::Value> {
+        let token = self
+            .parse_grant_token(token_str)
+            .map_err(PlatformError::unauthorized)?;
+        let session_id = token.session_id;
+        let session = self
+            .get_session(session_id, &token.session_namespace)
+            .await?;
+        // Tokens replaced by a refresh are rotated out
+        if session.session_token.as_str() != token_str {
+            return Err(PlatformError::unauthorized(
+                "Session token was replaced, use the latest one",
+            ));
         }
 
-        // Get session to extract app_id and instance_id
-        let session_id = Uuid::parse_str(session_id_str)
-            .map_err(|_| anyhow::anyhow!("Invalid session ID format"))?;
-
-        let sessions = self.sessions.read().await;
-        let session = sessions
-            .get(&session_id)
-            .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
-
         // Extract validator_hotkey to get app_id and instance_id
         // Format: "validator-{app_id_hex}-{instance_id_hex}"
         let validator_parts: Vec<&str> = session.validator_hotkey.split('-').collect();
@@ -376,8 +472,10 @@ impl AttestationService {
         };
 
         Ok(serde_json::json!({
-            "session_id": session_id_str,
-            "exp": expiration,
+            "session_id": session_id.to_string(),
+            "exp": token.expiration,
+            "aud": token.audience,
+            "session_namespace": token.session_namespace,
             "app_id": app_id,
             "instance_id": instance_id,
         }))
@@ -418,32 +516,186 @@ impl AttestationService {
         Ok((app_id, instance_id, compose_hash))
     }
 
+    /// Mint a grant token for `session_id`, namespaced to the validator the
+    /// session belongs to
     fn generate_grant_token(
         &self,
         session_id: &Uuid,
-        _verification: &VerificationResult,
+        session_namespace: &str,
+        verification: &VerificationResult,
+    ) -> Result<String> {
+        self.check_app_id_allowed(verification)?;
+        self.mint_token(
+            None,
+            session_id,
+            session_namespace,
+            self.config.session_timeout,
+        )
+    }
+
+    /// Mint a refresh token for `session_id`, valid for
+    /// [`TdxConfig::refresh_token_ttl`]
+    fn generate_refresh_token(&self, session_id: &Uuid, session_namespace: &str) -> Result<String> {
+        self.mint_token(
+            Some(REFRESH_TOKEN_TYPE),
+            session_id,
+            session_namespace,
+            self.config.refresh_token_ttl,
+        )
+    }
+
+    /// Mint a token of type `typ`, grant tokens having none, valid for
+    /// `ttl_secs`
+    fn mint_token(
+        &self,
+        typ: Option<&str>,
+        session_id: &Uuid,
+        session_namespace: &str,
+        ttl_secs: u64,
     ) -> Result<String> {
-        // Generate token format: session_id.expiration.signature
-        let session_id_str = session_id.to_string();
-        let expiration =
-            (Utc::now() + Duration::seconds(self.config.session_timeout as i64)).timestamp();
-        let expiration_str = expiration.to_string();
-
-        // Create HMAC signature
-        let message = format!("{}.{}", session_id_str, expiration_str);
-        let mut mac = HmacSha256::new_from_slice(&self.random_key)
+        if session_namespace.contains('.') {
+            return Err(anyhow::anyhow!("Invalid session namespace"));
+        }
+
+        let audience = self
+            .config
+            .token_audiences
+            .first()
+            .context("No token audience configured")?;
+        let expiration = (Utc::now() + Duration::seconds(ttl_secs as i64)).timestamp();
+
+        // Token format: [typ.]session_id.expiration.session_namespace.audience.signature
+        let mut message = format!(
+            "{}.{}.{}.{}",
+            session_id, expiration, session_namespace, audience
+        );
+        if let Some(typ) = typ {
+            message = format!("{}.{}", typ, message);
+        }
+        let signature = self.sign_token(&message)?;
+        Ok(format!("{}.{}", message, signature))
+    }
+
+    /// Fail unless the verified app id is in `allowed_app_ids`, when configured
+    fn check_app_id_allowed(&self, verification: &VerificationResult) -> Result<()> {
+        let Some(allowed) = &self.config.allowed_app_ids else {
+            return Ok(());
+        };
+
+        // TDX app ids are the event log's text, DCAP ones the raw MRENCLAVE
+        let app_id = verification.app_id.as_deref().map(|bytes| {
+            std::str::from_utf8(bytes).map_or_else(|_| hex::encode(bytes), String::from)
+        });
+        match app_id {
+            Some(app_id) if allowed.iter().any(|a| a.eq_ignore_ascii_case(&app_id)) => Ok(()),
+            Some(app_id) => {
+                tracing::warn!(app_id = %app_id, "Refusing grant token to app id not allow-listed");
+                Err(PlatformError::unauthorized(format!(
+                    "App id {} is not allowed. Attestation rejected.",
+                    app_id
+                ))
+                .into())
+            }
+            None => {
+                tracing::warn!("Refusing grant token to attestation without app id");
+                Err(PlatformError::unauthorized(
+                    "Security error: app_id missing from verification result",
+                )
+                .into())
+            }
+        }
+    }
+
+    /// Check that `receipt`, the grant token a validator attached to a job
+    /// result, was issued by this service and return its session id
+    ///
+    /// Unlike [`Self::verify_token`], expired tokens are accepted, as receipts
+    /// are checked long after the session they were issued for.
+    pub fn verify_receipt(&self, receipt: &str) -> PlatformResult<Uuid> {
+        let token = self
+            .parse_signed_token(receipt, None)
+            .map_err(PlatformError::unauthorized)?;
+        Ok(token.session_id)
+    }
+
+    /// Check the signature, expiration and audience of a grant token
+    fn parse_grant_token(&self, token: &str) -> Result<GrantToken> {
+        self.parse_unexpired_token(token, None)
+    }
+
+    /// Check the signature, expiration and audience of a token of type `typ`
+    fn parse_unexpired_token(&self, token: &str, typ: Option<&str>) -> Result<GrantToken> {
+        let token = self.parse_signed_token(token, typ)?;
+        if token.expiration < Utc::now().timestamp() {
+            return Err(anyhow::anyhow!("Token expired"));
+        }
+        Ok(token)
+    }
+
+    /// Check the signature and audience of a token of type `typ`, grant tokens
+    /// having none, whether expired or not
+    ///
+    /// The type is signed, so tokens of one type are never accepted as another.
+    fn parse_signed_token(&self, token: &str, typ: Option<&str>) -> Result<GrantToken> {
+        // Token format: [typ.]session_id.expiration.session_namespace.audience.signature,
+        // the audience may itself contain dots
+        let (message, signature) = token
+            .rsplit_once('.')
+            .ok_or_else(|| anyhow::anyhow!("Invalid token format"))?;
+        let claims = match typ {
+            Some(typ) => message
+                .strip_prefix(typ)
+                .and_then(|claims| claims.strip_prefix('.'))
+                .ok_or_else(|| anyhow::anyhow!("Not a {} token", typ))?,
+            None => message,
+        };
+        let parts: Vec<&str> = claims.splitn(4, '.').collect();
+        if parts.len() != 4 {
+            return Err(anyhow::anyhow!("Invalid token format"));
+        }
+
+        if signature != self.sign_token(message)? {
+            return Err(anyhow::anyhow!("Invalid token signature"));
+        }
+
+        let expiration = parts[1]
+            .parse::<i64>()
+            .map_err(|_| anyhow::anyhow!("Invalid expiration format"))?;
+
+        let audience = parts[3];
+        if !self.config.token_audiences.iter().any(|a| a == audience) {
+            return Err(anyhow::anyhow!("Token audience not accepted: {}", audience));
+        }
+
+        let session_id =
+            Uuid::parse_str(parts[0]).map_err(|_| anyhow::anyhow!("Invalid session ID format"))?;
+
+        Ok(GrantToken {
+            session_id,
+            expiration,
+            session_namespace: parts[2].to_string(),
+            audience: audience.to_string(),
+        })
+    }
+
+    /// Hex HMAC of `message` with the token signing key
+    fn sign_token(&self, message: &str) -> Result<String> {
+        let mut mac = HmacSha256::new_from_slice(&self.token_key)
             .map_err(|e| anyhow::anyhow!("Failed to create HMAC: {}", e))?;
         mac.update(message.as_bytes());
-        let signature = hex::encode(mac.finalize().into_bytes());
-
-        // Token format: session_id.expiration.signature
-        Ok(format!(
-            "{}.{}.{}",
-            session_id_str, expiration_str, signature
-        ))
+        Ok(hex::encode(mac.finalize().into_bytes()))
     }
 }
 
+/// Claims of a verified grant token
+struct GrantToken {
+    session_id: Uuid,
+    expiration: i64,
+    /// Validator hotkey of the session the token was issued for
+    session_namespace: String,
+    audience: String,
+}
+
 /// Verification result
 #[derive(Debug, Clone)]
 pub struct VerificationResult {
@@ -454,3 +706,294 @@ pub struct VerificationResult {
     pub device_id: Option<Vec<u8>>,
     pub error: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verification() -> VerificationResult {
+        VerificationResult {
+            is_valid: true,
+            measurements: vec![],
+            app_id: None,
+            instance_id: None,
+            device_id: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_grant_token_audience() {
+        let mut config = AttestationConfig::from_env();
+        config.token_audiences = vec!["executor-eu".to_string(), "executor-us".to_string()];
+        config.allowed_app_ids = None;
+        let mut service = AttestationService::new(&config).unwrap();
+
+        let token = service
+            .generate_grant_token(&Uuid::new_v4(), "validator-a", &verification())
+            .unwrap();
+        let claims = service.verify_token(&token).unwrap();
+        assert_eq!(claims["aud"], "executor-eu");
+
+        // Tokens minted for another fleet are rejected
+        service.config.token_audiences = vec!["executor-us".to_string()];
+        let err = service.verify_token(&token).unwrap_err();
+        assert!(err.to_string().contains("audience"));
+
+        // The audience is covered by the signature
+        let forged = token.replacen("executor-eu", "executor-us", 1);
+        let err = service.verify_token(&forged).unwrap_err();
+        assert!(err.to_string().contains("signature"));
+    }
+
+    #[test]
+    fn test_expired_grant_token_verifies_as_receipt() {
+        let mut config = AttestationConfig::from_env();
+        config.token_audiences = vec!["executor-eu".to_string()];
+        config.token_signing_key = None;
+        let service = AttestationService::new(&config).unwrap();
+        let session_id = Uuid::new_v4();
+
+        let expired = Utc::now().timestamp() - 60;
+        let message = format!("{}.{}.validator-a.executor-eu", session_id, expired);
+        let mut receipt = format!("{}.{}", message, service.sign_token(&message).unwrap());
+        let err = service.verify_token(&receipt).unwrap_err();
+        assert!(err.to_string().contains("expired"));
+        assert_eq!(service.verify_receipt(&receipt).unwrap(), session_id);
+
+        // Receipts of another service instance do not verify
+        let other = AttestationService::new(&config).unwrap();
+        assert!(other.verify_receipt(&receipt).is_err());
+        receipt.push('0');
+        assert!(service.verify_receipt(&receipt).is_err());
+    }
+
+    #[test]
+    fn test_receipts_verify_across_restarts_with_signing_key() {
+        let mut config = AttestationConfig::from_env();
+        config.token_audiences = vec!["executor-eu".to_string()];
+        config.token_signing_key = Some("2a".repeat(32));
+        let service = AttestationService::new(&config).unwrap();
+        let session_id = Uuid::new_v4();
+
+        let expired = Utc::now().timestamp() - 60;
+        let message = format!("{}.{}.validator-a.executor-eu", session_id, expired);
+        let receipt = format!("{}.{}", message, service.sign_token(&message).unwrap());
+
+        // A platform restarted with the same key still verifies the receipt
+        let restarted = AttestationService::new(&config).unwrap();
+        assert_eq!(restarted.verify_receipt(&receipt).unwrap(), session_id);
+
+        // but not one with another key
+        config.token_signing_key = Some("2b".repeat(32));
+        let rekeyed = AttestationService::new(&config).unwrap();
+        assert!(rekeyed.verify_receipt(&receipt).is_err());
+
+        config.token_signing_key = Some("2a".repeat(16));
+        assert!(AttestationService::new(&config).is_err());
+        config.token_signing_key = Some("not hex".to_string());
+        assert!(AttestationService::new(&config).is_err());
+    }
+
+    fn session(id: Uuid, validator_hotkey: &str, token: &str) -> AttestationSession {
+        AttestationSession {
+            id,
+            session_token: SessionToken::from(token),
+            attestation_type: AttestationType::Tdx,
+            status: platform_api_models::AttestationStatus::Verified,
+            validator_hotkey: validator_hotkey.to_string(),
+            created_at: Utc::now(),
+            expires_at: Utc::now() + Duration::hours(1),
+            verified_measurements: vec![],
+            policy: String::new(),
+            key_releases: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_session() {
+        let mut config = AttestationConfig::from_env();
+        config.allowed_app_ids = None;
+        config.session_timeout = 300;
+        let service = AttestationService::new(&config).unwrap();
+
+        let session_id = Uuid::new_v4();
+        let session_token = service
+            .generate_grant_token(&session_id, "validator-a", &verification())
+            .unwrap();
+        let refresh_token = service
+            .generate_refresh_token(&session_id, "validator-a")
+            .unwrap();
+        service.sessions.write().await.insert(
+            session_id,
+            session(session_id, "validator-a", &session_token),
+        );
+
+        let response = service.refresh_session(&refresh_token).await.unwrap();
+        assert_eq!(
+            response.status,
+            platform_api_models::AttestationStatus::Verified
+        );
+        assert!(response.expires_at <= Utc::now() + Duration::seconds(300));
+        // The refresh token is handed back as it was, not extended
+        assert_eq!(response.refresh_token, refresh_token);
+        let claims = service
+            .verify_token_async(response.session_token.as_str())
+            .await
+            .unwrap();
+        assert_eq!(claims["session_id"], session_id.to_string());
+        let stored = service
+            .get_session(session_id, "validator-a")
+            .await
+            .unwrap();
+        assert_eq!(stored.session_token, response.session_token);
+
+        // Neither kind of token is accepted as the other
+        assert!(service.verify_token(&refresh_token).is_err());
+        assert!(service.verify_receipt(&refresh_token).is_err());
+        let err = service.refresh_session(&session_token).await.unwrap_err();
+        assert_eq!(err.status_code(), 401);
+
+        // Nor tampered, expired or foreign refresh tokens
+        let tampered = refresh_token.replacen("validator-a", "validator-b", 1);
+        assert!(service.refresh_session(&tampered).await.is_err());
+        let message = format!(
+            "refresh.{}.{}.validator-a.{}",
+            session_id,
+            Utc::now().timestamp() - 60,
+            config.token_audiences[0]
+        );
+        let expired = format!("{}.{}", message, service.sign_token(&message).unwrap());
+        let err = service.refresh_session(&expired).await.unwrap_err();
+        assert!(err.to_string().contains("expired"));
+        let foreign = service
+            .generate_refresh_token(&session_id, "validator-b")
+            .unwrap();
+        assert_eq!(
+            service
+                .refresh_session(&foreign)
+                .await
+                .unwrap_err()
+                .status_code(),
+            401
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_and_revoke_ends_session() {
+        let mut config = AttestationConfig::from_env();
+        config.allowed_app_ids = None;
+        config.session_timeout = 300;
+        let service = AttestationService::new(&config).unwrap();
+
+        let session_id = Uuid::new_v4();
+        // Minted with another lifetime, so that the refreshed token differs
+        let session_token = service
+            .mint_token(None, &session_id, "validator-a", 60)
+            .unwrap();
+        let refresh_token = service
+            .generate_refresh_token(&session_id, "validator-a")
+            .unwrap();
+        service.sessions.write().await.insert(
+            session_id,
+            session(session_id, "validator-a", &session_token),
+        );
+
+        // The refreshed token replaces the previous one
+        let refreshed = service.refresh_session(&refresh_token).await.unwrap();
+        assert_ne!(refreshed.session_token.as_str(), session_token);
+        let err = service
+            .verify_token_async(&session_token)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), 401);
+        service
+            .verify_token_async(refreshed.session_token.as_str())
+            .await
+            .unwrap();
+
+        // The previous token no longer names the session
+        assert!(!service.revoke_session(&session_token).await);
+        assert!(
+            service
+                .revoke_session(refreshed.session_token.as_str())
+                .await
+        );
+
+        let err = service.refresh_session(&refresh_token).await.unwrap_err();
+        assert_eq!(err.status_code(), 401);
+        assert!(service
+            .verify_token_async(refreshed.session_token.as_str())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_isolated_by_validator() {
+        let mut config = AttestationConfig::from_env();
+        config.allowed_app_ids = None;
+        let service = AttestationService::new(&config).unwrap();
+
+        let session_id = Uuid::new_v4();
+        let token = service
+            .generate_grant_token(&session_id, "validator-a", &verification())
+            .unwrap();
+        service
+            .sessions
+            .write()
+            .await
+            .insert(session_id, session(session_id, "validator-a", &token));
+
+        let session = service
+            .get_session(session_id, "validator-a")
+            .await
+            .unwrap();
+        assert_eq!(session.session_token.as_str(), token);
+        let claims = service.verify_token_async(&token).await.unwrap();
+        assert_eq!(claims["session_namespace"], "validator-a");
+
+        // Another validator cannot tell the session exists
+        let err = service
+            .get_session(session_id, "validator-b")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PlatformError::NotFound { .. }));
+
+        // Nor read it with a token of its own naming the session
+        let forged = service
+            .generate_grant_token(&session_id, "validator-b", &verification())
+            .unwrap();
+        let err = service.verify_token_async(&forged).await.unwrap_err();
+        assert_eq!(err.status_code(), 404);
+    }
+
+    #[test]
+    fn test_grant_token_app_id_allow_list() {
+        let mut config = AttestationConfig::from_env();
+        config.allowed_app_ids = Some(vec!["ABCDEF".to_string()]);
+        let service = AttestationService::new(&config).unwrap();
+
+        let mut verification = verification();
+        let err = service
+            .generate_grant_token(&Uuid::new_v4(), "validator-a", &verification)
+            .unwrap_err();
+        assert!(err.to_string().contains("app_id missing"));
+
+        verification.app_id = Some(b"123456".to_vec());
+        let err = service
+            .generate_grant_token(&Uuid::new_v4(), "validator-a", &verification)
+            .unwrap_err();
+        assert!(err.to_string().contains("not allowed"));
+
+        verification.app_id = Some(b"abcdef".to_vec());
+        assert!(service
+            .generate_grant_token(&Uuid::new_v4(), "validator-a", &verification)
+            .is_ok());
+
+        // Raw app ids, such as MRENCLAVE, are compared hex-encoded
+        verification.app_id = Some(vec![0xab, 0xcd, 0xef]);
+        assert!(service
+            .generate_grant_token(&Uuid::new_v4(), "validator-a", &verification)
+            .is_ok());
+    }
+}
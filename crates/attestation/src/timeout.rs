@@ -0,0 +1,82 @@
+//! Bounded attestation verification
+//!
+//! Verifications may call out to remote services such as dstack-verifier or
+//! PCCS; [`with_verification_timeout`] keeps a hung one from blocking the
+//! validator connection waiting on it.
+
+use platform_api_models::PlatformError;
+use std::future::Future;
+use std::time::Duration;
+
+/// An attestation verification did not complete in time, see
+/// `TdxConfig::verification_timeout`
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Attestation verification timed out after {}s", timeout.as_secs_f64())]
+pub struct VerificationTimeout {
+    pub timeout: Duration,
+}
+
+impl From<VerificationTimeout> for PlatformError {
+    fn from(err: VerificationTimeout) -> Self {
+        PlatformError::upstream("attestation verifier", err)
+    }
+}
+
+/// Run `verification`, failing with [`VerificationTimeout`] if it takes
+/// longer than `timeout`
+pub async fn with_verification_timeout<T, E: From<VerificationTimeout>>(
+    timeout: Duration,
+    verification: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    match tokio::time::timeout(timeout, verification).await {
+        Ok(result) => result,
+        Err(_) => Err(VerificationTimeout { timeout }.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::time::Instant;
+
+    /// Verifier that hangs, like an unresponsive dstack-verifier
+    struct HungVerifier {
+        delay: Duration,
+    }
+
+    impl HungVerifier {
+        async fn verify(&self) -> Result<bool> {
+            tokio::time::sleep(self.delay).await;
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hung_verifier_times_out() {
+        let verifier = HungVerifier {
+            delay: Duration::from_secs(30),
+        };
+        let timeout = Duration::from_millis(50);
+
+        let started = Instant::now();
+        let err = with_verification_timeout(timeout, verifier.verify())
+            .await
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            err.downcast_ref::<VerificationTimeout>(),
+            Some(&VerificationTimeout { timeout })
+        );
+
+        // Verifications completing in time are unaffected
+        let verifier = HungVerifier {
+            delay: Duration::from_millis(1),
+        };
+        assert!(
+            with_verification_timeout(Duration::from_secs(5), verifier.verify())
+                .await
+                .unwrap()
+        );
+    }
+}
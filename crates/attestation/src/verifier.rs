@@ -19,7 +19,11 @@ struct EventLogEvent {
     event_payload: String,
 }
 
-const DEFAULT_PCCS_URL: &str = "https://pccs.bittensor.com/sgx/certification/v4/";
+pub(crate) const DEFAULT_PCCS_URL: &str = "https://pccs.bittensor.com/sgx/certification/v4/";
+
+/// TCB statuses of a verified quote that are accepted
+pub(crate) const ACCEPTED_TCB_STATUSES: [&str; 3] =
+    ["UpToDate", "SWHardeningNeeded", "ConfigurationNeeded"];
 
 impl TdxVerifier {
     pub fn new(config: TdxConfig) -> Self {
@@ -56,23 +60,8 @@ impl TdxVerifier {
 
         tracing::info!("Quote received: {} bytes", quote.len());
 
-        // Verify the quote using dcap-qvl
-        let pccs_url = self.config.pccs_url.as_deref().unwrap_or(DEFAULT_PCCS_URL);
-
         // Get collateral from PCCS or Intel PCS
-        let collateral = match dcap_qvl::collateral::get_collateral(pccs_url, quote).await {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::warn!(
-                    "Failed to get collateral from PCCS, trying Intel PCS: {}",
-                    e
-                );
-                // Fallback to Intel PCS
-                dcap_qvl::collateral::get_collateral_from_pcs(quote)
-                    .await
-                    .context("Failed to get collateral from Intel PCS")?
-            }
-        };
+        let collateral = crate::dcap::get_collateral(&self.config, quote).await?;
 
         // Verify the quote
         let now = std::time::SystemTime::now()
@@ -88,8 +77,7 @@ impl TdxVerifier {
         );
 
         // Only accept quotes with valid TCB status
-        let valid_statuses = ["UpToDate", "SWHardeningNeeded", "ConfigurationNeeded"];
-        let is_valid = valid_statuses.contains(&verified_report.status.as_str());
+        let is_valid = ACCEPTED_TCB_STATUSES.contains(&verified_report.status.as_str());
 
         if !is_valid {
             return Ok(VerificationResult {
@@ -0,0 +1,279 @@
+//! Content-addressed cache of built images
+//!
+//! Builds are keyed by the digest of their source, so building a source that
+//! was already built reuses the image pushed for it instead of building it
+//! again. The images of the entries add up to at most `build_cache_size`
+//! bytes; past that, entries are evicted least recently used first.
+//!
+//! The index is kept in memory and, with a database, stored in the
+//! `image_build_cache` table it is loaded from on startup.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// Image cached for a source digest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub source_digest: String,
+    pub image: String,
+    pub size_bytes: u64,
+}
+
+struct Entry {
+    image: String,
+    size_bytes: u64,
+    /// Order of the last use, higher is more recent
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Index {
+    entries: HashMap<String, Entry>,
+    uses: u64,
+}
+
+impl Index {
+    fn touch(&mut self) -> u64 {
+        self.uses += 1;
+        self.uses
+    }
+}
+
+/// Index of the images built from each source digest
+pub struct BuildCache {
+    capacity: u64,
+    database_pool: Option<Arc<PgPool>>,
+    index: Mutex<Index>,
+}
+
+impl BuildCache {
+    /// Cache of at most `capacity` bytes of images
+    pub fn new(capacity: u64, database_pool: Option<Arc<PgPool>>) -> Self {
+        Self {
+            capacity,
+            database_pool,
+            index: Mutex::new(Index::default()),
+        }
+    }
+
+    /// Load the stored entries, returning how many there are
+    pub async fn load(&self) -> Result<usize> {
+        let Some(pool) = &self.database_pool else {
+            return Ok(0);
+        };
+        let rows: Vec<(String, String, i64)> = sqlx::query_as(
+            "SELECT source_digest, image, size_bytes FROM image_build_cache ORDER BY last_used_at",
+        )
+        .fetch_all(pool.as_ref())
+        .await
+        .context("Failed to load image build cache")?;
+
+        let mut index = self.index.lock().await;
+        for (source_digest, image, size_bytes) in rows {
+            let last_used = index.touch();
+            index.entries.insert(
+                source_digest,
+                Entry {
+                    image,
+                    size_bytes: size_bytes.max(0) as u64,
+                    last_used,
+                },
+            );
+        }
+        Ok(index.entries.len())
+    }
+
+    /// Image built from `source_digest`, if cached, marking it used
+    pub async fn lookup(&self, source_digest: &str) -> Option<String> {
+        let image = {
+            let mut index = self.index.lock().await;
+            let last_used = index.touch();
+            let entry = index.entries.get_mut(source_digest)?;
+            entry.last_used = last_used;
+            entry.image.clone()
+        };
+
+        if let Some(pool) = &self.database_pool {
+            let touched = sqlx::query(
+                "UPDATE image_build_cache SET last_used_at = $2 WHERE source_digest = $1",
+            )
+            .bind(source_digest)
+            .bind(Utc::now())
+            .execute(pool.as_ref())
+            .await;
+            if let Err(e) = touched {
+                warn!(
+                    source_digest,
+                    "Failed to store image build cache use: {}", e
+                );
+            }
+        }
+        Some(image)
+    }
+
+    /// Cache `image`, built from `source_digest`, returning the entries
+    /// evicted to keep the cache within its capacity
+    ///
+    /// An image larger than the whole cache is evicted right away.
+    pub async fn insert(
+        &self,
+        source_digest: &str,
+        image: &str,
+        size_bytes: u64,
+    ) -> Result<Vec<CacheEntry>> {
+        let evicted = {
+            let mut index = self.index.lock().await;
+            let last_used = index.touch();
+            index.entries.insert(
+                source_digest.to_string(),
+                Entry {
+                    image: image.to_string(),
+                    size_bytes,
+                    last_used,
+                },
+            );
+            evict(&mut index.entries, self.capacity)
+        };
+
+        if let Some(pool) = &self.database_pool {
+            self.store(pool, source_digest, image, size_bytes, &evicted)
+                .await?;
+        }
+        Ok(evicted)
+    }
+
+    async fn store(
+        &self,
+        pool: &PgPool,
+        source_digest: &str,
+        image: &str,
+        size_bytes: u64,
+        evicted: &[CacheEntry],
+    ) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO image_build_cache (source_digest, image, size_bytes, last_used_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (source_digest) DO UPDATE SET
+                image = EXCLUDED.image,
+                size_bytes = EXCLUDED.size_bytes,
+                last_used_at = EXCLUDED.last_used_at
+            "#,
+        )
+        .bind(source_digest)
+        .bind(image)
+        .bind(size_bytes.min(i64::MAX as u64) as i64)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to store image build cache entry")?;
+
+        let evicted: Vec<&str> = evicted
+            .iter()
+            .map(|entry| entry.source_digest.as_str())
+            .collect();
+        sqlx::query("DELETE FROM image_build_cache WHERE source_digest = ANY($1)")
+            .bind(&evicted)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete evicted image build cache entries")?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Cached images, least recently used first
+    pub async fn entries(&self) -> Vec<CacheEntry> {
+        let index = self.index.lock().await;
+        let mut entries: Vec<_> = index.entries.iter().collect();
+        entries.sort_by_key(|(_, entry)| entry.last_used);
+        entries
+            .into_iter()
+            .map(|(source_digest, entry)| cache_entry(source_digest.clone(), entry))
+            .collect()
+    }
+}
+
+/// Remove the least recently used entries until the images of the others fit
+/// in `capacity` bytes
+fn evict(entries: &mut HashMap<String, Entry>, capacity: u64) -> Vec<CacheEntry> {
+    let mut total: u64 = entries.values().map(|entry| entry.size_bytes).sum();
+    let mut evicted = vec![];
+    while total > capacity {
+        let Some(oldest) = entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(source_digest, _)| source_digest.clone())
+        else {
+            break;
+        };
+        let entry = entries
+            .remove(&oldest)
+            .expect("oldest entry is in the index");
+        total -= entry.size_bytes;
+        evicted.push(cache_entry(oldest, &entry));
+    }
+    evicted
+}
+
+fn cache_entry(source_digest: String, entry: &Entry) -> CacheEntry {
+    CacheEntry {
+        source_digest,
+        image: entry.image.clone(),
+        size_bytes: entry.size_bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digests(entries: &[CacheEntry]) -> Vec<&str> {
+        entries
+            .iter()
+            .map(|entry| entry.source_digest.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_lookup_hit_and_miss() {
+        let cache = BuildCache::new(100, None);
+        assert_eq!(cache.lookup("commit:abc").await, None);
+
+        let evicted = cache
+            .insert("commit:abc", "registry/challenge-1:b1", 10)
+            .await
+            .unwrap();
+        assert!(evicted.is_empty());
+        assert_eq!(
+            cache.lookup("commit:abc").await.as_deref(),
+            Some("registry/challenge-1:b1")
+        );
+        assert_eq!(cache.lookup("commit:def").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_evicted_over_capacity() {
+        let cache = BuildCache::new(100, None);
+        cache.insert("a", "image-a", 40).await.unwrap();
+        cache.insert("b", "image-b", 40).await.unwrap();
+        // Using a makes b the least recently used
+        assert!(cache.lookup("a").await.is_some());
+
+        let evicted = cache.insert("c", "image-c", 40).await.unwrap();
+        assert_eq!(digests(&evicted), vec!["b"]);
+        assert_eq!(evicted[0].image, "image-b");
+        assert_eq!(digests(&cache.entries().await), vec!["a", "c"]);
+        assert_eq!(cache.lookup("b").await, None);
+
+        // An image larger than the cache does not stay in it
+        let evicted = cache.insert("d", "image-d", 150).await.unwrap();
+        assert_eq!(digests(&evicted), vec!["a", "c", "d"]);
+        assert!(cache.entries().await.is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+use chrono::Utc;
+use platform_api_models::{BuildLogEvent, BuildLogLevel};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Emitter for the structured log of a single build
+///
+/// Events are sent to the receiver returned by [`BuildLog::new`]; a disabled
+/// log drops them.
+#[derive(Clone)]
+pub struct BuildLog {
+    build_id: Uuid,
+    sender: Option<mpsc::UnboundedSender<BuildLogEvent>>,
+}
+
+impl BuildLog {
+    pub fn new(build_id: Uuid) -> (Self, mpsc::UnboundedReceiver<BuildLogEvent>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (
+            Self {
+                build_id,
+                sender: Some(sender),
+            },
+            receiver,
+        )
+    }
+
+    /// A log that discards all events
+    pub fn disabled() -> Self {
+        Self {
+            build_id: Uuid::nil(),
+            sender: None,
+        }
+    }
+
+    pub fn build_id(&self) -> Uuid {
+        self.build_id
+    }
+
+    pub fn info(&self, message: impl Into<String>) {
+        self.emit(BuildLogLevel::Info, message.into(), false);
+    }
+
+    pub fn warn(&self, message: impl Into<String>) {
+        self.emit(BuildLogLevel::Warn, message.into(), false);
+    }
+
+    pub fn error(&self, message: impl Into<String>) {
+        self.emit(BuildLogLevel::Error, message.into(), false);
+    }
+
+    /// Emit the last event of the build
+    pub fn finish(&self, level: BuildLogLevel, message: impl Into<String>) {
+        self.emit(level, message.into(), true);
+    }
+
+    fn emit(&self, level: BuildLogLevel, message: String, done: bool) {
+        if let Some(sender) = &self.sender {
+            // The receiver going away only means nobody is tailing the build
+            let _ = sender.send(BuildLogEvent {
+                build_id: self.build_id,
+                timestamp: Utc::now(),
+                level,
+                message,
+                done,
+            });
+        }
+    }
+}
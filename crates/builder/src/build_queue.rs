@@ -0,0 +1,240 @@
+//! Fair admission of image builds
+//!
+//! At most `max_running` builds run at once, and at most `max_per_owner` of
+//! any one owner, so an owner queuing many builds cannot take every slot.
+//! Queued builds start round-robin across their owners: once a build of an
+//! owner starts, the owners queued behind it go before its next build. An
+//! owner at its cap is skipped, keeping its turn, until one of its builds
+//! finishes.
+
+use std::collections::{HashMap, VecDeque};
+use uuid::Uuid;
+
+/// Queue of builds waiting for a slot, holding a `T` for each
+pub struct FairQueue<T> {
+    max_running: usize,
+    max_per_owner: usize,
+    /// Owners with queued builds, in the order they are served
+    owners: VecDeque<String>,
+    /// Queued builds of each owner, oldest first
+    queued: HashMap<String, VecDeque<(Uuid, T)>>,
+    /// Running builds of each owner
+    running: HashMap<String, usize>,
+}
+
+impl<T> FairQueue<T> {
+    pub fn new(max_running: usize, max_per_owner: usize) -> Self {
+        Self {
+            max_running: max_running.max(1),
+            max_per_owner: max_per_owner.max(1),
+            owners: VecDeque::new(),
+            queued: HashMap::new(),
+            running: HashMap::new(),
+        }
+    }
+
+    /// Queue build `build_id` of `owner`
+    pub fn push(&mut self, owner: &str, build_id: Uuid, item: T) {
+        let builds = self.queued.entry(owner.to_string()).or_default();
+        if builds.is_empty() {
+            self.owners.push_back(owner.to_string());
+        }
+        builds.push_back((build_id, item));
+    }
+
+    /// Start the next build, if a slot is free for it, returning its owner
+    ///
+    /// The build counts as running until [`Self::finish`] is called for its
+    /// owner.
+    pub fn pop(&mut self) -> Option<(String, Uuid, T)> {
+        if self.running() >= self.max_running {
+            return None;
+        }
+        let index = self
+            .owners
+            .iter()
+            .position(|owner| self.running.get(owner).copied().unwrap_or(0) < self.max_per_owner)?;
+        let owner = self.owners.remove(index)?;
+        let builds = self.queued.get_mut(&owner)?;
+        let (build_id, item) = builds.pop_front()?;
+        if builds.is_empty() {
+            self.queued.remove(&owner);
+        } else {
+            self.owners.push_back(owner.clone());
+        }
+        *self.running.entry(owner.clone()).or_default() += 1;
+        Some((owner, build_id, item))
+    }
+
+    /// Free the slot of a finished build of `owner`
+    pub fn finish(&mut self, owner: &str) {
+        if let Some(running) = self.running.get_mut(owner) {
+            *running -= 1;
+            if *running == 0 {
+                self.running.remove(owner);
+            }
+        }
+    }
+
+    /// Remove queued build `build_id`, returning it if it was queued
+    pub fn remove(&mut self, build_id: Uuid) -> Option<T> {
+        let (owner, builds) = self
+            .queued
+            .iter_mut()
+            .find(|(_, builds)| builds.iter().any(|(id, _)| *id == build_id))?;
+        let owner = owner.clone();
+        let index = builds.iter().position(|(id, _)| *id == build_id)?;
+        let (_, item) = builds.remove(index)?;
+        if builds.is_empty() {
+            self.queued.remove(&owner);
+            self.owners.retain(|queued| *queued != owner);
+        }
+        Some(item)
+    }
+
+    /// Place of queued build `build_id` in the order builds start, 1 being
+    /// next
+    ///
+    /// The per-owner cap is not accounted for, as it depends on when running
+    /// builds finish.
+    pub fn position(&self, build_id: Uuid) -> Option<usize> {
+        let mut position = 0;
+        for round in 0.. {
+            let mut served = false;
+            for owner in &self.owners {
+                let Some((id, _)) = self.queued.get(owner).and_then(|builds| builds.get(round))
+                else {
+                    continue;
+                };
+                served = true;
+                position += 1;
+                if *id == build_id {
+                    return Some(position);
+                }
+            }
+            if !served {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Number of running builds
+    pub fn running(&self) -> usize {
+        self.running.values().sum()
+    }
+
+    /// Number of queued builds
+    pub fn len(&self) -> usize {
+        self.queued.values().map(VecDeque::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    pub fn max_running(&self) -> usize {
+        self.max_running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Queue of `count` builds of each owner, returning their IDs by owner
+    fn queue(
+        max_running: usize,
+        max_per_owner: usize,
+        builds: &[(&str, usize)],
+    ) -> (FairQueue<()>, HashMap<String, Vec<Uuid>>) {
+        let mut queue = FairQueue::new(max_running, max_per_owner);
+        let mut ids: HashMap<String, Vec<Uuid>> = HashMap::new();
+        for (owner, count) in builds {
+            for _ in 0..*count {
+                let build_id = Uuid::new_v4();
+                queue.push(owner, build_id, ());
+                ids.entry(owner.to_string()).or_default().push(build_id);
+            }
+        }
+        (queue, ids)
+    }
+
+    #[test]
+    fn test_owners_served_round_robin() {
+        let (mut queue, ids) = queue(1, 1, &[("alice", 5), ("bob", 2)]);
+        assert_eq!(queue.len(), 7);
+        assert_eq!(queue.position(ids["bob"][0]), Some(2));
+        assert_eq!(queue.position(ids["alice"][2]), Some(5));
+        assert_eq!(queue.position(ids["alice"][4]), Some(7));
+
+        let mut order = vec![];
+        while let Some((owner, build_id, ())) = queue.pop() {
+            // One slot: nothing else starts until the build finishes
+            assert!(queue.pop().is_none());
+            assert_eq!(queue.running(), 1);
+            order.push((owner.clone(), build_id));
+            queue.finish(&owner);
+        }
+        let owners: Vec<_> = order.iter().map(|(owner, _)| owner.as_str()).collect();
+        assert_eq!(
+            owners,
+            vec!["alice", "bob", "alice", "bob", "alice", "alice", "alice"]
+        );
+        // Each owner's builds start in the order they were queued
+        let alice: Vec<_> = order
+            .iter()
+            .filter(|(owner, _)| owner == "alice")
+            .map(|(_, id)| *id)
+            .collect();
+        assert_eq!(alice, ids["alice"]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.running(), 0);
+    }
+
+    #[test]
+    fn test_per_owner_cap() {
+        let (mut queue, ids) = queue(4, 2, &[("alice", 6)]);
+        let first = queue.pop().unwrap();
+        let second = queue.pop().unwrap();
+        assert_eq!((first.1, second.1), (ids["alice"][0], ids["alice"][1]));
+        // Free slots are left unused rather than given to alice's third build
+        assert!(queue.pop().is_none());
+        assert_eq!(queue.running(), 2);
+
+        // Another owner gets them meanwhile
+        queue.push("bob", Uuid::new_v4(), ());
+        assert_eq!(queue.pop().unwrap().0, "bob");
+        assert!(queue.pop().is_none());
+
+        queue.finish("alice");
+        let third = queue.pop().unwrap();
+        assert_eq!((third.0.as_str(), third.1), ("alice", ids["alice"][2]));
+        assert!(queue.pop().is_none());
+        assert_eq!(queue.running(), 3);
+    }
+
+    #[test]
+    fn test_global_cap_and_remove() {
+        let (mut queue, ids) = queue(2, 2, &[("alice", 3), ("bob", 3)]);
+        let started: Vec<_> = std::iter::from_fn(|| queue.pop())
+            .map(|(owner, _, ())| owner)
+            .collect();
+        assert_eq!(started, vec!["alice", "bob"]);
+
+        // Removing queued builds leaves the running ones alone
+        assert!(queue.remove(ids["bob"][0]).is_none());
+        assert!(queue.remove(ids["bob"][1]).is_some());
+        assert!(queue.remove(ids["bob"][2]).is_some());
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.running(), 2);
+        assert_eq!(queue.position(ids["alice"][2]), Some(2));
+
+        queue.finish("bob");
+        assert_eq!(queue.pop().unwrap().1, ids["alice"][1]);
+        queue.finish("alice");
+        assert_eq!(queue.pop().unwrap().1, ids["alice"][2]);
+        assert!(queue.pop().is_none());
+        assert!(queue.is_empty());
+    }
+}
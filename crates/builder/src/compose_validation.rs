@@ -0,0 +1,558 @@
+//! Validation of challenge docker-compose files
+//!
+//! A compose file is checked when a challenge is created or its environment
+//! updated, so that a file the CVM cannot run is refused up front instead of
+//! failing to boot hours later. Every problem is reported as a
+//! [`ComposeFinding`], all at once:
+//!
+//! - the file must parse, declare a `version` 3.x and at least one service;
+//! - images must come from an allowed registry, see
+//!   [`ComposePolicy::allowed_registries`];
+//! - services cannot be privileged or use the host network;
+//! - bind mounts must be of allowed host paths, see
+//!   [`ComposePolicy::allowed_bind_mounts`];
+//! - variables the file references without a default, such as `${API_KEY}`,
+//!   and variables passed through from the environment, such as `- API_KEY`,
+//!   must be declared in the challenge's environment.
+//!
+//! A file holding only comments is a placeholder for a challenge whose compose
+//! file is not known yet, and is not checked.
+
+use platform_api_models::{FieldError, PlatformError};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+
+/// Registries images without an explicit one are pulled from
+const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// Rule a compose file breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComposeRule {
+    Syntax,
+    Version,
+    Image,
+    Privileged,
+    HostNetwork,
+    BindMount,
+    MissingEnv,
+}
+
+impl ComposeRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComposeRule::Syntax => "syntax",
+            ComposeRule::Version => "version",
+            ComposeRule::Image => "image",
+            ComposeRule::Privileged => "privileged",
+            ComposeRule::HostNetwork => "host_network",
+            ComposeRule::BindMount => "bind_mount",
+            ComposeRule::MissingEnv => "missing_env",
+        }
+    }
+}
+
+/// One problem of a compose file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComposeFinding {
+    pub rule: ComposeRule,
+    /// Service the problem is in, if any
+    pub service: Option<String>,
+    pub message: String,
+}
+
+/// A compose file was refused, see the module documentation
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Invalid docker-compose file: {}", summary(.findings))]
+pub struct InvalidCompose {
+    pub findings: Vec<ComposeFinding>,
+}
+
+fn summary(findings: &[ComposeFinding]) -> String {
+    findings
+        .iter()
+        .map(|finding| match &finding.service {
+            Some(service) => format!("{}: {}", service, finding.message),
+            None => finding.message.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Each finding is a field error, at `services.<name>` for the problems of a
+/// service and `compose` for those of the whole file
+impl From<InvalidCompose> for PlatformError {
+    fn from(err: InvalidCompose) -> Self {
+        let fields = err
+            .findings
+            .into_iter()
+            .map(|finding| FieldError {
+                field: match finding.service {
+                    Some(service) => format!("services.{}", service),
+                    None => "compose".to_string(),
+                },
+                message: finding.message,
+                rule: Some(finding.rule.as_str().to_string()),
+            })
+            .collect();
+        PlatformError::Validation { fields }
+    }
+}
+
+/// What compose files may use
+#[derive(Debug, Clone, Default)]
+pub struct ComposePolicy {
+    /// Registries images may come from, e.g. `ghcr.io`; an entry with a path,
+    /// e.g. `ghcr.io/cortexlm`, only allows the images below it
+    pub allowed_registries: Vec<String>,
+    /// Host paths, and the paths below them, that may be bind mounted. Sources
+    /// are compared after normalization, and ones with `..` are never allowed
+    pub allowed_bind_mounts: Vec<String>,
+}
+
+impl ComposePolicy {
+    /// Check `compose_yaml`, whose variables are resolved from `env`
+    pub fn validate(
+        &self,
+        compose_yaml: &str,
+        env: &BTreeMap<String, String>,
+    ) -> Result<(), InvalidCompose> {
+        let findings = self.findings(compose_yaml, env);
+        if findings.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidCompose { findings })
+        }
+    }
+
+    fn findings(&self, compose_yaml: &str, env: &BTreeMap<String, String>) -> Vec<ComposeFinding> {
+        let mut findings = Vec::new();
+        let mut add = |rule, service: Option<&str>, message: String| {
+            findings.push(ComposeFinding {
+                rule,
+                service: service.map(str::to_string),
+                message,
+            })
+        };
+
+        let compose: Value = match serde_yaml::from_str(compose_yaml) {
+            Ok(Value::Null) => return vec![],
+            Ok(compose @ Value::Mapping(_)) => compose,
+            Ok(_) => {
+                add(
+                    ComposeRule::Syntax,
+                    None,
+                    "the file must be a mapping".to_string(),
+                );
+                return findings;
+            }
+            Err(e) => {
+                add(ComposeRule::Syntax, None, format!("invalid YAML: {}", e));
+                return findings;
+            }
+        };
+
+        match compose.get("version").and_then(scalar) {
+            Some(version) if is_supported_version(&version) => {}
+            Some(version) => add(
+                ComposeRule::Version,
+                None,
+                format!("version {} is not supported, use 3.x", version),
+            ),
+            None => add(
+                ComposeRule::Version,
+                None,
+                "the file must declare a version".to_string(),
+            ),
+        }
+
+        let services = match compose.get("services").and_then(Value::as_mapping) {
+            Some(services) if !services.is_empty() => services,
+            _ => {
+                add(
+                    ComposeRule::Syntax,
+                    None,
+                    "the file must declare services".to_string(),
+                );
+                return findings;
+            }
+        };
+
+        for (name, service) in services {
+            let name = scalar(name).unwrap_or_default();
+            let service = service.as_mapping();
+            let field = |key: &str| service.and_then(|service| service.get(key));
+
+            match field("image").and_then(Value::as_str) {
+                Some(image) if self.registry_allowed(image) => {}
+                Some(image) => add(
+                    ComposeRule::Image,
+                    Some(&name),
+                    format!("image {} is not from an allowed registry", image),
+                ),
+                None => add(
+                    ComposeRule::Image,
+                    Some(&name),
+                    "the service must reference an image".to_string(),
+                ),
+            }
+
+            if field("privileged").and_then(Value::as_bool) == Some(true) {
+                add(
+                    ComposeRule::Privileged,
+                    Some(&name),
+                    "privileged containers are not allowed".to_string(),
+                );
+            }
+            if field("network_mode").and_then(Value::as_str) == Some("host") {
+                add(
+                    ComposeRule::HostNetwork,
+                    Some(&name),
+                    "host network mode is not allowed".to_string(),
+                );
+            }
+
+            let volumes = field("volumes").and_then(Value::as_sequence);
+            for source in volumes.into_iter().flatten().filter_map(bind_source) {
+                if !self.bind_mount_allowed(&source) {
+                    add(
+                        ComposeRule::BindMount,
+                        Some(&name),
+                        format!("bind mount of {} is not allowed", source),
+                    );
+                }
+            }
+
+            for key in passed_through(field("environment")) {
+                if !env.contains_key(&key) {
+                    add(
+                        ComposeRule::MissingEnv,
+                        Some(&name),
+                        format!("{} is passed through but not declared", key),
+                    );
+                }
+            }
+        }
+
+        let mut referenced = Vec::new();
+        variables(&compose, &mut referenced);
+        referenced.sort();
+        referenced.dedup();
+        for key in referenced {
+            if !env.contains_key(&key) {
+                add(
+                    ComposeRule::MissingEnv,
+                    None,
+                    format!("${{{}}} is referenced but not declared", key),
+                );
+            }
+        }
+
+        findings
+    }
+
+    fn registry_allowed(&self, image: &str) -> bool {
+        let image = qualified_image(image);
+        self.allowed_registries.iter().any(|allowed| {
+            let allowed = allowed.trim_end_matches('/').to_lowercase();
+            image == allowed || image.starts_with(&format!("{}/", allowed))
+        })
+    }
+
+    fn bind_mount_allowed(&self, source: &str) -> bool {
+        let Some(source) = normalized_path(source) else {
+            return false;
+        };
+        self.allowed_bind_mounts.iter().any(|allowed| {
+            normalized_path(allowed).is_some_and(|allowed| {
+                source == allowed || source.starts_with(&format!("{}/", allowed))
+            })
+        })
+    }
+}
+
+/// Absolute `path` without `.` components, repeated or trailing slashes, e.g.
+/// `/var/run` for `/var//./run/`, or `None` for relative paths and paths with
+/// `..` components, which could leave the directory they appear to be in
+fn normalized_path(path: &str) -> Option<String> {
+    if !path.starts_with('/') {
+        return None;
+    }
+    let mut normalized = String::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => return None,
+            component => {
+                normalized.push('/');
+                normalized.push_str(component);
+            }
+        }
+    }
+    Some(normalized)
+}
+
+/// String form of a scalar, e.g. of `version: 3.8`
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn is_supported_version(version: &str) -> bool {
+    let mut parts = version.split('.');
+    parts.next() == Some("3")
+        && parts.all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// `image` with its registry, lowercased, e.g. `docker.io/postgres:16` for `postgres:16`
+fn qualified_image(image: &str) -> String {
+    let image = image.to_lowercase();
+    match image.split_once('/') {
+        Some((host, _)) if host.contains('.') || host.contains(':') || host == "localhost" => image,
+        _ => format!("{}/{}", DEFAULT_REGISTRY, image),
+    }
+}
+
+/// Host path of a bind mount, in either the short or the long volume syntax
+fn bind_source(volume: &Value) -> Option<String> {
+    match volume {
+        Value::String(volume) => {
+            let (source, _) = volume.split_once(':')?;
+            source
+                .starts_with(['/', '.', '~'])
+                .then(|| source.to_string())
+        }
+        Value::Mapping(volume) => {
+            if volume.get("type").and_then(Value::as_str) != Some("bind") {
+                return None;
+            }
+            Some(
+                volume
+                    .get("source")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Variables a service takes from the environment it is started in, i.e.
+/// listed without a value
+fn passed_through(environment: Option<&Value>) -> Vec<String> {
+    match environment {
+        Some(Value::Sequence(entries)) => entries
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|entry| !entry.contains('='))
+            .map(str::to_string)
+            .collect(),
+        Some(Value::Mapping(entries)) => entries
+            .iter()
+            .filter(|(_, value)| value.is_null())
+            .filter_map(|(key, _)| scalar(key))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Collect the variables referenced without a default in the strings of `value`
+fn variables(value: &Value, referenced: &mut Vec<String>) {
+    match value {
+        Value::String(s) => referenced_variables(s, referenced),
+        Value::Sequence(items) => items.iter().for_each(|item| variables(item, referenced)),
+        Value::Mapping(entries) => entries
+            .iter()
+            .for_each(|(_, item)| variables(item, referenced)),
+        Value::Tagged(tagged) => variables(&tagged.value, referenced),
+        _ => {}
+    }
+}
+
+/// Variables of `s` referenced as `$NAME`, `${NAME}` or `${NAME?error}`;
+/// `${NAME-default}`, `${NAME:-default}` and escaped `$$` are skipped
+fn referenced_variables(s: &str, referenced: &mut Vec<String>) {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut rest = s;
+    while let Some(start) = rest.find('$') {
+        rest = &rest[start + 1..];
+        if let Some(escaped) = rest.strip_prefix('$') {
+            rest = escaped;
+            continue;
+        }
+        let (name, has_default) = match rest.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced.find(|c: char| !is_name(c)).unwrap_or(braced.len());
+                let has_default = braced[end..].starts_with('-') || braced[end..].starts_with(":-");
+                (&braced[..end], has_default)
+            }
+            None => {
+                let end = rest.find(|c: char| !is_name(c)).unwrap_or(rest.len());
+                (&rest[..end], false)
+            }
+        };
+        if !name.is_empty() && !has_default {
+            referenced.push(name.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ComposePolicy {
+        ComposePolicy {
+            allowed_registries: vec![
+                "registry.platform.network".to_string(),
+                "ghcr.io/cortexlm".to_string(),
+            ],
+            allowed_bind_mounts: vec!["/var/run/tappd.sock".to_string()],
+        }
+    }
+
+    fn env() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("API_KEY".to_string(), "secret".to_string()),
+            ("DB_PASSWORD".to_string(), "secret".to_string()),
+        ])
+    }
+
+    fn findings(compose_yaml: &str) -> Vec<(ComposeRule, Option<String>)> {
+        match policy().validate(compose_yaml, &env()) {
+            Ok(()) => vec![],
+            Err(e) => e
+                .findings
+                .into_iter()
+                .map(|finding| (finding.rule, finding.service))
+                .collect(),
+        }
+    }
+
+    fn service(name: &str) -> Option<String> {
+        Some(name.to_string())
+    }
+
+    #[test]
+    fn test_valid_compose() {
+        assert_eq!(
+            findings(include_str!("../fixtures/compose/valid.yml")),
+            vec![]
+        );
+        // Placeholders of challenges without a compose file yet
+        assert_eq!(findings("# Challenge: example\n"), vec![]);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        assert_eq!(
+            findings(include_str!("../fixtures/compose/unsupported_version.yml")),
+            vec![(ComposeRule::Version, None)]
+        );
+        assert_eq!(
+            findings("services:\n  app:\n    image: registry.platform.network/app\n"),
+            vec![(ComposeRule::Version, None)]
+        );
+        assert_eq!(
+            findings("version: '3.8'\nservices: [\n"),
+            vec![(ComposeRule::Syntax, None)]
+        );
+    }
+
+    #[test]
+    fn test_disallowed_registries() {
+        assert_eq!(
+            findings(include_str!("../fixtures/compose/disallowed_registry.yml")),
+            vec![
+                (ComposeRule::Image, service("implicit")),
+                (ComposeRule::Image, service("other-org")),
+                (ComposeRule::Image, service("lookalike")),
+                (ComposeRule::Image, service("build-only")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_privileged_and_host_network() {
+        assert_eq!(
+            findings(include_str!("../fixtures/compose/privileged.yml")),
+            vec![
+                (ComposeRule::Privileged, service("app")),
+                (ComposeRule::HostNetwork, service("app")),
+                (ComposeRule::HostNetwork, service("sidecar")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bind_mounts_outside_allowlist() {
+        assert_eq!(
+            findings(include_str!("../fixtures/compose/bind_mounts.yml")),
+            vec![
+                (ComposeRule::BindMount, service("app")),
+                (ComposeRule::BindMount, service("app")),
+                (ComposeRule::BindMount, service("app")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bind_mount_traversal() {
+        let compose = |source: &str| {
+            format!(
+                "version: '3.8'\nservices:\n  app:\n    image: registry.platform.network/app\n    volumes:\n      - {}:/mnt\n",
+                source
+            )
+        };
+        for source in [
+            "/var/run/tappd.sock",
+            "/var//run/./tappd.sock",
+            "/var/run/tappd.sock/",
+        ] {
+            assert_eq!(findings(&compose(source)), vec![], "{}", source);
+        }
+        for source in [
+            "/var/run/tappd.sock/../docker.sock",
+            "/var/run/tappd.sock/../../../etc",
+            "/var/run/tappd.sock/..",
+            "./var/run/tappd.sock",
+        ] {
+            assert_eq!(
+                findings(&compose(source)),
+                vec![(ComposeRule::BindMount, service("app"))],
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_undeclared_environment() {
+        let err = policy()
+            .validate(include_str!("../fixtures/compose/missing_env.yml"), &env())
+            .unwrap_err();
+        let messages: Vec<&str> = err
+            .findings
+            .iter()
+            .map(|finding| finding.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "WORKER_TOKEN is passed through but not declared",
+                "SENTRY_DSN is passed through but not declared",
+                "${ADMIN_PASSWORD} is referenced but not declared",
+                "${REDIS_URL} is referenced but not declared",
+            ]
+        );
+        assert!(err
+            .findings
+            .iter()
+            .all(|finding| finding.rule == ComposeRule::MissingEnv));
+    }
+}
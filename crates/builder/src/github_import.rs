@@ -0,0 +1,842 @@
+//! Import of challenges defined in GitHub repositories
+//!
+//! A repository defines a challenge with a `challenge.toml` or
+//! `challenge.yaml` manifest, see [`ChallengeManifest`], which names the
+//! challenge's compose file and, optionally, the JSON Schema of its job
+//! payloads, both relative to the manifest. All files are read through the
+//! GitHub API at the single commit the requested ref points to, authenticated
+//! with `BuilderConfig::github_token` so that private repositories can be
+//! imported. Requests GitHub refuses, rate limits included, fail with
+//! [`ChallengeError::Github`] carrying GitHub's detail.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use platform_api_models::{
+    ChallengeProvenance, ChallengeVisibility, CreateChallengeRequest, HarnessConfig,
+    ImportChallengeRequest, ImportChallengeResponse, JobDefaults, JobPriority, PlatformResult,
+    ResourceRequirements, ScoreBounds, ScorePolicy, UpdateChallengeRequest,
+};
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::image_build::{is_git_ref, is_github_repo};
+use crate::{BuildLog, BuilderService, ChallengeError};
+
+/// GitHub API used unless `BuilderConfig::github_api_url` says otherwise
+pub const DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Manifest file names looked up in the manifest directory, in order
+pub const MANIFEST_FILES: [&str; 3] = ["challenge.toml", "challenge.yaml", "challenge.yml"];
+
+/// Challenge definition of a repository
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChallengeManifest {
+    /// Existing challenge the manifest updates; a challenge is created when unset
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub description: String,
+    /// Compose file, relative to the manifest
+    #[serde(default = "default_compose_file")]
+    pub compose: String,
+    /// JSON Schema of job payloads, a JSON file relative to the manifest
+    #[serde(default)]
+    pub job_payload_schema: Option<String>,
+    #[serde(default)]
+    pub visibility: ChallengeVisibility,
+    #[serde(default)]
+    pub harness_config: HarnessConfig,
+    #[serde(default)]
+    pub dataset_urls: Vec<String>,
+    #[serde(default)]
+    pub resources: Option<ResourceRequirements>,
+    #[serde(default)]
+    pub default_job_priority: Option<JobPriority>,
+    #[serde(default)]
+    pub job_defaults: Option<JobDefaults>,
+    #[serde(default)]
+    pub score_bounds: Option<ScoreBounds>,
+    #[serde(default)]
+    pub score_policy: Option<ScorePolicy>,
+    #[serde(default)]
+    pub healthcheck_url: Option<String>,
+    #[serde(default)]
+    pub healthcheck_timeout_secs: Option<u32>,
+}
+
+fn default_compose_file() -> String {
+    "docker-compose.yml".to_string()
+}
+
+impl ChallengeManifest {
+    /// Parse the manifest file `file_name`, as TOML or YAML by its extension
+    pub fn parse(file_name: &str, content: &str) -> Result<Self, ChallengeError> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            ChallengeError::InvalidManifest(format!("{}: {}", file_name, e))
+        };
+        let manifest: Self = if file_name.ends_with(".toml") {
+            toml::from_str(content).map_err(|e| invalid(&e))?
+        } else {
+            serde_yaml::from_str(content).map_err(|e| invalid(&e))?
+        };
+
+        if manifest.name.trim().is_empty() {
+            return Err(invalid(&"name cannot be empty"));
+        }
+        if manifest.description.trim().is_empty() {
+            return Err(invalid(&"description cannot be empty"));
+        }
+        Ok(manifest)
+    }
+
+    /// Creation request of the challenge the manifest defines
+    pub fn create_request(&self, github_repo: &str) -> CreateChallengeRequest {
+        CreateChallengeRequest {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            visibility: self.visibility.clone(),
+            github_repo: Some(github_repo.to_string()),
+            harness_config: self.harness_config.clone(),
+            dataset_urls: self.dataset_urls.clone(),
+            resources: self.resources.clone(),
+            healthcheck_url: self.healthcheck_url.clone(),
+            healthcheck_timeout_secs: self.healthcheck_timeout_secs,
+        }
+    }
+}
+
+/// Challenge definition read from a repository at a single commit
+#[derive(Debug, Clone)]
+pub struct ChallengeSource {
+    pub manifest: ChallengeManifest,
+    pub compose_yaml: String,
+    pub job_payload_schema: Option<serde_json::Value>,
+    pub provenance: ChallengeProvenance,
+}
+
+impl ChallengeSource {
+    /// Challenge the source updates, or else the one it creates
+    pub fn challenge_id(&self) -> Uuid {
+        self.manifest.id.unwrap_or_else(|| {
+            BuilderService::challenge_id(&self.manifest.create_request(&self.provenance.repo_url))
+        })
+    }
+}
+
+/// Reads repository files through the GitHub REST API
+pub struct GithubClient {
+    http: reqwest::Client,
+    api_url: String,
+    token: Option<String>,
+}
+
+impl GithubClient {
+    pub fn new(api_url: &str, token: Option<String>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .user_agent("platform-api")
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Failed to create GitHub client")?;
+        Ok(Self {
+            http,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token,
+        })
+    }
+
+    /// Commit `git_ref` of `repo` points to, by default the head of its default branch
+    pub async fn resolve_commit(
+        &self,
+        repo: &str,
+        git_ref: Option<&str>,
+    ) -> Result<String, ChallengeError> {
+        let git_ref = git_ref.unwrap_or("HEAD");
+        let url = format!("{}/repos/{}/commits/{}", self.api_url, repo, git_ref);
+        let response = self.get(&url, "application/vnd.github.sha").await?;
+        let response =
+            check_response(response, &format!("resolve {} of {}", git_ref, repo)).await?;
+
+        let commit = response
+            .text()
+            .await
+            .map_err(|e| ChallengeError::Github(format!("failed to read commit: {}", e)))?;
+        let commit = commit.trim().to_lowercase();
+        if commit.len() != 40 || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ChallengeError::Github(format!(
+                "unexpected commit for {} of {}",
+                git_ref, repo
+            )));
+        }
+        Ok(commit)
+    }
+
+    /// Content of the file `path` of `repo` at `commit`; `None` if there is none
+    pub async fn fetch_file(
+        &self,
+        repo: &str,
+        path: &str,
+        commit: &str,
+    ) -> Result<Option<String>, ChallengeError> {
+        let url = format!(
+            "{}/repos/{}/contents/{}?ref={}",
+            self.api_url, repo, path, commit
+        );
+        let response = self.get(&url, "application/vnd.github.raw").await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check_response(response, &format!("fetch {} of {}", path, repo)).await?;
+
+        response
+            .text()
+            .await
+            .map(Some)
+            .map_err(|e| ChallengeError::Github(format!("failed to read {}: {}", path, e)))
+    }
+
+    async fn get(&self, url: &str, accept: &str) -> Result<reqwest::Response, ChallengeError> {
+        let mut request = self
+            .http
+            .get(url)
+            .header(header::ACCEPT, accept)
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        request
+            .send()
+            .await
+            .map_err(|e| ChallengeError::Github(format!("request failed: {}", e)))
+    }
+}
+
+/// Fail with GitHub's detail if `response` is not successful
+async fn check_response(
+    response: reqwest::Response,
+    action: &str,
+) -> Result<reqwest::Response, ChallengeError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+    };
+    let rate_limited = matches!(
+        status,
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+    ) && header("x-ratelimit-remaining").as_deref() == Some("0");
+    let reset = header("x-ratelimit-reset")
+        .and_then(|reset| reset.parse().ok())
+        .and_then(|reset| chrono::DateTime::from_timestamp(reset, 0));
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|body| body["message"].as_str().map(String::from))
+        .unwrap_or(body);
+
+    Err(ChallengeError::Github(match (rate_limited, reset) {
+        (true, Some(reset)) => format!(
+            "rate limit exceeded trying to {}, resets at {}: {}",
+            action,
+            reset.to_rfc3339(),
+            message
+        ),
+        (true, None) => format!("rate limit exceeded trying to {}: {}", action, message),
+        (false, _) => format!("failed to {} ({}): {}", action, status, message),
+    }))
+}
+
+/// Path in the repository of `file`, relative to the directory `dir`
+///
+/// Paths leaving the repository or with characters GitHub paths are not
+/// expected to have are refused.
+fn repo_path(dir: &str, file: &str) -> Result<String, ChallengeError> {
+    let mut segments = vec![];
+    for segment in dir.split('/').chain(file.split('/')) {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                return Err(ChallengeError::InvalidManifest(format!(
+                    "'{}' is outside the repository",
+                    file
+                )))
+            }
+            segment
+                if segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c)) =>
+            {
+                segments.push(segment)
+            }
+            _ => {
+                return Err(ChallengeError::InvalidManifest(format!(
+                    "'{}' is not a supported path",
+                    file
+                )))
+            }
+        }
+    }
+    Ok(segments.join("/"))
+}
+
+impl BuilderService {
+    fn github(&self) -> Result<GithubClient> {
+        GithubClient::new(
+            &self.config.github_api_url,
+            self.config.github_token.clone(),
+        )
+    }
+
+    /// Read the challenge defined in the repository of `request`
+    ///
+    /// The manifest is looked up in the requested directory under the names
+    /// of [`MANIFEST_FILES`]. Fails with [`ChallengeError::InvalidManifest`] if
+    /// the request or the manifest is invalid or the manifest or a file it
+    /// names is missing, and with [`ChallengeError::Github`] if GitHub refuses
+    /// a request.
+    pub async fn fetch_challenge_source(
+        &self,
+        request: &ImportChallengeRequest,
+    ) -> PlatformResult<ChallengeSource> {
+        if !is_github_repo(&request.repo) {
+            return Err(ChallengeError::InvalidManifest(format!(
+                "'{}' is not a GitHub repository of the form owner/name",
+                request.repo
+            ))
+            .into());
+        }
+        if let Some(git_ref) = request.git_ref.as_deref().filter(|r| !is_git_ref(r)) {
+            return Err(
+                ChallengeError::InvalidManifest(format!("'{}' is not a git ref", git_ref)).into(),
+            );
+        }
+        let dir = repo_path(request.path.as_deref().unwrap_or_default(), "")?;
+
+        let github = self.github()?;
+        let repo = &request.repo;
+        let commit = github
+            .resolve_commit(repo, request.git_ref.as_deref())
+            .await?;
+
+        let mut manifest = None;
+        for file in MANIFEST_FILES {
+            let path = repo_path(&dir, file)?;
+            if let Some(content) = github.fetch_file(repo, &path, &commit).await? {
+                manifest = Some((path, content));
+                break;
+            }
+        }
+        let (manifest_path, content) = manifest.ok_or_else(|| {
+            ChallengeError::InvalidManifest(format!(
+                "no challenge.toml or challenge.yaml in '/{}' at {}",
+                dir, commit
+            ))
+        })?;
+        let manifest = ChallengeManifest::parse(&manifest_path, &content)?;
+
+        let fetch_named = |file: String| {
+            let github = &github;
+            let commit = &commit;
+            let dir = &dir;
+            async move {
+                let path = repo_path(dir, &file)?;
+                github
+                    .fetch_file(repo, &path, commit)
+                    .await?
+                    .ok_or_else(|| {
+                        ChallengeError::InvalidManifest(format!(
+                            "'{}' named by the manifest does not exist",
+                            path
+                        ))
+                    })
+            }
+        };
+        let compose_yaml = fetch_named(manifest.compose.clone()).await?;
+        let job_payload_schema = match &manifest.job_payload_schema {
+            Some(file) => {
+                let schema = fetch_named(file.clone()).await?;
+                Some(
+                    serde_json::from_str(&schema)
+                        .map_err(|e| ChallengeError::InvalidManifest(format!("{}: {}", file, e)))?,
+                )
+            }
+            None => None,
+        };
+
+        info!(repo = %repo, commit = %commit, "Fetched challenge manifest {}", manifest_path);
+        Ok(ChallengeSource {
+            manifest,
+            compose_yaml,
+            job_payload_schema,
+            provenance: ChallengeProvenance {
+                repo_url: format!("https://github.com/{}", repo),
+                commit_sha: commit,
+                manifest_path,
+                imported_at: Utc::now(),
+            },
+        })
+    }
+
+    /// Create the challenge `source` defines, owned by `owner`, or update the
+    /// existing challenge its manifest names, and store its provenance
+    ///
+    /// The compose file goes through the validation of challenges created
+    /// through the API, see [`crate::compose_validation`], and the resource
+    /// requirements are checked against `BuilderConfig::max_resources`. An
+    /// update replaces the compose file and everything else the manifest
+    /// defines; whether `owner` may update the challenge is for the caller to
+    /// check.
+    pub async fn import_challenge(
+        &self,
+        source: ChallengeSource,
+        owner: &str,
+    ) -> PlatformResult<ImportChallengeResponse> {
+        let pool = self
+            .database_pool
+            .clone()
+            .context("Importing a challenge requires a database")?;
+        let ChallengeSource {
+            manifest,
+            compose_yaml,
+            job_payload_schema,
+            provenance,
+        } = source;
+        self.compose_policy()
+            .validate(&compose_yaml, &manifest.harness_config.environment)?;
+        if let Some(resources) = &manifest.resources {
+            self.check_resources(resources)?;
+        }
+
+        let (id, created) = match manifest.id {
+            Some(id) => {
+                let exists: bool =
+                    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM challenges WHERE id = $1)")
+                        .bind(id)
+                        .fetch_one(pool.as_ref())
+                        .await
+                        .context("Failed to look up challenge")?;
+                if !exists {
+                    return Err(ChallengeError::NotFound(id).into());
+                }
+                self.ensure_name_available(&manifest.name, id).await?;
+                sqlx::query(
+                    "UPDATE challenges SET compose_yaml = $2, github_repo = $3 WHERE id = $1",
+                )
+                .bind(id)
+                .bind(&compose_yaml)
+                .bind(&provenance.repo_url)
+                .execute(pool.as_ref())
+                .await
+                .context("Failed to update challenge compose file")?;
+                (id, false)
+            }
+            None => {
+                let request = manifest.create_request(&provenance.repo_url);
+                let challenge = self
+                    .build_challenge(request, Some(compose_yaml), owner, &BuildLog::disabled())
+                    .await?;
+                (challenge.id, true)
+            }
+        };
+
+        // Also recomputes the compose hash, as the name and harness config are set
+        let changes = UpdateChallengeRequest {
+            name: Some(manifest.name),
+            description: Some(manifest.description),
+            status: None,
+            harness_config: Some(manifest.harness_config),
+            default_job_priority: Some(manifest.default_job_priority.unwrap_or_default()),
+            job_payload_schema: job_payload_schema.clone(),
+            job_defaults: Some(manifest.job_defaults.unwrap_or_default()),
+            resources: Some(manifest.resources.unwrap_or_default()),
+            score_bounds: Some(manifest.score_bounds.unwrap_or_default()),
+            score_policy: Some(manifest.score_policy.unwrap_or_default()),
+            healthcheck_url: manifest.healthcheck_url,
+            healthcheck_timeout_secs: manifest.healthcheck_timeout_secs,
+        };
+        let mut challenge = self.update_challenge(id, changes).await?;
+
+        // A manifest without a schema removes the challenge's
+        sqlx::query("UPDATE challenges SET provenance = $2, job_payload_schema = $3 WHERE id = $1")
+            .bind(id)
+            .bind(serde_json::to_value(&provenance)?)
+            .bind(&job_payload_schema)
+            .execute(pool.as_ref())
+            .await
+            .context("Failed to store challenge provenance")?;
+        challenge.job_payload_schema = job_payload_schema;
+
+        info!(
+            challenge_id = %id,
+            repo_url = %provenance.repo_url,
+            commit_sha = %provenance.commit_sha,
+            created,
+            "Imported challenge"
+        );
+        Ok(ImportChallengeResponse {
+            challenge,
+            provenance,
+            created,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BuilderConfig;
+    use axum::http::{header::AUTHORIZATION, HeaderMap, StatusCode, Uri};
+    use axum::response::{IntoResponse, Response};
+    use axum::Router;
+    use platform_api_models::PlatformError;
+    use sqlx::PgPool;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    const REPO: &str = "cortex/challenge";
+    const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+    const TOKEN: &str = "ghp_secret";
+
+    /// Mock GitHub API serving `files` of the private repository [`REPO`] at
+    /// [`COMMIT`], to requests authenticated with [`TOKEN`] only
+    async fn mock_github(files: &[(&str, &'static str)]) -> String {
+        let files: Arc<HashMap<String, &'static str>> = Arc::new(
+            files
+                .iter()
+                .map(|(path, content)| (path.to_string(), *content))
+                .collect(),
+        );
+        let app = Router::new().fallback(move |uri: Uri, headers: HeaderMap| {
+            let files = files.clone();
+            async move {
+                let not_found =
+                    (StatusCode::NOT_FOUND, r#"{"message": "Not Found"}"#).into_response();
+                let authorized = headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok())
+                    == Some(&format!("Bearer {}", TOKEN));
+                let Some(path) = uri.path().strip_prefix(&format!("/repos/{}/", REPO)) else {
+                    return not_found;
+                };
+                if !authorized {
+                    return not_found;
+                }
+                if path.starts_with("commits/") {
+                    return COMMIT.into_response();
+                }
+                match path.strip_prefix("contents/") {
+                    Some(file) if uri.query() == Some(&format!("ref={}", COMMIT)) => files
+                        .get(file)
+                        .map_or(not_found, |content| content.into_response()),
+                    _ => not_found,
+                }
+            }
+        });
+        serve(app).await
+    }
+
+    async fn serve(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    fn service(github_api_url: &str, github_token: Option<&str>) -> BuilderService {
+        let config = BuilderConfig {
+            github_api_url: github_api_url.to_string(),
+            github_token: github_token.map(str::to_string),
+            ..BuilderConfig::default()
+        };
+        BuilderService::new(&config, None).unwrap()
+    }
+
+    fn request(path: Option<&str>) -> ImportChallengeRequest {
+        ImportChallengeRequest {
+            repo: REPO.to_string(),
+            git_ref: Some("main".to_string()),
+            path: path.map(str::to_string),
+        }
+    }
+
+    fn error_of(result: PlatformResult<impl std::fmt::Debug>) -> PlatformError {
+        result.unwrap_err()
+    }
+
+    fn valid_files() -> Vec<(&'static str, &'static str)> {
+        vec![
+            (
+                "challenges/imported/challenge.toml",
+                include_str!("../fixtures/import/challenge.toml"),
+            ),
+            (
+                "challenges/imported/compose/docker-compose.yml",
+                include_str!("../fixtures/import/docker-compose.yml"),
+            ),
+            (
+                "challenges/imported/schemas/payload.json",
+                include_str!("../fixtures/import/payload.json"),
+            ),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_fetch_challenge_source_from_private_repo() {
+        let url = mock_github(&valid_files()).await;
+
+        let source = service(&url, Some(TOKEN))
+            .fetch_challenge_source(&request(Some("challenges/imported/")))
+            .await
+            .unwrap();
+        assert_eq!(source.manifest.name, "imported-challenge");
+        assert_eq!(source.manifest.id, None);
+        assert_eq!(
+            source.manifest.default_job_priority,
+            Some(JobPriority::High)
+        );
+        assert_eq!(source.manifest.resources.as_ref().unwrap().vcpu, Some(4));
+        assert_eq!(
+            source.manifest.job_defaults.as_ref().unwrap().timeout,
+            Some(600)
+        );
+        assert_eq!(
+            source.compose_yaml,
+            include_str!("../fixtures/import/docker-compose.yml")
+        );
+        assert_eq!(
+            source.job_payload_schema.unwrap()["required"],
+            serde_json::json!(["task_id"])
+        );
+        assert_eq!(
+            source.provenance.repo_url,
+            "https://github.com/cortex/challenge"
+        );
+        assert_eq!(source.provenance.commit_sha, COMMIT);
+        assert_eq!(
+            source.provenance.manifest_path,
+            "challenges/imported/challenge.toml"
+        );
+
+        // The repository is private: without the token GitHub denies it exists
+        let err = error_of(
+            service(&url, None)
+                .fetch_challenge_source(&request(Some("challenges/imported")))
+                .await,
+        );
+        assert!(
+            matches!(err, PlatformError::Upstream { reason, .. } if reason.contains("Not Found"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_yaml_manifest_at_repo_root() {
+        let url = mock_github(&[
+            (
+                "challenge.yaml",
+                include_str!("../fixtures/import/challenge.yaml"),
+            ),
+            (
+                "docker-compose.yml",
+                include_str!("../fixtures/import/docker-compose.yml"),
+            ),
+        ])
+        .await;
+
+        let source = service(&url, Some(TOKEN))
+            .fetch_challenge_source(&request(None))
+            .await
+            .unwrap();
+        assert_eq!(source.manifest.name, "imported-yaml-challenge");
+        assert_eq!(source.manifest.visibility, ChallengeVisibility::Private);
+        assert_eq!(source.provenance.manifest_path, "challenge.yaml");
+        assert!(source.job_payload_schema.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_manifests_refused() {
+        let url = mock_github(&[
+            (
+                "malformed/challenge.toml",
+                include_str!("../fixtures/import/malformed.toml"),
+            ),
+            (
+                "no-compose/challenge.toml",
+                include_str!("../fixtures/import/challenge.toml"),
+            ),
+        ])
+        .await;
+        let service = service(&url, Some(TOKEN));
+
+        for (path, expected) in [
+            ("malformed", "compose_file"),
+            ("no-compose", "no-compose/compose/docker-compose.yml"),
+            ("empty", "no challenge.toml or challenge.yaml"),
+            ("../other", "outside the repository"),
+        ] {
+            let err = error_of(service.fetch_challenge_source(&request(Some(path))).await);
+            assert!(
+                matches!(
+                    &err,
+                    PlatformError::Validation { fields }
+                        if fields[0].field == "manifest" && fields[0].message.contains(expected)
+                ),
+                "{}: {:?}",
+                path,
+                err
+            );
+        }
+
+        let mut invalid_repo = request(None);
+        invalid_repo.repo = "cortex/challenge/extra".to_string();
+        assert!(matches!(
+            error_of(service.fetch_challenge_source(&invalid_repo).await),
+            PlatformError::Validation { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_reported() {
+        let app = Router::new().fallback(|| async {
+            (
+                StatusCode::FORBIDDEN,
+                [
+                    ("x-ratelimit-remaining", "0"),
+                    ("x-ratelimit-reset", "1700000000"),
+                ],
+                r#"{"message": "API rate limit exceeded for 203.0.113.7."}"#,
+            )
+                .into_response()
+        });
+        let url = serve(app).await;
+
+        let err = error_of(
+            service(&url, Some(TOKEN))
+                .fetch_challenge_source(&request(None))
+                .await,
+        );
+        let PlatformError::Upstream { reason: detail, .. } = err else {
+            panic!("not a GitHub error: {:?}", err);
+        };
+        assert!(detail.contains("rate limit exceeded"), "{}", detail);
+        assert!(detail.contains("2023-11-14T22:13:20+00:00"), "{}", detail);
+        assert!(detail.contains("API rate limit exceeded for 203.0.113.7."));
+
+        // Other refusals are reported with their status
+        let app = Router::new().fallback(|| async {
+            let response: Response = (
+                StatusCode::FORBIDDEN,
+                r#"{"message": "Resource not accessible"}"#,
+            )
+                .into_response();
+            response
+        });
+        let url = serve(app).await;
+        let err = error_of(
+            service(&url, Some(TOKEN))
+                .fetch_challenge_source(&request(None))
+                .await,
+        );
+        assert!(
+            matches!(&err, PlatformError::Upstream { reason, .. } if reason.contains("403 Forbidden") && reason.contains("Resource not accessible"))
+        );
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_import_creates_then_updates_challenge() {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = Arc::new(PgPool::connect(&database_url).await.unwrap());
+        let service = BuilderService::new(&BuilderConfig::default(), Some(pool.clone())).unwrap();
+        let owner = "import-test-owner";
+
+        let mut manifest = ChallengeManifest::parse(
+            "challenge.toml",
+            include_str!("../fixtures/import/challenge.toml"),
+        )
+        .unwrap();
+        manifest.name = format!("import-test-{}", Uuid::new_v4());
+        let mut source = ChallengeSource {
+            manifest,
+            compose_yaml: include_str!("../fixtures/import/docker-compose.yml").to_string(),
+            job_payload_schema: Some(
+                serde_json::from_str(include_str!("../fixtures/import/payload.json")).unwrap(),
+            ),
+            provenance: ChallengeProvenance {
+                repo_url: "https://github.com/cortex/challenge".to_string(),
+                commit_sha: COMMIT.to_string(),
+                manifest_path: "challenge.toml".to_string(),
+                imported_at: Utc::now(),
+            },
+        };
+
+        let imported = service
+            .import_challenge(source.clone(), owner)
+            .await
+            .unwrap();
+        assert!(imported.created);
+        let id = imported.challenge.id;
+        assert_eq!(id, source.challenge_id());
+        assert_eq!(imported.challenge.default_job_priority, JobPriority::High);
+        assert!(imported.challenge.job_payload_schema.is_some());
+        let stored: (String, String, serde_json::Value, String) = sqlx::query_as(
+            "SELECT owner, compose_yaml, provenance, compose_hash FROM challenges WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_one(pool.as_ref())
+        .await
+        .unwrap();
+        assert_eq!(stored.0, owner);
+        assert_eq!(stored.1, source.compose_yaml);
+        assert_eq!(
+            serde_json::from_value::<ChallengeProvenance>(stored.2).unwrap(),
+            source.provenance
+        );
+
+        // A manifest naming the challenge updates it, compose file included
+        source.manifest.id = Some(id);
+        source.manifest.description = "Updated from its repository".to_string();
+        source.manifest.job_payload_schema = None;
+        source.job_payload_schema = None;
+        source.compose_yaml = source.compose_yaml.replace("1.0.0", "1.1.0");
+        source.provenance.commit_sha = "89abcdef0123456789abcdef0123456789abcdef".to_string();
+        let updated = service
+            .import_challenge(source.clone(), owner)
+            .await
+            .unwrap();
+        assert!(!updated.created);
+        assert_eq!(updated.challenge.id, id);
+        assert_eq!(updated.challenge.description, "Updated from its repository");
+        assert!(updated.challenge.job_payload_schema.is_none());
+        let compose_hash = stored.3;
+        let stored: (String, Option<serde_json::Value>, serde_json::Value, String) =
+            sqlx::query_as(
+                "SELECT compose_yaml, job_payload_schema, provenance, compose_hash \
+                 FROM challenges WHERE id = $1",
+            )
+            .bind(id)
+            .fetch_one(pool.as_ref())
+            .await
+            .unwrap();
+        assert!(stored.0.contains("imported:1.1.0"));
+        assert!(stored.1.is_none());
+        assert_eq!(stored.2["commit_sha"], source.provenance.commit_sha);
+        assert_ne!(stored.3, compose_hash);
+
+        // Manifests naming unknown challenges are refused
+        source.manifest.id = Some(Uuid::new_v4());
+        assert!(matches!(
+            error_of(service.import_challenge(source.clone(), owner).await),
+            PlatformError::NotFound { .. }
+        ));
+
+        service.delete_challenge(id).await.unwrap();
+    }
+}
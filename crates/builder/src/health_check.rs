@@ -0,0 +1,461 @@
+//! Health checks of challenge containers
+//!
+//! A challenge declaring a `healthcheck_url` can be checked once its
+//! container started, before validators are routed to it: the URL must
+//! answer a GET request with a 2xx status within the challenge's
+//! `healthcheck_timeout_secs`. Challenges are checked when they are created,
+//! see `BuilderService::create_challenge_with_log`, and on demand with
+//! [`BuilderService::check_challenge_health`].
+//!
+//! Health check URLs are set by challenge owners, so the platform only sends
+//! the request to public addresses: the host is resolved first, and loopback,
+//! private, link-local and unspecified addresses are refused, for the URL and
+//! each redirect it leads to. The request goes to the address checked.
+
+use anyhow::Context;
+use platform_api_models::{HealthCheckResult, PlatformResult};
+use reqwest::{header::LOCATION, redirect, Url};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{BuilderService, ChallengeError};
+
+/// Longest timeout a challenge may set for its health checks, in seconds
+pub const MAX_HEALTHCHECK_TIMEOUT_SECS: u32 = 60;
+
+/// Redirects a health check follows at most
+const MAX_HEALTHCHECK_REDIRECTS: usize = 5;
+
+impl BuilderService {
+    /// Check the health of the stored challenge `id`
+    ///
+    /// Fails with [`ChallengeError::NoHealthcheck`] for a challenge without a
+    /// health check URL. A challenge that does not answer in time, or answers
+    /// with an error status, is reported unhealthy rather than failing.
+    pub async fn check_challenge_health(&self, id: Uuid) -> PlatformResult<HealthCheckResult> {
+        let pool = self
+            .database_pool
+            .as_ref()
+            .context("Checking the health of a challenge requires a database")?;
+
+        let (url, timeout_secs): (Option<String>, i32) = sqlx::query_as(
+            "SELECT healthcheck_url, healthcheck_timeout_secs FROM challenges WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(pool.as_ref())
+        .await
+        .context("Failed to load challenge")?
+        .ok_or(ChallengeError::NotFound(id))?;
+        let url = url.ok_or(ChallengeError::NoHealthcheck(id))?;
+
+        let timeout = Duration::from_secs(timeout_secs.max(1) as u64);
+        let result = check_health(&url, timeout).await;
+        if result.healthy {
+            info!(challenge_id = %id, latency_ms = result.latency_ms, "Challenge is healthy");
+        } else {
+            warn!(
+                challenge_id = %id,
+                error = result.error.as_deref().unwrap_or_default(),
+                "Challenge is unhealthy"
+            );
+        }
+        Ok(result)
+    }
+}
+
+/// Check that a health check URL is an HTTP(S) URL and its timeout within
+/// `1..=MAX_HEALTHCHECK_TIMEOUT_SECS`
+pub fn validate_healthcheck(
+    url: Option<&str>,
+    timeout_secs: Option<u32>,
+) -> Result<(), ChallengeError> {
+    if let Some(url) = url {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| ChallengeError::InvalidHealthcheck(format!("Invalid URL: {}", e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(ChallengeError::InvalidHealthcheck(
+                "The URL must be http or https".to_string(),
+            ));
+        }
+    }
+    match timeout_secs {
+        Some(0) => Err(ChallengeError::InvalidHealthcheck(
+            "The timeout must be greater than zero".to_string(),
+        )),
+        Some(secs) if secs > MAX_HEALTHCHECK_TIMEOUT_SECS => {
+            Err(ChallengeError::InvalidHealthcheck(format!(
+                "The timeout cannot exceed {} seconds",
+                MAX_HEALTHCHECK_TIMEOUT_SECS
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Send a GET request to `url`, waiting at most `timeout` for the answer
+///
+/// Only public addresses are reached, see the [module](self) documentation.
+pub async fn check_health(url: &str, timeout: Duration) -> HealthCheckResult {
+    check_health_reaching(url, timeout, is_public_address).await
+}
+
+/// Whether health checks may reach `ip`: false for loopback, private,
+/// link-local and unspecified addresses
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast())
+        }
+        IpAddr::V6(ip) => {
+            let unique_local = ip.segments()[0] & 0xfe00 == 0xfc00;
+            let link_local = ip.segments()[0] & 0xffc0 == 0xfe80;
+            !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+        }
+    }
+}
+
+/// [`check_health`], reaching the addresses `allowed` accepts
+async fn check_health_reaching(
+    url: &str,
+    timeout: Duration,
+    allowed: fn(IpAddr) -> bool,
+) -> HealthCheckResult {
+    let started = Instant::now();
+    let response = tokio::time::timeout(timeout, get_following_redirects(url, allowed)).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match response {
+        Ok(Ok(response)) => {
+            let status = response.status();
+            HealthCheckResult {
+                healthy: status.is_success(),
+                status_code: Some(status.as_u16()),
+                latency_ms,
+                error: (!status.is_success()).then(|| format!("Answered with {}", status)),
+            }
+        }
+        Ok(Err(error)) => HealthCheckResult {
+            healthy: false,
+            status_code: None,
+            latency_ms,
+            error: Some(error),
+        },
+        Err(_) => HealthCheckResult {
+            healthy: false,
+            status_code: None,
+            latency_ms,
+            error: Some(format!("No answer within {} seconds", timeout.as_secs())),
+        },
+    }
+}
+
+/// GET `url`, following its redirects, each to an address `allowed` accepts
+async fn get_following_redirects(
+    url: &str,
+    allowed: fn(IpAddr) -> bool,
+) -> Result<reqwest::Response, String> {
+    let mut url = Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    for _ in 0..=MAX_HEALTHCHECK_REDIRECTS {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Refused to follow {}: not http or https", url));
+        }
+        let response = pinned_client(&url, allowed)
+            .await?
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok());
+        match location {
+            Some(location) if response.status().is_redirection() => {
+                url = url
+                    .join(location)
+                    .map_err(|e| format!("Invalid redirect to {}: {}", location, e))?;
+            }
+            _ => return Ok(response),
+        }
+    }
+    Err(format!(
+        "Refused to follow more than {} redirects",
+        MAX_HEALTHCHECK_REDIRECTS
+    ))
+}
+
+/// Client sending requests for `url` to the addresses its host resolves to,
+/// once checked by `allowed`, and not following redirects
+async fn pinned_client(url: &Url, allowed: fn(IpAddr) -> bool) -> Result<reqwest::Client, String> {
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| format!("No port for {}", url))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("No host in {}", url))?;
+    // IPv6 hosts are bracketed in URLs
+    let addrs: Vec<SocketAddr> = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+            .collect(),
+    };
+    if addrs.is_empty() {
+        return Err(format!("Failed to resolve {}", host));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !allowed(addr.ip())) {
+        return Err(format!(
+            "Refused to reach {}: {} is not a public address",
+            host,
+            addr.ip()
+        ));
+    }
+
+    reqwest::Client::builder()
+        .redirect(redirect::Policy::none())
+        .resolve_to_addrs(host, &addrs)
+        .build()
+        .map_err(|e| format!("Request failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::response::Redirect;
+    use axum::routing::get;
+    use axum::Router;
+
+    /// [`check_health`], also reaching the loopback test servers
+    async fn check_health_reaching_test_server(
+        url: String,
+        timeout: Duration,
+    ) -> HealthCheckResult {
+        check_health_reaching(&url, timeout, |ip| {
+            ip.is_loopback() || is_public_address(ip)
+        })
+        .await
+    }
+
+    async fn serve(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_check_health() {
+        let url = serve(
+            Router::new()
+                .route("/health", get(|| async { "ok" }))
+                .route("/broken", get(|| async { StatusCode::SERVICE_UNAVAILABLE }))
+                .route(
+                    "/slow",
+                    get(|| async {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        "ok"
+                    }),
+                ),
+        )
+        .await;
+        let timeout = Duration::from_millis(200);
+
+        let result = check_health_reaching_test_server(format!("{}/health", url), timeout).await;
+        assert!(result.healthy);
+        assert_eq!(result.status_code, Some(200));
+        assert_eq!(result.error, None);
+
+        let result = check_health_reaching_test_server(format!("{}/broken", url), timeout).await;
+        assert!(!result.healthy);
+        assert_eq!(result.status_code, Some(503));
+        assert!(result.error.unwrap().contains("503"));
+
+        let result = check_health_reaching_test_server(format!("{}/slow", url), timeout).await;
+        assert!(!result.healthy);
+        assert_eq!(result.status_code, None);
+        assert!(result.latency_ms < 5000);
+        assert!(result.error.unwrap().contains("No answer"));
+
+        // Nothing listens on port 1
+        let result =
+            check_health_reaching_test_server("http://127.0.0.1:1/health".to_string(), timeout)
+                .await;
+        assert!(!result.healthy);
+        assert!(result.error.unwrap().starts_with("Request failed"));
+    }
+
+    #[tokio::test]
+    async fn test_check_health_refuses_internal_addresses() {
+        let url = serve(Router::new().route("/health", get(|| async { "ok" }))).await;
+        let port = url.rsplit(':').next().unwrap();
+        let timeout = Duration::from_secs(2);
+
+        for url in [
+            format!("http://127.0.0.1:{}/health", port),
+            format!("http://localhost:{}/health", port),
+            format!("http://[::1]:{}/health", port),
+            "http://169.254.169.254/latest/meta-data".to_string(),
+            "http://10.0.0.1/health".to_string(),
+            "http://192.168.1.1/health".to_string(),
+            "http://0.0.0.0/health".to_string(),
+            "http://[::ffff:127.0.0.1]/health".to_string(),
+        ] {
+            let result = check_health(&url, timeout).await;
+            assert!(!result.healthy, "{} was reached", url);
+            assert_eq!(result.status_code, None);
+            assert!(
+                result
+                    .error
+                    .as_deref()
+                    .unwrap()
+                    .contains("not a public address"),
+                "{}: {:?}",
+                url,
+                result.error
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_health_checks_redirects() {
+        let url = serve(
+            Router::new()
+                .route("/health", get(|| async { "ok" }))
+                .route("/moved", get(|| async { Redirect::temporary("/health") }))
+                .route(
+                    "/metadata",
+                    get(|| async {
+                        Redirect::temporary("http://169.254.169.254/latest/meta-data")
+                    }),
+                )
+                .route("/loop", get(|| async { Redirect::temporary("/loop") })),
+        )
+        .await;
+        let timeout = Duration::from_secs(2);
+
+        let result = check_health_reaching_test_server(format!("{}/moved", url), timeout).await;
+        assert!(result.healthy);
+        assert_eq!(result.status_code, Some(200));
+
+        let result = check_health_reaching_test_server(format!("{}/metadata", url), timeout).await;
+        assert!(!result.healthy);
+        assert!(result
+            .error
+            .unwrap()
+            .contains("169.254.169.254 is not a public address"));
+
+        let result = check_health_reaching_test_server(format!("{}/loop", url), timeout).await;
+        assert!(!result.healthy);
+        assert!(result.error.unwrap().contains("redirects"));
+    }
+
+    #[test]
+    fn test_is_public_address() {
+        for ip in ["93.184.216.34", "2606:2800:220:1::1"] {
+            assert!(is_public_address(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.0.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_check_stored_challenge_health() {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = std::sync::Arc::new(sqlx::PgPool::connect(&database_url).await.unwrap());
+        let service =
+            BuilderService::new(&crate::BuilderConfig::default(), Some(pool.clone())).unwrap();
+        let url = serve(Router::new().route("/health", get(|| async { "ok" }))).await;
+
+        let request =
+            |healthcheck_url: Option<String>| platform_api_models::CreateChallengeRequest {
+                name: format!("health-test-{}", Uuid::new_v4()),
+                description: "health checks".to_string(),
+                visibility: Default::default(),
+                github_repo: None,
+                harness_config: Default::default(),
+                dataset_urls: vec![],
+                resources: None,
+                healthcheck_url,
+                healthcheck_timeout_secs: Some(2),
+            };
+        let unchecked = service.create_challenge(request(None)).await.unwrap();
+        let err = service
+            .check_challenge_health(unchecked.id)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), 409);
+
+        let checked = service
+            .create_challenge(request(Some(format!("{}/health", url))))
+            .await
+            .unwrap();
+        assert_eq!(checked.healthcheck_timeout_secs, 2);
+        // The test server listens on loopback, which challenges cannot reach
+        let result = service.check_challenge_health(checked.id).await.unwrap();
+        assert!(!result.healthy);
+        assert!(result.error.unwrap().contains("not a public address"));
+
+        // An empty URL removes the health check
+        let update = serde_json::from_value(serde_json::json!({ "healthcheck_url": "" })).unwrap();
+        let updated = service.update_challenge(checked.id, update).await.unwrap();
+        assert_eq!(updated.healthcheck_url, None);
+        let err = service
+            .check_challenge_health(checked.id)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), 409);
+
+        let err = service
+            .check_challenge_health(Uuid::new_v4())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), 404);
+
+        for id in [unchecked.id, checked.id] {
+            service.delete_challenge(id).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_validate_healthcheck() {
+        assert!(validate_healthcheck(None, None).is_ok());
+        assert!(validate_healthcheck(Some("http://challenge:8080/health"), Some(60)).is_ok());
+        assert!(validate_healthcheck(Some("https://challenge.example/health"), None).is_ok());
+
+        for (url, timeout) in [
+            (Some("challenge/health"), None),
+            (Some("ftp://challenge/health"), None),
+            (None, Some(0)),
+            (None, Some(MAX_HEALTHCHECK_TIMEOUT_SECS + 1)),
+        ] {
+            assert!(matches!(
+                validate_healthcheck(url, timeout),
+                Err(ChallengeError::InvalidHealthcheck(_))
+            ));
+        }
+    }
+}
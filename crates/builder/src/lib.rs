@@ -1,10 +1,12 @@
 use anyhow::{Context, Result};
 use chrono::Utc;
 use platform_api_models::{
-    ChallengeMetadata, ChallengePort, ChallengeResources, ChallengeStatus, ChallengeVisibility,
-    CreateChallengeRequest, UpdateChallengeRequest,
+    expected_compose_hash, BuildLogLevel, ChallengeMetadata, ChallengePort, ChallengeResources,
+    ChallengeStatus, ChallengeVisibility, ComposeHashDrift, CreateChallengeRequest, HarnessConfig,
+    HashAlgorithm, JobDefaults, JobPriority, PlatformError, PlatformResult, ResourceRequirements,
+    ScoreBounds, ScorePolicy, UpdateChallengeRequest, DEFAULT_HEALTHCHECK_TIMEOUT_SECS,
 };
-use sha2::{Digest, Sha256};
+use sha2::Digest;
 use sqlx::PgPool;
 use std::collections::BTreeMap;
 use std::fs;
@@ -13,199 +15,231 @@ use std::sync::Arc;
 use tracing::{info, warn};
 use uuid::Uuid;
 
+pub mod build_cache;
+pub mod build_log;
+pub mod build_queue;
+pub mod compose_validation;
+pub mod github_import;
+pub mod health_check;
+pub mod image_build;
+
+pub use build_cache::{BuildCache, CacheEntry};
+pub use build_log::BuildLog;
+pub use build_queue::FairQueue;
+pub use compose_validation::{ComposeFinding, ComposePolicy, ComposeRule, InvalidCompose};
+pub use github_import::{ChallengeManifest, ChallengeSource, GithubClient};
+pub use health_check::{check_health, validate_healthcheck, MAX_HEALTHCHECK_TIMEOUT_SECS};
+pub use image_build::{
+    BuildBackend, BuildJob, BuildNotQueued, BuildOutput, DockerCliBackend, ImageBuildQueue,
+    InvalidBuildSource,
+};
+
+/// Owner of challenges created by the platform itself
+pub const SYSTEM_OWNER: &str = "platform-system";
+
+/// Compose hash of the predefined term-challenge in dev environments
+const TERM_CHALLENGE_COMPOSE_HASH: &str = "term-challenge-dev-001";
+
+/// Unique index of challenge names, ignoring case
+const NAME_INDEX: &str = "challenges_name_key";
+
+/// Unique constraint of challenge compose hashes
+const COMPOSE_HASH_CONSTRAINT: &str = "challenges_compose_hash_key";
+
+/// A stored challenge is missing or would clash with another
+#[derive(Debug, thiserror::Error)]
+pub enum ChallengeError {
+    #[error("Challenge {0} not found")]
+    NotFound(Uuid),
+    #[error("A challenge named '{0}' already exists")]
+    NameTaken(String),
+    #[error("Challenge {id} of this name and description was renamed to '{name}'")]
+    Renamed { id: Uuid, name: String },
+    #[error("Another challenge has compose hash {0}")]
+    ComposeHashTaken(String),
+    #[error("Invalid resource requirements: {0}")]
+    InvalidResources(String),
+    #[error("Invalid challenge manifest: {0}")]
+    InvalidManifest(String),
+    #[error("GitHub request failed: {0}")]
+    Github(String),
+    #[error("Invalid health check: {0}")]
+    InvalidHealthcheck(String),
+    #[error("Challenge {0} has no health check URL")]
+    NoHealthcheck(Uuid),
+}
+
+impl From<ChallengeError> for PlatformError {
+    fn from(err: ChallengeError) -> Self {
+        match err {
+            ChallengeError::NotFound(id) => PlatformError::not_found("challenge", id),
+            ChallengeError::NameTaken(_)
+            | ChallengeError::Renamed { .. }
+            | ChallengeError::ComposeHashTaken(_) => PlatformError::conflict(err),
+            ChallengeError::InvalidResources(reason) => PlatformError::invalid("resources", reason),
+            ChallengeError::InvalidManifest(reason) => PlatformError::invalid("manifest", reason),
+            ChallengeError::Github(reason) => PlatformError::upstream("GitHub", reason),
+            ChallengeError::InvalidHealthcheck(reason) => {
+                PlatformError::invalid("healthcheck", reason)
+            }
+            ChallengeError::NoHealthcheck(_) => PlatformError::conflict(err),
+        }
+    }
+}
+
 /// Builder service
 pub struct BuilderService {
     config: BuilderConfig,
     database_pool: Option<Arc<PgPool>>,
+    image_builds: ImageBuildQueue,
 }
 
 impl BuilderService {
     pub fn new(config: &BuilderConfig, database_pool: Option<Arc<PgPool>>) -> Result<Self> {
         Ok(Self {
             config: config.clone(),
+            image_builds: ImageBuildQueue::new(
+                config,
+                Arc::new(DockerCliBackend),
+                database_pool.clone(),
+            ),
             database_pool,
         })
     }
 
-    /// Calculate compose_hash from docker-compose file
-    /// Uses the same normalization as compose_hash.rs for consistency
-    fn calculate_compose_hash(&self, challenge_name: &str) -> Result<String> {
-        info!("Calculating compose_hash for challenge: {}", challenge_name);
-
-        // Try to find docker-compose file for this challenge
-        // For term-challenge, look in term-challenge directory
-        let possible_paths: Vec<String> = if challenge_name == "term-challenge" {
-            vec![
-                "term-challenge/docker-compose.yaml".to_string(),
-                "term-challenge/docker-compose.yml".to_string(),
-                "../term-challenge/docker-compose.yaml".to_string(),
-                "../term-challenge/docker-compose.yml".to_string(),
-                "./docker-compose.dev.yml".to_string(),
-                "../docker-compose.dev.yml".to_string(),
-                "docker-compose.dev.yml".to_string(),
-            ]
-        } else {
-            vec![
-                format!("{}/docker-compose.yaml", challenge_name),
-                format!("{}/docker-compose.yml", challenge_name),
-                "./docker-compose.dev.yml".to_string(),
-                "../docker-compose.dev.yml".to_string(),
-                "docker-compose.dev.yml".to_string(),
-            ]
-        };
+    /// Run image builds with `backend` instead of the docker CLI
+    pub fn with_build_backend(mut self, backend: Arc<dyn BuildBackend>) -> Self {
+        self.image_builds = ImageBuildQueue::new(&self.config, backend, self.database_pool.clone());
+        self
+    }
 
-        let mut file_content: Option<String> = None;
-        let mut used_path: Option<String> = None;
+    /// Queue of challenge image builds, see [`image_build`]
+    pub fn image_builds(&self) -> &ImageBuildQueue {
+        &self.image_builds
+    }
 
-        for path_str in possible_paths {
-            let path = Path::new(&path_str);
-            if path.exists() && path.is_file() {
-                match fs::read_to_string(path) {
-                    Ok(content) => {
-                        file_content = Some(content);
-                        used_path = Some(path_str.clone());
-                        break;
-                    }
-                    Err(e) => {
-                        warn!("Failed to read {}: {}", path_str, e);
-                        continue;
-                    }
-                }
-            }
+    /// Compose hash validators running the challenge `name` report
+    ///
+    /// This is the hash of the `app_compose` manifest the challenge's CVM is
+    /// deployed with, given `compose_yaml` and requiring the variables of
+    /// `env`, computed like the expected hash of validator VMs, see
+    /// [`expected_compose_hash`]. The predefined term-challenge keeps a fixed
+    /// dev hash.
+    pub fn expected_compose_hash(
+        name: &str,
+        compose_yaml: &str,
+        env: &BTreeMap<String, String>,
+        algorithm: HashAlgorithm,
+    ) -> String {
+        if name == "term-challenge" {
+            return TERM_CHALLENGE_COMPOSE_HASH.to_string();
         }
-
-        let content = file_content
-            .context("Could not find docker-compose file for challenge. Compose hash will be generated from name.")?;
-
-        // Use the same normalization as compose_hash.rs
-        let final_hash = Self::calculate_compose_hash_from_content(&content)?;
-
-        info!(
-            "Calculated compose hash from: {} -> {}",
-            used_path.as_deref().unwrap_or("unknown"),
-            final_hash
-        );
-        Ok(final_hash)
+        expected_compose_hash(name, compose_yaml, env.keys(), algorithm).hex
     }
 
-    /// Calculate SHA256 hash from docker-compose file content
-    ///
-    /// This function calculates the hash using the dstack-specific normalization:
-    /// - Parses the content as JSON or YAML
-    /// - Sets docker_config to an empty object
-    /// - Serializes to compact JSON
-    /// - Calculates SHA256 hash
+    /// Deterministic challenge ID derived from the request
     ///
-    /// This matches the normalization used in platform-api/crates/api/src/compose_hash.rs
-    fn calculate_compose_hash_from_content(content: &str) -> Result<String> {
-        // Normalize the content for consistent hashing
-        let normalized = Self::normalize_compose_content(content)?;
-
-        // Calculate SHA256 hash
-        let mut hasher = Sha256::new();
-        hasher.update(normalized.as_bytes());
-        let hash = hasher.finalize();
-        let hash_hex = hex::encode(hash);
-
-        info!("Calculated compose hash: {}", hash_hex);
+    /// Names are unique, so requests with the same name and description are
+    /// the same challenge, see `create_challenge_with_log`.
+    pub fn challenge_id(request: &CreateChallengeRequest) -> Uuid {
+        let id_bytes = format!("{}{}", request.name, request.description);
+        let id_hash = sha2::Sha256::digest(id_bytes.as_bytes());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&id_hash[..16]);
+        Uuid::from_bytes(bytes)
+    }
 
-        Ok(hash_hex)
+    pub async fn create_challenge(
+        &self,
+        request: CreateChallengeRequest,
+    ) -> PlatformResult<ChallengeMetadata> {
+        self.create_challenge_with_log(request, SYSTEM_OWNER, &BuildLog::disabled())
+            .await
     }
 
-    /// Normalize docker-compose content for consistent hashing.
-    ///
-    /// This function implements the dstack-specific normalization for compose manifests.
-    /// It handles both YAML (from docker-compose files) and JSON formats.
-    /// The content is converted to JSON, then `docker_config` is set to an empty object,
-    /// and the result is serialized as compact JSON.
+    /// Create a challenge owned by `owner`, reporting each step to `log`
     ///
-    /// This matches the normalization used in platform-api/crates/api/src/compose_hash.rs
-    fn normalize_compose_content(content: &str) -> Result<String> {
-        // Try to parse as JSON first (expected format for dstack compose manifests)
-        let mut json_value: serde_json::Value = match serde_json::from_str(content) {
-            Ok(value) => value,
-            Err(_) => {
-                // If JSON parsing fails, try YAML (format from docker-compose files)
-                let yaml_value: serde_yaml::Value = serde_yaml::from_str(content)
-                    .context("Failed to parse compose content as JSON or YAML")?;
-
-                // Convert YAML Value to JSON Value
-                serde_json::to_value(yaml_value).context("Failed to convert YAML to JSON")?
-            }
-        };
+    /// The last event emitted is marked `done`, whether the build succeeded or not.
+    /// Challenge names are unique: a request naming an existing challenge is
+    /// refused, unless it has the same description, making it a rebuild of
+    /// that challenge, and `owner` already owns it. A challenge with a health
+    /// check URL is checked once built, see [`health_check`]; an unhealthy
+    /// challenge is still created.
+    pub async fn create_challenge_with_log(
+        &self,
+        request: CreateChallengeRequest,
+        owner: &str,
+        log: &BuildLog,
+    ) -> PlatformResult<ChallengeMetadata> {
+        log.info(format!("Starting build for challenge '{}'", request.name));
 
-        // The dstack RTMR3 calculation for compose_hash requires setting docker_config to an empty object.
-        if let Some(obj) = json_value.as_object_mut() {
-            obj.insert(
-                "docker_config".to_string(),
-                serde_json::Value::Object(serde_json::Map::new()),
-            );
+        match self.build_challenge(request, None, owner, log).await {
+            Ok(challenge) => {
+                if let Some(url) = &challenge.healthcheck_url {
+                    let timeout =
+                        std::time::Duration::from_secs(challenge.healthcheck_timeout_secs.into());
+                    let health = check_health(url, timeout).await;
+                    match health.error {
+                        None => log.info(format!(
+                            "Health check passed in {} ms",
+                            health.latency_ms
+                        )),
+                        Some(error) => log.warn(format!("Health check failed: {}", error)),
+                    }
+                }
+                log.finish(
+                    BuildLogLevel::Info,
+                    format!(
+                        "Challenge '{}' created with ID {}",
+                        challenge.name, challenge.id
+                    ),
+                );
+                Ok(challenge)
+            }
+            Err(e) => {
+                log.finish(BuildLogLevel::Error, format!("Build failed: {:#}", e));
+                Err(e)
+            }
         }
-
-        // Serialize back to a compact string, which is what's hashed.
-        serde_json::to_string(&json_value).context("Failed to serialize normalized compose content")
     }
 
-    pub async fn create_challenge(
+    /// Build the challenge of `request`, with `compose_yaml` if given instead
+    /// of the compose file found for its name
+    pub(crate) async fn build_challenge(
         &self,
         request: CreateChallengeRequest,
-    ) -> Result<ChallengeMetadata> {
+        compose_yaml: Option<String>,
+        owner: &str,
+        log: &BuildLog,
+    ) -> PlatformResult<ChallengeMetadata> {
         // Generate deterministic ID from request data
-        let id_bytes = format!("{}{}", request.name, request.description);
-        let id_hash = sha2::Sha256::digest(id_bytes.as_bytes());
-        let id = Uuid::from_bytes([
-            id_hash[0],
-            id_hash[1],
-            id_hash[2],
-            id_hash[3],
-            id_hash[4],
-            id_hash[5],
-            id_hash[6],
-            id_hash[7],
-            id_hash[8],
-            id_hash[9],
-            id_hash[10],
-            id_hash[11],
-            id_hash[12],
-            id_hash[13],
-            id_hash[14],
-            id_hash[15],
-        ]);
+        let id = Self::challenge_id(&request);
 
         let now = Utc::now();
 
         // If database pool is available, insert into PostgreSQL
         if let Some(pool) = &self.database_pool {
             info!("Database pool available, inserting challenge into PostgreSQL");
-            // Calculate compose_hash - for term-challenge use a consistent hash
-            let compose_hash = if request.name == "term-challenge" {
-                // Use consistent hash for term-challenge in dev environment
-                let hash = "term-challenge-dev-001".to_string();
-                info!("Using predefined compose_hash for term-challenge: {}", hash);
-                hash
-            } else {
-                match self.calculate_compose_hash(&request.name) {
-                    Ok(hash) => {
-                        info!("Calculated compose_hash: {}", hash);
-                        hash
-                    }
-                    Err(e) => {
-                        warn!(
-                            "Failed to calculate compose_hash: {}. Using fallback hash.",
-                            e
-                        );
-                        // Fallback: generate hash from challenge name
-                        let mut hasher = Sha256::new();
-                        hasher.update(format!("{}-{}", request.name, now.timestamp()).as_bytes());
-                        let fallback_hash = format!("dev-{}", hex::encode(hasher.finalize()));
-                        info!("Using fallback compose_hash: {}", fallback_hash);
-                        fallback_hash
-                    }
-                }
-            };
-
+            match &compose_yaml {
+                Some(compose_yaml) => self
+                    .compose_policy()
+                    .validate(compose_yaml, &request.harness_config.environment)?,
+                None => self.check_compose(&request)?,
+            }
+            log.info("Compose file validated");
+            let resource_requirements = request.resources.clone().unwrap_or_default();
+            self.check_resources(&resource_requirements)?;
+            validate_healthcheck(
+                request.healthcheck_url.as_deref(),
+                request.healthcheck_timeout_secs,
+            )?;
+            let healthcheck_timeout_secs = request
+                .healthcheck_timeout_secs
+                .unwrap_or(DEFAULT_HEALTHCHECK_TIMEOUT_SECS);
             // Read compose_yaml (try to read docker-compose file)
-            let compose_yaml = if request.name == "term-challenge" {
+            let compose_yaml = if let Some(compose_yaml) = compose_yaml {
+                compose_yaml
+            } else if request.name == "term-challenge" {
                 // Use specific compose for term-challenge
                 r#"# Term Challenge Docker Compose
 # This is a placeholder compose file for term-challenge
@@ -231,6 +265,10 @@ services:
                 "Using compose_yaml (first 100 chars): {}...",
                 &compose_yaml[..compose_yaml.len().min(100)]
             );
+            log.info(format!(
+                "Compose file resolved ({} bytes)",
+                compose_yaml.len()
+            ));
 
             // Set default values for required fields
             let version = "1.0.0".to_string();
@@ -247,8 +285,21 @@ services:
                     request.harness_config.resources.disk_mb / 1024
                 )), // Convert MB to G
             };
+            log.info(format!(
+                "Resources validated: {} vCPU, {} memory, {} disk",
+                resources.vcpu,
+                resources.memory,
+                resources.disk.as_deref().unwrap_or("default")
+            ));
             let ports: Vec<ChallengePort> = vec![]; // Empty for now
             let env: BTreeMap<String, String> = request.harness_config.environment.clone();
+            let compose_hash = Self::expected_compose_hash(
+                &request.name,
+                &compose_yaml,
+                &env,
+                self.config.compose_hash_algorithm,
+            );
+            log.info(format!("Compose hash: {}", compose_hash));
             let emission_share = 1.0; // Default to 1.0 (100%)
             let mechanism_id: i16 = 0; // Default mechanism ID
             let weight: Option<f64> = None; // Will be auto-calculated
@@ -262,17 +313,22 @@ services:
                 version, images, resources
             );
 
-            // Insert into PostgreSQL
-            sqlx::query(
+            self.ensure_creatable(&request.name, id).await?;
+
+            // Insert into PostgreSQL; an existing challenge is only updated for
+            // its owner, and only if it was not renamed
+            let stored = sqlx::query(
                 r#"
                 INSERT INTO challenges (
                     id, name, compose_hash, compose_yaml, version, images,
                     resources, ports, env, emission_share, mechanism_id, weight,
-                    description, github_repo, created_at, updated_at
+                    description, github_repo, created_at, updated_at, owner, resource_requirements,
+                    healthcheck_url, healthcheck_timeout_secs
                 )
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
-                ON CONFLICT (compose_hash) DO UPDATE SET
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
+                ON CONFLICT (id) DO UPDATE SET
                     name = EXCLUDED.name,
+                    compose_hash = EXCLUDED.compose_hash,
                     compose_yaml = EXCLUDED.compose_yaml,
                     version = EXCLUDED.version,
                     images = EXCLUDED.images,
@@ -284,7 +340,13 @@ services:
                     weight = EXCLUDED.weight,
                     description = EXCLUDED.description,
                     github_repo = EXCLUDED.github_repo,
-                    updated_at = EXCLUDED.updated_at
+                    updated_at = EXCLUDED.updated_at,
+                    resource_requirements = EXCLUDED.resource_requirements,
+                    healthcheck_url = EXCLUDED.healthcheck_url,
+                    healthcheck_timeout_secs = EXCLUDED.healthcheck_timeout_secs
+                WHERE challenges.owner = EXCLUDED.owner
+                  AND LOWER(challenges.name) = LOWER(EXCLUDED.name)
+                RETURNING id
                 "#,
             )
             .bind(id)
@@ -303,20 +365,42 @@ services:
             .bind(request.github_repo.as_deref())
             .bind(now)
             .bind(now)
-            .execute(pool.as_ref())
+            .bind(owner)
+            .bind(serde_json::to_value(&resource_requirements)?)
+            .bind(request.healthcheck_url.as_deref())
+            .bind(healthcheck_timeout_secs as i32)
+            .fetch_optional(pool.as_ref())
             .await
-            .context("Failed to insert challenge into PostgreSQL")?;
+            .map_err(|e| {
+                write_error(
+                    e,
+                    &request.name,
+                    Some(&compose_hash),
+                    "Failed to insert challenge into PostgreSQL",
+                )
+            })?;
+
+            if stored.is_none() {
+                // Renamed since the check, or owned by someone else
+                self.ensure_creatable(&request.name, id).await?;
+                return Err(PlatformError::conflict(format!(
+                    "Challenge '{}' belongs to another owner",
+                    request.name
+                )));
+            }
 
             info!(
                 "✅ Challenge '{}' successfully inserted into PostgreSQL with compose_hash: {}",
                 request.name, compose_hash
             );
+            log.info("Challenge stored in database");
             info!(
                 "   ID: {}, Emission share: {}, Mechanism ID: {}",
                 id, emission_share, mechanism_id
             );
         } else {
             warn!("❌ No database pool available, challenge not inserted into PostgreSQL");
+            log.warn("No database available, challenge was not persisted");
         }
 
         Ok(ChallengeMetadata {
@@ -326,13 +410,265 @@ services:
             version: "1.0.0".to_string(),
             visibility: request.visibility,
             status: ChallengeStatus::Active,
-            owner: "platform-system".to_string(),
+            owner: owner.to_string(),
             created_at: now,
             updated_at: now,
             tags: vec![],
+            default_job_priority: JobPriority::default(),
+            job_payload_schema: None,
+            job_defaults: JobDefaults::default(),
+            resources: request.resources.unwrap_or_default(),
+            score_bounds: ScoreBounds::default(),
+            score_policy: ScorePolicy::default(),
+            healthcheck_url: request.healthcheck_url,
+            healthcheck_timeout_secs: request
+                .healthcheck_timeout_secs
+                .unwrap_or(DEFAULT_HEALTHCHECK_TIMEOUT_SECS),
         })
     }
 
+    /// Create a draft challenge from `source_id`, see `clone_challenge_as`
+    pub async fn clone_challenge(
+        &self,
+        source_id: Uuid,
+        overrides: CreateChallengeRequest,
+    ) -> PlatformResult<ChallengeMetadata> {
+        self.clone_challenge_as(source_id, overrides, SYSTEM_OWNER)
+            .await
+    }
+
+    /// Create a challenge owned by `owner` from the stored challenge `source_id`
+    ///
+    /// `overrides` must give the name and description of the new challenge.
+    /// Its other fields replace those of the source only when set: a GitHub
+    /// repository, resources other than the default ones, resource
+    /// requirements, a health check, and environment variables, which are added to the source's. Everything else, including
+    /// the compose file and emission settings, is copied from the source.
+    ///
+    /// The clone gets a new ID and starts as a `Draft`, with a placeholder
+    /// compose hash until it is built.
+    pub async fn clone_challenge_as(
+        &self,
+        source_id: Uuid,
+        overrides: CreateChallengeRequest,
+        owner: &str,
+    ) -> PlatformResult<ChallengeMetadata> {
+        if overrides.name.trim().is_empty() || overrides.description.trim().is_empty() {
+            return Err(PlatformError::invalid(
+                "name",
+                "A cloned challenge needs a name and a description",
+            ));
+        }
+        let pool = self
+            .database_pool
+            .as_ref()
+            .context("Cloning a challenge requires a database")?;
+
+        #[derive(sqlx::FromRow)]
+        struct SourceRow {
+            compose_yaml: String,
+            version: String,
+            images: Vec<String>,
+            resources: serde_json::Value,
+            ports: serde_json::Value,
+            env: serde_json::Value,
+            emission_share: f64,
+            mechanism_id: i16,
+            weight: Option<f64>,
+            mermaid_chart: Option<String>,
+            github_repo: Option<String>,
+            dstack_image: Option<String>,
+            default_job_priority: String,
+            job_payload_schema: Option<serde_json::Value>,
+            job_defaults: serde_json::Value,
+            resource_requirements: serde_json::Value,
+            score_bounds: serde_json::Value,
+            score_policy: serde_json::Value,
+            healthcheck_url: Option<String>,
+            healthcheck_timeout_secs: i32,
+        }
+
+        let source = sqlx::query_as::<_, SourceRow>(
+            r#"
+            SELECT compose_yaml, version, images, resources, ports, env, emission_share,
+                   mechanism_id, weight, mermaid_chart, github_repo, dstack_image,
+                   default_job_priority, job_payload_schema, job_defaults, resource_requirements,
+                   score_bounds, score_policy, healthcheck_url, healthcheck_timeout_secs
+            FROM challenges
+            WHERE id = $1
+            "#,
+        )
+        .bind(source_id)
+        .fetch_optional(pool.as_ref())
+        .await
+        .context("Failed to load source challenge")?
+        .ok_or(ChallengeError::NotFound(source_id))?;
+
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        self.ensure_name_available(&overrides.name, id).await?;
+
+        let limits = &overrides.harness_config.resources;
+        let resources = if *limits == HarnessConfig::default().resources {
+            source.resources
+        } else {
+            serde_json::to_value(ChallengeResources {
+                vcpu: limits.cpu_cores,
+                memory: format!("{}G", limits.memory_mb / 1024),
+                disk: Some(format!("{}G", limits.disk_mb / 1024)),
+            })?
+        };
+        let resource_requirements = match overrides.resources {
+            Some(requirements) => {
+                self.check_resources(&requirements)?;
+                requirements
+            }
+            None => serde_json::from_value(source.resource_requirements).unwrap_or_default(),
+        };
+        validate_healthcheck(
+            overrides.healthcheck_url.as_deref(),
+            overrides.healthcheck_timeout_secs,
+        )?;
+        let healthcheck_url = overrides.healthcheck_url.or(source.healthcheck_url);
+        let healthcheck_timeout_secs = overrides
+            .healthcheck_timeout_secs
+            .unwrap_or(source.healthcheck_timeout_secs as u32);
+        let mut env: BTreeMap<String, String> =
+            serde_json::from_value(source.env).context("Invalid env of source challenge")?;
+        env.extend(overrides.harness_config.environment);
+
+        sqlx::query(
+            r#"
+            INSERT INTO challenges (
+                id, name, compose_hash, compose_yaml, version, images,
+                resources, ports, env, emission_share, mechanism_id, weight,
+                description, mermaid_chart, github_repo, dstack_image,
+                created_at, updated_at, owner, status, default_job_priority, job_payload_schema,
+                job_defaults, resource_requirements, score_bounds, score_policy, healthcheck_url,
+                healthcheck_timeout_secs
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27)
+            "#,
+        )
+        .bind(id)
+        .bind(&overrides.name)
+        .bind(format!("clone-{}", id))
+        .bind(&source.compose_yaml)
+        .bind(&source.version)
+        .bind(&source.images)
+        .bind(&resources)
+        .bind(&source.ports)
+        .bind(serde_json::to_value(&env)?)
+        .bind(source.emission_share)
+        .bind(source.mechanism_id)
+        .bind(source.weight)
+        .bind(&overrides.description)
+        .bind(source.mermaid_chart.as_deref())
+        .bind(overrides.github_repo.as_deref().or(source.github_repo.as_deref()))
+        .bind(source.dstack_image.as_deref())
+        .bind(now)
+        .bind(owner)
+        .bind(ChallengeStatus::Draft.as_str())
+        .bind(&source.default_job_priority)
+        .bind(&source.job_payload_schema)
+        .bind(&source.job_defaults)
+        .bind(serde_json::to_value(&resource_requirements)?)
+        .bind(&source.score_bounds)
+        .bind(&source.score_policy)
+        .bind(healthcheck_url.as_deref())
+        .bind(healthcheck_timeout_secs as i32)
+        .execute(pool.as_ref())
+        .await
+        .map_err(|e| write_error(e, &overrides.name, None, "Failed to insert cloned challenge"))?;
+
+        info!(source_id = %source_id, challenge_id = %id, owner, "Cloned challenge");
+
+        Ok(ChallengeMetadata {
+            id,
+            name: overrides.name,
+            description: overrides.description,
+            version: source.version,
+            visibility: overrides.visibility,
+            status: ChallengeStatus::Draft,
+            owner: owner.to_string(),
+            created_at: now,
+            updated_at: now,
+            tags: vec![],
+            default_job_priority: JobPriority::from(source.default_job_priority.as_str()),
+            job_payload_schema: source.job_payload_schema,
+            job_defaults: serde_json::from_value(source.job_defaults).unwrap_or_default(),
+            resources: resource_requirements,
+            score_bounds: serde_json::from_value(source.score_bounds).unwrap_or_default(),
+            score_policy: serde_json::from_value(source.score_policy).unwrap_or_default(),
+            healthcheck_url,
+            healthcheck_timeout_secs,
+        })
+    }
+
+    /// What challenge compose files may use: images of the configured
+    /// registries, including `docker_registry`, and the configured bind mounts
+    pub fn compose_policy(&self) -> ComposePolicy {
+        let mut allowed_registries = vec![self.config.docker_registry.clone()];
+        allowed_registries.extend(self.config.allowed_registries.iter().cloned());
+        ComposePolicy {
+            allowed_registries,
+            allowed_bind_mounts: self.config.allowed_bind_mounts.clone(),
+        }
+    }
+
+    /// Check the compose file a challenge created from `request` gets, see
+    /// [`compose_validation`]
+    pub fn check_compose(&self, request: &CreateChallengeRequest) -> Result<(), InvalidCompose> {
+        // The predefined term-challenge compose file is trusted
+        if request.name == "term-challenge" {
+            return Ok(());
+        }
+        match self.read_compose_yaml(&request.name) {
+            Some(compose_yaml) => self
+                .compose_policy()
+                .validate(&compose_yaml, &request.harness_config.environment),
+            None => Ok(()),
+        }
+    }
+
+    /// Check the resource requirements of a challenge against the configured
+    /// maximums, see `BuilderConfig::max_resources`
+    pub fn check_resources(&self, resources: &ResourceRequirements) -> Result<(), ChallengeError> {
+        let max = &self.config.max_resources;
+        let check = |field: &str, value: Option<u64>, max: Option<u64>| match (value, max) {
+            (Some(0), _) => Err(ChallengeError::InvalidResources(format!(
+                "{} must be greater than zero",
+                field
+            ))),
+            (Some(value), Some(max)) if value > max => Err(ChallengeError::InvalidResources(
+                format!("{} cannot exceed {}", field, max),
+            )),
+            _ => Ok(()),
+        };
+        let gpu_count = resources.gpu_count.filter(|&count| count > 0);
+        check(
+            "vcpu",
+            resources.vcpu.map(Into::into),
+            max.vcpu.map(Into::into),
+        )?;
+        check("memory_gb", resources.memory_gb, max.memory_gb)?;
+        check(
+            "gpu_count",
+            gpu_count.map(Into::into),
+            max.gpu_count.map(Into::into),
+        )?;
+        check("disk_gb", resources.disk_gb, max.disk_gb)?;
+        match &resources.gpu_type {
+            Some(gpu_type) if gpu_type.trim().is_empty() => Err(ChallengeError::InvalidResources(
+                "gpu_type cannot be empty".to_string(),
+            )),
+            Some(_) if resources.gpus() == 0 => Err(ChallengeError::InvalidResources(
+                "gpu_type requires at least one GPU".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     /// Read compose_yaml from file
     fn read_compose_yaml(&self, challenge_name: &str) -> Option<String> {
         let possible_paths: Vec<String> = if challenge_name == "term-challenge" {
@@ -367,43 +703,384 @@ services:
         None
     }
 
+    /// Update the stored challenge `id`
+    ///
+    /// Only the fields set in `request` change: name, description, status,
+    /// default job priority, job payload schema, job defaults, resource
+    /// requirements, score bounds and policy, health check, and the resources and environment of a harness config. Renaming to the name of another
+    /// challenge is refused, and so is an environment missing variables the
+    /// compose file of the challenge needs, see [`compose_validation`].
+    /// A new name or environment changes the challenge's `app_compose`
+    /// manifest, so its compose hash is recomputed.
     pub async fn update_challenge(
         &self,
         id: Uuid,
         request: UpdateChallengeRequest,
-    ) -> Result<ChallengeMetadata> {
-        // Return updated metadata (minimal implementation)
+    ) -> PlatformResult<ChallengeMetadata> {
+        let pool = self
+            .database_pool
+            .as_ref()
+            .context("Updating a challenge requires a database")?;
+        if let Some(name) = &request.name {
+            if name.trim().is_empty() {
+                return Err(PlatformError::invalid(
+                    "name",
+                    "A challenge name cannot be empty",
+                ));
+            }
+            self.ensure_name_available(name, id).await?;
+        }
+        if let Some(resources) = &request.resources {
+            self.check_resources(resources)?;
+        }
+        // An empty health check URL removes the health check
+        validate_healthcheck(
+            request.healthcheck_url.as_deref().filter(|url| !url.is_empty()),
+            request.healthcheck_timeout_secs,
+        )?;
+        if let Some(harness_config) = &request.harness_config {
+            let (name, compose_yaml): (String, String) =
+                sqlx::query_as("SELECT name, compose_yaml FROM challenges WHERE id = $1")
+                    .bind(id)
+                    .fetch_optional(pool.as_ref())
+                    .await
+                    .context("Failed to load challenge")?
+                    .ok_or(ChallengeError::NotFound(id))?;
+            if name != "term-challenge" {
+                self.compose_policy()
+                    .validate(&compose_yaml, &harness_config.environment)?;
+            }
+        }
+
+        #[derive(sqlx::FromRow)]
+        struct UpdatedRow {
+            name: String,
+            description: Option<String>,
+            version: String,
+            owner: String,
+            status: String,
+            default_job_priority: String,
+            job_payload_schema: Option<serde_json::Value>,
+            job_defaults: serde_json::Value,
+            resource_requirements: serde_json::Value,
+            score_bounds: serde_json::Value,
+            score_policy: serde_json::Value,
+            healthcheck_url: Option<String>,
+            healthcheck_timeout_secs: i32,
+            created_at: chrono::DateTime<Utc>,
+            updated_at: chrono::DateTime<Utc>,
+        }
+
+        let default_job_priority = request
+            .default_job_priority
+            .as_ref()
+            .map(JobPriority::as_str);
+        let (resources, env) = match &request.harness_config {
+            Some(harness_config) => {
+                let limits = &harness_config.resources;
+                let resources = ChallengeResources {
+                    vcpu: limits.cpu_cores,
+                    memory: format!("{}G", limits.memory_mb / 1024),
+                    disk: Some(format!("{}G", limits.disk_mb / 1024)),
+                };
+                (
+                    Some(serde_json::to_value(resources)?),
+                    Some(serde_json::to_value(&harness_config.environment)?),
+                )
+            }
+            None => (None, None),
+        };
+        let job_defaults = request
+            .job_defaults
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?;
+        let resource_requirements = request
+            .resources
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?;
+        let score_bounds = request
+            .score_bounds
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?;
+        let score_policy = request
+            .score_policy
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?;
+        let row = sqlx::query_as::<_, UpdatedRow>(
+            r#"
+            UPDATE challenges
+            SET name = COALESCE($2, name),
+                description = COALESCE($3, description),
+                status = COALESCE($4, status),
+                default_job_priority = COALESCE($5, default_job_priority),
+                job_payload_schema = COALESCE($6, job_payload_schema),
+                resources = COALESCE($7, resources),
+                env = COALESCE($8, env),
+                job_defaults = COALESCE($9, job_defaults),
+                resource_requirements = COALESCE($10, resource_requirements),
+                score_bounds = COALESCE($11, score_bounds),
+                healthcheck_url = NULLIF(COALESCE($12, healthcheck_url), ''),
+                healthcheck_timeout_secs = COALESCE($13, healthcheck_timeout_secs),
+                score_policy = COALESCE($14, score_policy),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING name, description, version, owner, status, default_job_priority,
+                      job_payload_schema, job_defaults, resource_requirements, score_bounds,
+                      score_policy, healthcheck_url, healthcheck_timeout_secs, created_at,
+                      updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(request.name.as_deref())
+        .bind(request.description.as_deref())
+        .bind(request.status.as_ref().map(ChallengeStatus::as_str))
+        .bind(default_job_priority)
+        .bind(&request.job_payload_schema)
+        .bind(resources)
+        .bind(env)
+        .bind(job_defaults)
+        .bind(resource_requirements)
+        .bind(score_bounds)
+        .bind(request.healthcheck_url.as_deref())
+        .bind(request.healthcheck_timeout_secs.map(|secs| secs as i32))
+        .bind(score_policy)
+        .fetch_optional(pool.as_ref())
+        .await
+        .map_err(|e| {
+            write_error(
+                e,
+                request.name.as_deref().unwrap_or_default(),
+                None,
+                "Failed to update challenge",
+            )
+        })?
+        .ok_or(ChallengeError::NotFound(id))?;
+
+        if request.name.is_some() || request.harness_config.is_some() {
+            self.recompute_compose_hash(id).await?;
+        }
+
+        info!(challenge_id = %id, "Updated challenge");
+
         Ok(ChallengeMetadata {
             id,
-            name: request
-                .name
-                .unwrap_or_else(|| "Unnamed Challenge".to_string()),
-            description: request
-                .description
-                .unwrap_or_else(|| "No description".to_string()),
-            version: "1.0.0".to_string(),
+            name: row.name,
+            description: row.description.unwrap_or_default(),
+            version: row.version,
             visibility: ChallengeVisibility::Public,
-            status: request.status.unwrap_or(ChallengeStatus::Active),
-            owner: "platform-system".to_string(),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+            status: ChallengeStatus::from(row.status.as_str()),
+            owner: row.owner,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
             tags: vec![],
+            default_job_priority: JobPriority::from(row.default_job_priority.as_str()),
+            job_payload_schema: row.job_payload_schema,
+            job_defaults: serde_json::from_value(row.job_defaults).unwrap_or_default(),
+            resources: serde_json::from_value(row.resource_requirements).unwrap_or_default(),
+            score_bounds: serde_json::from_value(row.score_bounds).unwrap_or_default(),
+            score_policy: serde_json::from_value(row.score_policy).unwrap_or_default(),
+            healthcheck_url: row.healthcheck_url,
+            healthcheck_timeout_secs: row.healthcheck_timeout_secs as u32,
+        })
+    }
+
+    /// Recompute the compose hash of the stored challenge `id`, storing it if
+    /// it drifted from the stored one
+    ///
+    /// The hash is computed from the challenge's name, compose file and
+    /// environment, see [`Self::expected_compose_hash`]. A stored hash that
+    /// differs, e.g. in a record edited by hand, is replaced, and so is the
+    /// compose hash the challenge's environment variables are stored under.
+    pub async fn recompute_compose_hash(&self, id: Uuid) -> PlatformResult<ComposeHashDrift> {
+        let pool = self
+            .database_pool
+            .as_ref()
+            .context("Recomputing a compose hash requires a database")?;
+
+        let mut tx = pool.begin().await.context("Failed to start transaction")?;
+        let (name, compose_yaml, env, stored_hash): (String, String, serde_json::Value, String) =
+            sqlx::query_as(
+                "SELECT name, compose_yaml, env, compose_hash FROM challenges WHERE id = $1 FOR UPDATE",
+            )
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to load challenge")?
+            .ok_or(ChallengeError::NotFound(id))?;
+        let env: BTreeMap<String, String> =
+            serde_json::from_value(env).context("Invalid env of challenge")?;
+
+        let expected_hash = Self::expected_compose_hash(
+            &name,
+            &compose_yaml,
+            &env,
+            self.config.compose_hash_algorithm,
+        );
+        let drifted = expected_hash != stored_hash;
+        if drifted {
+            sqlx::query(
+                "UPDATE challenges SET compose_hash = $2, updated_at = NOW() WHERE id = $1",
+            )
+            .bind(id)
+            .bind(&expected_hash)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                write_error(
+                    e,
+                    &name,
+                    Some(&expected_hash),
+                    "Failed to store compose hash",
+                )
+            })?;
+            sqlx::query("UPDATE challenge_env_vars SET compose_hash = $2 WHERE compose_hash = $1")
+                .bind(&stored_hash)
+                .bind(&expected_hash)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to move challenge environment variables")?;
+            warn!(
+                challenge_id = %id,
+                stored_hash = %stored_hash,
+                expected_hash = %expected_hash,
+                "Compose hash of challenge drifted, storing the recomputed one"
+            );
+        }
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(ComposeHashDrift {
+            challenge_id: id,
+            stored_hash,
+            expected_hash,
+            drifted,
         })
     }
 
-    pub async fn delete_challenge(&self, _id: Uuid) -> Result<()> {
-        // Challenge deletion is successful
+    /// Fail with [`ChallengeError::NameTaken`] if a challenge other than `id`
+    /// is named `name`, ignoring case
+    ///
+    /// Names are only checked against stored challenges. Writes racing past
+    /// the check are refused by the unique index of names all the same.
+    pub async fn ensure_name_available(&self, name: &str, id: Uuid) -> PlatformResult<()> {
+        let Some(pool) = &self.database_pool else {
+            return Ok(());
+        };
+        let taken: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM challenges WHERE LOWER(name) = LOWER($1) AND id <> $2)",
+        )
+        .bind(name)
+        .bind(id)
+        .fetch_one(pool.as_ref())
+        .await
+        .context("Failed to check the challenge name")?;
+        if taken {
+            return Err(ChallengeError::NameTaken(name.to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Fail unless a challenge named `name` can be created, or rebuilt, as `id`
+    ///
+    /// Besides its name being available, the challenge `id` derives from its
+    /// name and description, see [`Self::challenge_id`], must not have been
+    /// renamed: the request would otherwise rebuild that challenge under the
+    /// name it left. That fails with [`ChallengeError::Renamed`].
+    pub async fn ensure_creatable(&self, name: &str, id: Uuid) -> PlatformResult<()> {
+        self.ensure_name_available(name, id).await?;
+        let Some(pool) = &self.database_pool else {
+            return Ok(());
+        };
+        let stored: Option<String> =
+            sqlx::query_scalar("SELECT name FROM challenges WHERE id = $1")
+                .bind(id)
+                .fetch_optional(pool.as_ref())
+                .await
+                .context("Failed to look up challenge")?;
+        match stored {
+            Some(stored) if stored.to_lowercase() != name.to_lowercase() => {
+                Err(ChallengeError::Renamed { id, name: stored }.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Delete the stored challenge `id` along with its environment variables
+    pub async fn delete_challenge(&self, id: Uuid) -> PlatformResult<()> {
+        let pool = self
+            .database_pool
+            .as_ref()
+            .context("Deleting a challenge requires a database")?;
+
+        let mut tx = pool.begin().await.context("Failed to start transaction")?;
+        let compose_hash: String =
+            sqlx::query_scalar("DELETE FROM challenges WHERE id = $1 RETURNING compose_hash")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await
+                .context("Failed to delete challenge")?
+                .ok_or(ChallengeError::NotFound(id))?;
+        sqlx::query("DELETE FROM challenge_env_vars WHERE compose_hash = $1")
+            .bind(&compose_hash)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete challenge environment variables")?;
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        info!(challenge_id = %id, "Deleted challenge");
         Ok(())
     }
 }
 
+/// Error of a failed write of challenge `name`, with `compose_hash` if set
+///
+/// Unique violations of the name, by a write racing past
+/// [`BuilderService::ensure_name_available`], or of the compose hash are
+/// conflicts rather than internal errors.
+fn write_error(
+    err: sqlx::Error,
+    name: &str,
+    compose_hash: Option<&str>,
+    context: &'static str,
+) -> PlatformError {
+    let constraint = err.as_database_error().and_then(|e| e.constraint());
+    match (constraint, compose_hash) {
+        (Some(NAME_INDEX), _) => ChallengeError::NameTaken(name.to_string()).into(),
+        (Some(COMPOSE_HASH_CONSTRAINT), Some(hash)) => {
+            ChallengeError::ComposeHashTaken(hash.to_string()).into()
+        }
+        _ => PlatformError::Internal(anyhow::Error::new(err).context(context)),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BuilderConfig {
     pub build_timeout: u64,
     pub max_concurrent_builds: u32,
+    /// Builds of one challenge owner running at once, see [`build_queue`]
+    pub max_builds_per_owner: u32,
     pub docker_registry: String,
     pub github_token: Option<String>,
+    /// GitHub API challenges are imported through, see [`github_import`]
+    pub github_api_url: String,
+    /// Bytes of images the build cache keeps, see [`build_cache`]
     pub build_cache_size: u64,
+    /// Delete the images evicted from the build cache from the registry
+    ///
+    /// Off by default, as challenges may still run an evicted image.
+    pub prune_evicted_images: bool,
+    /// Algorithm of challenge compose hashes; must match the attestation side
+    pub compose_hash_algorithm: HashAlgorithm,
+    /// Registries challenge images may come from, besides `docker_registry`
+    pub allowed_registries: Vec<String>,
+    /// Host paths challenge services may bind mount
+    pub allowed_bind_mounts: Vec<String>,
+    /// Largest resources a challenge may require; unset fields are unbounded
+    /// and `gpu_type` is ignored
+    pub max_resources: ResourceRequirements,
 }
 
 impl Default for BuilderConfig {
@@ -411,9 +1088,420 @@ impl Default for BuilderConfig {
         Self {
             build_timeout: 3600,
             max_concurrent_builds: 10,
+            max_builds_per_owner: 3,
             docker_registry: "registry.platform.network".to_string(),
             github_token: None,
+            github_api_url: github_import::DEFAULT_GITHUB_API_URL.to_string(),
             build_cache_size: 10000000000,
+            prune_evicted_images: false,
+            compose_hash_algorithm: HashAlgorithm::default(),
+            allowed_registries: vec![],
+            allowed_bind_mounts: vec![
+                "/var/run/dstack.sock".to_string(),
+                "/var/run/tappd.sock".to_string(),
+            ],
+            max_resources: ResourceRequirements {
+                vcpu: Some(64),
+                memory_gb: Some(512),
+                gpu_count: Some(8),
+                gpu_type: None,
+                disk_gb: Some(2048),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn service() -> (BuilderService, Arc<PgPool>) {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = Arc::new(PgPool::connect(&database_url).await.unwrap());
+        let service = BuilderService::new(&BuilderConfig::default(), Some(pool.clone())).unwrap();
+        (service, pool)
+    }
+
+    fn request(name: &str, description: &str) -> CreateChallengeRequest {
+        CreateChallengeRequest {
+            name: name.to_string(),
+            description: description.to_string(),
+            visibility: ChallengeVisibility::Public,
+            github_repo: None,
+            harness_config: HarnessConfig::default(),
+            dataset_urls: vec![],
+            resources: None,
+            healthcheck_url: None,
+            healthcheck_timeout_secs: None,
+        }
+    }
+
+    fn update(name: Option<&str>) -> UpdateChallengeRequest {
+        UpdateChallengeRequest {
+            name: name.map(str::to_string),
+            description: None,
+            status: None,
+            harness_config: None,
+            default_job_priority: None,
+            job_payload_schema: None,
+            job_defaults: None,
+            resources: None,
+            score_bounds: None,
+            score_policy: None,
+            healthcheck_url: None,
+            healthcheck_timeout_secs: None,
         }
     }
+
+    fn error_of(result: PlatformResult<impl std::fmt::Debug>) -> PlatformError {
+        result.unwrap_err()
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_challenge_crud_round_trip() {
+        let (service, pool) = service().await;
+        let name = format!("crud-test-{}", Uuid::new_v4());
+        let owner = "crud-test-owner";
+
+        let created = service
+            .create_challenge_with_log(request(&name, "first"), owner, &BuildLog::disabled())
+            .await
+            .unwrap();
+        let stored: (String, String) =
+            sqlx::query_as("SELECT name, owner FROM challenges WHERE id = $1")
+                .bind(created.id)
+                .fetch_one(pool.as_ref())
+                .await
+                .unwrap();
+        assert_eq!(stored, (name.clone(), owner.to_string()));
+
+        // Rebuilding is allowed for the owner only; other challenges cannot take the name
+        let rebuilt = service
+            .create_challenge_with_log(request(&name, "first"), owner, &BuildLog::disabled())
+            .await
+            .unwrap();
+        assert_eq!(rebuilt.id, created.id);
+        assert!(service
+            .create_challenge_with_log(request(&name, "first"), "other", &BuildLog::disabled())
+            .await
+            .is_err());
+        assert!(matches!(
+            error_of(service.create_challenge(request(&name, "second")).await),
+            PlatformError::Conflict { reason } if reason.contains(&name)
+        ));
+
+        let mut changes = update(Some(&format!("{}-renamed", name)));
+        changes.description = Some("updated".to_string());
+        changes.status = Some(ChallengeStatus::Paused);
+        let mut harness_config = HarnessConfig::default();
+        harness_config.resources.cpu_cores = 4;
+        harness_config
+            .environment
+            .insert("MODE".to_string(), "full".to_string());
+        changes.harness_config = Some(harness_config);
+        let requirements = ResourceRequirements {
+            vcpu: Some(8),
+            gpu_type: Some("h100".to_string()),
+            ..Default::default()
+        };
+        changes.resources = Some(requirements.clone());
+        let updated = service.update_challenge(created.id, changes).await.unwrap();
+        assert_eq!(updated.resources, requirements);
+        assert_eq!(updated.name, format!("{}-renamed", name));
+        assert_eq!(updated.description, "updated");
+        assert_eq!(updated.status, ChallengeStatus::Paused);
+        assert!(updated.updated_at > created.updated_at);
+        let stored: (String, String, serde_json::Value, serde_json::Value) =
+            sqlx::query_as("SELECT name, status, resources, env FROM challenges WHERE id = $1")
+                .bind(created.id)
+                .fetch_one(pool.as_ref())
+                .await
+                .unwrap();
+        assert_eq!(stored.0, updated.name);
+        assert_eq!(stored.1, ChallengeStatus::Paused.as_str());
+        assert_eq!(stored.2["vcpu"], 4);
+        assert_eq!(stored.3, serde_json::json!({ "MODE": "full" }));
+
+        // Requirements above the configured maximums are refused
+        let mut oversized = update(None);
+        oversized.resources = Some(ResourceRequirements {
+            memory_gb: Some(4096),
+            ..Default::default()
+        });
+        assert!(matches!(
+            error_of(service.update_challenge(created.id, oversized).await),
+            PlatformError::Validation { fields } if fields[0].field == "resources"
+        ));
+
+        // Names of other challenges cannot be reused by renaming or cloning
+        let other = service
+            .create_challenge(request(&name, "other"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            error_of(
+                service
+                    .update_challenge(other.id, update(Some(&updated.name)))
+                    .await
+            ),
+            PlatformError::Conflict { .. }
+        ));
+        assert!(matches!(
+            error_of(
+                service
+                    .clone_challenge(created.id, request(&name, "clone"))
+                    .await
+            ),
+            PlatformError::Conflict { .. }
+        ));
+
+        for id in [created.id, other.id] {
+            service.delete_challenge(id).await.unwrap();
+        }
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM challenges WHERE id = $1")
+            .bind(created.id)
+            .fetch_one(pool.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
+        assert!(matches!(
+            error_of(service.delete_challenge(created.id).await),
+            PlatformError::NotFound { resource, id }
+                if resource == "challenge" && id == created.id.to_string()
+        ));
+        assert!(matches!(
+            error_of(service.update_challenge(created.id, update(None)).await),
+            PlatformError::NotFound { .. }
+        ));
+        assert!(matches!(
+            error_of(
+                service
+                    .clone_challenge(created.id, request("clone", "clone"))
+                    .await
+            ),
+            PlatformError::NotFound { .. }
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_challenge_names_and_compose_hashes_unique() {
+        let (service, pool) = service().await;
+        let name = format!("unique-test-{}", Uuid::new_v4());
+        let conflict = |err: PlatformError, expected: &str| match err {
+            PlatformError::Conflict { reason } => assert!(reason.contains(expected), "{}", reason),
+            err => panic!("not a conflict: {}", err),
+        };
+
+        // Of concurrent creates of one name, only one gets it, whichever check refuses the other
+        let (first, second) = tokio::join!(
+            service.create_challenge(request(&name, "first")),
+            service.create_challenge(request(&name.to_uppercase(), "second")),
+        );
+        let (created, err) = match (first, second) {
+            (Ok(created), Err(err)) | (Err(err), Ok(created)) => (created, err),
+            (first, second) => panic!("{:?} {:?}", first, second),
+        };
+        conflict(err, "already exists");
+
+        // The ID of a renamed challenge is not rebuilt under the name it left
+        let renamed = format!("{}-renamed", name);
+        service
+            .update_challenge(created.id, update(Some(&renamed)))
+            .await
+            .unwrap();
+        let recreate = request(&created.name, &created.description);
+        assert_eq!(BuilderService::challenge_id(&recreate), created.id);
+        conflict(
+            error_of(service.create_challenge(recreate).await),
+            "was renamed",
+        );
+
+        // A compose hash taken by another challenge is a conflict too
+        let clone = service
+            .clone_challenge(created.id, request(&name, "clone"))
+            .await
+            .unwrap();
+        let hash: String = sqlx::query_scalar("SELECT compose_hash FROM challenges WHERE id = $1")
+            .bind(created.id)
+            .fetch_one(pool.as_ref())
+            .await
+            .unwrap();
+        sqlx::query("UPDATE challenges SET compose_hash = 'drifted-' || id WHERE id = $1")
+            .bind(created.id)
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        sqlx::query("UPDATE challenges SET compose_hash = $2 WHERE id = $1")
+            .bind(clone.id)
+            .bind(&hash)
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        conflict(
+            error_of(service.recompute_compose_hash(created.id).await),
+            &hash,
+        );
+
+        for id in [created.id, clone.id] {
+            service.delete_challenge(id).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_expected_compose_hash_covers_manifest() {
+        let hash_of = |name: &str, compose: &str, env: &[(&str, &str)]| {
+            let env = env
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            BuilderService::expected_compose_hash(name, compose, &env, HashAlgorithm::Sha256)
+        };
+        let compose = "services:\n  agent:\n    image: registry.platform.network/agent\n";
+        let hash = hash_of("demo", compose, &[("MODE", "full")]);
+        assert_eq!(hash.len(), 64);
+
+        // The name, compose file and variable names are part of the manifest, not the values
+        assert_eq!(hash_of("demo", compose, &[("MODE", "lite")]), hash);
+        assert_ne!(hash_of("other", compose, &[("MODE", "full")]), hash);
+        assert_ne!(hash_of("demo", compose, &[]), hash);
+        assert_ne!(hash_of("demo", "services: {}\n", &[("MODE", "full")]), hash);
+    }
+
+    #[test]
+    fn test_resource_requirements_bounded_by_config() {
+        let service = BuilderService::new(&BuilderConfig::default(), None).unwrap();
+        let check = |resources: ResourceRequirements| match service.check_resources(&resources) {
+            Ok(()) => Ok(()),
+            Err(ChallengeError::InvalidResources(reason)) => Err(reason),
+            Err(e) => panic!("unexpected error: {}", e),
+        };
+        let max = BuilderConfig::default().max_resources;
+
+        assert_eq!(check(ResourceRequirements::default()), Ok(()));
+        assert_eq!(check(max.clone()), Ok(()));
+        assert_eq!(
+            check(ResourceRequirements {
+                gpu_count: Some(0),
+                ..Default::default()
+            }),
+            Ok(())
+        );
+
+        let above = |resources: ResourceRequirements| check(resources).unwrap_err();
+        assert_eq!(
+            above(ResourceRequirements {
+                vcpu: Some(65),
+                ..Default::default()
+            }),
+            "vcpu cannot exceed 64"
+        );
+        assert_eq!(
+            above(ResourceRequirements {
+                memory_gb: Some(513),
+                ..Default::default()
+            }),
+            "memory_gb cannot exceed 512"
+        );
+        assert_eq!(
+            above(ResourceRequirements {
+                gpu_count: Some(9),
+                ..Default::default()
+            }),
+            "gpu_count cannot exceed 8"
+        );
+        assert_eq!(
+            above(ResourceRequirements {
+                disk_gb: Some(2049),
+                ..Default::default()
+            }),
+            "disk_gb cannot exceed 2048"
+        );
+        assert_eq!(
+            above(ResourceRequirements {
+                vcpu: Some(0),
+                ..Default::default()
+            }),
+            "vcpu must be greater than zero"
+        );
+        assert_eq!(
+            above(ResourceRequirements {
+                gpu_count: Some(0),
+                gpu_type: Some("h100".to_string()),
+                ..Default::default()
+            }),
+            "gpu_type requires at least one GPU"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_compose_hash_drift_recomputed() {
+        let (service, pool) = service().await;
+        let name = format!("hash-test-{}", Uuid::new_v4());
+
+        let created = service
+            .create_challenge(request(&name, "hash"))
+            .await
+            .unwrap();
+        let stored_hash = |pool: Arc<PgPool>| async move {
+            sqlx::query_scalar::<_, String>("SELECT compose_hash FROM challenges WHERE id = $1")
+                .bind(created.id)
+                .fetch_one(pool.as_ref())
+                .await
+                .unwrap()
+        };
+        let compose_yaml: String =
+            sqlx::query_scalar("SELECT compose_yaml FROM challenges WHERE id = $1")
+                .bind(created.id)
+                .fetch_one(pool.as_ref())
+                .await
+                .unwrap();
+        let expected = BuilderService::expected_compose_hash(
+            &name,
+            &compose_yaml,
+            &BTreeMap::new(),
+            HashAlgorithm::default(),
+        );
+        assert_eq!(stored_hash(pool.clone()).await, expected);
+        let drift = service.recompute_compose_hash(created.id).await.unwrap();
+        assert!(!drift.drifted);
+
+        // A hand-edited hash is reported and replaced
+        sqlx::query("UPDATE challenges SET compose_hash = 'edited' WHERE id = $1")
+            .bind(created.id)
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        let drift = service.recompute_compose_hash(created.id).await.unwrap();
+        assert!(drift.drifted);
+        assert_eq!(drift.stored_hash, "edited");
+        assert_eq!(drift.expected_hash, expected);
+        assert_eq!(stored_hash(pool.clone()).await, expected);
+
+        // A new environment changes the manifest, hence the hash
+        let mut changes = update(None);
+        let mut harness_config = HarnessConfig::default();
+        harness_config
+            .environment
+            .insert("MODE".to_string(), "full".to_string());
+        changes.harness_config = Some(harness_config.clone());
+        service.update_challenge(created.id, changes).await.unwrap();
+        assert_eq!(
+            stored_hash(pool.clone()).await,
+            BuilderService::expected_compose_hash(
+                &name,
+                &compose_yaml,
+                &harness_config.environment,
+                HashAlgorithm::default(),
+            )
+        );
+
+        service.delete_challenge(created.id).await.unwrap();
+        assert!(matches!(
+            error_of(service.recompute_compose_hash(created.id).await),
+            PlatformError::NotFound { .. }
+        ));
+    }
 }
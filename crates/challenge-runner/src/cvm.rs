@@ -1,3 +1,63 @@
 //! Challenge Virtual Machine management
+//!
+//! The lifecycle of a challenge reaches its CVM through a [`ChallengeDriver`]:
+//! the platform drives challenges against the dstack VMM with it, and tests
+//! against mocks.
 
-// Placeholder - will be populated with CVM management code
+use platform_api_models::ChallengeSpec;
+use serde::{Deserialize, Serialize};
+
+/// CVM provisioned for a challenge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvisionedCvm {
+    pub instance_id: String,
+    pub ip_address: String,
+    /// Port of the challenge's HTTP API, serving its migrations
+    pub api_port: u16,
+    /// Port the challenge SDK accepts WebSocket connections on
+    pub sdk_port: u16,
+}
+
+/// Failure of a step of a challenge's lifecycle
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message}")]
+pub struct StepError {
+    pub message: String,
+    /// Whether the step may succeed if attempted again, e.g. after a timeout
+    pub transient: bool,
+}
+
+impl StepError {
+    /// Failure worth retrying
+    pub fn transient(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            transient: true,
+        }
+    }
+
+    /// Failure that attempting again would not fix
+    pub fn permanent(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            transient: false,
+        }
+    }
+}
+
+/// Provisions, attests and stops the CVMs of challenges
+#[async_trait::async_trait]
+pub trait ChallengeDriver: Send + Sync {
+    /// Provision the CVM of `challenge` and wait until it is ready
+    async fn provision(&self, challenge: &ChallengeSpec) -> Result<ProvisionedCvm, StepError>;
+
+    /// Verify the attestation of `challenge` running in `cvm`
+    async fn attest(
+        &self,
+        challenge: &ChallengeSpec,
+        cvm: &ProvisionedCvm,
+    ) -> Result<(), StepError>;
+
+    /// Stop `cvm`
+    async fn stop(&self, cvm: &ProvisionedCvm) -> Result<(), StepError>;
+}
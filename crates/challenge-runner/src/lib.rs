@@ -1,9 +1,11 @@
 //! Challenge runner for executing and managing challenges
 //!
 //! This crate provides functionality for running challenges, managing
-//! challenge virtual machines (CVMs), and handling migrations.
+//! challenge virtual machines (CVMs), driving challenges through their
+//! auto-start lifecycle, and handling migrations.
 
 pub mod cvm;
+pub mod lifecycle;
 pub mod migrations;
 pub mod runner;
 
@@ -0,0 +1,637 @@
+//! Auto-start lifecycle of challenges
+//!
+//! A registered challenge is started by driving it through
+//! `Registered → Provisioning → Attesting → Active`: its CVM is provisioned,
+//! then the challenge running in it is attested. Transient failures of a step
+//! are retried with exponential backoff. A challenge whose step fails
+//! permanently, or still fails once its attempts run out, is stopped, and a
+//! CVM it was provisioned is stopped too. Challenges can also be stopped at
+//! any time, and started again once stopped.
+
+use crate::cvm::{ChallengeDriver, ProvisionedCvm, StepError};
+use chrono::{DateTime, Utc};
+use platform_api_models::ChallengeSpec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// State of a challenge in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeState {
+    /// Known to the platform, not started yet
+    Registered,
+    /// Its CVM is being provisioned
+    Provisioning,
+    /// Its CVM is up and the challenge is being attested
+    Attesting,
+    /// Attested and serving
+    Active,
+    /// Stopped, or failed to start
+    Stopped,
+}
+
+impl ChallengeState {
+    /// Whether a challenge can go from this state to `next`
+    ///
+    /// Challenges go through the states in order, can be stopped from any
+    /// other state, and registered again once stopped.
+    pub fn can_transition_to(self, next: ChallengeState) -> bool {
+        use ChallengeState::*;
+        matches!(
+            (self, next),
+            (Registered, Provisioning)
+                | (Provisioning, Attesting)
+                | (Attesting, Active)
+                | (Stopped, Registered)
+        ) || (next == Stopped && self != Stopped)
+    }
+}
+
+/// Transition refused by [`ChallengeState::can_transition_to`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("challenge cannot go from {from:?} to {to:?}")]
+pub struct InvalidTransition {
+    pub from: ChallengeState,
+    pub to: ChallengeState,
+}
+
+/// Where a challenge is in its lifecycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeLifecycle {
+    pub challenge_id: Uuid,
+    pub compose_hash: String,
+    pub state: ChallengeState,
+    /// Attempt at the step of the current state, from 1
+    pub attempt: u32,
+    /// Error of the last failed attempt, kept once stopped
+    pub last_error: Option<String>,
+    /// CVM of the challenge, once provisioned
+    pub cvm: Option<ProvisionedCvm>,
+    /// When the challenge entered its current state
+    pub entered_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ChallengeLifecycle {
+    /// Lifecycle of `challenge`, registered now
+    pub fn new(challenge: &ChallengeSpec) -> Self {
+        let now = Utc::now();
+        Self {
+            challenge_id: challenge.id,
+            compose_hash: challenge.compose_hash.clone(),
+            state: ChallengeState::Registered,
+            attempt: 1,
+            last_error: None,
+            cvm: None,
+            entered_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Move the challenge to `next`, with a first attempt at its step
+    pub fn transition(&mut self, next: ChallengeState) -> Result<(), InvalidTransition> {
+        if !self.state.can_transition_to(next) {
+            return Err(InvalidTransition {
+                from: self.state,
+                to: next,
+            });
+        }
+        let now = Utc::now();
+        self.state = next;
+        self.attempt = 1;
+        self.entered_at = now;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Record the failure of the current attempt before the next one
+    fn retry_after(&mut self, error: &StepError) {
+        self.attempt += 1;
+        self.last_error = Some(error.message.clone());
+        self.updated_at = Utc::now();
+    }
+}
+
+/// Retries of the transient failures of a lifecycle step
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts at a step, the first one included
+    pub max_attempts: u32,
+    /// Wait before the second attempt, doubled before each next one
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait after failed attempt `attempt`, counted from 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Lifecycle of a challenge and the task driving it
+struct Tracked {
+    lifecycle: Arc<watch::Sender<ChallengeLifecycle>>,
+    task: JoinHandle<()>,
+}
+
+/// Drives challenges through their lifecycle with a [`ChallengeDriver`]
+pub struct LifecycleOrchestrator {
+    driver: Arc<dyn ChallengeDriver>,
+    retry: RetryPolicy,
+    challenges: RwLock<HashMap<Uuid, Tracked>>,
+}
+
+impl LifecycleOrchestrator {
+    pub fn new(driver: Arc<dyn ChallengeDriver>, retry: RetryPolicy) -> Self {
+        Self {
+            driver,
+            retry,
+            challenges: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Register `challenge` and start driving it to `Active` in the
+    /// background, unless it is already on its way or active
+    ///
+    /// Returns a receiver of the challenge's lifecycle as it changes.
+    pub async fn start(&self, challenge: ChallengeSpec) -> watch::Receiver<ChallengeLifecycle> {
+        let mut challenges = self.challenges.write().await;
+        if let Some(tracked) = challenges.get(&challenge.id) {
+            if tracked.lifecycle.borrow().state != ChallengeState::Stopped {
+                return tracked.lifecycle.subscribe();
+            }
+        }
+
+        info!(
+            challenge_id = %challenge.id,
+            compose_hash = %challenge.compose_hash,
+            "Starting challenge"
+        );
+        let (lifecycle, receiver) = watch::channel(ChallengeLifecycle::new(&challenge));
+        let lifecycle = Arc::new(lifecycle);
+        let task = tokio::spawn(drive(
+            self.driver.clone(),
+            self.retry.clone(),
+            challenge.clone(),
+            lifecycle.clone(),
+        ));
+        challenges.insert(challenge.id, Tracked { lifecycle, task });
+        receiver
+    }
+
+    /// Lifecycle of challenge `challenge_id`, if it was started
+    pub async fn state(&self, challenge_id: Uuid) -> Option<ChallengeLifecycle> {
+        let challenges = self.challenges.read().await;
+        let lifecycle = challenges.get(&challenge_id)?.lifecycle.borrow().clone();
+        Some(lifecycle)
+    }
+
+    /// Stop challenge `challenge_id`, wherever it is in its lifecycle, and
+    /// its CVM if it was provisioned one
+    ///
+    /// Returns the lifecycle of the stopped challenge; `None` if it was never
+    /// started.
+    pub async fn stop(&self, challenge_id: Uuid) -> Option<ChallengeLifecycle> {
+        self.stop_matching(challenge_id, None).await
+    }
+
+    /// Stop challenge `challenge_id` as [`Self::stop`] does, if it was started
+    /// with `compose_hash`
+    ///
+    /// A newer version of the challenge, started since, is left running and
+    /// `None` returned.
+    pub async fn stop_version(
+        &self,
+        challenge_id: Uuid,
+        compose_hash: &str,
+    ) -> Option<ChallengeLifecycle> {
+        self.stop_matching(challenge_id, Some(compose_hash)).await
+    }
+
+    async fn stop_matching(
+        &self,
+        challenge_id: Uuid,
+        compose_hash: Option<&str>,
+    ) -> Option<ChallengeLifecycle> {
+        let challenges = self.challenges.read().await;
+        let tracked = challenges.get(&challenge_id)?;
+        if compose_hash.is_some_and(|hash| tracked.lifecycle.borrow().compose_hash != hash) {
+            return None;
+        }
+        tracked.task.abort();
+
+        let mut cvm = None;
+        tracked.lifecycle.send_if_modified(|lifecycle| {
+            if lifecycle.transition(ChallengeState::Stopped).is_err() {
+                return false;
+            }
+            cvm = lifecycle.cvm.clone();
+            true
+        });
+        if let Some(cvm) = cvm {
+            stop_cvm(self.driver.as_ref(), challenge_id, &cvm).await;
+        }
+        info!(challenge_id = %challenge_id, "Challenge stopped");
+
+        let lifecycle = tracked.lifecycle.borrow().clone();
+        Some(lifecycle)
+    }
+}
+
+/// Drive `challenge` from `Registered` to `Active`, stopping it if a step fails
+async fn drive(
+    driver: Arc<dyn ChallengeDriver>,
+    retry: RetryPolicy,
+    challenge: ChallengeSpec,
+    lifecycle: Arc<watch::Sender<ChallengeLifecycle>>,
+) {
+    let driver = driver.as_ref();
+    let challenge = &challenge;
+
+    advance(&lifecycle, ChallengeState::Provisioning);
+    let Some(cvm) = attempt(&retry, &lifecycle, move || driver.provision(challenge)).await else {
+        return;
+    };
+    lifecycle.send_modify(|lifecycle| lifecycle.cvm = Some(cvm.clone()));
+
+    advance(&lifecycle, ChallengeState::Attesting);
+    let cvm = &cvm;
+    if attempt(&retry, &lifecycle, move || driver.attest(challenge, cvm))
+        .await
+        .is_none()
+    {
+        stop_cvm(driver, challenge.id, cvm).await;
+        return;
+    }
+
+    advance(&lifecycle, ChallengeState::Active);
+    info!(challenge_id = %challenge.id, "Challenge active");
+}
+
+/// Move the challenge of `lifecycle` to `next`
+fn advance(lifecycle: &watch::Sender<ChallengeLifecycle>, next: ChallengeState) {
+    lifecycle.send_modify(|lifecycle| {
+        if let Err(e) = lifecycle.transition(next) {
+            // Only the driving task moves challenges forward
+            error!(challenge_id = %lifecycle.challenge_id, "{}", e);
+        }
+    });
+}
+
+/// Run `step` until it succeeds, retrying transient failures as `retry` allows
+///
+/// Stops the challenge and returns `None` once the step failed for good.
+async fn attempt<T, F, Fut>(
+    retry: &RetryPolicy,
+    lifecycle: &watch::Sender<ChallengeLifecycle>,
+    mut step: F,
+) -> Option<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, StepError>>,
+{
+    loop {
+        let error = match step().await {
+            Ok(output) => return Some(output),
+            Err(error) => error,
+        };
+        let (challenge_id, state, attempt) = {
+            let lifecycle = lifecycle.borrow();
+            (lifecycle.challenge_id, lifecycle.state, lifecycle.attempt)
+        };
+
+        if error.transient && attempt < retry.max_attempts {
+            let backoff = retry.backoff(attempt);
+            warn!(
+                challenge_id = %challenge_id,
+                state = ?state,
+                attempt,
+                "Challenge step failed, retrying in {:?}: {}",
+                backoff,
+                error
+            );
+            lifecycle.send_modify(|lifecycle| lifecycle.retry_after(&error));
+            tokio::time::sleep(backoff).await;
+            continue;
+        }
+
+        error!(
+            challenge_id = %challenge_id,
+            state = ?state,
+            attempt,
+            "Challenge step failed, stopping challenge: {}",
+            error
+        );
+        lifecycle.send_modify(|lifecycle| {
+            lifecycle.last_error = Some(error.message.clone());
+            let _ = lifecycle.transition(ChallengeState::Stopped);
+        });
+        return None;
+    }
+}
+
+async fn stop_cvm(driver: &dyn ChallengeDriver, challenge_id: Uuid, cvm: &ProvisionedCvm) {
+    if let Err(e) = driver.stop(cvm).await {
+        warn!(
+            challenge_id = %challenge_id,
+            instance_id = %cvm.instance_id,
+            "Failed to stop challenge CVM: {}",
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platform_api_models::ChallengeResources;
+    use std::collections::{BTreeMap, VecDeque};
+    use std::sync::Mutex;
+
+    fn challenge() -> ChallengeSpec {
+        let now = Utc::now();
+        ChallengeSpec {
+            id: Uuid::new_v4(),
+            name: "term-challenge".to_string(),
+            compose_hash: "hash-a".to_string(),
+            compose_yaml: String::new(),
+            version: "1.0.0".to_string(),
+            images: vec![],
+            resources: ChallengeResources {
+                vcpu: 2,
+                memory: "4G".to_string(),
+                disk: None,
+            },
+            ports: vec![],
+            env: BTreeMap::new(),
+            emission_share: 0.0,
+            mechanism_id: 0,
+            weight: None,
+            description: None,
+            mermaid_chart: None,
+            github_repo: None,
+            dstack_image: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn cvm() -> ProvisionedCvm {
+        ProvisionedCvm {
+            instance_id: "cvm-1".to_string(),
+            ip_address: "10.0.0.2".to_string(),
+            api_port: 8000,
+            sdk_port: 8080,
+        }
+    }
+
+    /// Driver answering each step with the outcomes queued for it, then
+    /// succeeding; provisioning waits while `hold` is set
+    #[derive(Default)]
+    struct MockDriver {
+        provisions: Mutex<VecDeque<StepError>>,
+        attestations: Mutex<VecDeque<StepError>>,
+        hold: watch::Sender<bool>,
+        stopped: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ChallengeDriver for MockDriver {
+        async fn provision(&self, _: &ChallengeSpec) -> Result<ProvisionedCvm, StepError> {
+            let _ = self.hold.subscribe().wait_for(|hold| !hold).await;
+            match self.provisions.lock().unwrap().pop_front() {
+                Some(error) => Err(error),
+                None => Ok(cvm()),
+            }
+        }
+
+        async fn attest(&self, _: &ChallengeSpec, _: &ProvisionedCvm) -> Result<(), StepError> {
+            match self.attestations.lock().unwrap().pop_front() {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        }
+
+        async fn stop(&self, cvm: &ProvisionedCvm) -> Result<(), StepError> {
+            self.stopped.lock().unwrap().push(cvm.instance_id.clone());
+            Ok(())
+        }
+    }
+
+    fn new_orchestrator(driver: &Arc<MockDriver>) -> LifecycleOrchestrator {
+        LifecycleOrchestrator::new(
+            driver.clone(),
+            RetryPolicy {
+                max_attempts: 3,
+                initial_backoff: Duration::ZERO,
+                max_backoff: Duration::ZERO,
+            },
+        )
+    }
+
+    async fn settled(mut lifecycle: watch::Receiver<ChallengeLifecycle>) -> ChallengeLifecycle {
+        lifecycle
+            .wait_for(|l| matches!(l.state, ChallengeState::Active | ChallengeState::Stopped))
+            .await
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn test_transitions() {
+        use ChallengeState::*;
+        let states = [Registered, Provisioning, Attesting, Active, Stopped];
+        let allowed: Vec<(ChallengeState, ChallengeState)> = states
+            .iter()
+            .flat_map(|from| states.iter().map(move |to| (*from, *to)))
+            .filter(|(from, to)| from.can_transition_to(*to))
+            .collect();
+        assert_eq!(
+            allowed,
+            vec![
+                (Registered, Provisioning),
+                (Registered, Stopped),
+                (Provisioning, Attesting),
+                (Provisioning, Stopped),
+                (Attesting, Active),
+                (Attesting, Stopped),
+                (Active, Stopped),
+                (Stopped, Registered),
+            ]
+        );
+
+        let mut lifecycle = ChallengeLifecycle::new(&challenge());
+        assert_eq!(
+            lifecycle.transition(Active),
+            Err(InvalidTransition {
+                from: Registered,
+                to: Active
+            })
+        );
+        assert_eq!(lifecycle.state, Registered);
+        lifecycle.transition(Provisioning).unwrap();
+        assert_eq!(lifecycle.state, Provisioning);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let retry = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(10),
+        };
+        let backoffs: Vec<u64> = (1..=5).map(|n| retry.backoff(n).as_secs()).collect();
+        assert_eq!(backoffs, vec![2, 4, 8, 10, 10]);
+        assert_eq!(retry.backoff(u32::MAX), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_retried() {
+        let driver = Arc::new(MockDriver::default());
+        driver.provisions.lock().unwrap().extend([
+            StepError::transient("VMM unavailable"),
+            StepError::transient("VMM unavailable"),
+        ]);
+        driver
+            .attestations
+            .lock()
+            .unwrap()
+            .push_back(StepError::transient("connection refused"));
+        let orchestrator = new_orchestrator(&driver);
+        let challenge = challenge();
+
+        let active = settled(orchestrator.start(challenge.clone()).await).await;
+        assert_eq!(active.state, ChallengeState::Active);
+        assert_eq!(active.cvm, Some(cvm()));
+        assert_eq!(active.last_error.as_deref(), Some("connection refused"));
+        assert!(driver.stopped.lock().unwrap().is_empty());
+
+        // Started once
+        let again = orchestrator.start(challenge.clone()).await;
+        assert_eq!(again.borrow().state, ChallengeState::Active);
+        assert_eq!(
+            orchestrator.state(challenge.id).await.unwrap().entered_at,
+            active.entered_at
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_steps_stop_challenge() {
+        // Out of attempts while provisioning
+        let driver = Arc::new(MockDriver::default());
+        driver
+            .provisions
+            .lock()
+            .unwrap()
+            .extend((0..3).map(|_| StepError::transient("VMM unavailable")));
+        let orchestrator = new_orchestrator(&driver);
+        let stopped = settled(orchestrator.start(challenge()).await).await;
+        assert_eq!(stopped.state, ChallengeState::Stopped);
+        assert_eq!(stopped.cvm, None);
+        assert_eq!(stopped.last_error.as_deref(), Some("VMM unavailable"));
+
+        // Refused attestation, not retried; the CVM is stopped
+        let driver = Arc::new(MockDriver::default());
+        driver.attestations.lock().unwrap().extend([
+            StepError::permanent("compose hash mismatch"),
+            StepError::transient("unreachable"),
+        ]);
+        let orchestrator = new_orchestrator(&driver);
+        let challenge = challenge();
+        let stopped = settled(orchestrator.start(challenge.clone()).await).await;
+        assert_eq!(stopped.state, ChallengeState::Stopped);
+        assert_eq!(stopped.attempt, 1);
+        assert_eq!(stopped.last_error.as_deref(), Some("compose hash mismatch"));
+        assert_eq!(*driver.stopped.lock().unwrap(), vec!["cvm-1".to_string()]);
+
+        // Stopped challenges start again
+        let restarted = settled(orchestrator.start(challenge).await).await;
+        assert_eq!(restarted.state, ChallengeState::Active);
+        assert_eq!(restarted.last_error.as_deref(), Some("unreachable"));
+    }
+
+    #[tokio::test]
+    async fn test_stop() {
+        let driver = Arc::new(MockDriver::default());
+        let orchestrator = new_orchestrator(&driver);
+        let challenge = challenge();
+        assert!(orchestrator.stop(challenge.id).await.is_none());
+
+        // While provisioning
+        driver.hold.send_replace(true);
+        let mut lifecycle = orchestrator.start(challenge.clone()).await;
+        lifecycle
+            .wait_for(|l| l.state == ChallengeState::Provisioning)
+            .await
+            .unwrap();
+        let stopped = orchestrator.stop(challenge.id).await.unwrap();
+        assert_eq!(stopped.state, ChallengeState::Stopped);
+        assert!(driver.stopped.lock().unwrap().is_empty());
+
+        // Once active
+        driver.hold.send_replace(false);
+        let active = settled(orchestrator.start(challenge.clone()).await).await;
+        assert_eq!(active.state, ChallengeState::Active);
+        let stopped = orchestrator.stop(challenge.id).await.unwrap();
+        assert_eq!(stopped.state, ChallengeState::Stopped);
+        assert_eq!(*driver.stopped.lock().unwrap(), vec!["cvm-1".to_string()]);
+        assert_eq!(
+            orchestrator.state(challenge.id).await.unwrap().state,
+            ChallengeState::Stopped
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stop_version() {
+        let driver = Arc::new(MockDriver::default());
+        let orchestrator = new_orchestrator(&driver);
+        let challenge = challenge();
+        let old_hash = challenge.compose_hash.clone();
+        settled(orchestrator.start(challenge.clone()).await).await;
+
+        // The challenge, stopped and started again under a new compose hash
+        orchestrator.stop(challenge.id).await.unwrap();
+        let upgraded = ChallengeSpec {
+            compose_hash: "hash-new".to_string(),
+            ..challenge.clone()
+        };
+        let active = settled(orchestrator.start(upgraded).await).await;
+        assert_eq!(active.state, ChallengeState::Active);
+
+        // Stopping the old version leaves the new one running
+        assert!(orchestrator
+            .stop_version(challenge.id, &old_hash)
+            .await
+            .is_none());
+        let running = orchestrator.state(challenge.id).await.unwrap();
+        assert_eq!(running.state, ChallengeState::Active);
+        assert_eq!(running.compose_hash, "hash-new");
+
+        let stopped = orchestrator
+            .stop_version(challenge.id, "hash-new")
+            .await
+            .unwrap();
+        assert_eq!(stopped.state, ChallengeState::Stopped);
+    }
+}
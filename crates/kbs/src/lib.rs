@@ -1,5 +1,5 @@
 use anyhow::Result;
-use platform_api_models::{KeyReleaseRequest, KeyReleaseResponse};
+use platform_api_models::{KeyReleaseRequest, KeyReleaseResponse, SessionToken};
 use ring::aead::BoundKey;
 use ring::rand::{SecureRandom, SystemRandom};
 use serde::{Deserialize, Serialize};
@@ -12,7 +12,7 @@ use tokio::sync::RwLock;
 pub struct KeyBrokerService {
     config: KbsConfig,
     random: SystemRandom,
-    sessions: Arc<RwLock<HashMap<String, SessionInfo>>>,
+    sessions: Arc<RwLock<HashMap<SessionToken, SessionInfo>>>,
 }
 
 struct SessionInfo {
@@ -123,7 +123,7 @@ impl Default for KbsConfig {
 #[derive(Debug, Deserialize)]
 pub struct VerifyKeyRequest {
     pub key_id: String,
-    pub session_token: String,
+    pub session_token: SessionToken,
     pub policy: String,
 }
 
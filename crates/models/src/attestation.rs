@@ -39,6 +39,16 @@ pub struct AttestationResponse {
     pub verified_measurements: Vec<Measurement>,
     pub policy: Policy,
     pub error: Option<String>,
+    /// Token exchanged for a new `session_token` on `POST /attestation/refresh`
+    /// until it expires; empty when the attestation failed
+    #[serde(default)]
+    pub refresh_token: String,
+}
+
+/// Request for a new session token, see `AttestationResponse::refresh_token`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshSessionRequest {
+    pub refresh_token: String,
 }
 
 /// Key release request
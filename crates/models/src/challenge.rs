@@ -1,10 +1,11 @@
-use super::{Digest, Hotkey, Id};
+use super::{Digest, Hotkey, Id, JobPriority, Score};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Challenge visibility level
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub enum ChallengeVisibility {
+    #[default]
     Public,
     Private,
 }
@@ -12,12 +13,36 @@ pub enum ChallengeVisibility {
 /// Challenge status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ChallengeStatus {
+    /// Not yet run by validators, e.g. a challenge just cloned from another
     Draft,
     Active,
     Paused,
     Archived,
 }
 
+impl ChallengeStatus {
+    /// Status as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChallengeStatus::Draft => "draft",
+            ChallengeStatus::Active => "active",
+            ChallengeStatus::Paused => "paused",
+            ChallengeStatus::Archived => "archived",
+        }
+    }
+}
+
+impl From<&str> for ChallengeStatus {
+    fn from(s: &str) -> Self {
+        match s {
+            "draft" => ChallengeStatus::Draft,
+            "paused" => ChallengeStatus::Paused,
+            "archived" => ChallengeStatus::Archived,
+            _ => ChallengeStatus::Active,
+        }
+    }
+}
+
 /// Challenge metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChallengeMetadata {
@@ -27,10 +52,269 @@ pub struct ChallengeMetadata {
     pub version: String,
     pub visibility: ChallengeVisibility,
     pub status: ChallengeStatus,
-    pub owner: Hotkey,
+    pub owner: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub tags: Vec<String>,
+    /// Priority of the challenge's jobs submitted without one
+    #[serde(default)]
+    pub default_job_priority: JobPriority,
+    /// JSON Schema the challenge's job payloads must match; any payload is accepted without one
+    #[serde(default)]
+    pub job_payload_schema: Option<serde_json::Value>,
+    /// Parameters of the challenge's jobs submitted without them
+    #[serde(default)]
+    pub job_defaults: JobDefaults,
+    /// What the challenge's evaluation jobs need from the validators running them
+    #[serde(default)]
+    pub resources: ResourceRequirements,
+    /// Range the scores of the challenge's job results must fall in
+    #[serde(default)]
+    pub score_bounds: ScoreBounds,
+    /// What the challenge's job results must report, checked once their
+    /// scores are within `score_bounds`
+    #[serde(default)]
+    pub score_policy: ScorePolicy,
+    /// URL answering GET requests once the challenge's container responds,
+    /// see [`HealthCheckResult`]
+    #[serde(default)]
+    pub healthcheck_url: Option<String>,
+    /// Seconds a health check waits for `healthcheck_url` to answer
+    #[serde(default = "default_healthcheck_timeout_secs")]
+    pub healthcheck_timeout_secs: u32,
+}
+
+/// Seconds a health check waits for an answer when the challenge sets no timeout
+pub const DEFAULT_HEALTHCHECK_TIMEOUT_SECS: u32 = 10;
+
+fn default_healthcheck_timeout_secs() -> u32 {
+    DEFAULT_HEALTHCHECK_TIMEOUT_SECS
+}
+
+/// Defaults of a challenge for the parameters its jobs are submitted without
+///
+/// The default priority is `ChallengeMetadata::default_job_priority`. Unset
+/// fields fall back to the scheduler's own defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobDefaults {
+    pub runtime: Option<RuntimeType>,
+    /// Timeout in seconds
+    pub timeout: Option<u64>,
+    pub max_retries: Option<u32>,
+    /// Seconds a failed job waits before it is retried
+    pub retry_delay: Option<u64>,
+}
+
+/// Resources a challenge's evaluation jobs need from the validator running them
+///
+/// Unset fields are not required. Validators declare what they have when
+/// registering, see [`ValidatorRegistration`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceRequirements {
+    pub vcpu: Option<u32>,
+    pub memory_gb: Option<u64>,
+    pub gpu_count: Option<u32>,
+    /// GPU model, e.g. `h100`, compared case-insensitively
+    pub gpu_type: Option<String>,
+    pub disk_gb: Option<u64>,
+}
+
+impl ResourceRequirements {
+    /// Whether nothing is required
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// GPUs required; a GPU type without a count requires one
+    pub fn gpus(&self) -> u32 {
+        match (self.gpu_count, &self.gpu_type) {
+            (Some(count), _) => count,
+            (None, Some(_)) => 1,
+            (None, None) => 0,
+        }
+    }
+
+    /// Capabilities a validator must claim the jobs with: `gpu` when GPUs are
+    /// required, and `gpu:{type}` for a GPU type
+    pub fn capabilities(&self) -> Vec<String> {
+        let mut capabilities = vec![];
+        if self.gpus() > 0 {
+            capabilities.push("gpu".to_string());
+        }
+        if let Some(gpu_type) = &self.gpu_type {
+            capabilities.push(format!("gpu:{}", gpu_type.to_lowercase()));
+        }
+        capabilities
+    }
+
+    /// Whether `validator` declared at least these resources
+    pub fn satisfied_by(&self, validator: &ValidatorRegistration) -> bool {
+        let gpus = validator.gpus();
+        self.vcpu.is_none_or(|vcpu| validator.cpu_cores >= vcpu)
+            && self
+                .memory_gb
+                .is_none_or(|memory| validator.memory_gb >= memory)
+            && gpus >= self.gpus()
+            && self.gpu_type.as_ref().is_none_or(|gpu_type| {
+                validator
+                    .gpu_type
+                    .as_ref()
+                    .is_some_and(|declared| declared.eq_ignore_ascii_case(gpu_type))
+            })
+            && self
+                .disk_gb
+                .is_none_or(|disk| validator.disk_gb.is_some_and(|declared| declared >= disk))
+    }
+}
+
+/// Range the scores of a challenge's job results must fall in, see `EvalResult::scores`
+///
+/// Unset bounds are open. Results with scores outside the range are handled
+/// as `out_of_range` says.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreBounds {
+    pub min: Option<Score>,
+    pub max: Option<Score>,
+    pub out_of_range: OutOfRangeScores,
+}
+
+impl ScoreBounds {
+    /// Whether `score` is within the bounds
+    pub fn contains(&self, score: Score) -> bool {
+        self.min.is_none_or(|min| score >= min) && self.max.is_none_or(|max| score <= max)
+    }
+
+    /// `score` brought within the bounds
+    pub fn clamp(&self, score: Score) -> Score {
+        let score = self.min.map_or(score, |min| score.max(min));
+        self.max.map_or(score, |max| score.min(max))
+    }
+}
+
+/// What happens to a job result with scores outside its challenge's bounds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum OutOfRangeScores {
+    /// The result is refused and the job left as it was
+    #[default]
+    Reject,
+    /// The scores are clamped to the bounds; their raw values are recorded
+    /// in the job's completion event
+    Clamp,
+}
+
+/// Largest metrics map a job result may have unless its challenge's policy
+/// sets another
+pub const DEFAULT_MAX_METRICS: usize = 256;
+
+/// Inclusive range of a score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreRange {
+    pub min: Score,
+    pub max: Score,
+}
+
+impl Default for ScoreRange {
+    /// `0.0..=1.0`
+    fn default() -> Self {
+        Self { min: 0.0, max: 1.0 }
+    }
+}
+
+impl ScoreRange {
+    /// Whether `score` is within the range, never for `NaN`
+    pub fn contains(&self, score: Score) -> bool {
+        score >= self.min && score <= self.max
+    }
+}
+
+/// What the job results of a challenge must report, see `EvalResult::validate`
+///
+/// Checked after the scores were brought within the challenge's
+/// [`ScoreBounds`]; results breaking the policy are refused.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScorePolicy {
+    /// Range of the scores without one in `ranges`; `null` leaves them
+    /// unbounded
+    pub default_range: Option<ScoreRange>,
+    /// Range of each score, by key
+    pub ranges: BTreeMap<String, ScoreRange>,
+    /// Whether scores and metrics must be finite, refusing `NaN` and infinities
+    pub require_finite: bool,
+    /// Keys of the scores every result must report
+    pub required_scores: Vec<String>,
+    /// Largest number of metrics a result may report
+    pub max_metrics: usize,
+}
+
+impl Default for ScorePolicy {
+    fn default() -> Self {
+        Self {
+            default_range: Some(ScoreRange::default()),
+            ranges: BTreeMap::new(),
+            require_finite: true,
+            required_scores: vec![],
+            max_metrics: DEFAULT_MAX_METRICS,
+        }
+    }
+}
+
+impl ScorePolicy {
+    /// Range of the score `key`, if it is bounded
+    pub fn range_of(&self, key: &str) -> Option<ScoreRange> {
+        self.ranges.get(key).copied().or(self.default_range)
+    }
+}
+
+/// How a job result breaks the [`ScorePolicy`] of its challenge
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ScoreViolation {
+    #[error("required score is missing")]
+    MissingScore { key: String },
+
+    #[error("score {value} is not finite")]
+    NonFiniteScore { key: String, value: Score },
+
+    #[error("score {value} is outside {}..={}", .range.min, .range.max)]
+    ScoreOutOfRange {
+        key: String,
+        value: Score,
+        range: ScoreRange,
+    },
+
+    #[error("metric {value} is not finite")]
+    NonFiniteMetric { key: String, value: f64 },
+
+    #[error("{count} metrics reported, at most {max} allowed")]
+    TooManyMetrics { count: usize, max: usize },
+}
+
+impl ScoreViolation {
+    /// Path of the offending entry in the result, e.g. `scores/accuracy`
+    pub fn field(&self) -> String {
+        match self {
+            ScoreViolation::MissingScore { key }
+            | ScoreViolation::NonFiniteScore { key, .. }
+            | ScoreViolation::ScoreOutOfRange { key, .. } => format!("scores/{}", key),
+            ScoreViolation::NonFiniteMetric { key, .. } => format!("metrics/{}", key),
+            ScoreViolation::TooManyMetrics { .. } => "metrics".to_string(),
+        }
+    }
+
+    /// Rule of the policy that was broken
+    pub fn rule(&self) -> &'static str {
+        match self {
+            ScoreViolation::MissingScore { .. } => "required_scores",
+            ScoreViolation::NonFiniteScore { .. } | ScoreViolation::NonFiniteMetric { .. } => {
+                "require_finite"
+            }
+            ScoreViolation::ScoreOutOfRange { .. } => "ranges",
+            ScoreViolation::TooManyMetrics { .. } => "max_metrics",
+        }
+    }
 }
 
 /// Harness configuration
@@ -59,7 +343,7 @@ impl Default for HarnessConfig {
 }
 
 /// Runtime type for execution
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum RuntimeType {
     Standard,
     Docker,
@@ -93,7 +377,7 @@ impl std::fmt::Display for RuntimeType {
 }
 
 /// Resource limits for execution
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ResourceLimits {
     pub cpu_cores: u32,
     pub memory_mb: u64,
@@ -102,14 +386,246 @@ pub struct ResourceLimits {
 }
 
 /// Challenge creation request
+///
+/// Also carries the overrides of a cloned challenge, where fields left at
+/// their default keep the value of the source challenge.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateChallengeRequest {
     pub name: String,
     pub description: String,
+    #[serde(default)]
     pub visibility: ChallengeVisibility,
+    #[serde(default)]
     pub github_repo: Option<String>,
+    #[serde(default)]
     pub harness_config: HarnessConfig,
+    #[serde(default)]
     pub dataset_urls: Vec<String>,
+    /// Resources the challenge's jobs require; a clone without them keeps the source's
+    #[serde(default)]
+    pub resources: Option<ResourceRequirements>,
+    /// URL checked once the challenge is created; a clone without one keeps the source's
+    #[serde(default)]
+    pub healthcheck_url: Option<String>,
+    /// Timeout of health checks in seconds, [`DEFAULT_HEALTHCHECK_TIMEOUT_SECS`] when unset
+    #[serde(default)]
+    pub healthcheck_timeout_secs: Option<u32>,
+}
+
+/// Challenge creation response
+///
+/// The challenge is built in the background; progress can be followed on
+/// `GET /builds/:build_id/logs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateChallengeResponse {
+    pub build_id: Id,
+    pub challenge_id: Id,
+}
+
+/// Request to import a challenge from the manifest in a GitHub repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportChallengeRequest {
+    /// GitHub repository `owner/name`
+    pub repo: String,
+    /// Branch, tag or commit; the default branch when unset
+    #[serde(rename = "ref", default)]
+    pub git_ref: Option<String>,
+    /// Directory of the manifest in the repository; the root when unset
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// Source an imported challenge was defined in, kept for audit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChallengeProvenance {
+    pub repo_url: String,
+    /// Commit the manifest and compose file were read at
+    pub commit_sha: String,
+    pub manifest_path: String,
+    pub imported_at: DateTime<Utc>,
+}
+
+/// Challenge created or updated from a GitHub repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportChallengeResponse {
+    pub challenge: ChallengeMetadata,
+    pub provenance: ChallengeProvenance,
+    /// False when the manifest named an existing challenge, which was updated
+    pub created: bool,
+}
+
+/// Severity of a build log event
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildLogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+/// Structured progress event emitted while a challenge is built
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildLogEvent {
+    pub build_id: Id,
+    pub timestamp: DateTime<Utc>,
+    pub level: BuildLogLevel,
+    pub message: String,
+    /// Set on the last event of a build
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// Source of a challenge image build
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageBuildSource {
+    /// GitHub repository `owner/name` at a branch, tag or commit
+    Github {
+        repo: String,
+        #[serde(rename = "ref")]
+        git_ref: String,
+    },
+    /// Build context tarball uploaded at an HTTPS URL
+    Artifact { url: String },
+}
+
+/// Status of a challenge image build
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageBuildStatus {
+    Queued,
+    Building,
+    Pushed,
+    Failed,
+    /// Removed from the queue before it started
+    Cancelled,
+}
+
+impl ImageBuildStatus {
+    /// Status as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageBuildStatus::Queued => "queued",
+            ImageBuildStatus::Building => "building",
+            ImageBuildStatus::Pushed => "pushed",
+            ImageBuildStatus::Failed => "failed",
+            ImageBuildStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the build is over, pushed, failed or cancelled
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            ImageBuildStatus::Pushed | ImageBuildStatus::Failed | ImageBuildStatus::Cancelled
+        )
+    }
+}
+
+impl From<&str> for ImageBuildStatus {
+    fn from(s: &str) -> Self {
+        match s {
+            "building" => ImageBuildStatus::Building,
+            "pushed" => ImageBuildStatus::Pushed,
+            "failed" => ImageBuildStatus::Failed,
+            "cancelled" => ImageBuildStatus::Cancelled,
+            _ => ImageBuildStatus::Queued,
+        }
+    }
+}
+
+/// Whether a build reused an image from the build cache
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildCacheResult {
+    /// The source was built before and its image was reused
+    Hit,
+    /// The source was built and its image added to the cache
+    Miss,
+}
+
+impl BuildCacheResult {
+    /// Result as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BuildCacheResult::Hit => "hit",
+            BuildCacheResult::Miss => "miss",
+        }
+    }
+}
+
+impl From<&str> for BuildCacheResult {
+    fn from(s: &str) -> Self {
+        match s {
+            "hit" => BuildCacheResult::Hit,
+            _ => BuildCacheResult::Miss,
+        }
+    }
+}
+
+/// Docker image build of a challenge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageBuild {
+    pub id: Id,
+    pub challenge_id: Id,
+    pub source: ImageBuildSource,
+    pub status: ImageBuildStatus,
+    /// Reference the image is pushed to, or the cached image reused
+    pub image: String,
+    /// Digest of the built source: the commit of a GitHub ref, or the SHA-256
+    /// of an artifact
+    #[serde(default)]
+    pub source_digest: Option<String>,
+    /// Whether the image came from the build cache, unset until the source
+    /// digest is known
+    #[serde(default)]
+    pub cache: Option<BuildCacheResult>,
+    /// Why the build failed
+    pub error: Option<String>,
+    /// Last lines of the build output
+    pub log_tail: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Place of a queued build in the order builds start, 1 being next
+    #[serde(default)]
+    pub queue_position: Option<usize>,
+    /// Seconds a queued build is expected to wait before it starts, from the
+    /// duration of recent builds
+    #[serde(default)]
+    pub estimated_wait_secs: Option<u64>,
+}
+
+/// Request to build the image of a challenge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateImageBuildRequest {
+    pub source: ImageBuildSource,
+}
+
+/// Stored compose hash of a challenge compared with the one recomputed from it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComposeHashDrift {
+    pub challenge_id: Uuid,
+    /// Hash the challenge was stored with
+    pub stored_hash: String,
+    /// Hash recomputed from the challenge's name, compose file and environment
+    pub expected_hash: String,
+    /// Whether the hashes differ, in which case the expected one was stored
+    pub drifted: bool,
+}
+
+/// Outcome of a GET request to the health check URL of a challenge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheckResult {
+    /// Whether the challenge answered with a 2xx status in time
+    pub healthy: bool,
+    /// Status of the answer, unset when there was none
+    pub status_code: Option<u16>,
+    /// Time until the answer or the failure
+    pub latency_ms: u64,
+    /// Why the check failed: no answer in time, a refused connection, or an
+    /// error status
+    pub error: Option<String>,
 }
 
 /// Challenge update request
@@ -119,6 +635,26 @@ pub struct UpdateChallengeRequest {
     pub description: Option<String>,
     pub status: Option<ChallengeStatus>,
     pub harness_config: Option<HarnessConfig>,
+    #[serde(default)]
+    pub default_job_priority: Option<JobPriority>,
+    #[serde(default)]
+    pub job_payload_schema: Option<serde_json::Value>,
+    /// Replaces all the job defaults of the challenge
+    #[serde(default)]
+    pub job_defaults: Option<JobDefaults>,
+    /// Replaces all the resource requirements of the challenge
+    #[serde(default)]
+    pub resources: Option<ResourceRequirements>,
+    /// Replaces the score bounds of the challenge
+    #[serde(default)]
+    pub score_bounds: Option<ScoreBounds>,
+    /// Replaces the score policy of the challenge
+    #[serde(default)]
+    pub score_policy: Option<ScorePolicy>,
+    #[serde(default)]
+    pub healthcheck_url: Option<String>,
+    #[serde(default)]
+    pub healthcheck_timeout_secs: Option<u32>,
 }
 
 /// Challenge list response
@@ -135,6 +671,24 @@ pub struct ChallengeListResponse {
 pub struct ChallengeDetailResponse {
     pub metadata: ChallengeMetadata,
     pub emissions: Option<super::emissions::EmissionSchedule>,
+    /// Live aggregates, only present when requested with `include_stats=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ChallengeLiveStats>,
+}
+
+/// Live job counts of a challenge
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ChallengeJobCounts {
+    pub pending: u64,
+    pub running: u64,
+    pub completed: u64,
+}
+
+/// Live aggregates shown on the challenge detail view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeLiveStats {
+    pub jobs: ChallengeJobCounts,
+    pub latest_compose_hash: String,
 }
 
 /// Emissions schedule for challenges (deprecated, use emissions::EmissionSchedule)
@@ -214,6 +768,42 @@ pub enum ValidatorChallengeState {
     Recycling, // Challenge is being recycled
 }
 
+impl ValidatorChallengeState {
+    /// Summarize the states reported by validators into one challenge-wide state
+    ///
+    /// A challenge is Active if any validator serves it, Provisioning while one is
+    /// still bringing it up, Failed if every report failed, and Inactive otherwise.
+    pub fn aggregate<'a>(states: impl IntoIterator<Item = &'a ValidatorChallengeState>) -> Self {
+        let mut reported = false;
+        let mut provisioning = false;
+        let mut all_failed = true;
+
+        for state in states {
+            reported = true;
+            match state {
+                ValidatorChallengeState::Active => return ValidatorChallengeState::Active,
+                ValidatorChallengeState::Failed => {}
+                ValidatorChallengeState::Inactive => all_failed = false,
+                ValidatorChallengeState::Provisioning
+                | ValidatorChallengeState::Created
+                | ValidatorChallengeState::Probing
+                | ValidatorChallengeState::Recycling => {
+                    provisioning = true;
+                    all_failed = false;
+                }
+            }
+        }
+
+        if provisioning {
+            ValidatorChallengeState::Provisioning
+        } else if reported && all_failed {
+            ValidatorChallengeState::Failed
+        } else {
+            ValidatorChallengeState::Inactive
+        }
+    }
+}
+
 /// Validator challenge status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidatorChallengeStatus {
@@ -224,6 +814,61 @@ pub struct ValidatorChallengeStatus {
     pub penalty_reason: Option<String>,
 }
 
+/// Capabilities a validator declares when registering
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValidatorRegistration {
+    pub hotkey: Hotkey,
+    pub runtimes: Vec<RuntimeType>,
+    pub gpu: bool,
+    pub memory_gb: u64,
+    pub cpu_cores: u32,
+    /// Challenges the validator would rather serve, a routing hint
+    pub preferred_challenges: Vec<Uuid>,
+    // Fields below are left out of the signed JSON when unset, so that
+    // registrations signed before they existed still verify
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub gpu_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_gb: Option<u64>,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+impl ValidatorRegistration {
+    /// GPUs declared; a GPU without a count is one
+    pub fn gpus(&self) -> u32 {
+        match self.gpu_count {
+            0 if self.gpu => 1,
+            count => count,
+        }
+    }
+
+    /// Bytes the hotkey signs to register at unix time `timestamp` with
+    /// `nonce`: `"{timestamp}:{nonce}:"` followed by the registration as JSON
+    pub fn signing_payload(&self, timestamp: u64, nonce: &str) -> Vec<u8> {
+        let mut payload = format!("{}:{}:", timestamp, nonce).into_bytes();
+        // Serializing a plain struct cannot fail
+        payload.extend(serde_json::to_vec(self).unwrap_or_default());
+        payload
+    }
+}
+
+/// Validator registration signed by its hotkey
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterValidatorRequest {
+    pub registration: ValidatorRegistration,
+    /// Unix time of the signature, in seconds
+    pub timestamp: u64,
+    /// Random string, accepted once per hotkey within the signature max age
+    pub nonce: String,
+    /// Hex sr25519 signature of [`ValidatorRegistration::signing_payload`]
+    pub signature: String,
+}
+
 /// Penalty reason enum
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum PenaltyReason {
@@ -256,3 +901,60 @@ pub struct ChallengeEmission {
     pub owner_hotkey: String,
     pub created_at: DateTime<Utc>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_requirements_satisfied_by_registration() {
+        let mut registration = ValidatorRegistration {
+            hotkey: Hotkey::from_public_key(&[1; 32]),
+            runtimes: vec![RuntimeType::Docker],
+            gpu: true,
+            memory_gb: 64,
+            cpu_cores: 16,
+            preferred_challenges: vec![],
+            gpu_count: 0,
+            gpu_type: None,
+            disk_gb: None,
+        };
+        // Registrations without the newer fields sign the same JSON as before
+        let signed = String::from_utf8(registration.signing_payload(1, "n")).unwrap();
+        assert!(signed.ends_with(r#""preferred_challenges":[]}"#));
+
+        let requirements = |json| serde_json::from_value::<ResourceRequirements>(json).unwrap();
+        assert!(requirements(serde_json::json!({})).satisfied_by(&registration));
+        assert!(
+            requirements(serde_json::json!({"vcpu": 16, "memory_gb": 64, "gpu_count": 1}))
+                .satisfied_by(&registration)
+        );
+        assert!(!requirements(serde_json::json!({"vcpu": 32})).satisfied_by(&registration));
+        assert!(!requirements(serde_json::json!({"gpu_count": 2})).satisfied_by(&registration));
+        assert!(!requirements(serde_json::json!({"disk_gb": 100})).satisfied_by(&registration));
+
+        let h100 = requirements(serde_json::json!({"gpu_type": "H100"}));
+        assert_eq!(h100.capabilities(), vec!["gpu", "gpu:h100"]);
+        assert!(!h100.satisfied_by(&registration));
+        registration.gpu_type = Some("h100".to_string());
+        assert!(h100.satisfied_by(&registration));
+    }
+
+    #[test]
+    fn test_score_bounds() {
+        let unbounded = ScoreBounds::default();
+        assert!(unbounded.contains(-1e9) && unbounded.contains(1e9));
+        assert_eq!(unbounded.clamp(1e9), 1e9);
+
+        let bounds: ScoreBounds = serde_json::from_value(
+            serde_json::json!({"min": 0.0, "max": 1.0, "out_of_range": "Clamp"}),
+        )
+        .unwrap();
+        assert_eq!(bounds.out_of_range, OutOfRangeScores::Clamp);
+        assert!(bounds.contains(0.0) && bounds.contains(1.0));
+        assert!(!bounds.contains(-0.1) && !bounds.contains(1.1) && !bounds.contains(f64::NAN));
+        assert_eq!(bounds.clamp(-0.1), 0.0);
+        assert_eq!(bounds.clamp(1e9), 1.0);
+        assert_eq!(bounds.clamp(0.5), 0.5);
+    }
+}
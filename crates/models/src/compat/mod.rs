@@ -0,0 +1,214 @@
+//! Compatibility of the job payloads exchanged with validators
+//!
+//! Validators and the platform are deployed at different times, so a
+//! [`JobMetadata`] or [`EvalResult`] may be read by an older or a newer
+//! version than the one that wrote it. Both carry a `schema_version`, and
+//! changes to them follow these rules:
+//!
+//! - Fields are only added, with `#[serde(default)]`, so that payloads
+//!   written before a field existed still deserialize. Unknown fields are
+//!   ignored, never refused with `deny_unknown_fields`, so that payloads
+//!   written by newer versions deserialize too.
+//! - A change that defaults cannot cover, e.g. a renamed field or a value
+//!   whose meaning changed, bumps the schema version and registers an
+//!   [`Upgrade`] from the previous version in [`JOB_METADATA_UPGRADES`] or
+//!   [`EVAL_RESULT_UPGRADES`]. Payloads are upgraded one version at a time
+//!   before they are deserialized; those of newer versions are read as they
+//!   are.
+//! - Every version has a fixture in `compat/fixtures`, a payload as that
+//!   version wrote it, registered in [`JOB_METADATA_FIXTURES`] or
+//!   [`EVAL_RESULT_FIXTURES`]. Fixtures are frozen: they are never edited and
+//!   must keep deserializing forever.
+//!
+//! Version 1 is the format from before versioning, without a `schema_version`.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::{EvalResult, JobMetadata};
+
+/// Schema version of the [`JobMetadata`] written by this version
+pub const JOB_METADATA_SCHEMA_VERSION: u32 = 2;
+
+/// Schema version of the [`EvalResult`] written by this version
+pub const EVAL_RESULT_SCHEMA_VERSION: u32 = 2;
+
+/// Upgrade of the fields of a payload to the next schema version
+pub type Upgrade = fn(&mut Map<String, Value>);
+
+/// Upgrades of [`JobMetadata`] payloads, from version `i + 1` at index `i`
+pub const JOB_METADATA_UPGRADES: &[Upgrade] = &[
+    // 1 to 2: only the version is added
+    |_| {},
+];
+
+/// Upgrades of [`EvalResult`] payloads, from version `i + 1` at index `i`
+pub const EVAL_RESULT_UPGRADES: &[Upgrade] = &[
+    // 1 to 2: only the version is added
+    |_| {},
+];
+
+/// [`JobMetadata`] payloads of each schema version, by version
+pub const JOB_METADATA_FIXTURES: &[(u32, &str)] = &[
+    (1, include_str!("fixtures/job_metadata_v1.json")),
+    (2, include_str!("fixtures/job_metadata_v2.json")),
+];
+
+/// [`EvalResult`] payloads of each schema version, by version
+pub const EVAL_RESULT_FIXTURES: &[(u32, &str)] = &[
+    (1, include_str!("fixtures/eval_result_v1.json")),
+    (2, include_str!("fixtures/eval_result_v2.json")),
+];
+
+/// Upgrade `value`, a payload of a type at schema version `current`, to that
+/// version with `upgrades`
+///
+/// Values other than objects are left for deserialization to refuse.
+fn upgrade(mut value: Value, current: u32, upgrades: &[Upgrade]) -> Result<Value, String> {
+    let Value::Object(fields) = &mut value else {
+        return Ok(value);
+    };
+    let version = match fields.get("schema_version") {
+        None | Some(Value::Null) => 1,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|&version| version >= 1)
+            .ok_or_else(|| format!("invalid schema_version: {}", version))?,
+    };
+    if version >= current {
+        return Ok(value);
+    }
+    for upgrade in &upgrades[version as usize - 1..] {
+        upgrade(fields);
+    }
+    fields.insert("schema_version".to_string(), current.into());
+    Ok(value)
+}
+
+/// Implements `Serialize` and `Deserialize` for a type deriving them with
+/// `#[serde(remote = "Self")]`, upgrading payloads before deserializing them
+macro_rules! versioned {
+    ($name:ident, $current:expr, $upgrades:expr) => {
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                $name::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = upgrade(Value::deserialize(deserializer)?, $current, $upgrades)
+                    .map_err(D::Error::custom)?;
+                $name::deserialize(value).map_err(D::Error::custom)
+            }
+        }
+    };
+}
+
+versioned!(
+    JobMetadata,
+    JOB_METADATA_SCHEMA_VERSION,
+    JOB_METADATA_UPGRADES
+);
+versioned!(EvalResult, EVAL_RESULT_SCHEMA_VERSION, EVAL_RESULT_UPGRADES);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+
+    /// Check every version has an upgrade and a fixture, and every fixture
+    /// deserializes at the current version and survives a round trip
+    fn check_fixtures<T: Serialize + DeserializeOwned>(
+        current: u32,
+        upgrades: &[Upgrade],
+        fixtures: &[(u32, &str)],
+    ) -> Vec<T> {
+        assert_eq!(upgrades.len(), current as usize - 1);
+        let versions: Vec<u32> = fixtures.iter().map(|(version, _)| *version).collect();
+        assert_eq!(versions, (1..=current).collect::<Vec<_>>());
+
+        fixtures
+            .iter()
+            .map(|(version, fixture)| {
+                let parsed: T = serde_json::from_str(fixture)
+                    .unwrap_or_else(|e| panic!("fixture of version {}: {}", version, e));
+                let value = serde_json::to_value(&parsed).unwrap();
+                assert_eq!(value["schema_version"], current);
+                let reparsed: T = serde_json::from_value(value.clone()).unwrap();
+                assert_eq!(serde_json::to_value(&reparsed).unwrap(), value);
+                parsed
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_job_metadata_fixtures() {
+        let jobs: Vec<JobMetadata> = check_fixtures(
+            JOB_METADATA_SCHEMA_VERSION,
+            JOB_METADATA_UPGRADES,
+            JOB_METADATA_FIXTURES,
+        );
+
+        let v1 = &jobs[0];
+        assert_eq!(v1.retry_count, 0);
+        assert_eq!(v1.version, 0);
+        assert!(v1.depends_on.is_empty() && v1.required_capabilities.is_empty());
+        assert!(v1.resources.is_empty());
+        let v2 = &jobs[1];
+        assert_eq!(v2.version, 4);
+        assert_eq!(v2.resources.gpus(), 1);
+    }
+
+    #[test]
+    fn test_eval_result_fixtures() {
+        let results: Vec<EvalResult> = check_fixtures(
+            EVAL_RESULT_SCHEMA_VERSION,
+            EVAL_RESULT_UPGRADES,
+            EVAL_RESULT_FIXTURES,
+        );
+
+        assert_eq!(results[0].scores["accuracy"], 0.92);
+        assert_eq!(
+            results[1].attestation_receipt.as_deref(),
+            Some("receipt-0123")
+        );
+    }
+
+    #[test]
+    fn test_newer_versions_readable() {
+        let (_, fixture) = JOB_METADATA_FIXTURES.last().unwrap();
+        let mut value: Value = serde_json::from_str(fixture).unwrap();
+        value["schema_version"] = (JOB_METADATA_SCHEMA_VERSION + 1).into();
+        value["field_of_a_newer_version"] = "ignored".into();
+        let job: JobMetadata = serde_json::from_value(value).unwrap();
+        assert_eq!(job.schema_version, JOB_METADATA_SCHEMA_VERSION + 1);
+
+        let (_, fixture) = EVAL_RESULT_FIXTURES.last().unwrap();
+        let mut value: Value = serde_json::from_str(fixture).unwrap();
+        value["schema_version"] = (EVAL_RESULT_SCHEMA_VERSION + 1).into();
+        value["error"] = Value::Null;
+        value.as_object_mut().unwrap().remove("attestation_receipt");
+        let result: EvalResult = serde_json::from_value(value).unwrap();
+        assert_eq!(result.error, None);
+        assert_eq!(result.attestation_receipt, None);
+    }
+
+    #[test]
+    fn test_invalid_schema_version_refused() {
+        let (_, fixture) = EVAL_RESULT_FIXTURES[0];
+        let mut value: Value = serde_json::from_str(fixture).unwrap();
+        for version in [Value::from(0), Value::from("2"), Value::from(-1)] {
+            value["schema_version"] = version;
+            let err = serde_json::from_value::<EvalResult>(value.clone()).unwrap_err();
+            assert!(
+                err.to_string().contains("invalid schema_version"),
+                "{}",
+                err
+            );
+        }
+        assert!(serde_json::from_str::<EvalResult>("[]").is_err());
+    }
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 /// Subnet configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubnetConfig {
-    pub owner_hotkey: Hotkey,
+    pub owner_hotkey: String,
     pub rake: f64,
     pub validator_set_hints: Vec<ValidatorHint>,
     pub timing_windows: TimingWindows,
@@ -79,7 +79,7 @@ pub struct EmissionSchedule {
 /// Configuration update request
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateConfigRequest {
-    pub owner_hotkey: Option<Hotkey>,
+    pub owner_hotkey: Option<String>,
     pub rake: Option<f64>,
     pub validator_set_hints: Option<Vec<ValidatorHint>>,
     pub timing_windows: Option<TimingWindows>,
@@ -132,7 +132,7 @@ pub struct ConfigChangeLog {
     pub change_type: ConfigChangeType,
     pub old_value: Option<serde_json::Value>,
     pub new_value: serde_json::Value,
-    pub changed_by: Hotkey,
+    pub changed_by: String,
     pub timestamp: DateTime<Utc>,
     pub reason: Option<String>,
 }
@@ -154,7 +154,7 @@ pub struct ConfigBackup {
     pub id: uuid::Uuid,
     pub config: SubnetConfig,
     pub created_at: DateTime<Utc>,
-    pub created_by: Hotkey,
+    pub created_by: String,
     pub version: u32,
     pub checksum: String,
 }
@@ -1,4 +1,4 @@
-use super::{Hotkey, Id};
+use super::{Hotkey, Id, Score};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -23,7 +23,24 @@ pub enum EmissionStatus {
     Cancelled,
 }
 
+/// Seconds between blocks of the chain
+pub const BLOCK_TIME_SECS: u64 = 12;
+
+/// Blocks of an emission epoch unless configured otherwise, one subnet tempo
+pub const DEFAULT_EPOCH_BLOCKS: u64 = 360;
+
+/// Units an amount of emission is split into, RAO for amounts in TAO
+pub const UNITS_PER_EMISSION: u64 = 1_000_000_000;
+
+fn default_epoch_secs() -> u64 {
+    DEFAULT_EPOCH_BLOCKS * BLOCK_TIME_SECS
+}
+
 /// Emission schedule
+///
+/// `emission_rate` is the amount emitted per epoch of `epoch_secs`, epochs
+/// being counted from `start_time`; see [`Self::allocation_at`] for how the
+/// distribution curve changes it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmissionSchedule {
     pub id: Id,
@@ -36,6 +53,9 @@ pub struct EmissionSchedule {
     pub distributed_amount: f64,
     pub status: EmissionStatus,
     pub distribution_curve: DistributionCurve,
+    /// Length of the epochs `emission_rate` is given for
+    #[serde(default = "default_epoch_secs")]
+    pub epoch_secs: u64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -53,12 +73,167 @@ impl Default for EmissionSchedule {
             distributed_amount: 0.0,
             status: EmissionStatus::Scheduled,
             distribution_curve: DistributionCurve::Linear,
+            epoch_secs: default_epoch_secs(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     }
 }
 
+impl EmissionSchedule {
+    /// Allocation of the epoch containing `at`, if the schedule emits then
+    ///
+    /// Nothing is allocated before `start_time`, from `end_time` on, and by
+    /// schedules that are paused, cancelled or completed. The amount of
+    /// epoch `n`, counted from 0, depends on the distribution curve:
+    ///
+    /// - `Linear`: `emission_rate`
+    /// - `Exponential`: `emission_rate * decay_factor^n`
+    /// - `Step`: the rate of the last interval effective at the start of the
+    ///   epoch, so an interval taking effect during an epoch applies from the
+    ///   next one; `emission_rate` before the first
+    /// - `Custom`: the rate at `n` of the line through the `(epoch, rate)`
+    ///   points, held at the first and last rates outside them;
+    ///   `emission_rate` without points
+    pub fn allocation_at(&self, at: DateTime<Utc>) -> Option<EmissionAllocation> {
+        if matches!(
+            self.status,
+            EmissionStatus::Paused | EmissionStatus::Cancelled | EmissionStatus::Completed
+        ) || at < self.start_time
+            || self.end_time.is_some_and(|end| at >= end)
+        {
+            return None;
+        }
+        let epoch_secs = self.epoch_secs.max(1);
+        let epoch = (at - self.start_time).num_seconds() as u64 / epoch_secs;
+        Some(self.allocation_of(epoch))
+    }
+
+    /// Allocations of the `epochs` epochs from the one containing `from`, or
+    /// from the first one if the schedule has not started yet
+    ///
+    /// Stops at the end of the schedule. Projections follow the distribution
+    /// curve and are not limited to what remains of `total_amount`.
+    pub fn project_from(&self, from: DateTime<Utc>, epochs: u32) -> Vec<EmissionAllocation> {
+        let Some(first) = self.allocation_at(from.max(self.start_time)) else {
+            return vec![];
+        };
+        (first.epoch..first.epoch + epochs as u64)
+            .map(|epoch| self.allocation_of(epoch))
+            .take_while(|allocation| self.end_time.is_none_or(|end| allocation.starts_at < end))
+            .collect()
+    }
+
+    /// Allocations of the `epochs` epochs from the current one, see
+    /// [`Self::project_from`]
+    pub fn project(&self, epochs: u32) -> Vec<EmissionAllocation> {
+        self.project_from(Utc::now(), epochs)
+    }
+
+    fn allocation_of(&self, epoch: u64) -> EmissionAllocation {
+        let epoch_secs = self.epoch_secs.max(1);
+        let starts_at = self.start_time + chrono::Duration::seconds((epoch * epoch_secs) as i64);
+        let rate = match &self.distribution_curve {
+            DistributionCurve::Linear => self.emission_rate,
+            DistributionCurve::Exponential { decay_factor } => {
+                self.emission_rate * decay_factor.powf(epoch as f64)
+            }
+            DistributionCurve::Step { intervals } => intervals
+                .iter()
+                .filter(|(effective_from, _)| *effective_from <= starts_at)
+                .max_by_key(|(effective_from, _)| *effective_from)
+                .map_or(self.emission_rate, |(_, rate)| *rate),
+            DistributionCurve::Custom { points } => {
+                interpolate(points, epoch as f64).unwrap_or(self.emission_rate)
+            }
+        };
+        EmissionAllocation {
+            epoch,
+            starts_at,
+            // Saturates: negative and NaN rates allocate nothing
+            amount: (rate * UNITS_PER_EMISSION as f64).round() as u64,
+        }
+    }
+}
+
+/// Value at `x` of the line through `points`, held constant outside them
+fn interpolate(points: &[(f64, f64)], x: f64) -> Option<f64> {
+    let mut points: Vec<(f64, f64)> = points.to_vec();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let (first, last) = (*points.first()?, *points.last()?);
+    if x <= first.0 {
+        return Some(first.1);
+    }
+    if x >= last.0 {
+        return Some(last.1);
+    }
+    let segment = points.windows(2).find(|pair| x <= pair[1].0)?;
+    let ((x0, y0), (x1, y1)) = (segment[0], segment[1]);
+    Some(y0 + (y1 - y0) * (x - x0) / (x1 - x0))
+}
+
+/// Emission of one epoch of an [`EmissionSchedule`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmissionAllocation {
+    /// Epoch number, counted from 0 at the start of the schedule
+    pub epoch: u64,
+    pub starts_at: DateTime<Utc>,
+    /// Amount emitted in the epoch, in units of [`UNITS_PER_EMISSION`]
+    pub amount: u64,
+}
+
+impl EmissionAllocation {
+    /// Split the amount pro rata of `scores`
+    ///
+    /// Hotkeys with a score that is not positive and finite get nothing and
+    /// are left out; without any such score, nothing is split. Otherwise the
+    /// parts always sum to the amount: each hotkey gets its share rounded
+    /// down, and the units left are given one each to the largest remainders,
+    /// ties going to the hotkeys that sort first.
+    pub fn split_between(&self, scores: &BTreeMap<Hotkey, Score>) -> BTreeMap<Hotkey, u64> {
+        split_units(self.amount, scores)
+    }
+}
+
+/// Split `amount` units pro rata of `scores`, see
+/// [`EmissionAllocation::split_between`]
+pub fn split_units(amount: u64, scores: &BTreeMap<Hotkey, Score>) -> BTreeMap<Hotkey, u64> {
+    let scores: Vec<(&Hotkey, f64)> = scores
+        .iter()
+        .map(|(hotkey, score)| (hotkey, *score))
+        .filter(|(_, score)| score.is_finite() && *score > 0.0)
+        .collect();
+    let Some(max) = scores.iter().map(|(_, score)| *score).reduce(f64::max) else {
+        return BTreeMap::new();
+    };
+
+    // Weights as integers so the split is exact: fractions of the highest
+    // score with 53 bits of precision, the precision of the scores
+    let weights: Vec<(&Hotkey, u128)> = scores
+        .into_iter()
+        .map(|(hotkey, score)| (hotkey, (score / max * (1u64 << 53) as f64) as u128))
+        .collect();
+    let total: u128 = weights.iter().map(|(_, weight)| weight).sum();
+
+    let mut parts: Vec<(&Hotkey, u64, u128)> = weights
+        .into_iter()
+        .map(|(hotkey, weight)| {
+            let share = amount as u128 * weight;
+            (hotkey, (share / total) as u64, share % total)
+        })
+        .collect();
+    let left = amount - parts.iter().map(|(_, part, _)| part).sum::<u64>();
+    // Stable, so equal remainders keep the order of the hotkeys
+    parts.sort_by_key(|(_, _, remainder)| std::cmp::Reverse(*remainder));
+    for (_, part, _) in parts.iter_mut().take(left as usize) {
+        *part += 1;
+    }
+    parts
+        .into_iter()
+        .map(|(hotkey, part, _)| (hotkey.clone(), part))
+        .collect()
+}
+
 /// Distribution curve for emissions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DistributionCurve {
@@ -78,7 +253,7 @@ pub enum DistributionCurve {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmissionDistribution {
     pub schedule_id: Id,
-    pub recipient_hotkey: Hotkey,
+    pub recipient_hotkey: String,
     pub amount: f64,
     pub percentage: f64,
     pub distributed_at: DateTime<Utc>,
@@ -86,6 +261,19 @@ pub struct EmissionDistribution {
     pub receipt: String,
 }
 
+/// Emission epoch, a block range over which emissions are accounted
+///
+/// Epochs are numbered from 1; `end_block` is exclusive and is the
+/// `start_block` of the next epoch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmissionEpoch {
+    pub epoch_number: u64,
+    pub start_block: u64,
+    pub end_block: u64,
+    pub total_emissions: f64,
+    pub distributed_at: Option<DateTime<Utc>>,
+}
+
 /// Emission aggregate
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EmissionAggregate {
@@ -118,7 +306,7 @@ pub struct ChallengeEmissionMetrics {
 /// Validator emission metrics
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidatorEmissionMetrics {
-    pub validator_hotkey: Hotkey,
+    pub validator_hotkey: String,
     pub total_emission: f64,
     pub distributed_emission: f64,
     pub pending_emission: f64,
@@ -133,7 +321,7 @@ pub struct ValidatorEmissionMetrics {
 /// Miner emission metrics
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MinerEmissionMetrics {
-    pub miner_hotkey: Hotkey,
+    pub miner_hotkey: String,
     pub total_emission: f64,
     pub distributed_emission: f64,
     pub pending_emission: f64,
@@ -210,7 +398,7 @@ pub struct DistributeEmissionRequest {
 /// Emission recipient
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EmissionRecipient {
-    pub hotkey: Hotkey,
+    pub hotkey: String,
     pub amount: f64,
     pub percentage: f64,
     pub reason: String,
@@ -223,7 +411,7 @@ pub struct EmissionAuditLog {
     pub schedule_id: Id,
     pub event_type: EmissionEventType,
     pub amount: f64,
-    pub recipient_hotkey: Option<Hotkey>,
+    pub recipient_hotkey: Option<String>,
     pub timestamp: DateTime<Utc>,
     pub details: BTreeMap<String, String>,
     pub receipt: String,
@@ -267,6 +455,31 @@ pub struct SubnetEmissions {
     pub mechanisms: Vec<MechanismEmissionBreakdown>,
 }
 
+/// Hyperparameters of a subnet, as read from the chain
+///
+/// Parameters the chain did not return are unset.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubnetParams {
+    pub netuid: u16,
+    /// Blocks per epoch
+    pub tempo: Option<u64>,
+    /// Blocks newly registered neurons are protected from deregistration
+    pub immunity_period: Option<u64>,
+    /// Minimum number of weights a validator must set
+    pub min_allowed_weights: Option<u64>,
+    /// Largest weight a validator may give a single neuron, out of `u16::MAX`
+    pub max_weights_limit: Option<u64>,
+    /// Maximum number of validators with a validator permit
+    pub max_allowed_validators: Option<u64>,
+    pub kappa: Option<u64>,
+    pub rho: Option<u64>,
+    /// Minimum stake to hold a validator permit, in RAO
+    pub min_stake: Option<u64>,
+    /// Block the parameters were read at
+    pub block: u64,
+    pub fetched_at: DateTime<Utc>,
+}
+
 /// Mechanism emission breakdown
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MechanismEmissionBreakdown {
@@ -306,3 +519,187 @@ pub struct ChallengeEmissions {
     pub challenge_emission_share: f64,
     pub daily_emissions_tao: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const EPOCH: i64 = (DEFAULT_EPOCH_BLOCKS * BLOCK_TIME_SECS) as i64;
+
+    fn schedule(curve: DistributionCurve) -> EmissionSchedule {
+        EmissionSchedule {
+            start_time: Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap(),
+            emission_rate: 2.0,
+            status: EmissionStatus::Active,
+            distribution_curve: curve,
+            ..Default::default()
+        }
+    }
+
+    fn amount_at(schedule: &EmissionSchedule, secs: i64) -> Option<u64> {
+        schedule
+            .allocation_at(schedule.start_time + Duration::seconds(secs))
+            .map(|allocation| allocation.amount)
+    }
+
+    fn hotkey(i: u8) -> Hotkey {
+        Hotkey::from_public_key(&[i; 32])
+    }
+
+    #[test]
+    fn test_allocation_boundaries() {
+        let mut linear = schedule(DistributionCurve::Linear);
+        linear.end_time = Some(linear.start_time + Duration::seconds(3 * EPOCH));
+        assert_eq!(amount_at(&linear, -1), None);
+        assert_eq!(amount_at(&linear, 0), Some(2 * UNITS_PER_EMISSION));
+        let last = linear
+            .allocation_at(linear.start_time + Duration::seconds(3 * EPOCH - 1))
+            .unwrap();
+        assert_eq!(last.epoch, 2);
+        assert_eq!(
+            last.starts_at,
+            linear.start_time + Duration::seconds(2 * EPOCH)
+        );
+        assert_eq!(amount_at(&linear, 3 * EPOCH), None);
+
+        linear.status = EmissionStatus::Paused;
+        assert_eq!(amount_at(&linear, 0), None);
+    }
+
+    #[test]
+    fn test_allocation_curves() {
+        let exponential = schedule(DistributionCurve::Exponential { decay_factor: 0.5 });
+        assert_eq!(amount_at(&exponential, 0), Some(2 * UNITS_PER_EMISSION));
+        assert_eq!(
+            amount_at(&exponential, 2 * EPOCH),
+            Some(UNITS_PER_EMISSION / 2)
+        );
+
+        // A step taking effect during an epoch applies from the next one
+        let start = schedule(DistributionCurve::Linear).start_time;
+        let step = schedule(DistributionCurve::Step {
+            intervals: vec![
+                (start + Duration::seconds(EPOCH), 1.0),
+                (start + Duration::seconds(2 * EPOCH + 1), 3.0),
+            ],
+        });
+        assert_eq!(amount_at(&step, EPOCH - 1), Some(2 * UNITS_PER_EMISSION));
+        assert_eq!(amount_at(&step, EPOCH), Some(UNITS_PER_EMISSION));
+        assert_eq!(amount_at(&step, 2 * EPOCH + 1), Some(UNITS_PER_EMISSION));
+        assert_eq!(amount_at(&step, 3 * EPOCH), Some(3 * UNITS_PER_EMISSION));
+
+        let custom = schedule(DistributionCurve::Custom {
+            points: vec![(4.0, 0.0), (0.0, 1.0)],
+        });
+        assert_eq!(amount_at(&custom, 0), Some(UNITS_PER_EMISSION));
+        assert_eq!(amount_at(&custom, EPOCH), Some(UNITS_PER_EMISSION * 3 / 4));
+        assert_eq!(amount_at(&custom, 10 * EPOCH), Some(0));
+        let empty = schedule(DistributionCurve::Custom { points: vec![] });
+        assert_eq!(amount_at(&empty, 0), Some(2 * UNITS_PER_EMISSION));
+    }
+
+    #[test]
+    fn test_project() {
+        let mut linear = schedule(DistributionCurve::Linear);
+        linear.end_time = Some(linear.start_time + Duration::seconds(5 * EPOCH));
+
+        let before_start = linear.start_time - Duration::days(1);
+        let projection = linear.project_from(before_start, 3);
+        assert_eq!(
+            projection.iter().map(|a| a.epoch).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+
+        let projection =
+            linear.project_from(linear.start_time + Duration::seconds(EPOCH * 3 + 7), 10);
+        assert_eq!(
+            projection.iter().map(|a| a.epoch).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+        assert!(projection
+            .iter()
+            .all(|a| a.amount == 2 * UNITS_PER_EMISSION));
+
+        let ended = linear.start_time + Duration::seconds(EPOCH * 5);
+        assert!(linear.project_from(ended, 10).is_empty());
+    }
+
+    #[test]
+    fn test_split_between() {
+        let allocation = EmissionAllocation {
+            epoch: 0,
+            starts_at: Utc::now(),
+            amount: 10,
+        };
+        let scores = BTreeMap::from([
+            (hotkey(1), 1.0),
+            (hotkey(2), 1.0),
+            (hotkey(3), 1.0),
+            (hotkey(4), 0.0),
+            (hotkey(5), f64::NAN),
+        ]);
+        let parts = allocation.split_between(&scores);
+        // The unit left goes to the hotkey sorting first
+        let first = parts.keys().next().unwrap().clone();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[&first], 4);
+        assert_eq!(parts.values().sum::<u64>(), 10);
+
+        let scores = BTreeMap::from([(hotkey(1), 3.0), (hotkey(2), 1.0)]);
+        let parts = split_units(1_000, &scores);
+        assert_eq!((parts[&hotkey(1)], parts[&hotkey(2)]), (750, 250));
+
+        assert!(split_units(1_000, &BTreeMap::from([(hotkey(1), -1.0)])).is_empty());
+        assert_eq!(split_units(0, &scores).values().sum::<u64>(), 0);
+    }
+
+    /// Splits never lose or create units, whatever the amount and scores
+    #[test]
+    fn test_split_conserves_units() {
+        let mut rng = StdRng::seed_from_u64(1882);
+        for _ in 0..2_000 {
+            let amount = match rng.gen_range(0..3) {
+                0 => rng.gen_range(0..100),
+                1 => rng.gen::<u32>() as u64,
+                _ => rng.gen(),
+            };
+            let scores: BTreeMap<Hotkey, Score> = (0..rng.gen_range(1..40u8))
+                .map(|i| {
+                    let score = match rng.gen_range(0..4) {
+                        0 => rng.gen::<f64>(),
+                        1 => rng.gen::<f64>() * 1e-12,
+                        2 => rng.gen::<f64>() * 1e12,
+                        _ => 1.0,
+                    };
+                    (hotkey(i), score)
+                })
+                .collect();
+
+            let parts = split_units(amount, &scores);
+            assert_eq!(
+                parts.values().sum::<u64>(),
+                amount,
+                "{} {:?}",
+                amount,
+                scores
+            );
+            assert_eq!(parts, split_units(amount, &scores));
+
+            // Parts are within a unit of the exact shares, up to the
+            // precision of the scores
+            let total: f64 = scores.values().sum();
+            for (hotkey, part) in &parts {
+                let exact = amount as f64 * scores[hotkey] / total;
+                let tolerance = 1.0 + amount as f64 * 1e-12;
+                assert!(
+                    (*part as f64 - exact).abs() <= tolerance,
+                    "{} vs {}",
+                    part,
+                    exact
+                );
+            }
+        }
+    }
+}
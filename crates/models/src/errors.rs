@@ -1,161 +1,152 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 use thiserror::Error;
 
+/// A field of a request that failed validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// Path of the field, e.g. `payload/task`
+    pub field: String,
+    pub message: String,
+    /// Rule the field broke, for checks made of named rules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Display) -> Self {
+        Self {
+            field: field.into(),
+            message: message.to_string(),
+            rule: None,
+        }
+    }
+}
+
 /// Platform API errors
+///
+/// Services return these so the API can tell clients what went wrong: each
+/// variant maps to an HTTP status. Errors raised as a `PlatformError` inside
+/// code returning `anyhow::Result` keep their variant when converted back,
+/// everything else is `Internal`.
 #[derive(Error, Debug)]
 pub enum PlatformError {
-    #[error("Challenge not found: {id}")]
-    ChallengeNotFound { id: String },
-
-    #[error("Job not found: {id}")]
-    JobNotFound { id: String },
-
-    #[error("Invalid challenge configuration: {reason}")]
-    InvalidChallengeConfig { reason: String },
-
-    #[error("Invalid job configuration: {reason}")]
-    InvalidJobConfig { reason: String },
+    #[error("{resource} {id} not found")]
+    NotFound { resource: String, id: String },
 
-    #[error("Attestation failed: {reason}")]
-    AttestationFailed { reason: String },
+    /// The resource is not in a state the operation applies to, or changed
+    /// since the caller read it
+    #[error("{reason}")]
+    Conflict { reason: String },
 
-    #[error("Key release failed: {reason}")]
-    KeyReleaseFailed { reason: String },
+    #[error("Validation failed: {}", summary(.fields))]
+    Validation { fields: Vec<FieldError> },
 
-    #[error("Storage error: {reason}")]
-    StorageError { reason: String },
+    /// The request is well-formed but what it carries is refused, e.g. a job
+    /// result breaking the score policy of its challenge
+    #[error("Unprocessable: {}", summary(.fields))]
+    Unprocessable { fields: Vec<FieldError> },
 
-    #[error("Builder error: {reason}")]
-    BuilderError { reason: String },
+    #[error("Unauthorized: {reason}")]
+    Unauthorized { reason: String },
 
-    #[error("Scheduler error: {reason}")]
-    SchedulerError { reason: String },
+    #[error("{reason}")]
+    PayloadTooLarge { reason: String },
 
-    #[error("Configuration error: {reason}")]
-    ConfigError { reason: String },
+    #[error("{reason}")]
+    RateLimited { reason: String },
 
-    #[error("Emission error: {reason}")]
-    EmissionError { reason: String },
+    /// A service the platform depends on failed or timed out
+    #[error("{service} request failed: {reason}")]
+    Upstream { service: String, reason: String },
 
-    #[error("Authentication failed: {reason}")]
-    AuthenticationFailed { reason: String },
-
-    #[error("Authorization failed: {reason}")]
-    AuthorizationFailed { reason: String },
-
-    #[error("Rate limit exceeded: {limit}")]
-    RateLimitExceeded { limit: u32 },
-
-    #[error("Validation error: {field} - {reason}")]
-    ValidationError { field: String, reason: String },
-
-    #[error("Serialization error: {reason}")]
-    SerializationError { reason: String },
-
-    #[error("Network error: {reason}")]
-    NetworkError { reason: String },
-
-    #[error("Database error: {reason}")]
-    DatabaseError { reason: String },
-
-    #[error("Internal server error: {reason}")]
-    InternalError { reason: String },
+    #[error(transparent)]
+    Internal(anyhow::Error),
+}
 
-    #[error("Service unavailable: {service}")]
-    ServiceUnavailable { service: String },
+fn summary(fields: &[FieldError]) -> String {
+    fields
+        .iter()
+        .map(|field| format!("{}: {}", field.field, field.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
 
-    #[error("Timeout error: {operation}")]
-    TimeoutError { operation: String },
+impl PlatformError {
+    pub fn not_found(resource: &str, id: impl Display) -> Self {
+        PlatformError::NotFound {
+            resource: resource.to_string(),
+            id: id.to_string(),
+        }
+    }
 
-    #[error("Resource not found: {resource}")]
-    ResourceNotFound { resource: String },
+    pub fn conflict(reason: impl Display) -> Self {
+        PlatformError::Conflict {
+            reason: reason.to_string(),
+        }
+    }
 
-    #[error("Resource already exists: {resource}")]
-    ResourceAlreadyExists { resource: String },
+    /// Validation failure of a single field
+    pub fn invalid(field: impl Into<String>, message: impl Display) -> Self {
+        PlatformError::Validation {
+            fields: vec![FieldError::new(field, message)],
+        }
+    }
 
-    #[error("Invalid request: {reason}")]
-    InvalidRequest { reason: String },
+    pub fn unauthorized(reason: impl Display) -> Self {
+        PlatformError::Unauthorized {
+            reason: reason.to_string(),
+        }
+    }
 
-    #[error("Unsupported operation: {operation}")]
-    UnsupportedOperation { operation: String },
+    pub fn rate_limited(reason: impl Display) -> Self {
+        PlatformError::RateLimited {
+            reason: reason.to_string(),
+        }
+    }
 
-    #[error("External service error: {service} - {reason}")]
-    ExternalServiceError { service: String, reason: String },
-}
+    pub fn upstream(service: &str, reason: impl Display) -> Self {
+        PlatformError::Upstream {
+            service: service.to_string(),
+            reason: reason.to_string(),
+        }
+    }
 
-impl PlatformError {
     /// Get HTTP status code for the error
     pub fn status_code(&self) -> u16 {
         match self {
-            PlatformError::ChallengeNotFound { .. } => 404,
-            PlatformError::JobNotFound { .. } => 404,
-            PlatformError::ResourceNotFound { .. } => 404,
-            PlatformError::InvalidChallengeConfig { .. } => 400,
-            PlatformError::InvalidJobConfig { .. } => 400,
-            PlatformError::ValidationError { .. } => 400,
-            PlatformError::InvalidRequest { .. } => 400,
-            PlatformError::AuthenticationFailed { .. } => 401,
-            PlatformError::AuthorizationFailed { .. } => 403,
-            PlatformError::ResourceAlreadyExists { .. } => 409,
-            PlatformError::RateLimitExceeded { .. } => 429,
-            PlatformError::TimeoutError { .. } => 408,
-            PlatformError::ServiceUnavailable { .. } => 503,
-            PlatformError::ExternalServiceError { .. } => 502,
-            PlatformError::AttestationFailed { .. } => 422,
-            PlatformError::KeyReleaseFailed { .. } => 422,
-            PlatformError::StorageError { .. } => 500,
-            PlatformError::BuilderError { .. } => 500,
-            PlatformError::SchedulerError { .. } => 500,
-            PlatformError::ConfigError { .. } => 500,
-            PlatformError::EmissionError { .. } => 500,
-            PlatformError::SerializationError { .. } => 500,
-            PlatformError::NetworkError { .. } => 500,
-            PlatformError::DatabaseError { .. } => 500,
-            PlatformError::InternalError { .. } => 500,
-            PlatformError::UnsupportedOperation { .. } => 501,
+            PlatformError::NotFound { .. } => 404,
+            PlatformError::Conflict { .. } => 409,
+            PlatformError::Validation { .. } => 400,
+            PlatformError::Unprocessable { .. } => 422,
+            PlatformError::Unauthorized { .. } => 401,
+            PlatformError::PayloadTooLarge { .. } => 413,
+            PlatformError::RateLimited { .. } => 429,
+            PlatformError::Upstream { .. } => 502,
+            PlatformError::Internal(_) => 500,
         }
     }
 
-    /// Check if error is retryable
+    /// Check if the same request may succeed when retried later
     pub fn is_retryable(&self) -> bool {
         matches!(
             self,
-            PlatformError::NetworkError { .. }
-                | PlatformError::DatabaseError { .. }
-                | PlatformError::TimeoutError { .. }
-                | PlatformError::ServiceUnavailable { .. }
-                | PlatformError::ExternalServiceError { .. }
+            PlatformError::RateLimited { .. } | PlatformError::Upstream { .. }
         )
     }
 
     /// Get error category
     pub fn category(&self) -> &'static str {
         match self {
-            PlatformError::ChallengeNotFound { .. } => "challenge",
-            PlatformError::JobNotFound { .. } => "job",
-            PlatformError::InvalidChallengeConfig { .. } => "validation",
-            PlatformError::InvalidJobConfig { .. } => "validation",
-            PlatformError::AttestationFailed { .. } => "attestation",
-            PlatformError::KeyReleaseFailed { .. } => "attestation",
-            PlatformError::StorageError { .. } => "storage",
-            PlatformError::BuilderError { .. } => "builder",
-            PlatformError::SchedulerError { .. } => "scheduler",
-            PlatformError::ConfigError { .. } => "config",
-            PlatformError::EmissionError { .. } => "emission",
-            PlatformError::AuthenticationFailed { .. } => "auth",
-            PlatformError::AuthorizationFailed { .. } => "auth",
-            PlatformError::RateLimitExceeded { .. } => "rate_limit",
-            PlatformError::ValidationError { .. } => "validation",
-            PlatformError::SerializationError { .. } => "serialization",
-            PlatformError::NetworkError { .. } => "network",
-            PlatformError::DatabaseError { .. } => "database",
-            PlatformError::InternalError { .. } => "internal",
-            PlatformError::ServiceUnavailable { .. } => "service",
-            PlatformError::TimeoutError { .. } => "timeout",
-            PlatformError::ResourceNotFound { .. } => "resource",
-            PlatformError::ResourceAlreadyExists { .. } => "resource",
-            PlatformError::InvalidRequest { .. } => "request",
-            PlatformError::UnsupportedOperation { .. } => "operation",
-            PlatformError::ExternalServiceError { .. } => "external",
+            PlatformError::NotFound { .. } => "not_found",
+            PlatformError::Conflict { .. } => "conflict",
+            PlatformError::Validation { .. } => "validation",
+            PlatformError::Unprocessable { .. } => "unprocessable",
+            PlatformError::Unauthorized { .. } => "unauthorized",
+            PlatformError::PayloadTooLarge { .. } => "payload_too_large",
+            PlatformError::RateLimited { .. } => "rate_limited",
+            PlatformError::Upstream { .. } => "upstream",
+            PlatformError::Internal(_) => "internal",
         }
     }
 }
@@ -164,25 +155,47 @@ impl PlatformError {
 pub type PlatformResult<T> = Result<T, PlatformError>;
 
 /// Error response for API endpoints
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
+    /// Category of the error, see `PlatformError::category`
     pub error: String,
     pub message: String,
     pub code: u16,
-    pub category: String,
     pub retryable: bool,
+    /// Kind of resource that was not found
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    /// Fields that failed validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub request_id: Option<String>,
 }
 
 impl From<PlatformError> for ErrorResponse {
     fn from(err: PlatformError) -> Self {
+        let message = match &err {
+            // Internal details are logged, not sent to clients
+            PlatformError::Internal(_) => "Internal server error".to_string(),
+            err => err.to_string(),
+        };
+        let code = err.status_code();
+        let retryable = err.is_retryable();
+        let error = err.category().to_string();
+        let (resource, fields) = match err {
+            PlatformError::NotFound { resource, .. } => (Some(resource), vec![]),
+            PlatformError::Validation { fields } | PlatformError::Unprocessable { fields } => {
+                (None, fields)
+            }
+            _ => (None, vec![]),
+        };
         Self {
-            error: err.category().to_string(),
-            message: err.to_string(),
-            code: err.status_code(),
-            category: err.category().to_string(),
-            retryable: err.is_retryable(),
+            error,
+            message,
+            code,
+            retryable,
+            resource,
+            fields,
             timestamp: chrono::Utc::now(),
             request_id: None,
         }
@@ -191,8 +204,62 @@ impl From<PlatformError> for ErrorResponse {
 
 impl From<anyhow::Error> for PlatformError {
     fn from(err: anyhow::Error) -> Self {
-        PlatformError::InternalError {
-            reason: err.to_string(),
-        }
+        err.downcast::<PlatformError>()
+            .unwrap_or_else(PlatformError::Internal)
+    }
+}
+
+impl From<serde_json::Error> for PlatformError {
+    fn from(err: serde_json::Error) -> Self {
+        PlatformError::Internal(err.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_error_survives_anyhow() {
+        let err: anyhow::Error = PlatformError::not_found("job", "42").into();
+        let err = PlatformError::from(err.context("Failed to complete job"));
+        assert!(matches!(
+            &err,
+            PlatformError::NotFound { resource, id } if resource == "job" && id == "42"
+        ));
+
+        let err = PlatformError::from(anyhow::anyhow!("connection reset"));
+        assert!(matches!(err, PlatformError::Internal(_)));
+        assert_eq!(err.status_code(), 500);
+    }
+
+    #[test]
+    fn test_error_response() {
+        let response = ErrorResponse::from(PlatformError::not_found("challenge", "abc"));
+        assert_eq!(response.code, 404);
+        assert_eq!(response.error, "not_found");
+        assert_eq!(response.resource.as_deref(), Some("challenge"));
+        assert_eq!(response.message, "challenge abc not found");
+
+        let response = ErrorResponse::from(PlatformError::invalid("timeout", "too long"));
+        assert_eq!(response.code, 400);
+        assert_eq!(
+            response.fields,
+            vec![FieldError::new("timeout", "too long")]
+        );
+
+        let response = ErrorResponse::from(PlatformError::Unprocessable {
+            fields: vec![FieldError::new("scores/accuracy", "score is not finite")],
+        });
+        assert_eq!(response.code, 422);
+        assert_eq!(response.error, "unprocessable");
+        assert_eq!(response.fields[0].field, "scores/accuracy");
+
+        // Internal errors do not leak their cause
+        let response = ErrorResponse::from(PlatformError::from(anyhow::anyhow!("db password")));
+        assert_eq!(response.code, 500);
+        assert!(!response.message.contains("password"));
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json.get("fields").is_none());
     }
 }
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::fmt;
+use std::str::FromStr;
+
+/// Hash algorithm of compose hashes and attestation challenge hashes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha384 => "sha384",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    /// Digest of `data`
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            HashAlgorithm::Sha384 => Sha384::digest(data).to_vec(),
+            HashAlgorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    /// Hex-encoded digest of `data`
+    pub fn hex_digest(&self, data: &[u8]) -> String {
+        hex::encode(self.digest(data))
+    }
+
+    /// Length of the digests, in bytes
+    pub fn digest_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48,
+            HashAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// Algorithm whose hex-encoded digests have the length of `hex`
+    pub fn of_hex_digest(hex: &str) -> Option<Self> {
+        [
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Sha384,
+            HashAlgorithm::Sha512,
+        ]
+        .into_iter()
+        .find(|algorithm| algorithm.digest_len() * 2 == hex.len())
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "").as_str() {
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha384" => Ok(HashAlgorithm::Sha384),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            _ => Err(format!("Unknown hash algorithm: {}", s)),
+        }
+    }
+}
+
+/// Hex-encoded digest along with the algorithm it was computed with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentHash {
+    pub algorithm: HashAlgorithm,
+    pub hex: String,
+}
+
+impl ContentHash {
+    pub fn compute(algorithm: HashAlgorithm, data: &[u8]) -> Self {
+        Self {
+            algorithm,
+            hex: algorithm.hex_digest(data),
+        }
+    }
+
+    /// Check that `actual` is this hash, computed with the same algorithm
+    pub fn verify(&self, actual: &ContentHash) -> Result<(), HashMismatch> {
+        if self.algorithm != actual.algorithm {
+            return Err(HashMismatch::Algorithm {
+                expected: self.algorithm,
+                actual: actual.algorithm,
+            });
+        }
+        if !self.hex.eq_ignore_ascii_case(&actual.hex) {
+            return Err(HashMismatch::Digest {
+                expected: self.hex.clone(),
+                actual: actual.hex.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Two hashes of what should be the same content differ
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HashMismatch {
+    #[error("Hash algorithm mismatch: expected {expected}, got {actual}")]
+    Algorithm {
+        expected: HashAlgorithm,
+        actual: HashAlgorithm,
+    },
+    #[error("Hash mismatch: expected {expected}, got {actual}")]
+    Digest { expected: String, actual: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &[u8] = br#"{"docker_config":{},"services":{}}"#;
+
+    #[test]
+    fn test_sha256_is_the_default() {
+        assert_eq!(HashAlgorithm::default(), HashAlgorithm::Sha256);
+        assert_eq!(
+            HashAlgorithm::Sha256.hex_digest(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let expected = ContentHash::compute(HashAlgorithm::default(), CONTENT);
+        let actual = ContentHash::compute(HashAlgorithm::Sha256, CONTENT);
+        assert_eq!(expected.verify(&actual), Ok(()));
+        assert_eq!(
+            HashAlgorithm::of_hex_digest(&actual.hex),
+            Some(HashAlgorithm::Sha256)
+        );
+    }
+
+    #[test]
+    fn test_different_algorithms_mismatch() {
+        let expected = ContentHash::compute(HashAlgorithm::Sha256, CONTENT);
+        let actual = ContentHash::compute(HashAlgorithm::Sha512, CONTENT);
+        assert_eq!(
+            expected.verify(&actual),
+            Err(HashMismatch::Algorithm {
+                expected: HashAlgorithm::Sha256,
+                actual: HashAlgorithm::Sha512,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_algorithm() {
+        assert_eq!("SHA-384".parse(), Ok(HashAlgorithm::Sha384));
+        assert_eq!("sha512".parse(), Ok(HashAlgorithm::Sha512));
+        assert!("md5".parse::<HashAlgorithm>().is_err());
+    }
+}
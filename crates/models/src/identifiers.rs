@@ -0,0 +1,332 @@
+//! String identifiers checked when they are parsed
+//!
+//! A [`Hotkey`] or [`ComposeHash`] can only be built from a valid value, so
+//! code receiving one does not need to check it again. All of them deref to
+//! `str` and serialize as plain strings.
+
+use blake2::{Blake2b512, Digest};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use crate::PlatformError;
+
+/// A string that is not a valid identifier of its kind
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid {kind}: {reason}")]
+pub struct InvalidIdentifier {
+    /// Kind of identifier, e.g. `hotkey`
+    pub kind: &'static str,
+    pub reason: &'static str,
+}
+
+impl From<InvalidIdentifier> for PlatformError {
+    fn from(err: InvalidIdentifier) -> Self {
+        PlatformError::invalid(err.kind, err.reason)
+    }
+}
+
+/// Implements the conversions shared by the identifiers wrapping a `String`
+macro_rules! string_identifier {
+    ($name:ident) => {
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+    };
+}
+
+/// Implements parsing for the identifiers validated by `$name::validate`
+macro_rules! validated_identifier {
+    ($name:ident) => {
+        string_identifier!($name);
+
+        impl FromStr for $name {
+            type Err = InvalidIdentifier;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::validate(s)?;
+                Ok(Self(s.to_string()))
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = InvalidIdentifier;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                Self::validate(&value)?;
+                Ok(Self(value))
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = InvalidIdentifier;
+
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                value.parse()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+    };
+}
+
+/// SS58 address prefix of Bittensor accounts, the generic Substrate one
+pub const SS58_PREFIX: u16 = 42;
+
+/// Length of the public keys hotkeys encode, in bytes
+const PUBLIC_KEY_LEN: usize = 32;
+
+/// Length of the SS58 checksum, in bytes
+const SS58_CHECKSUM_LEN: usize = 2;
+
+/// SS58 address of a Bittensor account
+///
+/// Parsing checks the base58 encoding, the address prefix, the length of the
+/// public key and the checksum. Any network prefix is accepted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Hotkey(String);
+
+validated_identifier!(Hotkey);
+
+impl Hotkey {
+    /// Address of `public_key` on Bittensor
+    pub fn from_public_key(public_key: &[u8; PUBLIC_KEY_LEN]) -> Self {
+        let mut data = vec![SS58_PREFIX as u8];
+        data.extend_from_slice(public_key);
+        let checksum = ss58_checksum(&data);
+        data.extend_from_slice(&checksum[..SS58_CHECKSUM_LEN]);
+        Self(bs58::encode(data).into_string())
+    }
+
+    fn validate(s: &str) -> Result<(), InvalidIdentifier> {
+        let invalid = |reason| InvalidIdentifier {
+            kind: "hotkey",
+            reason,
+        };
+        let data = bs58::decode(s)
+            .into_vec()
+            .map_err(|_| invalid("not base58"))?;
+        // Prefixes below 64 take one byte, those up to 16383 two
+        let prefix_len = match data.first() {
+            Some(0..=63) => 1,
+            Some(64..=127) => 2,
+            Some(_) => return Err(invalid("unknown address format")),
+            None => return Err(invalid("empty")),
+        };
+        if data.len() != prefix_len + PUBLIC_KEY_LEN + SS58_CHECKSUM_LEN {
+            return Err(invalid("not a 32-byte public key"));
+        }
+        let (payload, checksum) = data.split_at(data.len() - SS58_CHECKSUM_LEN);
+        if ss58_checksum(payload)[..SS58_CHECKSUM_LEN] != *checksum {
+            return Err(invalid("checksum mismatch"));
+        }
+        Ok(())
+    }
+}
+
+fn ss58_checksum(payload: &[u8]) -> Vec<u8> {
+    let mut hasher = Blake2b512::new();
+    hasher.update(b"SS58PRE");
+    hasher.update(payload);
+    hasher.finalize().to_vec()
+}
+
+/// Hex-encoded SHA-256 digest of a compose manifest, as measured by dstack
+///
+/// Parsing accepts exactly 64 lowercase hex characters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ComposeHash(String);
+
+validated_identifier!(ComposeHash);
+
+impl ComposeHash {
+    fn validate(s: &str) -> Result<(), InvalidIdentifier> {
+        let invalid = |reason| InvalidIdentifier {
+            kind: "compose hash",
+            reason,
+        };
+        if s.len() != 64 {
+            return Err(invalid("not 64 characters long"));
+        }
+        if !s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+            return Err(invalid("not lowercase hex"));
+        }
+        Ok(())
+    }
+}
+
+/// Token of an authenticated session
+///
+/// Tokens are opaque: any string is accepted. They are kept out of logs, so
+/// `Debug` and `Display` print a placeholder; the token itself is read
+/// through `as_str` or `Deref`.
+#[derive(Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SessionToken(String);
+
+string_identifier!(SessionToken);
+
+impl SessionToken {
+    /// New random token
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+}
+
+impl From<String> for SessionToken {
+    fn from(token: String) -> Self {
+        Self(token)
+    }
+}
+
+impl From<&str> for SessionToken {
+    fn from(token: &str) -> Self {
+        Self(token.to_string())
+    }
+}
+
+impl FromStr for SessionToken {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
+    }
+}
+
+impl fmt::Debug for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SessionToken(<redacted>)")
+    }
+}
+
+impl fmt::Display for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[test]
+    fn test_parse_hotkey() {
+        let alice: Hotkey = ALICE.parse().unwrap();
+        assert_eq!(alice, ALICE);
+        assert_eq!(alice.len(), 48);
+        assert_eq!(Hotkey::try_from(ALICE.to_string()), Ok(alice.clone()));
+
+        // The public key of //Alice
+        let public_key =
+            hex::decode("d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d")
+                .unwrap();
+        assert_eq!(
+            Hotkey::from_public_key(&public_key.try_into().unwrap()),
+            alice
+        );
+
+        let json = serde_json::to_string(&alice).unwrap();
+        assert_eq!(json, format!("\"{}\"", ALICE));
+        assert_eq!(serde_json::from_str::<Hotkey>(&json).unwrap(), alice);
+    }
+
+    #[test]
+    fn test_reject_malformed_hotkeys() {
+        let reason = |s: &str| s.parse::<Hotkey>().unwrap_err().reason;
+
+        assert_eq!(reason(""), "empty");
+        assert_eq!(reason("validator-a"), "not base58");
+        // 0, O, I and l are not in the base58 alphabet
+        assert_eq!(reason(&ALICE.replace('G', "0")), "not base58");
+        assert_eq!(reason(&ALICE[..40]), "not a 32-byte public key");
+        assert_eq!(reason(&format!("{}1", ALICE)), "not a 32-byte public key");
+        // A single changed character breaks the checksum
+        assert_eq!(reason(&ALICE.replace("QY", "QZ")), "checksum mismatch");
+        assert_eq!(
+            reason(&bs58::encode([255u8; 35]).into_string()),
+            "unknown address format"
+        );
+
+        let err = serde_json::from_str::<Hotkey>("\"validator-a\"").unwrap_err();
+        assert!(err.to_string().contains("Invalid hotkey"));
+        let err = PlatformError::from(Hotkey::try_from("alice").unwrap_err());
+        assert_eq!(err.status_code(), 400);
+    }
+
+    #[test]
+    fn test_parse_compose_hash() {
+        let hex = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let hash: ComposeHash = hex.parse().unwrap();
+        assert_eq!(hash.as_str(), hex);
+        assert_eq!(serde_json::to_value(&hash).unwrap(), hex);
+
+        let reason = |s: &str| s.parse::<ComposeHash>().unwrap_err().reason;
+        assert_eq!(reason(""), "not 64 characters long");
+        assert_eq!(reason(&hex[..63]), "not 64 characters long");
+        assert_eq!(reason(&format!("{}00", hex)), "not 64 characters long");
+        assert_eq!(reason(&hex.to_uppercase()), "not lowercase hex");
+        assert_eq!(reason(&hex.replace('a', "g")), "not lowercase hex");
+        assert!(serde_json::from_str::<ComposeHash>("\"sha256:abc\"").is_err());
+    }
+
+    #[test]
+    fn test_session_token_is_redacted() {
+        let token = SessionToken::from("secret-token");
+        assert_eq!(token.to_string(), "<redacted>");
+        assert_eq!(format!("{:?}", token), "SessionToken(<redacted>)");
+        assert_eq!(token.as_str(), "secret-token");
+        assert_eq!(serde_json::to_value(&token).unwrap(), "secret-token");
+
+        assert_ne!(SessionToken::generate(), SessionToken::generate());
+    }
+}
@@ -5,29 +5,127 @@ use serde::{Deserialize, Serialize};
 /// Job status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum JobStatus {
+    /// Waiting for its dependencies to complete
+    Blocked,
     Pending,
     Claimed,
     Running,
     Completed,
     Failed,
     Timeout,
+    /// Failed with no retries left; kept for triage until requeued
+    DeadLettered,
+}
+
+impl JobStatus {
+    /// Status as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Blocked => "blocked",
+            JobStatus::Pending => "pending",
+            JobStatus::Claimed => "claimed",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::Timeout => "timeout",
+            JobStatus::DeadLettered => "dead_lettered",
+        }
+    }
+}
+
+impl From<&str> for JobStatus {
+    fn from(s: &str) -> Self {
+        match s {
+            "blocked" => JobStatus::Blocked,
+            "claimed" => JobStatus::Claimed,
+            "running" => JobStatus::Running,
+            "completed" => JobStatus::Completed,
+            "failed" => JobStatus::Failed,
+            "timeout" => JobStatus::Timeout,
+            "dead_lettered" => JobStatus::DeadLettered,
+            _ => JobStatus::Pending,
+        }
+    }
 }
 
 /// Job priority
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub enum JobPriority {
     Low,
+    #[default]
     Normal,
     High,
     Critical,
 }
 
+impl JobPriority {
+    /// Priority as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobPriority::Low => "low",
+            JobPriority::Normal => "normal",
+            JobPriority::High => "high",
+            JobPriority::Critical => "critical",
+        }
+    }
+}
+
+impl From<&str> for JobPriority {
+    fn from(s: &str) -> Self {
+        match s {
+            "low" => JobPriority::Low,
+            "high" => JobPriority::High,
+            "critical" => JobPriority::Critical,
+            _ => JobPriority::Normal,
+        }
+    }
+}
+
+/// Failure category for a failed job
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    Timeout,
+    ExecutionError,
+    ValidatorCrash,
+    ResourceExhausted,
+    ValidationFailed,
+}
+
+impl From<&str> for FailureCategory {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "timeout" => FailureCategory::Timeout,
+            "validator_crash" => FailureCategory::ValidatorCrash,
+            "resource_exhausted" => FailureCategory::ResourceExhausted,
+            "validation_failed" => FailureCategory::ValidationFailed,
+            _ => FailureCategory::ExecutionError,
+        }
+    }
+}
+
+impl std::fmt::Display for FailureCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailureCategory::Timeout => write!(f, "timeout"),
+            FailureCategory::ExecutionError => write!(f, "execution_error"),
+            FailureCategory::ValidatorCrash => write!(f, "validator_crash"),
+            FailureCategory::ResourceExhausted => write!(f, "resource_exhausted"),
+            FailureCategory::ValidationFailed => write!(f, "validation_failed"),
+        }
+    }
+}
+
 /// Job metadata
+///
+/// Payloads of older schema versions are upgraded when deserialized, see
+/// [`crate::compat`] before adding a field.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct JobMetadata {
     pub id: Id,
     pub challenge_id: Id,
-    pub validator_hotkey: Option<Hotkey>,
+    pub validator_hotkey: Option<String>,
     pub status: JobStatus,
     pub priority: JobPriority,
     pub runtime: RuntimeType,
@@ -35,11 +133,46 @@ pub struct JobMetadata {
     pub claimed_at: Option<DateTime<Utc>>,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
+    /// Time by which the job must finish, set when it is claimed
     pub timeout_at: Option<DateTime<Utc>>,
+    /// Seconds the job may run once claimed, see `JobConfig::timeout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
     pub retry_count: u32,
     pub max_retries: u32,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub payload: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_category: Option<FailureCategory>,
+    /// Jobs that must complete before this one can be claimed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<Id>,
+    /// Time by which the job should be claimed; it is ranked higher as the deadline nears
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Priority of a pending job after aging and deadline boosts, as of when it was read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_priority: Option<f64>,
+    /// Incremented by every change of the job, see `SubmitResultRequest::expected_version`
+    #[serde(default)]
+    pub version: u64,
+    /// Capabilities a validator must claim the job with, see `ClaimJobRequest::capabilities`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_capabilities: Vec<String>,
+    /// Resources the job needs, handed to the validator in its `JobConfig`
+    #[serde(default, skip_serializing_if = "ResourceRequirements::is_empty")]
+    pub resources: ResourceRequirements,
+    /// Version of the schema the job was written with, see [`crate::JOB_METADATA_SCHEMA_VERSION`]
+    pub schema_version: u32,
+}
+
+impl JobMetadata {
+    /// Whether a validator claiming with `capabilities` may run the job
+    pub fn runs_with(&self, capabilities: &[String]) -> bool {
+        self.required_capabilities
+            .iter()
+            .all(|required| capabilities.contains(required))
+    }
 }
 
 /// Job claim request
@@ -88,17 +221,79 @@ pub struct SubmissionMetadata {
 }
 
 /// Evaluation result
+///
+/// Payloads of older schema versions are upgraded when deserialized, see
+/// [`crate::compat`] before adding a field.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct EvalResult {
     pub job_id: Id,
     pub submission_id: Id,
     pub scores: std::collections::BTreeMap<String, Score>,
     pub metrics: std::collections::BTreeMap<String, f64>,
     pub logs: Vec<String>,
+    #[serde(default)]
     pub error: Option<String>,
     pub execution_time: u64,
     pub resource_usage: ResourceUsage,
+    #[serde(default)]
     pub attestation_receipt: Option<String>,
+    /// Version of the schema the result was written with, see [`crate::EVAL_RESULT_SCHEMA_VERSION`]
+    pub schema_version: u32,
+}
+
+impl EvalResult {
+    /// Check the result reports what `policy` requires
+    ///
+    /// Returns every violation, missing scores first, then those of the
+    /// scores and of the metrics by key.
+    pub fn validate(&self, policy: &ScorePolicy) -> Result<(), Vec<ScoreViolation>> {
+        let mut violations: Vec<ScoreViolation> = policy
+            .required_scores
+            .iter()
+            .filter(|key| !self.scores.contains_key(*key))
+            .map(|key| ScoreViolation::MissingScore { key: key.clone() })
+            .collect();
+
+        for (key, &value) in &self.scores {
+            if policy.require_finite && !value.is_finite() {
+                violations.push(ScoreViolation::NonFiniteScore {
+                    key: key.clone(),
+                    value,
+                });
+            } else if let Some(range) = policy.range_of(key).filter(|r| !r.contains(value)) {
+                violations.push(ScoreViolation::ScoreOutOfRange {
+                    key: key.clone(),
+                    value,
+                    range,
+                });
+            }
+        }
+
+        if self.metrics.len() > policy.max_metrics {
+            violations.push(ScoreViolation::TooManyMetrics {
+                count: self.metrics.len(),
+                max: policy.max_metrics,
+            });
+        }
+        if policy.require_finite {
+            violations.extend(
+                self.metrics
+                    .iter()
+                    .filter(|(_, value)| !value.is_finite())
+                    .map(|(key, &value)| ScoreViolation::NonFiniteMetric {
+                        key: key.clone(),
+                        value,
+                    }),
+            );
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
 }
 
 /// Resource usage during execution
@@ -116,6 +311,10 @@ pub struct SubmitResultRequest {
     pub job_id: Id,
     pub result: EvalResult,
     pub receipts: Vec<String>,
+    /// Version of the job the result is for, usually the one it was claimed
+    /// at; the result is refused if the job changed since
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_version: Option<u64>,
 }
 
 /// Request to fail a job
@@ -123,6 +322,11 @@ pub struct SubmitResultRequest {
 pub struct FailJobRequest {
     pub reason: String,
     pub error_details: Option<String>,
+    #[serde(default)]
+    pub failure_category: Option<FailureCategory>,
+    /// Version of the job the failure is for, see `SubmitResultRequest::expected_version`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_version: Option<u64>,
 }
 
 /// Job list response
@@ -134,6 +338,64 @@ pub struct JobListResponse {
     pub per_page: u32,
 }
 
+/// One failed attempt of a job
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobAttempt {
+    /// 1 for the first run, then one more for each retry
+    pub attempt: u32,
+    pub validator_hotkey: Option<String>,
+    pub reason: String,
+    pub error_details: Option<String>,
+    #[serde(default)]
+    pub failure_category: Option<FailureCategory>,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// Job that exhausted its retries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetteredJob {
+    pub job: JobMetadata,
+    /// Reason of the final failure
+    pub reason: Option<String>,
+    /// Every failed attempt, oldest first
+    pub retry_history: Vec<JobAttempt>,
+    pub dead_lettered_at: Option<DateTime<Utc>>,
+}
+
+/// Status transition of a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobEvent {
+    pub id: Id,
+    pub job_id: Id,
+    pub old_status: Option<JobStatus>,
+    pub new_status: JobStatus,
+    /// Validator, admin or scheduler task that caused the transition
+    pub actor: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub metadata: serde_json::Value,
+}
+
+/// Receipts a validator submitted with the result of a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobReceipts {
+    pub job_id: Id,
+    pub validator_hotkey: Option<String>,
+    /// Unset until the job completes
+    pub completed_at: Option<DateTime<Utc>>,
+    pub receipts: Vec<String>,
+    /// `EvalResult::attestation_receipt` of the result
+    pub attestation_receipt: Option<String>,
+}
+
+/// Dead-lettered job list response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadLetteredJobListResponse {
+    pub jobs: Vec<DeadLetteredJob>,
+    pub total: u64,
+    pub page: u32,
+    pub per_page: u32,
+}
+
 /// Job statistics
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JobStats {
@@ -144,6 +406,126 @@ pub struct JobStats {
     pub failed_jobs: u64,
     pub avg_execution_time: f64,
     pub success_rate: f64,
+    pub failures_by_category: std::collections::HashMap<String, u64>,
 }
 
-use super::challenge::ResourceLimits;
+use super::challenge::{ResourceLimits, ResourceRequirements, ScorePolicy, ScoreViolation};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScoreRange;
+
+    fn result(scores: &[(&str, Score)], metrics: &[(&str, f64)]) -> EvalResult {
+        EvalResult {
+            job_id: Id::from(uuid::Uuid::new_v4()),
+            submission_id: Id::from(uuid::Uuid::new_v4()),
+            scores: scores.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+            metrics: metrics.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+            logs: vec![],
+            error: None,
+            execution_time: 0,
+            resource_usage: ResourceUsage {
+                cpu_time: 0,
+                memory_peak: 0,
+                disk_usage: 0,
+                network_bytes: 0,
+            },
+            attestation_receipt: None,
+            schema_version: crate::EVAL_RESULT_SCHEMA_VERSION,
+        }
+    }
+
+    #[test]
+    fn test_validate_default_policy() {
+        let policy = ScorePolicy::default();
+        assert_eq!(
+            result(&[("accuracy", 0.0), ("f1", 1.0)], &[("latency_ms", 1e6)]).validate(&policy),
+            Ok(())
+        );
+
+        let violations = result(
+            &[("accuracy", f64::NAN), ("f1", 1.5), ("recall", -0.1)],
+            &[],
+        )
+        .validate(&policy)
+        .unwrap_err();
+        assert_eq!(violations.len(), 3);
+        assert!(matches!(
+            &violations[0],
+            ScoreViolation::NonFiniteScore { key, value } if key == "accuracy" && value.is_nan()
+        ));
+        assert_eq!(
+            violations[1],
+            ScoreViolation::ScoreOutOfRange {
+                key: "f1".to_string(),
+                value: 1.5,
+                range: ScoreRange::default(),
+            }
+        );
+        assert_eq!(violations[2].field(), "scores/recall");
+        assert_eq!(violations[2].rule(), "ranges");
+        assert_eq!(violations[2].to_string(), "score -0.1 is outside 0..=1");
+
+        let violations = result(&[], &[("latency_ms", f64::INFINITY)])
+            .validate(&policy)
+            .unwrap_err();
+        assert_eq!(violations[0].field(), "metrics/latency_ms");
+        assert_eq!(violations[0].rule(), "require_finite");
+    }
+
+    #[test]
+    fn test_validate_challenge_policy() {
+        let policy: ScorePolicy = serde_json::from_value(serde_json::json!({
+            "default_range": null,
+            "ranges": { "accuracy": { "min": 0.0, "max": 100.0 } },
+            "required_scores": ["accuracy", "f1"],
+            "max_metrics": 2,
+        }))
+        .unwrap();
+        assert!(policy.require_finite);
+
+        assert_eq!(
+            result(&[("accuracy", 99.0), ("f1", 42.0)], &[]).validate(&policy),
+            Ok(())
+        );
+
+        let metrics = [("a", 1.0), ("b", 2.0), ("c", 3.0)];
+        let violations = result(&[("accuracy", 101.0)], &metrics)
+            .validate(&policy)
+            .unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                ScoreViolation::MissingScore {
+                    key: "f1".to_string()
+                },
+                ScoreViolation::ScoreOutOfRange {
+                    key: "accuracy".to_string(),
+                    value: 101.0,
+                    range: ScoreRange {
+                        min: 0.0,
+                        max: 100.0
+                    },
+                },
+                ScoreViolation::TooManyMetrics { count: 3, max: 2 },
+            ]
+        );
+        assert_eq!(violations[0].field(), "scores/f1");
+        assert_eq!(violations[2].field(), "metrics");
+
+        // Without finiteness, unbounded scores may be infinite
+        let policy = ScorePolicy {
+            require_finite: false,
+            ..policy
+        };
+        assert_eq!(
+            result(
+                &[("accuracy", 1.0), ("f1", f64::INFINITY)],
+                &[("a", f64::NAN)]
+            )
+            .validate(&policy),
+            Ok(())
+        );
+    }
+}
@@ -2,19 +2,27 @@ use uuid::Uuid;
 
 pub mod attestation;
 pub mod challenge;
+pub mod compat;
 pub mod config;
 pub mod emissions;
 pub mod errors;
+pub mod hash;
+pub mod identifiers;
 pub mod job;
+pub mod metagraph;
 pub mod pool;
 pub mod vm_compose;
 
 pub use attestation::*;
 pub use challenge::*;
+pub use compat::{EVAL_RESULT_SCHEMA_VERSION, JOB_METADATA_SCHEMA_VERSION};
 pub use config::*;
 pub use emissions::*;
 pub use errors::*;
+pub use hash::*;
+pub use identifiers::*;
 pub use job::*;
+pub use metagraph::*;
 pub use pool::*;
 pub use vm_compose::*;
 
@@ -26,9 +34,6 @@ pub type EmissionsSchedule = emissions::EmissionSchedule;
 /// Common identifier type
 pub type Id = Uuid;
 
-/// Hotkey type for Bittensor integration
-pub type Hotkey = String;
-
 /// Score type for evaluation results
 pub type Score = f64;
 
@@ -50,9 +55,6 @@ pub type Measurement = Vec<u8>;
 /// Policy type for key release
 pub type Policy = String;
 
-/// Session token for authenticated operations
-pub type SessionToken = String;
-
 /// Key material for encryption/decryption
 pub type KeyMaterial = Vec<u8>;
 
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::Hotkey;
+
+/// A neuron of the subnet, as read on a metagraph sync
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NeuronInfo {
+    pub hotkey: Hotkey,
+    pub uid: u16,
+    pub stake: f64,
+}
+
+/// Neurons that changed from one metagraph sync to the next
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MetagraphDiff {
+    pub added: Vec<NeuronInfo>,
+    pub removed: Vec<Hotkey>,
+    /// Neurons whose uid or stake changed
+    pub updated: Vec<NeuronInfo>,
+}
+
+impl MetagraphDiff {
+    /// Changes from the neurons in `previous`, by hotkey, to `current`, each
+    /// list sorted by hotkey
+    pub fn between(previous: &HashMap<Hotkey, NeuronInfo>, current: &[NeuronInfo]) -> Self {
+        let mut diff = Self::default();
+        for neuron in current {
+            match previous.get(&neuron.hotkey) {
+                None => diff.added.push(neuron.clone()),
+                Some(old) if old != neuron => diff.updated.push(neuron.clone()),
+                Some(_) => {}
+            }
+        }
+        let current: HashMap<&str, &NeuronInfo> = current
+            .iter()
+            .map(|neuron| (neuron.hotkey.as_str(), neuron))
+            .collect();
+        diff.removed = previous
+            .keys()
+            .filter(|hotkey| !current.contains_key(hotkey.as_str()))
+            .cloned()
+            .collect();
+
+        diff.added.sort_by(|a, b| a.hotkey.cmp(&b.hotkey));
+        diff.updated.sort_by(|a, b| a.hotkey.cmp(&b.hotkey));
+        diff.removed.sort();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+
+    /// Neurons added or updated
+    pub fn changed(&self) -> impl Iterator<Item = &NeuronInfo> {
+        self.added.iter().chain(&self.updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hotkey(name: &str) -> Hotkey {
+        let seed = ["alice", "bob", "carol", "dave"]
+            .iter()
+            .position(|n| *n == name)
+            .unwrap();
+        Hotkey::from_public_key(&[seed as u8; 32])
+    }
+
+    fn neuron(name: &str, uid: u16, stake: f64) -> NeuronInfo {
+        NeuronInfo {
+            hotkey: hotkey(name),
+            uid,
+            stake,
+        }
+    }
+
+    #[test]
+    fn test_metagraph_diff() {
+        let previous: HashMap<Hotkey, NeuronInfo> = [
+            neuron("alice", 0, 10.0),
+            neuron("bob", 1, 20.0),
+            neuron("carol", 2, 30.0),
+        ]
+        .into_iter()
+        .map(|n| (n.hotkey.clone(), n))
+        .collect();
+
+        let unchanged: Vec<NeuronInfo> = previous.values().cloned().collect();
+        assert!(MetagraphDiff::between(&previous, &unchanged).is_empty());
+
+        // bob's stake changes, carol deregisters and dave takes her uid
+        let current = vec![
+            neuron("alice", 0, 10.0),
+            neuron("bob", 1, 25.0),
+            neuron("dave", 2, 0.0),
+        ];
+        let diff = MetagraphDiff::between(&previous, &current);
+        assert_eq!(diff.added, vec![neuron("dave", 2, 0.0)]);
+        assert_eq!(diff.updated, vec![neuron("bob", 1, 25.0)]);
+        assert_eq!(diff.removed, vec![hotkey("carol")]);
+        let changed: Vec<&Hotkey> = diff.changed().map(|n| &n.hotkey).collect();
+        assert_eq!(changed, vec![&hotkey("dave"), &hotkey("bob")]);
+
+        // The first sync adds every neuron
+        let first = MetagraphDiff::between(&HashMap::new(), &current);
+        assert_eq!(first.added.len(), 3);
+    }
+}
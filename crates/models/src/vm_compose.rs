@@ -1,7 +1,10 @@
+use anyhow::Context;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::hash::{ContentHash, HashAlgorithm};
+
 /// VM Compose Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VmComposeConfig {
@@ -88,6 +91,114 @@ impl Default for VmManifestDefaults {
     }
 }
 
+impl VmManifestDefaults {
+    /// Defaults of the manifest of the CVM named `name`
+    pub fn named(name: &str) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// The dstack `app_compose` manifest of a CVM running `compose_content`
+    /// and given the environment variables `allowed_envs`
+    ///
+    /// This is the manifest CVMs are deployed with, whose hash dstack
+    /// measures as the compose hash, see [`app_compose_hash`].
+    pub fn app_compose(&self, compose_content: &str, allowed_envs: &[String]) -> serde_json::Value {
+        serde_json::json!({
+            "manifest_version": self.manifest_version,
+            "name": self.name,
+            "runner": self.runner,
+            "docker_compose_file": compose_content,
+            "kms_enabled": self.kms_enabled,
+            "gateway_enabled": self.gateway_enabled,
+            "local_key_provider_enabled": self.local_key_provider_enabled,
+            "key_provider_id": self.key_provider_id,
+            "public_logs": self.public_logs,
+            "public_sysinfo": self.public_sysinfo,
+            "public_tcbinfo": self.public_tcbinfo,
+            "allowed_envs": allowed_envs,
+            "no_instance_id": self.no_instance_id,
+            "secure_time": self.secure_time,
+        })
+    }
+}
+
+/// Compose hash of an `app_compose` manifest, computed with `algorithm`
+/// over its JSON with sorted keys
+pub fn app_compose_hash(manifest: &serde_json::Value, algorithm: HashAlgorithm) -> ContentHash {
+    let normalized = sort_json_keys(manifest).to_string();
+    ContentHash::compute(algorithm, normalized.as_bytes())
+}
+
+/// Environment variables every CVM is provisioned with, besides those its
+/// compose config requires
+pub const PROVISIONING_ENV_KEYS: &[&str] =
+    &["DSTACK_VMM_URL", "HOTKEY_PASSPHRASE", "VALIDATOR_BASE_URL"];
+
+/// Variables a CVM requiring `required_env` is allowed: the
+/// [`PROVISIONING_ENV_KEYS`] and those, sorted and deduplicated
+pub fn provisioning_env_keys<'a>(
+    required_env: impl IntoIterator<Item = &'a String>,
+) -> Vec<String> {
+    let mut env_keys: Vec<String> = PROVISIONING_ENV_KEYS
+        .iter()
+        .map(|key| key.to_string())
+        .chain(required_env.into_iter().cloned())
+        .collect();
+    env_keys.sort();
+    env_keys.dedup();
+    env_keys
+}
+
+/// Compose hash of the CVM named `name` running `compose_content` and
+/// requiring the variables `required_env`
+///
+/// This is the hash of the `app_compose` manifest deployers build from the
+/// provisioning bundle of the CVM, allowed its [`provisioning_env_keys`].
+/// The builder and the attestation of validators both compute compose hashes
+/// with it, so that they agree.
+pub fn expected_compose_hash<'a>(
+    name: &str,
+    compose_content: &str,
+    required_env: impl IntoIterator<Item = &'a String>,
+    algorithm: HashAlgorithm,
+) -> ContentHash {
+    let manifest = VmManifestDefaults::named(name)
+        .app_compose(compose_content, &provisioning_env_keys(required_env));
+    app_compose_hash(&manifest, algorithm)
+}
+
+/// Normalize JSON by sorting all object keys alphabetically
+/// This ensures consistent hashing regardless of key insertion order
+pub fn normalize_json_for_hashing(json_str: &str) -> anyhow::Result<String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json_str).context("Failed to parse JSON for normalization")?;
+
+    serde_json::to_string(&sort_json_keys(&value)).context("Failed to serialize normalized JSON")
+}
+
+/// Recursively sort all object keys in a JSON value
+///
+/// The compact JSON of the sorted value is the same whatever order the keys
+/// were inserted or parsed in, which hashes and signatures over JSON rely on.
+pub fn sort_json_keys(value: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => {
+            let sorted: std::collections::BTreeMap<String, Value> = map
+                .iter()
+                .map(|(k, v)| (k.clone(), sort_json_keys(v)))
+                .collect();
+            Value::Object(sorted.into_iter().collect())
+        }
+        Value::Array(arr) => Value::Array(arr.iter().map(sort_json_keys).collect()),
+        _ => value.clone(),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VmHardwareSpec {
     pub name: Option<String>,
@@ -128,3 +239,66 @@ pub struct VmPortMapping {
     pub vm_port: u16,
     pub host_address: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_compose_hash_ignores_key_order() {
+        let manifest = VmManifestDefaults::named("validator_vm")
+            .app_compose("services: {}\n", &["HOTKEY_PASSPHRASE".to_string()]);
+        let normalized = normalize_json_for_hashing(&manifest.to_string()).unwrap();
+        assert!(normalized
+            .starts_with(r#"{"allowed_envs":["HOTKEY_PASSPHRASE"],"docker_compose_file""#));
+
+        let reordered =
+            normalize_json_for_hashing(r#"{"b":{"y":1,"x":[{"d":2,"c":3}]},"a":null}"#).unwrap();
+        assert_eq!(reordered, r#"{"a":null,"b":{"x":[{"c":3,"d":2}],"y":1}}"#);
+
+        let hash = app_compose_hash(&manifest, HashAlgorithm::Sha256);
+        assert_eq!(
+            hash,
+            ContentHash::compute(HashAlgorithm::Sha256, normalized.as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_expected_compose_hash_allows_provisioning_env() {
+        let required_env = ["MODE".to_string(), "HOTKEY_PASSPHRASE".to_string()];
+        assert_eq!(
+            provisioning_env_keys(&required_env),
+            [
+                "DSTACK_VMM_URL",
+                "HOTKEY_PASSPHRASE",
+                "MODE",
+                "VALIDATOR_BASE_URL"
+            ]
+        );
+
+        let compose = "services: {}\n";
+        let manifest = VmManifestDefaults::named("demo")
+            .app_compose(compose, &provisioning_env_keys(&required_env));
+        let hash = expected_compose_hash("demo", compose, &required_env, HashAlgorithm::Sha256);
+        assert_eq!(hash, app_compose_hash(&manifest, HashAlgorithm::Sha256));
+
+        // The provisioning variables are allowed whether required or not
+        let without_provisioning = VmManifestDefaults::named("demo").app_compose(
+            compose,
+            &["HOTKEY_PASSPHRASE".to_string(), "MODE".to_string()],
+        );
+        assert_ne!(
+            hash,
+            app_compose_hash(&without_provisioning, HashAlgorithm::Sha256)
+        );
+        assert_eq!(
+            expected_compose_hash(
+                "demo",
+                compose,
+                &["MODE".to_string()],
+                HashAlgorithm::Sha256
+            ),
+            hash
+        );
+    }
+}
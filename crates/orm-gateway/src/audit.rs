@@ -0,0 +1,535 @@
+//! Audit log of the queries executed through the gateway
+//!
+//! Every query and transaction, executed or refused, is recorded in
+//! `orm_query_audit` with the challenge it was made for, its operation and
+//! table, a summary of its filters and its outcome. Filter values are never
+//! recorded: the summary only names the columns and operators. The SQL text,
+//! with its values as `$n` parameters, is kept only for queries that failed or
+//! ran longer than `AuditConfig::slow_query_threshold_ms`.
+//!
+//! Entries are handed to a background writer that inserts them in batches, so
+//! auditing adds no database round trip to the queries themselves.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::{FilterGroup, FilterLogic, FilterOperator, ORMQuery, QueryFilter};
+
+/// Auditing configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// Queries running at least this long have their SQL recorded
+    pub slow_query_threshold_ms: u64,
+    /// Largest number of entries written at once
+    pub batch_size: usize,
+    /// Longest an entry waits for its batch to fill before being written
+    pub flush_interval_ms: u64,
+    /// Entries waiting to be written, past which new entries are dropped
+    pub queue_capacity: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            slow_query_threshold_ms: 1000,
+            batch_size: 100,
+            flush_interval_ms: 1000,
+            queue_capacity: 10_000,
+        }
+    }
+}
+
+impl AuditConfig {
+    /// Create configuration from environment variables
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            slow_query_threshold_ms: std::env::var("ORM_AUDIT_SLOW_QUERY_MS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default.slow_query_threshold_ms),
+            ..default
+        }
+    }
+}
+
+/// How an audited query ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Error,
+}
+
+impl AuditOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Error => "error",
+        }
+    }
+}
+
+impl From<&str> for AuditOutcome {
+    fn from(s: &str) -> Self {
+        match s {
+            "success" => AuditOutcome::Success,
+            _ => AuditOutcome::Error,
+        }
+    }
+}
+
+/// One audited query or transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Schema of the challenge the query was made for, if known
+    pub challenge: Option<String>,
+    pub operation: String,
+    /// Table of the query; the tables of a transaction, comma-separated
+    pub table_name: String,
+    /// Filters with their values redacted, see [`filter_summary`]
+    pub filter_summary: Option<String>,
+    /// Rows returned, or written by an insert, update, delete or transaction
+    pub row_count: Option<i64>,
+    pub duration_ms: f64,
+    pub outcome: AuditOutcome,
+    pub error: Option<String>,
+    /// SQL text, for failed and slow queries only
+    pub sql: Option<String>,
+    pub executed_at: DateTime<Utc>,
+}
+
+/// Filters of the audit log, see [`list_audit_entries`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditFilter {
+    pub challenge: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+/// Largest number of entries returned by [`list_audit_entries`]
+pub const MAX_AUDIT_ENTRIES: i64 = 1000;
+
+/// The filters of `query` with every value replaced by `?`, e.g.
+/// `score > ? AND (name = ? OR id IN (?))`
+pub fn filter_summary(query: &ORMQuery) -> Option<String> {
+    let filters = query.filters.as_deref().unwrap_or(&[]);
+    combine(filters.iter().map(redact).collect(), &query.filter_logic)
+}
+
+fn redact(filter: &QueryFilter) -> String {
+    match filter.operator {
+        FilterOperator::In | FilterOperator::NotIn => {
+            format!("{} {} (?)", filter.column, filter.operator.as_sql())
+        }
+        FilterOperator::Between | FilterOperator::NotBetween => {
+            format!("{} {} ? AND ?", filter.column, filter.operator.as_sql())
+        }
+        FilterOperator::IsNull | FilterOperator::IsNotNull => {
+            format!("{} {}", filter.column, filter.operator.as_sql())
+        }
+        _ => format!("{} {} ?", filter.column, filter.operator.as_sql()),
+    }
+}
+
+fn group_summary(group: &FilterGroup) -> Option<String> {
+    let mut parts: Vec<String> = group.filters.iter().map(redact).collect();
+    parts.extend(
+        group
+            .groups
+            .iter()
+            .filter_map(group_summary)
+            .map(|condition| format!("({})", condition)),
+    );
+    combine(parts, &group.logic)
+}
+
+/// Join condition parts like the executor builds the WHERE clause
+fn combine(mut parts: Vec<String>, logic: &FilterLogic) -> Option<String> {
+    let separator = match logic {
+        FilterLogic::And => " AND ",
+        FilterLogic::Or => " OR ",
+        FilterLogic::Nested(group) => {
+            if let Some(condition) = group_summary(group) {
+                parts.push(format!("({})", condition));
+            }
+            " AND "
+        }
+    };
+    (!parts.is_empty()).then(|| parts.join(separator))
+}
+
+/// Records audit entries through a background batch writer
+#[derive(Debug, Clone)]
+pub struct QueryAuditor {
+    sender: mpsc::Sender<AuditEntry>,
+    slow_query_threshold: Duration,
+}
+
+impl QueryAuditor {
+    /// Start writing entries to `orm_query_audit` in the background
+    pub fn spawn(pool: PgPool, config: AuditConfig) -> Self {
+        let (auditor, receiver) = Self::channel(&config);
+        tokio::spawn(write_entries(pool, receiver, config));
+        auditor
+    }
+
+    /// Auditor whose entries are received from the returned channel
+    pub(crate) fn channel(config: &AuditConfig) -> (Self, mpsc::Receiver<AuditEntry>) {
+        let (sender, receiver) = mpsc::channel(config.queue_capacity.max(1));
+        let auditor = Self {
+            sender,
+            slow_query_threshold: Duration::from_millis(config.slow_query_threshold_ms),
+        };
+        (auditor, receiver)
+    }
+
+    /// Whether the SQL of a query that took `duration` is recorded
+    pub fn captures_sql(&self, duration: Duration, failed: bool) -> bool {
+        failed || duration >= self.slow_query_threshold
+    }
+
+    /// Queue `entry` to be written, without waiting
+    pub fn record(&self, entry: AuditEntry) {
+        if let Err(e) = self.sender.try_send(entry) {
+            warn!(error = %e, "Dropped ORM audit entry");
+        }
+    }
+}
+
+/// Write the received entries in batches until every auditor is dropped
+async fn write_entries(
+    pool: PgPool,
+    mut receiver: mpsc::Receiver<AuditEntry>,
+    config: AuditConfig,
+) {
+    let batch_size = config.batch_size.max(1);
+    let flush_interval = Duration::from_millis(config.flush_interval_ms);
+
+    while let Some(first) = receiver.recv().await {
+        let mut batch = vec![first];
+        let deadline = tokio::time::Instant::now() + flush_interval;
+        while batch.len() < batch_size {
+            match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(entry)) => batch.push(entry),
+                // Every auditor was dropped, or the batch waited long enough
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        if let Err(e) = insert_entries(&pool, &batch).await {
+            warn!(error = %e, count = batch.len(), "Failed to write ORM audit entries");
+        }
+    }
+}
+
+/// Insert `entries` in a single statement
+pub async fn insert_entries(pool: &PgPool, entries: &[AuditEntry]) -> Result<()> {
+    let mut challenges = Vec::with_capacity(entries.len());
+    let mut operations = Vec::with_capacity(entries.len());
+    let mut tables = Vec::with_capacity(entries.len());
+    let mut filter_summaries = Vec::with_capacity(entries.len());
+    let mut row_counts = Vec::with_capacity(entries.len());
+    let mut durations = Vec::with_capacity(entries.len());
+    let mut outcomes = Vec::with_capacity(entries.len());
+    let mut errors = Vec::with_capacity(entries.len());
+    let mut sqls = Vec::with_capacity(entries.len());
+    let mut executed_ats = Vec::with_capacity(entries.len());
+    for entry in entries {
+        challenges.push(entry.challenge.clone());
+        operations.push(entry.operation.clone());
+        tables.push(entry.table_name.clone());
+        filter_summaries.push(entry.filter_summary.clone());
+        row_counts.push(entry.row_count);
+        durations.push(entry.duration_ms);
+        outcomes.push(entry.outcome.as_str());
+        errors.push(entry.error.clone());
+        sqls.push(entry.sql.clone());
+        executed_ats.push(entry.executed_at);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO orm_query_audit (
+            challenge, operation, table_name, filter_summary, row_count,
+            duration_ms, outcome, error, sql, executed_at
+        )
+        SELECT * FROM UNNEST(
+            $1::text[], $2::text[], $3::text[], $4::text[], $5::bigint[],
+            $6::double precision[], $7::text[], $8::text[], $9::text[], $10::timestamptz[]
+        )
+        "#,
+    )
+    .bind(&challenges)
+    .bind(&operations)
+    .bind(&tables)
+    .bind(&filter_summaries)
+    .bind(&row_counts)
+    .bind(&durations)
+    .bind(&outcomes)
+    .bind(&errors)
+    .bind(&sqls)
+    .bind(&executed_ats)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    challenge: Option<String>,
+    operation: String,
+    table_name: String,
+    filter_summary: Option<String>,
+    row_count: Option<i64>,
+    duration_ms: f64,
+    outcome: String,
+    error: Option<String>,
+    sql: Option<String>,
+    executed_at: DateTime<Utc>,
+}
+
+impl From<AuditRow> for AuditEntry {
+    fn from(row: AuditRow) -> Self {
+        Self {
+            challenge: row.challenge,
+            operation: row.operation,
+            table_name: row.table_name,
+            filter_summary: row.filter_summary,
+            row_count: row.row_count,
+            duration_ms: row.duration_ms,
+            outcome: AuditOutcome::from(row.outcome.as_str()),
+            error: row.error,
+            sql: row.sql,
+            executed_at: row.executed_at,
+        }
+    }
+}
+
+/// Audit entries matching `filter`, most recent first
+pub async fn list_audit_entries(pool: &PgPool, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+    let rows = sqlx::query_as::<_, AuditRow>(
+        r#"
+        SELECT challenge, operation, table_name, filter_summary, row_count,
+               duration_ms, outcome, error, sql, executed_at
+        FROM orm_query_audit
+        WHERE ($1::text IS NULL OR challenge = $1)
+          AND ($2::timestamptz IS NULL OR executed_at >= $2)
+          AND ($3::timestamptz IS NULL OR executed_at < $3)
+        ORDER BY executed_at DESC, id DESC
+        LIMIT $4
+        "#,
+    )
+    .bind(filter.challenge.as_deref())
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.limit.unwrap_or(100).clamp(1, MAX_AUDIT_ENTRIES))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Into::into).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{provision_challenge_schema, ChallengeSchema, ORMGatewayConfig, SecureORMGateway};
+    use serde_json::json;
+
+    fn filter(column: &str, operator: FilterOperator, value: serde_json::Value) -> QueryFilter {
+        QueryFilter {
+            column: column.to_string(),
+            operator,
+            value,
+        }
+    }
+
+    fn select(schema: &str, filters: Vec<QueryFilter>, filter_logic: FilterLogic) -> ORMQuery {
+        ORMQuery {
+            operation: "select".to_string(),
+            table: "submissions".to_string(),
+            schema: Some(schema.to_string()),
+            db_version: None,
+            columns: None,
+            filters: Some(filters),
+            filter_logic,
+            order_by: None,
+            limit: None,
+            offset: None,
+            aggregations: None,
+            group_by: None,
+            having: None,
+            values: None,
+            set_values: None,
+            expected_version: None,
+        }
+    }
+
+    fn auditor(slow_query_threshold_ms: u64) -> (QueryAuditor, mpsc::Receiver<AuditEntry>) {
+        QueryAuditor::channel(&AuditConfig {
+            slow_query_threshold_ms,
+            ..AuditConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_filter_values_are_redacted() {
+        let query = select(
+            "challenge_a",
+            vec![
+                filter("api_key", FilterOperator::Eq, json!("sk-secret")),
+                filter("score", FilterOperator::Between, json!([17, 42])),
+            ],
+            FilterLogic::Nested(Box::new(FilterGroup {
+                logic: FilterLogic::Or,
+                filters: vec![
+                    filter("miner", FilterOperator::In, json!(["5Hotkey", "5Other"])),
+                    filter("deleted_at", FilterOperator::IsNull, json!(null)),
+                ],
+                groups: vec![],
+            })),
+        );
+
+        let summary = filter_summary(&query).unwrap();
+        assert_eq!(
+            summary,
+            "api_key = ? AND score BETWEEN ? AND ? AND (miner IN (?) OR deleted_at IS NULL)"
+        );
+        for value in ["sk-secret", "17", "42", "5Hotkey", "5Other"] {
+            assert!(
+                !summary.contains(value),
+                "{} leaked into {}",
+                value,
+                summary
+            );
+        }
+        assert_eq!(
+            filter_summary(&select("challenge_a", vec![], FilterLogic::And)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_slow_query_threshold() {
+        let (auditor, _) = auditor(500);
+        assert!(!auditor.captures_sql(Duration::from_millis(499), false));
+        assert!(auditor.captures_sql(Duration::from_millis(500), false));
+        // Failed queries are captured however fast
+        assert!(auditor.captures_sql(Duration::from_millis(1), true));
+    }
+
+    #[tokio::test]
+    async fn test_refused_query_is_audited() {
+        let (auditor, mut entries) = auditor(u64::MAX);
+        // Never connects: the query is refused before execution
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let gateway =
+            SecureORMGateway::new(ORMGatewayConfig::read_only(), pool).with_auditor(auditor);
+
+        let query = select(
+            "challenge_b",
+            vec![filter("api_key", FilterOperator::Eq, json!("sk-secret"))],
+            FilterLogic::And,
+        );
+        let scope = ChallengeSchema {
+            challenge_id: uuid::Uuid::nil(),
+            schema: "challenge_a".to_string(),
+            role: "challenge_a_rw".to_string(),
+        };
+        assert!(gateway.execute_scoped_query(&scope, query).await.is_err());
+
+        let entry = entries.try_recv().unwrap();
+        assert_eq!(entry.challenge.as_deref(), Some("challenge_a"));
+        assert_eq!(entry.operation, "select");
+        assert_eq!(entry.table_name, "submissions");
+        assert_eq!(entry.filter_summary.as_deref(), Some("api_key = ?"));
+        assert_eq!(entry.outcome, AuditOutcome::Error);
+        assert_eq!(entry.row_count, None);
+        assert!(entry.error.unwrap().contains("challenge_b"));
+        // Refused queries have their SQL captured, without the values
+        let sql = entry.sql.unwrap();
+        assert!(sql.contains("api_key = $1") && !sql.contains("sk-secret"));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_sql_captured_past_threshold_only() {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        sqlx::raw_sql(include_str!(
+            "../../storage/migrations/024_create_orm_query_audit_table.sql"
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+        let scope = provision_challenge_schema(&pool, uuid::Uuid::new_v4())
+            .await
+            .unwrap();
+        let schema = scope.schema.as_str();
+        for statement in [
+            format!(
+                "CREATE TABLE {}.submissions (id BIGINT PRIMARY KEY, api_key TEXT)",
+                schema
+            ),
+            format!("INSERT INTO {}.submissions VALUES (1, 'sk-secret')", schema),
+        ] {
+            sqlx::query(&statement).execute(&pool).await.unwrap();
+        }
+        let query = || {
+            select(
+                schema,
+                vec![filter("api_key", FilterOperator::Eq, json!("sk-secret"))],
+                FilterLogic::And,
+            )
+        };
+
+        let mut recorded = Vec::new();
+        for (threshold_ms, captured) in [(u64::MAX, false), (0, true)] {
+            let (auditor, mut entries) = auditor(threshold_ms);
+            let gateway = SecureORMGateway::new(ORMGatewayConfig::read_only(), pool.clone())
+                .with_auditor(auditor);
+            gateway.execute_scoped_query(&scope, query()).await.unwrap();
+
+            let entry = entries.try_recv().unwrap();
+            assert_eq!(entry.outcome, AuditOutcome::Success);
+            assert_eq!(entry.row_count, Some(1));
+            assert_eq!(entry.sql.is_some(), captured);
+            recorded.push(entry);
+        }
+
+        // Entries round-trip through the table and are filtered by challenge
+        sqlx::query("DELETE FROM orm_query_audit WHERE challenge = $1")
+            .bind(schema)
+            .execute(&pool)
+            .await
+            .unwrap();
+        insert_entries(&pool, &recorded).await.unwrap();
+        let listed = list_audit_entries(
+            &pool,
+            &AuditFilter {
+                challenge: Some(schema.to_string()),
+                ..AuditFilter::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed
+            .iter()
+            .all(|entry| entry.challenge.as_deref() == Some(schema)));
+
+        for statement in [
+            format!("DROP SCHEMA {} CASCADE", schema),
+            format!("DROP ROLE {}", scope.role),
+        ] {
+            sqlx::query(&statement).execute(&pool).await.unwrap();
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+use super::ORMQuery;
+
+/// Columns of a table that queries may reference
+///
+/// Queries naming any other column, in the select list, a filter, an order by,
+/// an aggregation, a group by, a having condition or the values written, are
+/// rejected. A select without an
+/// explicit column list (or with `*`) returns only the allowlisted columns.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ColumnAllowlist {
+    /// Columns allowed for every role, in the order `*` expands to
+    pub columns: Vec<String>,
+    /// Columns allowed for a specific role, replacing `columns` for that role
+    #[serde(default)]
+    pub roles: HashMap<String, Vec<String>>,
+}
+
+impl ColumnAllowlist {
+    pub fn new(columns: &[&str]) -> Self {
+        Self {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            roles: HashMap::new(),
+        }
+    }
+
+    /// Allow `columns` instead of the default ones for `role`
+    pub fn with_role(mut self, role: &str, columns: &[&str]) -> Self {
+        self.roles.insert(
+            role.to_string(),
+            columns.iter().map(|c| c.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Columns allowed for `role`
+    pub fn columns_for(&self, role: Option<&str>) -> &[String] {
+        role.and_then(|role| self.roles.get(role))
+            .unwrap_or(&self.columns)
+    }
+
+    /// Reject `query` if it references a column outside the allowlist, and
+    /// expand an implicit or `*` select list to the allowed columns
+    pub fn enforce(&self, query: &mut ORMQuery, role: Option<&str>) -> Result<()> {
+        let allowed = self.columns_for(role);
+        let check = |column: &str, clause: &str| -> Result<()> {
+            if allowed.iter().any(|c| c == column) {
+                Ok(())
+            } else {
+                warn!(
+                    table = &query.table,
+                    column = column,
+                    clause = clause,
+                    "Query references a column outside the allowlist"
+                );
+                Err(anyhow::anyhow!(
+                    "Access denied to column '{}' of table '{}' in {}",
+                    column,
+                    query.table,
+                    clause
+                ))
+            }
+        };
+
+        for column in query.columns.iter().flatten().filter(|c| *c != "*") {
+            check(column, "select list")?;
+        }
+        for filter in query.all_filters() {
+            check(&filter.column, "filter")?;
+        }
+        for order in query.order_by.iter().flatten() {
+            if !query.is_aggregation_alias(&order.column) {
+                check(&order.column, "order by")?;
+            }
+        }
+        for agg in query.aggregations.iter().flatten().filter(|a| a.column != "*") {
+            check(&agg.column, "aggregation")?;
+        }
+        for column in query.group_by.iter().flatten() {
+            check(column, "group by")?;
+        }
+        for condition in query.having.iter().flatten().filter(|h| h.column != "*") {
+            check(&condition.column, "having")?;
+        }
+        for cv in query.values.iter().flatten() {
+            check(&cv.column, "insert values")?;
+        }
+        for cv in query.set_values.iter().flatten() {
+            check(&cv.column, "update set values")?;
+        }
+
+        let implicit_columns = match &query.columns {
+            None => query.aggregations.is_none(),
+            Some(columns) => columns.iter().any(|c| c == "*"),
+        };
+        if query.operation == "select" && implicit_columns {
+            query.columns = Some(allowed.to_vec());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnValue, FilterGroup, FilterLogic, FilterOperator, OrderBy, QueryFilter};
+    use serde_json::json;
+
+    fn allowlist() -> ColumnAllowlist {
+        ColumnAllowlist::new(&["id", "name", "status"]).with_role("admin", &["id", "session_token"])
+    }
+
+    fn query(operation: &str) -> ORMQuery {
+        ORMQuery {
+            operation: operation.to_string(),
+            table: "users".to_string(),
+            schema: None,
+            db_version: None,
+            columns: None,
+            filters: None,
+            filter_logic: FilterLogic::And,
+            order_by: None,
+            limit: None,
+            offset: None,
+            aggregations: None,
+            group_by: None,
+            having: None,
+            values: None,
+            set_values: None,
+            expected_version: None,
+        }
+    }
+
+    fn filter(column: &str) -> QueryFilter {
+        QueryFilter {
+            column: column.to_string(),
+            operator: FilterOperator::Eq,
+            value: json!("x"),
+        }
+    }
+
+    fn denied(mut query: ORMQuery, clause: &str) {
+        let err = allowlist().enforce(&mut query, None).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            format!(
+                "Access denied to column 'session_token' of table 'users' in {}",
+                clause
+            )
+        );
+    }
+
+    #[test]
+    fn test_blocked_column_in_each_clause() {
+        let mut select = query("select");
+        select.columns = Some(vec!["id".to_string(), "session_token".to_string()]);
+        denied(select, "select list");
+
+        let mut filtered = query("select");
+        filtered.filters = Some(vec![filter("session_token")]);
+        denied(filtered, "filter");
+
+        let mut nested = query("count");
+        nested.filter_logic = FilterLogic::Nested(Box::new(FilterGroup {
+            logic: FilterLogic::Or,
+            filters: vec![filter("name"), filter("session_token")],
+            groups: vec![],
+        }));
+        denied(nested, "filter");
+
+        let mut ordered = query("select");
+        ordered.order_by = Some(vec![OrderBy {
+            column: "session_token".to_string(),
+            direction: "ASC".to_string(),
+        }]);
+        denied(ordered, "order by");
+
+        let mut update = query("update");
+        update.filters = Some(vec![filter("id")]);
+        update.set_values = Some(vec![
+            ColumnValue {
+                column: "name".to_string(),
+                value: json!("alice"),
+            },
+            ColumnValue {
+                column: "session_token".to_string(),
+                value: json!("stolen"),
+            },
+        ]);
+        denied(update, "update set values");
+
+        let mut grouped = query("select");
+        grouped.aggregations = Some(vec![crate::Aggregation {
+            function: "COUNT".to_string(),
+            column: "*".to_string(),
+            alias: "total".to_string(),
+        }]);
+        grouped.group_by = Some(vec!["session_token".to_string()]);
+        denied(grouped, "group by");
+    }
+
+    #[test]
+    fn test_wildcard_expands_to_allowlist() {
+        let mut implicit = query("select");
+        allowlist().enforce(&mut implicit, None).unwrap();
+        assert_eq!(
+            implicit.columns,
+            Some(vec!["id".to_string(), "name".to_string(), "status".to_string()])
+        );
+
+        let mut star = query("select");
+        star.columns = Some(vec!["*".to_string()]);
+        allowlist().enforce(&mut star, Some("admin")).unwrap();
+        assert_eq!(
+            star.columns,
+            Some(vec!["id".to_string(), "session_token".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_role_variant_replaces_default_columns() {
+        let mut admin = query("select");
+        admin.filters = Some(vec![filter("session_token")]);
+        assert!(allowlist().enforce(&mut admin, Some("admin")).is_ok());
+
+        // Roles without a variant get the default columns
+        let mut other = query("select");
+        other.filters = Some(vec![filter("session_token")]);
+        assert!(allowlist().enforce(&mut other, Some("validator")).is_err());
+
+        let mut admin_name = query("select");
+        admin_name.columns = Some(vec!["name".to_string()]);
+        assert!(allowlist().enforce(&mut admin_name, Some("admin")).is_err());
+    }
+}
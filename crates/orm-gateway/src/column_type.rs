@@ -0,0 +1,50 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Declared type of a column, used to check filter operands before execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    Text,
+    Integer,
+    Float,
+    Boolean,
+    Uuid,
+    Timestamp,
+    Json,
+}
+
+impl ColumnType {
+    /// Whether `value` is a valid operand for a column of this type
+    pub fn accepts(&self, value: &serde_json::Value) -> bool {
+        match self {
+            ColumnType::Text => value.is_string(),
+            ColumnType::Integer => value.is_i64(),
+            ColumnType::Float => value.is_number(),
+            ColumnType::Boolean => value.is_boolean(),
+            ColumnType::Uuid => value
+                .as_str()
+                .is_some_and(|s| uuid::Uuid::parse_str(s).is_ok()),
+            ColumnType::Timestamp => value.as_str().and_then(parse_timestamp).is_some(),
+            ColumnType::Json => true,
+        }
+    }
+
+    /// Whether columns of this type can be matched with `LIKE` / `ILIKE`
+    pub fn is_text(&self) -> bool {
+        matches!(self, ColumnType::Text)
+    }
+}
+
+/// Parse an RFC 3339 timestamp, or a timestamp without timezone taken as UTC
+pub(crate) fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+                .map(|ndt| ndt.and_utc())
+        })
+}
@@ -0,0 +1,32 @@
+//! Errors the gateway reports distinctly from rejected queries
+
+/// Typed gateway error, found with `anyhow::Error::downcast_ref`
+#[derive(Debug, thiserror::Error)]
+pub enum ORMError {
+    /// An UPDATE with `expected_version` matched no row at that version, so
+    /// the row was changed (or removed) since it was read
+    #[error("Version conflict on table '{table}': no matching row at version {expected_version}")]
+    VersionConflict {
+        table: String,
+        expected_version: u64,
+    },
+    /// The query ran past the statement timeout and was cancelled
+    #[error("Query cancelled after exceeding the statement timeout of {timeout_secs}s")]
+    Timeout { timeout_secs: u64 },
+    /// The query was refused before running for exceeding a guardrail: its
+    /// limit, offset or estimated cost
+    #[error("{reason}")]
+    Rejected { reason: String },
+    /// The query named a schema other than the one of the challenge it was
+    /// made for
+    #[error("Access denied to schema '{schema}', queries are confined to '{allowed}'")]
+    SchemaDenied { schema: String, allowed: String },
+}
+
+impl ORMError {
+    pub(crate) fn rejected(reason: impl Into<String>) -> Self {
+        ORMError::Rejected {
+            reason: reason.into(),
+        }
+    }
+}
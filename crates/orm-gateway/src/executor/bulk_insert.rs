@@ -0,0 +1,198 @@
+//! Multi-row INSERT execution
+
+use anyhow::Result;
+use sqlx::{Postgres, QueryBuilder};
+use std::time::Instant;
+use tracing::info;
+
+use crate::{BulkInsertQuery, ConflictPolicy};
+
+use super::{types::BindValue, QueryExecutor};
+
+/// Column returned by a bulk insert updating on conflict, true for the rows
+/// it inserted rather than updated
+const INSERTED_COLUMN: &str = "inserted";
+
+impl QueryExecutor {
+    /// Execute a validated bulk insert, as `role` if given, returning the
+    /// number of rows inserted
+    pub async fn execute_bulk_insert(
+        &self,
+        query: &BulkInsertQuery,
+        role: Option<&str>,
+    ) -> Result<u64> {
+        let start_time = Instant::now();
+        let (sql, bind_values) = self.build_bulk_insert(query)?;
+
+        let mut tx = self.begin(role).await?;
+        self.check_plan_cost(&mut *tx, &sql, &bind_values).await?;
+        let inserted = match query.conflict_policy {
+            // Updated rows are affected too, so only the returned flags tell them apart
+            ConflictPolicy::Update(_) => {
+                let rows = self.execute_raw_query(&mut *tx, &sql, bind_values).await?;
+                rows.iter()
+                    .filter(|row| row[INSERTED_COLUMN] == serde_json::Value::Bool(true))
+                    .count() as u64
+            }
+            ConflictPolicy::Error | ConflictPolicy::Ignore => {
+                self.execute_raw_statement(&mut *tx, &sql, bind_values)
+                    .await?
+            }
+        };
+        tx.commit().await?;
+
+        info!(
+            table = &query.table,
+            row_count = query.rows.len(),
+            inserted,
+            execution_time_ms = start_time.elapsed().as_millis() as u64,
+            "Bulk insert executed"
+        );
+        Ok(inserted)
+    }
+
+    /// SQL text of a bulk insert, its values left as `$n` parameters
+    pub fn bulk_insert_sql(&self, query: &BulkInsertQuery) -> Result<String> {
+        self.build_bulk_insert(query).map(|(sql, _)| sql)
+    }
+
+    /// Build a multi-row INSERT, with the ON CONFLICT clause of its policy
+    ///
+    /// A column missing from a row is inserted as `DEFAULT`.
+    pub(super) fn build_bulk_insert(
+        &self,
+        query: &BulkInsertQuery,
+    ) -> Result<(String, Vec<BindValue>)> {
+        let columns = query.columns();
+        if columns.is_empty() {
+            return Err(anyhow::anyhow!("Bulk insert requires values"));
+        }
+        let mut bind_values: Vec<BindValue> = Vec::new();
+
+        let mut builder = QueryBuilder::<Postgres>::new("INSERT INTO ");
+        if let Some(schema) = &query.schema {
+            builder.push(format_args!("{}.{}", schema, query.table));
+        } else {
+            builder.push(&query.table);
+        }
+
+        builder.push(" (");
+        let mut column_list = builder.separated(", ");
+        for column in &columns {
+            column_list.push(column);
+        }
+        builder.push(") VALUES ");
+
+        for (index, row) in query.rows.iter().enumerate() {
+            if index > 0 {
+                builder.push(", ");
+            }
+            builder.push("(");
+            let mut values = builder.separated(", ");
+            for column in &columns {
+                match row.get(*column) {
+                    Some(value) => {
+                        bind_values.push(BindValue::Json(value.clone()));
+                        values.push(format_args!("${}", bind_values.len()));
+                    }
+                    None => {
+                        values.push("DEFAULT");
+                    }
+                }
+            }
+            builder.push(")");
+        }
+
+        match &query.conflict_policy {
+            ConflictPolicy::Error => {}
+            ConflictPolicy::Ignore => {
+                builder.push(" ON CONFLICT");
+                if !query.conflict_columns.is_empty() {
+                    builder.push(format_args!(" ({})", query.conflict_columns.join(", ")));
+                }
+                builder.push(" DO NOTHING");
+            }
+            ConflictPolicy::Update(update_columns) => {
+                builder.push(format_args!(
+                    " ON CONFLICT ({}) DO UPDATE SET ",
+                    query.conflict_columns.join(", ")
+                ));
+                let mut assignments = builder.separated(", ");
+                for column in update_columns {
+                    assignments.push(format_args!("{0} = EXCLUDED.{0}", column));
+                }
+                // Rows inserted rather than updated have no deleting transaction
+                builder.push(format_args!(" RETURNING (xmax = 0) AS {}", INSERTED_COLUMN));
+            }
+        }
+
+        Ok((builder.into_sql(), bind_values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn row(values: &[(&str, serde_json::Value)]) -> HashMap<String, serde_json::Value> {
+        values
+            .iter()
+            .map(|(column, value)| (column.to_string(), value.clone()))
+            .collect()
+    }
+
+    fn bulk_insert(conflict_policy: ConflictPolicy) -> BulkInsertQuery {
+        BulkInsertQuery {
+            table: "results".to_string(),
+            schema: Some("challenge_term_v1".to_string()),
+            rows: vec![
+                row(&[("id", json!(1)), ("score", json!(0.5))]),
+                row(&[("id", json!(2)), ("note", json!("flaky"))]),
+            ],
+            conflict_policy,
+            conflict_columns: vec!["id".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert_sql() {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let executor = QueryExecutor::new(pool, 30, HashMap::new());
+
+        let (sql, bind_values) = executor
+            .build_bulk_insert(&bulk_insert(ConflictPolicy::Error))
+            .unwrap();
+        assert_eq!(
+            sql,
+            "INSERT INTO challenge_term_v1.results (id, note, score) \
+             VALUES ($1, DEFAULT, $2), ($3, $4, DEFAULT)"
+        );
+        assert_eq!(
+            bind_values,
+            vec![
+                BindValue::Json(json!(1)),
+                BindValue::Json(json!(0.5)),
+                BindValue::Json(json!(2)),
+                BindValue::Json(json!("flaky")),
+            ]
+        );
+
+        let sql = executor
+            .bulk_insert_sql(&bulk_insert(ConflictPolicy::Ignore))
+            .unwrap();
+        assert!(sql.ends_with("($3, $4, DEFAULT) ON CONFLICT (id) DO NOTHING"));
+
+        let sql = executor
+            .bulk_insert_sql(&bulk_insert(ConflictPolicy::Update(vec![
+                "score".to_string(),
+                "note".to_string(),
+            ])))
+            .unwrap();
+        assert!(sql.ends_with(
+            "ON CONFLICT (id) DO UPDATE SET score = EXCLUDED.score, note = EXCLUDED.note \
+             RETURNING (xmax = 0) AS inserted"
+        ));
+    }
+}
@@ -1,31 +1,218 @@
 //! Main query execution dispatcher
 
 use anyhow::Result;
+use sqlx::{PgConnection, PgExecutor, Postgres, Transaction};
 use std::time::Instant;
+use tracing::warn;
 
-use crate::{ORMQuery, QueryResult};
+use crate::{ORMError, ORMQuery, QueryResult, TransactionResult};
 
-use super::QueryExecutor;
+use super::{types::BindValue, QueryExecutor};
 
 impl QueryExecutor {
-    /// Execute a validated query
-    pub async fn execute(&self, query: &ORMQuery) -> Result<QueryResult> {
+    /// Execute a validated query, as `role` if given
+    ///
+    /// The query runs in its own transaction so that the statement timeout
+    /// and role apply to it alone.
+    pub async fn execute(&self, query: &ORMQuery, role: Option<&str>) -> Result<QueryResult> {
+        let mut tx = self.begin(role).await?;
+        let result = self.execute_in(&mut tx, query).await?;
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Execute validated queries in a single transaction, as `role` if given
+    ///
+    /// Either every query is committed, or the transaction is rolled back at
+    /// the first failing query and its error returned. A query taking the rows
+    /// written by the transaction past `max_rows_affected` fails with
+    /// `ORMError::Rejected`.
+    pub async fn execute_transaction(
+        &self,
+        queries: &[ORMQuery],
+        max_rows_affected: u64,
+        role: Option<&str>,
+    ) -> Result<TransactionResult> {
+        let mut tx = self.begin(role).await?;
+        let mut results = Vec::with_capacity(queries.len());
+        let mut rows_affected = 0;
+
+        for (index, query) in queries.iter().enumerate() {
+            let outcome = self.execute_in(&mut tx, query).await.and_then(|result| {
+                rows_affected += result.rows_affected;
+                if rows_affected > max_rows_affected {
+                    return Err(ORMError::rejected(format!(
+                        "Transaction affects more than {} rows",
+                        max_rows_affected
+                    ))
+                    .into());
+                }
+                Ok(result)
+            });
+            match outcome {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    warn!(index, "Query failed, rolling back transaction");
+                    tx.rollback().await?;
+                    // Kept as context so that typed errors such as
+                    // `ORMError::VersionConflict` can still be downcast
+                    let message = format!("Transaction rolled back, query {} failed: {}", index, e);
+                    return Err(e.context(message));
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(TransactionResult {
+            committed: true,
+            results,
+            rows_affected,
+        })
+    }
+
+    /// Build, cost-check and run a query on a connection
+    async fn execute_in(&self, conn: &mut PgConnection, query: &ORMQuery) -> Result<QueryResult> {
         let start_time = Instant::now();
+        let (sql, bind_values) = self.build(query)?;
+        self.check_plan_cost(&mut *conn, &sql, &bind_values).await?;
+        let result = match query.operation.as_str() {
+            // A versioned update returns the rows it updated
+            "update" if query.expected_version.is_some() => {
+                let mut result = self.run(conn, &sql, bind_values, start_time).await?;
+                result.rows_affected = result.row_count as u64;
+                result
+            }
+            "insert" | "update" | "delete" => {
+                let rows_affected = self.execute_raw_statement(conn, &sql, bind_values).await?;
+                QueryResult {
+                    rows: vec![],
+                    row_count: 0,
+                    rows_affected,
+                    execution_time_ms: start_time.elapsed().as_millis() as u64,
+                }
+            }
+            _ => self.run(conn, &sql, bind_values, start_time).await?,
+        };
+        Self::check_version_conflict(query, &result)?;
+        Ok(result)
+    }
+
+    /// Begin a transaction whose statements are cancelled past
+    /// `query_timeout`, and run with the privileges of `role` if given
+    ///
+    /// The login of the pool must be a member of `role`.
+    pub(super) async fn begin(&self, role: Option<&str>) -> Result<Transaction<'static, Postgres>> {
+        let mut tx = self.db_pool.begin().await?;
+        self.set_statement_timeout(&mut tx).await?;
+        if let Some(role) = role {
+            // The bindable form of `SET LOCAL ROLE`, as for the timeout
+            sqlx::query("SELECT set_config('role', $1, true)")
+                .bind(role)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(tx)
+    }
+
+    /// Cancel any statement of the current transaction running past `query_timeout`
+    async fn set_statement_timeout(&self, conn: &mut PgConnection) -> Result<()> {
+        // `SET LOCAL` takes no parameters; `set_config(.., true)` is its bindable form
+        sqlx::query("SELECT set_config('statement_timeout', $1, true)")
+            .bind(format!("{}s", self.query_timeout))
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// SQL text of a query, its values left as `$n` parameters
+    pub fn sql(&self, query: &ORMQuery) -> Result<String> {
+        self.build(query).map(|(sql, _)| sql)
+    }
 
+    /// SQL and bind values of a query
+    fn build(&self, query: &ORMQuery) -> Result<(String, Vec<BindValue>)> {
         match query.operation.as_str() {
-            "select" => self.execute_select(query).await,
-            "count" => self.execute_count(query).await,
-            "insert" => self.execute_insert(query).await,
-            "update" => self.execute_update(query).await,
-            "delete" => self.execute_delete(query).await,
+            "select" => self.build_select(query),
+            "count" => self.build_count(query),
+            "insert" => self.build_insert(query),
+            "update" => self.build_update(query),
+            "delete" => self.build_delete(query),
             _ => Err(anyhow::anyhow!(
                 "Unsupported operation: {}",
                 query.operation
             )),
         }
-        .map(|mut result| {
-            result.execution_time_ms = start_time.elapsed().as_millis() as u64;
-            result
+    }
+
+    /// Run built SQL on `executor`
+    async fn run<'e, E: PgExecutor<'e>>(
+        &self,
+        executor: E,
+        sql: &str,
+        bind_values: Vec<BindValue>,
+        start_time: Instant,
+    ) -> Result<QueryResult> {
+        let rows = self.execute_raw_query(executor, sql, bind_values).await?;
+
+        Ok(QueryResult {
+            row_count: rows.len(),
+            rows,
+            rows_affected: 0,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FilterLogic, ORMError};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_slow_query_hits_statement_timeout() {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = sqlx::PgPool::connect(&database_url).await.unwrap();
+
+        // The gateway cannot call functions, so the sleep is seeded behind a view
+        sqlx::query("CREATE SCHEMA IF NOT EXISTS orm_timeout_test")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "CREATE OR REPLACE VIEW orm_timeout_test.slow AS SELECT pg_sleep(3) IS NULL AS slept",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let executor = QueryExecutor::new(pool, 1, HashMap::new());
+        let query = ORMQuery {
+            operation: "select".to_string(),
+            table: "slow".to_string(),
+            schema: Some("orm_timeout_test".to_string()),
+            db_version: None,
+            columns: None,
+            filters: None,
+            filter_logic: FilterLogic::And,
+            order_by: None,
+            limit: Some(1),
+            offset: None,
+            aggregations: None,
+            group_by: None,
+            having: None,
+            values: None,
+            set_values: None,
+            expected_version: None,
+        };
+
+        let started = Instant::now();
+        let err = executor.execute(&query, None).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ORMError>(),
+            Some(ORMError::Timeout { timeout_secs: 1 })
+        ));
+        assert!(started.elapsed() < std::time::Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,90 @@
+//! Plan cost check run before a query
+
+use anyhow::Result;
+use sqlx::PgExecutor;
+use tracing::warn;
+
+use crate::ORMError;
+
+use super::{types::BindValue, QueryExecutor};
+
+impl QueryExecutor {
+    /// Reject `sql` if the planner estimates its total cost above `max_plan_cost`
+    ///
+    /// Runs `EXPLAIN` without `ANALYZE`, so the query itself is not executed.
+    /// The error carries the plan so the caller can see which node is costly.
+    pub(super) async fn check_plan_cost<'e, E: PgExecutor<'e>>(
+        &self,
+        executor: E,
+        sql: &str,
+        bind_values: &[BindValue],
+    ) -> Result<()> {
+        let Some(max_cost) = self.max_plan_cost else {
+            return Ok(());
+        };
+
+        let rows = self
+            .execute_raw_query(
+                executor,
+                &format!("EXPLAIN (FORMAT JSON) {}", sql),
+                bind_values.to_vec(),
+            )
+            .await?;
+        let plan = rows
+            .into_iter()
+            .next()
+            .and_then(|mut row| row.get_mut("QUERY PLAN").map(serde_json::Value::take))
+            .ok_or_else(|| anyhow::anyhow!("EXPLAIN returned no plan"))?;
+        let cost = plan_total_cost(&plan)
+            .ok_or_else(|| anyhow::anyhow!("EXPLAIN plan has no total cost: {}", plan))?;
+
+        if cost > max_cost {
+            warn!(cost, max_cost, "Query rejected, estimated cost too high");
+            return Err(ORMError::rejected(format!(
+                "Estimated query cost {:.2} exceeds the maximum of {:.2}, plan: {}",
+                cost, max_cost, plan
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Total cost of the root node of an `EXPLAIN (FORMAT JSON)` plan
+fn plan_total_cost(plan: &serde_json::Value) -> Option<f64> {
+    plan.get(0)?.get("Plan")?.get("Total Cost")?.as_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_plan_total_cost() {
+        let plan = json!([{
+            "Plan": {
+                "Node Type": "Seq Scan",
+                "Relation Name": "jobs",
+                "Startup Cost": 0.0,
+                "Total Cost": 18334.5,
+                "Plan Rows": 1000000,
+            }
+        }]);
+        assert_eq!(plan_total_cost(&plan), Some(18334.5));
+        assert_eq!(plan_total_cost(&json!([])), None);
+    }
+
+    #[tokio::test]
+    async fn test_check_disabled_without_threshold() {
+        // No EXPLAIN is sent when no threshold is configured, so the unreachable
+        // database is never contacted
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let executor = QueryExecutor::new(pool.clone(), 30, Default::default());
+        assert!(executor
+            .check_plan_cost(&pool, "SELECT * FROM jobs", &[])
+            .await
+            .is_ok());
+    }
+}
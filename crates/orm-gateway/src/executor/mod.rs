@@ -1,6 +1,8 @@
 //! Query executor modules
 
+mod bulk_insert;
 mod execute;
+mod explain;
 mod modify;
 mod select;
 mod types;
@@ -1,17 +1,17 @@
 //! INSERT, UPDATE, DELETE query execution
 
 use anyhow::Result;
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::{ORMQuery, QueryResult};
+use crate::{ORMError, ORMQuery, QueryResult, VERSION_COLUMN};
 
-use super::QueryExecutor;
+use super::{types::BindValue, QueryExecutor};
 
 impl QueryExecutor {
-    /// Execute INSERT query
-    pub(super) async fn execute_insert(&self, query: &ORMQuery) -> Result<QueryResult> {
+    /// Build INSERT query
+    pub(super) fn build_insert(&self, query: &ORMQuery) -> Result<(String, Vec<BindValue>)> {
         let mut sql = String::new();
-        let mut bind_values: Vec<serde_json::Value> = Vec::new();
+        let mut bind_values: Vec<BindValue> = Vec::new();
 
         sql.push_str("INSERT INTO ");
         if let Some(schema) = &query.schema {
@@ -34,22 +34,16 @@ impl QueryExecutor {
         sql.push_str(&placeholders.join(", "));
         sql.push(')');
 
-        bind_values.extend(values.into_iter().cloned());
+        bind_values.extend(values.into_iter().cloned().map(BindValue::Json));
 
         info!(sql = &sql, "Executing INSERT query");
-        let rows = self.execute_raw_query(&sql, bind_values).await?;
-
-        Ok(QueryResult {
-            row_count: rows.len(),
-            rows,
-            execution_time_ms: 0,
-        })
+        Ok((sql, bind_values))
     }
 
-    /// Execute UPDATE query
-    pub(super) async fn execute_update(&self, query: &ORMQuery) -> Result<QueryResult> {
+    /// Build UPDATE query
+    pub(super) fn build_update(&self, query: &ORMQuery) -> Result<(String, Vec<BindValue>)> {
         let mut sql = String::new();
-        let mut bind_values: Vec<serde_json::Value> = Vec::new();
+        let mut bind_values: Vec<BindValue> = Vec::new();
 
         sql.push_str("UPDATE ");
         if let Some(schema) = &query.schema {
@@ -66,33 +60,38 @@ impl QueryExecutor {
         sql.push_str(" SET ");
         let mut set_parts: Vec<String> = Vec::new();
         for cv in set_values.iter() {
-            bind_values.push(cv.value.clone());
+            bind_values.push(BindValue::Json(cv.value.clone()));
             set_parts.push(format!("{} = ${}", cv.column, bind_values.len()));
         }
+        if query.expected_version.is_some() {
+            set_parts.push(format!("{0} = {0} + 1", VERSION_COLUMN));
+        }
         sql.push_str(&set_parts.join(", "));
 
-        if let Some(filters) = &query.filters {
-            if !filters.is_empty() {
-                sql.push_str(" WHERE ");
-                let where_parts = self.build_where_clause(filters, &mut bind_values)?;
-                sql.push_str(&where_parts.join(" AND "));
+        let condition = self.build_where_condition(query, &mut bind_values)?;
+        if let Some(expected_version) = query.expected_version {
+            // Only rows still at the version read are updated, and returned
+            bind_values.push(BindValue::Json(serde_json::json!(expected_version)));
+            let version_check = format!("{} = ${}", VERSION_COLUMN, bind_values.len());
+            sql.push_str(" WHERE ");
+            match condition {
+                Some(condition) => sql.push_str(&format!("({}) AND {}", condition, version_check)),
+                None => sql.push_str(&version_check),
             }
+            sql.push_str(&format!(" RETURNING {}", VERSION_COLUMN));
+        } else if let Some(condition) = condition {
+            sql.push_str(" WHERE ");
+            sql.push_str(&condition);
         }
 
         info!(sql = &sql, "Executing UPDATE query");
-        let rows = self.execute_raw_query(&sql, bind_values).await?;
-
-        Ok(QueryResult {
-            row_count: rows.len(),
-            rows,
-            execution_time_ms: 0,
-        })
+        Ok((sql, bind_values))
     }
 
-    /// Execute DELETE query
-    pub(super) async fn execute_delete(&self, query: &ORMQuery) -> Result<QueryResult> {
+    /// Build DELETE query
+    pub(super) fn build_delete(&self, query: &ORMQuery) -> Result<(String, Vec<BindValue>)> {
         let mut sql = String::new();
-        let mut bind_values: Vec<serde_json::Value> = Vec::new();
+        let mut bind_values: Vec<BindValue> = Vec::new();
 
         sql.push_str("DELETE FROM ");
         if let Some(schema) = &query.schema {
@@ -101,21 +100,104 @@ impl QueryExecutor {
             sql.push_str(&query.table);
         }
 
-        if let Some(filters) = &query.filters {
-            if !filters.is_empty() {
-                sql.push_str(" WHERE ");
-                let where_parts = self.build_where_clause(filters, &mut bind_values)?;
-                sql.push_str(&where_parts.join(" AND "));
-            }
+        if let Some(condition) = self.build_where_condition(query, &mut bind_values)? {
+            sql.push_str(" WHERE ");
+            sql.push_str(&condition);
         }
 
         info!(sql = &sql, "Executing DELETE query");
-        let rows = self.execute_raw_query(&sql, bind_values).await?;
+        Ok((sql, bind_values))
+    }
+
+    /// Fail a versioned UPDATE that matched no row at its expected version
+    pub(super) fn check_version_conflict(query: &ORMQuery, result: &QueryResult) -> Result<()> {
+        match query.expected_version {
+            Some(expected_version) if result.row_count == 0 => {
+                warn!(
+                    table = &query.table,
+                    expected_version, "Optimistic lock conflict on UPDATE"
+                );
+                Err(ORMError::VersionConflict {
+                    table: query.table.clone(),
+                    expected_version,
+                }
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
 
-        Ok(QueryResult {
-            row_count: rows.len(),
-            rows,
-            execution_time_ms: 0,
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnValue, FilterLogic, FilterOperator, QueryFilter};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_versioned_update() {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let executor = QueryExecutor::new(pool, 30, HashMap::new());
+
+        let query = ORMQuery {
+            operation: "update".to_string(),
+            table: "results".to_string(),
+            schema: Some("challenge_term_v1".to_string()),
+            db_version: None,
+            columns: None,
+            filters: Some(vec![QueryFilter {
+                column: "id".to_string(),
+                operator: FilterOperator::Eq,
+                value: json!(7),
+            }]),
+            filter_logic: FilterLogic::And,
+            order_by: None,
+            limit: None,
+            offset: None,
+            aggregations: None,
+            group_by: None,
+            having: None,
+            values: None,
+            set_values: Some(vec![ColumnValue {
+                column: "score".to_string(),
+                value: json!(0.5),
+            }]),
+            expected_version: Some(3),
+        };
+
+        let (sql, bind_values) = executor.build_update(&query).unwrap();
+        assert_eq!(
+            sql,
+            "UPDATE challenge_term_v1.results SET score = $1, version = version + 1 \
+             WHERE (id = $2) AND version = $3 RETURNING version"
+        );
+        assert_eq!(
+            bind_values,
+            vec![
+                BindValue::Json(json!(0.5)),
+                BindValue::Json(json!(7)),
+                BindValue::Json(json!(3)),
+            ]
+        );
+
+        // No row returned means another writer bumped the version first
+        let conflict = QueryExecutor::check_version_conflict(
+            &query,
+            &QueryResult {
+                rows: vec![],
+                row_count: 0,
+                rows_affected: 0,
+                execution_time_ms: 0,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            conflict.downcast_ref::<ORMError>(),
+            Some(ORMError::VersionConflict {
+                expected_version: 3,
+                ..
+            })
+        ));
     }
 }
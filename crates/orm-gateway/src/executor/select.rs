@@ -3,24 +3,30 @@
 use anyhow::Result;
 use tracing::info;
 
-use crate::{ORMQuery, QueryResult};
+use crate::{aggregate_sql, ORMQuery, QueryFilter};
 
-use super::QueryExecutor;
+use super::{types::BindValue, QueryExecutor};
 
 impl QueryExecutor {
-    /// Execute SELECT query
-    pub(super) async fn execute_select(&self, query: &ORMQuery) -> Result<QueryResult> {
+    /// Build SELECT query
+    pub(super) fn build_select(&self, query: &ORMQuery) -> Result<(String, Vec<BindValue>)> {
         let mut sql = String::new();
-        let mut bind_values: Vec<serde_json::Value> = Vec::new();
+        let mut bind_values: Vec<BindValue> = Vec::new();
 
         sql.push_str("SELECT ");
 
         if let Some(aggregations) = &query.aggregations {
-            let agg_parts: Vec<String> = aggregations
+            // Group columns first, then the aggregates
+            let select_parts: Vec<String> = query
+                .group_by
                 .iter()
-                .map(|agg| format!("{}({}) AS {}", agg.function, agg.column, agg.alias))
+                .flatten()
+                .cloned()
+                .chain(aggregations.iter().map(|agg| {
+                    format!("{} AS {}", aggregate_sql(&agg.function, &agg.column), agg.alias)
+                }))
                 .collect();
-            sql.push_str(&agg_parts.join(", "));
+            sql.push_str(&select_parts.join(", "));
         } else if let Some(columns) = &query.columns {
             sql.push_str(&columns.join(", "));
         } else {
@@ -34,39 +40,29 @@ impl QueryExecutor {
             sql.push_str(&query.table);
         }
 
-        if let Some(filters) = &query.filters {
-            if !filters.is_empty() {
-                sql.push_str(" WHERE ");
-                let where_parts = self.build_where_clause(filters, &mut bind_values)?;
-                sql.push_str(&where_parts.join(" AND "));
-            }
+        if let Some(condition) = self.build_where_condition(query, &mut bind_values)? {
+            sql.push_str(" WHERE ");
+            sql.push_str(&condition);
         }
 
-        if query.aggregations.is_some() {
-            if let Some(columns) = &query.columns {
-                let non_agg_columns: Vec<&String> = columns
-                    .iter()
-                    .filter(|col| {
-                        query
-                            .aggregations
-                            .as_ref()
-                            .unwrap()
-                            .iter()
-                            .all(|agg| &agg.column != *col)
-                    })
-                    .collect();
+        if let Some(group_by) = query.group_by.as_ref().filter(|g| !g.is_empty()) {
+            sql.push_str(" GROUP BY ");
+            sql.push_str(&group_by.join(", "));
+        }
 
-                if !non_agg_columns.is_empty() {
-                    sql.push_str(" GROUP BY ");
-                    sql.push_str(
-                        &non_agg_columns
-                            .iter()
-                            .map(|c| c.as_str())
-                            .collect::<Vec<_>>()
-                            .join(", "),
-                    );
-                }
-            }
+        if let Some(having) = query.having.as_ref().filter(|h| !h.is_empty()) {
+            // Rendered like WHERE filters, over the aggregate instead of a column
+            let filters: Vec<QueryFilter> = having
+                .iter()
+                .map(|h| QueryFilter {
+                    column: aggregate_sql(&h.function, &h.column),
+                    operator: h.operator,
+                    value: h.value.clone(),
+                })
+                .collect();
+            let parts = self.build_where_clause(&filters, None, &mut bind_values)?;
+            sql.push_str(" HAVING ");
+            sql.push_str(&parts.join(" AND "));
         }
 
         if let Some(order_by) = &query.order_by {
@@ -88,19 +84,13 @@ impl QueryExecutor {
         }
 
         info!(sql = &sql, "Executing SELECT query");
-        let rows = self.execute_raw_query(&sql, bind_values).await?;
-
-        Ok(QueryResult {
-            row_count: rows.len(),
-            rows,
-            execution_time_ms: 0,
-        })
+        Ok((sql, bind_values))
     }
 
-    /// Execute COUNT query
-    pub(super) async fn execute_count(&self, query: &ORMQuery) -> Result<QueryResult> {
+    /// Build COUNT query
+    pub(super) fn build_count(&self, query: &ORMQuery) -> Result<(String, Vec<BindValue>)> {
         let mut sql = String::new();
-        let mut bind_values: Vec<serde_json::Value> = Vec::new();
+        let mut bind_values: Vec<BindValue> = Vec::new();
 
         sql.push_str("SELECT COUNT(*) as count FROM ");
         if let Some(schema) = &query.schema {
@@ -109,21 +99,81 @@ impl QueryExecutor {
             sql.push_str(&query.table);
         }
 
-        if let Some(filters) = &query.filters {
-            if !filters.is_empty() {
-                sql.push_str(" WHERE ");
-                let where_parts = self.build_where_clause(filters, &mut bind_values)?;
-                sql.push_str(&where_parts.join(" AND "));
-            }
+        if let Some(condition) = self.build_where_condition(query, &mut bind_values)? {
+            sql.push_str(" WHERE ");
+            sql.push_str(&condition);
         }
 
         info!(sql = &sql, "Executing COUNT query");
-        let rows = self.execute_raw_query(&sql, bind_values).await?;
+        Ok((sql, bind_values))
+    }
+}
 
-        Ok(QueryResult {
-            row_count: rows.len(),
-            rows,
-            execution_time_ms: 0,
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Aggregation, FilterLogic, FilterOperator, HavingFilter, OrderBy};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_grouped_count_on_jobs() {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let executor = QueryExecutor::new(pool, 30, HashMap::new());
+        let challenge_id = uuid::Uuid::nil();
+
+        let query = ORMQuery {
+            operation: "select".to_string(),
+            table: "jobs".to_string(),
+            schema: None,
+            db_version: None,
+            columns: None,
+            filters: Some(vec![QueryFilter {
+                column: "challenge_id".to_string(),
+                operator: FilterOperator::Eq,
+                value: json!(challenge_id),
+            }]),
+            filter_logic: FilterLogic::And,
+            order_by: Some(vec![OrderBy {
+                column: "job_count".to_string(),
+                direction: "DESC".to_string(),
+            }]),
+            limit: Some(50),
+            offset: None,
+            aggregations: Some(vec![
+                Aggregation {
+                    function: "count".to_string(),
+                    column: "*".to_string(),
+                    alias: "job_count".to_string(),
+                },
+                Aggregation {
+                    function: "max".to_string(),
+                    column: "retry_count".to_string(),
+                    alias: "max_retries_used".to_string(),
+                },
+            ]),
+            group_by: Some(vec!["status".to_string(), "runtime".to_string()]),
+            having: Some(vec![HavingFilter {
+                function: "COUNT".to_string(),
+                column: "*".to_string(),
+                operator: FilterOperator::Gte,
+                value: json!(2),
+            }]),
+            values: None,
+            set_values: None,
+            expected_version: None,
+        };
+
+        let (sql, bind_values) = executor.build_select(&query).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT status, runtime, COUNT(*) AS job_count, MAX(retry_count) AS max_retries_used \
+             FROM jobs WHERE challenge_id = $1 GROUP BY status, runtime HAVING COUNT(*) >= $2 \
+             ORDER BY job_count DESC LIMIT 50"
+        );
+        assert_eq!(
+            bind_values,
+            vec![BindValue::Json(json!(challenge_id)), BindValue::Json(json!(2))]
+        );
     }
 }
@@ -1,18 +1,96 @@
 //! Query executor types
 
-use sqlx::PgPool;
+use sqlx::postgres::{PgArguments, Postgres};
+use sqlx::{Arguments, PgPool, TypeInfo};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{ColumnType, StatementCache};
 
 /// Query executor for safe SQL execution
 pub struct QueryExecutor {
     pub(super) db_pool: PgPool,
     pub(super) query_timeout: u64,
+    /// Declared column types, per table
+    pub(super) column_types: HashMap<String, HashMap<String, ColumnType>>,
+    /// Estimated plan cost above which queries are rejected before running
+    pub(super) max_plan_cost: Option<f64>,
+    /// Shapes run as prepared statements; disabled unless set
+    pub(super) statement_cache: Arc<StatementCache>,
 }
 
 impl QueryExecutor {
-    pub fn new(db_pool: PgPool, query_timeout: u64) -> Self {
+    pub fn new(
+        db_pool: PgPool,
+        query_timeout: u64,
+        column_types: HashMap<String, HashMap<String, ColumnType>>,
+    ) -> Self {
         Self {
             db_pool,
             query_timeout,
+            column_types,
+            max_plan_cost: None,
+            statement_cache: Arc::new(StatementCache::new(0)),
+        }
+    }
+
+    /// Check the `EXPLAIN` cost of every query against `max_plan_cost` before running it
+    pub fn with_max_plan_cost(mut self, max_plan_cost: Option<f64>) -> Self {
+        self.max_plan_cost = max_plan_cost;
+        self
+    }
+
+    /// Run repeated query shapes as prepared statements cached in `statement_cache`
+    pub fn with_statement_cache(mut self, statement_cache: Arc<StatementCache>) -> Self {
+        self.statement_cache = statement_cache;
+        self
+    }
+}
+
+/// Value bound to a query parameter
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum BindValue {
+    /// Scalar, or JSON for arrays and objects
+    Json(serde_json::Value),
+    /// List bound as a single Postgres array, typed after the column when declared
+    List(Vec<serde_json::Value>, Option<ColumnType>),
+}
+
+/// Values bound to the parameters of a query, with their Postgres types
+#[derive(Default)]
+pub(super) struct Parameters {
+    arguments: PgArguments,
+    /// Types of the parameters, comma-separated
+    pub(super) types: String,
+    /// First value that failed to encode, reported on execution like `Query::bind` does
+    error: Option<sqlx::error::BoxDynError>,
+}
+
+impl Parameters {
+    /// Bind `value` to the next parameter
+    pub(super) fn bind<T>(mut self, value: T) -> Self
+    where
+        T: 'static + sqlx::Encode<'static, Postgres> + sqlx::Type<Postgres>,
+    {
+        if !self.types.is_empty() {
+            self.types.push(',');
+        }
+        self.types.push_str(T::type_info().name());
+        if let Err(e) = Arguments::add(&mut self.arguments, value) {
+            self.error.get_or_insert(e);
+        }
+        self
+    }
+
+    /// Query running `sql` with these parameters
+    pub(super) fn query(
+        self,
+        sql: &str,
+        persistent: bool,
+    ) -> anyhow::Result<sqlx::query::Query<'_, Postgres, PgArguments>> {
+        if let Some(e) = self.error {
+            return Err(anyhow::anyhow!("Failed to encode query parameter: {}", e));
         }
+        Ok(sqlx::query_with(sql, self.arguments).persistent(persistent))
     }
 }
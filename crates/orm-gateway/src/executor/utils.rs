@@ -4,65 +4,150 @@ use anyhow::{Context, Result};
 use base64::{engine::general_purpose::STANDARD as base64_engine, Engine as _};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use sqlx::{Column, Row, TypeInfo};
+use std::collections::HashMap;
 use std::str::FromStr;
+use tracing::warn;
 
-use crate::QueryFilter;
+use crate::column_type::parse_timestamp;
+use crate::{
+    ColumnType, FilterGroup, FilterLogic, FilterOperator, ORMError, ORMQuery, QueryFilter,
+};
 
-use super::QueryExecutor;
+use super::{
+    types::{BindValue, Parameters},
+    QueryExecutor,
+};
+
+/// Declared types of the columns of one table
+type ColumnTypes = HashMap<String, ColumnType>;
 
 impl QueryExecutor {
+    /// Build the full WHERE condition of a query, honouring `filter_logic`
+    ///
+    /// Returns `None` when the query has no filters at all.
+    pub(super) fn build_where_condition(
+        &self,
+        query: &ORMQuery,
+        bind_values: &mut Vec<BindValue>,
+    ) -> Result<Option<String>> {
+        let types = self.column_types.get(&query.table);
+        let filters = query.filters.as_deref().unwrap_or(&[]);
+        let parts = self.build_where_clause(filters, types, bind_values)?;
+        self.combine_filter_parts(parts, &query.filter_logic, types, bind_values)
+    }
+
+    /// Build a parenthesizable condition for a filter group and its sub-groups
+    fn build_group_condition(
+        &self,
+        group: &FilterGroup,
+        types: Option<&ColumnTypes>,
+        bind_values: &mut Vec<BindValue>,
+    ) -> Result<Option<String>> {
+        let mut parts = self.build_where_clause(&group.filters, types, bind_values)?;
+        for sub_group in &group.groups {
+            if let Some(condition) = self.build_group_condition(sub_group, types, bind_values)? {
+                parts.push(format!("({})", condition));
+            }
+        }
+        self.combine_filter_parts(parts, &group.logic, types, bind_values)
+    }
+
+    /// Join condition parts according to the filter logic
+    fn combine_filter_parts(
+        &self,
+        mut parts: Vec<String>,
+        logic: &FilterLogic,
+        types: Option<&ColumnTypes>,
+        bind_values: &mut Vec<BindValue>,
+    ) -> Result<Option<String>> {
+        let separator = match logic {
+            FilterLogic::And => " AND ",
+            FilterLogic::Or => " OR ",
+            FilterLogic::Nested(group) => {
+                if let Some(condition) = self.build_group_condition(group, types, bind_values)? {
+                    parts.push(format!("({})", condition));
+                }
+                " AND "
+            }
+        };
+
+        if parts.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(parts.join(separator)))
+        }
+    }
+
     /// Build WHERE clause from filters
+    ///
+    /// Values are only ever bound as parameters; the SQL text holds the
+    /// validated column names, fixed operator text and placeholders.
     pub(super) fn build_where_clause(
         &self,
         filters: &[QueryFilter],
-        bind_values: &mut Vec<serde_json::Value>,
+        types: Option<&ColumnTypes>,
+        bind_values: &mut Vec<BindValue>,
     ) -> Result<Vec<String>> {
         let mut parts = Vec::new();
 
         for filter in filters {
-            let part = match filter.operator.to_uppercase().as_str() {
-                "=" | "!=" | "<" | "<=" | ">" | ">=" => {
-                    bind_values.push(filter.value.clone());
+            let part = match filter.operator {
+                FilterOperator::Eq
+                | FilterOperator::NotEq
+                | FilterOperator::Lt
+                | FilterOperator::Lte
+                | FilterOperator::Gt
+                | FilterOperator::Gte
+                | FilterOperator::Like
+                | FilterOperator::NotLike
+                | FilterOperator::ILike
+                | FilterOperator::NotILike => {
+                    bind_values.push(BindValue::Json(filter.value.clone()));
                     format!(
                         "{} {} ${}",
                         filter.column,
-                        filter.operator,
+                        filter.operator.as_sql(),
                         bind_values.len()
                     )
                 }
-                "IN" | "NOT IN" => {
+                FilterOperator::In | FilterOperator::NotIn => {
                     let array = filter
                         .value
                         .as_array()
                         .ok_or_else(|| anyhow::anyhow!("IN operator requires array value"))?;
 
-                    let placeholders: Vec<String> = array
-                        .iter()
-                        .map(|v| {
-                            bind_values.push(v.clone());
-                            format!("${}", bind_values.len())
-                        })
-                        .collect();
-
-                    format!(
-                        "{} {} ({})",
-                        filter.column,
-                        filter.operator,
-                        placeholders.join(", ")
-                    )
+                    // The whole list is a single array parameter
+                    let column_type = types.and_then(|t| t.get(&filter.column)).copied();
+                    bind_values.push(BindValue::List(array.clone(), column_type));
+                    let comparison = if filter.operator == FilterOperator::In {
+                        "= ANY"
+                    } else {
+                        "<> ALL"
+                    };
+                    format!("{} {}(${})", filter.column, comparison, bind_values.len())
                 }
-                "LIKE" | "NOT LIKE" => {
-                    bind_values.push(filter.value.clone());
+                FilterOperator::Between | FilterOperator::NotBetween => {
+                    let bounds = filter
+                        .value
+                        .as_array()
+                        .filter(|bounds| bounds.len() == 2)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("BETWEEN operator requires [low, high] array value")
+                        })?;
+
+                    bind_values.push(BindValue::Json(bounds[0].clone()));
+                    bind_values.push(BindValue::Json(bounds[1].clone()));
                     format!(
-                        "{} {} ${}",
+                        "{} {} ${} AND ${}",
                         filter.column,
-                        filter.operator,
+                        filter.operator.as_sql(),
+                        bind_values.len() - 1,
                         bind_values.len()
                     )
                 }
-                "IS NULL" => format!("{} IS NULL", filter.column),
-                "IS NOT NULL" => format!("{} IS NOT NULL", filter.column),
-                _ => return Err(anyhow::anyhow!("Unsupported operator: {}", filter.operator)),
+                FilterOperator::IsNull | FilterOperator::IsNotNull => {
+                    format!("{} {}", filter.column, filter.operator.as_sql())
+                }
             };
 
             parts.push(part);
@@ -72,14 +157,60 @@ impl QueryExecutor {
     }
 
     /// Execute raw SQL query with bindings
-    pub(super) async fn execute_raw_query(
+    pub(super) async fn execute_raw_query<'e, E: sqlx::PgExecutor<'e>>(
         &self,
+        executor: E,
         sql: &str,
-        bind_values: Vec<serde_json::Value>,
+        bind_values: Vec<BindValue>,
     ) -> Result<Vec<serde_json::Value>> {
-        let mut query = sqlx::query(sql);
+        let parameters = self.bind_parameters(bind_values)?;
+        let statement = self.statement_cache.statement(sql, &parameters.types);
+        let query = parameters.query(&statement.sql, statement.persistent)?;
+        let rows = self.with_timeout(sql, query.fetch_all(executor)).await?;
+
+        let mut json_rows = Vec::new();
+        for row in rows {
+            let mut json_row = serde_json::Map::new();
+
+            for (i, column) in row.columns().iter().enumerate() {
+                let column_name = column.name();
+                let type_name = column.type_info().name();
+                let value = self.convert_column_value(&row, i, type_name);
+                json_row.insert(column_name.to_string(), value);
+            }
+
+            json_rows.push(serde_json::Value::Object(json_row));
+        }
+
+        Ok(json_rows)
+    }
+
+    /// Execute a raw SQL statement returning no rows, and return the number of rows it affected
+    pub(super) async fn execute_raw_statement<'e, E: sqlx::PgExecutor<'e>>(
+        &self,
+        executor: E,
+        sql: &str,
+        bind_values: Vec<BindValue>,
+    ) -> Result<u64> {
+        let parameters = self.bind_parameters(bind_values)?;
+        let statement = self.statement_cache.statement(sql, &parameters.types);
+        let query = parameters.query(&statement.sql, statement.persistent)?;
+        let result = self.with_timeout(sql, query.execute(executor)).await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Bind the values of a query
+    fn bind_parameters(&self, bind_values: Vec<BindValue>) -> Result<Parameters> {
+        let mut query = Parameters::default();
 
         for value in bind_values {
+            let value = match value {
+                BindValue::Json(value) => value,
+                BindValue::List(values, column_type) => {
+                    query = Self::bind_list(query, &values, column_type)?;
+                    continue;
+                }
+            };
             query = match value {
                 serde_json::Value::Null => query.bind(None::<String>),
                 serde_json::Value::Bool(b) => query.bind(b),
@@ -114,11 +245,19 @@ impl QueryExecutor {
             };
         }
 
-        let query = query.persistent(false);
+        Ok(query)
+    }
 
-        let rows_result = tokio::time::timeout(
+    /// Await the execution of `sql`, mapping a timeout or statement
+    /// cancellation to `ORMError::Timeout`
+    async fn with_timeout<T>(
+        &self,
+        sql: &str,
+        execution: impl std::future::Future<Output = Result<T, sqlx::Error>>,
+    ) -> Result<T> {
+        let result = tokio::time::timeout(
             std::time::Duration::from_secs(self.query_timeout),
-            query.fetch_all(&self.db_pool),
+            execution,
         )
         .await
         .context(format!(
@@ -126,9 +265,40 @@ impl QueryExecutor {
             self.query_timeout
         ));
 
-        let rows = match rows_result {
-            Ok(Ok(rows)) => rows,
+        match result {
+            Ok(Ok(value)) => Ok(value),
             Ok(Err(e)) => {
+                // query_canceled, raised when the statement timeout is reached
+                if e.as_database_error()
+                    .and_then(|db_err| db_err.code())
+                    .as_deref()
+                    == Some("57014")
+                {
+                    warn!(
+                        sql = sql,
+                        timeout_secs = self.query_timeout,
+                        "Query cancelled by statement timeout"
+                    );
+                    return Err(ORMError::Timeout {
+                        timeout_secs: self.query_timeout,
+                    }
+                    .into());
+                }
+
+                // feature_not_supported, raised when the result columns of a
+                // prepared statement changed, e.g. a table behind `SELECT *` was altered
+                if e.as_database_error()
+                    .and_then(|db_err| db_err.code())
+                    .as_deref()
+                    == Some("0A000")
+                {
+                    warn!(
+                        sql = sql,
+                        "Prepared statement outdated, invalidating the statement cache"
+                    );
+                    self.statement_cache.invalidate();
+                }
+
                 let mut error_msg = format!("Query execution failed for SQL: {}", sql);
                 if let Some(db_err) = e.as_database_error() {
                     error_msg.push_str(&format!("\nPostgreSQL Error Code: {:?}", db_err.code()));
@@ -143,40 +313,70 @@ impl QueryExecutor {
                 } else {
                     error_msg.push_str(&format!("\nError: {}", e));
                 }
-                return Err(anyhow::anyhow!(error_msg));
+                Err(anyhow::anyhow!(error_msg))
             }
             Err(_) => {
-                return Err(anyhow::anyhow!(
-                    "Query timeout after {} seconds for SQL: {}",
-                    self.query_timeout,
-                    sql
-                ));
+                warn!(
+                    sql = sql,
+                    timeout_secs = self.query_timeout,
+                    "Query timed out"
+                );
+                Err(ORMError::Timeout {
+                    timeout_secs: self.query_timeout,
+                }
+                .into())
             }
-        };
+        }
+    }
 
-        let mut json_rows = Vec::new();
-        for row in rows {
-            let mut json_row = serde_json::Map::new();
+    /// Bind a list as a single typed array
+    ///
+    /// Without a declared column type, the array type follows the values,
+    /// which must then all be of the same JSON type.
+    fn bind_list(
+        query: Parameters,
+        values: &[serde_json::Value],
+        column_type: Option<ColumnType>,
+    ) -> Result<Parameters> {
+        fn collect<T>(
+            values: &[serde_json::Value],
+            convert: impl Fn(&serde_json::Value) -> Option<T>,
+        ) -> Option<Vec<T>> {
+            values.iter().map(convert).collect()
+        }
 
-            for (i, column) in row.columns().iter().enumerate() {
-                let column_name = column.name();
-                let type_name = column.type_info().name();
-                let value = self.convert_column_value(&row, i, type_name);
-                json_row.insert(column_name.to_string(), value);
+        let column_type = column_type.or_else(|| match values.first() {
+            Some(serde_json::Value::Bool(_)) => Some(ColumnType::Boolean),
+            Some(serde_json::Value::Number(_)) if values.iter().all(|v| v.is_i64()) => {
+                Some(ColumnType::Integer)
             }
+            Some(serde_json::Value::Number(_)) => Some(ColumnType::Float),
+            Some(serde_json::Value::String(_)) => Some(ColumnType::Text),
+            _ => None,
+        });
 
-            json_rows.push(serde_json::Value::Object(json_row));
-        }
+        let query = match column_type {
+            Some(ColumnType::Text) => {
+                collect(values, |v| v.as_str().map(str::to_string)).map(|v| query.bind(v))
+            }
+            Some(ColumnType::Integer) => collect(values, |v| v.as_i64()).map(|v| query.bind(v)),
+            Some(ColumnType::Float) => collect(values, |v| v.as_f64()).map(|v| query.bind(v)),
+            Some(ColumnType::Boolean) => collect(values, |v| v.as_bool()).map(|v| query.bind(v)),
+            Some(ColumnType::Uuid) => collect(values, |v| {
+                v.as_str().and_then(|s| uuid::Uuid::parse_str(s).ok())
+            })
+            .map(|v| query.bind(v)),
+            Some(ColumnType::Timestamp) => {
+                collect(values, |v| v.as_str().and_then(parse_timestamp)).map(|v| query.bind(v))
+            }
+            Some(ColumnType::Json) | None => None,
+        };
 
-        Ok(json_rows)
+        query.ok_or_else(|| anyhow::anyhow!("IN list values must all be scalars of the same type"))
     }
 
     /// Bind timestamp or string to query
-    fn bind_timestamp_or_string<'a>(
-        &self,
-        mut query: sqlx::query::Query<'a, sqlx::Postgres, sqlx::postgres::PgArguments>,
-        s: &str,
-    ) -> sqlx::query::Query<'a, sqlx::Postgres, sqlx::postgres::PgArguments> {
+    fn bind_timestamp_or_string(&self, mut query: Parameters, s: &str) -> Parameters {
         if s.len() >= 19 && (s.contains('T') || s.contains(' ')) {
             if let Ok(ndt) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f") {
                 query.bind(ndt)
@@ -256,3 +456,97 @@ impl QueryExecutor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn filter(column: &str, operator: FilterOperator, value: serde_json::Value) -> QueryFilter {
+        QueryFilter {
+            column: column.to_string(),
+            operator,
+            value,
+        }
+    }
+
+    fn executor() -> QueryExecutor {
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        QueryExecutor::new(pool, 30, HashMap::new())
+    }
+
+    #[tokio::test]
+    async fn test_list_and_range_values_bound_as_parameters() {
+        let types = HashMap::from([("job_id".to_string(), ColumnType::Uuid)]);
+
+        let mut bind_values = Vec::new();
+        let parts = executor()
+            .build_where_clause(
+                &[
+                    filter("status", FilterOperator::In, json!(["pending", "running"])),
+                    filter("job_id", FilterOperator::NotIn, json!([uuid::Uuid::nil()])),
+                    filter("priority", FilterOperator::NotBetween, json!([2, 5])),
+                    filter("error", FilterOperator::IsNull, json!(null)),
+                    filter("id", FilterOperator::Gt, json!(7)),
+                ],
+                Some(&types),
+                &mut bind_values,
+            )
+            .unwrap();
+
+        assert_eq!(
+            parts,
+            vec![
+                "status = ANY($1)",
+                "job_id <> ALL($2)",
+                "priority NOT BETWEEN $3 AND $4",
+                "error IS NULL",
+                "id > $5",
+            ]
+        );
+        assert_eq!(
+            bind_values,
+            vec![
+                BindValue::List(vec![json!("pending"), json!("running")], None),
+                BindValue::List(vec![json!(uuid::Uuid::nil())], Some(ColumnType::Uuid)),
+                BindValue::Json(json!(2)),
+                BindValue::Json(json!(5)),
+                BindValue::Json(json!(7)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_like_pattern_is_never_interpolated() {
+        let injection = "%' OR 1=1; DROP TABLE users; --";
+
+        let mut bind_values = Vec::new();
+        let parts = executor()
+            .build_where_clause(
+                &[
+                    filter("name", FilterOperator::Like, json!(injection)),
+                    filter("name", FilterOperator::NotILike, json!(injection)),
+                ],
+                None,
+                &mut bind_values,
+            )
+            .unwrap();
+
+        assert_eq!(parts, vec!["name LIKE $1", "name NOT ILIKE $2"]);
+        assert!(parts.iter().all(|part| !part.contains("DROP")));
+        assert_eq!(
+            bind_values,
+            vec![BindValue::Json(json!(injection)), BindValue::Json(json!(injection))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mixed_list_is_refused() {
+        let parameters = Parameters::default();
+        assert!(QueryExecutor::bind_list(parameters, &[json!(1), json!("one")], None).is_err());
+
+        let parameters =
+            QueryExecutor::bind_list(Parameters::default(), &[json!(1), json!(2.5)], None).unwrap();
+        assert_eq!(parameters.types, "FLOAT8[]");
+    }
+}
@@ -3,13 +3,27 @@
 //! This crate provides a secure ORM gateway that allows challenges to execute
 //! SQL queries with schema isolation and permission validation.
 
+pub mod audit;
+pub mod column_allowlist;
+pub mod column_type;
+mod error;
 mod executor;
 mod mod_rs;
 pub mod permissions;
 pub mod query_validator;
+pub mod schema_isolation;
+pub mod statement_cache;
 
+pub use audit::{AuditConfig, AuditEntry, AuditFilter, AuditOutcome, QueryAuditor};
+pub use column_allowlist::ColumnAllowlist;
+pub use column_type::ColumnType;
+pub use error::ORMError;
 pub use executor::QueryExecutor;
 pub use mod_rs::*;
 pub use permissions::{ORMPermissions, TablePermission};
 pub use query_validator::QueryValidator;
+pub use schema_isolation::{
+    challenge_schema, provision_challenge_schema, ChallengeSchema, CHALLENGE_SCHEMA_PREFIX,
+};
+pub use statement_cache::{StatementCache, StatementCacheStats};
 
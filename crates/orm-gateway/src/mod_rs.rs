@@ -1,29 +1,126 @@
 use anyhow::Result;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::info;
 
-use crate::{executor::QueryExecutor, permissions::{ORMPermissions, TablePermission}, query_validator::QueryValidator};
+use crate::{
+    audit::{filter_summary, AuditEntry, AuditOutcome, QueryAuditor},
+    column_allowlist::ColumnAllowlist,
+    column_type::ColumnType,
+    executor::QueryExecutor,
+    permissions::{ORMPermissions, TablePermission},
+    query_validator::QueryValidator,
+    schema_isolation::{confine_to_schema, ChallengeSchema},
+    statement_cache::{StatementCache, StatementCacheStats},
+    ORMError,
+};
 
 /// Configuration for ORM Gateway
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ORMGatewayConfig {
     pub max_query_limit: usize,
+    /// Limit of a select that gives none
+    #[serde(default = "default_query_limit")]
+    pub default_query_limit: usize,
+    /// Largest offset accepted; deeper pages must use keyset pagination
+    #[serde(default = "default_max_query_offset")]
+    pub max_query_offset: usize,
+    /// Statement timeout of each query, in seconds
     pub query_timeout: u64,
     pub allowed_operations: Vec<String>,
     pub enable_aggregations: bool,
     pub read_only: bool, // If true, only SELECT/COUNT allowed
+    /// Columns queries may reference, per table; tables without an entry expose all columns
+    #[serde(default)]
+    pub column_allowlists: HashMap<String, ColumnAllowlist>,
+    /// Role of the callers, selecting the role variant of the column allowlists
+    #[serde(default)]
+    pub role: Option<String>,
+    /// Declared column types, per table; filter operands are checked against them
+    #[serde(default)]
+    pub column_types: HashMap<String, HashMap<String, ColumnType>>,
+    /// Maximum number of values in an `IN` / `NOT IN` list
+    #[serde(default = "default_max_in_list_len")]
+    pub max_in_list_len: usize,
+    /// When set, each query is first run through `EXPLAIN` and rejected if the
+    /// planner's estimated total cost exceeds this value
+    #[serde(default)]
+    pub max_plan_cost: Option<f64>,
+    /// Schemas queries may name; a trailing `*` matches any suffix. Empty
+    /// allows every schema except the system ones, which are always rejected
+    #[serde(default)]
+    pub allowed_schemas: Vec<String>,
+    /// Tables queries may name; empty allows every table
+    #[serde(default)]
+    pub allowed_tables: Vec<String>,
+    /// Maximum number of statements in a transaction
+    #[serde(default = "default_max_transaction_statements")]
+    pub max_transaction_statements: usize,
+    /// Maximum number of rows all the statements of a transaction may write
+    #[serde(default = "default_max_transaction_rows")]
+    pub max_transaction_rows: u64,
+    /// Query shapes kept as prepared statements, see `statement_cache`; 0 disables it
+    #[serde(default = "default_statement_cache_capacity")]
+    pub statement_cache_capacity: usize,
+    /// Maximum number of rows of a bulk insert
+    #[serde(default = "default_max_bulk_insert_rows")]
+    pub max_bulk_insert_rows: usize,
+}
+
+fn default_max_bulk_insert_rows() -> usize {
+    1000
+}
+
+fn default_max_transaction_statements() -> usize {
+    16
+}
+
+fn default_max_transaction_rows() -> u64 {
+    1000
+}
+
+fn default_statement_cache_capacity() -> usize {
+    // Statements prepared per connection by sqlx by default
+    100
+}
+
+fn default_max_in_list_len() -> usize {
+    1000
+}
+
+fn default_query_limit() -> usize {
+    100
+}
+
+fn default_max_query_offset() -> usize {
+    10_000
 }
 
 impl Default for ORMGatewayConfig {
     fn default() -> Self {
         Self {
             max_query_limit: 1000,
+            default_query_limit: default_query_limit(),
+            max_query_offset: default_max_query_offset(),
             query_timeout: 30,
             allowed_operations: vec!["select".to_string(), "count".to_string()],
             enable_aggregations: true,
             read_only: true, // Default to read-only
+            column_allowlists: HashMap::new(),
+            role: None,
+            column_types: HashMap::new(),
+            max_in_list_len: default_max_in_list_len(),
+            max_plan_cost: None,
+            allowed_schemas: Vec::new(),
+            allowed_tables: Vec::new(),
+            max_transaction_statements: default_max_transaction_statements(),
+            max_transaction_rows: default_max_transaction_rows(),
+            statement_cache_capacity: default_statement_cache_capacity(),
+            max_bulk_insert_rows: default_max_bulk_insert_rows(),
         }
     }
 }
@@ -33,6 +130,8 @@ impl ORMGatewayConfig {
     pub fn read_write() -> Self {
         Self {
             max_query_limit: 1000,
+            default_query_limit: default_query_limit(),
+            max_query_offset: default_max_query_offset(),
             query_timeout: 30,
             allowed_operations: vec![
                 "select".to_string(),
@@ -43,6 +142,17 @@ impl ORMGatewayConfig {
             ],
             enable_aggregations: true,
             read_only: false,
+            column_allowlists: HashMap::new(),
+            role: None,
+            column_types: HashMap::new(),
+            max_in_list_len: default_max_in_list_len(),
+            max_plan_cost: None,
+            allowed_schemas: Vec::new(),
+            allowed_tables: Vec::new(),
+            max_transaction_statements: default_max_transaction_statements(),
+            max_transaction_rows: default_max_transaction_rows(),
+            statement_cache_capacity: default_statement_cache_capacity(),
+            max_bulk_insert_rows: default_max_bulk_insert_rows(),
         }
     }
 
@@ -59,13 +169,18 @@ pub struct SecureORMGateway {
     permissions: ORMPermissions,
     query_validator: QueryValidator,
     query_executor: QueryExecutor,
+    /// Repeated query shapes, kept across config changes
+    statement_cache: Arc<StatementCache>,
+    /// Records every query; none are audited without it
+    auditor: Option<QueryAuditor>,
 }
 
 impl SecureORMGateway {
     pub fn new(config: ORMGatewayConfig, db_pool: PgPool) -> Self {
         let permissions = ORMPermissions::new();
         let query_validator = QueryValidator::new(config.clone());
-        let query_executor = QueryExecutor::new(db_pool.clone(), config.query_timeout);
+        let statement_cache = Arc::new(StatementCache::new(config.statement_cache_capacity));
+        let query_executor = Self::query_executor(&config, &db_pool, &statement_cache);
 
         Self {
             config,
@@ -73,11 +188,359 @@ impl SecureORMGateway {
             permissions,
             query_validator,
             query_executor,
+            statement_cache,
+            auditor: None,
         }
     }
 
+    fn query_executor(
+        config: &ORMGatewayConfig,
+        db_pool: &PgPool,
+        statement_cache: &Arc<StatementCache>,
+    ) -> QueryExecutor {
+        QueryExecutor::new(
+            db_pool.clone(),
+            config.query_timeout,
+            config.column_types.clone(),
+        )
+        .with_max_plan_cost(config.max_plan_cost)
+        .with_statement_cache(statement_cache.clone())
+    }
+
+    /// Replace the configuration, e.g. with new allowlists
+    ///
+    /// Invalidates the statement cache, so no statement prepared under the
+    /// previous configuration is reused.
+    pub fn set_config(&mut self, config: ORMGatewayConfig) {
+        self.statement_cache.set_capacity(config.statement_cache_capacity);
+        self.query_validator = QueryValidator::new(config.clone());
+        self.query_executor = Self::query_executor(&config, &self.db_pool, &self.statement_cache);
+        self.config = config;
+    }
+
+    /// Hit and miss counters of the statement cache
+    pub fn statement_cache_stats(&self) -> StatementCacheStats {
+        self.statement_cache.stats()
+    }
+
+    /// Record every query executed or refused through the gateway with `auditor`
+    pub fn with_auditor(mut self, auditor: QueryAuditor) -> Self {
+        self.auditor = Some(auditor);
+        self
+    }
+
     /// Execute a query (read or write depending on config)
     pub async fn execute_query(&self, query: ORMQuery) -> Result<QueryResult> {
+        self.execute_audited(None, query).await
+    }
+
+    /// Execute queries in a single transaction, returning the result of each
+    ///
+    /// Every query must pass the same checks as `execute_query` before any is
+    /// run, and transactions may not be nested. The transaction is refused if
+    /// it has more than `max_transaction_statements` queries, and rolled back
+    /// if a query fails or the queries write more than `max_transaction_rows`
+    /// rows.
+    pub async fn execute_transaction(&self, queries: Vec<ORMQuery>) -> Result<TransactionResult> {
+        self.execute_transaction_audited(None, queries).await
+    }
+
+    /// Execute a query on behalf of the challenge owning `scope`
+    ///
+    /// The scope is derived by the caller from the ID of the authenticated
+    /// challenge, see [`ChallengeSchema::for_challenge`]. A query naming any
+    /// other schema fails with `ORMError::SchemaDenied`, and the query runs as
+    /// the challenge role, so the database itself keeps it to its schema.
+    pub async fn execute_scoped_query(
+        &self,
+        scope: &ChallengeSchema,
+        query: ORMQuery,
+    ) -> Result<QueryResult> {
+        self.execute_audited(Some(scope), query).await
+    }
+
+    /// Execute a transaction on behalf of the challenge owning `scope`, see
+    /// `execute_scoped_query`
+    pub async fn execute_scoped_transaction(
+        &self,
+        scope: &ChallengeSchema,
+        queries: Vec<ORMQuery>,
+    ) -> Result<TransactionResult> {
+        self.execute_transaction_audited(Some(scope), queries).await
+    }
+
+    /// Insert rows in a single statement, returning the number inserted
+    ///
+    /// The rows are checked like an `insert` query of all their columns, and
+    /// are refused if there are more than `max_bulk_insert_rows`. Rows
+    /// conflicting with existing ones fail the statement, are skipped or
+    /// update the existing rows, depending on `conflict_policy`; skipped and
+    /// updated rows are not counted.
+    pub async fn execute_bulk_insert(&self, query: BulkInsertQuery) -> Result<u64> {
+        self.execute_bulk_insert_audited(None, query).await
+    }
+
+    /// Bulk insert on behalf of the challenge owning `scope`, see
+    /// `execute_scoped_query`
+    pub async fn execute_scoped_bulk_insert(
+        &self,
+        scope: &ChallengeSchema,
+        query: BulkInsertQuery,
+    ) -> Result<u64> {
+        self.execute_bulk_insert_audited(Some(scope), query).await
+    }
+
+    /// Execute a query, confined to `scope` if given, and audit it
+    async fn execute_audited(
+        &self,
+        scope: Option<&ChallengeSchema>,
+        mut query: ORMQuery,
+    ) -> Result<QueryResult> {
+        let started = Instant::now();
+        let result = self.run_query(scope, &mut query).await;
+        self.audit(
+            scope,
+            &query.operation,
+            std::slice::from_ref(&query),
+            started.elapsed(),
+            result.as_ref().map(rows_of),
+            || self.statements_sql(std::slice::from_ref(&query)),
+        );
+        result
+    }
+
+    async fn run_query(
+        &self,
+        scope: Option<&ChallengeSchema>,
+        query: &mut ORMQuery,
+    ) -> Result<QueryResult> {
+        if let Some(scope) = scope {
+            confine_to_schema(query, &scope.schema)?;
+        }
+        self.check_query(query)?;
+        self.query_executor.execute(query, role_of(scope)).await
+    }
+
+    /// Execute a transaction, confined to `scope` if given, and audit it
+    async fn execute_transaction_audited(
+        &self,
+        scope: Option<&ChallengeSchema>,
+        mut queries: Vec<ORMQuery>,
+    ) -> Result<TransactionResult> {
+        let started = Instant::now();
+        let result = self.run_transaction(scope, &mut queries).await;
+        self.audit(
+            scope,
+            TRANSACTION_OPERATION,
+            &queries,
+            started.elapsed(),
+            result
+                .as_ref()
+                .map(|transaction| transaction.results.iter().map(rows_of).sum()),
+            || self.statements_sql(&queries),
+        );
+        result
+    }
+
+    async fn run_transaction(
+        &self,
+        scope: Option<&ChallengeSchema>,
+        queries: &mut [ORMQuery],
+    ) -> Result<TransactionResult> {
+        if let Some(scope) = scope {
+            for (index, query) in queries.iter_mut().enumerate() {
+                confine_to_schema(query, &scope.schema).map_err(|e| {
+                    let message = format!("Query {} of the transaction rejected: {}", index, e);
+                    e.context(message)
+                })?;
+            }
+        }
+
+        if queries.is_empty() {
+            return Err(ORMError::rejected("Transaction has no statements").into());
+        }
+        if queries.len() > self.config.max_transaction_statements {
+            return Err(ORMError::rejected(format!(
+                "Transaction has {} statements, maximum allowed: {}",
+                queries.len(),
+                self.config.max_transaction_statements
+            ))
+            .into());
+        }
+
+        for (index, query) in queries.iter_mut().enumerate() {
+            let checked = if query.operation == TRANSACTION_OPERATION {
+                Err(ORMError::rejected("Nested transactions are not supported").into())
+            } else {
+                self.check_query(query)
+            };
+            checked.map_err(|e| {
+                let message = format!("Query {} of the transaction rejected: {}", index, e);
+                e.context(message)
+            })?;
+        }
+
+        info!(query_count = queries.len(), "Executing transaction");
+        self.query_executor
+            .execute_transaction(queries, self.config.max_transaction_rows, role_of(scope))
+            .await
+    }
+
+    /// Execute a bulk insert, confined to `scope` if given, and audit it
+    async fn execute_bulk_insert_audited(
+        &self,
+        scope: Option<&ChallengeSchema>,
+        mut query: BulkInsertQuery,
+    ) -> Result<u64> {
+        let started = Instant::now();
+        let mut insert = query.insert_query();
+        let result = self.run_bulk_insert(scope, &mut query, &mut insert).await;
+        self.audit(
+            scope,
+            BULK_INSERT_OPERATION,
+            std::slice::from_ref(&insert),
+            started.elapsed(),
+            result.as_ref().copied(),
+            || {
+                self.query_executor
+                    .bulk_insert_sql(&query)
+                    .unwrap_or_default()
+            },
+        );
+        result
+    }
+
+    async fn run_bulk_insert(
+        &self,
+        scope: Option<&ChallengeSchema>,
+        query: &mut BulkInsertQuery,
+        insert: &mut ORMQuery,
+    ) -> Result<u64> {
+        if let Some(scope) = scope {
+            confine_to_schema(insert, &scope.schema)?;
+        }
+        self.check_bulk_insert(query, insert)?;
+
+        info!(
+            table = &query.table,
+            row_count = query.rows.len(),
+            "Executing bulk insert"
+        );
+        self.query_executor
+            .execute_bulk_insert(query, role_of(scope))
+            .await
+    }
+
+    /// Check a bulk insert against the row caps and its conflict policy, then
+    /// as `insert`, its `insert` query, whose schema and table it is given
+    fn check_bulk_insert(&self, query: &mut BulkInsertQuery, insert: &mut ORMQuery) -> Result<()> {
+        if query.rows.is_empty() {
+            return Err(ORMError::rejected("Bulk insert has no rows").into());
+        }
+        if query.rows.len() > self.config.max_bulk_insert_rows {
+            return Err(ORMError::rejected(format!(
+                "Bulk insert has {} rows, maximum allowed: {}",
+                query.rows.len(),
+                self.config.max_bulk_insert_rows
+            ))
+            .into());
+        }
+        let value_count: usize = query.rows.iter().map(HashMap::len).sum();
+        if value_count > MAX_BIND_PARAMETERS {
+            return Err(ORMError::rejected(format!(
+                "Bulk insert has {} values, maximum allowed: {}",
+                value_count, MAX_BIND_PARAMETERS
+            ))
+            .into());
+        }
+
+        if let ConflictPolicy::Update(columns) = &query.conflict_policy {
+            if query.conflict_columns.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Updating on conflict requires the conflict columns"
+                ));
+            }
+            if columns.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Updating on conflict requires columns to update"
+                ));
+            }
+            let inserted = query.columns();
+            if let Some(column) = columns.iter().find(|c| !inserted.contains(&c.as_str())) {
+                return Err(anyhow::anyhow!(
+                    "Column '{}' is updated on conflict but not inserted",
+                    column
+                ));
+            }
+        }
+
+        self.check_query(insert)?;
+        query.schema = insert.schema.clone();
+        query.table = insert.table.clone();
+        Ok(())
+    }
+
+    /// SQL text of `queries`, one statement per line
+    fn statements_sql(&self, queries: &[ORMQuery]) -> String {
+        queries
+            .iter()
+            .filter_map(|query| self.query_executor.sql(query).ok())
+            .collect::<Vec<_>>()
+            .join(";\n")
+    }
+
+    /// Queue the audit entry of `queries`, run as a single query or transaction
+    /// that returned or wrote `rows`; `sql` gives the SQL text they ran as
+    fn audit(
+        &self,
+        scope: Option<&ChallengeSchema>,
+        operation: &str,
+        queries: &[ORMQuery],
+        duration: Duration,
+        rows: Result<u64, &anyhow::Error>,
+        sql: impl FnOnce() -> String,
+    ) {
+        let Some(auditor) = &self.auditor else {
+            return;
+        };
+
+        let mut tables: Vec<&str> = Vec::new();
+        for query in queries {
+            if !tables.contains(&query.table.as_str()) {
+                tables.push(&query.table);
+            }
+        }
+        let summaries: Vec<String> = queries.iter().filter_map(filter_summary).collect();
+        // Built again rather than kept from the execution, as few queries need it
+        let sql = auditor
+            .captures_sql(duration, rows.is_err())
+            .then(sql)
+            .filter(|sql| !sql.is_empty());
+
+        auditor.record(AuditEntry {
+            challenge: scope
+                .map(|scope| scope.schema.as_str())
+                .or_else(|| queries.first().and_then(|q| q.schema.as_deref()))
+                .map(str::to_string),
+            operation: operation.to_string(),
+            table_name: tables.join(","),
+            filter_summary: (!summaries.is_empty()).then(|| summaries.join("; ")),
+            row_count: rows.as_ref().ok().map(|rows| *rows as i64),
+            duration_ms: duration.as_secs_f64() * 1000.0,
+            outcome: if rows.is_ok() {
+                AuditOutcome::Success
+            } else {
+                AuditOutcome::Error
+            },
+            error: rows.err().map(|e| e.to_string()),
+            sql,
+            executed_at: Utc::now(),
+        });
+    }
+
+    /// Check a query against the configured mode, validation rules, column
+    /// allowlists and permissions
+    fn check_query(&self, query: &mut ORMQuery) -> Result<()> {
         // Check if write operations are allowed
         if self.config.read_only {
             match query.operation.as_str() {
@@ -90,16 +553,26 @@ impl SecureORMGateway {
             }
         }
 
+        // Split a `schema.table` name and check both against the allow-lists
+        self.query_validator.qualify_table(query)?;
+
         // Validate query
-        self.query_validator.validate(&query)?;
+        self.query_validator.validate(query)?;
+
+        // Restrict the query to the allowlisted columns of its table
+        if let Some(allowlist) = self.config.column_allowlists.get(&query.table) {
+            allowlist.enforce(query, self.config.role.as_deref())?;
+        }
 
         // Check permissions
-        self.permissions.check_query_permissions(&query)?;
+        self.permissions.check_query_permissions(query)?;
 
-        // Execute query
-        let result = self.query_executor.execute(&query).await?;
+        // Never return an unbounded number of rows
+        if query.operation == "select" && query.limit.is_none() {
+            query.limit = Some(self.config.default_query_limit);
+        }
 
-        Ok(result)
+        Ok(())
     }
 
     /// Execute a read-only query (alias for compatibility)
@@ -121,6 +594,7 @@ impl SecureORMGateway {
 
         self.permissions
             .load_permissions(challenge_id, permissions)?;
+        self.statement_cache.invalidate();
         Ok(())
     }
 
@@ -177,6 +651,16 @@ impl SecureORMGateway {
     }
 }
 
+/// Role the queries of `scope` run as, none for unscoped queries
+fn role_of(scope: Option<&ChallengeSchema>) -> Option<&str> {
+    scope.map(|scope| scope.role.as_str())
+}
+
+/// Rows a query returned or wrote
+fn rows_of(result: &QueryResult) -> u64 {
+    (result.row_count as u64).max(result.rows_affected)
+}
+
 /// ORM Query structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ORMQuery {
@@ -186,13 +670,107 @@ pub struct ORMQuery {
     pub db_version: Option<u32>, // Database version for schema resolution
     pub columns: Option<Vec<String>>,
     pub filters: Option<Vec<QueryFilter>>,
+    #[serde(default)]
+    pub filter_logic: FilterLogic, // How `filters` are combined in the WHERE clause
     pub order_by: Option<Vec<OrderBy>>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
     pub aggregations: Option<Vec<Aggregation>>,
+    /// Columns the aggregations are grouped by, selected ahead of the aggregates
+    #[serde(default)]
+    pub group_by: Option<Vec<String>>,
+    /// Conditions on aggregates, all of which must hold for a group to be returned
+    #[serde(default)]
+    pub having: Option<Vec<HavingFilter>>,
     // For INSERT/UPDATE operations
     pub values: Option<Vec<ColumnValue>>, // For INSERT: column -> value mapping
     pub set_values: Option<Vec<ColumnValue>>, // For UPDATE: column -> value mapping
+    /// For UPDATE: only update rows still at this `version`, and bump it.
+    /// The table needs a `version BIGINT DEFAULT 0` column
+    #[serde(default)]
+    pub expected_version: Option<u64>,
+}
+
+/// Ordered statements to execute in a single transaction, see
+/// `SecureORMGateway::execute_transaction`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ORMTransaction {
+    pub statements: Vec<ORMQuery>,
+}
+
+/// Rows inserted in a single statement, see
+/// `SecureORMGateway::execute_bulk_insert`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkInsertQuery {
+    pub table: String,
+    #[serde(default)]
+    pub schema: Option<String>,
+    /// Column -> value of each row; a column missing from a row is given its default
+    pub rows: Vec<HashMap<String, serde_json::Value>>,
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    /// Columns of the unique constraint conflicts are detected on; required
+    /// to update on conflict, any constraint otherwise
+    #[serde(default)]
+    pub conflict_columns: Vec<String>,
+}
+
+/// What a bulk insert does with rows conflicting with existing ones
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Fail the whole statement
+    #[default]
+    Error,
+    /// Skip the conflicting rows
+    Ignore,
+    /// Update these columns of the existing rows with the inserted values
+    Update(Vec<String>),
+}
+
+impl BulkInsertQuery {
+    /// Columns of all the rows, sorted
+    pub fn columns(&self) -> Vec<&str> {
+        let columns: BTreeSet<&str> = self
+            .rows
+            .iter()
+            .flat_map(|row| row.keys().map(String::as_str))
+            .collect();
+        columns.into_iter().collect()
+    }
+
+    /// `insert` query of a row with every column, checked in place of the rows
+    ///
+    /// The conflict columns are read by the statement, so they are checked as
+    /// the columns it selects.
+    pub(crate) fn insert_query(&self) -> ORMQuery {
+        ORMQuery {
+            operation: "insert".to_string(),
+            table: self.table.clone(),
+            schema: self.schema.clone(),
+            db_version: None,
+            columns: (!self.conflict_columns.is_empty()).then(|| self.conflict_columns.clone()),
+            filters: None,
+            filter_logic: FilterLogic::And,
+            order_by: None,
+            limit: None,
+            offset: None,
+            aggregations: None,
+            group_by: None,
+            having: None,
+            values: Some(
+                self.columns()
+                    .into_iter()
+                    .map(|column| ColumnValue {
+                        column: column.to_string(),
+                        value: serde_json::Value::Null,
+                    })
+                    .collect(),
+            ),
+            set_values: None,
+            expected_version: None,
+        }
+    }
 }
 
 /// Column-value pair for INSERT/UPDATE operations
@@ -206,10 +784,226 @@ pub struct ColumnValue {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryFilter {
     pub column: String,
-    pub operator: String,
+    pub operator: FilterOperator,
+    /// Array for `IN` / `NOT IN`, `[low, high]` for `BETWEEN`, omitted or null for `IS [NOT] NULL`
+    ///
+    /// Always bound as a query parameter, never written into the SQL text.
+    #[serde(default)]
     pub value: serde_json::Value,
 }
 
+/// Comparison operator of a filter
+///
+/// Only these operators are accepted; each maps to a fixed SQL fragment so the
+/// operator text sent by the caller is never interpolated into SQL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum FilterOperator {
+    Eq,
+    NotEq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Like,
+    NotLike,
+    ILike,
+    NotILike,
+    In,
+    NotIn,
+    Between,
+    NotBetween,
+    IsNull,
+    IsNotNull,
+}
+
+impl FilterOperator {
+    pub const ALL: [FilterOperator; 16] = [
+        FilterOperator::Eq,
+        FilterOperator::NotEq,
+        FilterOperator::Lt,
+        FilterOperator::Lte,
+        FilterOperator::Gt,
+        FilterOperator::Gte,
+        FilterOperator::Like,
+        FilterOperator::NotLike,
+        FilterOperator::ILike,
+        FilterOperator::NotILike,
+        FilterOperator::In,
+        FilterOperator::NotIn,
+        FilterOperator::Between,
+        FilterOperator::NotBetween,
+        FilterOperator::IsNull,
+        FilterOperator::IsNotNull,
+    ];
+
+    /// SQL text of the operator
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            FilterOperator::Eq => "=",
+            FilterOperator::NotEq => "!=",
+            FilterOperator::Lt => "<",
+            FilterOperator::Lte => "<=",
+            FilterOperator::Gt => ">",
+            FilterOperator::Gte => ">=",
+            FilterOperator::Like => "LIKE",
+            FilterOperator::NotLike => "NOT LIKE",
+            FilterOperator::ILike => "ILIKE",
+            FilterOperator::NotILike => "NOT ILIKE",
+            FilterOperator::In => "IN",
+            FilterOperator::NotIn => "NOT IN",
+            FilterOperator::Between => "BETWEEN",
+            FilterOperator::NotBetween => "NOT BETWEEN",
+            FilterOperator::IsNull => "IS NULL",
+            FilterOperator::IsNotNull => "IS NOT NULL",
+        }
+    }
+}
+
+impl std::fmt::Display for FilterOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_sql())
+    }
+}
+
+impl std::str::FromStr for FilterOperator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Case and runs of whitespace are not significant ("is  null" == "IS NULL")
+        let normalized = s.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+        Self::ALL
+            .into_iter()
+            .find(|op| op.as_sql() == normalized)
+            .ok_or_else(|| {
+                let allowed: Vec<&str> = Self::ALL.iter().map(|op| op.as_sql()).collect();
+                format!(
+                    "Unsupported filter operator '{}', expected one of: {}",
+                    s,
+                    allowed.join(", ")
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for FilterOperator {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<FilterOperator> for String {
+    fn from(op: FilterOperator) -> Self {
+        op.as_sql().to_string()
+    }
+}
+
+/// Schema of queries that name neither a schema nor a `schema.table`
+pub const DEFAULT_SCHEMA: &str = "public";
+
+/// Operation name of a transaction request, never valid for a statement inside one
+pub const TRANSACTION_OPERATION: &str = "transaction";
+
+/// Operation name of a bulk insert in the audit log
+pub const BULK_INSERT_OPERATION: &str = "bulk_insert";
+
+/// Maximum number of values bound to a single statement, a Postgres limit
+pub const MAX_BIND_PARAMETERS: usize = u16::MAX as usize;
+
+/// Column holding the row version checked by `ORMQuery::expected_version`
+pub const VERSION_COLUMN: &str = "version";
+
+/// Maximum nesting depth of filter groups (the top-level filter list counts as one level)
+pub const MAX_FILTER_DEPTH: usize = 3;
+
+/// How a list of filters is combined in the WHERE clause
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterLogic {
+    /// All filters must match
+    #[default]
+    And,
+    /// At least one filter must match
+    Or,
+    /// Filters are AND-ed with a parenthesized sub-group
+    Nested(Box<FilterGroup>),
+}
+
+/// Group of filters combined with the same logic
+///
+/// `groups` holds parenthesized sub-groups combined with `filters` using the
+/// same logic, e.g. `(a AND b) OR (c AND d)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterGroup {
+    #[serde(default)]
+    pub logic: FilterLogic,
+    #[serde(default)]
+    pub filters: Vec<QueryFilter>,
+    #[serde(default)]
+    pub groups: Vec<FilterGroup>,
+}
+
+impl FilterLogic {
+    /// Nesting depth of this logic (a flat AND/OR list is one level)
+    pub fn depth(&self) -> usize {
+        match self {
+            FilterLogic::And | FilterLogic::Or => 1,
+            FilterLogic::Nested(group) => 1 + group.depth(),
+        }
+    }
+
+    /// Collect every filter nested under this logic
+    fn collect_filters<'a>(&'a self, out: &mut Vec<&'a QueryFilter>) {
+        if let FilterLogic::Nested(group) = self {
+            group.collect_filters(out);
+        }
+    }
+}
+
+impl FilterGroup {
+    /// Nesting depth of this group, including its sub-groups
+    pub fn depth(&self) -> usize {
+        let logic_depth = match &self.logic {
+            FilterLogic::Nested(group) => 1 + group.depth(),
+            _ => 1,
+        };
+        let groups_depth = self
+            .groups
+            .iter()
+            .map(|g| 1 + g.depth())
+            .max()
+            .unwrap_or(1);
+        logic_depth.max(groups_depth)
+    }
+
+    fn collect_filters<'a>(&'a self, out: &mut Vec<&'a QueryFilter>) {
+        out.extend(self.filters.iter());
+        for group in &self.groups {
+            group.collect_filters(out);
+        }
+        self.logic.collect_filters(out);
+    }
+}
+
+impl ORMQuery {
+    /// All filters of the query, including those inside nested groups
+    pub fn all_filters(&self) -> Vec<&QueryFilter> {
+        let mut out: Vec<&QueryFilter> = self.filters.iter().flatten().collect();
+        self.filter_logic.collect_filters(&mut out);
+        out
+    }
+
+    /// Whether `name` is the alias of one of the query's aggregations
+    pub fn is_aggregation_alias(&self, name: &str) -> bool {
+        self.aggregations
+            .iter()
+            .flatten()
+            .any(|agg| agg.alias == name)
+    }
+}
+
 /// Order by clause
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderBy {
@@ -221,18 +1015,48 @@ pub struct OrderBy {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Aggregation {
     pub function: String, // COUNT, SUM, AVG, MIN, MAX
-    pub column: String,
+    pub column: String,   // `*` only with COUNT
     pub alias: String,
 }
 
+/// Condition on an aggregate, in the HAVING clause of a grouped query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HavingFilter {
+    pub function: String, // COUNT, SUM, AVG, MIN, MAX
+    pub column: String,   // `*` only with COUNT
+    pub operator: FilterOperator,
+    /// Bound as a query parameter, like the value of a `QueryFilter`
+    #[serde(default)]
+    pub value: serde_json::Value,
+}
+
+/// SQL text of an aggregate over a column, from a validated function and column
+pub(crate) fn aggregate_sql(function: &str, column: &str) -> String {
+    format!("{}({})", function.to_uppercase(), column)
+}
+
 /// Query result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryResult {
     pub rows: Vec<serde_json::Value>,
     pub row_count: usize,
+    /// Rows written by an insert, update or delete; zero for reads
+    #[serde(default)]
+    pub rows_affected: u64,
     pub execution_time_ms: u64,
 }
 
+/// Outcome of a transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionResult {
+    /// Whether the transaction was committed
+    pub committed: bool,
+    /// Result of each statement, in order
+    pub results: Vec<QueryResult>,
+    /// Rows written by all the statements
+    pub rows_affected: u64,
+}
+
 /// Table schema information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableSchema {
@@ -248,3 +1072,389 @@ pub struct ColumnInfo {
     pub nullable: bool,
     pub default: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ORMError;
+    use serde_json::json;
+
+    fn query(operation: &str) -> ORMQuery {
+        ORMQuery {
+            operation: operation.to_string(),
+            table: "scores".to_string(),
+            schema: Some("challenge_term_v1".to_string()),
+            db_version: None,
+            columns: None,
+            filters: None,
+            filter_logic: FilterLogic::And,
+            order_by: None,
+            limit: None,
+            offset: None,
+            aggregations: None,
+            group_by: None,
+            having: None,
+            values: Some(vec![ColumnValue {
+                column: "score".to_string(),
+                value: json!(0.5),
+            }]),
+            set_values: None,
+            expected_version: None,
+        }
+    }
+
+    fn gateway(config: ORMGatewayConfig) -> SecureORMGateway {
+        // Never connects: every test batch is rejected before execution
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        SecureORMGateway::new(config, pool)
+    }
+
+    #[tokio::test]
+    async fn test_transaction_checks_every_query() {
+        let err = gateway(ORMGatewayConfig::read_only())
+            .execute_transaction(vec![query("select"), query("insert")])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "Query 1 of the transaction rejected: Write operations not allowed in read-only mode"
+        );
+
+        let mut config = ORMGatewayConfig::read_write();
+        config
+            .column_allowlists
+            .insert("scores".to_string(), ColumnAllowlist::new(&["id"]));
+        let err = gateway(config)
+            .execute_transaction(vec![query("insert")])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Query 0 of the transaction rejected: Access denied to column 'score'"));
+    }
+
+    #[tokio::test]
+    async fn test_transaction_limits() {
+        let gateway = gateway(ORMGatewayConfig {
+            max_transaction_statements: 2,
+            ..ORMGatewayConfig::read_write()
+        });
+
+        let err = gateway
+            .execute_transaction(vec![query("insert"), query("insert"), query("insert")])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ORMError>(),
+            Some(ORMError::Rejected { .. })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Transaction has 3 statements, maximum allowed: 2"
+        );
+
+        let err = gateway
+            .execute_transaction(vec![query("insert"), query(TRANSACTION_OPERATION)])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Query 1 of the transaction rejected: Nested transactions are not supported"
+        );
+
+        assert!(gateway.execute_transaction(vec![]).await.is_err());
+    }
+
+    /// Gateway on a fresh `orm_tx_test_v1` schema holding a quota row with 5
+    /// remaining and an empty table of records
+    async fn transaction_test_gateway() -> (SecureORMGateway, PgPool) {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        for statement in [
+            "DROP SCHEMA IF EXISTS orm_tx_test_v1 CASCADE",
+            "CREATE SCHEMA orm_tx_test_v1",
+            "CREATE TABLE orm_tx_test_v1.quotas (id BIGINT PRIMARY KEY, remaining BIGINT NOT NULL CHECK (remaining >= 0))",
+            "CREATE TABLE orm_tx_test_v1.records (id BIGINT PRIMARY KEY, note TEXT)",
+            "INSERT INTO orm_tx_test_v1.quotas VALUES (1, 5)",
+        ] {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        (
+            SecureORMGateway::new(ORMGatewayConfig::read_write(), pool.clone()),
+            pool,
+        )
+    }
+
+    fn set_remaining(remaining: i64) -> ORMQuery {
+        ORMQuery {
+            table: "quotas".to_string(),
+            schema: Some("orm_tx_test_v1".to_string()),
+            filters: Some(vec![QueryFilter {
+                column: "id".to_string(),
+                operator: FilterOperator::Eq,
+                value: json!(1),
+            }]),
+            values: None,
+            set_values: Some(vec![ColumnValue {
+                column: "remaining".to_string(),
+                value: json!(remaining),
+            }]),
+            ..query("update")
+        }
+    }
+
+    fn insert_record(id: i64, column: &str) -> ORMQuery {
+        ORMQuery {
+            table: "records".to_string(),
+            schema: Some("orm_tx_test_v1".to_string()),
+            values: Some(vec![
+                ColumnValue {
+                    column: "id".to_string(),
+                    value: json!(id),
+                },
+                ColumnValue {
+                    column: column.to_string(),
+                    value: json!("spent"),
+                },
+            ]),
+            ..query("insert")
+        }
+    }
+
+    async fn state(pool: &PgPool) -> (i64, i64) {
+        sqlx::query_as(
+            "SELECT (SELECT remaining FROM orm_tx_test_v1.quotas WHERE id = 1), \
+                    (SELECT COUNT(*) FROM orm_tx_test_v1.records)",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_transaction_commits_every_statement() {
+        let (gateway, pool) = transaction_test_gateway().await;
+
+        let result = gateway
+            .execute_transaction(vec![set_remaining(4), insert_record(1, "note")])
+            .await
+            .unwrap();
+        assert!(result.committed);
+        assert_eq!(result.results.len(), 2);
+        assert_eq!(result.rows_affected, 2);
+        assert_eq!(state(&pool).await, (4, 1));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_transaction_rolls_back_on_failure() {
+        let (gateway, pool) = transaction_test_gateway().await;
+
+        // A statement failing validation stops the transaction before any runs
+        let err = gateway
+            .execute_transaction(vec![set_remaining(4), insert_record(1, "bad column;")])
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Query 1 of the transaction rejected"));
+        assert_eq!(state(&pool).await, (5, 0));
+
+        // A statement failing mid-transaction undoes the ones before it
+        let err = gateway
+            .execute_transaction(vec![insert_record(1, "note"), set_remaining(-1)])
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Transaction rolled back, query 1 failed"));
+        assert_eq!(state(&pool).await, (5, 0));
+
+        // So does a statement taking the transaction past its row cap
+        let capped = SecureORMGateway::new(
+            ORMGatewayConfig {
+                max_transaction_rows: 1,
+                ..ORMGatewayConfig::read_write()
+            },
+            pool.clone(),
+        );
+        let err = capped
+            .execute_transaction(vec![set_remaining(4), insert_record(1, "note")])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ORMError>(),
+            Some(ORMError::Rejected { .. })
+        ));
+        assert_eq!(state(&pool).await, (5, 0));
+    }
+
+    fn results(ids: &[i64], score: f64, conflict_policy: ConflictPolicy) -> BulkInsertQuery {
+        BulkInsertQuery {
+            table: "results".to_string(),
+            schema: Some("orm_tx_test_v1".to_string()),
+            rows: ids
+                .iter()
+                .map(|id| {
+                    HashMap::from([
+                        ("id".to_string(), json!(id)),
+                        ("score".to_string(), json!(score)),
+                    ])
+                })
+                .collect(),
+            conflict_policy,
+            conflict_columns: vec!["id".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert_checks() {
+        let gateway = gateway(ORMGatewayConfig {
+            max_bulk_insert_rows: 2,
+            ..ORMGatewayConfig::read_write()
+        });
+
+        let err = gateway
+            .execute_bulk_insert(results(&[1, 2, 3], 0.5, ConflictPolicy::Error))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ORMError>(),
+            Some(ORMError::Rejected { .. })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Bulk insert has 3 rows, maximum allowed: 2"
+        );
+
+        let err = gateway
+            .execute_bulk_insert(results(&[], 0.5, ConflictPolicy::Error))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Bulk insert has no rows");
+
+        let err = gateway
+            .execute_bulk_insert(results(
+                &[1],
+                0.5,
+                ConflictPolicy::Update(vec!["note".to_string()]),
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Column 'note' is updated on conflict but not inserted"
+        );
+
+        let mut unnamed = results(&[1], 0.5, ConflictPolicy::Update(vec!["score".to_string()]));
+        unnamed.conflict_columns.clear();
+        assert!(gateway.execute_bulk_insert(unnamed).await.is_err());
+
+        // The rows are checked like any insert
+        let err = gateway
+            .execute_scoped_bulk_insert(
+                &ChallengeSchema::for_challenge(uuid::Uuid::new_v4()),
+                results(&[1], 0.5, ConflictPolicy::Error),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ORMError>(),
+            Some(ORMError::SchemaDenied { .. })
+        ));
+        let err = self::gateway(ORMGatewayConfig::read_only())
+            .execute_bulk_insert(results(&[1], 0.5, ConflictPolicy::Error))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Write operations not allowed in read-only mode"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_bulk_insert_conflict_policies() {
+        let (gateway, pool) = transaction_test_gateway().await;
+        sqlx::query(
+            "CREATE TABLE orm_tx_test_v1.results (id BIGINT PRIMARY KEY, score DOUBLE PRECISION)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let scores = || async {
+            sqlx::query_as::<_, (i64, f64)>(
+                "SELECT id, score FROM orm_tx_test_v1.results ORDER BY id",
+            )
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+        };
+
+        let inserted = gateway
+            .execute_bulk_insert(results(&[1, 2], 0.5, ConflictPolicy::Error))
+            .await
+            .unwrap();
+        assert_eq!(inserted, 2);
+
+        // A conflicting row fails the whole statement
+        assert!(gateway
+            .execute_bulk_insert(results(&[2, 3], 0.7, ConflictPolicy::Error))
+            .await
+            .is_err());
+        assert_eq!(scores().await, vec![(1, 0.5), (2, 0.5)]);
+
+        let inserted = gateway
+            .execute_bulk_insert(results(&[2, 3], 0.7, ConflictPolicy::Ignore))
+            .await
+            .unwrap();
+        assert_eq!(inserted, 1);
+        assert_eq!(scores().await, vec![(1, 0.5), (2, 0.5), (3, 0.7)]);
+
+        let inserted = gateway
+            .execute_bulk_insert(results(
+                &[3, 4],
+                0.9,
+                ConflictPolicy::Update(vec!["score".to_string()]),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(inserted, 1);
+        assert_eq!(scores().await, vec![(1, 0.5), (2, 0.5), (3, 0.9), (4, 0.9)]);
+    }
+
+    #[tokio::test]
+    async fn test_row_guardrails() {
+        let gateway = gateway(ORMGatewayConfig::read_only());
+        let select = || ORMQuery {
+            values: None,
+            ..query("select")
+        };
+
+        let mut unbounded = select();
+        gateway.check_query(&mut unbounded).unwrap();
+        assert_eq!(unbounded.limit, Some(100));
+
+        let mut deep_page = select();
+        deep_page.offset = Some(50_000);
+        let err = gateway.check_query(&mut deep_page).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ORMError>(),
+            Some(ORMError::Rejected { .. })
+        ));
+        assert!(err
+            .to_string()
+            .starts_with("Query offset 50000 exceeds maximum allowed: 10000"));
+
+        // Rejections stay typed inside a transaction
+        let err = gateway
+            .execute_transaction(vec![select(), deep_page])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ORMError>(),
+            Some(ORMError::Rejected { .. })
+        ));
+    }
+}
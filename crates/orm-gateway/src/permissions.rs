@@ -136,27 +136,23 @@ impl ORMPermissions {
                 }
 
                 // Also check filter columns for updates
-                if let Some(filters) = &query.filters {
-                    for filter in filters {
-                        if !table_perms.readable_columns.contains(&filter.column) {
-                            return Err(anyhow::anyhow!(
-                                "Access denied to filter column: {}",
-                                filter.column
-                            ));
-                        }
+                for filter in query.all_filters() {
+                    if !table_perms.readable_columns.contains(&filter.column) {
+                        return Err(anyhow::anyhow!(
+                            "Access denied to filter column: {}",
+                            filter.column
+                        ));
                     }
                 }
             }
             "delete" => {
                 // For delete operations, only check filter columns
-                if let Some(filters) = &query.filters {
-                    for filter in filters {
-                        if !table_perms.readable_columns.contains(&filter.column) {
-                            return Err(anyhow::anyhow!(
-                                "Access denied to filter column: {}",
-                                filter.column
-                            ));
-                        }
+                for filter in query.all_filters() {
+                    if !table_perms.readable_columns.contains(&filter.column) {
+                        return Err(anyhow::anyhow!(
+                            "Access denied to filter column: {}",
+                            filter.column
+                        ));
                     }
                 }
             }
@@ -177,7 +173,9 @@ impl ORMPermissions {
         // Check order by column permissions
         if let Some(order_by) = &query.order_by {
             for order in order_by {
-                if !table_perms.readable_columns.contains(&order.column) {
+                if !query.is_aggregation_alias(&order.column)
+                    && !table_perms.readable_columns.contains(&order.column)
+                {
                     return Err(anyhow::anyhow!(
                         "Access denied to order column: {}",
                         order.column
@@ -195,14 +193,24 @@ impl ORMPermissions {
                 ));
             }
 
-            for agg in aggregations {
-                if !table_perms.readable_columns.contains(&agg.column) {
+            let aggregated = aggregations
+                .iter()
+                .map(|agg| &agg.column)
+                .chain(query.having.iter().flatten().map(|h| &h.column));
+            for column in aggregated.filter(|c| *c != "*") {
+                if !table_perms.readable_columns.contains(column) {
                     return Err(anyhow::anyhow!(
                         "Access denied to aggregate column: {}",
-                        agg.column
+                        column
                     ));
                 }
             }
+
+            for column in query.group_by.iter().flatten() {
+                if !table_perms.readable_columns.contains(column) {
+                    return Err(anyhow::anyhow!("Access denied to group by column: {}", column));
+                }
+            }
         }
 
         // Check row limit
@@ -421,14 +429,19 @@ mod tests {
             operation: "select".to_string(),
             table: "challenge_submissions".to_string(),
             schema: Some("challenge_test-challenge".to_string()),
+            db_version: None,
             columns: Some(vec!["id".to_string(), "score".to_string()]),
             filters: None,
+            filter_logic: crate::FilterLogic::And,
             order_by: None,
             limit: Some(100),
             offset: None,
             aggregations: None,
+            group_by: None,
+            having: None,
             values: None,
             set_values: None,
+            expected_version: None,
         };
 
         assert!(permissions.check_query_permissions(&query).is_ok());
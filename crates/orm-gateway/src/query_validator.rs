@@ -2,32 +2,19 @@ use anyhow::Result;
 use std::collections::HashSet;
 use tracing::warn;
 
-use super::{ORMGatewayConfig, ORMQuery};
+use super::{
+    ColumnType, FilterOperator, ORMError, ORMGatewayConfig, ORMQuery, DEFAULT_SCHEMA,
+    MAX_FILTER_DEPTH, VERSION_COLUMN,
+};
 
 /// Query validator to ensure queries are safe and allowed
 pub struct QueryValidator {
     config: ORMGatewayConfig,
-    allowed_operators: HashSet<String>,
     allowed_aggregations: HashSet<String>,
 }
 
 impl QueryValidator {
     pub fn new(config: ORMGatewayConfig) -> Self {
-        let allowed_operators = HashSet::from([
-            "=".to_string(),
-            "!=".to_string(),
-            "<".to_string(),
-            "<=".to_string(),
-            ">".to_string(),
-            ">=".to_string(),
-            "IN".to_string(),
-            "NOT IN".to_string(),
-            "LIKE".to_string(),
-            "NOT LIKE".to_string(),
-            "IS NULL".to_string(),
-            "IS NOT NULL".to_string(),
-        ]);
-
         let allowed_aggregations = HashSet::from([
             "COUNT".to_string(),
             "SUM".to_string(),
@@ -38,11 +25,63 @@ impl QueryValidator {
 
         Self {
             config,
-            allowed_operators,
             allowed_aggregations,
         }
     }
 
+    /// Resolve the schema of `query` and check it and the table against the allow-lists
+    ///
+    /// A `schema.table` name is split into `query.schema` and `query.table`;
+    /// the schema defaults to `public`. System schemas are always rejected.
+    pub fn qualify_table(&self, query: &mut ORMQuery) -> Result<()> {
+        if let Some((schema, table)) = query.table.split_once('.') {
+            if let Some(named) = query.schema.as_deref().filter(|named| *named != schema) {
+                return Err(anyhow::anyhow!(
+                    "Table '{}' conflicts with schema '{}'",
+                    query.table,
+                    named
+                ));
+            }
+            let (schema, table) = (schema.to_string(), table.to_string());
+            query.schema = Some(schema);
+            query.table = table;
+        }
+        let schema = query
+            .schema
+            .get_or_insert_with(|| DEFAULT_SCHEMA.to_string());
+
+        self.validate_name(schema, "schema")?;
+        self.validate_name(&query.table, "table")?;
+
+        if is_system_schema(schema) {
+            warn!(
+                schema = schema.as_str(),
+                table = &query.table,
+                "Query targets a system schema"
+            );
+            return Err(anyhow::anyhow!(
+                "Access denied to system schema '{}'",
+                schema
+            ));
+        }
+        if !self.config.allowed_schemas.is_empty()
+            && !self
+                .config
+                .allowed_schemas
+                .iter()
+                .any(|allowed| matches_allowed(allowed, schema))
+        {
+            return Err(anyhow::anyhow!("Schema '{}' is not allowed", schema));
+        }
+        if !self.config.allowed_tables.is_empty()
+            && !self.config.allowed_tables.contains(&query.table)
+        {
+            return Err(anyhow::anyhow!("Table '{}' is not allowed", query.table));
+        }
+
+        Ok(())
+    }
+
     /// Validate a query
     pub fn validate(&self, query: &ORMQuery) -> Result<()> {
         // Check operation is allowed
@@ -76,23 +115,26 @@ impl QueryValidator {
             }
         }
 
-        // Validate filters
-        if let Some(filters) = &query.filters {
-            for filter in filters {
-                self.validate_identifier(&filter.column, "filter column")?;
+        // Validate filter nesting depth
+        let filter_depth = query.filter_logic.depth();
+        if filter_depth > MAX_FILTER_DEPTH {
+            return Err(anyhow::anyhow!(
+                "Filter nesting depth {} exceeds maximum allowed: {}",
+                filter_depth,
+                MAX_FILTER_DEPTH
+            ));
+        }
 
-                if !self
-                    .allowed_operators
-                    .contains(&filter.operator.to_uppercase())
-                {
-                    return Err(anyhow::anyhow!(
-                        "Filter operator not allowed: {}",
-                        filter.operator
-                    ));
-                }
+        // Validate filters, including those inside nested groups
+        let column_types = self.config.column_types.get(&query.table);
+        for filter in query.all_filters() {
+            self.validate_identifier(&filter.column, "filter column")?;
+
+            // Operators are restricted by `FilterOperator`; validate the value it expects
+            self.validate_filter_value(filter.operator, &filter.value)?;
 
-                // Validate filter value based on operator
-                self.validate_filter_value(&filter.operator, &filter.value)?;
+            if let Some(column_type) = column_types.and_then(|t| t.get(&filter.column)) {
+                self.validate_filter_type(&filter.column, *column_type, filter.operator, &filter.value)?;
             }
         }
 
@@ -123,34 +165,39 @@ impl QueryValidator {
                     max = self.config.max_query_limit,
                     "Query limit exceeds maximum"
                 );
-                return Err(anyhow::anyhow!(
+                return Err(ORMError::rejected(format!(
                     "Query limit {} exceeds maximum allowed: {}",
-                    limit,
-                    self.config.max_query_limit
-                ));
+                    limit, self.config.max_query_limit
+                ))
+                .into());
             }
         }
 
-        // Validate aggregations
-        if let Some(aggregations) = &query.aggregations {
-            if !self.config.enable_aggregations {
-                return Err(anyhow::anyhow!("Aggregations are not enabled"));
+        // Validate offset; scanning past deep offsets is as costly as returning the rows
+        if let Some(offset) = query.offset {
+            if offset > self.config.max_query_offset {
+                warn!(
+                    requested = offset,
+                    max = self.config.max_query_offset,
+                    "Query offset exceeds maximum"
+                );
+                return Err(ORMError::rejected(format!(
+                    "Query offset {} exceeds maximum allowed: {}; page with a filter on the \
+                     last row's order column instead",
+                    offset, self.config.max_query_offset
+                ))
+                .into());
             }
+        }
 
-            for agg in aggregations {
-                self.validate_identifier(&agg.column, "aggregation column")?;
-                self.validate_identifier(&agg.alias, "aggregation alias")?;
+        // Validate aggregations, grouping and HAVING conditions
+        self.validate_aggregations(query)?;
 
-                if !self
-                    .allowed_aggregations
-                    .contains(&agg.function.to_uppercase())
-                {
-                    return Err(anyhow::anyhow!(
-                        "Aggregation function not allowed: {}",
-                        agg.function
-                    ));
-                }
-            }
+        if query.expected_version.is_some() && query.operation != "update" {
+            return Err(anyhow::anyhow!(
+                "expected_version is only supported on update, not {}",
+                query.operation
+            ));
         }
 
         // Validate INSERT/UPDATE/DELETE operations
@@ -192,14 +239,24 @@ impl QueryValidator {
 
                 for cv in set_values {
                     self.validate_identifier(&cv.column, "UPDATE column")?;
+                    if query.expected_version.is_some() && cv.column == VERSION_COLUMN {
+                        return Err(anyhow::anyhow!(
+                            "'{}' is set by the gateway when expected_version is given",
+                            VERSION_COLUMN
+                        ));
+                    }
                 }
 
-                if query.filters.is_none() || query.filters.as_ref().unwrap().is_empty() {
+                if query.all_filters().is_empty() {
                     return Err(anyhow::anyhow!("UPDATE requires WHERE clause (filters)"));
                 }
+
+                if query.expected_version.is_some_and(|v| v > i64::MAX as u64) {
+                    return Err(anyhow::anyhow!("expected_version is out of range"));
+                }
             }
             "delete" => {
-                if query.filters.is_none() || query.filters.as_ref().unwrap().is_empty() {
+                if query.all_filters().is_empty() {
                     return Err(anyhow::anyhow!("DELETE requires WHERE clause (filters)"));
                 }
             }
@@ -209,6 +266,117 @@ impl QueryValidator {
         Ok(())
     }
 
+    /// Validate the aggregations of a query with its GROUP BY and HAVING clauses
+    fn validate_aggregations(&self, query: &ORMQuery) -> Result<()> {
+        let group_by = query.group_by.as_deref().unwrap_or(&[]);
+        let having = query.having.as_deref().unwrap_or(&[]);
+
+        let aggregations = match &query.aggregations {
+            Some(aggregations) => aggregations,
+            None if group_by.is_empty() && having.is_empty() => return Ok(()),
+            None => {
+                return Err(anyhow::anyhow!(
+                    "group_by and having require at least one aggregation"
+                ))
+            }
+        };
+
+        if !self.config.enable_aggregations {
+            return Err(anyhow::anyhow!("Aggregations are not enabled"));
+        }
+        if query.operation != "select" {
+            return Err(anyhow::anyhow!(
+                "Aggregations are only supported on select, not {}",
+                query.operation
+            ));
+        }
+        if aggregations.is_empty() {
+            return Err(anyhow::anyhow!("Aggregation list cannot be empty"));
+        }
+
+        for column in group_by {
+            self.validate_identifier(column, "group by column")?;
+        }
+
+        let mut aliases = HashSet::new();
+        for agg in aggregations {
+            self.validate_aggregate(&agg.function, &agg.column)?;
+            self.validate_identifier(&agg.alias, "aggregation alias")?;
+            if !aliases.insert(agg.alias.as_str()) || group_by.contains(&agg.alias) {
+                return Err(anyhow::anyhow!(
+                    "Duplicate output column in aggregation query: {}",
+                    agg.alias
+                ));
+            }
+        }
+
+        for condition in having {
+            self.validate_aggregate(&condition.function, &condition.column)?;
+            self.validate_filter_value(condition.operator, &condition.value)?;
+        }
+
+        // Only grouped columns have a single value per result row
+        for column in query.columns.iter().flatten() {
+            if !group_by.contains(column) {
+                return Err(if group_by.is_empty() {
+                    anyhow::anyhow!(
+                        "Cannot select column '{}' alongside aggregations without group_by",
+                        column
+                    )
+                } else {
+                    anyhow::anyhow!(
+                        "Column '{}' must be in group_by to be selected alongside aggregations",
+                        column
+                    )
+                });
+            }
+        }
+        for order in query.order_by.iter().flatten() {
+            if !group_by.contains(&order.column) && !query.is_aggregation_alias(&order.column) {
+                return Err(anyhow::anyhow!(
+                    "Aggregation queries can only be ordered by group_by columns or aggregation aliases, not {}",
+                    order.column
+                ));
+            }
+        }
+
+        // One row per group, so the group count must be bounded like any other result set
+        if !group_by.is_empty() && query.limit.is_none() {
+            return Err(anyhow::anyhow!(
+                "Grouped queries require a limit (at most {})",
+                self.config.max_query_limit
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate an aggregate function and the column it applies to
+    fn validate_aggregate(&self, function: &str, column: &str) -> Result<()> {
+        let function = function.to_uppercase();
+        if !self.allowed_aggregations.contains(&function) {
+            return Err(anyhow::anyhow!(
+                "Aggregation function not allowed: {}",
+                function
+            ));
+        }
+        if column == "*" {
+            if function != "COUNT" {
+                return Err(anyhow::anyhow!("Only COUNT can aggregate over *"));
+            }
+            return Ok(());
+        }
+        self.validate_identifier(column, "aggregation column")
+    }
+
+    /// Validate a schema or table name, which cannot be qualified any further
+    fn validate_name(&self, name: &str, context: &str) -> Result<()> {
+        if name.contains('.') {
+            return Err(anyhow::anyhow!("Invalid {} name: {}", context, name));
+        }
+        self.validate_identifier(name, context)
+    }
+
     /// Validate identifier to prevent SQL injection
     fn validate_identifier(&self, identifier: &str, context: &str) -> Result<()> {
         // Check for empty
@@ -286,9 +454,13 @@ impl QueryValidator {
     }
 
     /// Validate filter value based on operator
-    fn validate_filter_value(&self, operator: &str, value: &serde_json::Value) -> Result<()> {
-        match operator.to_uppercase().as_str() {
-            "IN" | "NOT IN" => {
+    fn validate_filter_value(
+        &self,
+        operator: FilterOperator,
+        value: &serde_json::Value,
+    ) -> Result<()> {
+        match operator {
+            FilterOperator::In | FilterOperator::NotIn => {
                 if !value.is_array() {
                     return Err(anyhow::anyhow!(
                         "Value for {} operator must be an array",
@@ -304,14 +476,41 @@ impl QueryValidator {
                     ));
                 }
 
-                if arr.len() > 1000 {
+                if arr.len() > self.config.max_in_list_len {
+                    warn!(
+                        len = arr.len(),
+                        max = self.config.max_in_list_len,
+                        "IN list exceeds maximum length"
+                    );
+                    return Err(anyhow::anyhow!(
+                        "Array for {} operator too large (max {} items)",
+                        operator,
+                        self.config.max_in_list_len
+                    ));
+                }
+
+                if arr.iter().any(|v| v.is_null() || v.is_array() || v.is_object()) {
+                    return Err(anyhow::anyhow!(
+                        "Array for {} operator must contain only scalar values",
+                        operator
+                    ));
+                }
+            }
+            FilterOperator::Between | FilterOperator::NotBetween => {
+                let bounds = value.as_array().filter(|bounds| bounds.len() == 2);
+                let valid = bounds.is_some_and(|bounds| {
+                    bounds
+                        .iter()
+                        .all(|v| !(v.is_null() || v.is_array() || v.is_object()))
+                });
+                if !valid {
                     return Err(anyhow::anyhow!(
-                        "Array for {} operator too large (max 1000 items)",
+                        "Value for {} operator must be a [low, high] array of scalars",
                         operator
                     ));
                 }
             }
-            "IS NULL" | "IS NOT NULL" => {
+            FilterOperator::IsNull | FilterOperator::IsNotNull => {
                 if !value.is_null() {
                     return Err(anyhow::anyhow!(
                         "Value for {} operator must be null",
@@ -319,7 +518,10 @@ impl QueryValidator {
                     ));
                 }
             }
-            "LIKE" | "NOT LIKE" => {
+            FilterOperator::Like
+            | FilterOperator::NotLike
+            | FilterOperator::ILike
+            | FilterOperator::NotILike => {
                 if !value.is_string() {
                     return Err(anyhow::anyhow!(
                         "Value for {} operator must be a string",
@@ -346,13 +548,99 @@ impl QueryValidator {
 
         Ok(())
     }
+
+    /// Validate filter operands against the declared type of the column
+    fn validate_filter_type(
+        &self,
+        column: &str,
+        column_type: ColumnType,
+        operator: FilterOperator,
+        value: &serde_json::Value,
+    ) -> Result<()> {
+        let operands: Vec<&serde_json::Value> = match operator {
+            FilterOperator::IsNull | FilterOperator::IsNotNull => return Ok(()),
+            FilterOperator::Like
+            | FilterOperator::NotLike
+            | FilterOperator::ILike
+            | FilterOperator::NotILike => {
+                if !column_type.is_text() {
+                    return Err(anyhow::anyhow!(
+                        "{} operator requires a text column, '{}' is {:?}",
+                        operator,
+                        column,
+                        column_type
+                    ));
+                }
+                vec![value]
+            }
+            FilterOperator::In
+            | FilterOperator::NotIn
+            | FilterOperator::Between
+            | FilterOperator::NotBetween => value.as_array().into_iter().flatten().collect(),
+            _ => vec![value],
+        };
+
+        match operands.into_iter().find(|v| !v.is_null() && !column_type.accepts(v)) {
+            Some(operand) => Err(anyhow::anyhow!(
+                "Value {} does not match the type {:?} of column '{}'",
+                operand,
+                column_type,
+                column
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether `schema` belongs to Postgres itself, whose `pg_` prefix is reserved
+fn is_system_schema(schema: &str) -> bool {
+    let schema = schema.to_lowercase();
+    schema == "information_schema" || schema.starts_with("pg_")
+}
+
+/// Whether `name` matches an allow-list entry, which may end with a `*` wildcard
+fn matches_allowed(allowed: &str, name: &str) -> bool {
+    match allowed.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => allowed == name,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::{FilterGroup, FilterLogic, QueryFilter};
     use serde_json::json;
 
+    fn filter(column: &str, value: serde_json::Value) -> QueryFilter {
+        QueryFilter {
+            column: column.to_string(),
+            operator: FilterOperator::Eq,
+            value,
+        }
+    }
+
+    fn nested_query(filter_logic: FilterLogic) -> ORMQuery {
+        ORMQuery {
+            operation: "select".to_string(),
+            table: "jobs".to_string(),
+            schema: None,
+            db_version: None,
+            columns: None,
+            filters: None,
+            filter_logic,
+            order_by: None,
+            limit: None,
+            offset: None,
+            aggregations: None,
+            group_by: None,
+            having: None,
+            values: None,
+            set_values: None,
+            expected_version: None,
+        }
+    }
+
     #[test]
     fn test_valid_query() {
         let config = ORMGatewayConfig::default();
@@ -362,12 +650,14 @@ mod tests {
             operation: "select".to_string(),
             table: "users".to_string(),
             schema: Some("public".to_string()),
+            db_version: None,
             columns: Some(vec!["id".to_string(), "name".to_string()]),
             filters: Some(vec![super::super::QueryFilter {
                 column: "id".to_string(),
-                operator: "=".to_string(),
+                operator: FilterOperator::Eq,
                 value: json!(1),
             }]),
+            filter_logic: FilterLogic::And,
             order_by: Some(vec![super::super::OrderBy {
                 column: "name".to_string(),
                 direction: "ASC".to_string(),
@@ -375,8 +665,11 @@ mod tests {
             limit: Some(100),
             offset: None,
             aggregations: None,
+            group_by: None,
+            having: None,
             values: None,
             set_values: None,
+            expected_version: None,
         };
 
         assert!(validator.validate(&query).is_ok());
@@ -392,16 +685,338 @@ mod tests {
             operation: "select".to_string(),
             table: "users; DROP TABLE users;".to_string(),
             schema: None,
+            db_version: None,
             columns: None,
             filters: None,
+            filter_logic: FilterLogic::And,
             order_by: None,
             limit: None,
             offset: None,
             aggregations: None,
+            group_by: None,
+            having: None,
             values: None,
             set_values: None,
+            expected_version: None,
         };
 
         assert!(validator.validate(&query).is_err());
     }
+
+    #[test]
+    fn test_nested_filter_groups() {
+        let validator = QueryValidator::new(ORMGatewayConfig::default());
+
+        // (status = 'pending' AND priority = 'high') OR (status = 'claimed' AND priority = 'low')
+        let query = nested_query(FilterLogic::Nested(Box::new(FilterGroup {
+            logic: FilterLogic::Or,
+            filters: vec![],
+            groups: vec![
+                FilterGroup {
+                    logic: FilterLogic::And,
+                    filters: vec![
+                        filter("status", json!("pending")),
+                        filter("priority", json!("high")),
+                    ],
+                    groups: vec![],
+                },
+                FilterGroup {
+                    logic: FilterLogic::And,
+                    filters: vec![
+                        filter("status", json!("claimed")),
+                        filter("priority", json!("low")),
+                    ],
+                    groups: vec![],
+                },
+            ],
+        })));
+        assert_eq!(query.filter_logic.depth(), 3);
+        assert_eq!(query.all_filters().len(), 4);
+        assert!(validator.validate(&query).is_ok());
+
+        // Nested filters are validated like top-level ones
+        let query = nested_query(FilterLogic::Nested(Box::new(FilterGroup {
+            logic: FilterLogic::Or,
+            filters: vec![filter("status; DROP TABLE jobs", json!(1))],
+            groups: vec![],
+        })));
+        assert!(validator.validate(&query).is_err());
+    }
+
+    #[test]
+    fn test_filter_depth_limit() {
+        let validator = QueryValidator::new(ORMGatewayConfig::default());
+
+        let innermost = FilterGroup {
+            logic: FilterLogic::And,
+            filters: vec![filter("id", json!(1))],
+            groups: vec![],
+        };
+        let too_deep = nested_query(FilterLogic::Nested(Box::new(FilterGroup {
+            logic: FilterLogic::Or,
+            filters: vec![],
+            groups: vec![FilterGroup {
+                logic: FilterLogic::Or,
+                filters: vec![],
+                groups: vec![innermost],
+            }],
+        })));
+
+        assert_eq!(too_deep.filter_logic.depth(), 4);
+        assert!(validator.validate(&too_deep).is_err());
+    }
+
+    #[test]
+    fn test_filter_operator_parsing() {
+        assert_eq!("=".parse::<FilterOperator>().unwrap(), FilterOperator::Eq);
+        assert_eq!("like".parse::<FilterOperator>().unwrap(), FilterOperator::Like);
+        assert_eq!(
+            " is   null ".parse::<FilterOperator>().unwrap(),
+            FilterOperator::IsNull
+        );
+
+        for op in FilterOperator::ALL {
+            assert_eq!(op.as_sql().parse::<FilterOperator>().unwrap(), op);
+        }
+    }
+
+    #[test]
+    fn test_injected_operator_is_refused() {
+        let err = "= 1; DROP TABLE".parse::<FilterOperator>().unwrap_err();
+        assert!(err.contains("Unsupported filter operator '= 1; DROP TABLE'"));
+
+        let parsed: Result<QueryFilter, _> = serde_json::from_value(json!({
+            "column": "id",
+            "operator": "= 1; DROP TABLE users; --",
+            "value": 1
+        }));
+        let err = parsed.unwrap_err().to_string();
+        assert!(err.contains("Unsupported filter operator"), "{}", err);
+
+        for op in ["OR 1=1", "==", "; DELETE", "<>", "SIMILAR TO"] {
+            assert!(op.parse::<FilterOperator>().is_err(), "{} accepted", op);
+        }
+    }
+
+    #[test]
+    fn test_list_range_and_null_filter_values() {
+        let validator = QueryValidator::new(ORMGatewayConfig::default());
+        let with_filter = |operator: &str, value: serde_json::Value| {
+            let mut query = nested_query(FilterLogic::And);
+            query.filters = Some(vec![serde_json::from_value(json!({
+                "column": "status",
+                "operator": operator,
+                "value": value
+            }))
+            .unwrap()]);
+            validator.validate(&query)
+        };
+
+        assert!(with_filter("IN", json!(["pending", "running"])).is_ok());
+        assert!(with_filter("NOT IN", json!([1, 2, 3])).is_ok());
+        assert!(with_filter("IN", json!([])).is_err());
+        assert!(with_filter("IN", json!("pending")).is_err());
+        assert!(with_filter("IN", json!([["nested"]])).is_err());
+
+        assert!(with_filter("BETWEEN", json!([1, 10])).is_ok());
+        assert!(with_filter("not between", json!(["2026-01-01", "2026-02-01"])).is_ok());
+        assert!(with_filter("BETWEEN", json!([1])).is_err());
+        assert!(with_filter("BETWEEN", json!([1, null])).is_err());
+        assert!(with_filter("BETWEEN", json!(5)).is_err());
+
+        assert!(with_filter("IS NULL", json!(null)).is_ok());
+        assert!(with_filter("IS NOT NULL", json!(1)).is_err());
+
+        // The value may be omitted for IS [NOT] NULL
+        let filter: QueryFilter =
+            serde_json::from_value(json!({"column": "error", "operator": "IS NOT NULL"})).unwrap();
+        assert!(filter.value.is_null());
+    }
+
+    #[test]
+    fn test_in_list_length_is_capped() {
+        let config = ORMGatewayConfig {
+            max_in_list_len: 3,
+            ..ORMGatewayConfig::default()
+        };
+        let validator = QueryValidator::new(config);
+
+        let in_list = |len: usize| {
+            let mut query = nested_query(FilterLogic::And);
+            query.filters = Some(vec![QueryFilter {
+                column: "id".to_string(),
+                operator: FilterOperator::In,
+                value: json!((0..len).collect::<Vec<_>>()),
+            }]);
+            validator.validate(&query)
+        };
+
+        assert!(in_list(3).is_ok());
+        let err = in_list(10_000).unwrap_err().to_string();
+        assert_eq!(err, "Array for IN operator too large (max 3 items)");
+    }
+
+    #[test]
+    fn test_operands_checked_against_declared_types() {
+        let mut config = ORMGatewayConfig::default();
+        config.column_types.insert(
+            "jobs".to_string(),
+            std::collections::HashMap::from([
+                ("id".to_string(), ColumnType::Uuid),
+                ("retries".to_string(), ColumnType::Integer),
+                ("name".to_string(), ColumnType::Text),
+            ]),
+        );
+        let validator = QueryValidator::new(config);
+        let with_filter = |column: &str, operator: &str, value: serde_json::Value| {
+            let mut query = nested_query(FilterLogic::And);
+            query.filters = Some(vec![serde_json::from_value(json!({
+                "column": column,
+                "operator": operator,
+                "value": value
+            }))
+            .unwrap()]);
+            validator.validate(&query)
+        };
+
+        assert!(with_filter("id", "IN", json!([uuid::Uuid::new_v4()])).is_ok());
+        assert!(with_filter("id", "IN", json!(["not-a-uuid"])).is_err());
+        assert!(with_filter("retries", "BETWEEN", json!([1, 3])).is_ok());
+        assert!(with_filter("retries", "BETWEEN", json!([1, "3"])).is_err());
+        assert!(with_filter("retries", ">", json!(1.5)).is_err());
+        assert!(with_filter("name", "ilike", json!("%alice%")).is_ok());
+        assert!(with_filter("retries", "LIKE", json!("1%")).is_err());
+        assert!(with_filter("retries", "IS NULL", json!(null)).is_ok());
+
+        // Columns without a declared type are not checked
+        assert!(with_filter("status", "=", json!(1)).is_ok());
+    }
+
+    #[test]
+    fn test_aggregation_validation_failures() {
+        let validator = QueryValidator::new(ORMGatewayConfig::default());
+        let grouped = || {
+            let mut query = nested_query(FilterLogic::And);
+            query.aggregations = Some(vec![super::super::Aggregation {
+                function: "count".to_string(),
+                column: "*".to_string(),
+                alias: "job_count".to_string(),
+            }]);
+            query.group_by = Some(vec!["status".to_string()]);
+            query.limit = Some(50);
+            query
+        };
+        assert!(validator.validate(&grouped()).is_ok());
+        let rejected = |query: ORMQuery| validator.validate(&query).unwrap_err().to_string();
+
+        let mut mixed = grouped();
+        mixed.group_by = None;
+        mixed.columns = Some(vec!["status".to_string()]);
+        assert_eq!(
+            rejected(mixed),
+            "Cannot select column 'status' alongside aggregations without group_by"
+        );
+
+        let mut ungrouped_column = grouped();
+        ungrouped_column.columns = Some(vec!["status".to_string(), "runtime".to_string()]);
+        assert_eq!(
+            rejected(ungrouped_column),
+            "Column 'runtime' must be in group_by to be selected alongside aggregations"
+        );
+
+        let mut unbounded = grouped();
+        unbounded.limit = None;
+        assert_eq!(rejected(unbounded), "Grouped queries require a limit (at most 1000)");
+
+        let mut over_limit = grouped();
+        over_limit.limit = Some(5000);
+        assert!(validator.validate(&over_limit).is_err());
+
+        let mut sum_all = grouped();
+        sum_all.aggregations.as_mut().unwrap()[0].function = "SUM".to_string();
+        assert_eq!(rejected(sum_all), "Only COUNT can aggregate over *");
+
+        let mut injected = grouped();
+        injected.aggregations.as_mut().unwrap()[0].function = "pg_sleep".to_string();
+        assert!(validator.validate(&injected).is_err());
+
+        let mut injected_group = grouped();
+        injected_group.group_by = Some(vec!["status; DROP TABLE jobs".to_string()]);
+        assert!(validator.validate(&injected_group).is_err());
+
+        let mut unordered = grouped();
+        unordered.order_by = Some(vec![super::super::OrderBy {
+            column: "created_at".to_string(),
+            direction: "DESC".to_string(),
+        }]);
+        assert!(validator.validate(&unordered).is_err());
+        unordered.order_by.as_mut().unwrap()[0].column = "job_count".to_string();
+        assert!(validator.validate(&unordered).is_ok());
+
+        let mut having_without_aggregation = nested_query(FilterLogic::And);
+        having_without_aggregation.having = Some(vec![super::super::HavingFilter {
+            function: "COUNT".to_string(),
+            column: "*".to_string(),
+            operator: FilterOperator::Gt,
+            value: json!(1),
+        }]);
+        assert_eq!(
+            rejected(having_without_aggregation),
+            "group_by and having require at least one aggregation"
+        );
+
+        let mut count_operation = grouped();
+        count_operation.operation = "count".to_string();
+        assert_eq!(
+            rejected(count_operation),
+            "Aggregations are only supported on select, not count"
+        );
+    }
+
+    #[test]
+    fn test_schema_qualified_tables() {
+        let config = ORMGatewayConfig {
+            allowed_schemas: vec!["public".to_string(), "challenge_*".to_string()],
+            allowed_tables: vec!["jobs".to_string(), "results".to_string()],
+            ..ORMGatewayConfig::default()
+        };
+        let validator = QueryValidator::new(config);
+        let qualify = |table: &str, schema: Option<&str>| {
+            let mut query = nested_query(FilterLogic::And);
+            query.table = table.to_string();
+            query.schema = schema.map(str::to_string);
+            validator.qualify_table(&mut query).map(|_| query)
+        };
+
+        let query = qualify("jobs", None).unwrap();
+        assert_eq!(
+            (query.schema.as_deref(), query.table.as_str()),
+            (Some("public"), "jobs")
+        );
+
+        let query = qualify("challenge_term_v1.results", None).unwrap();
+        assert_eq!(
+            (query.schema.as_deref(), query.table.as_str()),
+            (Some("challenge_term_v1"), "results")
+        );
+        assert!(qualify("challenge_term_v1.results", Some("challenge_term_v1")).is_ok());
+        assert!(qualify("challenge_term_v1.results", Some("public")).is_err());
+
+        let rejected =
+            |table: &str, schema: Option<&str>| qualify(table, schema).unwrap_err().to_string();
+        assert_eq!(
+            rejected("pg_catalog.pg_authid", None),
+            "Access denied to system schema 'pg_catalog'"
+        );
+        assert_eq!(
+            rejected("tables", Some("information_schema")),
+            "Access denied to system schema 'information_schema'"
+        );
+        assert_eq!(
+            rejected("private.jobs", None),
+            "Schema 'private' is not allowed"
+        );
+        assert_eq!(rejected("users", None), "Table 'users' is not allowed");
+        assert_eq!(rejected("a.b.jobs", None), "Invalid table name: b.jobs");
+    }
 }
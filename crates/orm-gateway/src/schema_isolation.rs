@@ -0,0 +1,298 @@
+//! Per-challenge schema isolation
+//!
+//! Every challenge owns a Postgres schema, `challenge_<shortid>`, and a role
+//! whose privileges are limited to that schema. Queries made on behalf of a
+//! challenge are confined to the schema derived from its immutable ID, see
+//! `SecureORMGateway::execute_scoped_query`: a query naming any other schema is
+//! refused with `ORMError::SchemaDenied` instead of being trusted, and the
+//! query runs as the challenge role, so that the database enforces the
+//! confinement too.
+
+use anyhow::Result;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{ORMError, ORMQuery};
+
+/// Prefix of the schema of every challenge
+pub const CHALLENGE_SCHEMA_PREFIX: &str = "challenge_";
+
+/// Hex digits of the challenge ID kept in its schema name
+const SHORT_ID_LEN: usize = 16;
+
+/// Schema and role provisioned for a challenge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChallengeSchema {
+    pub challenge_id: Uuid,
+    pub schema: String,
+    pub role: String,
+}
+
+impl ChallengeSchema {
+    /// Names of the schema and role of `challenge_id`
+    pub fn for_challenge(challenge_id: Uuid) -> Self {
+        let schema = challenge_schema(&challenge_id);
+        Self {
+            challenge_id,
+            role: format!("{}_rw", schema),
+            schema,
+        }
+    }
+}
+
+/// Schema of a challenge: `challenge_` followed by the first 16 hex digits of its ID
+pub fn challenge_schema(challenge_id: &Uuid) -> String {
+    let short_id = &challenge_id.simple().to_string()[..SHORT_ID_LEN];
+    format!("{}{}", CHALLENGE_SCHEMA_PREFIX, short_id)
+}
+
+/// Confine `query` to `schema`
+///
+/// The schema named by `query.schema` or a `schema.table` name must be
+/// `schema`; queries naming none are given it.
+pub fn confine_to_schema(query: &mut ORMQuery, schema: &str) -> Result<()> {
+    let qualifier = query.table.split_once('.').map(|(named, _)| named);
+    for named in [query.schema.as_deref(), qualifier].into_iter().flatten() {
+        if named != schema {
+            return Err(ORMError::SchemaDenied {
+                schema: named.to_string(),
+                allowed: schema.to_string(),
+            }
+            .into());
+        }
+    }
+
+    query.schema = Some(schema.to_string());
+    Ok(())
+}
+
+/// Create the schema of a challenge and a role with privileges limited to it
+///
+/// Idempotent: provisioning a challenge again only re-applies the grants, so
+/// it is safe on every registration. The role cannot log in; the platform's
+/// login is made a member of it, to run the challenge's queries as it.
+pub async fn provision_challenge_schema(
+    pool: &PgPool,
+    challenge_id: Uuid,
+) -> Result<ChallengeSchema> {
+    let names = ChallengeSchema::for_challenge(challenge_id);
+    // Both names are built from hex digits only, so they are safe to interpolate
+    let (schema, role) = (&names.schema, &names.role);
+
+    let mut tx = pool.begin().await?;
+    // Concurrent provisioning of the same challenge would race on CREATE ROLE
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1::text, 2))")
+        .bind(schema)
+        .execute(&mut *tx)
+        .await?;
+
+    for statement in [
+        format!("CREATE SCHEMA IF NOT EXISTS {}", schema),
+        format!(
+            "DO $$ BEGIN \
+                IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = '{role}') THEN \
+                    CREATE ROLE {role} NOLOGIN; \
+                END IF; \
+             END $$",
+            role = role
+        ),
+        format!("GRANT {} TO CURRENT_USER", role),
+        format!("REVOKE ALL ON SCHEMA {} FROM PUBLIC", schema),
+        format!("GRANT USAGE, CREATE ON SCHEMA {} TO {}", schema, role),
+        format!(
+            "GRANT SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA {} TO {}",
+            schema, role
+        ),
+        format!(
+            "GRANT USAGE, SELECT ON ALL SEQUENCES IN SCHEMA {} TO {}",
+            schema, role
+        ),
+        format!(
+            "ALTER DEFAULT PRIVILEGES IN SCHEMA {} GRANT SELECT, INSERT, UPDATE, DELETE ON TABLES TO {}",
+            schema, role
+        ),
+        format!(
+            "ALTER DEFAULT PRIVILEGES IN SCHEMA {} GRANT USAGE, SELECT ON SEQUENCES TO {}",
+            schema, role
+        ),
+    ] {
+        sqlx::query(&statement).execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+
+    info!(
+        challenge_id = %challenge_id,
+        schema = schema.as_str(),
+        role = role.as_str(),
+        "Provisioned challenge schema"
+    );
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FilterLogic, ORMGatewayConfig, SecureORMGateway};
+
+    fn query(table: &str, schema: Option<&str>) -> ORMQuery {
+        ORMQuery {
+            operation: "select".to_string(),
+            table: table.to_string(),
+            schema: schema.map(str::to_string),
+            db_version: None,
+            columns: None,
+            filters: None,
+            filter_logic: FilterLogic::And,
+            order_by: None,
+            limit: None,
+            offset: None,
+            aggregations: None,
+            group_by: None,
+            having: None,
+            values: None,
+            set_values: None,
+            expected_version: None,
+        }
+    }
+
+    #[test]
+    fn test_challenge_schema_name() {
+        let id = Uuid::parse_str("0f1e2d3c-4b5a-6978-8796-a5b4c3d2e1f0").unwrap();
+        let names = ChallengeSchema::for_challenge(id);
+        assert_eq!(names.schema, "challenge_0f1e2d3c4b5a6978");
+        assert_eq!(names.role, "challenge_0f1e2d3c4b5a6978_rw");
+    }
+
+    #[tokio::test]
+    async fn test_cross_schema_access_is_rejected() {
+        let own = ChallengeSchema::for_challenge(Uuid::new_v4());
+        let other = challenge_schema(&Uuid::new_v4());
+        // Never connects: every query is rejected before execution
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let gateway = SecureORMGateway::new(ORMGatewayConfig::read_only(), pool);
+
+        for denied in [
+            query("scores", Some(&other)),
+            query(&format!("{}.scores", other), None),
+            query("scores", Some("public")),
+            // The qualifier cannot contradict an allowed schema field either
+            query(&format!("{}.scores", other), Some(&own.schema)),
+        ] {
+            let err = gateway
+                .execute_scoped_query(&own, denied)
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(
+                    err.downcast_ref::<ORMError>(),
+                    Some(ORMError::SchemaDenied { allowed, .. }) if *allowed == own.schema
+                ),
+                "unexpected error: {}",
+                err
+            );
+        }
+
+        let mut unqualified = query("scores", None);
+        confine_to_schema(&mut unqualified, &own.schema).unwrap();
+        assert_eq!(unqualified.schema.as_deref(), Some(own.schema.as_str()));
+        let mut qualified = query(&format!("{}.scores", own.schema), None);
+        confine_to_schema(&mut qualified, &own.schema).unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_provisioning_is_idempotent() {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let first = provision_challenge_schema(&pool, Uuid::new_v4())
+            .await
+            .unwrap();
+        let other = provision_challenge_schema(&pool, Uuid::new_v4())
+            .await
+            .unwrap();
+        sqlx::query(&format!("CREATE TABLE {}.scores (id BIGINT)", first.schema))
+            .execute(&pool)
+            .await
+            .unwrap();
+        let again = provision_challenge_schema(&pool, first.challenge_id)
+            .await
+            .unwrap();
+        assert_eq!(again, first);
+
+        // The role reaches its challenge schema and tables, not other challenges'
+        let (own_schema, own_table, other_schema): (bool, bool, bool) = sqlx::query_as(
+            "SELECT has_schema_privilege($1, $2, 'USAGE'), \
+                    has_table_privilege($1, $2 || '.scores', 'INSERT'), \
+                    has_schema_privilege($1, $3, 'USAGE')",
+        )
+        .bind(&first.role)
+        .bind(&first.schema)
+        .bind(&other.schema)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(own_schema && own_table && !other_schema);
+
+        for names in [first, other] {
+            sqlx::query(&format!("DROP SCHEMA {} CASCADE", names.schema))
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query(&format!("DROP ROLE {}", names.role))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_scoped_queries_run_as_challenge_role() {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        let names = provision_challenge_schema(&pool, Uuid::new_v4())
+            .await
+            .unwrap();
+        for statement in [
+            format!("CREATE TABLE {}.scores (id BIGINT)", names.schema),
+            format!("INSERT INTO {}.scores VALUES (1)", names.schema),
+        ] {
+            sqlx::query(&statement).execute(&pool).await.unwrap();
+        }
+        let gateway = SecureORMGateway::new(ORMGatewayConfig::read_only(), pool.clone());
+
+        let result = gateway
+            .execute_scoped_query(&names, query("scores", None))
+            .await
+            .unwrap();
+        assert_eq!(result.row_count, 1);
+
+        // Without the grant to its role, the challenge cannot read its own table
+        sqlx::query(&format!(
+            "REVOKE SELECT ON {}.scores FROM {}",
+            names.schema, names.role
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+        let err = gateway
+            .execute_scoped_query(&names, query("scores", None))
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("permission denied"),
+            "{:#}",
+            err
+        );
+
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", names.schema))
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(&format!("DROP ROLE {}", names.role))
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+//! Prepared statements for repeated query shapes
+//!
+//! Challenge SDKs send the same query shape many times with different values.
+//! The shape of a query is its SQL, values left as `$n` parameters, together
+//! with the Postgres types of those parameters. A query whose shape ran
+//! recently is run as a named prepared statement, which each connection parses
+//! and plans once and then reuses; other queries run unnamed, so one-off
+//! shapes do not evict hot statements from the connection caches.
+//!
+//! Shapes are kept in an LRU of `ORMGatewayConfig::statement_cache_capacity`
+//! entries. Invalidating the cache, as the gateway does when its config or
+//! permissions change, also renames every statement, so none prepared before
+//! is reused.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Counters of a statement cache
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatementCacheStats {
+    /// Queries run as a cached prepared statement
+    pub hits: u64,
+    /// Queries whose shape was not cached
+    pub misses: u64,
+    /// Shapes currently cached
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+/// SQL to send for a query, and whether to prepare it as a named statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    pub sql: String,
+    pub persistent: bool,
+}
+
+#[derive(Debug, Default)]
+struct Shapes {
+    capacity: usize,
+    /// Last use of each cached shape, keyed by its statement SQL
+    last_used: HashMap<String, u64>,
+    /// Cached shapes by last use, least recent first
+    by_last_use: BTreeMap<u64, String>,
+    clock: u64,
+    /// Bumped on every invalidation, and part of every statement SQL
+    generation: u64,
+}
+
+/// LRU of recently run query shapes, see the module documentation
+#[derive(Debug)]
+pub struct StatementCache {
+    shapes: Mutex<Shapes>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl StatementCache {
+    /// Cache of up to `capacity` shapes; 0 disables caching
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            shapes: Mutex::new(Shapes {
+                capacity,
+                ..Shapes::default()
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Statement running `sql`, whose parameters have the Postgres types
+    /// listed in `parameter_types`
+    ///
+    /// The first query of a shape runs unnamed and caches the shape; the
+    /// following ones run as a prepared statement while the shape is cached.
+    pub fn statement(&self, sql: &str, parameter_types: &str) -> Statement {
+        let mut shapes = self.shapes.lock().unwrap();
+        if shapes.capacity == 0 {
+            return Statement {
+                sql: sql.to_string(),
+                persistent: false,
+            };
+        }
+
+        // Prepared statements are cached by connections under their SQL alone,
+        // so the SQL names the parameter types a statement was prepared with
+        let shape = format!(
+            "{} /* shape {}: {} */",
+            sql, shapes.generation, parameter_types
+        );
+        shapes.clock += 1;
+        let now = shapes.clock;
+
+        let hit = match shapes.last_used.insert(shape.clone(), now) {
+            Some(last_use) => {
+                shapes.by_last_use.remove(&last_use);
+                true
+            }
+            None => false,
+        };
+        shapes.by_last_use.insert(now, shape.clone());
+        if shapes.last_used.len() > shapes.capacity {
+            if let Some((_, evicted)) = shapes.by_last_use.pop_first() {
+                shapes.last_used.remove(&evicted);
+            }
+        }
+
+        if hit {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            Statement {
+                sql: shape,
+                persistent: true,
+            }
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            Statement {
+                sql: sql.to_string(),
+                persistent: false,
+            }
+        }
+    }
+
+    /// Forget every cached shape; statements prepared so far are not reused
+    pub fn invalidate(&self) {
+        let mut shapes = self.shapes.lock().unwrap();
+        shapes.last_used.clear();
+        shapes.by_last_use.clear();
+        shapes.generation += 1;
+    }
+
+    /// Keep up to `capacity` shapes from now on; invalidates the cache
+    pub fn set_capacity(&self, capacity: usize) {
+        self.shapes.lock().unwrap().capacity = capacity;
+        self.invalidate();
+    }
+
+    pub fn stats(&self) -> StatementCacheStats {
+        let shapes = self.shapes.lock().unwrap();
+        StatementCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: shapes.last_used.len(),
+            capacity: shapes.capacity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        FilterLogic, FilterOperator, ORMGatewayConfig, ORMQuery, QueryFilter, SecureORMGateway,
+    };
+    use serde_json::{json, Value};
+    use sqlx::PgPool;
+
+    const SQL: &str = "SELECT * FROM scores WHERE id = $1 LIMIT 100";
+
+    #[test]
+    fn test_repeated_shapes_are_prepared() {
+        let cache = StatementCache::new(2);
+
+        let first = cache.statement(SQL, "INT8");
+        assert_eq!(first.sql, SQL);
+        assert!(!first.persistent);
+        let second = cache.statement(SQL, "INT8");
+        assert!(second.persistent);
+        assert_eq!(cache.statement(SQL, "INT8"), second);
+
+        // The same SQL with other parameter types is another shape
+        assert!(!cache.statement(SQL, "TEXT").persistent);
+        assert_ne!(cache.statement(SQL, "TEXT").sql, second.sql);
+
+        assert_eq!(
+            cache.stats(),
+            StatementCacheStats {
+                hits: 3,
+                misses: 2,
+                entries: 2,
+                capacity: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_least_recently_used_shape_is_evicted() {
+        let cache = StatementCache::new(2);
+        for shape in ["INT8", "TEXT", "INT8", "BOOL"] {
+            cache.statement(SQL, shape);
+        }
+
+        assert!(cache.statement(SQL, "INT8").persistent);
+        assert!(cache.statement(SQL, "BOOL").persistent);
+        assert!(!cache.statement(SQL, "TEXT").persistent);
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[test]
+    fn test_invalidation_renames_statements() {
+        let cache = StatementCache::new(2);
+        cache.statement(SQL, "INT8");
+        let before = cache.statement(SQL, "INT8");
+
+        cache.invalidate();
+        assert_eq!(cache.stats().entries, 0);
+        assert!(!cache.statement(SQL, "INT8").persistent);
+        let after = cache.statement(SQL, "INT8");
+        assert!(after.persistent);
+        assert_ne!(after.sql, before.sql);
+    }
+
+    #[test]
+    fn test_disabled_cache() {
+        let cache = StatementCache::new(0);
+        cache.statement(SQL, "INT8");
+        assert!(!cache.statement(SQL, "INT8").persistent);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    /// Gateway with a statement cache of `capacity` shapes on a fresh
+    /// `orm_stmt_cache_test_v1` schema holding 50 scores
+    async fn scores_gateway(capacity: usize) -> SecureORMGateway {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = PgPool::connect(&database_url).await.unwrap();
+        for statement in [
+            "DROP SCHEMA IF EXISTS orm_stmt_cache_test_v1 CASCADE",
+            "CREATE SCHEMA orm_stmt_cache_test_v1",
+            "CREATE TABLE orm_stmt_cache_test_v1.scores (id BIGINT PRIMARY KEY, score FLOAT8 NOT NULL)",
+            "INSERT INTO orm_stmt_cache_test_v1.scores SELECT i, i / 2.0 FROM generate_series(1, 50) i",
+        ] {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        SecureORMGateway::new(
+            ORMGatewayConfig {
+                statement_cache_capacity: capacity,
+                ..ORMGatewayConfig::read_only()
+            },
+            pool,
+        )
+    }
+
+    fn scores_where(filters: Vec<(&str, FilterOperator, Value)>) -> ORMQuery {
+        ORMQuery {
+            operation: "select".to_string(),
+            table: "scores".to_string(),
+            schema: Some("orm_stmt_cache_test_v1".to_string()),
+            db_version: None,
+            columns: Some(vec!["id".to_string()]),
+            filters: Some(
+                filters
+                    .into_iter()
+                    .map(|(column, operator, value)| QueryFilter {
+                        column: column.to_string(),
+                        operator,
+                        value,
+                    })
+                    .collect(),
+            ),
+            filter_logic: FilterLogic::And,
+            order_by: None,
+            limit: None,
+            offset: None,
+            aggregations: None,
+            group_by: None,
+            having: None,
+            values: None,
+            set_values: None,
+            expected_version: None,
+        }
+    }
+
+    async fn ids(gateway: &SecureORMGateway, query: ORMQuery) -> Vec<i64> {
+        let mut ids: Vec<i64> = gateway
+            .execute_query(query)
+            .await
+            .unwrap()
+            .rows
+            .iter()
+            .map(|row| row["id"].as_i64().unwrap())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_cached_shape_binds_each_query_values() {
+        let gateway = scores_gateway(10).await;
+
+        for _ in 0..3 {
+            for id in [7, 21, 50, 51] {
+                let query = scores_where(vec![("id", FilterOperator::Eq, json!(id))]);
+                let expected: Vec<i64> = (id <= 50).then_some(id).into_iter().collect();
+                assert_eq!(ids(&gateway, query).await, expected);
+            }
+        }
+        let stats = gateway.statement_cache_stats();
+        assert_eq!((stats.hits, stats.misses), (11, 1));
+
+        // Same SQL, but an integer and then a float parameter: each is its own
+        // statement, prepared with the type of its values
+        for _ in 0..2 {
+            let query = scores_where(vec![("score", FilterOperator::Gte, json!(24))]);
+            assert_eq!(ids(&gateway, query).await, vec![48, 49, 50]);
+            let query = scores_where(vec![("score", FilterOperator::Gte, json!(24.5))]);
+            assert_eq!(ids(&gateway, query).await, vec![49, 50]);
+        }
+
+        // Lists of any length are a single array parameter
+        for list in [json!([1, 2]), json!([3]), json!([4, 5, 60])] {
+            let expected: Vec<i64> = list
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter_map(Value::as_i64)
+                .filter(|id| *id <= 50)
+                .collect();
+            let query = scores_where(vec![("id", FilterOperator::In, list)]);
+            assert_eq!(ids(&gateway, query).await, expected);
+        }
+
+        let stats = gateway.statement_cache_stats();
+        assert_eq!((stats.hits, stats.misses), (15, 4));
+        assert_eq!(stats.entries, 4);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_repeated_shape_is_planned_once() {
+        const QUERIES: u64 = 500;
+
+        async fn run(gateway: &SecureORMGateway) {
+            for i in 0..QUERIES {
+                let query = scores_where(vec![
+                    ("id", FilterOperator::Gte, json!(i % 50)),
+                    ("score", FilterOperator::Lt, json!((i % 50) as f64 + 0.5)),
+                    ("id", FilterOperator::NotIn, json!([1, 2, 3])),
+                ]);
+                gateway.execute_query(query).await.unwrap();
+            }
+        }
+
+        // Without a cache every query is parsed and planned again
+        let uncached = scores_gateway(0).await;
+        run(&uncached).await;
+        assert_eq!(
+            uncached.statement_cache_stats(),
+            StatementCacheStats::default()
+        );
+
+        // With one, only the first query of the shape is; the following ones
+        // reuse its prepared statement
+        let cached = scores_gateway(100).await;
+        run(&cached).await;
+        assert_eq!(
+            cached.statement_cache_stats(),
+            StatementCacheStats {
+                hits: QUERIES - 1,
+                misses: 1,
+                entries: 1,
+                capacity: 100,
+            }
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::Json,
     routing::{get, post},
     Router,
@@ -14,7 +14,7 @@ use uuid::Uuid;
 use platform_api::state::AppState;
 use platform_api_models::{
     AttestationRequest, AttestationResponse, AttestationSession, KeyReleaseRequest,
-    KeyReleaseResponse,
+    KeyReleaseResponse, SessionToken,
 };
 
 /// Create attestation router
@@ -78,14 +78,30 @@ pub async fn verify_attestation(
     Ok(Json(response))
 }
 
-/// Get attestation session
+/// Get attestation session of the validator the `X-Attestation-Token` was issued to
+///
+/// Sessions of other validators are reported as not found.
 pub async fn get_attestation_session(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
 ) -> Result<Json<AttestationSession>, StatusCode> {
+    let token = headers
+        .get("X-Attestation-Token")
+        .and_then(|v| v.to_str().ok())
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let claims = state
+        .attestation
+        .verify_token_async(token)
+        .await
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let validator_hotkey = claims["session_namespace"]
+        .as_str()
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
     let session = state
         .attestation
-        .get_session(id)
+        .get_session(id, validator_hotkey)
         .await
         .map_err(|_| StatusCode::NOT_FOUND)?;
 
@@ -156,7 +172,7 @@ pub async fn get_policy(
 #[derive(Debug, serde::Deserialize)]
 pub struct VerifyKeyRequest {
     pub key_id: String,
-    pub session_token: String,
+    pub session_token: SessionToken,
     pub policy: String,
 }
 
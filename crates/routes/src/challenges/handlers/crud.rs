@@ -8,7 +8,8 @@ use uuid::Uuid;
 use platform_api::state::AppState;
 use platform_api_models::{
     ChallengeDetailResponse, ChallengeMetadata, ChallengeStatus, ChallengeVisibility,
-    CreateChallengeRequest, Hotkey, Id, UpdateChallengeRequest,
+    CreateChallengeRequest, Id, JobPriority, UpdateChallengeRequest,
+    DEFAULT_HEALTHCHECK_TIMEOUT_SECS,
 };
 
 use crate::challenges::types::ChallengeRow;
@@ -51,15 +52,24 @@ pub async fn get_challenge(
             version: row.version,
             visibility: ChallengeVisibility::Public,
             status: ChallengeStatus::Active,
-            owner: Hotkey::from("platform"),
+            owner: "platform".to_string(),
             created_at: row.created_at,
             updated_at: row.updated_at,
             tags: vec![],
+            default_job_priority: JobPriority::default(),
+            job_payload_schema: None,
+            job_defaults: Default::default(),
+            resources: Default::default(),
+            score_bounds: Default::default(),
+            score_policy: Default::default(),
+            healthcheck_url: None,
+            healthcheck_timeout_secs: DEFAULT_HEALTHCHECK_TIMEOUT_SECS,
         };
 
         let response = ChallengeDetailResponse {
             metadata,
             emissions: None,
+            stats: None,
         };
 
         Ok(Json(response))
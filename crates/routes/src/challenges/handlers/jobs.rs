@@ -22,7 +22,7 @@ pub async fn get_challenge_jobs(
 
     let jobs = state
         .scheduler
-        .list_jobs(page, per_page, params.status, Some(id))
+        .list_jobs(page, per_page, params.status, Some(id), &Default::default())
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
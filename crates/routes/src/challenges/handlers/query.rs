@@ -8,7 +8,8 @@ use sqlx::Row;
 
 use platform_api::state::AppState;
 use platform_api_models::{
-    ChallengeListResponse, ChallengeMetadata, ChallengeStatus, ChallengeVisibility, Hotkey, Id,
+    ChallengeListResponse, ChallengeMetadata, ChallengeStatus, ChallengeVisibility, Id,
+    JobPriority, DEFAULT_HEALTHCHECK_TIMEOUT_SECS,
 };
 use tracing::debug;
 
@@ -112,10 +113,18 @@ pub async fn list_challenges(
             version: row.version.clone(),
             visibility: ChallengeVisibility::Public, // Default to Public
             status: ChallengeStatus::Active, // All challenges in database are considered active
-            owner: Hotkey::from("platform"), // Default owner
+            owner: "platform".to_string(),   // Default owner
             created_at: row.created_at,
             updated_at: row.updated_at,
             tags: vec![], // No tags for now
+            default_job_priority: JobPriority::default(),
+            job_payload_schema: None,
+            job_defaults: Default::default(),
+            resources: Default::default(),
+            score_bounds: Default::default(),
+            score_policy: Default::default(),
+            healthcheck_url: None,
+            healthcheck_timeout_secs: DEFAULT_HEALTHCHECK_TIMEOUT_SECS,
         })
         .collect();
 
@@ -7,12 +7,14 @@ use uuid::Uuid;
 
 use platform_api::job_distributor::{DistributeJobRequest, JobDistributor};
 use platform_api::state::AppState;
-use platform_api_models::JobMetadata;
+use platform_api_models::{JobMetadata, PlatformError};
 use platform_api_scheduler::CreateJobRequest;
 
 use crate::jobs::types::ChallengeCreateJobRequest;
 
 /// Create a job from challenge SDK
+///
+/// Payloads above the scheduler's `max_job_payload_bytes` are refused with 413.
 pub async fn create_job_from_challenge(
     State(state): State<AppState>,
     Json(request): Json<ChallengeCreateJobRequest>,
@@ -63,9 +65,14 @@ pub async fn create_job_from_challenge(
         challenge_id: platform_api_models::Id::from(challenge_uuid),
         payload: request.payload.clone(),
         priority,
-        runtime: platform_api_models::RuntimeType::Docker,
+        runtime: None,
         timeout: request.timeout,
         max_retries: request.max_retries,
+        job_id: None,
+        depends_on: vec![],
+        deadline: None,
+        required_capabilities: None,
+        resources: None,
     };
 
     // Create the job in the scheduler
@@ -75,7 +82,11 @@ pub async fn create_job_from_challenge(
         .await
         .map_err(|e| {
             tracing::error!("Failed to create job from challenge: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            if matches!(e, PlatformError::PayloadTooLarge { .. }) {
+                StatusCode::PAYLOAD_TOO_LARGE
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
         })?;
 
     // Try to get challenge info and distribute job
@@ -7,12 +7,16 @@ use uuid::Uuid;
 
 use platform_api::job_distributor::{DistributeJobRequest, JobDistributor};
 use platform_api::state::AppState;
-use platform_api_models::{ClaimJobRequest, ClaimJobResponse, JobListResponse, JobMetadata, JobStats};
-use platform_api_scheduler::CreateJobRequest;
+use platform_api_models::{
+    ClaimJobRequest, ClaimJobResponse, JobListResponse, JobMetadata, JobStats, PlatformError,
+};
+use platform_api_scheduler::{CreateJobRequest, JobSearch};
 
 use crate::jobs::types::{GetNextJobParams, ListJobsParams, PendingJobsParams};
 
 /// Create a new job
+///
+/// Payloads above the scheduler's `max_job_payload_bytes` are refused with 413.
 pub async fn create_job(
     State(state): State<AppState>,
     Json(request): Json<CreateJobRequest>,
@@ -24,7 +28,11 @@ pub async fn create_job(
     // Create the job in the scheduler
     let job = state.scheduler.create_job(request).await.map_err(|e| {
         tracing::error!("Failed to create job: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        if matches!(e, PlatformError::PayloadTooLarge { .. }) {
+            StatusCode::PAYLOAD_TOO_LARGE
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
     })?;
 
     // Get challenge info to find compose_hash
@@ -103,6 +111,11 @@ pub async fn list_jobs(
             params.per_page.unwrap_or(20),
             params.status,
             params.challenge_id,
+            &JobSearch {
+                search: params.search,
+                created_after: params.created_after,
+                created_before: params.created_before,
+            },
         )
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -142,7 +155,7 @@ pub async fn get_pending_jobs(
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     let jobs = state
         .scheduler
-        .list_jobs(1, 100, Some("pending".to_string()), None)
+        .list_jobs(1, 100, Some("pending".to_string()), None, &JobSearch::default())
         .await
         .map_err(|e| {
             tracing::error!("Failed to list pending jobs: {}", e);
@@ -218,6 +231,13 @@ pub async fn get_next_job(
     State(state): State<AppState>,
     Query(params): Query<GetNextJobParams>,
 ) -> Result<Json<Option<ClaimJobResponse>>, StatusCode> {
+    if let Some(capacity) = params.capacity {
+        state
+            .scheduler
+            .report_validator_capacity(&params.validator_hotkey, capacity)
+            .await;
+    }
+
     let job = state
         .scheduler
         .get_next_job(params.validator_hotkey, params.runtime)
@@ -5,41 +5,43 @@ use axum::{
 };
 use uuid::Uuid;
 
+use platform_api::error::ApiResult;
 use platform_api::state::AppState;
 use platform_api_models::SubmitResultRequest;
 
 use crate::jobs::types::FailJobRequest;
 
 /// Complete job with results
+///
+/// Results breaking the score policy of the job's challenge are refused with
+/// 422 and a field error per violation, results for a version of the job
+/// other than the current one with 409.
 pub async fn complete_job(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
     Json(request): Json<SubmitResultRequest>,
-) -> Result<StatusCode, StatusCode> {
-    state
-        .scheduler
-        .complete_job(id, request)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+) -> ApiResult<StatusCode> {
+    state.scheduler.complete_job(id, request).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 /// Fail job
+///
+/// A failure for a version of the job other than the current one, e.g. from
+/// before an operator reset it, is refused with 409.
 pub async fn fail_job(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
     Json(request): Json<FailJobRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> ApiResult<StatusCode> {
     let fail_request = platform_api_models::FailJobRequest {
         reason: request.reason.clone(),
         error_details: request.error_details.clone(),
+        failure_category: request.failure_category.clone(),
+        expected_version: request.expected_version,
     };
-    state
-        .scheduler
-        .fail_job(id, fail_request)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    state.scheduler.fail_job(id, fail_request).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -49,14 +51,10 @@ pub async fn submit_results(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
     Json(request): Json<SubmitResultRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> ApiResult<StatusCode> {
     // Complete job in scheduler
     let eval_result = request.result.clone();
-    state
-        .scheduler
-        .complete_job(id, request)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    state.scheduler.complete_job(id, request).await?;
 
     // Also forward result to challenge if job was distributed
     let job_id_str = id.to_string();
@@ -1,3 +1,5 @@
+use chrono::{DateTime, Utc};
+use platform_api_models::{FailureCategory, Hotkey};
 use serde::Deserialize;
 use serde_json::Value as JsonValue;
 use uuid::Uuid;
@@ -9,13 +11,19 @@ pub struct ListJobsParams {
     pub per_page: Option<u32>,
     pub status: Option<String>,
     pub challenge_id: Option<Uuid>,
+    /// Text matched against the top-level keys and values of the job payload
+    pub search: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
 }
 
 /// Query parameters for getting next job
 #[derive(Debug, Deserialize)]
 pub struct GetNextJobParams {
-    pub validator_hotkey: String,
+    pub validator_hotkey: Hotkey,
     pub runtime: Option<String>,
+    /// Number of jobs the validator can run at once; it is not handed more
+    pub capacity: Option<u32>,
 }
 
 /// Request to fail a job
@@ -23,6 +31,12 @@ pub struct GetNextJobParams {
 pub struct FailJobRequest {
     pub reason: String,
     pub error_details: Option<String>,
+    #[serde(default)]
+    pub failure_category: Option<FailureCategory>,
+    /// Version of the job the failure is for, see
+    /// `SubmitResultRequest::expected_version`
+    #[serde(default)]
+    pub expected_version: Option<u64>,
 }
 
 /// Query parameters for pending jobs
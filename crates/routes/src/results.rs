@@ -43,6 +43,10 @@ pub struct ResultSubmitRequest {
     pub metrics: std::collections::BTreeMap<String, f64>,
     pub logs: Vec<String>,
     pub error: Option<String>,
+    /// Version of the job the result is for, see
+    /// `SubmitResultRequest::expected_version`
+    #[serde(default)]
+    pub expected_version: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -131,12 +135,14 @@ pub async fn submit_result(
             network_bytes: metrics_clone.get("network_bytes").map(|v| *v as u64).unwrap_or(0),
         },
         attestation_receipt: None,
+        schema_version: platform_api_models::EVAL_RESULT_SCHEMA_VERSION,
     };
 
     let submit_request = platform_api_models::SubmitResultRequest {
         job_id,
         result: eval_result,
         receipts: vec![format!("result:{}:{}", req.session_token, Utc::now())],
+        expected_version: req.expected_version,
     };
 
     // Complete the job via scheduler
@@ -149,6 +155,10 @@ pub async fn submit_result(
                 receipt,
             }))
         }
+        Err(e @ platform_api_models::PlatformError::Conflict { .. }) => {
+            tracing::warn!("Refused result for job {}: {}", job_id, e);
+            Err(StatusCode::CONFLICT)
+        }
         Err(e) => {
             tracing::error!("Failed to complete job {}: {}", job_id, e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -9,8 +9,10 @@ use tracing::{info, warn};
 use platform_api::services::DstackVerifierClient;
 use platform_api::state::AppState;
 use dstack_types::VmConfig;
+use platform_api_attestation::with_verification_timeout;
 use platform_api_models::{AttestationRequest, AttestationType};
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::messages::{AttestationMessage, SecureMessage};
 use super::utils::extract_compose_hash_from_event_log;
@@ -46,12 +48,7 @@ pub async fn verify_secure_message(
     let public_key = sr25519::Public::from_ss58check(&msg.public_key)
         .map_err(|e| anyhow::anyhow!("Invalid public key: {}", e))?;
 
-    // Recreate message to verify
-    let mut message = Vec::new();
-    message.extend_from_slice(msg.message_type.as_bytes());
-    message.extend_from_slice(msg.timestamp.to_string().as_bytes());
-    message.extend_from_slice(msg.nonce.as_bytes());
-    message.extend_from_slice(msg.data.to_string().as_bytes());
+    let message = msg.signing_bytes();
 
     // Decode signature
     let signature_bytes =
@@ -123,11 +120,14 @@ pub async fn verify_validator_attestation(
 
     // Verify attestation with event log
     let event_log = msg.event_log.as_deref();
-    let result = state
-        .attestation
-        .verify_attestation_with_event_log(attest_request, event_log)
-        .await
-        .context("Failed to verify attestation")?;
+    let result = with_verification_timeout(
+        verification_timeout(state),
+        state
+            .attestation
+            .verify_attestation_with_event_log(attest_request, event_log),
+    )
+    .await
+    .context("Failed to verify attestation")?;
 
     if !matches!(
         result.status,
@@ -158,6 +158,11 @@ pub async fn verify_validator_attestation(
     Ok(())
 }
 
+/// Bound on each attestation verification, see `TdxConfig::verification_timeout`
+fn verification_timeout(state: &AppState) -> Duration {
+    Duration::from_secs(state.config.attestation_config.verification_timeout)
+}
+
 /// Verify challenge binding in report data
 pub fn verify_challenge_binding(
     report_data_hex: &str,
@@ -342,10 +347,12 @@ async fn verify_validator_with_dstack_verifier(
 
         info!("Calling dstack-verifier for full TDX verification");
         
-        let verification_result = verifier
-            .verify(verification_request)
-            .await
-            .context("Failed to verify TDX quote with dstack-verifier")?;
+        let verification_result = with_verification_timeout(
+            verification_timeout(state),
+            verifier.verify(verification_request),
+        )
+        .await
+        .context("Failed to verify TDX quote with dstack-verifier")?;
 
         if !verification_result.is_valid {
             return Err(anyhow::anyhow!(
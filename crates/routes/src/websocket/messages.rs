@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Shared with the API, so that both verify signatures over the same bytes
+pub use platform_api::routes::websocket::SecureMessage;
+
 #[derive(Debug, Deserialize)]
 pub struct HandshakeMessage {
     #[serde(rename = "type")]
@@ -19,16 +22,6 @@ pub struct AttestationMessage {
     pub vm_config: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct SecureMessage {
-    pub message_type: String,
-    pub data: serde_json::Value,
-    pub timestamp: u64,
-    pub nonce: String,
-    pub signature: String,
-    pub public_key: String,
-}
-
 #[derive(Debug, Clone, Serialize)]
 pub struct ValidatorNotification {
     pub msg_type: String,
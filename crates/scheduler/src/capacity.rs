@@ -1,3 +1,5 @@
+use crate::{rows::ValidatorRegistrationRow, service::SchedulerService, types::CapacityExhausted};
+use anyhow::Context;
 use platform_api_models::*;
 use uuid::Uuid;
 
@@ -170,6 +172,89 @@ impl CapacityMatcher {
     }
 }
 
+impl SchedulerService {
+    /// Record the number of jobs `validator_hotkey` can run at once
+    pub async fn report_validator_capacity(&self, validator_hotkey: &str, capacity: u32) {
+        self.validator_capacity
+            .write()
+            .await
+            .insert(validator_hotkey.to_string(), capacity);
+    }
+
+    /// Capacity `validator_hotkey` last reported, if any
+    pub async fn validator_capacity(&self, validator_hotkey: &str) -> Option<u32> {
+        self.validator_capacity
+            .read()
+            .await
+            .get(validator_hotkey)
+            .copied()
+    }
+
+    /// Record the resources validator `registration.hotkey` registered with
+    pub async fn report_validator_registration(&self, registration: ValidatorRegistration) {
+        self.validator_registrations
+            .write()
+            .await
+            .insert(registration.hotkey.to_string(), registration);
+    }
+
+    /// Resources `validator_hotkey` registered with, if it did
+    ///
+    /// With a database they are read from the `validators` table, which every
+    /// API instance writes registrations to, so that a validator registering
+    /// again through another instance is seen right away. Without one, the
+    /// registrations reported since startup are used.
+    pub async fn validator_registration(
+        &self,
+        validator_hotkey: &Hotkey,
+    ) -> PlatformResult<Option<ValidatorRegistration>> {
+        let Some(pool) = &self.database_pool else {
+            return Ok(self
+                .validator_registrations
+                .read()
+                .await
+                .get(validator_hotkey.as_str())
+                .cloned());
+        };
+
+        let row = sqlx::query_as::<_, ValidatorRegistrationRow>(
+            r#"
+            SELECT runtimes, gpu, memory_gb, cpu_cores, preferred_challenges,
+                   gpu_count, gpu_type, disk_gb
+            FROM validators
+            WHERE hotkey = $1
+            "#,
+        )
+        .bind(validator_hotkey.as_str())
+        .fetch_optional(pool.as_ref())
+        .await
+        .context("Failed to read validator registration")?;
+        Ok(row.map(|row| row.into_registration(validator_hotkey.clone())))
+    }
+}
+
+/// Fail if `validator_hotkey` already has `capacity` jobs in flight
+pub(crate) fn check_capacity(
+    validator_hotkey: &str,
+    capacity: u32,
+    in_flight: u64,
+) -> PlatformResult<()> {
+    if in_flight >= capacity as u64 {
+        tracing::debug!(
+            validator_hotkey,
+            capacity,
+            in_flight,
+            "Validator has no free capacity"
+        );
+        return Err(CapacityExhausted {
+            validator_hotkey: validator_hotkey.to_string(),
+            capacity,
+        }
+        .into());
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,4 +301,131 @@ mod tests {
         assert!(score.is_some());
         assert!(score.unwrap() > 0.0);
     }
+
+    #[tokio::test]
+    async fn test_concurrent_claims_respect_reported_capacity() {
+        use crate::types::{CreateJobRequest, SchedulerConfig};
+        use std::sync::Arc;
+
+        let scheduler = Arc::new(SchedulerService::new(&SchedulerConfig::default()).unwrap());
+        for _ in 0..5 {
+            scheduler
+                .create_job(CreateJobRequest {
+                    challenge_id: Uuid::new_v4(),
+                    payload: serde_json::json!({}),
+                    priority: None,
+                    runtime: Some(RuntimeType::Docker),
+                    timeout: None,
+                    max_retries: None,
+                    job_id: None,
+                    depends_on: vec![],
+                    deadline: None,
+                    required_capabilities: None,
+                    resources: None,
+                })
+                .await
+                .unwrap();
+        }
+        let validator = Hotkey::from_public_key(&[1; 32]);
+        scheduler.report_validator_capacity(&validator, 2).await;
+
+        let claims: Vec<_> = (0..8)
+            .map(|_| {
+                let (scheduler, validator) = (scheduler.clone(), validator.clone());
+                tokio::spawn(async move { scheduler.get_next_job(validator, None).await })
+            })
+            .collect();
+        let mut claimed = vec![];
+        for claim in claims {
+            claimed.extend(claim.await.unwrap().unwrap());
+        }
+        assert_eq!(claimed.len(), 2);
+
+        // Another validator is not limited by the first one's capacity
+        assert!(scheduler
+            .get_next_job(Hotkey::from_public_key(&[2; 32]), None)
+            .await
+            .unwrap()
+            .is_some());
+
+        // Failing a job frees its slot
+        scheduler
+            .fail_job(
+                claimed[0].job.id,
+                FailJobRequest {
+                    reason: "crashed".to_string(),
+                    error_details: None,
+                    failure_category: None,
+                    expected_version: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert!(scheduler
+            .get_next_job(validator.clone(), None)
+            .await
+            .unwrap()
+            .is_some());
+        let err = scheduler
+            .claim_job(ClaimJobRequest {
+                validator_hotkey: validator,
+                runtime: RuntimeType::Docker,
+                capabilities: vec![],
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PlatformError::RateLimited { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_registration_read_from_database() {
+        use crate::types::SchedulerConfig;
+        use std::sync::Arc;
+
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = Arc::new(sqlx::PgPool::connect(&database_url).await.unwrap());
+        let scheduler =
+            SchedulerService::with_database(&SchedulerConfig::default(), pool.clone()).unwrap();
+        let mut public_key = [3; 32];
+        public_key[..16].copy_from_slice(Uuid::new_v4().as_bytes());
+        let validator = Hotkey::from_public_key(&public_key);
+        let register = |memory_gb: i64| {
+            sqlx::query(
+                r#"
+                INSERT INTO validators (hotkey, runtimes, memory_gb, cpu_cores)
+                VALUES ($1, ARRAY['docker'], $2, 8)
+                ON CONFLICT (hotkey) DO UPDATE SET memory_gb = EXCLUDED.memory_gb
+                "#,
+            )
+            .bind(validator.as_str())
+            .bind(memory_gb)
+            .execute(pool.as_ref())
+        };
+
+        // Registered through this instance
+        register(32).await.unwrap();
+        scheduler
+            .report_validator_registration(ValidatorRegistration {
+                hotkey: validator.clone(),
+                runtimes: vec![RuntimeType::Docker],
+                gpu: false,
+                memory_gb: 32,
+                cpu_cores: 8,
+                preferred_challenges: vec![],
+                gpu_count: 0,
+                gpu_type: None,
+                disk_gb: None,
+            })
+            .await;
+        // then again through another one
+        register(64).await.unwrap();
+
+        let registration = scheduler
+            .validator_registration(&validator)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(registration.memory_gb, 64);
+    }
 }
@@ -0,0 +1,247 @@
+//! Operator interventions on jobs: reprioritizing, resetting and clearing retries
+//!
+//! The store records each change in `job_events` with the operator as actor,
+//! see [`JobChange::event_details`]. Claims read the jobs as they are stored,
+//! so a change applies from the next claim.
+
+use crate::{service::SchedulerService, types::JobAdminError};
+use anyhow::Result;
+use platform_api_models::*;
+use tracing::info;
+use uuid::Uuid;
+
+/// Statuses a job can be reset to pending from
+const RESETTABLE: [JobStatus; 5] = [
+    JobStatus::Claimed,
+    JobStatus::Running,
+    JobStatus::Failed,
+    JobStatus::Timeout,
+    JobStatus::DeadLettered,
+];
+
+/// An operator's change to a job, see `JobStore::change_job`
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobChange {
+    /// New priority of a job that has not completed
+    Priority(JobPriority),
+    /// Back to pending, or blocked until its dependencies complete, released
+    /// from its validator, from one of the `RESETTABLE` statuses
+    Reset,
+    /// Retry count of a job that has not completed back to zero
+    ClearRetries,
+}
+
+impl JobChange {
+    /// Fail with `JobAdminError::InvalidStatus` unless the change applies to a
+    /// job in `status`
+    pub fn check(&self, job_id: Uuid, status: &JobStatus) -> Result<(), JobAdminError> {
+        let (allowed, action) = match self {
+            JobChange::Priority(_) => (*status != JobStatus::Completed, "reprioritized"),
+            JobChange::Reset => (RESETTABLE.contains(status), "reset"),
+            JobChange::ClearRetries => (*status != JobStatus::Completed, "retried"),
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(JobAdminError::InvalidStatus {
+                job_id,
+                status: status.as_str(),
+                action,
+            })
+        }
+    }
+
+    /// Metadata of the event of the change to a job that was `before`
+    pub fn event_details(&self, before: &JobMetadata) -> serde_json::Value {
+        match self {
+            JobChange::Priority(priority) => serde_json::json!({
+                "action": "set_priority",
+                "old_priority": before.priority.as_str(),
+                "priority": priority.as_str(),
+            }),
+            JobChange::Reset => serde_json::json!({ "action": "reset" }),
+            JobChange::ClearRetries => serde_json::json!({
+                "action": "clear_retries",
+                "old_retry_count": before.retry_count,
+            }),
+        }
+    }
+}
+
+impl SchedulerService {
+    /// Set the priority of a job that has not completed, on behalf of `actor`
+    #[tracing::instrument(name = "scheduler.set_job_priority", skip_all, fields(job_id = %job_id))]
+    pub async fn set_job_priority(
+        &self,
+        job_id: Uuid,
+        priority: JobPriority,
+        actor: &str,
+    ) -> PlatformResult<JobMetadata> {
+        let before = self
+            .store
+            .change_job(job_id, &JobChange::Priority(priority.clone()), actor)
+            .await?;
+
+        info!(
+            job_id = %job_id,
+            actor = actor,
+            old_priority = before.priority.as_str(),
+            priority = priority.as_str(),
+            "Reprioritized job"
+        );
+        self.get_job(job_id).await
+    }
+
+    /// Reset a claimed, running, failed, timed out or dead-lettered job to
+    /// pending, on behalf of `actor`
+    ///
+    /// The job is released from its validator and claimed again like a new
+    /// one, once its dependencies have completed; its retry count is kept.
+    #[tracing::instrument(name = "scheduler.reset_job", skip_all, fields(job_id = %job_id))]
+    pub async fn reset_job(&self, job_id: Uuid, actor: &str) -> PlatformResult<JobMetadata> {
+        let status = self
+            .store
+            .change_job(job_id, &JobChange::Reset, actor)
+            .await?
+            .status;
+
+        let job = self.get_job(job_id).await?;
+
+        info!(
+            job_id = %job_id,
+            actor = actor,
+            old_status = status.as_str(),
+            status = job.status.as_str(),
+            "Reset job"
+        );
+        Ok(job)
+    }
+
+    /// Set the retry count of a job that has not completed back to zero, on
+    /// behalf of `actor`
+    ///
+    /// The job gets its full `max_retries` again; a dead-lettered job stays
+    /// dead-lettered until requeued or reset.
+    #[tracing::instrument(name = "scheduler.clear_job_retries", skip_all, fields(job_id = %job_id))]
+    pub async fn clear_job_retries(&self, job_id: Uuid, actor: &str) -> PlatformResult<JobMetadata> {
+        let before = self
+            .store
+            .change_job(job_id, &JobChange::ClearRetries, actor)
+            .await?;
+        let old_retry_count = before.retry_count;
+
+        info!(job_id = %job_id, actor = actor, old_retry_count, "Cleared job retry count");
+        self.get_job(job_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{service::SchedulerService, types::*};
+    use platform_api_models::*;
+    use uuid::Uuid;
+
+    fn create_request(priority: JobPriority) -> CreateJobRequest {
+        CreateJobRequest {
+            challenge_id: Uuid::new_v4(),
+            payload: serde_json::json!({}),
+            priority: Some(priority),
+            runtime: Some(RuntimeType::Docker),
+            timeout: None,
+            max_retries: Some(1),
+            job_id: None,
+            depends_on: vec![],
+            deadline: None,
+            required_capabilities: None,
+            resources: None,
+        }
+    }
+
+    fn claim_request() -> ClaimJobRequest {
+        ClaimJobRequest {
+            validator_hotkey: Hotkey::from_public_key(&[1; 32]),
+            runtime: RuntimeType::Docker,
+            capabilities: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reprioritized_job_is_claimed_first() {
+        let scheduler = SchedulerService::new(&SchedulerConfig::default()).unwrap();
+        let high = scheduler
+            .create_job(create_request(JobPriority::High))
+            .await
+            .unwrap();
+        let low = scheduler
+            .create_job(create_request(JobPriority::Low))
+            .await
+            .unwrap();
+
+        let bumped = scheduler
+            .set_job_priority(low.id, JobPriority::Critical, "admin")
+            .await
+            .unwrap();
+        assert_eq!(bumped.priority, JobPriority::Critical);
+
+        let claimed = scheduler.claim_job(claim_request()).await.unwrap();
+        assert_eq!(claimed.job.id, low.id);
+        assert_ne!(claimed.job.id, high.id);
+    }
+
+    #[tokio::test]
+    async fn test_reset_and_clear_retries() {
+        let scheduler = SchedulerService::new(&SchedulerConfig {
+            retry_delay: 0,
+            ..SchedulerConfig::default()
+        })
+        .unwrap();
+        let job = scheduler
+            .create_job(create_request(JobPriority::Normal))
+            .await
+            .unwrap();
+
+        // A pending job has nothing to reset
+        let err = scheduler.reset_job(job.id, "admin").await.unwrap_err();
+        assert!(matches!(err, PlatformError::Conflict { .. }));
+
+        scheduler.claim_job(claim_request()).await.unwrap();
+        let reset = scheduler.reset_job(job.id, "admin").await.unwrap();
+        assert_eq!(reset.status, JobStatus::Pending);
+        assert_eq!(reset.validator_hotkey, None);
+        assert_eq!(reset.timeout_at, None);
+
+        // The timeout starts over when the job is claimed again
+        let reclaimed = scheduler.claim_job(claim_request()).await.unwrap();
+        let claimed_at = reclaimed.job.claimed_at.unwrap();
+        assert_eq!(
+            reclaimed.job.timeout_at,
+            Some(claimed_at + chrono::Duration::seconds(reclaimed.config.timeout as i64))
+        );
+        scheduler.reset_job(job.id, "admin").await.unwrap();
+
+        scheduler
+            .fail_job(
+                job.id,
+                FailJobRequest {
+                    reason: "exit code 1".to_string(),
+                    error_details: None,
+                    failure_category: None,
+                    expected_version: None,
+                },
+            )
+            .await
+            .unwrap();
+        scheduler.retry_failed_jobs().await.unwrap();
+        assert_eq!(scheduler.get_job(job.id).await.unwrap().retry_count, 1);
+
+        let cleared = scheduler.clear_job_retries(job.id, "admin").await.unwrap();
+        assert_eq!(cleared.retry_count, 0);
+        assert_eq!(cleared.status, JobStatus::Pending);
+
+        let err = scheduler
+            .clear_job_retries(Uuid::new_v4(), "admin")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PlatformError::NotFound { .. }));
+    }
+}
@@ -1,7 +1,6 @@
 //! Job claim operations
 
-use crate::{rows::JobRow, service::SchedulerService};
-use anyhow::Result;
+use crate::{scorer::SCORED_CANDIDATES, service::SchedulerService, store::JobClaim};
 use chrono::Utc;
 use platform_api_models::*;
 use std::collections::BTreeMap;
@@ -10,175 +9,113 @@ use uuid::Uuid;
 
 impl SchedulerService {
     /// Claim the next available pending job
-    pub async fn claim_job(&self, request: ClaimJobRequest) -> Result<ClaimJobResponse> {
-        if let Some(pool) = &self.database_pool {
-            let now = Utc::now();
-
-            // Try to claim a pending job (atomic update)
-            let row = sqlx::query_as::<_, JobRow>(
-                r#"
-                UPDATE jobs 
-                SET status = 'claimed',
-                    validator_hotkey = $1,
-                    claimed_at = $2
-                WHERE id = (
-                    SELECT id FROM jobs 
-                    WHERE status = 'pending' 
-                    ORDER BY created_at ASC 
-                    LIMIT 1
-                    FOR UPDATE SKIP LOCKED
-                )
-                RETURNING id, challenge_id, validator_hotkey, status, priority, runtime,
-                          created_at, claimed_at, started_at, completed_at, timeout_at,
-                          retry_count, max_retries, payload
-                "#,
-            )
-            .bind(request.validator_hotkey.to_string())
-            .bind(now)
-            .fetch_optional(pool.as_ref())
+    ///
+    /// Jobs requiring capabilities the request does not list, see
+    /// `JobMetadata::required_capabilities`, or more resources than the
+    /// validator registered, see `ValidatorInfo::has_resources_for`, are left
+    /// to other validators.
+    #[tracing::instrument(
+        name = "scheduler.claim_job",
+        skip_all,
+        fields(validator_hotkey = %request.validator_hotkey),
+    )]
+    pub async fn claim_job(&self, request: ClaimJobRequest) -> PlatformResult<ClaimJobResponse> {
+        let config = self.config().await;
+        let now = Utc::now();
+
+        // Custom scorers may rank jobs differently from the database ordering,
+        // so they are given several candidates to choose from
+        let custom_scorers = !config.challenge_scorers.is_empty();
+        let validator = self
+            .validator_info(&request.validator_hotkey, custom_scorers)
             .await?;
 
-            if let Some(r) = row {
-                let job: JobMetadata = r.into();
-
-                info!(job_id = %job.id, validator_hotkey = %request.validator_hotkey, "Claimed job");
-
-                Ok(ClaimJobResponse {
-                    job,
-                    config: JobConfig {
-                        timeout: self.config.job_timeout,
-                        resources: ResourceLimits {
-                            cpu_cores: 1,
-                            memory_mb: 1024,
-                            disk_mb: 10240,
-                            network_enabled: true,
-                        },
-                        environment: BTreeMap::new(),
-                        attestation_required: false,
-                        policy: None,
-                    },
-                })
-            } else {
-                Err(anyhow::anyhow!("No pending jobs available"))
-            }
-        } else {
-            // Fallback to in-memory
-            let mut jobs = self.jobs.write().await;
-            let job = jobs
-                .values_mut()
-                .find(|j| j.status == JobStatus::Pending)
-                .ok_or_else(|| anyhow::anyhow!("No pending jobs available"))?;
-
-            job.status = JobStatus::Claimed;
-            job.validator_hotkey = Some(request.validator_hotkey.clone());
-            job.claimed_at = Some(Utc::now());
-
-            Ok(ClaimJobResponse {
-                job: job.clone(),
-                config: JobConfig {
-                    timeout: self.config.job_timeout,
-                    resources: ResourceLimits {
-                        cpu_cores: 1,
-                        memory_mb: 1024,
-                        disk_mb: 10240,
-                        network_enabled: true,
-                    },
-                    environment: BTreeMap::new(),
-                    attestation_required: false,
-                    policy: None,
-                },
+        let select =
+            |candidates: &[&JobMetadata]| self.best_candidate(&config, candidates, &validator, now);
+        let job = self
+            .store
+            .claim_job(JobClaim {
+                config: &config,
+                validator: &validator,
+                candidate_limit: if custom_scorers { SCORED_CANDIDATES } else { 1 },
+                capabilities: &request.capabilities,
+                now,
+                select: &select,
             })
-        }
+            .await?
+            .ok_or_else(|| {
+                PlatformError::not_found("pending job for validator", &request.validator_hotkey)
+            })?;
+
+        info!(job_id = %job.id, validator_hotkey = %request.validator_hotkey, "Claimed job");
+
+        let timeout = config.job_timeout(&job);
+        Ok(claim_response(job, timeout))
     }
 
     /// Claim a specific job by ID
+    ///
+    /// Fails with a conflict if the validator lacks the capabilities or the
+    /// resources the job requires, as `claim_job` would skip it.
+    #[tracing::instrument(
+        name = "scheduler.claim_specific_job",
+        skip_all,
+        fields(job_id = %job_id, validator_hotkey = %request.validator_hotkey)
+    )]
     pub async fn claim_specific_job(
         &self,
         job_id: Uuid,
         request: ClaimJobRequest,
-    ) -> Result<ClaimJobResponse> {
-        if let Some(pool) = &self.database_pool {
-            let now = Utc::now();
-
-            let row = sqlx::query_as::<_, JobRow>(
-                r#"
-                UPDATE jobs 
-                SET status = 'claimed',
-                    validator_hotkey = $1,
-                    claimed_at = $2
-                WHERE id = $3 AND status = 'pending'
-                RETURNING id, challenge_id, validator_hotkey, status, priority, runtime,
-                          created_at, claimed_at, started_at, completed_at, timeout_at,
-                          retry_count, max_retries, payload
-                "#,
+    ) -> PlatformResult<ClaimJobResponse> {
+        let job = self
+            .store
+            .get_job(job_id)
+            .await?
+            .ok_or_else(|| PlatformError::not_found("job", job_id))?;
+        if !job.runs_with(&request.capabilities) {
+            return Err(PlatformError::conflict(format!(
+                "Job {} requires capabilities {:?}",
+                job_id, job.required_capabilities
+            )));
+        }
+        let registration = self
+            .validator_registration(&request.validator_hotkey)
+            .await?;
+        if registration.is_some_and(|registration| !job.resources.satisfied_by(&registration)) {
+            return Err(PlatformError::conflict(format!(
+                "Job {} requires more resources than the validator registered",
+                job_id
+            )));
+        }
+        self.check_in_flight_quota(job.challenge_id).await?;
+
+        let timeout = self.config().await.job_timeout(&job);
+        let capacity = self.validator_capacity(&request.validator_hotkey).await;
+        let job = self
+            .store
+            .claim_specific_job(
+                job_id,
+                job.version,
+                &request.validator_hotkey,
+                capacity,
+                timeout,
+                Utc::now(),
             )
-            .bind(request.validator_hotkey.to_string())
-            .bind(now)
-            .bind(job_id)
-            .fetch_optional(pool.as_ref())
             .await?;
 
-            let r = row.ok_or_else(|| anyhow::anyhow!("Job not available or already claimed"))?;
-            let job: JobMetadata = r.into();
-
-            info!(job_id = %job.id, validator_hotkey = %request.validator_hotkey, "Claimed specific job");
-
-            Ok(ClaimJobResponse {
-                job,
-                config: JobConfig {
-                    timeout: self.config.job_timeout,
-                    resources: ResourceLimits {
-                        cpu_cores: 1,
-                        memory_mb: 1024,
-                        disk_mb: 10240,
-                        network_enabled: true,
-                    },
-                    environment: BTreeMap::new(),
-                    attestation_required: false,
-                    policy: None,
-                },
-            })
-        } else {
-            // Fallback to in-memory
-            let mut jobs = self.jobs.write().await;
-            let job = jobs
-                .get_mut(&job_id)
-                .ok_or_else(|| anyhow::anyhow!("Job not found"))?;
-
-            if job.status != JobStatus::Pending {
-                return Err(anyhow::anyhow!("Job not available or already claimed"));
-            }
-
-            job.status = JobStatus::Claimed;
-            job.validator_hotkey = Some(request.validator_hotkey.clone());
-            job.claimed_at = Some(Utc::now());
-
-            Ok(ClaimJobResponse {
-                job: job.clone(),
-                config: JobConfig {
-                    timeout: self.config.job_timeout,
-                    resources: ResourceLimits {
-                        cpu_cores: 1,
-                        memory_mb: 1024,
-                        disk_mb: 10240,
-                        network_enabled: true,
-                    },
-                    environment: BTreeMap::new(),
-                    attestation_required: false,
-                    policy: None,
-                },
-            })
-        }
+        info!(job_id = %job.id, validator_hotkey = %request.validator_hotkey, "Claimed specific job");
+
+        Ok(claim_response(job, timeout))
     }
 
     /// Get next available job for validator (uses claim_job internally)
     pub async fn get_next_job(
         &self,
-        validator_hotkey: String,
+        validator_hotkey: Hotkey,
         runtime: Option<String>,
-    ) -> Result<Option<ClaimJobResponse>> {
+    ) -> PlatformResult<Option<ClaimJobResponse>> {
         let request = ClaimJobRequest {
-            validator_hotkey: Hotkey::from(validator_hotkey),
+            validator_hotkey,
             runtime: runtime
                 .map(|r| RuntimeType::from(r.as_str()))
                 .unwrap_or(RuntimeType::Docker),
@@ -192,3 +129,56 @@ impl SchedulerService {
     }
 }
 
+/// Claimed job with the configuration it runs with
+///
+/// The resource limits are those the job requires, or 1 vCPU, 1 GB of memory
+/// and 10 GB of disk. Amounts too large to express in MB are capped.
+fn claim_response(job: JobMetadata, timeout: u64) -> ClaimJobResponse {
+    let resources = ResourceLimits {
+        cpu_cores: job.resources.vcpu.unwrap_or(1),
+        memory_mb: job.resources.memory_gb.unwrap_or(1).saturating_mul(1024),
+        disk_mb: job.resources.disk_gb.unwrap_or(10).saturating_mul(1024),
+        network_enabled: true,
+    };
+    ClaimJobResponse {
+        job,
+        config: JobConfig {
+            timeout,
+            resources,
+            environment: BTreeMap::new(),
+            attestation_required: false,
+            policy: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_response_caps_resources() {
+        let job: JobMetadata = serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4(),
+            "challenge_id": Uuid::new_v4(),
+            "validator_hotkey": null,
+            "status": "Claimed",
+            "priority": "Normal",
+            "runtime": "Docker",
+            "created_at": Utc::now(),
+            "claimed_at": null,
+            "started_at": null,
+            "completed_at": null,
+            "timeout_at": null,
+            "retry_count": 0,
+            "max_retries": 3,
+            "resources": { "memory_gb": u64::MAX, "disk_gb": u64::MAX / 2 },
+        }))
+        .unwrap();
+
+        let response = claim_response(job, 60);
+        assert_eq!(response.config.resources.memory_mb, u64::MAX);
+        assert_eq!(response.config.resources.disk_mb, u64::MAX);
+        assert_eq!(response.config.resources.cpu_cores, 1);
+    }
+}
@@ -1,98 +1,702 @@
 //! Job creation operations
 
-use crate::{rows::JobRow, service::SchedulerService, types::CreateJobRequest};
+use crate::{
+    payload_schema::validate_payload,
+    service::SchedulerService,
+    types::{check_payload_size, BatchCreateJobsResponse, BatchJobResult, CreateJobRequest},
+};
 use anyhow::Result;
 use chrono::Utc;
 use platform_api_models::*;
+use std::collections::{HashMap, HashSet};
 use tracing::{info, warn};
 use uuid::Uuid;
 
 impl SchedulerService {
     /// Create a new job
-    pub async fn create_job(&self, request: CreateJobRequest) -> Result<JobMetadata> {
-        let job_id = Uuid::new_v4();
-        let now = Utc::now();
-
-        // Convert challenge_id to Uuid if it's a string
-        let challenge_uuid = match request.challenge_id.to_string().parse::<Uuid>() {
-            Ok(uuid) => uuid,
-            Err(_) => {
-                warn!(
-                    "challenge_id '{}' is not a valid UUID, using default",
-                    request.challenge_id.to_string()
-                );
-                Uuid::new_v4()
+    ///
+    /// Fails with `PlatformError::PayloadTooLarge` if the payload exceeds
+    /// `max_job_payload_bytes`.
+    #[tracing::instrument(
+        name = "scheduler.create_job",
+        skip_all,
+        fields(challenge_id = ?request.challenge_id),
+    )]
+    pub async fn create_job(&self, mut request: CreateJobRequest) -> PlatformResult<JobMetadata> {
+        let config = self.config().await;
+        check_payload_size(&request.payload, config.max_job_payload_bytes)?;
+        self.apply_challenge_defaults(std::slice::from_mut(&mut request))
+            .await?;
+        request
+            .validate()
+            .map_err(|e| PlatformError::Validation { fields: vec![e] })?;
+        let timeout = config.job_timeout_of(&request)?;
+        let schemas = self.job_payload_schemas([&request]).await?;
+        if let Some(schema) = schemas.get(&request.challenge_id) {
+            validate_payload(schema, &request.payload)?;
+        }
+        let mut job = new_job(&request, timeout, config.retry_attempts);
+        if job.depends_on.contains(&job.id) {
+            return Err(PlatformError::invalid(
+                "depends_on",
+                "Job cannot depend on itself",
+            ));
+        }
+
+        let known = self.dependency_completion(&job.depends_on).await?;
+        if let Some(missing) = job.depends_on.iter().find(|id| !known.contains_key(id)) {
+            return Err(PlatformError::invalid(
+                "depends_on",
+                format!("Unknown dependency {}", missing),
+            ));
+        }
+        job.status = initial_status(&job.depends_on, &known);
+
+        self.store
+            .insert_jobs(&config, std::slice::from_ref(&job))
+            .await?;
+        info!(job_id = %job.id, challenge_id = %job.challenge_id, "Created job");
+
+        Ok(job)
+    }
+
+    /// Create several jobs atomically
+    ///
+    /// Every request is validated first; if any is invalid, no job is created
+    /// and the response carries the per-item errors. Otherwise all jobs are
+    /// inserted in a single transaction.
+    #[tracing::instrument(
+        name = "scheduler.create_jobs_batch",
+        skip_all,
+        fields(batch_size = requests.len()),
+    )]
+    pub async fn create_jobs_batch(
+        &self,
+        mut requests: Vec<CreateJobRequest>,
+    ) -> PlatformResult<BatchCreateJobsResponse> {
+        let config = self.config().await;
+        let max_batch_size = config.max_batch_size;
+        if requests.is_empty() {
+            return Err(PlatformError::invalid(
+                "jobs",
+                "Batch must contain at least one job",
+            ));
+        }
+        if requests.len() > max_batch_size {
+            return Err(PlatformError::invalid(
+                "jobs",
+                format!(
+                    "Batch of {} jobs exceeds the maximum of {}",
+                    requests.len(),
+                    max_batch_size
+                ),
+            ));
+        }
+
+        self.apply_challenge_defaults(&mut requests).await?;
+        let schemas = self.job_payload_schemas(&requests).await?;
+        let mut errors: Vec<BatchJobResult> = requests
+            .iter()
+            .enumerate()
+            .filter_map(|(index, request)| {
+                let valid = request
+                    .validate()
+                    .map_err(|e| e.message)
+                    .and_then(|()| {
+                        check_payload_size(&request.payload, config.max_job_payload_bytes)
+                            .map_err(|e| e.to_string())
+                    })
+                    .and_then(|()| config.job_timeout_of(request).map_err(|e| e.to_string()))
+                    .and_then(|_| match schemas.get(&request.challenge_id) {
+                        Some(schema) => {
+                            validate_payload(schema, &request.payload).map_err(|e| e.to_string())
+                        }
+                        None => Ok(()),
+                    });
+                valid.err().map(|error| BatchJobResult {
+                    index,
+                    job_id: None,
+                    error: Some(error),
+                })
+            })
+            .collect();
+
+        let mut jobs: Vec<JobMetadata> = requests
+            .iter()
+            .map(|request| {
+                // Batches with a timeout above the maximum are rejected below
+                let timeout = config
+                    .job_timeout_of(request)
+                    .unwrap_or(config.max_job_timeout);
+                new_job(request, timeout, config.retry_attempts)
+            })
+            .collect();
+
+        if errors.is_empty() {
+            errors = self.check_batch_dependencies(&mut jobs).await?;
+        }
+
+        if !errors.is_empty() {
+            warn!(
+                invalid = errors.len(),
+                "Rejected job batch with invalid items"
+            );
+            return Ok(BatchCreateJobsResponse {
+                created: false,
+                results: errors,
+            });
+        }
+
+        // The quota check is all or nothing too: a batch that does not fit is rejected whole
+        self.store.insert_jobs(&config, &jobs).await?;
+        info!(count = jobs.len(), "Created job batch");
+
+        Ok(BatchCreateJobsResponse {
+            created: true,
+            results: jobs
+                .iter()
+                .enumerate()
+                .map(|(index, job)| BatchJobResult {
+                    index,
+                    job_id: Some(job.id),
+                    error: None,
+                })
+                .collect(),
+        })
+    }
+
+    /// Resolve the dependencies of a batch and set the initial status of its jobs
+    ///
+    /// Jobs may depend on existing jobs or on other jobs of the batch. Returns
+    /// the per-item errors for unknown dependencies, duplicate ids and cycles.
+    async fn check_batch_dependencies(
+        &self,
+        jobs: &mut [JobMetadata],
+    ) -> Result<Vec<BatchJobResult>> {
+        let mut errors = Vec::new();
+        let mut batch_ids = HashSet::new();
+        for (index, job) in jobs.iter().enumerate() {
+            if !batch_ids.insert(job.id) {
+                errors.push(BatchJobResult {
+                    index,
+                    job_id: Some(job.id),
+                    error: Some(format!("Duplicate job_id {}", job.id)),
+                });
             }
+        }
+        if !errors.is_empty() {
+            return Ok(errors);
+        }
+
+        let external: Vec<Id> = jobs
+            .iter()
+            .flat_map(|job| job.depends_on.iter().copied())
+            .filter(|id| !batch_ids.contains(id))
+            .collect();
+        let known = self.dependency_completion(&external).await?;
+
+        for (index, job) in jobs.iter().enumerate() {
+            let missing = job
+                .depends_on
+                .iter()
+                .find(|id| !batch_ids.contains(id) && !known.contains_key(id));
+            if let Some(missing) = missing {
+                errors.push(BatchJobResult {
+                    index,
+                    job_id: None,
+                    error: Some(format!("Unknown dependency {}", missing)),
+                });
+            }
+        }
+
+        for index in dependency_cycle(jobs) {
+            errors.push(BatchJobResult {
+                index,
+                job_id: None,
+                error: Some("Job is part of a dependency cycle".to_string()),
+            });
+        }
+
+        if errors.is_empty() {
+            for job in jobs.iter_mut() {
+                job.status = if job.depends_on.iter().any(|id| batch_ids.contains(id)) {
+                    JobStatus::Blocked
+                } else {
+                    initial_status(&job.depends_on, &known)
+                };
+            }
+        }
+        errors.sort_by_key(|result| result.index);
+
+        Ok(errors)
+    }
+
+    /// Fill the parameters the requests leave unset from their challenge's defaults
+    ///
+    /// See `ChallengeMetadata::default_job_priority`,
+    /// `ChallengeMetadata::job_defaults` and `ChallengeMetadata::resources`.
+    /// Challenges are only stored in the database; requests of unknown ones
+    /// are left as they are.
+    async fn apply_challenge_defaults(&self, requests: &mut [CreateJobRequest]) -> Result<()> {
+        let challenge_ids: Vec<Id> = requests
+            .iter()
+            .filter(|request| {
+                request.priority.is_none()
+                    || request.runtime.is_none()
+                    || request.timeout.is_none()
+                    || request.max_retries.is_none()
+                    || request.resources.is_none()
+            })
+            .map(|request| request.challenge_id)
+            .collect();
+        let Some(pool) = &self.database_pool else {
+            return Ok(());
         };
+        if challenge_ids.is_empty() {
+            return Ok(());
+        }
+
+        let rows = sqlx::query_as::<_, (Uuid, String, serde_json::Value, serde_json::Value)>(
+            r#"
+            SELECT id, default_job_priority, job_defaults, resource_requirements
+            FROM challenges
+            WHERE id = ANY($1)
+            "#,
+        )
+        .bind(&challenge_ids)
+        .fetch_all(pool.as_ref())
+        .await?;
+        let defaults: HashMap<Id, (JobPriority, JobDefaults, ResourceRequirements)> = rows
+            .into_iter()
+            .map(|(id, priority, defaults, resources)| {
+                let defaults = serde_json::from_value(defaults).unwrap_or_else(|e| {
+                    warn!(challenge_id = %id, "Ignoring invalid job defaults: {}", e);
+                    JobDefaults::default()
+                });
+                let resources = serde_json::from_value(resources).unwrap_or_else(|e| {
+                    warn!(challenge_id = %id, "Ignoring invalid resource requirements: {}", e);
+                    ResourceRequirements::default()
+                });
+                let priority = JobPriority::from(priority.as_str());
+                (id, (priority, defaults, resources))
+            })
+            .collect();
+
+        for request in requests.iter_mut() {
+            if let Some((priority, defaults, resources)) = defaults.get(&request.challenge_id) {
+                request.apply_defaults(priority, defaults, resources);
+            }
+        }
+        Ok(())
+    }
 
-        let challenge_uuid_for_db = challenge_uuid;
-
-        let job = JobMetadata {
-            id: Id::from(job_id),
-            challenge_id: Id::from(challenge_uuid),
-            validator_hotkey: None,
-            status: JobStatus::Pending,
-            priority: request.priority.unwrap_or(JobPriority::Normal),
-            runtime: request.runtime.clone(),
-            created_at: now,
-            claimed_at: None,
-            started_at: None,
-            completed_at: None,
-            timeout_at: request
-                .timeout
-                .map(|secs| now + chrono::Duration::seconds(secs as i64)),
-            retry_count: 0,
-            max_retries: request.max_retries.unwrap_or(3),
-            payload: Some(request.payload.clone()),
+    /// Job payload schema of the challenges of the given requests
+    ///
+    /// See `ChallengeMetadata::job_payload_schema`. Challenges without a
+    /// schema, and unknown ones, are absent from the map.
+    async fn job_payload_schemas<'a>(
+        &self,
+        requests: impl IntoIterator<Item = &'a CreateJobRequest>,
+    ) -> Result<HashMap<Id, serde_json::Value>> {
+        let Some(pool) = &self.database_pool else {
+            return Ok(HashMap::new());
         };
+        let challenge_ids: Vec<Id> = requests
+            .into_iter()
+            .map(|request| request.challenge_id)
+            .collect();
 
-        if let Some(pool) = &self.database_pool {
-            let status_str = match job.status {
-                JobStatus::Pending => "pending",
-                JobStatus::Claimed => "claimed",
-                JobStatus::Running => "running",
-                JobStatus::Completed => "completed",
-                JobStatus::Failed => "failed",
-                JobStatus::Timeout => "timeout",
-            };
-
-            let priority_str = match job.priority {
-                JobPriority::Low => "low",
-                JobPriority::Normal => "normal",
-                JobPriority::High => "high",
-                JobPriority::Critical => "critical",
-            };
-
-            sqlx::query(
-                r#"
-                INSERT INTO jobs (
-                    id, challenge_id, status, priority, runtime, payload,
-                    created_at, timeout_at, retry_count, max_retries
-                )
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-                "#,
-            )
-            .bind(job_id)
-            .bind(challenge_uuid_for_db)
-            .bind(status_str)
-            .bind(priority_str)
-            .bind(job.runtime.to_string())
-            .bind(serde_json::to_value(&request.payload)?)
-            .bind(job.created_at)
-            .bind(job.timeout_at)
-            .bind(job.retry_count as i32)
-            .bind(job.max_retries as i32)
-            .execute(pool.as_ref())
-            .await?;
+        let rows = sqlx::query_as::<_, (Uuid, serde_json::Value)>(
+            r#"
+            SELECT id, job_payload_schema FROM challenges
+            WHERE id = ANY($1) AND job_payload_schema IS NOT NULL
+            "#,
+        )
+        .bind(&challenge_ids)
+        .fetch_all(pool.as_ref())
+        .await?;
 
-            info!(job_id = %job_id, challenge_id = %job.challenge_id, "Created job in database");
-        } else {
-            let mut jobs = self.jobs.write().await;
-            jobs.insert(job_id, job.clone());
-            info!(job_id = %job_id, "Created job in memory");
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Whether each of the given jobs has completed; unknown ids are absent from the map
+    async fn dependency_completion(&self, ids: &[Id]) -> Result<HashMap<Id, bool>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
         }
 
-        Ok(job)
+        self.store.dependency_completion(ids).await
     }
 }
 
+/// Status a new job starts in: blocked until every dependency has completed
+fn initial_status(depends_on: &[Id], completed: &HashMap<Id, bool>) -> JobStatus {
+    if depends_on
+        .iter()
+        .all(|id| completed.get(id).copied().unwrap_or(false))
+    {
+        JobStatus::Pending
+    } else {
+        JobStatus::Blocked
+    }
+}
+
+/// Indexes of the jobs that lie on a dependency cycle within the batch
+///
+/// Jobs that can be ordered from either end of the dependency graph are
+/// peeled off; whatever remains sits on, or between, cycles.
+fn dependency_cycle(jobs: &[JobMetadata]) -> Vec<usize> {
+    let index_of: HashMap<Id, usize> = jobs
+        .iter()
+        .enumerate()
+        .map(|(index, job)| (job.id, index))
+        .collect();
+    // Edges within the batch, from a job to each of its dependencies
+    let edges: Vec<Vec<usize>> = jobs
+        .iter()
+        .map(|job| {
+            job.depends_on
+                .iter()
+                .filter_map(|id| index_of.get(id).copied())
+                .collect()
+        })
+        .collect();
+
+    let mut remaining = vec![true; jobs.len()];
+    loop {
+        let mut has_dependency = vec![false; jobs.len()];
+        let mut has_dependent = vec![false; jobs.len()];
+        for (from, targets) in edges.iter().enumerate() {
+            for &to in targets {
+                if remaining[from] && remaining[to] {
+                    has_dependency[from] = true;
+                    has_dependent[to] = true;
+                }
+            }
+        }
+
+        let mut peeled = false;
+        for index in 0..jobs.len() {
+            if remaining[index] && (!has_dependency[index] || !has_dependent[index]) {
+                remaining[index] = false;
+                peeled = true;
+            }
+        }
+        if !peeled {
+            break;
+        }
+    }
+
+    (0..jobs.len()).filter(|&index| remaining[index]).collect()
+}
+
+/// Build the metadata of a new pending job timing out `timeout` seconds after it is claimed
+///
+/// The request is expected to carry the defaults of its challenge already;
+/// without max retries, the job gets `retry_attempts`.
+fn new_job(request: &CreateJobRequest, timeout: u64, retry_attempts: u32) -> JobMetadata {
+    let job_id = request.job_id.unwrap_or_else(Uuid::new_v4);
+    let now = Utc::now();
+    let resources = request.resources.clone().unwrap_or_default();
+
+    // Convert challenge_id to Uuid if it's a string
+    let challenge_uuid = match request.challenge_id.to_string().parse::<Uuid>() {
+        Ok(uuid) => uuid,
+        Err(_) => {
+            warn!(
+                "challenge_id '{}' is not a valid UUID, using default",
+                request.challenge_id.to_string()
+            );
+            Uuid::new_v4()
+        }
+    };
+
+    JobMetadata {
+        id: Id::from(job_id),
+        challenge_id: Id::from(challenge_uuid),
+        validator_hotkey: None,
+        status: JobStatus::Pending,
+        priority: request.priority.clone().unwrap_or_default(),
+        runtime: request.runtime_or_default(),
+        created_at: now,
+        claimed_at: None,
+        started_at: None,
+        completed_at: None,
+        timeout_at: None,
+        timeout: Some(timeout),
+        retry_count: 0,
+        max_retries: request.max_retries.unwrap_or(retry_attempts),
+        payload: Some(request.payload.clone()),
+        failure_category: None,
+        depends_on: request.depends_on.clone(),
+        deadline: request.deadline,
+        effective_priority: None,
+        version: 0,
+        required_capabilities: request
+            .required_capabilities
+            .clone()
+            .unwrap_or_else(|| resources.capabilities()),
+        resources,
+        schema_version: JOB_METADATA_SCHEMA_VERSION,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{SchedulerConfig, MAX_JOB_RETRIES};
+
+    fn request(runtime: RuntimeType, timeout: Option<u64>) -> CreateJobRequest {
+        CreateJobRequest {
+            challenge_id: Uuid::new_v4(),
+            payload: serde_json::json!({}),
+            priority: None,
+            runtime: Some(runtime),
+            timeout,
+            max_retries: None,
+            job_id: None,
+            depends_on: vec![],
+            deadline: None,
+            required_capabilities: None,
+            resources: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_job_timeout_by_runtime() {
+        let scheduler = SchedulerService::new(&SchedulerConfig {
+            job_timeout: 1800,
+            runtime_timeouts: HashMap::from([(RuntimeType::Sgx, 4 * 3600)]),
+            max_job_timeout: 8 * 3600,
+            ..SchedulerConfig::default()
+        })
+        .unwrap();
+        let timeout_secs = |job: &JobMetadata| job.timeout.unwrap();
+
+        let long = scheduler
+            .create_job(request(RuntimeType::Sgx, None))
+            .await
+            .unwrap();
+        assert_eq!(timeout_secs(&long), 4 * 3600);
+        let short = scheduler
+            .create_job(request(RuntimeType::Docker, None))
+            .await
+            .unwrap();
+        assert_eq!(timeout_secs(&short), 1800);
+        let explicit = scheduler
+            .create_job(request(RuntimeType::Sgx, Some(600)))
+            .await
+            .unwrap();
+        assert_eq!(timeout_secs(&explicit), 600);
+
+        // The timeout runs from the claim, and validators are told the job's
+        assert!(long.timeout_at.is_none());
+        let claim = |job_id| {
+            scheduler.claim_specific_job(
+                job_id,
+                ClaimJobRequest {
+                    validator_hotkey: Hotkey::from_public_key(&[1; 32]),
+                    runtime: RuntimeType::Sgx,
+                    capabilities: vec![],
+                },
+            )
+        };
+        let claimed = claim(long.id).await.unwrap();
+        assert_eq!(claimed.config.timeout, 4 * 3600);
+        let claimed_at = claimed.job.claimed_at.unwrap();
+        assert_eq!(
+            claimed.job.timeout_at,
+            Some(claimed_at + chrono::Duration::seconds(4 * 3600))
+        );
+        let claimed = claim(explicit.id).await.unwrap();
+        assert_eq!(claimed.config.timeout, 600);
+        let claimed_at = claimed.job.claimed_at.unwrap();
+        assert_eq!(
+            claimed.job.timeout_at,
+            Some(claimed_at + chrono::Duration::seconds(600))
+        );
+
+        // Explicit timeouts cannot exceed the ceiling, in batches either
+        let err = scheduler
+            .create_job(request(RuntimeType::Sgx, Some(9 * 3600)))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            PlatformError::Validation { fields }
+                if fields[0].field == "timeout" && fields[0].message.contains("28800")
+        ));
+        let batch = scheduler
+            .create_jobs_batch(vec![
+                request(RuntimeType::Docker, None),
+                request(RuntimeType::Docker, Some(9 * 3600)),
+            ])
+            .await
+            .unwrap();
+        assert!(!batch.created);
+        assert_eq!(batch.results.len(), 1);
+        assert_eq!(batch.results[0].index, 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_payload_rejected() {
+        let scheduler = SchedulerService::new(&SchedulerConfig::default()).unwrap();
+        let mut oversized = request(RuntimeType::Docker, None);
+        oversized.payload = serde_json::json!({ "data": "x".repeat(2 * 1024 * 1024) });
+
+        let err = scheduler.create_job(oversized.clone()).await.unwrap_err();
+        assert_eq!(err.status_code(), 413);
+        assert!(err.to_string().contains("1048576"));
+
+        let batch = scheduler
+            .create_jobs_batch(vec![request(RuntimeType::Docker, None), oversized])
+            .await
+            .unwrap();
+        assert!(!batch.created);
+        assert_eq!(batch.results.len(), 1);
+        assert_eq!(batch.results[0].index, 1);
+        assert!(scheduler
+            .create_job(request(RuntimeType::Docker, None))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_single_jobs_validated_like_batches() {
+        let scheduler = SchedulerService::new(&SchedulerConfig::default()).unwrap();
+        let job_id = Uuid::new_v4();
+        for (invalid, field) in [
+            (
+                CreateJobRequest {
+                    challenge_id: Uuid::nil(),
+                    ..request(RuntimeType::Docker, None)
+                },
+                "challenge_id",
+            ),
+            (
+                CreateJobRequest {
+                    payload: serde_json::Value::Null,
+                    ..request(RuntimeType::Docker, None)
+                },
+                "payload",
+            ),
+            (request(RuntimeType::Docker, Some(0)), "timeout"),
+            (
+                CreateJobRequest {
+                    max_retries: Some(MAX_JOB_RETRIES + 1),
+                    ..request(RuntimeType::Docker, None)
+                },
+                "max_retries",
+            ),
+            (
+                CreateJobRequest {
+                    deadline: Some(Utc::now() - chrono::Duration::minutes(1)),
+                    ..request(RuntimeType::Docker, None)
+                },
+                "deadline",
+            ),
+            (
+                CreateJobRequest {
+                    job_id: Some(job_id),
+                    depends_on: vec![job_id],
+                    ..request(RuntimeType::Docker, None)
+                },
+                "depends_on",
+            ),
+        ] {
+            let expected = invalid.validate().unwrap_err();
+            assert_eq!(expected.field, field);
+
+            let err = scheduler.create_job(invalid.clone()).await.unwrap_err();
+            assert!(
+                matches!(&err, PlatformError::Validation { fields } if fields[0] == expected),
+                "{}: {}",
+                field,
+                err
+            );
+            let batch = scheduler.create_jobs_batch(vec![invalid]).await.unwrap();
+            assert!(!batch.created);
+            assert_eq!(batch.results[0].error.as_deref(), Some(&*expected.message));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_job_resources_checked() {
+        let scheduler = SchedulerService::new(&SchedulerConfig::default()).unwrap();
+        let with_resources = |resources: ResourceRequirements| CreateJobRequest {
+            required_capabilities: Some(vec![]),
+            resources: Some(resources),
+            ..request(RuntimeType::Docker, None)
+        };
+
+        for invalid in [
+            ResourceRequirements {
+                vcpu: Some(0),
+                ..Default::default()
+            },
+            ResourceRequirements {
+                memory_gb: Some(u64::MAX),
+                ..Default::default()
+            },
+            ResourceRequirements {
+                gpu_count: Some(0),
+                gpu_type: Some("h100".to_string()),
+                ..Default::default()
+            },
+        ] {
+            let err = scheduler
+                .create_job(with_resources(invalid.clone()))
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(&err, PlatformError::Validation { fields } if fields[0].field == "resources"),
+                "{:?}: {}",
+                invalid,
+                err
+            );
+        }
+
+        let job = scheduler
+            .create_job(with_resources(ResourceRequirements {
+                vcpu: Some(8),
+                memory_gb: Some(64),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        let claim = |hotkey: &Hotkey| ClaimJobRequest {
+            validator_hotkey: hotkey.clone(),
+            runtime: RuntimeType::Docker,
+            capabilities: vec![],
+        };
+
+        // A validator registered with less memory is not given the job
+        let small = Hotkey::from_public_key(&[1; 32]);
+        scheduler
+            .report_validator_registration(ValidatorRegistration {
+                hotkey: small.clone(),
+                runtimes: vec![RuntimeType::Docker],
+                gpu: false,
+                memory_gb: 32,
+                cpu_cores: 16,
+                preferred_challenges: vec![],
+                gpu_count: 0,
+                gpu_type: None,
+                disk_gb: None,
+            })
+            .await;
+        assert!(scheduler.claim_job(claim(&small)).await.is_err());
+        let err = scheduler
+            .claim_specific_job(job.id, claim(&small))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PlatformError::Conflict { .. }), "{}", err);
+
+        let claimed = scheduler
+            .claim_job(claim(&Hotkey::from_public_key(&[2; 32])))
+            .await
+            .unwrap();
+        assert_eq!(claimed.job.id, job.id);
+        assert_eq!(claimed.config.resources.memory_mb, 64 * 1024);
+    }
+}
@@ -0,0 +1,390 @@
+//! Job retries and the dead-letter queue
+
+use super::{STALE_JOB_CLEANUP_ACTOR, VALIDATOR_DISCONNECT_ACTOR};
+use crate::service::SchedulerService;
+use chrono::Utc;
+use platform_api_models::*;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+impl SchedulerService {
+    /// Re-queue failed jobs that have retries left
+    ///
+    /// A job is retried once the retry delay of its challenge, or else
+    /// `retry_delay` seconds, have passed since it failed, see
+    /// `JobDefaults::retry_delay`. Returns the number of re-queued jobs.
+    pub async fn retry_failed_jobs(&self) -> PlatformResult<u64> {
+        let retry_delay = self.config().await.retry_delay;
+
+        let retried = self
+            .store
+            .retry_failed_jobs(Utc::now(), retry_delay)
+            .await?;
+        let retried = retried.len() as u64;
+
+        if retried > 0 {
+            info!(retried, "Re-queued failed jobs for retry");
+        }
+
+        Ok(retried)
+    }
+
+    /// Re-queue the claimed and running jobs of validator `hotkey`, which
+    /// disconnected without finishing them
+    ///
+    /// Each job is failed with `FailureCategory::ValidatorCrash`, using up one
+    /// of its retries, and returned to pending right away for another
+    /// validator to claim; jobs without retries left are dead-lettered. Jobs
+    /// that changed meanwhile, e.g. because their result came in, are left as
+    /// they are. Returns the number of re-queued jobs.
+    #[tracing::instrument(name = "scheduler.requeue_validator_jobs", skip_all, fields(validator_hotkey = %hotkey))]
+    pub async fn requeue_validator_jobs(&self, hotkey: &str) -> PlatformResult<u64> {
+        let orphaned = self.store.validator_jobs_in_flight(hotkey).await?;
+
+        let mut requeued = 0;
+        for (job_id, version) in orphaned {
+            let request = FailJobRequest {
+                reason: "Validator disconnected".to_string(),
+                error_details: None,
+                failure_category: Some(FailureCategory::ValidatorCrash),
+                expected_version: Some(version),
+            };
+            if self
+                .fail_and_requeue(job_id, request, VALIDATOR_DISCONNECT_ACTOR)
+                .await?
+            {
+                requeued += 1;
+            }
+        }
+
+        if requeued > 0 {
+            warn!(requeued, "Re-queued jobs of disconnected validator");
+        }
+
+        Ok(requeued)
+    }
+
+    /// Re-queue the jobs still claimed `job_timeout` seconds after they were
+    /// claimed, whose validator never started them
+    ///
+    /// Claims are only released when their validator disconnects, so the
+    /// claims of validators connected when the platform stopped would be held
+    /// forever. Each job is failed with `FailureCategory::Timeout`, using up
+    /// one of its retries, and goes back to pending; jobs without retries left
+    /// are dead-lettered. Returns the number of re-queued jobs.
+    pub async fn cleanup_stale_jobs(&self) -> PlatformResult<u64> {
+        let job_timeout = self.config().await.job_timeout;
+        let claimed_before = Utc::now() - chrono::Duration::seconds(job_timeout as i64);
+
+        let stale = self.store.stale_claims(claimed_before).await?;
+        let mut requeued = 0;
+        for (job_id, version) in stale {
+            let request = FailJobRequest {
+                reason: "Claim was never started".to_string(),
+                error_details: None,
+                failure_category: Some(FailureCategory::Timeout),
+                expected_version: Some(version),
+            };
+            if self
+                .fail_and_requeue(job_id, request, STALE_JOB_CLEANUP_ACTOR)
+                .await?
+            {
+                warn!(job_id = %job_id, "Re-queued stale claimed job");
+                requeued += 1;
+            }
+        }
+
+        Ok(requeued)
+    }
+
+    /// Fail job `job_id` with `request` on behalf of `actor` and return it to
+    /// pending right away if it has retries left
+    ///
+    /// Returns whether the job was re-queued; it is not if it changed since
+    /// `request.expected_version` or was dead-lettered.
+    async fn fail_and_requeue(
+        &self,
+        job_id: Uuid,
+        request: FailJobRequest,
+        actor: &str,
+    ) -> PlatformResult<bool> {
+        match self.fail_job_as(job_id, request, Some(actor)).await {
+            Ok(()) => {}
+            Err(PlatformError::Conflict { .. }) => {
+                info!(job_id = %job_id, actor, "Job changed before it could be re-queued");
+                return Ok(false);
+            }
+            Err(e) => return Err(e),
+        }
+
+        Ok(self.store.retry_failed_job(job_id, actor).await?.is_some())
+    }
+
+    /// List dead-lettered jobs, most recently dead-lettered first
+    pub async fn list_dead_lettered_jobs(
+        &self,
+        page: u32,
+        per_page: u32,
+    ) -> PlatformResult<DeadLetteredJobListResponse> {
+        Ok(self.store.list_dead_lettered_jobs(page, per_page).await?)
+    }
+
+    /// Re-queue a dead-lettered job with a fresh set of retries, on behalf of `actor`
+    ///
+    /// The retry history is kept. A job whose dependencies have not all
+    /// completed, e.g. one dead-lettered along with a dependency, goes back to
+    /// blocked. Fails if the job is not dead-lettered.
+    #[tracing::instrument(name = "scheduler.requeue_job", skip_all, fields(job_id = %job_id))]
+    pub async fn requeue_job(&self, job_id: Uuid, actor: &str) -> PlatformResult<JobMetadata> {
+        if !self.store.requeue_job(job_id, actor).await? {
+            return Err(PlatformError::conflict(format!(
+                "Job {} is not dead-lettered",
+                job_id
+            )));
+        }
+
+        let job = self.get_job(job_id).await?;
+
+        info!(job_id = %job_id, actor = actor, "Requeued dead-lettered job");
+        Ok(job)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{service::SchedulerService, types::*};
+    use platform_api_models::*;
+    use uuid::Uuid;
+
+    fn fail_request() -> FailJobRequest {
+        FailJobRequest {
+            reason: "exit code 1".to_string(),
+            error_details: None,
+            failure_category: Some(FailureCategory::ExecutionError),
+            expected_version: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_job_is_dead_lettered_and_requeued() {
+        let scheduler = SchedulerService::new(&SchedulerConfig {
+            retry_delay: 0,
+            ..SchedulerConfig::default()
+        })
+        .unwrap();
+
+        let job = scheduler
+            .create_job(CreateJobRequest {
+                challenge_id: Uuid::new_v4(),
+                payload: serde_json::json!({}),
+                priority: None,
+                runtime: Some(RuntimeType::Docker),
+                timeout: None,
+                max_retries: Some(1),
+                job_id: None,
+                depends_on: vec![],
+                deadline: None,
+                required_capabilities: None,
+                resources: None,
+            })
+            .await
+            .unwrap();
+        let job_id = job.id;
+
+        scheduler.fail_job(job_id, fail_request()).await.unwrap();
+        assert_eq!(
+            scheduler.get_job(job_id).await.unwrap().status,
+            JobStatus::Failed
+        );
+
+        assert_eq!(scheduler.retry_failed_jobs().await.unwrap(), 1);
+        let retried = scheduler.get_job(job_id).await.unwrap();
+        assert_eq!(retried.status, JobStatus::Pending);
+        assert_eq!(retried.retry_count, 1);
+
+        scheduler.fail_job(job_id, fail_request()).await.unwrap();
+        assert_eq!(
+            scheduler.get_job(job_id).await.unwrap().status,
+            JobStatus::DeadLettered
+        );
+        assert_eq!(scheduler.retry_failed_jobs().await.unwrap(), 0);
+
+        let dead_lettered = scheduler.list_dead_lettered_jobs(1, 20).await.unwrap();
+        assert_eq!(dead_lettered.total, 1);
+        assert_eq!(dead_lettered.jobs[0].reason.as_deref(), Some("exit code 1"));
+        let attempts: Vec<u32> = dead_lettered.jobs[0]
+            .retry_history
+            .iter()
+            .map(|a| a.attempt)
+            .collect();
+        assert_eq!(attempts, vec![1, 2]);
+        let past = scheduler
+            .list_dead_lettered_jobs(u32::MAX, u32::MAX)
+            .await
+            .unwrap();
+        assert_eq!(past.total, 1);
+        assert!(past.jobs.is_empty());
+
+        let requeued = scheduler.requeue_job(job_id, "admin").await.unwrap();
+        assert_eq!(requeued.status, JobStatus::Pending);
+        assert_eq!(requeued.retry_count, 0);
+        assert!(scheduler.requeue_job(job_id, "admin").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_jobs_of_disconnected_validator_are_requeued() {
+        let scheduler = SchedulerService::new(&SchedulerConfig::default()).unwrap();
+        let validator_a = Hotkey::from_public_key(&[1; 32]);
+        let validator_b = Hotkey::from_public_key(&[2; 32]);
+        let claim = |hotkey: &Hotkey| ClaimJobRequest {
+            validator_hotkey: hotkey.clone(),
+            runtime: RuntimeType::Docker,
+            capabilities: vec![],
+        };
+
+        let job = scheduler
+            .create_job(CreateJobRequest {
+                challenge_id: Uuid::new_v4(),
+                payload: serde_json::json!({}),
+                priority: None,
+                runtime: Some(RuntimeType::Docker),
+                timeout: None,
+                max_retries: Some(1),
+                job_id: None,
+                depends_on: vec![],
+                deadline: None,
+                required_capabilities: None,
+                resources: None,
+            })
+            .await
+            .unwrap();
+        let job_id = job.id;
+        scheduler.claim_job(claim(&validator_a)).await.unwrap();
+
+        // Another validator's disconnect leaves the job alone
+        assert_eq!(
+            scheduler
+                .requeue_validator_jobs(&validator_b)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            scheduler.get_job(job_id).await.unwrap().status,
+            JobStatus::Claimed
+        );
+
+        assert_eq!(
+            scheduler
+                .requeue_validator_jobs(&validator_a)
+                .await
+                .unwrap(),
+            1
+        );
+        let requeued = scheduler.get_job(job_id).await.unwrap();
+        assert_eq!(requeued.status, JobStatus::Pending);
+        assert_eq!(requeued.retry_count, 1);
+        assert_eq!(requeued.validator_hotkey, None);
+        assert_eq!(requeued.timeout_at, None);
+
+        // Another validator picks it up with a new timeout; its disconnect
+        // uses the last retry
+        let claimed = scheduler.claim_job(claim(&validator_b)).await.unwrap();
+        assert_eq!(claimed.job.id, job_id);
+        let claimed_at = claimed.job.claimed_at.unwrap();
+        assert_eq!(
+            claimed.job.timeout_at,
+            Some(claimed_at + chrono::Duration::seconds(claimed.config.timeout as i64))
+        );
+        assert_eq!(
+            scheduler
+                .requeue_validator_jobs(&validator_b)
+                .await
+                .unwrap(),
+            0
+        );
+        let dead_lettered = scheduler.list_dead_lettered_jobs(1, 20).await.unwrap();
+        assert_eq!(dead_lettered.total, 1);
+        assert_eq!(
+            dead_lettered.jobs[0].retry_history[1].failure_category,
+            Some(FailureCategory::ValidatorCrash)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stale_claimed_jobs_are_requeued() {
+        let config = SchedulerConfig::default();
+        let scheduler = SchedulerService::new(&config).unwrap();
+        let validator = Hotkey::from_public_key(&[1; 32]);
+
+        let job = scheduler
+            .create_job(CreateJobRequest {
+                challenge_id: Uuid::new_v4(),
+                payload: serde_json::json!({}),
+                priority: None,
+                runtime: Some(RuntimeType::Docker),
+                timeout: Some(60),
+                max_retries: Some(1),
+                job_id: None,
+                depends_on: vec![],
+                deadline: None,
+                required_capabilities: None,
+                resources: None,
+            })
+            .await
+            .unwrap();
+        scheduler
+            .claim_job(ClaimJobRequest {
+                validator_hotkey: validator.clone(),
+                runtime: RuntimeType::Docker,
+                capabilities: vec![],
+            })
+            .await
+            .unwrap();
+
+        // Claimed less than `job_timeout` ago
+        assert_eq!(scheduler.cleanup_stale_jobs().await.unwrap(), 0);
+        assert_eq!(
+            scheduler.get_job(job.id).await.unwrap().status,
+            JobStatus::Claimed
+        );
+
+        scheduler
+            .update_config(SchedulerConfig {
+                job_timeout: 0,
+                ..config
+            })
+            .await;
+        assert_eq!(scheduler.cleanup_stale_jobs().await.unwrap(), 1);
+        let requeued = scheduler.get_job(job.id).await.unwrap();
+        assert_eq!(requeued.status, JobStatus::Pending);
+        assert_eq!(requeued.retry_count, 1);
+        assert_eq!(requeued.validator_hotkey, None);
+        assert_eq!(requeued.timeout_at, None);
+        assert_eq!(scheduler.cleanup_stale_jobs().await.unwrap(), 0);
+
+        // Its timeout starts over when it is claimed again
+        let claimed = scheduler
+            .claim_job(ClaimJobRequest {
+                validator_hotkey: validator.clone(),
+                runtime: RuntimeType::Docker,
+                capabilities: vec![],
+            })
+            .await
+            .unwrap();
+        let claimed_at = claimed.job.claimed_at.unwrap();
+        assert_eq!(
+            claimed.job.timeout_at,
+            Some(claimed_at + chrono::Duration::seconds(60))
+        );
+
+        // Going stale again uses up its last retry
+        assert_eq!(scheduler.cleanup_stale_jobs().await.unwrap(), 0);
+        let dead_lettered = scheduler.get_job(job.id).await.unwrap();
+        assert_eq!(dead_lettered.status, JobStatus::DeadLettered);
+        assert_eq!(
+            dead_lettered.failure_category,
+            Some(FailureCategory::Timeout)
+        );
+    }
+}
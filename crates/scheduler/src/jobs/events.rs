@@ -0,0 +1,16 @@
+//! Actors of the job events the scheduler records on its own behalf
+//!
+//! The job stores record the events in `job_events` along with the
+//! transitions they describe.
+
+/// Actor of the transitions made by the timeout enforcer
+pub const TIMEOUT_ENFORCER_ACTOR: &str = "timeout_enforcer";
+
+/// Actor of the transitions of jobs orphaned by their validator disconnecting
+pub const VALIDATOR_DISCONNECT_ACTOR: &str = "validator_disconnect";
+
+/// Actor of the transitions of jobs left claimed across a restart
+pub const STALE_JOB_CLEANUP_ACTOR: &str = "stale_job_cleanup";
+
+/// Actor of the transitions made by the scheduler itself, such as retries
+pub const SCHEDULER_ACTOR: &str = "scheduler";
@@ -1,229 +1,286 @@
-//! Job lifecycle operations (complete, fail)
+//! Job lifecycle operations (complete, fail, timeout)
 
-use crate::{rows::JobRow, service::SchedulerService, types::TestResultData};
+use super::TIMEOUT_ENFORCER_ACTOR;
+use crate::{
+    service::SchedulerService,
+    store::FailedJob,
+    types::{ScoreOutOfBounds, ScorePolicyViolated},
+    webhooks::JobWebhookEvent,
+};
 use anyhow::Result;
 use chrono::Utc;
 use platform_api_models::*;
-use tracing::info;
+use std::collections::BTreeMap;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 impl SchedulerService {
     /// Mark a job as completed with results
-    pub async fn complete_job(&self, job_id: Uuid, result: SubmitResultRequest) -> Result<()> {
-        if let Some(pool) = &self.database_pool {
-            let now = Utc::now();
-
-            // Extract progress metrics from result
-            let result_json = serde_json::to_value(&result.result)?;
-            let progress_percent = result_json
-                .get("progress")
-                .and_then(|p| p.get("progress_percent"))
-                .and_then(|v| v.as_f64())
-                .map(|v| v * 100.0);
-            let total_tasks = result_json
-                .get("progress")
-                .and_then(|p| p.get("total_tasks"))
-                .and_then(|v| v.as_i64())
-                .map(|v| v as i32);
-            let completed_tasks = result_json
-                .get("progress")
-                .and_then(|p| p.get("completed_tasks"))
-                .and_then(|v| v.as_i64())
-                .map(|v| v as i32);
-            let resolved_tasks = result_json
-                .get("progress")
-                .and_then(|p| p.get("resolved_tasks"))
-                .and_then(|v| v.as_i64())
-                .map(|v| v as i32);
-            let unresolved_tasks = result_json
-                .get("progress")
-                .and_then(|p| p.get("unresolved_tasks"))
-                .and_then(|v| v.as_i64())
-                .map(|v| v as i32);
-
-            // Get challenge_id from job
-            let job_row =
-                sqlx::query_as::<_, JobRow>("SELECT challenge_id FROM jobs WHERE id = $1")
-                    .bind(job_id)
-                    .fetch_optional(pool.as_ref())
-                    .await?;
-
-            let challenge_id = job_row
-                .map(|r| r.challenge_id)
-                .ok_or_else(|| anyhow::anyhow!("Job {} not found", job_id))?;
-
-            // Update job with progress metrics
-            sqlx::query(
-                r#"
-                UPDATE jobs 
-                SET status = 'completed',
-                    started_at = COALESCE(started_at, $1),
-                    completed_at = $1,
-                    result = $2,
-                    progress_percent = $4,
-                    total_tasks = $5,
-                    completed_tasks = $6,
-                    resolved_tasks = $7,
-                    unresolved_tasks = $8
-                WHERE id = $3
-                "#,
+    ///
+    /// Fails with a `PlatformError::Conflict` if the job changed since the
+    /// version the result expects, e.g. if it was reset after its validator
+    /// claimed it. Scores outside the bounds of the job's challenge are
+    /// clamped, their raw values recorded in the completion event, or the
+    /// result is refused as `ScoreOutOfBounds`, see
+    /// `ChallengeMetadata::score_bounds`. Results breaking the score policy
+    /// of the challenge are then refused as `ScorePolicyViolated`, listing
+    /// each violation, see `ChallengeMetadata::score_policy`.
+    #[tracing::instrument(name = "scheduler.complete_job", skip_all, fields(job_id = %job_id))]
+    pub async fn complete_job(
+        &self,
+        job_id: Uuid,
+        mut result: SubmitResultRequest,
+    ) -> PlatformResult<()> {
+        let (bounds, policy) = self.job_score_rules(job_id).await?;
+        let raw_scores = bound_scores(job_id, &mut result.result, &bounds)?;
+        if !raw_scores.is_empty() {
+            warn!(job_id = %job_id, raw_scores = ?raw_scores, "Clamped out of bounds scores");
+        }
+        result
+            .result
+            .validate(&policy)
+            .map_err(|violations| ScorePolicyViolated { job_id, violations })?;
+
+        let version = self
+            .update_version(job_id, result.expected_version)
+            .await?
+            .ok_or_else(|| PlatformError::not_found("job", job_id))?;
+        let mut details = serde_json::json!({});
+        if !raw_scores.is_empty() {
+            details["raw_scores"] = serde_json::to_value(&raw_scores)?;
+        }
+        let completed = self
+            .store
+            .complete_job(
+                job_id,
+                version,
+                &serde_json::to_value(&result.result)?,
+                &result.receipts,
+                details,
+                Utc::now(),
             )
-            .bind(now)
-            .bind(&result_json)
-            .bind(job_id)
-            .bind(progress_percent)
-            .bind(total_tasks)
-            .bind(completed_tasks)
-            .bind(resolved_tasks)
-            .bind(unresolved_tasks)
-            .execute(pool.as_ref())
             .await?;
+        if let Some(hotkey) = &completed.validator_hotkey {
+            self.record_validator_outcome(hotkey, &completed.old_status, true)
+                .await;
+        }
+        self.notify_webhook(JobWebhookEvent::new(
+            job_id,
+            completed.challenge_id,
+            JobStatus::Completed,
+            completed.validator_hotkey,
+        ))
+        .await;
 
-            // Extract and store individual test results
-            if let Some(results_array) = result_json
-                .get("results")
-                .and_then(|r| r.get("results"))
-                .and_then(|r| r.as_array())
-            {
-                for test_result in results_array {
-                    if let Ok(test_data) = Self::extract_test_result(test_result) {
-                        sqlx::query(
-                            r#"
-                            INSERT INTO job_test_results (
-                                job_id, challenge_id, task_id, test_name, status,
-                                is_resolved, error_message, execution_time_ms,
-                                output_text, logs, metrics
-                            )
-                            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-                            "#,
-                        )
-                        .bind(job_id)
-                        .bind(challenge_id)
-                        .bind(&test_data.task_id)
-                        .bind(test_data.test_name.as_deref())
-                        .bind(&test_data.status)
-                        .bind(test_data.is_resolved)
-                        .bind(test_data.error_message.as_deref())
-                        .bind(test_data.execution_time_ms)
-                        .bind(test_data.output_text.as_deref())
-                        .bind(&test_data.logs)
-                        .bind(&test_data.metrics)
-                        .execute(pool.as_ref())
-                        .await?;
-                    }
-                }
-            }
+        if !completed.unblocked.is_empty() {
+            info!(job_id = %job_id, unblocked = completed.unblocked.len(), "Unblocked dependent jobs");
+        }
+
+        info!(job_id = %job_id, "Job completed");
+        Ok(())
+    }
 
-            info!(job_id = %job_id, "Job completed with detailed results stored");
+    /// Mark a job as failed
+    ///
+    /// The attempt is appended to the job's retry history. A job that has no
+    /// retries left is dead-lettered instead of failed, along with the jobs
+    /// blocked on it. Fails with a
+    /// `PlatformError::Conflict` like [`Self::complete_job`].
+    #[tracing::instrument(name = "scheduler.fail_job", skip_all, fields(job_id = %job_id))]
+    pub async fn fail_job(&self, job_id: Uuid, request: FailJobRequest) -> PlatformResult<()> {
+        self.fail_job_as(job_id, request, None).await
+    }
+
+    /// Mark a job as failed on behalf of `actor`, by default its validator
+    pub(crate) async fn fail_job_as(
+        &self,
+        job_id: Uuid,
+        request: FailJobRequest,
+        actor: Option<&str>,
+    ) -> PlatformResult<()> {
+        let now = Utc::now();
+
+        let Some(version) = self
+            .update_version(job_id, request.expected_version)
+            .await?
+        else {
+            return Ok(());
+        };
+        let Some(failed) = self
+            .store
+            .fail_job(job_id, version, &request, actor, now)
+            .await?
+        else {
+            return Ok(());
+        };
+        if !failed.dead_lettered_dependents.is_empty() {
+            warn!(
+                job_id = %job_id,
+                dependents = failed.dead_lettered_dependents.len(),
+                "Dead-lettered dependent jobs"
+            );
+        }
+
+        let FailedJob {
+            status,
+            challenge_id,
+            validator_hotkey,
+            old_status,
+            ..
+        } = failed;
+        if let Some(hotkey) = &validator_hotkey {
+            self.record_validator_outcome(hotkey, &old_status, false)
+                .await;
+        }
+        self.notify_webhook(
+            JobWebhookEvent::new(job_id, challenge_id, status.clone(), validator_hotkey)
+                .with_failure(&request.reason, request.failure_category.clone()),
+        )
+        .await;
+
+        if status == JobStatus::DeadLettered {
+            warn!(
+                job_id = %job_id,
+                reason = %request.reason,
+                failure_category = ?request.failure_category,
+                "Job exhausted its retries and was dead-lettered"
+            );
         } else {
-            let mut jobs = self.jobs.write().await;
-            if let Some(job) = jobs.get_mut(&job_id) {
-                job.status = JobStatus::Completed;
-                job.completed_at = Some(Utc::now());
-                if job.started_at.is_none() {
-                    job.started_at = Some(Utc::now());
-                }
-            }
+            info!(
+                job_id = %job_id,
+                reason = %request.reason,
+                failure_category = ?request.failure_category,
+                "Job failed"
+            );
         }
 
         Ok(())
     }
 
-    /// Extract test result data from Terminal-Bench result JSON
-    fn extract_test_result(test_result: &serde_json::Value) -> Result<TestResultData> {
-        let task_id = test_result
-            .get("task_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Missing task_id"))?
-            .to_string();
-
-        let test_name = test_result
-            .get("test_name")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-
-        let is_resolved = test_result
-            .get("is_resolved")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-
-        let status = if is_resolved {
-            "passed".to_string()
-        } else if test_result.get("error").is_some() {
-            "error".to_string()
-        } else {
-            "failed".to_string()
+    /// Score bounds and policy of the challenge of job `job_id`; the defaults
+    /// without a database
+    async fn job_score_rules(&self, job_id: Uuid) -> Result<(ScoreBounds, ScorePolicy)> {
+        let Some(pool) = &self.database_pool else {
+            return Ok(Default::default());
         };
+        let rules: Option<(serde_json::Value, serde_json::Value)> = sqlx::query_as(
+            r#"
+            SELECT c.score_bounds, c.score_policy FROM jobs j
+            JOIN challenges c ON c.id = j.challenge_id
+            WHERE j.id = $1
+            "#,
+        )
+        .bind(job_id)
+        .fetch_optional(pool.as_ref())
+        .await?;
 
-        let error_message = test_result
-            .get("error")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-
-        let execution_time_ms = test_result
-            .get("execution_time_ms")
-            .or_else(|| test_result.get("execution_time"))
-            .and_then(|v| v.as_i64());
-
-        let output_text = test_result
-            .get("output")
-            .or_else(|| test_result.get("output_text"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-
-        let logs = test_result
-            .get("logs")
-            .cloned()
-            .unwrap_or(serde_json::Value::Null);
-
-        let metrics = test_result
-            .get("metrics")
-            .cloned()
-            .unwrap_or(serde_json::Value::Null);
-
-        Ok(TestResultData {
-            task_id,
-            test_name,
-            status,
-            is_resolved,
-            error_message,
-            execution_time_ms,
-            output_text,
-            logs,
-            metrics,
-        })
+        let Some((bounds, policy)) = rules else {
+            return Ok(Default::default());
+        };
+        let bounds = serde_json::from_value(bounds).unwrap_or_else(|e| {
+            warn!(job_id = %job_id, "Ignoring invalid score bounds: {}", e);
+            ScoreBounds::default()
+        });
+        let policy = score_policy(policy, &bounds).unwrap_or_else(|e| {
+            warn!(job_id = %job_id, "Ignoring invalid score policy: {}", e);
+            ScorePolicy::default()
+        });
+        Ok((bounds, policy))
     }
 
-    /// Mark a job as failed
-    pub async fn fail_job(&self, job_id: Uuid, request: FailJobRequest) -> Result<()> {
-        if let Some(pool) = &self.database_pool {
-            let now = Utc::now();
-
-            sqlx::query(
-                r#"
-                UPDATE jobs 
-                SET status = 'failed',
-                    error_message = $1,
-                    completed_at = $2
-                WHERE id = $3
-                "#,
-            )
-            .bind(&request.reason)
-            .bind(now)
-            .bind(job_id)
-            .execute(pool.as_ref())
-            .await?;
+    /// Version of job `job_id` an update expecting `expected` is made at: that
+    /// one or, without an expectation, the current one; `None` for unknown jobs
+    async fn update_version(&self, job_id: Uuid, expected: Option<u64>) -> Result<Option<u64>> {
+        match expected {
+            Some(version) => Ok(Some(version)),
+            None => Ok(self.store.get_job(job_id).await?.map(|job| job.version)),
+        }
+    }
 
-            info!(job_id = %job_id, reason = %request.reason, "Job failed");
-        } else {
-            let mut jobs = self.jobs.write().await;
-            if let Some(job) = jobs.get_mut(&job_id) {
-                job.status = JobStatus::Failed;
-                job.completed_at = Some(Utc::now());
+    /// Fail all claimed or running jobs whose `timeout_at` has passed.
+    ///
+    /// Jobs that changed since they were found expired, e.g. because their
+    /// result came in meanwhile, are left as they are. Returns the number of
+    /// jobs that were failed with `FailureCategory::Timeout`.
+    pub async fn enforce_timeouts(&self) -> PlatformResult<u64> {
+        let now = Utc::now();
+
+        let expired = self.store.expired_jobs(now).await?;
+
+        let mut failed = 0;
+        for (job_id, version) in expired {
+            warn!(job_id = %job_id, "Job exceeded its timeout, marking as failed");
+            let result = self
+                .fail_job_as(
+                    job_id,
+                    FailJobRequest {
+                        reason: "Job exceeded timeout".to_string(),
+                        error_details: None,
+                        failure_category: Some(FailureCategory::Timeout),
+                        expected_version: Some(version),
+                    },
+                    Some(TIMEOUT_ENFORCER_ACTOR),
+                )
+                .await;
+            match result {
+                Ok(()) => failed += 1,
+                Err(PlatformError::Conflict { .. }) => {
+                    info!(job_id = %job_id, "Job changed before its timeout was enforced");
+                }
+                Err(e) => return Err(e),
             }
         }
 
-        Ok(())
+        Ok(failed)
     }
 }
 
+/// Bring the scores of `result` within `bounds`, returning the raw values of
+/// those that were clamped
+///
+/// Fails with `ScoreOutOfBounds` on the first score out of bounds that
+/// rejects.
+fn bound_scores(
+    job_id: Uuid,
+    result: &mut EvalResult,
+    bounds: &ScoreBounds,
+) -> PlatformResult<BTreeMap<String, Score>> {
+    let mut raw_scores = BTreeMap::new();
+    for (name, score) in result.scores.iter_mut() {
+        if bounds.contains(*score) {
+            continue;
+        }
+        match bounds.out_of_range {
+            OutOfRangeScores::Reject => {
+                return Err(ScoreOutOfBounds {
+                    job_id,
+                    name: name.clone(),
+                    score: *score,
+                }
+                .into())
+            }
+            OutOfRangeScores::Clamp => {
+                raw_scores.insert(name.clone(), *score);
+                *score = bounds.clamp(*score);
+            }
+        }
+    }
+    Ok(raw_scores)
+}
+
+/// Score policy of a challenge with score `bounds`, as stored
+///
+/// A policy without a `default_range` of its own holds the scores of a
+/// challenge with bounds to those bounds only, rather than to the default
+/// `0.0..=1.0` range they may be wider than.
+fn score_policy(
+    policy: serde_json::Value,
+    bounds: &ScoreBounds,
+) -> serde_json::Result<ScorePolicy> {
+    let own_default_range = policy.get("default_range").is_some();
+    let mut policy: ScorePolicy = serde_json::from_value(policy)?;
+    if !own_default_range && (bounds.min.is_some() || bounds.max.is_some()) {
+        policy.default_range = None;
+    }
+    Ok(policy)
+}
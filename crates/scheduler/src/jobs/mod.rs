@@ -1,13 +1,20 @@
 //! Job operations for the scheduler service
 
+mod admin;
 mod claim;
 mod create;
+mod dead_letter;
+mod events;
 mod lifecycle;
 mod query;
+mod quota;
 
 // Re-export all implementations
+pub use admin::*;
 pub use claim::*;
 pub use create::*;
+pub use events::*;
 pub use lifecycle::*;
 pub use query::*;
+pub use quota::*;
 
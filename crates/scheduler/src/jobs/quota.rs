@@ -0,0 +1,146 @@
+//! Per-challenge job quotas
+//!
+//! A challenge may only have so many jobs waiting and so many jobs being
+//! evaluated at once, see `ChallengeJobQuota`, so that one challenge cannot
+//! flood the queue or take every validator.
+
+use crate::{
+    service::SchedulerService,
+    types::{ChallengeJobUsage, QuotaExceeded, SchedulerConfig},
+};
+use platform_api_models::*;
+use std::collections::BTreeMap;
+
+/// Statuses counted against `ChallengeJobQuota::max_pending`
+pub const PENDING_STATUSES: [&str; 2] = ["pending", "blocked"];
+
+/// Statuses counted against `ChallengeJobQuota::max_in_flight`
+pub const IN_FLIGHT_STATUSES: [&str; 2] = ["claimed", "running"];
+
+impl SchedulerService {
+    /// Jobs `challenge_id` currently has pending and in flight, with its quota
+    pub async fn challenge_job_usage(&self, challenge_id: Id) -> PlatformResult<ChallengeJobUsage> {
+        let quota = self.config().await.challenge_quota(&challenge_id);
+
+        let (pending, in_flight) = self.store.challenge_usage(challenge_id).await?;
+
+        Ok(ChallengeJobUsage {
+            pending,
+            in_flight,
+            quota,
+        })
+    }
+
+    /// Fail if `challenge_id` already has its quota of jobs in flight
+    pub(crate) async fn check_in_flight_quota(&self, challenge_id: Id) -> PlatformResult<()> {
+        let usage = self.challenge_job_usage(challenge_id).await?;
+        if usage.in_flight >= usage.quota.max_in_flight {
+            return Err(QuotaExceeded::InFlight {
+                challenge_id,
+                limit: usage.quota.max_in_flight,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Number of new jobs of each challenge among `jobs`
+pub(crate) fn jobs_per_challenge<'a>(
+    jobs: impl IntoIterator<Item = &'a JobMetadata>,
+) -> BTreeMap<Id, u64> {
+    let mut counts = BTreeMap::new();
+    for job in jobs {
+        *counts.entry(job.challenge_id).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Fail if `adding` more pending jobs of `challenge_id` would exceed its quota
+pub(crate) fn check_pending(
+    config: &SchedulerConfig,
+    challenge_id: Id,
+    pending: u64,
+    adding: u64,
+) -> PlatformResult<()> {
+    let limit = config.challenge_quota(&challenge_id).max_pending;
+    if pending + adding > limit {
+        tracing::warn!(
+            challenge_id = %challenge_id,
+            pending,
+            adding,
+            limit,
+            "Rejected jobs over the challenge quota"
+        );
+        return Err(QuotaExceeded::Pending {
+            challenge_id,
+            limit,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChallengeJobQuota, CreateJobRequest};
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn request(challenge_id: Id) -> CreateJobRequest {
+        CreateJobRequest {
+            challenge_id,
+            payload: serde_json::json!({}),
+            priority: None,
+            runtime: Some(RuntimeType::Docker),
+            timeout: None,
+            max_retries: None,
+            job_id: None,
+            depends_on: vec![],
+            deadline: None,
+            required_capabilities: None,
+            resources: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_challenge_quota_in_memory() {
+        let flooding = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let quota = ChallengeJobQuota {
+            max_pending: 2,
+            max_in_flight: 1,
+        };
+        let scheduler = SchedulerService::new(&SchedulerConfig {
+            challenge_quotas: HashMap::from([(flooding, quota)]),
+            ..SchedulerConfig::default()
+        })
+        .unwrap();
+
+        scheduler.create_job(request(flooding)).await.unwrap();
+        scheduler.create_job(request(flooding)).await.unwrap();
+        let err = scheduler.create_job(request(flooding)).await.unwrap_err();
+        assert!(matches!(err, PlatformError::RateLimited { .. }));
+        assert!(err.to_string().contains("quota of 2 pending jobs"), "{}", err);
+        scheduler.create_job(request(other)).await.unwrap();
+
+        let usage = scheduler.challenge_job_usage(flooding).await.unwrap();
+        assert_eq!((usage.pending, usage.in_flight), (2, 0));
+        assert_eq!(usage.quota, quota);
+
+        // Only one job of the flooding challenge may be in flight, so the
+        // second claim goes to the other challenge
+        let claim = || {
+            scheduler.claim_job(ClaimJobRequest {
+                validator_hotkey: Hotkey::from_public_key(&[1; 32]),
+                runtime: RuntimeType::Docker,
+                capabilities: vec![],
+            })
+        };
+        let first = claim().await.unwrap().job;
+        let second = claim().await.unwrap().job;
+        assert_ne!(first.challenge_id, second.challenge_id);
+        assert!(claim().await.is_err());
+    }
+}
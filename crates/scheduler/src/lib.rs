@@ -2,14 +2,28 @@
 
 mod capacity;
 mod jobs;
+mod payload_schema;
+mod priority;
+mod reliability;
 mod rows;
+mod scorer;
 mod scoring;
 mod service;
+mod store;
+mod trust;
 mod types;
+mod webhooks;
 
 pub use capacity::*;
 pub use jobs::*;
+pub use payload_schema::*;
+pub use priority::*;
+pub use reliability::*;
 pub use rows::*;
+pub use scorer::*;
 pub use scoring::*;
 pub use service::*;
+pub use store::*;
+pub use trust::*;
 pub use types::*;
+pub use webhooks::*;
@@ -0,0 +1,502 @@
+//! Validation of job payloads against the JSON Schema of their challenge
+//!
+//! A challenge may register a JSON Schema for its job payloads, see
+//! `ChallengeMetadata::job_payload_schema`. Jobs whose payload does not match
+//! it are rejected when created, with the path of the offending value, instead
+//! of failing once a validator runs them. Challenges without a schema accept
+//! any payload.
+//!
+//! The supported keywords are `type`, `enum`, `const`, `properties`,
+//! `required`, `additionalProperties`, `items`, `minItems`, `maxItems`,
+//! `minLength`, `maxLength`, `minimum`, `maximum`, `exclusiveMinimum` and
+//! `exclusiveMaximum`. The annotations `$schema`, `$id`, `$comment`, `title`,
+//! `description`, `default` and `examples` are allowed and have no effect.
+//! Schemas using any other keyword, e.g. `pattern`, `format`, `$ref`, `$defs`
+//! or `oneOf`, are rejected, rather than accepted without enforcing it.
+//!
+//! As in JSON Schema, `enum` and `const` compare numbers by value, so `1`
+//! matches `1.0`.
+
+use serde_json::{Map, Value};
+
+use crate::types::InvalidPayload;
+
+const TYPES: [&str; 7] = [
+    "null", "boolean", "object", "array", "number", "integer", "string",
+];
+
+/// Keywords that describe a schema without constraining payloads
+const ANNOTATIONS: [&str; 7] = [
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+];
+
+/// Check that `schema` is a schema the scheduler can validate payloads against
+///
+/// The error names the offending keyword, e.g.
+/// `schema/properties/seed/type: invalid value "int"` or
+/// `schema/properties/name/pattern: unsupported keyword`.
+pub fn check_payload_schema(schema: &Value) -> Result<(), String> {
+    check_schema(schema, "schema")
+}
+
+/// Check `payload` against `schema`
+///
+/// The error carries the path of the first value that does not match, e.g.
+/// `payload/tasks/0/name`. The schema is expected to have passed
+/// [`check_payload_schema`]; malformed keywords are ignored.
+pub fn validate_payload(schema: &Value, payload: &Value) -> Result<(), InvalidPayload> {
+    validate(schema, payload, "payload")
+}
+
+fn check_schema(schema: &Value, path: &str) -> Result<(), String> {
+    let schema = match schema {
+        Value::Bool(_) => return Ok(()),
+        Value::Object(schema) => schema,
+        _ => return Err(format!("{}: a schema must be an object or a boolean", path)),
+    };
+
+    for (keyword, value) in schema {
+        let keyword_path = child(path, keyword);
+        let valid = match keyword.as_str() {
+            "type" => match value {
+                Value::String(name) => TYPES.contains(&name.as_str()),
+                Value::Array(names) => names
+                    .iter()
+                    .all(|name| name.as_str().is_some_and(|name| TYPES.contains(&name))),
+                _ => false,
+            },
+            "enum" => value.is_array(),
+            "const" => true,
+            "required" => value
+                .as_array()
+                .is_some_and(|names| names.iter().all(Value::is_string)),
+            "minItems" | "maxItems" | "minLength" | "maxLength" => value.is_u64(),
+            "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" => value.is_number(),
+            "properties" => {
+                let Some(properties) = value.as_object() else {
+                    return Err(format!("{}: must be an object", keyword_path));
+                };
+                for (name, property) in properties {
+                    check_schema(property, &child(&keyword_path, name))?;
+                }
+                true
+            }
+            "additionalProperties" | "items" => {
+                check_schema(value, &keyword_path)?;
+                true
+            }
+            keyword if ANNOTATIONS.contains(&keyword) => true,
+            _ => return Err(format!("{}: unsupported keyword", keyword_path)),
+        };
+        if !valid {
+            return Err(format!("{}: invalid value {}", keyword_path, value));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate(schema: &Value, value: &Value, path: &str) -> Result<(), InvalidPayload> {
+    let invalid = |message: String| InvalidPayload {
+        path: path.to_string(),
+        message,
+    };
+    let schema = match schema {
+        Value::Bool(false) => return Err(invalid("no value is allowed here".to_string())),
+        Value::Object(schema) => schema,
+        _ => return Ok(()),
+    };
+
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+        return Err(invalid(format!(
+            "expected {}, found {}",
+            types.join(" or "),
+            type_name(value)
+        )));
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.iter().any(|allowed| json_equal(allowed, value)) {
+            return Err(invalid(format!(
+                "{} is not one of {}",
+                value,
+                Value::Array(allowed.clone())
+            )));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if !json_equal(value, expected) {
+            return Err(invalid(format!("expected {}, found {}", expected, value)));
+        }
+    }
+
+    match value {
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or(f64::NAN);
+            let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+            if let Some(minimum) = bound("minimum").filter(|minimum| number < *minimum) {
+                return Err(invalid(format!(
+                    "{} is below the minimum of {}",
+                    value, minimum
+                )));
+            }
+            if let Some(maximum) = bound("maximum").filter(|maximum| number > *maximum) {
+                return Err(invalid(format!(
+                    "{} is above the maximum of {}",
+                    value, maximum
+                )));
+            }
+            if let Some(minimum) = bound("exclusiveMinimum").filter(|minimum| number <= *minimum) {
+                return Err(invalid(format!(
+                    "{} must be greater than {}",
+                    value, minimum
+                )));
+            }
+            if let Some(maximum) = bound("exclusiveMaximum").filter(|maximum| number >= *maximum) {
+                return Err(invalid(format!("{} must be less than {}", value, maximum)));
+            }
+        }
+        Value::String(string) => {
+            let length = string.chars().count() as u64;
+            check_size(schema, "minLength", "maxLength", length, "characters").map_err(invalid)?;
+        }
+        Value::Array(items) => {
+            check_size(schema, "minItems", "maxItems", items.len() as u64, "items")
+                .map_err(invalid)?;
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate(item_schema, item, &child(path, &index.to_string()))?;
+                }
+            }
+        }
+        Value::Object(object) => validate_object(schema, object, path)?,
+        Value::Null | Value::Bool(_) => {}
+    }
+
+    Ok(())
+}
+
+fn validate_object(
+    schema: &Map<String, Value>,
+    object: &Map<String, Value>,
+    path: &str,
+) -> Result<(), InvalidPayload> {
+    let empty = Map::new();
+    let properties = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(name) {
+                return Err(InvalidPayload {
+                    path: child(path, name),
+                    message: "required property is missing".to_string(),
+                });
+            }
+        }
+    }
+
+    for (name, value) in object {
+        let property_path = child(path, name);
+        match properties.get(name) {
+            Some(property) => validate(property, value, &property_path)?,
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    return Err(InvalidPayload {
+                        path: property_path,
+                        message: "property is not allowed".to_string(),
+                    })
+                }
+                Some(additional) => validate(additional, value, &property_path)?,
+                None => {}
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// Check `size` against the `min` and `max` keywords of `schema`
+fn check_size(
+    schema: &Map<String, Value>,
+    min: &str,
+    max: &str,
+    size: u64,
+    unit: &str,
+) -> Result<(), String> {
+    if let Some(min) = schema
+        .get(min)
+        .and_then(Value::as_u64)
+        .filter(|min| size < *min)
+    {
+        return Err(format!(
+            "has {} {}, fewer than the minimum of {}",
+            size, unit, min
+        ));
+    }
+    if let Some(max) = schema
+        .get(max)
+        .and_then(Value::as_u64)
+        .filter(|max| size > *max)
+    {
+        return Err(format!(
+            "has {} {}, more than the maximum of {}",
+            size, unit, max
+        ));
+    }
+    Ok(())
+}
+
+/// Equality of JSON values as JSON Schema defines it: numbers are equal when
+/// their values are, whether written as integers or not
+fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => match (integer(a), integer(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => a.as_f64() == b.as_f64(),
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_equal(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(name, a)| b.get(name).is_some_and(|b| json_equal(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+/// `number` exactly, when it is written as an integer
+fn integer(number: &serde_json::Number) -> Option<i128> {
+    number
+        .as_i64()
+        .map(i128::from)
+        .or_else(|| number.as_u64().map(i128::from))
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => value.as_f64().is_some_and(|number| number.fract() == 0.0),
+        "number" => value.is_number(),
+        _ => type_name(value) == name,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// `path` followed by the JSON Pointer reference token of `name`
+fn child(path: &str, name: &str) -> String {
+    format!("{}/{}", path, name.replace('~', "~0").replace('/', "~1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn task_schema() -> Value {
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "required": ["tasks"],
+            "properties": {
+                "tasks": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "required": ["name"],
+                        "additionalProperties": false,
+                        "properties": {
+                            "name": { "type": "string", "minLength": 1 },
+                            "attempts": { "type": "integer", "minimum": 1, "maximum": 5 },
+                            "mode": { "enum": ["fast", "full"] }
+                        }
+                    }
+                },
+                "seed": { "type": ["integer", "null"] }
+            }
+        })
+    }
+
+    fn error_of(payload: Value) -> (String, String) {
+        let err = validate_payload(&task_schema(), &payload).unwrap_err();
+        (err.path, err.message)
+    }
+
+    #[test]
+    fn test_matching_payload() {
+        let schema = task_schema();
+        assert_eq!(check_payload_schema(&schema), Ok(()));
+        let payload = json!({
+            "tasks": [{ "name": "build", "attempts": 3, "mode": "fast" }, { "name": "test" }],
+            "seed": null,
+            "extra": "allowed at the top level"
+        });
+        assert_eq!(validate_payload(&schema, &payload), Ok(()));
+        assert_eq!(validate_payload(&json!({}), &payload), Ok(()));
+        assert_eq!(validate_payload(&json!(true), &payload), Ok(()));
+    }
+
+    #[test]
+    fn test_errors_point_at_the_offending_value() {
+        assert_eq!(
+            error_of(json!([])),
+            ("payload".into(), "expected object, found array".into())
+        );
+        assert_eq!(
+            error_of(json!({ "seed": 1 })),
+            (
+                "payload/tasks".into(),
+                "required property is missing".into()
+            )
+        );
+        assert_eq!(
+            error_of(json!({ "tasks": [] })),
+            (
+                "payload/tasks".into(),
+                "has 0 items, fewer than the minimum of 1".into()
+            )
+        );
+        assert_eq!(
+            error_of(json!({ "tasks": [{ "name": "build" }, { "name": 7 }] })),
+            (
+                "payload/tasks/1/name".into(),
+                "expected string, found number".into()
+            )
+        );
+        assert_eq!(
+            error_of(json!({ "tasks": [{ "name": "build", "attempts": 2.5 }] })),
+            (
+                "payload/tasks/0/attempts".into(),
+                "expected integer, found number".into()
+            )
+        );
+        assert_eq!(
+            error_of(json!({ "tasks": [{ "name": "build", "attempts": 9 }] })),
+            (
+                "payload/tasks/0/attempts".into(),
+                "9 is above the maximum of 5".into()
+            )
+        );
+        assert_eq!(
+            error_of(json!({ "tasks": [{ "name": "build", "mode": "slow" }] })),
+            (
+                "payload/tasks/0/mode".into(),
+                r#""slow" is not one of ["fast","full"]"#.into()
+            )
+        );
+        assert_eq!(
+            error_of(json!({ "tasks": [{ "name": "build", "a/b": 1 }] })),
+            (
+                "payload/tasks/0/a~1b".into(),
+                "property is not allowed".into()
+            )
+        );
+        assert_eq!(
+            error_of(json!({ "tasks": [{ "name": "build" }], "seed": "42" })),
+            (
+                "payload/seed".into(),
+                "expected integer or null, found string".into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_enum_and_const_compare_numbers_by_value() {
+        let schema = json!({
+            "properties": {
+                "level": { "enum": [1, 2.5, "max"] },
+                "version": { "const": 2 },
+                "weights": { "const": { "a": [1, 0.5] } }
+            }
+        });
+        let validate = |payload: Value| validate_payload(&schema, &payload);
+
+        assert_eq!(validate(json!({ "level": 1.0, "version": 2.0 })), Ok(()));
+        assert_eq!(validate(json!({ "level": 2.5, "version": 2 })), Ok(()));
+        assert_eq!(validate(json!({ "weights": { "a": [1.0, 0.5] } })), Ok(()));
+        assert_eq!(
+            validate(json!({ "level": 1.5 })).unwrap_err().message,
+            r#"1.5 is not one of [1,2.5,"max"]"#
+        );
+        assert_eq!(
+            validate(json!({ "version": 2.1 })).unwrap_err().message,
+            "expected 2, found 2.1"
+        );
+        assert!(validate(json!({ "level": "1" })).is_err());
+        assert!(validate(json!({ "weights": { "a": [1, 0.5, 0] } })).is_err());
+        // Integers too large for a float are compared exactly
+        let schema = json!({ "const": u64::MAX });
+        assert!(validate_payload(&schema, &json!(u64::MAX - 1)).is_err());
+        assert_eq!(validate_payload(&schema, &json!(u64::MAX)), Ok(()));
+    }
+
+    #[test]
+    fn test_malformed_schemas_are_rejected() {
+        assert_eq!(
+            check_payload_schema(&json!("object")),
+            Err("schema: a schema must be an object or a boolean".to_string())
+        );
+        assert_eq!(
+            check_payload_schema(&json!({ "properties": { "seed": { "type": "int" } } })),
+            Err(r#"schema/properties/seed/type: invalid value "int""#.to_string())
+        );
+        assert_eq!(
+            check_payload_schema(&json!({ "items": { "minItems": -1 } })),
+            Err("schema/items/minItems: invalid value -1".to_string())
+        );
+        assert!(check_payload_schema(&json!({ "required": "name" })).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_keywords_are_rejected() {
+        assert_eq!(
+            check_payload_schema(&json!({
+                "properties": { "name": { "type": "string", "pattern": "^[a-z]+$" } }
+            })),
+            Err("schema/properties/name/pattern: unsupported keyword".to_string())
+        );
+        assert_eq!(
+            check_payload_schema(&json!({ "oneOf": [{ "type": "string" }] })),
+            Err("schema/oneOf: unsupported keyword".to_string())
+        );
+        assert_eq!(
+            check_payload_schema(&json!({ "items": { "maxItem": 3 } })),
+            Err("schema/items/maxItem: unsupported keyword".to_string())
+        );
+        // Annotations constrain nothing but are allowed
+        assert_eq!(
+            check_payload_schema(&json!({
+                "$id": "https://example.com/task.json",
+                "title": "Task",
+                "properties": {
+                    "mode": { "const": "fast", "description": "Run mode", "default": "fast" }
+                }
+            })),
+            Ok(())
+        );
+    }
+}
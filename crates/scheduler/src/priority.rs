@@ -0,0 +1,175 @@
+//! Effective job priority: the base priority raised by aging and approaching deadlines
+//!
+//! A pending job gains one level per `priority_aging_secs` it has waited, up to
+//! `max_aging_boost` levels, so that a steady stream of high-priority jobs cannot
+//! starve low-priority ones. Over the `deadline_window_secs` before its deadline
+//! a job additionally ramps up to a boost that puts it ahead of every job without
+//! a deadline.
+
+use chrono::{DateTime, Utc};
+use platform_api_models::*;
+
+use crate::{service::SchedulerService, types::SchedulerConfig};
+
+/// Level of a base priority, before any boost
+pub fn priority_level(priority: &JobPriority) -> f64 {
+    match priority {
+        JobPriority::Low => 0.0,
+        JobPriority::Normal => 1.0,
+        JobPriority::High => 2.0,
+        JobPriority::Critical => 3.0,
+    }
+}
+
+/// Levels a job gains per unit of validator trust above neutral
+///
+/// Positive for high priorities and negative for low ones, so trusted
+/// validators are routed high-priority jobs and untrusted ones low-priority jobs.
+pub fn trust_shift(priority: &JobPriority) -> f64 {
+    match priority {
+        JobPriority::Low => -1.5,
+        JobPriority::Normal => -0.5,
+        JobPriority::High => 0.5,
+        JobPriority::Critical => 1.5,
+    }
+}
+
+impl SchedulerConfig {
+    /// Levels gained per second spent pending
+    pub fn aging_rate(&self) -> f64 {
+        if self.priority_aging_secs == 0 {
+            0.0
+        } else {
+            1.0 / self.priority_aging_secs as f64
+        }
+    }
+
+    /// Boost of a job whose deadline has been reached
+    pub fn deadline_boost(&self) -> f64 {
+        priority_level(&JobPriority::Critical) + self.max_aging_boost + 1.0
+    }
+
+    /// Effective priority of `job` at `now`
+    ///
+    /// Mirrors the ordering used by `claim_job` in the database.
+    pub fn effective_priority(&self, job: &JobMetadata, now: DateTime<Utc>) -> f64 {
+        let waited_secs = (now - job.created_at).num_milliseconds().max(0) as f64 / 1000.0;
+        let aging = (waited_secs * self.aging_rate()).min(self.max_aging_boost);
+
+        let urgency = job
+            .deadline
+            .map(|deadline| {
+                let remaining_secs = (deadline - now).num_milliseconds() as f64 / 1000.0;
+                let window_secs = self.deadline_window_secs.max(1) as f64;
+                (1.0 - remaining_secs / window_secs).clamp(0.0, 1.0)
+            })
+            .unwrap_or(0.0);
+
+        priority_level(&job.priority) + aging + urgency * self.deadline_boost()
+    }
+}
+
+impl SchedulerService {
+    /// Set the effective priority of the pending jobs, for observability
+    pub(crate) async fn set_effective_priorities(&self, jobs: &mut [JobMetadata]) {
+        let config = self.config().await;
+        let now = Utc::now();
+        for job in jobs.iter_mut().filter(|j| j.status == JobStatus::Pending) {
+            job.effective_priority = Some(config.effective_priority(job, now));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    fn job(priority: JobPriority, created_at: DateTime<Utc>) -> JobMetadata {
+        JobMetadata {
+            id: Id::from(Uuid::new_v4()),
+            challenge_id: Id::from(Uuid::new_v4()),
+            validator_hotkey: None,
+            status: JobStatus::Pending,
+            priority,
+            runtime: RuntimeType::Docker,
+            created_at,
+            claimed_at: None,
+            started_at: None,
+            completed_at: None,
+            timeout_at: None,
+            timeout: None,
+            retry_count: 0,
+            max_retries: 3,
+            payload: None,
+            failure_category: None,
+            depends_on: vec![],
+            deadline: None,
+            effective_priority: None,
+            version: 0,
+            required_capabilities: vec![],
+            resources: Default::default(),
+            schema_version: JOB_METADATA_SCHEMA_VERSION,
+        }
+    }
+
+    #[test]
+    fn test_aged_low_priority_outranks_fresh_high_priority() {
+        let config = SchedulerConfig::default();
+        let now = Utc::now();
+
+        let fresh_high = job(JobPriority::High, now);
+        let fresh_critical = job(JobPriority::Critical, now);
+        let low = |waited_secs: i64| job(JobPriority::Low, now - Duration::seconds(waited_secs));
+
+        let interval = config.priority_aging_secs as i64;
+        let high = config.effective_priority(&fresh_high, now);
+        assert!(config.effective_priority(&low(interval), now) < high);
+        assert!(config.effective_priority(&low(3 * interval), now) > high);
+
+        // Aging is capped, so an old job only ties with the highest base priority
+        let ancient = config.effective_priority(&low(1_000 * interval), now);
+        assert_eq!(ancient, config.max_aging_boost);
+        assert!(ancient <= config.effective_priority(&fresh_critical, now));
+    }
+
+    #[test]
+    fn test_aging_disabled() {
+        let config = SchedulerConfig {
+            priority_aging_secs: 0,
+            ..SchedulerConfig::default()
+        };
+        let now = Utc::now();
+        let old_low = job(JobPriority::Low, now - Duration::days(30));
+        assert_eq!(config.effective_priority(&old_low, now), 0.0);
+    }
+
+    #[test]
+    fn test_deadline_moves_job_to_front() {
+        let config = SchedulerConfig::default();
+        let now = Utc::now();
+        let window = Duration::seconds(config.deadline_window_secs as i64);
+
+        let distant = JobMetadata {
+            deadline: Some(now + window * 2),
+            ..job(JobPriority::Low, now)
+        };
+        let nearing = JobMetadata {
+            deadline: Some(now + window / 2),
+            ..job(JobPriority::Low, now)
+        };
+        let due = JobMetadata {
+            deadline: Some(now),
+            ..job(JobPriority::Low, now)
+        };
+        let aged_critical = job(JobPriority::Critical, now - Duration::days(1));
+
+        assert_eq!(config.effective_priority(&distant, now), 0.0);
+        let fresh_high = config.effective_priority(&job(JobPriority::High, now), now);
+        assert!(config.effective_priority(&nearing, now) > fresh_high);
+        assert!(
+            config.effective_priority(&due, now) > config.effective_priority(&aged_critical, now)
+        );
+    }
+}
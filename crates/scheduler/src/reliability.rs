@@ -0,0 +1,209 @@
+//! Validator reliability: how often each validator completes the jobs it claims
+//!
+//! Every job a validator completes or fails, timeouts included, is counted
+//! once in `validator_reliability`, so the record survives restarts. Counts lose half
+//! their weight every `SchedulerConfig::reliability_half_life_secs`, so old
+//! failures fade. The resulting success rate is given to scorers in
+//! `ValidatorInfo::success_rate` and down-weights the trust of validators that
+//! fail often, see `ValidatorInfo::effective_trust`.
+
+use anyhow::{Context, Result};
+use platform_api_models::{JobStatus, PlatformResult};
+use chrono::{DateTime, Utc};
+use tracing::warn;
+
+use crate::service::SchedulerService;
+
+/// Success rate of a validator without any recorded job
+pub const NEUTRAL_SUCCESS_RATE: f64 = 0.5;
+
+/// Decayed job counts of a validator
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorReliability {
+    pub completed_jobs: f64,
+    pub failed_jobs: f64,
+    /// Time the counts were decayed to
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ValidatorReliability {
+    /// No recorded job as of `now`
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            completed_jobs: 0.0,
+            failed_jobs: 0.0,
+            updated_at: now,
+        }
+    }
+
+    /// Counts decayed from `updated_at` to `now`; a half-life of 0 disables decay
+    pub fn decayed(&self, now: DateTime<Utc>, half_life_secs: u64) -> Self {
+        let factor = decay_factor(self.updated_at, now, half_life_secs);
+        Self {
+            completed_jobs: self.completed_jobs * factor,
+            failed_jobs: self.failed_jobs * factor,
+            updated_at: now.max(self.updated_at),
+        }
+    }
+
+    /// Count a job finished at `now`
+    pub fn record(&mut self, completed: bool, now: DateTime<Utc>, half_life_secs: u64) {
+        *self = self.decayed(now, half_life_secs);
+        if completed {
+            self.completed_jobs += 1.0;
+        } else {
+            self.failed_jobs += 1.0;
+        }
+    }
+
+    /// Share of finished jobs that completed
+    ///
+    /// Smoothed like `ValidatorPerformance::success_rate`, so a validator with
+    /// few jobs stays close to [`NEUTRAL_SUCCESS_RATE`].
+    pub fn success_rate(&self) -> f64 {
+        (self.completed_jobs + 1.0) / (self.completed_jobs + self.failed_jobs + 2.0)
+    }
+}
+
+fn decay_factor(from: DateTime<Utc>, to: DateTime<Utc>, half_life_secs: u64) -> f64 {
+    if half_life_secs == 0 {
+        return 1.0;
+    }
+    let elapsed_secs = (to - from).num_milliseconds().max(0) as f64 / 1000.0;
+    0.5_f64.powf(elapsed_secs / half_life_secs as f64)
+}
+
+impl SchedulerService {
+    /// Reliability of `hotkey`, decayed to now
+    pub async fn validator_reliability(&self, hotkey: &str) -> PlatformResult<ValidatorReliability> {
+        let now = Utc::now();
+        let half_life_secs = self.config().await.reliability_half_life_secs;
+
+        let stored = if let Some(pool) = &self.database_pool {
+            sqlx::query_as::<_, (f64, f64, DateTime<Utc>)>(
+                r#"
+                SELECT completed_jobs, failed_jobs, updated_at
+                FROM validator_reliability
+                WHERE validator_hotkey = $1
+                "#,
+            )
+            .bind(hotkey)
+            .fetch_optional(pool.as_ref())
+            .await
+            .context("Failed to read validator reliability")?
+            .map(
+                |(completed_jobs, failed_jobs, updated_at)| ValidatorReliability {
+                    completed_jobs,
+                    failed_jobs,
+                    updated_at,
+                },
+            )
+        } else {
+            self.validator_reliability.read().await.get(hotkey).cloned()
+        };
+
+        Ok(stored
+            .map(|reliability| reliability.decayed(now, half_life_secs))
+            .unwrap_or_else(|| ValidatorReliability::new(now)))
+    }
+
+    /// Count a job `hotkey` completed or failed, once it left `old_status`
+    ///
+    /// Only jobs leaving a claim are counted, so that a result or failure
+    /// reported again for a job that already finished does not count twice.
+    /// The job has changed by then, so failing to count it is only logged.
+    pub(crate) async fn record_validator_outcome(
+        &self,
+        hotkey: &str,
+        old_status: &JobStatus,
+        completed: bool,
+    ) {
+        if !matches!(old_status, JobStatus::Claimed | JobStatus::Running) {
+            return;
+        }
+        if let Err(e) = self.count_validator_outcome(hotkey, completed).await {
+            warn!(
+                validator_hotkey = hotkey,
+                "Failed to record validator outcome: {:#}", e
+            );
+        }
+    }
+
+    async fn count_validator_outcome(&self, hotkey: &str, completed: bool) -> Result<()> {
+        let now = Utc::now();
+        let half_life_secs = self.config().await.reliability_half_life_secs;
+
+        if let Some(pool) = &self.database_pool {
+            // Decayed and incremented in one statement, so concurrent outcomes all count
+            sqlx::query(
+                r#"
+                INSERT INTO validator_reliability (validator_hotkey, completed_jobs, failed_jobs, updated_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (validator_hotkey) DO UPDATE
+                SET completed_jobs = validator_reliability.completed_jobs
+                        * COALESCE(POWER(0.5, GREATEST(EXTRACT(EPOCH FROM
+                              (EXCLUDED.updated_at - validator_reliability.updated_at))::DOUBLE PRECISION, 0)
+                              / NULLIF($5, 0)), 1.0)
+                        + EXCLUDED.completed_jobs,
+                    failed_jobs = validator_reliability.failed_jobs
+                        * COALESCE(POWER(0.5, GREATEST(EXTRACT(EPOCH FROM
+                              (EXCLUDED.updated_at - validator_reliability.updated_at))::DOUBLE PRECISION, 0)
+                              / NULLIF($5, 0)), 1.0)
+                        + EXCLUDED.failed_jobs,
+                    updated_at = GREATEST(validator_reliability.updated_at, EXCLUDED.updated_at)
+                "#,
+            )
+            .bind(hotkey)
+            .bind(if completed { 1.0 } else { 0.0 })
+            .bind(if completed { 0.0 } else { 1.0 })
+            .bind(now)
+            .bind(half_life_secs as f64)
+            .execute(pool.as_ref())
+            .await?;
+        } else {
+            self.validator_reliability
+                .write()
+                .await
+                .entry(hotkey.to_string())
+                .or_insert_with(|| ValidatorReliability::new(now))
+                .record(completed, now, half_life_secs);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const HALF_LIFE_SECS: u64 = 3600;
+
+    #[test]
+    fn test_old_failures_fade() {
+        let start = Utc::now();
+        let mut reliability = ValidatorReliability::new(start);
+        assert_eq!(reliability.success_rate(), NEUTRAL_SUCCESS_RATE);
+
+        for _ in 0..8 {
+            reliability.record(false, start, HALF_LIFE_SECS);
+        }
+        assert_eq!(reliability.success_rate(), 0.1);
+
+        // One half-life later the failures weigh half as much
+        let later = start + Duration::seconds(HALF_LIFE_SECS as i64);
+        reliability.record(true, later, HALF_LIFE_SECS);
+        assert_eq!(reliability.failed_jobs, 4.0);
+        assert_eq!(reliability.success_rate(), 2.0 / 7.0);
+
+        // Long after, the validator is back to neutral
+        let much_later = later + Duration::days(30);
+        let faded = reliability.decayed(much_later, HALF_LIFE_SECS);
+        assert!((faded.success_rate() - NEUTRAL_SUCCESS_RATE).abs() < 1e-6);
+        assert_eq!(faded.updated_at, much_later);
+
+        // Without a half-life nothing fades
+        assert_eq!(reliability.decayed(much_later, 0).failed_jobs, 4.0);
+    }
+}
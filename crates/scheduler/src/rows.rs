@@ -20,37 +20,40 @@ pub struct JobRow {
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub timeout_at: Option<DateTime<Utc>>,
+    pub timeout_secs: Option<i64>,
     pub retry_count: i32,
     pub max_retries: i32,
     pub payload: Option<JsonValue>,
+    pub failure_category: Option<String>,
+    pub depends_on: Vec<Uuid>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub version: i64,
+    pub required_capabilities: Vec<String>,
+    pub resources: JsonValue,
 }
 
 impl From<JobRow> for JobMetadata {
     fn from(row: JobRow) -> Self {
         let status = match row.status.as_str() {
+            "blocked" => JobStatus::Blocked,
             "pending" => JobStatus::Pending,
             "claimed" => JobStatus::Claimed,
             "running" => JobStatus::Running,
             "completed" => JobStatus::Completed,
             "failed" => JobStatus::Failed,
             "timeout" => JobStatus::Timeout,
+            "dead_lettered" => JobStatus::DeadLettered,
             _ => JobStatus::Pending,
         };
 
-        let priority = match row.priority.as_str() {
-            "low" => JobPriority::Low,
-            "normal" => JobPriority::Normal,
-            "high" => JobPriority::High,
-            "critical" => JobPriority::Critical,
-            _ => JobPriority::Normal,
-        };
+        let priority = JobPriority::from(row.priority.as_str());
 
         let runtime = RuntimeType::from(row.runtime.as_str());
 
         JobMetadata {
             id: Id::from(row.id),
             challenge_id: Id::from(row.challenge_id),
-            validator_hotkey: row.validator_hotkey.map(Hotkey::from),
+            validator_hotkey: row.validator_hotkey,
             status,
             priority,
             runtime,
@@ -59,9 +62,72 @@ impl From<JobRow> for JobMetadata {
             started_at: row.started_at,
             completed_at: row.completed_at,
             timeout_at: row.timeout_at,
+            timeout: row.timeout_secs.map(|secs| secs as u64),
             retry_count: row.retry_count as u32,
             max_retries: row.max_retries as u32,
             payload: row.payload,
+            failure_category: row
+                .failure_category
+                .as_deref()
+                .map(FailureCategory::from),
+            depends_on: row.depends_on,
+            deadline: row.deadline,
+            effective_priority: None,
+            version: row.version as u64,
+            required_capabilities: row.required_capabilities,
+            resources: serde_json::from_value(row.resources).unwrap_or_default(),
+            schema_version: JOB_METADATA_SCHEMA_VERSION,
+        }
+    }
+}
+
+/// Database row for a dead-lettered job
+#[derive(Debug, FromRow)]
+pub struct DeadLetteredJobRow {
+    #[sqlx(flatten)]
+    pub job: JobRow,
+    pub error_message: Option<String>,
+    pub retry_history: JsonValue,
+    pub dead_lettered_at: Option<DateTime<Utc>>,
+}
+
+impl From<DeadLetteredJobRow> for DeadLetteredJob {
+    fn from(row: DeadLetteredJobRow) -> Self {
+        DeadLetteredJob {
+            job: row.job.into(),
+            reason: row.error_message,
+            retry_history: serde_json::from_value(row.retry_history).unwrap_or_default(),
+            dead_lettered_at: row.dead_lettered_at,
+        }
+    }
+}
+
+/// Database row of a registered validator, without its hotkey
+#[derive(Debug, FromRow)]
+pub struct ValidatorRegistrationRow {
+    pub runtimes: Vec<String>,
+    pub gpu: bool,
+    pub memory_gb: i64,
+    pub cpu_cores: i32,
+    pub preferred_challenges: Vec<Uuid>,
+    pub gpu_count: i32,
+    pub gpu_type: Option<String>,
+    pub disk_gb: Option<i64>,
+}
+
+impl ValidatorRegistrationRow {
+    /// Registration of validator `hotkey`
+    pub fn into_registration(self, hotkey: Hotkey) -> ValidatorRegistration {
+        ValidatorRegistration {
+            hotkey,
+            runtimes: self.runtimes.iter().map(|r| r.as_str().into()).collect(),
+            gpu: self.gpu,
+            memory_gb: self.memory_gb.max(0) as u64,
+            cpu_cores: self.cpu_cores.max(0) as u32,
+            preferred_challenges: self.preferred_challenges,
+            gpu_count: self.gpu_count.max(0) as u32,
+            gpu_type: self.gpu_type,
+            disk_gb: self.disk_gb.map(|disk| disk.max(0) as u64),
         }
     }
 }
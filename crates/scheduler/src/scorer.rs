@@ -0,0 +1,360 @@
+//! Pluggable scoring of pending jobs for the validator claiming one
+//!
+//! When a validator claims a job, every candidate is scored by the scorer of
+//! its challenge and the highest score wins. Challenges pick a scorer by name
+//! in `SchedulerConfig::challenge_scorers`; scorers are registered with
+//! `SchedulerService::with_scorer`. Challenges without one use
+//! [`PriorityScorer`], the effective priority shifted by validator trust.
+//! Validators that often fail or time out are trusted less, see
+//! [`ValidatorInfo::effective_trust`].
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use platform_api_models::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    priority::trust_shift,
+    reliability::NEUTRAL_SUCCESS_RATE,
+    service::SchedulerService,
+    trust::{ValidatorPerformance, NEUTRAL_TRUST},
+    types::SchedulerConfig,
+};
+
+/// Name under which challenges may select the default scorer explicitly
+pub const DEFAULT_SCORER: &str = "priority";
+
+/// Pending jobs scored per claim when some challenge has its own scorer
+pub const SCORED_CANDIDATES: usize = 32;
+
+/// Job history window of the performance given to custom scorers
+pub const SCORER_PERFORMANCE_WINDOW_DAYS: u32 = 7;
+
+/// What is known of the validator claiming a job
+#[derive(Debug, Clone)]
+pub struct ValidatorInfo {
+    pub hotkey: Hotkey,
+    /// Latest trust score, in [0, 1]
+    pub trust: f64,
+    /// Stake relative to the largest stake on the subnet
+    pub stake_share: f64,
+    /// Jobs the validator reported it can run at once
+    pub capacity: Option<u32>,
+    /// Share of its jobs the validator completed, with old jobs fading, see
+    /// `SchedulerService::validator_reliability`
+    pub success_rate: f64,
+    /// Recent job history, loaded only when a challenge has its own scorer
+    /// and the scheduler has a database
+    pub performance: Option<ValidatorPerformance>,
+    /// Resources the validator registered with; jobs requiring more are left
+    /// to other validators, see `ResourceRequirements::satisfied_by`
+    pub registration: Option<ValidatorRegistration>,
+}
+
+impl ValidatorInfo {
+    /// Validator with neutral trust and no stake, history, capacity or
+    /// registration
+    pub fn new(hotkey: Hotkey) -> Self {
+        Self {
+            hotkey,
+            trust: NEUTRAL_TRUST,
+            stake_share: 0.0,
+            capacity: None,
+            success_rate: NEUTRAL_SUCCESS_RATE,
+            performance: None,
+            registration: None,
+        }
+    }
+
+    /// Whether the validator registered the resources `job` requires;
+    /// validators that never registered are given any job
+    pub fn has_resources_for(&self, job: &JobMetadata) -> bool {
+        self.registration
+            .as_ref()
+            .is_none_or(|registration| job.resources.satisfied_by(registration))
+    }
+
+    /// Trust, scaled down in proportion for validators completing fewer of
+    /// their jobs than a new validator would
+    pub fn effective_trust(&self) -> f64 {
+        self.trust * (self.success_rate / NEUTRAL_SUCCESS_RATE).min(1.0)
+    }
+}
+
+/// Ranks a pending job for a validator; the job scoring highest is claimed
+///
+/// Scores of jobs of different challenges are compared with each other, so
+/// custom scorers should stay on the scale of [`PriorityScorer`]: priority
+/// levels, from 0 to about 10.
+pub trait Scorer: Send + Sync {
+    fn score(&self, job: &JobMetadata, validator: &ValidatorInfo) -> f64;
+}
+
+/// Default scorer: effective priority, plus a shift by effective validator
+/// trust that routes high-priority jobs to trusted validators and low-priority
+/// jobs to untrusted ones
+///
+/// Mirrors the ordering of `claim_job` in the database.
+pub struct PriorityScorer<'a> {
+    config: &'a SchedulerConfig,
+    now: DateTime<Utc>,
+}
+
+impl<'a> PriorityScorer<'a> {
+    pub fn new(config: &'a SchedulerConfig, now: DateTime<Utc>) -> Self {
+        Self { config, now }
+    }
+}
+
+impl Scorer for PriorityScorer<'_> {
+    fn score(&self, job: &JobMetadata, validator: &ValidatorInfo) -> f64 {
+        self.config.effective_priority(job, self.now)
+            + trust_shift(&job.priority) * (validator.effective_trust() - NEUTRAL_TRUST)
+    }
+}
+
+impl SchedulerService {
+    /// Register `scorer` under `name`, for challenges that select it in
+    /// `SchedulerConfig::challenge_scorers`
+    pub fn with_scorer(mut self, name: &str, scorer: impl Scorer + 'static) -> Self {
+        self.scorers.insert(name.to_string(), Arc::new(scorer));
+        self
+    }
+
+    /// Trust, stake, capacity and success rate of `hotkey`, and its job history
+    /// if `with_performance`
+    pub async fn validator_info(
+        &self,
+        hotkey: &Hotkey,
+        with_performance: bool,
+    ) -> PlatformResult<ValidatorInfo> {
+        let key = hotkey.to_string();
+        let performance = match (with_performance, self.trust_score()) {
+            (true, Some(trust)) => Some(
+                trust
+                    .performance(&key, SCORER_PERFORMANCE_WINDOW_DAYS)
+                    .await?,
+            ),
+            _ => None,
+        };
+
+        Ok(ValidatorInfo {
+            hotkey: hotkey.clone(),
+            trust: self.validator_trust(&key).await?,
+            stake_share: self
+                .validator_stakes
+                .read()
+                .await
+                .get(&key)
+                .copied()
+                .unwrap_or(0.0),
+            capacity: self.validator_capacity(&key).await,
+            success_rate: self.validator_reliability(&key).await?.success_rate(),
+            performance,
+            registration: self.validator_registration(hotkey).await?,
+        })
+    }
+
+    /// Index of the candidate scoring highest for `validator`; ties go to the
+    /// oldest job
+    pub(crate) fn best_candidate(
+        &self,
+        config: &SchedulerConfig,
+        candidates: &[&JobMetadata],
+        validator: &ValidatorInfo,
+        now: DateTime<Utc>,
+    ) -> Option<usize> {
+        let default = PriorityScorer::new(config, now);
+        let scored = candidates.iter().map(|job| {
+            let scorer: &dyn Scorer = match config.challenge_scorers.get(&job.challenge_id) {
+                None => &default,
+                Some(name) if name == DEFAULT_SCORER => &default,
+                Some(name) => match self.scorers.get(name) {
+                    Some(scorer) => scorer.as_ref(),
+                    None => {
+                        warn!(challenge_id = %job.challenge_id, scorer = %name, "Unknown scorer, using the default one");
+                        &default
+                    }
+                },
+            };
+            scorer.score(job, validator)
+        });
+
+        scored
+            .enumerate()
+            .max_by(|(a, score_a), (b, score_b)| {
+                score_a
+                    .total_cmp(score_b)
+                    .then(candidates[*b].created_at.cmp(&candidates[*a].created_at))
+            })
+            .map(|(index, _)| index)
+    }
+}
+
+/// Parse a `<challenge_id>=<scorer>` comma-separated list of challenge scorers
+pub fn parse_challenge_scorers(spec: &str) -> Result<HashMap<Id, String>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (challenge_id, scorer) = entry.split_once('=').ok_or_else(|| {
+                anyhow::anyhow!("Expected <challenge_id>=<scorer>, got '{}'", entry)
+            })?;
+            Ok((
+                Uuid::parse_str(challenge_id.trim())?,
+                scorer.trim().to_string(),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CreateJobRequest;
+
+    /// Prefers, for validators holding most of the stake, jobs whose payload asks for it
+    struct StakeScorer;
+
+    impl Scorer for StakeScorer {
+        fn score(&self, job: &JobMetadata, validator: &ValidatorInfo) -> f64 {
+            let wants_stake = job
+                .payload
+                .as_ref()
+                .is_some_and(|p| p["needs_stake"] == true);
+            if wants_stake && validator.stake_share > 0.5 {
+                10.0
+            } else {
+                0.0
+            }
+        }
+    }
+
+    fn request(
+        challenge_id: Uuid,
+        priority: JobPriority,
+        payload: serde_json::Value,
+    ) -> CreateJobRequest {
+        CreateJobRequest {
+            challenge_id,
+            payload,
+            priority: Some(priority),
+            runtime: Some(RuntimeType::Docker),
+            timeout: None,
+            max_retries: None,
+            job_id: None,
+            depends_on: vec![],
+            deadline: None,
+            required_capabilities: None,
+            resources: None,
+        }
+    }
+
+    fn claim(hotkey: &Hotkey) -> ClaimJobRequest {
+        ClaimJobRequest {
+            validator_hotkey: hotkey.clone(),
+            runtime: RuntimeType::Docker,
+            capabilities: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_challenge_scorer_routes_jobs_by_validator() {
+        let (plain, staked) = (Uuid::new_v4(), Uuid::new_v4());
+        let config = SchedulerConfig {
+            challenge_scorers: HashMap::from([(staked, "stake".to_string())]),
+            ..SchedulerConfig::default()
+        };
+
+        // The stake scorer lifts the low-priority job above the normal one for the whale only
+        let whale = Hotkey::from_public_key(&[1; 32]);
+        let shrimp = Hotkey::from_public_key(&[2; 32]);
+        for (hotkey, expects_staked) in [(&whale, true), (&shrimp, false)] {
+            let scheduler = SchedulerService::new(&config)
+                .unwrap()
+                .with_scorer("stake", StakeScorer);
+            scheduler
+                .update_validator_stakes(HashMap::from([(whale.to_string(), 1.0)]))
+                .await;
+            let normal = scheduler
+                .create_job(request(plain, JobPriority::Normal, serde_json::json!({})))
+                .await
+                .unwrap();
+            let low = scheduler
+                .create_job(request(
+                    staked,
+                    JobPriority::Low,
+                    serde_json::json!({"needs_stake": true}),
+                ))
+                .await
+                .unwrap();
+
+            let claimed = scheduler.claim_job(claim(hotkey)).await.unwrap().job.id;
+            let expected = if expects_staked { low.id } else { normal.id };
+            assert_eq!(claimed, expected, "{} claimed the wrong job", hotkey);
+        }
+    }
+
+    #[test]
+    fn test_default_scorer_shifts_by_trust() {
+        let config = SchedulerConfig::default();
+        let now = Utc::now();
+        let scorer = PriorityScorer::new(&config, now);
+        let job = |priority| JobMetadata {
+            id: Uuid::new_v4(),
+            challenge_id: Uuid::new_v4(),
+            validator_hotkey: None,
+            status: JobStatus::Pending,
+            priority,
+            runtime: RuntimeType::Docker,
+            created_at: now,
+            claimed_at: None,
+            started_at: None,
+            completed_at: None,
+            timeout_at: None,
+            timeout: None,
+            retry_count: 0,
+            max_retries: 3,
+            payload: None,
+            failure_category: None,
+            depends_on: vec![],
+            deadline: None,
+            effective_priority: None,
+            version: 0,
+            required_capabilities: vec![],
+            resources: Default::default(),
+            schema_version: JOB_METADATA_SCHEMA_VERSION,
+        };
+        let trusted = ValidatorInfo {
+            trust: 1.0,
+            ..ValidatorInfo::new(Hotkey::from_public_key(&[1; 32]))
+        };
+        let neutral = ValidatorInfo::new(Hotkey::from_public_key(&[2; 32]));
+        let flaky = ValidatorInfo {
+            trust: 1.0,
+            success_rate: 0.25,
+            ..ValidatorInfo::new(Hotkey::from_public_key(&[3; 32]))
+        };
+
+        let critical = job(JobPriority::Critical);
+        assert_eq!(
+            scorer.score(&critical, &neutral),
+            config.effective_priority(&critical, now)
+        );
+        assert!(scorer.score(&critical, &trusted) > scorer.score(&critical, &neutral));
+        assert!(
+            scorer.score(&job(JobPriority::Low), &trusted)
+                < scorer.score(&job(JobPriority::Low), &neutral)
+        );
+
+        // Succeeding half as often as a new validator halves the trust
+        assert_eq!(flaky.effective_trust(), 0.5);
+        assert_eq!(
+            scorer.score(&critical, &flaky),
+            scorer.score(&critical, &neutral)
+        );
+    }
+}
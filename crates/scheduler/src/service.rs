@@ -1,36 +1,94 @@
 //! Scheduler service implementation
 
-use crate::types::SchedulerConfig;
+use crate::{
+    reliability::ValidatorReliability,
+    scorer::Scorer,
+    store::{JobStore, MemoryJobStore, PostgresJobStore},
+    types::SchedulerConfig,
+    webhooks::WebhookDispatcher,
+};
 use anyhow::Result;
-use platform_api_models::JobMetadata;
 use sqlx::PgPool;
 use std::sync::Arc;
-use uuid::Uuid;
+use tracing::info;
 
 /// Scheduler service for managing job lifecycle
 pub struct SchedulerService {
-    pub(crate) config: SchedulerConfig,
+    pub(crate) config: tokio::sync::RwLock<SchedulerConfig>,
+    /// Jobs and their state transitions
+    pub(crate) store: Arc<dyn JobStore>,
+    /// Challenge job defaults, trust scores and validator reliability are
+    /// kept in the database; without it, trust scores are not recorded
     pub(crate) database_pool: Option<Arc<PgPool>>,
-    // Fallback to in-memory if no database pool
-    pub(crate) jobs: tokio::sync::RwLock<std::collections::HashMap<Uuid, JobMetadata>>,
+    /// Relative on-chain stake of each validator hotkey, used for trust scores
+    pub(crate) validator_stakes: Arc<tokio::sync::RwLock<std::collections::HashMap<String, f64>>>,
+    /// Number of jobs each validator hotkey reported it can run at once
+    pub(crate) validator_capacity: tokio::sync::RwLock<std::collections::HashMap<String, u32>>,
+    /// Resources each validator hotkey registered with since startup, read
+    /// from the `validators` table instead when there is a database
+    pub(crate) validator_registrations: tokio::sync::RwLock<
+        std::collections::HashMap<String, platform_api_models::ValidatorRegistration>,
+    >,
+    /// Completed and failed job counts of each validator, without a database
+    pub(crate) validator_reliability:
+        tokio::sync::RwLock<std::collections::HashMap<String, ValidatorReliability>>,
+    /// Delivers job events to challenge webhooks; none are sent without it
+    pub(crate) webhooks: Option<Arc<WebhookDispatcher>>,
+    /// Scorers challenges may select by name
+    pub(crate) scorers: std::collections::HashMap<String, Arc<dyn Scorer>>,
 }
 
 impl SchedulerService {
     /// Create a new scheduler service with in-memory storage
     pub fn new(config: &SchedulerConfig) -> Result<Self> {
-        Ok(Self {
-            config: config.clone(),
-            database_pool: None,
-            jobs: tokio::sync::RwLock::new(std::collections::HashMap::new()),
-        })
+        Self::with_store(config, Arc::new(MemoryJobStore::new()))
     }
 
     /// Create scheduler with database pool (for PostgreSQL storage)
     pub fn with_database(config: &SchedulerConfig, database_pool: Arc<PgPool>) -> Result<Self> {
         Ok(Self {
-            config: config.clone(),
-            database_pool: Some(database_pool),
-            jobs: tokio::sync::RwLock::new(std::collections::HashMap::new()),
+            database_pool: Some(database_pool.clone()),
+            ..Self::with_store(config, Arc::new(PostgresJobStore::new(database_pool)))?
+        })
+    }
+
+    /// Create scheduler keeping its jobs in `store`
+    ///
+    /// Features that need the database, see `with_database`, are disabled.
+    pub fn with_store(config: &SchedulerConfig, store: Arc<dyn JobStore>) -> Result<Self> {
+        Ok(Self {
+            config: tokio::sync::RwLock::new(config.clone()),
+            store,
+            database_pool: None,
+            validator_stakes: Arc::default(),
+            validator_capacity: tokio::sync::RwLock::new(std::collections::HashMap::new()),
+            validator_registrations: tokio::sync::RwLock::new(std::collections::HashMap::new()),
+            validator_reliability: tokio::sync::RwLock::new(std::collections::HashMap::new()),
+            webhooks: None,
+            scorers: std::collections::HashMap::new(),
         })
     }
+
+    /// Notify challenge webhooks of job terminal states through `dispatcher`
+    pub fn with_webhooks(mut self, dispatcher: WebhookDispatcher) -> Self {
+        self.webhooks = Some(Arc::new(dispatcher));
+        self
+    }
+
+    /// Get the current scheduler configuration
+    pub async fn config(&self) -> SchedulerConfig {
+        self.config.read().await.clone()
+    }
+
+    /// Replace the scheduler configuration at runtime
+    pub async fn update_config(&self, config: SchedulerConfig) {
+        info!(
+            job_timeout = config.job_timeout,
+            retry_attempts = config.retry_attempts,
+            retry_delay = config.retry_delay,
+            max_concurrent_jobs = config.max_concurrent_jobs,
+            "Scheduler configuration updated"
+        );
+        *self.config.write().await = config;
+    }
 }
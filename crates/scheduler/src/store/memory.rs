@@ -0,0 +1,597 @@
+//! In-memory job store
+
+use super::{CompletedJob, FailedJob, JobClaim, JobStore};
+use crate::{
+    capacity::check_capacity,
+    jobs::{check_pending, JobChange, IN_FLIGHT_STATUSES, PENDING_STATUSES},
+    types::{JobAdminError, JobConflict, JobSearch, SchedulerConfig},
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use platform_api_models::*;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Job store keeping the jobs of a single scheduler in memory
+///
+/// Every transition holds the write lock of the jobs, so quota and capacity
+/// checks cannot race with other claims and creations. No job events are
+/// recorded.
+#[derive(Default)]
+pub struct MemoryJobStore {
+    jobs: RwLock<HashMap<Uuid, JobMetadata>>,
+    /// Failed attempts of each job
+    retry_history: RwLock<HashMap<Uuid, Vec<JobAttempt>>>,
+    /// Receipts of each completed job
+    receipts: RwLock<HashMap<Uuid, CompletionReceipts>>,
+}
+
+/// Receipts a job was completed with, see [`JobReceipts`]
+#[derive(Clone, Default)]
+struct CompletionReceipts {
+    receipts: Vec<String>,
+    attestation_receipt: Option<String>,
+}
+
+impl MemoryJobStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Pending and in-flight job counts of each challenge among `jobs`
+fn usage(jobs: &HashMap<Uuid, JobMetadata>) -> HashMap<Id, (u64, u64)> {
+    let mut usage: HashMap<Id, (u64, u64)> = HashMap::new();
+    for job in jobs.values() {
+        let entry = usage.entry(job.challenge_id).or_default();
+        if PENDING_STATUSES.contains(&job.status.as_str()) {
+            entry.0 += 1;
+        } else if IN_FLIGHT_STATUSES.contains(&job.status.as_str()) {
+            entry.1 += 1;
+        }
+    }
+    usage
+}
+
+/// Check that `validator_hotkey` has a free job slot among `jobs`
+fn reserve_validator_slot(
+    capacity: Option<u32>,
+    jobs: &HashMap<Uuid, JobMetadata>,
+    validator_hotkey: &str,
+) -> PlatformResult<()> {
+    let Some(capacity) = capacity else {
+        return Ok(());
+    };
+    let in_flight = jobs
+        .values()
+        .filter(|j| IN_FLIGHT_STATUSES.contains(&j.status.as_str()))
+        .filter(|j| j.validator_hotkey.as_deref() == Some(validator_hotkey))
+        .count();
+    check_capacity(validator_hotkey, capacity, in_flight as u64)
+}
+
+/// Check that `job` is still at the `expected` version
+fn check_version(job: &JobMetadata, expected: u64) -> PlatformResult<()> {
+    if job.version == expected {
+        Ok(())
+    } else {
+        Err(JobConflict {
+            job_id: job.id,
+            expected,
+        }
+        .into())
+    }
+}
+
+/// Release a job from its validator, back to pending
+fn release(job: &mut JobMetadata) {
+    job.status = JobStatus::Pending;
+    job.validator_hotkey = None;
+    job.claimed_at = None;
+    job.started_at = None;
+    job.completed_at = None;
+    job.timeout_at = None;
+}
+
+/// Whether every dependency of `job` has completed
+fn dependencies_completed(jobs: &HashMap<Uuid, JobMetadata>, job: &JobMetadata) -> bool {
+    job.depends_on.iter().all(|dep| {
+        jobs.get(dep)
+            .is_some_and(|d| d.status == JobStatus::Completed)
+    })
+}
+
+/// Dead-letter the blocked jobs that depend on dead-lettered job `job_id`,
+/// directly or through one another, returning their ids
+fn dead_letter_dependents(
+    jobs: &mut HashMap<Uuid, JobMetadata>,
+    job_id: Uuid,
+    now: DateTime<Utc>,
+) -> Vec<Uuid> {
+    let mut dependents = Vec::new();
+    let mut dead_lettered = vec![job_id];
+    while let Some(id) = dead_lettered.pop() {
+        let blocked: Vec<Uuid> = jobs
+            .values()
+            .filter(|j| j.status == JobStatus::Blocked && j.depends_on.contains(&id))
+            .map(|j| j.id)
+            .collect();
+        for dependent in blocked {
+            if let Some(job) = jobs.get_mut(&dependent) {
+                job.status = JobStatus::DeadLettered;
+                job.completed_at = Some(now);
+                job.version += 1;
+            }
+            dependents.push(dependent);
+            dead_lettered.push(dependent);
+        }
+    }
+    dependents
+}
+
+fn page_of<T: Clone>(items: &[T], page: u32, per_page: u32) -> Vec<T> {
+    let start = (page.saturating_sub(1) as usize).saturating_mul(per_page as usize);
+    let end = start.saturating_add(per_page as usize).min(items.len());
+    items.get(start..end).unwrap_or_default().to_vec()
+}
+
+#[async_trait::async_trait]
+impl JobStore for MemoryJobStore {
+    async fn insert_jobs(&self, config: &SchedulerConfig, new_jobs: &[JobMetadata]) -> Result<()> {
+        let mut jobs = self.jobs.write().await;
+        if let Some(job) = new_jobs.iter().find(|job| jobs.contains_key(&job.id)) {
+            return Err(PlatformError::conflict(format!("Job {} already exists", job.id)).into());
+        }
+
+        let usage = usage(&jobs);
+        for (challenge_id, count) in crate::jobs::jobs_per_challenge(new_jobs) {
+            let (pending, _) = usage.get(&challenge_id).copied().unwrap_or_default();
+            check_pending(config, challenge_id, pending, count)?;
+        }
+
+        for job in new_jobs {
+            jobs.insert(job.id, job.clone());
+        }
+        Ok(())
+    }
+
+    async fn get_job(&self, id: Uuid) -> Result<Option<JobMetadata>> {
+        Ok(self.jobs.read().await.get(&id).cloned())
+    }
+
+    async fn list_jobs(
+        &self,
+        page: u32,
+        per_page: u32,
+        status: Option<&str>,
+        challenge_id: Option<Uuid>,
+        search: &JobSearch,
+    ) -> Result<JobListResponse> {
+        let jobs = self.jobs.read().await;
+        let mut job_list: Vec<JobMetadata> = jobs.values().cloned().collect();
+
+        if let Some(status_filter) = status {
+            job_list.retain(|j| format!("{:?}", j.status).to_lowercase() == status_filter);
+        }
+
+        if let Some(challenge_id_filter) = challenge_id {
+            job_list.retain(|j| j.challenge_id == challenge_id_filter);
+        }
+
+        job_list.retain(|j| search.matches(j));
+
+        job_list.sort_by_key(|j| Reverse(j.created_at));
+
+        Ok(JobListResponse {
+            jobs: page_of(&job_list, page, per_page),
+            total: job_list.len() as u64,
+            page,
+            per_page,
+        })
+    }
+
+    async fn dependency_completion(&self, ids: &[Id]) -> Result<HashMap<Id, bool>> {
+        let jobs = self.jobs.read().await;
+        Ok(ids
+            .iter()
+            .filter_map(|id| {
+                jobs.get(id)
+                    .map(|job| (*id, job.status == JobStatus::Completed))
+            })
+            .collect())
+    }
+
+    async fn challenge_usage(&self, challenge_id: Id) -> Result<(u64, u64)> {
+        let jobs = self.jobs.read().await;
+        Ok(usage(&jobs).get(&challenge_id).copied().unwrap_or_default())
+    }
+
+    async fn challenge_job_counts(&self, challenge_id: Uuid) -> Result<ChallengeJobCounts> {
+        let mut counts = ChallengeJobCounts::default();
+        let jobs = self.jobs.read().await;
+        for job in jobs.values().filter(|j| j.challenge_id == challenge_id) {
+            match job.status {
+                JobStatus::Pending => counts.pending += 1,
+                JobStatus::Running => counts.running += 1,
+                JobStatus::Completed => counts.completed += 1,
+                _ => {}
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn job_stats(&self) -> Result<JobStats> {
+        let jobs = self.jobs.read().await;
+        let total = jobs.len() as u64;
+        let count = |status: JobStatus| jobs.values().filter(|j| j.status == status).count() as u64;
+        let completed = count(JobStatus::Completed);
+
+        let mut failures_by_category: HashMap<String, u64> = HashMap::new();
+        for category in jobs
+            .values()
+            .filter(|j| matches!(j.status, JobStatus::Failed))
+            .filter_map(|j| j.failure_category.as_ref())
+        {
+            *failures_by_category
+                .entry(category.to_string())
+                .or_insert(0) += 1;
+        }
+
+        Ok(JobStats {
+            total_jobs: total,
+            pending_jobs: count(JobStatus::Pending),
+            running_jobs: count(JobStatus::Running),
+            completed_jobs: completed,
+            failed_jobs: count(JobStatus::Failed),
+            avg_execution_time: 0.0,
+            success_rate: if total > 0 {
+                completed as f64 / total as f64
+            } else {
+                0.0
+            },
+            failures_by_category,
+        })
+    }
+
+    async fn job_receipts(&self, job_id: Uuid) -> Result<Option<JobReceipts>> {
+        let jobs = self.jobs.read().await;
+        let Some(job) = jobs.get(&job_id) else {
+            return Ok(None);
+        };
+        let CompletionReceipts {
+            receipts,
+            attestation_receipt,
+        } = self
+            .receipts
+            .read()
+            .await
+            .get(&job_id)
+            .cloned()
+            .unwrap_or_default();
+        Ok(Some(JobReceipts {
+            job_id,
+            validator_hotkey: job.validator_hotkey.clone(),
+            completed_at: job.completed_at,
+            receipts,
+            attestation_receipt,
+        }))
+    }
+
+    async fn claim_job(&self, claim: JobClaim<'_>) -> Result<Option<JobMetadata>> {
+        let hotkey = &claim.validator.hotkey;
+        let mut jobs = self.jobs.write().await;
+        reserve_validator_slot(claim.validator.capacity, &jobs, hotkey)?;
+
+        let usage = usage(&jobs);
+        let candidates: Vec<&JobMetadata> = jobs
+            .values()
+            .filter(|j| j.status == JobStatus::Pending && j.runs_with(claim.capabilities))
+            .filter(|j| claim.validator.has_resources_for(j))
+            .filter(|j| {
+                let (_, in_flight) = usage.get(&j.challenge_id).copied().unwrap_or_default();
+                in_flight < claim.config.challenge_quota(&j.challenge_id).max_in_flight
+            })
+            .collect();
+        let Some(job_id) = (claim.select)(&candidates).map(|index| candidates[index].id) else {
+            return Ok(None);
+        };
+
+        let job = jobs.get_mut(&job_id).expect("candidate is in the map");
+        job.status = JobStatus::Claimed;
+        job.validator_hotkey = Some(hotkey.to_string());
+        job.claimed_at = Some(claim.now);
+        job.timeout_at =
+            Some(claim.now + chrono::Duration::seconds(claim.config.job_timeout(job) as i64));
+        job.version += 1;
+        Ok(Some(job.clone()))
+    }
+
+    async fn claim_specific_job(
+        &self,
+        job_id: Uuid,
+        version: u64,
+        validator_hotkey: &str,
+        capacity: Option<u32>,
+        timeout: u64,
+        now: DateTime<Utc>,
+    ) -> Result<JobMetadata> {
+        let mut jobs = self.jobs.write().await;
+        reserve_validator_slot(capacity, &jobs, validator_hotkey)?;
+        let job = jobs
+            .get_mut(&job_id)
+            .ok_or_else(|| PlatformError::not_found("job", job_id))?;
+        check_version(job, version)?;
+
+        if job.status != JobStatus::Pending {
+            return Err(PlatformError::conflict("Job not available or already claimed").into());
+        }
+
+        job.status = JobStatus::Claimed;
+        job.validator_hotkey = Some(validator_hotkey.to_string());
+        job.claimed_at = Some(now);
+        job.timeout_at = Some(now + chrono::Duration::seconds(timeout as i64));
+        job.version += 1;
+        Ok(job.clone())
+    }
+
+    async fn complete_job(
+        &self,
+        job_id: Uuid,
+        version: u64,
+        result: &serde_json::Value,
+        receipts: &[String],
+        _details: serde_json::Value,
+        now: DateTime<Utc>,
+    ) -> Result<CompletedJob> {
+        let mut jobs = self.jobs.write().await;
+        let job = jobs
+            .get_mut(&job_id)
+            .ok_or_else(|| PlatformError::not_found("job", job_id))?;
+        check_version(job, version)?;
+        let old_status = std::mem::replace(&mut job.status, JobStatus::Completed);
+        job.completed_at = Some(now);
+        job.started_at.get_or_insert(now);
+        job.version += 1;
+        let challenge_id = job.challenge_id;
+        let validator_hotkey = job.validator_hotkey.clone();
+
+        let unblocked: Vec<Uuid> = jobs
+            .values()
+            .filter(|j| j.status == JobStatus::Blocked && j.depends_on.contains(&job_id))
+            .filter(|j| dependencies_completed(&jobs, j))
+            .map(|j| j.id)
+            .collect();
+        for id in &unblocked {
+            if let Some(job) = jobs.get_mut(id) {
+                job.status = JobStatus::Pending;
+                job.version += 1;
+            }
+        }
+
+        let attestation_receipt = result
+            .get("attestation_receipt")
+            .and_then(|r| r.as_str())
+            .map(String::from);
+        self.receipts.write().await.insert(
+            job_id,
+            CompletionReceipts {
+                receipts: receipts.to_vec(),
+                attestation_receipt,
+            },
+        );
+
+        Ok(CompletedJob {
+            challenge_id,
+            old_status,
+            validator_hotkey,
+            unblocked,
+        })
+    }
+
+    async fn fail_job(
+        &self,
+        job_id: Uuid,
+        version: u64,
+        request: &FailJobRequest,
+        _actor: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Option<FailedJob>> {
+        let mut jobs = self.jobs.write().await;
+        let Some(job) = jobs.get_mut(&job_id) else {
+            return Ok(None);
+        };
+        check_version(job, version)?;
+
+        let status = if job.retry_count >= job.max_retries {
+            JobStatus::DeadLettered
+        } else {
+            JobStatus::Failed
+        };
+        let old_status = std::mem::replace(&mut job.status, status.clone());
+        job.completed_at = Some(now);
+        job.failure_category = request.failure_category.clone();
+        job.version += 1;
+
+        let attempt = job.retry_count + 1;
+        self.retry_history
+            .write()
+            .await
+            .entry(job_id)
+            .or_default()
+            .push(JobAttempt {
+                attempt,
+                validator_hotkey: job.validator_hotkey.clone(),
+                reason: request.reason.clone(),
+                error_details: request.error_details.clone(),
+                failure_category: request.failure_category.clone(),
+                failed_at: now,
+            });
+
+        let mut failed = FailedJob {
+            challenge_id: job.challenge_id,
+            old_status,
+            status,
+            validator_hotkey: job.validator_hotkey.clone(),
+            attempt,
+            dead_lettered_dependents: vec![],
+        };
+        if failed.status == JobStatus::DeadLettered {
+            failed.dead_lettered_dependents = dead_letter_dependents(&mut jobs, job_id, now);
+        }
+        Ok(Some(failed))
+    }
+
+    async fn expired_jobs(&self, now: DateTime<Utc>) -> Result<Vec<(Uuid, u64)>> {
+        let jobs = self.jobs.read().await;
+        Ok(jobs
+            .values()
+            .filter(|j| matches!(j.status, JobStatus::Claimed | JobStatus::Running))
+            .filter(|j| j.timeout_at.is_some_and(|t| t < now))
+            .map(|j| (j.id, j.version))
+            .collect())
+    }
+
+    async fn validator_jobs_in_flight(&self, hotkey: &str) -> Result<Vec<(Uuid, u64)>> {
+        let jobs = self.jobs.read().await;
+        Ok(jobs
+            .values()
+            .filter(|j| matches!(j.status, JobStatus::Claimed | JobStatus::Running))
+            .filter(|j| j.validator_hotkey.as_deref() == Some(hotkey))
+            .map(|j| (j.id, j.version))
+            .collect())
+    }
+
+    async fn stale_claims(&self, claimed_before: DateTime<Utc>) -> Result<Vec<(Uuid, u64)>> {
+        let jobs = self.jobs.read().await;
+        Ok(jobs
+            .values()
+            .filter(|j| {
+                j.status == JobStatus::Claimed && j.claimed_at.is_some_and(|t| t < claimed_before)
+            })
+            .map(|j| (j.id, j.version))
+            .collect())
+    }
+
+    async fn retry_failed_jobs(
+        &self,
+        now: DateTime<Utc>,
+        retry_delay: u64,
+    ) -> Result<Vec<(Uuid, u32)>> {
+        // Challenges, and their retry delays, are only stored in the database
+        let retry_before = now - chrono::Duration::seconds(retry_delay as i64);
+        let mut jobs = self.jobs.write().await;
+        let mut retried = Vec::new();
+        for job in jobs.values_mut().filter(|j| {
+            j.status == JobStatus::Failed
+                && j.retry_count < j.max_retries
+                && j.completed_at.is_some_and(|t| t <= retry_before)
+        }) {
+            release(job);
+            job.retry_count += 1;
+            job.version += 1;
+            retried.push((job.id, job.retry_count));
+        }
+        Ok(retried)
+    }
+
+    async fn retry_failed_job(&self, job_id: Uuid, _actor: &str) -> Result<Option<u32>> {
+        let mut jobs = self.jobs.write().await;
+        let Some(job) = jobs
+            .get_mut(&job_id)
+            .filter(|j| j.status == JobStatus::Failed && j.retry_count < j.max_retries)
+        else {
+            return Ok(None);
+        };
+        release(job);
+        job.retry_count += 1;
+        job.version += 1;
+        Ok(Some(job.retry_count))
+    }
+
+    async fn list_dead_lettered_jobs(
+        &self,
+        page: u32,
+        per_page: u32,
+    ) -> Result<DeadLetteredJobListResponse> {
+        let jobs = self.jobs.read().await;
+        let retry_history = self.retry_history.read().await;
+
+        let mut dead_lettered: Vec<DeadLetteredJob> = jobs
+            .values()
+            .filter(|j| j.status == JobStatus::DeadLettered)
+            .map(|job| {
+                let history = retry_history.get(&job.id).cloned().unwrap_or_default();
+                DeadLetteredJob {
+                    job: job.clone(),
+                    reason: history.last().map(|a| a.reason.clone()),
+                    dead_lettered_at: job.completed_at,
+                    retry_history: history,
+                }
+            })
+            .collect();
+        dead_lettered.sort_by_key(|j| Reverse(j.dead_lettered_at));
+
+        Ok(DeadLetteredJobListResponse {
+            jobs: page_of(&dead_lettered, page, per_page),
+            total: dead_lettered.len() as u64,
+            page,
+            per_page,
+        })
+    }
+
+    async fn requeue_job(&self, job_id: Uuid, _actor: &str) -> Result<bool> {
+        let mut jobs = self.jobs.write().await;
+        let blocked = jobs
+            .get(&job_id)
+            .is_some_and(|job| !dependencies_completed(&jobs, job));
+        match jobs
+            .get_mut(&job_id)
+            .filter(|j| j.status == JobStatus::DeadLettered)
+        {
+            Some(job) => {
+                release(job);
+                if blocked {
+                    job.status = JobStatus::Blocked;
+                }
+                job.retry_count = 0;
+                job.version += 1;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn change_job(
+        &self,
+        job_id: Uuid,
+        change: &JobChange,
+        _actor: &str,
+    ) -> Result<JobMetadata> {
+        let mut jobs = self.jobs.write().await;
+        let blocked = jobs
+            .get(&job_id)
+            .is_some_and(|job| !dependencies_completed(&jobs, job));
+        let job = jobs
+            .get_mut(&job_id)
+            .ok_or_else(|| PlatformError::from(JobAdminError::NotFound(job_id)))?;
+        change
+            .check(job_id, &job.status)
+            .map_err(PlatformError::from)?;
+
+        let before = job.clone();
+        match change {
+            JobChange::Priority(priority) => job.priority = priority.clone(),
+            JobChange::Reset => {
+                release(job);
+                if blocked {
+                    job.status = JobStatus::Blocked;
+                }
+            }
+            JobChange::ClearRetries => job.retry_count = 0,
+        }
+        job.version += 1;
+        Ok(before)
+    }
+}
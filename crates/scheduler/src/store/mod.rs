@@ -0,0 +1,629 @@
+//! Storage of the scheduler's jobs
+//!
+//! [`JobStore`] holds the jobs and makes their state transitions atomically:
+//! creation within the challenge quotas, claims within the validator
+//! capacities, completion releasing dependents, failures and retries.
+//! Each transition is recorded in `job_events` within the same transaction
+//! when the store has one, so that the history never disagrees with the job.
+//! `SchedulerService` adds what is common to every store on top of it:
+//! validation, webhooks and validator reliability.
+//!
+//! Every change of a job increments its `version`. Completions, failures and
+//! claims of a specific job are made at the version their caller read, and
+//! fail with `JobConflict` if another change got there first.
+//!
+//! Errors callers act on, such as `JobConflict` or `QuotaExceeded`, are
+//! raised as the `PlatformError` they convert to, so that they keep their
+//! variant through `anyhow` up to the API.
+//! [`PostgresJobStore`] backs production deployments and [`MemoryJobStore`]
+//! single processes and tests.
+
+mod memory;
+mod postgres;
+
+pub use memory::*;
+pub use postgres::*;
+
+use crate::{
+    jobs::JobChange,
+    scorer::ValidatorInfo,
+    types::{JobSearch, SchedulerConfig},
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use platform_api_models::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Picks the index of the candidate to claim, see [`JobClaim::select`]
+pub type CandidateSelector<'a> = dyn Fn(&[&JobMetadata]) -> Option<usize> + Send + Sync + 'a;
+
+/// A validator's claim of the best pending job, see [`JobStore::claim_job`]
+pub struct JobClaim<'a> {
+    pub config: &'a SchedulerConfig,
+    pub validator: &'a ValidatorInfo,
+    /// Pending jobs of highest effective priority the candidates are taken from
+    pub candidate_limit: usize,
+    /// Capabilities the validator claims with; jobs requiring others are skipped
+    pub capabilities: &'a [String],
+    pub now: DateTime<Utc>,
+    /// Chooses among the pending jobs whose challenge is within its in-flight quota
+    pub select: &'a CandidateSelector<'a>,
+}
+
+/// Progress a validator reported with the result of a job
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobProgress {
+    /// Share of the tasks done, in percent
+    pub percent: Option<f64>,
+    pub total_tasks: Option<i32>,
+    pub completed_tasks: Option<i32>,
+    pub resolved_tasks: Option<i32>,
+    pub unresolved_tasks: Option<i32>,
+}
+
+impl JobProgress {
+    /// Progress in the `progress` object of a result, whose `progress_percent` is a fraction
+    pub fn of(result: &serde_json::Value) -> Self {
+        let progress = result.get("progress");
+        let count = |key: &str| {
+            progress
+                .and_then(|p| p.get(key))
+                .and_then(|v| v.as_i64())
+                .map(|v| v as i32)
+        };
+        Self {
+            percent: progress
+                .and_then(|p| p.get("progress_percent"))
+                .and_then(|v| v.as_f64())
+                .map(|v| v * 100.0),
+            total_tasks: count("total_tasks"),
+            completed_tasks: count("completed_tasks"),
+            resolved_tasks: count("resolved_tasks"),
+            unresolved_tasks: count("unresolved_tasks"),
+        }
+    }
+}
+
+/// A job marked completed by [`JobStore::complete_job`]
+#[derive(Debug, Clone)]
+pub struct CompletedJob {
+    pub challenge_id: Id,
+    pub old_status: JobStatus,
+    pub validator_hotkey: Option<String>,
+    /// Blocked jobs whose last outstanding dependency was this one, now pending
+    pub unblocked: Vec<Uuid>,
+}
+
+/// A job marked failed or dead-lettered by [`JobStore::fail_job`]
+#[derive(Debug, Clone)]
+pub struct FailedJob {
+    pub challenge_id: Id,
+    pub old_status: JobStatus,
+    pub status: JobStatus,
+    pub validator_hotkey: Option<String>,
+    /// Number of the failed attempt, starting at 1
+    pub attempt: u32,
+    /// Blocked jobs that depended on this one, directly or through one
+    /// another, dead-lettered along with it
+    pub dead_lettered_dependents: Vec<Uuid>,
+}
+
+/// Job storage backend of the scheduler
+#[async_trait::async_trait]
+pub trait JobStore: Send + Sync {
+    /// Insert new jobs, all of them or none
+    ///
+    /// Fails with `QuotaExceeded` if they do not fit in the pending quota of
+    /// their challenge.
+    async fn insert_jobs(&self, config: &SchedulerConfig, jobs: &[JobMetadata]) -> Result<()>;
+    async fn get_job(&self, id: Uuid) -> Result<Option<JobMetadata>>;
+    /// Jobs matching the filters, most recent first, and their total count
+    async fn list_jobs(
+        &self,
+        page: u32,
+        per_page: u32,
+        status: Option<&str>,
+        challenge_id: Option<Uuid>,
+        search: &JobSearch,
+    ) -> Result<JobListResponse>;
+    /// Whether each of the given jobs has completed; unknown ids are absent from the map
+    async fn dependency_completion(&self, ids: &[Id]) -> Result<HashMap<Id, bool>>;
+    /// Jobs `challenge_id` has pending and in flight
+    async fn challenge_usage(&self, challenge_id: Id) -> Result<(u64, u64)>;
+    async fn challenge_job_counts(&self, challenge_id: Uuid) -> Result<ChallengeJobCounts>;
+    async fn job_stats(&self) -> Result<JobStats>;
+    /// Receipts of the last completion of a job; `None` for unknown jobs
+    async fn job_receipts(&self, job_id: Uuid) -> Result<Option<JobReceipts>>;
+
+    /// Claim the candidate `claim.select` picks for its validator, if any
+    ///
+    /// Fails with `CapacityExhausted` if the validator has as many jobs in
+    /// flight as it reported it can run. The job times out its
+    /// `SchedulerConfig::job_timeout` after `claim.now`.
+    async fn claim_job(&self, claim: JobClaim<'_>) -> Result<Option<JobMetadata>>;
+    /// Claim the pending job `job_id`, at `version`, for `validator_hotkey`,
+    /// within `capacity`, timing out `timeout` seconds after `now`
+    async fn claim_specific_job(
+        &self,
+        job_id: Uuid,
+        version: u64,
+        validator_hotkey: &str,
+        capacity: Option<u32>,
+        timeout: u64,
+        now: DateTime<Utc>,
+    ) -> Result<JobMetadata>;
+    /// Mark a job at `version` completed with `result` and `receipts` and
+    /// release its dependents
+    ///
+    /// The completion event gets `details` along with the progress percent of
+    /// `result`, see [`JobProgress::of`].
+    async fn complete_job(
+        &self,
+        job_id: Uuid,
+        version: u64,
+        result: &serde_json::Value,
+        receipts: &[String],
+        details: serde_json::Value,
+        now: DateTime<Utc>,
+    ) -> Result<CompletedJob>;
+    /// Mark a job at `version` failed, or dead-lettered once it has no retries
+    /// left, on behalf of `actor`, by default its validator, and append the
+    /// attempt to its retry history
+    ///
+    /// The jobs blocked on a dead-lettered job can never run, so they are
+    /// dead-lettered with it.
+    ///
+    /// Returns `None` for unknown jobs.
+    async fn fail_job(
+        &self,
+        job_id: Uuid,
+        version: u64,
+        request: &FailJobRequest,
+        actor: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Option<FailedJob>>;
+    /// Claimed or running jobs whose `timeout_at` is before `now`, with their versions
+    async fn expired_jobs(&self, now: DateTime<Utc>) -> Result<Vec<(Uuid, u64)>>;
+    /// Claimed or running jobs of validator `hotkey`, with their versions
+    async fn validator_jobs_in_flight(&self, hotkey: &str) -> Result<Vec<(Uuid, u64)>>;
+    /// Jobs still claimed, never started, that were claimed before
+    /// `claimed_before`, with their versions
+    async fn stale_claims(&self, claimed_before: DateTime<Utc>) -> Result<Vec<(Uuid, u64)>>;
+    /// Return to pending the failed jobs with retries left that failed at
+    /// least their challenge's retry delay, or else `retry_delay` seconds,
+    /// before `now`, with their new retry count
+    async fn retry_failed_jobs(
+        &self,
+        now: DateTime<Utc>,
+        retry_delay: u64,
+    ) -> Result<Vec<(Uuid, u32)>>;
+    /// Return failed job `job_id` to pending right away, on behalf of
+    /// `actor`, if it has retries left, with its new retry count
+    async fn retry_failed_job(&self, job_id: Uuid, actor: &str) -> Result<Option<u32>>;
+    /// Dead-lettered jobs, most recently dead-lettered first
+    async fn list_dead_lettered_jobs(
+        &self,
+        page: u32,
+        per_page: u32,
+    ) -> Result<DeadLetteredJobListResponse>;
+    /// Return a dead-lettered job to pending, or to blocked if one of its
+    /// dependencies has not completed, with a fresh set of retries, on behalf
+    /// of `actor`; false if the job is not dead-lettered
+    async fn requeue_job(&self, job_id: Uuid, actor: &str) -> Result<bool>;
+    /// Apply an operator's change to a job on behalf of `actor`, returning
+    /// the job as it was before
+    ///
+    /// Fails with `JobAdminError` if the job is unknown or its status does not
+    /// allow the change.
+    async fn change_job(
+        &self,
+        job_id: Uuid,
+        change: &JobChange,
+        actor: &str,
+    ) -> Result<JobMetadata>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{service::SchedulerService, types::*};
+    use platform_api_models::*;
+    use sqlx::PgPool;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn config(challenge_id: Uuid) -> SchedulerConfig {
+        // Jobs of other challenges, e.g. left in the database by other tests,
+        // are never handed out
+        SchedulerConfig {
+            retry_delay: 0,
+            default_challenge_quota: ChallengeJobQuota {
+                max_pending: 1000,
+                max_in_flight: 0,
+            },
+            challenge_quotas: HashMap::from([(
+                challenge_id,
+                ChallengeJobQuota {
+                    max_pending: 3,
+                    max_in_flight: 2,
+                },
+            )]),
+            ..SchedulerConfig::default()
+        }
+    }
+
+    fn create_request(challenge_id: Uuid, depends_on: Vec<Uuid>) -> CreateJobRequest {
+        CreateJobRequest {
+            challenge_id,
+            payload: serde_json::json!({ "task": "hello-world" }),
+            priority: None,
+            runtime: Some(RuntimeType::Docker),
+            timeout: None,
+            max_retries: Some(0),
+            job_id: None,
+            depends_on,
+            deadline: None,
+            required_capabilities: None,
+            resources: None,
+        }
+    }
+
+    fn claim_request(hotkey: &Hotkey) -> ClaimJobRequest {
+        ClaimJobRequest {
+            validator_hotkey: hotkey.clone(),
+            runtime: RuntimeType::Docker,
+            capabilities: vec![],
+        }
+    }
+
+    /// Creates, claims, completes, fails and requeues jobs of `challenge_id`
+    async fn job_lifecycle(scheduler: SchedulerService, challenge_id: Uuid) {
+        let mut public_key = [0; 32];
+        public_key[..16].copy_from_slice(challenge_id.as_bytes());
+        let hotkey = Hotkey::from_public_key(&public_key);
+
+        let first = scheduler
+            .create_job(create_request(challenge_id, vec![]))
+            .await
+            .unwrap();
+        let second = scheduler
+            .create_job(create_request(challenge_id, vec![first.id]))
+            .await
+            .unwrap();
+        assert_eq!(first.status, JobStatus::Pending);
+        assert_eq!(second.status, JobStatus::Blocked);
+
+        // The batch would take the challenge over its pending quota
+        let err = scheduler
+            .create_jobs_batch(vec![
+                create_request(challenge_id, vec![]),
+                create_request(challenge_id, vec![]),
+            ])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PlatformError::RateLimited { .. }), "{}", err);
+        let usage = scheduler.challenge_job_usage(challenge_id).await.unwrap();
+        assert_eq!((usage.pending, usage.in_flight), (2, 0));
+
+        // Only the job without dependencies can be claimed
+        let claimed = scheduler.claim_job(claim_request(&hotkey)).await.unwrap();
+        assert_eq!(claimed.job.id, first.id);
+        assert_eq!(
+            claimed.job.validator_hotkey.as_deref(),
+            Some(hotkey.as_str())
+        );
+        assert!(scheduler.claim_job(claim_request(&hotkey)).await.is_err());
+
+        // A failure expecting the job as it was before the claim is refused
+        let err = scheduler
+            .fail_job(
+                first.id,
+                FailJobRequest {
+                    reason: "stale".to_string(),
+                    error_details: None,
+                    failure_category: None,
+                    expected_version: Some(first.version),
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PlatformError::Conflict { .. }), "{}", err);
+
+        scheduler
+            .complete_job(
+                first.id,
+                SubmitResultRequest {
+                    job_id: first.id,
+                    result: EvalResult {
+                        job_id: first.id,
+                        submission_id: Uuid::new_v4(),
+                        scores: Default::default(),
+                        metrics: Default::default(),
+                        logs: vec![],
+                        error: None,
+                        execution_time: 10,
+                        resource_usage: ResourceUsage {
+                            cpu_time: 0,
+                            memory_peak: 0,
+                            disk_usage: 0,
+                            network_bytes: 0,
+                        },
+                        attestation_receipt: Some("attestation".to_string()),
+                        schema_version: EVAL_RESULT_SCHEMA_VERSION,
+                    },
+                    receipts: vec!["receipt".to_string()],
+                    expected_version: Some(claimed.job.version),
+                },
+            )
+            .await
+            .unwrap();
+        let completed = scheduler.get_job(first.id).await.unwrap();
+        assert_eq!(completed.status, JobStatus::Completed);
+        assert!(completed.completed_at.is_some());
+        let receipts = scheduler.get_job_receipts(first.id).await.unwrap();
+        assert_eq!(receipts.receipts, vec!["receipt"]);
+        assert_eq!(receipts.attestation_receipt.as_deref(), Some("attestation"));
+        assert_eq!(receipts.completed_at, completed.completed_at);
+
+        // Completing the dependency released the second job
+        let claimed = scheduler
+            .claim_specific_job(second.id, claim_request(&hotkey))
+            .await
+            .unwrap();
+        assert_eq!(claimed.job.status, JobStatus::Claimed);
+        scheduler
+            .fail_job(
+                second.id,
+                FailJobRequest {
+                    reason: "exit code 1".to_string(),
+                    error_details: None,
+                    failure_category: Some(FailureCategory::ExecutionError),
+                    expected_version: None,
+                },
+            )
+            .await
+            .unwrap();
+        let failed = scheduler.get_job(second.id).await.unwrap();
+        assert_eq!(failed.status, JobStatus::DeadLettered);
+        assert_eq!(
+            failed.failure_category,
+            Some(FailureCategory::ExecutionError)
+        );
+        assert_eq!(scheduler.retry_failed_jobs().await.unwrap(), 0);
+
+        let requeued = scheduler.requeue_job(second.id, "admin").await.unwrap();
+        assert_eq!(requeued.status, JobStatus::Pending);
+        let reprioritized = scheduler
+            .set_job_priority(second.id, JobPriority::Critical, "admin")
+            .await
+            .unwrap();
+        assert_eq!(reprioritized.priority, JobPriority::Critical);
+
+        let listed = scheduler
+            .list_jobs(1, 10, None, Some(challenge_id), &JobSearch::default())
+            .await
+            .unwrap();
+        assert_eq!(listed.total, 2);
+        assert_eq!(listed.jobs[0].id, second.id);
+        let counts = scheduler
+            .get_challenge_job_counts(challenge_id)
+            .await
+            .unwrap();
+        assert_eq!((counts.pending, counts.completed), (1, 1));
+    }
+
+    /// Dead-letters a job of `challenge_id` with a chain of jobs blocked on it
+    async fn dead_lettered_dependency(scheduler: SchedulerService, challenge_id: Uuid) {
+        let mut public_key = [0; 32];
+        public_key[..16].copy_from_slice(challenge_id.as_bytes());
+        let hotkey = Hotkey::from_public_key(&public_key);
+
+        let first = scheduler
+            .create_job(create_request(challenge_id, vec![]))
+            .await
+            .unwrap();
+        let second = scheduler
+            .create_job(create_request(challenge_id, vec![first.id]))
+            .await
+            .unwrap();
+        let third = scheduler
+            .create_job(create_request(challenge_id, vec![second.id]))
+            .await
+            .unwrap();
+
+        // The jobs blocked on the dead-lettered job, directly or not, go with it
+        let claimed = scheduler.claim_job(claim_request(&hotkey)).await.unwrap();
+        assert_eq!(claimed.job.id, first.id);
+        scheduler
+            .fail_job(first.id, failure_request(claimed.job.version))
+            .await
+            .unwrap();
+        for job in [first.id, second.id, third.id] {
+            let job = scheduler.get_job(job).await.unwrap();
+            assert_eq!(job.status, JobStatus::DeadLettered);
+            assert!(job.completed_at.is_some());
+        }
+        let dead_lettered = scheduler.list_dead_lettered_jobs(1, 10).await.unwrap();
+        let dependent = dead_lettered
+            .jobs
+            .iter()
+            .find(|j| j.job.id == third.id)
+            .unwrap();
+        assert_eq!(dependent.job.version, third.version + 1);
+
+        // Requeued, they are blocked until their dependencies complete
+        let requeued = scheduler.requeue_job(second.id, "admin").await.unwrap();
+        assert_eq!(requeued.status, JobStatus::Blocked);
+        let reset = scheduler.reset_job(third.id, "admin").await.unwrap();
+        assert_eq!(reset.status, JobStatus::Blocked);
+        let requeued = scheduler.requeue_job(first.id, "admin").await.unwrap();
+        assert_eq!(requeued.status, JobStatus::Pending);
+
+        let claimed = scheduler.claim_job(claim_request(&hotkey)).await.unwrap();
+        assert_eq!(claimed.job.id, first.id);
+        scheduler
+            .complete_job(first.id, result_request(first.id, claimed.job.version))
+            .await
+            .unwrap();
+        assert_eq!(
+            scheduler.get_job(second.id).await.unwrap().status,
+            JobStatus::Pending
+        );
+        assert_eq!(
+            scheduler.get_job(third.id).await.unwrap().status,
+            JobStatus::Blocked
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dead_lettered_dependency_in_memory() {
+        let challenge_id = Uuid::new_v4();
+        let scheduler = SchedulerService::new(&config(challenge_id)).unwrap();
+        dead_lettered_dependency(scheduler, challenge_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_dead_lettered_dependency_in_postgres() {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = Arc::new(PgPool::connect(&database_url).await.unwrap());
+        let challenge_id = Uuid::new_v4();
+        let scheduler = SchedulerService::with_database(&config(challenge_id), pool).unwrap();
+        dead_lettered_dependency(scheduler, challenge_id).await;
+    }
+
+    fn result_request(job_id: Uuid, expected_version: u64) -> SubmitResultRequest {
+        SubmitResultRequest {
+            job_id,
+            result: EvalResult {
+                job_id,
+                submission_id: Uuid::new_v4(),
+                scores: Default::default(),
+                metrics: Default::default(),
+                logs: vec![],
+                error: None,
+                execution_time: 10,
+                resource_usage: ResourceUsage {
+                    cpu_time: 0,
+                    memory_peak: 0,
+                    disk_usage: 0,
+                    network_bytes: 0,
+                },
+                attestation_receipt: None,
+                schema_version: EVAL_RESULT_SCHEMA_VERSION,
+            },
+            receipts: vec![],
+            expected_version: Some(expected_version),
+        }
+    }
+
+    fn failure_request(expected_version: u64) -> FailJobRequest {
+        FailJobRequest {
+            reason: "exit code 1".to_string(),
+            error_details: None,
+            failure_category: Some(FailureCategory::ExecutionError),
+            expected_version: Some(expected_version),
+        }
+    }
+
+    /// Races updates of a job of `challenge_id`, each made at the version its
+    /// validator claimed the job at
+    async fn concurrent_updates(scheduler: SchedulerService, challenge_id: Uuid) {
+        let hotkey = |i: u8| {
+            let mut public_key = [i; 32];
+            public_key[..16].copy_from_slice(challenge_id.as_bytes());
+            Hotkey::from_public_key(&public_key)
+        };
+        let job = scheduler
+            .create_job(create_request(challenge_id, vec![]))
+            .await
+            .unwrap();
+
+        // Validator 1 claims the job, an operator resets it and validator 2
+        // claims it again
+        let first = scheduler
+            .claim_job(claim_request(&hotkey(1)))
+            .await
+            .unwrap();
+        scheduler.reset_job(job.id, "admin").await.unwrap();
+        let second = scheduler
+            .claim_job(claim_request(&hotkey(2)))
+            .await
+            .unwrap();
+        assert_eq!(second.job.id, job.id);
+        assert!(second.job.version > first.job.version);
+
+        // The late result of validator 1 races the result of validator 2:
+        // only the latter applies
+        let (late, current) = tokio::join!(
+            scheduler.complete_job(job.id, result_request(job.id, first.job.version)),
+            scheduler.complete_job(job.id, result_request(job.id, second.job.version)),
+        );
+        let err = late.unwrap_err();
+        assert!(matches!(err, PlatformError::Conflict { .. }), "{}", err);
+        current.unwrap();
+
+        // A failure of validator 2 racing its own result, both at the version
+        // it claimed: one of them applies, the other conflicts
+        let job = scheduler
+            .create_job(create_request(challenge_id, vec![]))
+            .await
+            .unwrap();
+        let claimed = scheduler
+            .claim_job(claim_request(&hotkey(2)))
+            .await
+            .unwrap();
+        assert_eq!(claimed.job.id, job.id);
+        let (completed, failed) = tokio::join!(
+            scheduler.complete_job(job.id, result_request(job.id, claimed.job.version)),
+            scheduler.fail_job(job.id, failure_request(claimed.job.version)),
+        );
+        let conflicts = [completed.err(), failed.err()]
+            .into_iter()
+            .flatten()
+            .inspect(|err| assert!(matches!(err, PlatformError::Conflict { .. }), "{}", err))
+            .count();
+        assert_eq!(conflicts, 1);
+        let status = scheduler.get_job(job.id).await.unwrap().status;
+        assert!(
+            matches!(status, JobStatus::Completed | JobStatus::DeadLettered),
+            "{:?}",
+            status
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_updates_in_memory() {
+        let challenge_id = Uuid::new_v4();
+        let scheduler = SchedulerService::new(&config(challenge_id)).unwrap();
+        concurrent_updates(scheduler, challenge_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_concurrent_updates_in_postgres() {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = Arc::new(PgPool::connect(&database_url).await.unwrap());
+        let challenge_id = Uuid::new_v4();
+        let scheduler = SchedulerService::with_database(&config(challenge_id), pool).unwrap();
+        concurrent_updates(scheduler, challenge_id).await;
+    }
+
+    #[tokio::test]
+    async fn test_job_lifecycle_in_memory() {
+        let challenge_id = Uuid::new_v4();
+        let scheduler = SchedulerService::new(&config(challenge_id)).unwrap();
+        job_lifecycle(scheduler, challenge_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database at DATABASE_URL
+    async fn test_job_lifecycle_in_postgres() {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set");
+        let pool = Arc::new(PgPool::connect(&database_url).await.unwrap());
+        let challenge_id = Uuid::new_v4();
+        let scheduler = SchedulerService::with_database(&config(challenge_id), pool).unwrap();
+        job_lifecycle(scheduler, challenge_id).await;
+    }
+}
@@ -0,0 +1,1219 @@
+//! PostgreSQL job store
+
+use super::{CompletedJob, FailedJob, JobClaim, JobProgress, JobStore};
+use crate::{
+    capacity::check_capacity,
+    jobs::{
+        check_pending, jobs_per_challenge, JobChange, IN_FLIGHT_STATUSES, PENDING_STATUSES,
+        SCHEDULER_ACTOR,
+    },
+    rows::{DeadLetteredJobRow, JobRow},
+    types::{JobAdminError, JobConflict, JobSearch, SchedulerConfig, TestResultData},
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use platform_api_models::*;
+use sqlx::{PgConnection, PgPool};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Columns of the `jobs` table read into a [`JobRow`]
+const JOB_COLUMNS: &str = r#"
+    id, challenge_id, validator_hotkey, status, priority, runtime,
+    created_at, claimed_at, started_at, completed_at, timeout_at, timeout_secs,
+    retry_count, max_retries, payload, failure_category, depends_on, deadline, version,
+    required_capabilities, resources
+"#;
+
+/// Job store backed by the `jobs` table
+///
+/// Claims and creations lock their validator and challenges with
+/// transaction-scoped advisory locks, so that several API instances can share
+/// the database.
+pub struct PostgresJobStore {
+    pool: Arc<PgPool>,
+}
+
+impl PostgresJobStore {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+}
+
+/// Check that `adding` more pending jobs of each challenge fit in its quota
+///
+/// Takes a transaction-scoped advisory lock per challenge, so that
+/// concurrent creations for the same challenge are counted one after the
+/// other. The jobs must be inserted in the same transaction.
+async fn check_pending_quota(
+    conn: &mut PgConnection,
+    config: &SchedulerConfig,
+    adding: &BTreeMap<Id, u64>,
+) -> Result<()> {
+    // Iterating in key order takes the locks in the same order in every transaction
+    for (challenge_id, count) in adding {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1::text, 0))")
+            .bind(challenge_id)
+            .execute(&mut *conn)
+            .await?;
+
+        let pending: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM jobs WHERE challenge_id = $1 AND status = ANY($2)",
+        )
+        .bind(challenge_id)
+        .bind(&PENDING_STATUSES[..])
+        .fetch_one(&mut *conn)
+        .await?;
+
+        check_pending(config, *challenge_id, pending as u64, *count)?;
+    }
+
+    Ok(())
+}
+
+/// Reserve one of the job slots of `validator_hotkey` for a claim made on `conn`
+///
+/// Takes a transaction-scoped advisory lock per validator and counts its
+/// jobs in flight, so that concurrent claims by the same validator reserve
+/// one after the other. The job must be claimed in the same transaction;
+/// its slot is released when it leaves `IN_FLIGHT_STATUSES`, i.e. when it
+/// completes, fails or times out. Validators without a capacity are not limited.
+async fn reserve_validator_slot(
+    conn: &mut PgConnection,
+    capacity: Option<u32>,
+    validator_hotkey: &str,
+) -> Result<()> {
+    let Some(capacity) = capacity else {
+        return Ok(());
+    };
+
+    // Seed 1 keeps validator locks apart from the per-challenge quota locks
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1::text, 1))")
+        .bind(validator_hotkey)
+        .execute(&mut *conn)
+        .await?;
+
+    let in_flight: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM jobs WHERE validator_hotkey = $1 AND status = ANY($2)",
+    )
+    .bind(validator_hotkey)
+    .bind(&IN_FLIGHT_STATUSES[..])
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(check_capacity(validator_hotkey, capacity, in_flight as u64)?)
+}
+
+/// Current version of job `job_id`, if it exists
+///
+/// Tells why an update made at a version matched no row.
+async fn job_version<'e, E>(executor: E, job_id: Uuid) -> Result<Option<u64>>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let version: Option<i64> = sqlx::query_scalar("SELECT version FROM jobs WHERE id = $1")
+        .bind(job_id)
+        .fetch_optional(executor)
+        .await?;
+    Ok(version.map(|v| v as u64))
+}
+
+/// Record a status transition of job `job_id` in `job_events`
+///
+/// Made in the transaction of the transition, so that it is recorded if and
+/// only if the transition is.
+async fn insert_job_event<'e, E>(
+    executor: E,
+    job_id: Uuid,
+    old_status: Option<&str>,
+    new_status: &str,
+    actor: Option<&str>,
+    metadata: serde_json::Value,
+) -> Result<()>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO job_events (job_id, old_status, new_status, actor, metadata)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(job_id)
+    .bind(old_status)
+    .bind(new_status)
+    .bind(actor)
+    .bind(metadata)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Insert a new job row
+async fn insert_job<'e, E>(executor: E, job: &JobMetadata) -> Result<()>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO jobs (
+            id, challenge_id, status, priority, runtime, payload,
+            created_at, timeout_at, timeout_secs, retry_count, max_retries, depends_on,
+            deadline, required_capabilities, resources
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        "#,
+    )
+    .bind(job.id)
+    .bind(job.challenge_id)
+    .bind(job.status.as_str())
+    .bind(job.priority.as_str())
+    .bind(job.runtime.to_string())
+    .bind(&job.payload)
+    .bind(job.created_at)
+    .bind(job.timeout_at)
+    .bind(job.timeout.map(|secs| secs as i64))
+    .bind(job.retry_count as i32)
+    .bind(job.max_retries as i32)
+    .bind(&job.depends_on)
+    .bind(job.deadline)
+    .bind(&job.required_capabilities)
+    .bind(serde_json::to_value(&job.resources)?)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Extract test result data from Terminal-Bench result JSON
+fn extract_test_result(test_result: &serde_json::Value) -> Result<TestResultData> {
+    let task_id = test_result
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing task_id"))?
+        .to_string();
+
+    let test_name = test_result
+        .get("test_name")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let is_resolved = test_result
+        .get("is_resolved")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let status = if is_resolved {
+        "passed".to_string()
+    } else if test_result.get("error").is_some() {
+        "error".to_string()
+    } else {
+        "failed".to_string()
+    };
+
+    let error_message = test_result
+        .get("error")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let execution_time_ms = test_result
+        .get("execution_time_ms")
+        .or_else(|| test_result.get("execution_time"))
+        .and_then(|v| v.as_i64());
+
+    let output_text = test_result
+        .get("output")
+        .or_else(|| test_result.get("output_text"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let logs = test_result
+        .get("logs")
+        .cloned()
+        .unwrap_or(serde_json::Value::Null);
+
+    let metrics = test_result
+        .get("metrics")
+        .cloned()
+        .unwrap_or(serde_json::Value::Null);
+
+    Ok(TestResultData {
+        task_id,
+        test_name,
+        status,
+        is_resolved,
+        error_message,
+        execution_time_ms,
+        output_text,
+        logs,
+        metrics,
+    })
+}
+
+#[async_trait::async_trait]
+impl JobStore for PostgresJobStore {
+    async fn insert_jobs(&self, config: &SchedulerConfig, jobs: &[JobMetadata]) -> Result<()> {
+        // Dropping the transaction on error rolls back every insert
+        let mut tx = self.pool.begin().await?;
+        check_pending_quota(&mut tx, config, &jobs_per_challenge(jobs)).await?;
+        for job in jobs {
+            insert_job(&mut *tx, job).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_job(&self, id: Uuid) -> Result<Option<JobMetadata>> {
+        let row =
+            sqlx::query_as::<_, JobRow>(&format!("SELECT {} FROM jobs WHERE id = $1", JOB_COLUMNS))
+                .bind(id)
+                .fetch_optional(self.pool.as_ref())
+                .await?;
+
+        Ok(row.map(Into::into))
+    }
+
+    async fn list_jobs(
+        &self,
+        page: u32,
+        per_page: u32,
+        status: Option<&str>,
+        challenge_id: Option<Uuid>,
+        search: &JobSearch,
+    ) -> Result<JobListResponse> {
+        let offset = page.saturating_sub(1) as i64 * per_page as i64;
+        let pattern = search.like_pattern();
+
+        // Every filter is skipped when its parameter is NULL
+        const FILTERS: &str = r#"
+            WHERE ($1::TEXT IS NULL OR status = $1)
+              AND ($2::UUID IS NULL OR challenge_id = $2)
+              AND ($3::TEXT IS NULL OR CASE
+                  WHEN jsonb_typeof(payload) = 'object' THEN EXISTS (
+                      SELECT 1 FROM jsonb_each_text(payload) AS field
+                      WHERE field.key ILIKE $3 OR field.value ILIKE $3
+                  )
+                  ELSE payload::text ILIKE $3
+              END)
+              AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)
+              AND ($5::TIMESTAMPTZ IS NULL OR created_at < $5)
+        "#;
+
+        let rows = sqlx::query_as::<_, JobRow>(&format!(
+            r#"
+            SELECT {}
+            FROM jobs
+            {}
+            ORDER BY created_at DESC
+            LIMIT $6 OFFSET $7
+            "#,
+            JOB_COLUMNS, FILTERS
+        ))
+        .bind(status)
+        .bind(challenge_id)
+        .bind(&pattern)
+        .bind(search.created_after)
+        .bind(search.created_before)
+        .bind(per_page as i64)
+        .bind(offset)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM jobs {}", FILTERS))
+            .bind(status)
+            .bind(challenge_id)
+            .bind(&pattern)
+            .bind(search.created_after)
+            .bind(search.created_before)
+            .fetch_one(self.pool.as_ref())
+            .await?;
+
+        Ok(JobListResponse {
+            jobs: rows.into_iter().map(Into::into).collect(),
+            total: total as u64,
+            page,
+            per_page,
+        })
+    }
+
+    async fn dependency_completion(&self, ids: &[Id]) -> Result<HashMap<Id, bool>> {
+        let rows =
+            sqlx::query_as::<_, (Uuid, String)>("SELECT id, status FROM jobs WHERE id = ANY($1)")
+                .bind(ids)
+                .fetch_all(self.pool.as_ref())
+                .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, status)| (id, status == "completed"))
+            .collect())
+    }
+
+    async fn challenge_usage(&self, challenge_id: Id) -> Result<(u64, u64)> {
+        let (pending, in_flight) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT COUNT(*) FILTER (WHERE status = ANY($2)),
+                   COUNT(*) FILTER (WHERE status = ANY($3))
+            FROM jobs
+            WHERE challenge_id = $1
+            "#,
+        )
+        .bind(challenge_id)
+        .bind(&PENDING_STATUSES[..])
+        .bind(&IN_FLIGHT_STATUSES[..])
+        .fetch_one(self.pool.as_ref())
+        .await?;
+
+        Ok((pending as u64, in_flight as u64))
+    }
+
+    async fn challenge_job_counts(&self, challenge_id: Uuid) -> Result<ChallengeJobCounts> {
+        let rows = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT status, COUNT(*) FROM jobs
+            WHERE challenge_id = $1 AND status IN ('pending', 'running', 'completed')
+            GROUP BY status
+            "#,
+        )
+        .bind(challenge_id)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        let mut counts = ChallengeJobCounts::default();
+        for (status, count) in rows {
+            match status.as_str() {
+                "pending" => counts.pending = count as u64,
+                "running" => counts.running = count as u64,
+                "completed" => counts.completed = count as u64,
+                _ => {}
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn job_stats(&self) -> Result<JobStats> {
+        let pool = self.pool.as_ref();
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs")
+            .fetch_one(pool)
+            .await?;
+
+        let pending: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE status = 'pending'")
+            .fetch_one(pool)
+            .await?;
+
+        let running: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE status = 'running'")
+            .fetch_one(pool)
+            .await?;
+
+        let completed: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE status = 'completed'")
+                .fetch_one(pool)
+                .await?;
+
+        let failed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE status = 'failed'")
+            .fetch_one(pool)
+            .await?;
+
+        let category_rows = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT failure_category, COUNT(*) FROM jobs
+            WHERE status = 'failed' AND failure_category IS NOT NULL
+            GROUP BY failure_category
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let failures_by_category: HashMap<String, u64> = category_rows
+            .into_iter()
+            .map(|(category, count)| (category, count as u64))
+            .collect();
+
+        Ok(JobStats {
+            total_jobs: total as u64,
+            pending_jobs: pending as u64,
+            running_jobs: running as u64,
+            completed_jobs: completed as u64,
+            failed_jobs: failed as u64,
+            avg_execution_time: 0.0,
+            success_rate: if total > 0 {
+                completed as f64 / total as f64
+            } else {
+                0.0
+            },
+            failures_by_category,
+        })
+    }
+
+    async fn job_receipts(&self, job_id: Uuid) -> Result<Option<JobReceipts>> {
+        let row = sqlx::query_as::<
+            _,
+            (
+                Option<String>,
+                Option<DateTime<Utc>>,
+                Vec<String>,
+                Option<String>,
+            ),
+        >(
+            r#"
+            SELECT validator_hotkey, completed_at, receipts, result->>'attestation_receipt'
+            FROM jobs WHERE id = $1
+            "#,
+        )
+        .bind(job_id)
+        .fetch_optional(self.pool.as_ref())
+        .await?;
+
+        Ok(row.map(
+            |(validator_hotkey, completed_at, receipts, attestation_receipt)| JobReceipts {
+                job_id,
+                validator_hotkey,
+                completed_at,
+                receipts,
+                attestation_receipt,
+            },
+        ))
+    }
+
+    async fn claim_job(&self, claim: JobClaim<'_>) -> Result<Option<JobMetadata>> {
+        let config = claim.config;
+        let validator = claim.validator;
+        let registration = validator.registration.as_ref();
+
+        // Concurrent claims by this validator wait here until this one commits
+        let mut tx = self.pool.begin().await?;
+        reserve_validator_slot(&mut tx, validator.capacity, &validator.hotkey).await?;
+
+        // Lock the pending jobs of highest effective priority, see
+        // `SchedulerConfig::effective_priority`. Effective trust then shifts
+        // the base priority: trusted validators are routed high-priority jobs
+        // ahead, untrusted or unreliable ones low-priority jobs. Jobs of challenges that already
+        // have their quota of jobs in flight are skipped, and so are jobs
+        // requiring capabilities the validator did not claim with or more
+        // resources than it registered, as `ValidatorInfo::has_resources_for`
+        // would. The candidate selected among them, see `Scorer`, is claimed.
+        let (quota_challenges, quota_limits): (Vec<Uuid>, Vec<i64>) = config
+            .challenge_quotas
+            .iter()
+            .map(|(id, quota)| (*id, quota.max_in_flight as i64))
+            .unzip();
+        let candidates: Vec<JobMetadata> = sqlx::query_as::<_, JobRow>(&format!(
+            r#"
+            SELECT {}
+            FROM jobs
+            WHERE status = 'pending'
+              AND (SELECT COUNT(*) FROM jobs in_flight
+                   WHERE in_flight.challenge_id = jobs.challenge_id
+                     AND in_flight.status = ANY($7))
+                  < COALESCE((SELECT quota.max_in_flight
+                              FROM UNNEST($8::uuid[], $9::bigint[])
+                                   AS quota(challenge_id, max_in_flight)
+                              WHERE quota.challenge_id = jobs.challenge_id), $10)
+              AND required_capabilities <@ $12
+              AND (NOT $13 OR (
+                  COALESCE((resources->>'vcpu')::NUMERIC <= $14, TRUE)
+                  AND COALESCE((resources->>'memory_gb')::NUMERIC <= $15, TRUE)
+                  AND COALESCE((resources->>'gpu_count')::NUMERIC,
+                               CASE WHEN resources->>'gpu_type' IS NULL THEN 0 ELSE 1 END) <= $16
+                  AND COALESCE(LOWER(resources->>'gpu_type') = LOWER($17),
+                               resources->>'gpu_type' IS NULL)
+                  AND COALESCE((resources->>'disk_gb')::NUMERIC <= $18,
+                               resources->>'disk_gb' IS NULL)
+              ))
+            ORDER BY (CASE priority
+                          WHEN 'critical' THEN 3.0
+                          WHEN 'high' THEN 2.0
+                          WHEN 'normal' THEN 1.0
+                          ELSE 0.0
+                      END)
+                     + (CASE priority
+                            WHEN 'critical' THEN 1.5
+                            WHEN 'high' THEN 0.5
+                            WHEN 'normal' THEN -0.5
+                            ELSE -1.5
+                        END) * ($2 - 0.5)
+                     + LEAST(EXTRACT(EPOCH FROM ($1 - created_at))::DOUBLE PRECISION * $3, $4)
+                     + COALESCE(LEAST(GREATEST(
+                           1.0 - EXTRACT(EPOCH FROM (deadline - $1))::DOUBLE PRECISION / $5,
+                           0.0), 1.0), 0.0) * $6 DESC,
+                     created_at ASC
+            LIMIT $11
+            FOR UPDATE SKIP LOCKED
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(claim.now)
+        .bind(validator.effective_trust())
+        .bind(config.aging_rate())
+        .bind(config.max_aging_boost)
+        .bind(config.deadline_window_secs.max(1) as f64)
+        .bind(config.deadline_boost())
+        .bind(&IN_FLIGHT_STATUSES[..])
+        .bind(&quota_challenges)
+        .bind(&quota_limits)
+        .bind(config.default_challenge_quota.max_in_flight as i64)
+        .bind(claim.candidate_limit as i64)
+        .bind(claim.capabilities)
+        .bind(registration.is_some())
+        .bind(registration.map_or(0, |r| r.cpu_cores as i64))
+        .bind(registration.map_or(0, |r| r.memory_gb.min(i64::MAX as u64) as i64))
+        .bind(registration.map_or(0, |r| r.gpus() as i64))
+        .bind(registration.and_then(|r| r.gpu_type.as_deref()))
+        .bind(registration.and_then(|r| r.disk_gb.map(|disk| disk.min(i64::MAX as u64) as i64)))
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+        let Some(index) = (claim.select)(&candidates.iter().collect::<Vec<_>>()) else {
+            return Ok(None);
+        };
+        let timeout = config.job_timeout(&candidates[index]);
+        let row = sqlx::query_as::<_, JobRow>(&format!(
+            r#"
+            UPDATE jobs
+            SET status = 'claimed',
+                validator_hotkey = $1,
+                claimed_at = $2,
+                timeout_at = $3,
+                version = version + 1
+            WHERE id = $4
+            RETURNING {}
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(validator.hotkey.as_str())
+        .bind(claim.now)
+        .bind(claim.now + chrono::Duration::seconds(timeout as i64))
+        .bind(candidates[index].id)
+        .fetch_one(&mut *tx)
+        .await?;
+        insert_job_event(
+            &mut *tx,
+            row.id,
+            Some("pending"),
+            "claimed",
+            Some(validator.hotkey.as_str()),
+            serde_json::json!({}),
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(row.into()))
+    }
+
+    async fn claim_specific_job(
+        &self,
+        job_id: Uuid,
+        version: u64,
+        validator_hotkey: &str,
+        capacity: Option<u32>,
+        timeout: u64,
+        now: DateTime<Utc>,
+    ) -> Result<JobMetadata> {
+        let mut tx = self.pool.begin().await?;
+        reserve_validator_slot(&mut tx, capacity, validator_hotkey).await?;
+        let row = sqlx::query_as::<_, JobRow>(&format!(
+            r#"
+            UPDATE jobs
+            SET status = 'claimed',
+                validator_hotkey = $1,
+                claimed_at = $2,
+                timeout_at = $3,
+                version = version + 1
+            WHERE id = $4 AND status = 'pending' AND version = $5
+            RETURNING {}
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(validator_hotkey)
+        .bind(now)
+        .bind(now + chrono::Duration::seconds(timeout as i64))
+        .bind(job_id)
+        .bind(version as i64)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(row) = row else {
+            if job_version(&mut *tx, job_id)
+                .await?
+                .is_some_and(|v| v != version)
+            {
+                return Err(PlatformError::from(JobConflict {
+                    job_id,
+                    expected: version,
+                })
+                .into());
+            }
+            return Err(PlatformError::conflict("Job not available or already claimed").into());
+        };
+        insert_job_event(
+            &mut *tx,
+            job_id,
+            Some("pending"),
+            "claimed",
+            Some(validator_hotkey),
+            serde_json::json!({}),
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(row.into())
+    }
+
+    async fn complete_job(
+        &self,
+        job_id: Uuid,
+        version: u64,
+        result: &serde_json::Value,
+        receipts: &[String],
+        mut details: serde_json::Value,
+        now: DateTime<Utc>,
+    ) -> Result<CompletedJob> {
+        let progress = JobProgress::of(result);
+        let mut tx = self.pool.begin().await?;
+
+        // Update job with progress metrics
+        let completed = sqlx::query_as::<_, (String, Option<String>, Uuid)>(
+            r#"
+            UPDATE jobs
+            SET status = 'completed',
+                started_at = COALESCE(started_at, $1),
+                completed_at = $1,
+                result = $2,
+                progress_percent = $4,
+                total_tasks = $5,
+                completed_tasks = $6,
+                resolved_tasks = $7,
+                unresolved_tasks = $8,
+                receipts = $10,
+                version = jobs.version + 1
+            FROM (SELECT id, status FROM jobs
+                  WHERE id = $3 AND version = $9 FOR UPDATE) AS prev
+            WHERE jobs.id = prev.id
+            RETURNING prev.status, jobs.validator_hotkey, jobs.challenge_id
+            "#,
+        )
+        .bind(now)
+        .bind(result)
+        .bind(job_id)
+        .bind(progress.percent)
+        .bind(progress.total_tasks)
+        .bind(progress.completed_tasks)
+        .bind(progress.resolved_tasks)
+        .bind(progress.unresolved_tasks)
+        .bind(version as i64)
+        .bind(receipts)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((old_status, validator_hotkey, challenge_id)) = completed else {
+            return Err(match job_version(&mut *tx, job_id).await? {
+                Some(_) => PlatformError::from(JobConflict {
+                    job_id,
+                    expected: version,
+                }),
+                None => PlatformError::not_found("job", job_id),
+            }
+            .into());
+        };
+        details["progress_percent"] = serde_json::json!(progress.percent);
+        insert_job_event(
+            &mut *tx,
+            job_id,
+            Some(&old_status),
+            "completed",
+            validator_hotkey.as_deref(),
+            details,
+        )
+        .await?;
+
+        // Release dependents whose last outstanding dependency was this job
+        let unblocked = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE jobs
+            SET status = 'pending',
+                version = version + 1
+            WHERE status = 'blocked'
+              AND $1 = ANY(depends_on)
+              AND NOT EXISTS (
+                  SELECT 1 FROM jobs dep
+                  WHERE dep.id = ANY(jobs.depends_on) AND dep.status <> 'completed'
+              )
+            RETURNING id
+            "#,
+        )
+        .bind(job_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        for dependent in &unblocked {
+            insert_job_event(
+                &mut *tx,
+                *dependent,
+                Some("blocked"),
+                "pending",
+                Some(SCHEDULER_ACTOR),
+                serde_json::json!({ "completed_dependency": job_id }),
+            )
+            .await?;
+        }
+
+        // Extract and store individual test results
+        if let Some(results_array) = result
+            .get("results")
+            .and_then(|r| r.get("results"))
+            .and_then(|r| r.as_array())
+        {
+            for test_result in results_array {
+                if let Ok(test_data) = extract_test_result(test_result) {
+                    sqlx::query(
+                        r#"
+                        INSERT INTO job_test_results (
+                            job_id, challenge_id, task_id, test_name, status,
+                            is_resolved, error_message, execution_time_ms,
+                            output_text, logs, metrics
+                        )
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                        "#,
+                    )
+                    .bind(job_id)
+                    .bind(challenge_id)
+                    .bind(&test_data.task_id)
+                    .bind(test_data.test_name.as_deref())
+                    .bind(&test_data.status)
+                    .bind(test_data.is_resolved)
+                    .bind(test_data.error_message.as_deref())
+                    .bind(test_data.execution_time_ms)
+                    .bind(test_data.output_text.as_deref())
+                    .bind(&test_data.logs)
+                    .bind(&test_data.metrics)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+        tx.commit().await?;
+
+        Ok(CompletedJob {
+            challenge_id,
+            old_status: JobStatus::from(old_status.as_str()),
+            validator_hotkey,
+            unblocked,
+        })
+    }
+
+    async fn fail_job(
+        &self,
+        job_id: Uuid,
+        version: u64,
+        request: &FailJobRequest,
+        actor: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Option<FailedJob>> {
+        let mut tx = self.pool.begin().await?;
+        let transition = sqlx::query_as::<_, (String, String, Option<String>, i32, Uuid)>(
+            r#"
+            UPDATE jobs
+            SET status = CASE WHEN retry_count >= max_retries
+                              THEN 'dead_lettered' ELSE 'failed' END,
+                error_message = $1,
+                completed_at = $2,
+                failure_category = $4,
+                dead_lettered_at = CASE WHEN retry_count >= max_retries
+                                        THEN $2 ELSE NULL END,
+                retry_history = retry_history || jsonb_build_array(jsonb_build_object(
+                    'attempt', retry_count + 1,
+                    'validator_hotkey', validator_hotkey,
+                    'reason', $1::text,
+                    'error_details', $5::text,
+                    'failure_category', $4::text,
+                    'failed_at', $2::timestamptz
+                )),
+                version = jobs.version + 1
+            FROM (SELECT id, status FROM jobs
+                  WHERE id = $3 AND version = $6 FOR UPDATE) AS prev
+            WHERE jobs.id = prev.id
+            RETURNING prev.status, jobs.status, jobs.validator_hotkey, jobs.retry_count,
+                      jobs.challenge_id
+            "#,
+        )
+        .bind(&request.reason)
+        .bind(now)
+        .bind(job_id)
+        .bind(request.failure_category.as_ref().map(|c| c.to_string()))
+        .bind(request.error_details.as_deref())
+        .bind(version as i64)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((old_status, status, validator_hotkey, retry_count, challenge_id)) = transition
+        else {
+            if job_version(&mut *tx, job_id).await?.is_some() {
+                return Err(PlatformError::from(JobConflict {
+                    job_id,
+                    expected: version,
+                })
+                .into());
+            }
+            return Ok(None);
+        };
+        let attempt = retry_count as u32 + 1;
+        insert_job_event(
+            &mut *tx,
+            job_id,
+            Some(&old_status),
+            &status,
+            actor.or(validator_hotkey.as_deref()),
+            serde_json::json!({
+                "reason": request.reason,
+                "failure_category": request.failure_category,
+                "attempt": attempt,
+            }),
+        )
+        .await?;
+
+        // Dead-letter the jobs that can no longer be unblocked
+        let mut dead_lettered_dependents = vec![];
+        if status == "dead_lettered" {
+            dead_lettered_dependents = sqlx::query_scalar::<_, Uuid>(
+                r#"
+                WITH RECURSIVE dependents AS (
+                    SELECT id FROM jobs
+                    WHERE status = 'blocked' AND $1 = ANY(depends_on)
+                    UNION
+                    SELECT jobs.id FROM jobs
+                    JOIN dependents ON dependents.id = ANY(jobs.depends_on)
+                    WHERE jobs.status = 'blocked'
+                )
+                UPDATE jobs
+                SET status = 'dead_lettered',
+                    error_message = 'Dependency ' || $1::text || ' was dead-lettered',
+                    completed_at = $2,
+                    dead_lettered_at = $2,
+                    version = version + 1
+                WHERE id IN (SELECT id FROM dependents)
+                RETURNING id
+                "#,
+            )
+            .bind(job_id)
+            .bind(now)
+            .fetch_all(&mut *tx)
+            .await?;
+        }
+        for dependent in &dead_lettered_dependents {
+            insert_job_event(
+                &mut *tx,
+                *dependent,
+                Some("blocked"),
+                "dead_lettered",
+                Some(SCHEDULER_ACTOR),
+                serde_json::json!({ "dead_lettered_dependency": job_id }),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(Some(FailedJob {
+            challenge_id,
+            old_status: JobStatus::from(old_status.as_str()),
+            status: JobStatus::from(status.as_str()),
+            validator_hotkey,
+            attempt,
+            dead_lettered_dependents,
+        }))
+    }
+
+    async fn expired_jobs(&self, now: DateTime<Utc>) -> Result<Vec<(Uuid, u64)>> {
+        let expired = sqlx::query_as::<_, (Uuid, i64)>(
+            r#"
+            SELECT id, version FROM jobs
+            WHERE status IN ('claimed', 'running')
+              AND timeout_at IS NOT NULL
+              AND timeout_at < $1
+            "#,
+        )
+        .bind(now)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        Ok(expired
+            .into_iter()
+            .map(|(id, version)| (id, version as u64))
+            .collect())
+    }
+
+    async fn validator_jobs_in_flight(&self, hotkey: &str) -> Result<Vec<(Uuid, u64)>> {
+        let jobs = sqlx::query_as::<_, (Uuid, i64)>(
+            r#"
+            SELECT id, version FROM jobs
+            WHERE status IN ('claimed', 'running')
+              AND validator_hotkey = $1
+            "#,
+        )
+        .bind(hotkey)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        Ok(jobs
+            .into_iter()
+            .map(|(id, version)| (id, version as u64))
+            .collect())
+    }
+
+    async fn stale_claims(&self, claimed_before: DateTime<Utc>) -> Result<Vec<(Uuid, u64)>> {
+        let jobs = sqlx::query_as::<_, (Uuid, i64)>(
+            r#"
+            SELECT id, version FROM jobs
+            WHERE status = 'claimed'
+              AND claimed_at < $1
+            "#,
+        )
+        .bind(claimed_before)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        Ok(jobs
+            .into_iter()
+            .map(|(id, version)| (id, version as u64))
+            .collect())
+    }
+
+    async fn retry_failed_jobs(
+        &self,
+        now: DateTime<Utc>,
+        retry_delay: u64,
+    ) -> Result<Vec<(Uuid, u32)>> {
+        let mut tx = self.pool.begin().await?;
+        let retried = sqlx::query_as::<_, (Uuid, i32)>(
+            r#"
+            UPDATE jobs
+            SET status = 'pending',
+                retry_count = retry_count + 1,
+                validator_hotkey = NULL,
+                claimed_at = NULL,
+                started_at = NULL,
+                completed_at = NULL,
+                timeout_at = NULL,
+                version = version + 1
+            WHERE status = 'failed'
+              AND retry_count < max_retries
+              AND completed_at <= $1 - make_interval(secs => COALESCE(
+                  (SELECT (job_defaults->>'retry_delay')::DOUBLE PRECISION
+                   FROM challenges WHERE challenges.id = jobs.challenge_id),
+                  $2
+              ))
+            RETURNING id, retry_count
+            "#,
+        )
+        .bind(now)
+        .bind(retry_delay as f64)
+        .fetch_all(&mut *tx)
+        .await?;
+        for (job_id, retry_count) in &retried {
+            insert_job_event(
+                &mut *tx,
+                *job_id,
+                Some("failed"),
+                "pending",
+                Some(SCHEDULER_ACTOR),
+                serde_json::json!({ "retry_count": retry_count }),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(retried
+            .into_iter()
+            .map(|(id, retry_count)| (id, retry_count as u32))
+            .collect())
+    }
+
+    async fn retry_failed_job(&self, job_id: Uuid, actor: &str) -> Result<Option<u32>> {
+        let mut tx = self.pool.begin().await?;
+        let retry_count = sqlx::query_scalar::<_, i32>(
+            r#"
+            UPDATE jobs
+            SET status = 'pending',
+                retry_count = retry_count + 1,
+                validator_hotkey = NULL,
+                claimed_at = NULL,
+                started_at = NULL,
+                completed_at = NULL,
+                timeout_at = NULL,
+                version = version + 1
+            WHERE id = $1
+              AND status = 'failed'
+              AND retry_count < max_retries
+            RETURNING retry_count
+            "#,
+        )
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(retry_count) = retry_count else {
+            return Ok(None);
+        };
+        insert_job_event(
+            &mut *tx,
+            job_id,
+            Some("failed"),
+            "pending",
+            Some(actor),
+            serde_json::json!({ "retry_count": retry_count }),
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(retry_count as u32))
+    }
+
+    async fn list_dead_lettered_jobs(
+        &self,
+        page: u32,
+        per_page: u32,
+    ) -> Result<DeadLetteredJobListResponse> {
+        let offset = page.saturating_sub(1) as i64 * per_page as i64;
+
+        let rows = sqlx::query_as::<_, DeadLetteredJobRow>(&format!(
+            r#"
+            SELECT {}, error_message, retry_history, dead_lettered_at
+            FROM jobs
+            WHERE status = 'dead_lettered'
+            ORDER BY dead_lettered_at DESC
+            LIMIT $1 OFFSET $2
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(per_page as i64)
+        .bind(offset)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        let total = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM jobs WHERE status = 'dead_lettered'",
+        )
+        .fetch_one(self.pool.as_ref())
+        .await?;
+
+        Ok(DeadLetteredJobListResponse {
+            jobs: rows.into_iter().map(Into::into).collect(),
+            total: total as u64,
+            page,
+            per_page,
+        })
+    }
+
+    async fn requeue_job(&self, job_id: Uuid, actor: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let status = sqlx::query_scalar::<_, String>(
+            r#"
+            UPDATE jobs
+            SET status = CASE WHEN EXISTS (
+                    SELECT 1 FROM jobs dep
+                    WHERE dep.id = ANY(jobs.depends_on) AND dep.status <> 'completed'
+                ) THEN 'blocked' ELSE 'pending' END,
+                retry_count = 0,
+                validator_hotkey = NULL,
+                claimed_at = NULL,
+                started_at = NULL,
+                completed_at = NULL,
+                timeout_at = NULL,
+                dead_lettered_at = NULL,
+                version = version + 1
+            WHERE id = $1 AND status = 'dead_lettered'
+            RETURNING status
+            "#,
+        )
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(status) = status else {
+            return Ok(false);
+        };
+        insert_job_event(
+            &mut *tx,
+            job_id,
+            Some("dead_lettered"),
+            &status,
+            Some(actor),
+            serde_json::json!({}),
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn change_job(
+        &self,
+        job_id: Uuid,
+        change: &JobChange,
+        actor: &str,
+    ) -> Result<JobMetadata> {
+        let mut tx = self.pool.begin().await?;
+        let before: JobMetadata = sqlx::query_as::<_, JobRow>(&format!(
+            "SELECT {} FROM jobs WHERE id = $1 FOR UPDATE",
+            JOB_COLUMNS
+        ))
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| PlatformError::from(JobAdminError::NotFound(job_id)))?
+        .into();
+        change
+            .check(job_id, &before.status)
+            .map_err(PlatformError::from)?;
+
+        let status = match change {
+            JobChange::Priority(priority) => {
+                sqlx::query("UPDATE jobs SET priority = $2, version = version + 1 WHERE id = $1")
+                    .bind(job_id)
+                    .bind(priority.as_str())
+                    .execute(&mut *tx)
+                    .await?;
+                before.status.as_str().to_string()
+            }
+            JobChange::Reset => {
+                sqlx::query_scalar::<_, String>(
+                    r#"
+                    UPDATE jobs
+                    SET status = CASE WHEN EXISTS (
+                            SELECT 1 FROM jobs dep
+                            WHERE dep.id = ANY(jobs.depends_on) AND dep.status <> 'completed'
+                        ) THEN 'blocked' ELSE 'pending' END,
+                        validator_hotkey = NULL,
+                        claimed_at = NULL,
+                        started_at = NULL,
+                        completed_at = NULL,
+                        timeout_at = NULL,
+                        dead_lettered_at = NULL,
+                        version = version + 1
+                    WHERE id = $1
+                    RETURNING status
+                    "#,
+                )
+                .bind(job_id)
+                .fetch_one(&mut *tx)
+                .await?
+            }
+            JobChange::ClearRetries => {
+                sqlx::query("UPDATE jobs SET retry_count = 0, version = version + 1 WHERE id = $1")
+                    .bind(job_id)
+                    .execute(&mut *tx)
+                    .await?;
+                before.status.as_str().to_string()
+            }
+        };
+        insert_job_event(
+            &mut *tx,
+            job_id,
+            Some(before.status.as_str()),
+            &status,
+            Some(actor),
+            change.event_details(&before),
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(before)
+    }
+}
@@ -0,0 +1,245 @@
+//! Validator trust scores derived from historical job performance
+
+use anyhow::{Context, Result};
+use platform_api_models::PlatformResult;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+
+/// Trust assumed for validators that have no computed score yet
+pub const NEUTRAL_TRUST: f64 = 0.5;
+
+/// Execution time at which the speed component is worth half its weight
+const REFERENCE_EXECUTION_TIME_MS: f64 = 60_000.0;
+
+const SUCCESS_WEIGHT: f64 = 0.5;
+const SPEED_WEIGHT: f64 = 0.1;
+const ATTESTATION_WEIGHT: f64 = 0.2;
+const STAKE_WEIGHT: f64 = 0.2;
+
+/// Performance of a validator over a time window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidatorPerformance {
+    pub completed_jobs: i64,
+    pub failed_jobs: i64,
+    /// Average wall time of completed jobs
+    pub avg_execution_time_ms: Option<f64>,
+    /// Jobs whose result or attestation failed validation
+    pub attestation_failures_count: i64,
+}
+
+impl ValidatorPerformance {
+    /// Share of finished jobs that completed
+    ///
+    /// Smoothed so that a validator with few jobs stays close to neutral.
+    pub fn success_rate(&self) -> f64 {
+        let finished = self.completed_jobs + self.failed_jobs;
+        (self.completed_jobs as f64 + 1.0) / (finished as f64 + 2.0)
+    }
+}
+
+/// Composite trust score of validators
+///
+/// Combines the job history in the `jobs` table with the validator's share of
+/// on-chain stake. Every computed score is recorded in `validator_trust_scores`.
+pub struct TrustScore {
+    pool: Arc<PgPool>,
+    /// Stake of each hotkey, relative to the largest stake on the subnet
+    stakes: Arc<RwLock<HashMap<String, f64>>>,
+}
+
+impl TrustScore {
+    pub fn new(pool: Arc<PgPool>, stakes: Arc<RwLock<HashMap<String, f64>>>) -> Self {
+        Self { pool, stakes }
+    }
+
+    /// Compute and store the trust score of `hotkey`, in [0, 1]
+    pub async fn compute(&self, hotkey: &str, window_days: u32) -> Result<f64> {
+        let performance = self.performance(hotkey, window_days).await?;
+        let stake_share = self.stakes.read().await.get(hotkey).copied().unwrap_or(0.0);
+        let score = Self::combine(&performance, stake_share);
+
+        sqlx::query(
+            r#"
+            INSERT INTO validator_trust_scores (
+                validator_hotkey, score, success_rate, avg_execution_time_ms,
+                attestation_failures_count, stake_share, window_days, computed_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+            "#,
+        )
+        .bind(hotkey)
+        .bind(score)
+        .bind(performance.success_rate())
+        .bind(performance.avg_execution_time_ms)
+        .bind(performance.attestation_failures_count)
+        .bind(stake_share)
+        .bind(window_days as i32)
+        .execute(self.pool.as_ref())
+        .await?;
+
+        Ok(score)
+    }
+
+    /// Job history of `hotkey` over the last `window_days`
+    pub async fn performance(&self, hotkey: &str, window_days: u32) -> Result<ValidatorPerformance> {
+        let (completed_jobs, failed_jobs, avg_execution_time_ms, attestation_failures_count) =
+            sqlx::query_as::<_, (i64, i64, Option<f64>, i64)>(
+                r#"
+                SELECT
+                    COUNT(*) FILTER (WHERE status = 'completed'),
+                    COUNT(*) FILTER (WHERE status IN ('failed', 'timeout')),
+                    (AVG(EXTRACT(EPOCH FROM (completed_at - started_at)) * 1000)
+                        FILTER (WHERE status = 'completed' AND started_at IS NOT NULL))::DOUBLE PRECISION,
+                    COUNT(*) FILTER (WHERE failure_category = 'validation_failed')
+                FROM jobs
+                WHERE validator_hotkey = $1
+                  AND created_at >= NOW() - make_interval(days => $2)
+                "#,
+            )
+            .bind(hotkey)
+            .bind(window_days as i32)
+            .fetch_one(self.pool.as_ref())
+            .await?;
+
+        Ok(ValidatorPerformance {
+            completed_jobs,
+            failed_jobs,
+            avg_execution_time_ms,
+            attestation_failures_count,
+        })
+    }
+
+    /// Latest stored trust score of `hotkey`
+    pub async fn latest(&self, hotkey: &str) -> Result<Option<f64>> {
+        let score = sqlx::query_scalar::<_, f64>(
+            r#"
+            SELECT score FROM validator_trust_scores
+            WHERE validator_hotkey = $1
+            ORDER BY seq DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(hotkey)
+        .fetch_optional(self.pool.as_ref())
+        .await?;
+
+        Ok(score)
+    }
+
+    /// Combine performance and relative stake into a score in [0, 1]
+    pub fn combine(performance: &ValidatorPerformance, stake_share: f64) -> f64 {
+        let speed = performance
+            .avg_execution_time_ms
+            .map(|ms| REFERENCE_EXECUTION_TIME_MS / (REFERENCE_EXECUTION_TIME_MS + ms.max(0.0)))
+            .unwrap_or(0.5);
+        let attestation = 1.0 / (1.0 + performance.attestation_failures_count as f64);
+
+        let score = SUCCESS_WEIGHT * performance.success_rate()
+            + SPEED_WEIGHT * speed
+            + ATTESTATION_WEIGHT * attestation
+            + STAKE_WEIGHT * stake_share.clamp(0.0, 1.0);
+        score.clamp(0.0, 1.0)
+    }
+}
+
+impl crate::service::SchedulerService {
+    /// Trust score calculator, available with database storage only
+    pub fn trust_score(&self) -> Option<TrustScore> {
+        self.database_pool
+            .clone()
+            .map(|pool| TrustScore::new(pool, self.validator_stakes.clone()))
+    }
+
+    /// Latest trust score of `hotkey`, or [`NEUTRAL_TRUST`] if none was computed
+    pub async fn validator_trust(&self, hotkey: &str) -> PlatformResult<f64> {
+        match self.trust_score() {
+            Some(trust) => Ok(trust.latest(hotkey).await?.unwrap_or(NEUTRAL_TRUST)),
+            None => Ok(NEUTRAL_TRUST),
+        }
+    }
+
+    /// Replace the relative on-chain stake of validators
+    pub async fn update_validator_stakes(&self, stakes: HashMap<String, f64>) {
+        *self.validator_stakes.write().await = stakes;
+    }
+
+    /// Recompute the trust score of every validator active within the window
+    pub async fn refresh_trust_scores(&self, window_days: u32) -> PlatformResult<usize> {
+        let Some(trust) = self.trust_score() else {
+            return Ok(0);
+        };
+
+        let hotkeys = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT validator_hotkey FROM jobs
+            WHERE validator_hotkey IS NOT NULL
+              AND created_at >= NOW() - make_interval(days => $1)
+            "#,
+        )
+        .bind(window_days as i32)
+        .fetch_all(trust.pool.as_ref())
+        .await
+        .context("Failed to list active validators")?;
+
+        for hotkey in &hotkeys {
+            let score = trust.compute(hotkey, window_days).await?;
+            info!(validator_hotkey = %hotkey, score, "Computed validator trust score");
+        }
+
+        Ok(hotkeys.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_is_bounded() {
+        let perfect = ValidatorPerformance {
+            completed_jobs: 1_000_000,
+            failed_jobs: 0,
+            avg_execution_time_ms: Some(0.0),
+            attestation_failures_count: 0,
+        };
+        let terrible = ValidatorPerformance {
+            completed_jobs: 0,
+            failed_jobs: 1_000_000,
+            avg_execution_time_ms: Some(f64::MAX),
+            attestation_failures_count: 1_000_000,
+        };
+
+        let high = TrustScore::combine(&perfect, 5.0);
+        let low = TrustScore::combine(&terrible, -1.0);
+        assert!(high <= 1.0 && high > 0.99);
+        assert!((0.0..0.01).contains(&low));
+    }
+
+    #[test]
+    fn test_history_outweighs_stake() {
+        let reliable = ValidatorPerformance {
+            completed_jobs: 95,
+            failed_jobs: 5,
+            avg_execution_time_ms: Some(30_000.0),
+            attestation_failures_count: 0,
+        };
+        let unreliable = ValidatorPerformance {
+            completed_jobs: 40,
+            failed_jobs: 60,
+            avg_execution_time_ms: Some(30_000.0),
+            attestation_failures_count: 4,
+        };
+
+        // A whale with a poor record ranks below a small, reliable validator
+        assert!(TrustScore::combine(&reliable, 0.1) > TrustScore::combine(&unreliable, 1.0));
+    }
+
+    #[test]
+    fn test_new_validator_is_near_neutral() {
+        let score = TrustScore::combine(&ValidatorPerformance::default(), 0.5);
+        assert!((score - NEUTRAL_TRUST).abs() < 0.2);
+    }
+}
@@ -1,27 +1,340 @@
 //! Type definitions for scheduler requests and responses
 
+use chrono::{DateTime, Utc};
 use platform_api_models::*;
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
 
 /// Request to create a new job
+///
+/// Unset priority, runtime, timeout and max retries are taken from the
+/// challenge's job defaults, see `ChallengeMetadata::job_defaults`, and unset
+/// resources from its resource requirements, see `ChallengeMetadata::resources`.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CreateJobRequest {
     pub challenge_id: Id,
     pub payload: JsonValue,
     pub priority: Option<JobPriority>,
-    pub runtime: RuntimeType,
+    #[serde(default)]
+    pub runtime: Option<RuntimeType>,
     pub timeout: Option<u64>,
     pub max_retries: Option<u32>,
+    /// Id to give the job, so that other jobs of the same batch can depend on it
+    #[serde(default)]
+    pub job_id: Option<Id>,
+    /// Jobs that must complete before this one becomes pending
+    #[serde(default)]
+    pub depends_on: Vec<Id>,
+    /// Time by which the job should be claimed
+    #[serde(default)]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Capabilities a validator must claim the job with; without them, those
+    /// of the job's resources, see `ResourceRequirements::capabilities`
+    #[serde(default)]
+    pub required_capabilities: Option<Vec<String>>,
+    #[serde(default)]
+    pub resources: Option<ResourceRequirements>,
+}
+
+/// Upper bound on `CreateJobRequest::max_retries`
+pub const MAX_JOB_RETRIES: u32 = 20;
+
+/// Upper bound on the memory and disk a job requires, in GB: 1 PiB
+pub const MAX_JOB_RESOURCE_GB: u64 = 1 << 20;
+
+/// Upper bound on `JobDefaults::retry_delay`, a week
+pub const MAX_JOB_RETRY_DELAY: u64 = 7 * 24 * 3600;
+
+/// Default of `SchedulerConfig::max_job_payload_bytes`, 1 MiB
+pub const DEFAULT_MAX_JOB_PAYLOAD_BYTES: usize = 1024 * 1024;
+
+impl CreateJobRequest {
+    /// Runtime of the job: Docker unless the request or its challenge sets one
+    pub fn runtime_or_default(&self) -> RuntimeType {
+        self.runtime.clone().unwrap_or(RuntimeType::Docker)
+    }
+
+    /// Fill the parameters the request leaves unset from its challenge's defaults
+    pub fn apply_defaults(
+        &mut self,
+        priority: &JobPriority,
+        defaults: &JobDefaults,
+        resources: &ResourceRequirements,
+    ) {
+        if self.priority.is_none() {
+            self.priority = Some(priority.clone());
+        }
+        if self.runtime.is_none() {
+            self.runtime = defaults.runtime.clone();
+        }
+        if self.timeout.is_none() {
+            self.timeout = defaults.timeout;
+        }
+        if self.max_retries.is_none() {
+            self.max_retries = defaults.max_retries;
+        }
+        if self.resources.is_none() {
+            self.resources = Some(resources.clone());
+        }
+    }
+
+    /// Check the request before any job is created
+    ///
+    /// Both single and batch creation run it; the error names the offending
+    /// field.
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.challenge_id.is_nil() {
+            return Err(FieldError::new("challenge_id", "challenge_id is required"));
+        }
+        if self.payload.is_null() {
+            return Err(FieldError::new("payload", "payload is required"));
+        }
+        if self.timeout == Some(0) {
+            return Err(FieldError::new(
+                "timeout",
+                "timeout must be greater than zero",
+            ));
+        }
+        if self.max_retries.is_some_and(|retries| retries > MAX_JOB_RETRIES) {
+            return Err(FieldError::new(
+                "max_retries",
+                format!("max_retries cannot exceed {}", MAX_JOB_RETRIES),
+            ));
+        }
+        if self.deadline.is_some_and(|deadline| deadline <= Utc::now()) {
+            return Err(FieldError::new(
+                "deadline",
+                "deadline must be in the future",
+            ));
+        }
+        if let Some(resources) = &self.resources {
+            validate_resources(resources).map_err(|e| FieldError::new("resources", e))?;
+        }
+        if let Some(job_id) = self.job_id {
+            if job_id.is_nil() {
+                return Err(FieldError::new("job_id", "job_id cannot be nil"));
+            }
+            if self.depends_on.contains(&job_id) {
+                return Err(FieldError::new("depends_on", "job cannot depend on itself"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check the resources a job requires before it is created
+///
+/// Amounts must be greater than zero, memory and disk at most
+/// `MAX_JOB_RESOURCE_GB`, and a GPU type requires a GPU.
+pub fn validate_resources(resources: &ResourceRequirements) -> Result<(), String> {
+    if resources.vcpu == Some(0) {
+        return Err("vcpu must be greater than zero".to_string());
+    }
+    for (field, amount) in [
+        ("memory_gb", resources.memory_gb),
+        ("disk_gb", resources.disk_gb),
+    ] {
+        match amount {
+            Some(0) => return Err(format!("{} must be greater than zero", field)),
+            Some(amount) if amount > MAX_JOB_RESOURCE_GB => {
+                return Err(format!("{} cannot exceed {}", field, MAX_JOB_RESOURCE_GB));
+            }
+            _ => {}
+        }
+    }
+    match &resources.gpu_type {
+        Some(gpu_type) if gpu_type.trim().is_empty() => Err("gpu_type cannot be empty".to_string()),
+        Some(_) if resources.gpus() == 0 => Err("gpu_type requires at least one GPU".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Check the job defaults of a challenge before they are stored
+pub fn validate_job_defaults(defaults: &JobDefaults) -> Result<(), String> {
+    if defaults.timeout == Some(0) {
+        return Err("timeout must be greater than zero".to_string());
+    }
+    if defaults.max_retries.is_some_and(|retries| retries > MAX_JOB_RETRIES) {
+        return Err(format!("max_retries cannot exceed {}", MAX_JOB_RETRIES));
+    }
+    if defaults.retry_delay.is_some_and(|delay| delay > MAX_JOB_RETRY_DELAY) {
+        return Err(format!("retry_delay cannot exceed {} seconds", MAX_JOB_RETRY_DELAY));
+    }
+    Ok(())
+}
+
+/// Check the score bounds of a challenge before they are stored
+pub fn validate_score_bounds(bounds: &ScoreBounds) -> Result<(), String> {
+    if bounds.min.is_some_and(|min| !min.is_finite())
+        || bounds.max.is_some_and(|max| !max.is_finite())
+    {
+        return Err("score bounds must be finite".to_string());
+    }
+    if let (Some(min), Some(max)) = (bounds.min, bounds.max) {
+        if min > max {
+            return Err(format!(
+                "min score {} is greater than max score {}",
+                min, max
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check the score policy of a challenge before it is stored
+pub fn validate_score_policy(policy: &ScorePolicy) -> Result<(), String> {
+    let ranges = policy
+        .default_range
+        .iter()
+        .map(|range| ("default_range".to_string(), range))
+        .chain(
+            policy
+                .ranges
+                .iter()
+                .map(|(key, range)| (format!("range of '{}'", key), range)),
+        );
+    for (name, range) in ranges {
+        if !range.min.is_finite() || !range.max.is_finite() {
+            return Err(format!("{} must be finite", name));
+        }
+        if range.min > range.max {
+            return Err(format!(
+                "{} has min {} greater than max {}",
+                name, range.min, range.max
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Outcome of one item of a batch job creation
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BatchJobResult {
+    pub index: usize,
+    pub job_id: Option<Id>,
+    pub error: Option<String>,
+}
+
+/// Result of a batch job creation
+///
+/// The batch is atomic: either every job was created, or none was and the
+/// failing items carry an error.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BatchCreateJobsResponse {
+    pub created: bool,
+    pub results: Vec<BatchJobResult>,
 }
 
 /// Scheduler configuration
 #[derive(Debug, Clone)]
 pub struct SchedulerConfig {
     pub max_concurrent_jobs: u32,
+    /// Timeout of jobs created without one, unless set for their runtime
     pub job_timeout: u64,
+    /// Timeout of jobs of the runtime created without one
+    pub runtime_timeouts: HashMap<RuntimeType, u64>,
+    /// Longest timeout a job can be created with
+    pub max_job_timeout: u64,
     pub retry_attempts: u32,
     pub retry_delay: u64,
     pub cleanup_interval: u64,
+    /// Maximum number of jobs accepted by a single batch creation
+    pub max_batch_size: usize,
+    /// Time pending after which a job's priority has risen by one level; 0 disables aging
+    pub priority_aging_secs: u64,
+    /// Maximum number of levels a job can gain by aging
+    pub max_aging_boost: f64,
+    /// Time before its deadline over which a job is moved to the front
+    pub deadline_window_secs: u64,
+    /// Job quota of challenges without an entry in `challenge_quotas`
+    pub default_challenge_quota: ChallengeJobQuota,
+    /// Job quota of specific challenges
+    pub challenge_quotas: HashMap<Id, ChallengeJobQuota>,
+    /// URL notified when a job of the challenge completes, fails or is dead-lettered
+    pub challenge_webhooks: HashMap<Id, String>,
+    /// Scorer ranking the jobs of the challenge for the claiming validator, by
+    /// the name it was registered under; others use the default scorer
+    pub challenge_scorers: HashMap<Id, String>,
+    /// Time over which the completed and failed jobs counted for a validator
+    /// lose half their weight; 0 keeps them forever
+    pub reliability_half_life_secs: u64,
+    /// Largest job payload accepted, in bytes of its JSON serialization
+    pub max_job_payload_bytes: usize,
+}
+
+impl SchedulerConfig {
+    /// Job quota of `challenge_id`
+    pub fn challenge_quota(&self, challenge_id: &Id) -> ChallengeJobQuota {
+        self.challenge_quotas
+            .get(challenge_id)
+            .copied()
+            .unwrap_or(self.default_challenge_quota)
+    }
+
+    /// Timeout of jobs of `runtime` created without one
+    pub fn runtime_timeout(&self, runtime: &RuntimeType) -> u64 {
+        self.runtime_timeouts
+            .get(runtime)
+            .copied()
+            .unwrap_or(self.job_timeout)
+    }
+
+    /// Timeout of the job created by `request`
+    ///
+    /// Fails if the request sets a timeout above `max_job_timeout`.
+    pub fn job_timeout_of(&self, request: &CreateJobRequest) -> Result<u64, TimeoutTooLong> {
+        match request.timeout {
+            Some(timeout) if timeout > self.max_job_timeout => Err(TimeoutTooLong {
+                timeout,
+                max: self.max_job_timeout,
+            }),
+            Some(timeout) => Ok(timeout),
+            None => Ok(self.runtime_timeout(&request.runtime_or_default())),
+        }
+    }
+
+    /// Seconds `job` may run once claimed
+    ///
+    /// Jobs stored without a timeout get the one of their runtime.
+    pub fn job_timeout(&self, job: &JobMetadata) -> u64 {
+        job.timeout
+            .unwrap_or_else(|| self.runtime_timeout(&job.runtime))
+    }
+}
+
+/// Check that `payload` is at most `limit` bytes serialized as JSON
+pub fn check_payload_size(
+    payload: &serde_json::Value,
+    limit: usize,
+) -> Result<(), PayloadTooLarge> {
+    let size = serde_json::to_string(payload).map_or(0, |json| json.len());
+    if size > limit {
+        return Err(PayloadTooLarge { size, limit });
+    }
+    Ok(())
+}
+
+/// Parse a `<runtime>=<seconds>` comma-separated list of runtime job timeouts
+pub fn parse_runtime_timeouts(spec: &str) -> anyhow::Result<HashMap<RuntimeType, u64>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (runtime, timeout) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected <runtime>=<seconds>, got '{}'", entry))?;
+            let runtime = runtime.trim();
+            let parsed = RuntimeType::from(runtime);
+            if parsed == RuntimeType::Standard && !runtime.eq_ignore_ascii_case("standard") {
+                anyhow::bail!("Unknown runtime '{}'", runtime);
+            }
+            let timeout: u64 = timeout.trim().parse()?;
+            if timeout == 0 {
+                anyhow::bail!("Timeout of runtime '{}' must be greater than zero", runtime);
+            }
+            Ok((parsed, timeout))
+        })
+        .collect()
 }
 
 impl Default for SchedulerConfig {
@@ -29,9 +342,256 @@ impl Default for SchedulerConfig {
         Self {
             max_concurrent_jobs: 100,
             job_timeout: 3600,
+            runtime_timeouts: HashMap::new(),
+            max_job_timeout: 24 * 3600,
             retry_attempts: 3,
             retry_delay: 60,
             cleanup_interval: 3600,
+            max_batch_size: 100,
+            priority_aging_secs: 600,
+            max_aging_boost: 3.0,
+            deadline_window_secs: 900,
+            default_challenge_quota: ChallengeJobQuota::default(),
+            challenge_quotas: HashMap::new(),
+            challenge_webhooks: HashMap::new(),
+            challenge_scorers: HashMap::new(),
+            reliability_half_life_secs: 7 * 24 * 3600,
+            max_job_payload_bytes: DEFAULT_MAX_JOB_PAYLOAD_BYTES,
+        }
+    }
+}
+
+/// Payload search and creation date range for listing jobs
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct JobSearch {
+    /// Text matched, case-insensitively, against the top-level keys and values of the payload
+    #[serde(default)]
+    pub search: Option<String>,
+    /// Only jobs created at or after this time
+    #[serde(default)]
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only jobs created before this time
+    #[serde(default)]
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+impl JobSearch {
+    /// `search` as an escaped `ILIKE` pattern matching anywhere in a string
+    pub(crate) fn like_pattern(&self) -> Option<String> {
+        self.search.as_deref().map(|text| {
+            let escaped = text
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        })
+    }
+
+    /// Whether `job` matches the search, as the SQL filter would
+    pub(crate) fn matches(&self, job: &JobMetadata) -> bool {
+        if self.created_after.is_some_and(|after| job.created_at < after) {
+            return false;
+        }
+        if self.created_before.is_some_and(|before| job.created_at >= before) {
+            return false;
+        }
+        let Some(text) = &self.search else {
+            return true;
+        };
+
+        let text = text.to_lowercase();
+        let contains = |s: &str| s.to_lowercase().contains(&text);
+        match &job.payload {
+            Some(JsonValue::Object(fields)) => fields.iter().any(|(key, value)| {
+                contains(key)
+                    || match value {
+                        JsonValue::String(s) => contains(s),
+                        JsonValue::Null => false,
+                        other => contains(&other.to_string()),
+                    }
+            }),
+            Some(other) => contains(&other.to_string()),
+            None => false,
+        }
+    }
+}
+
+/// Limits on the jobs a single challenge can have queued or running at once
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChallengeJobQuota {
+    /// Jobs pending or blocked on their dependencies; further creations are rejected
+    pub max_pending: u64,
+    /// Jobs claimed or running; further jobs of the challenge are not handed out
+    pub max_in_flight: u64,
+}
+
+impl Default for ChallengeJobQuota {
+    fn default() -> Self {
+        Self {
+            max_pending: 1000,
+            max_in_flight: 100,
+        }
+    }
+}
+
+/// Jobs a challenge currently has against its quota
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ChallengeJobUsage {
+    pub pending: u64,
+    pub in_flight: u64,
+    pub quota: ChallengeJobQuota,
+}
+
+/// A challenge has reached its job quota
+#[derive(Debug, thiserror::Error)]
+pub enum QuotaExceeded {
+    #[error("Challenge {challenge_id} has reached its quota of {limit} pending jobs")]
+    Pending { challenge_id: Id, limit: u64 },
+    #[error("Challenge {challenge_id} has reached its quota of {limit} jobs in flight")]
+    InFlight { challenge_id: Id, limit: u64 },
+}
+
+/// A job was created with a timeout above `SchedulerConfig::max_job_timeout`
+#[derive(Debug, thiserror::Error)]
+#[error("Job timeout of {timeout}s exceeds the maximum of {max}s")]
+pub struct TimeoutTooLong {
+    pub timeout: u64,
+    pub max: u64,
+}
+
+/// A job payload is larger than `SchedulerConfig::max_job_payload_bytes`
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Job payload of {size} bytes exceeds the limit of {limit} bytes")]
+pub struct PayloadTooLarge {
+    pub size: usize,
+    pub limit: usize,
+}
+
+/// A job payload does not match the payload schema of its challenge
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Invalid job payload at {path}: {message}")]
+pub struct InvalidPayload {
+    /// JSON Pointer of the offending value, prefixed with `payload`
+    pub path: String,
+    pub message: String,
+}
+
+/// A validator already runs as many jobs as the capacity it reported
+#[derive(Debug, thiserror::Error)]
+#[error("Validator {validator_hotkey} has no free capacity ({capacity} jobs in flight)")]
+pub struct CapacityExhausted {
+    pub validator_hotkey: String,
+    pub capacity: u32,
+}
+
+/// An operator action on a job was refused
+#[derive(Debug, thiserror::Error)]
+pub enum JobAdminError {
+    #[error("Job {0} not found")]
+    NotFound(Id),
+    #[error("Job {job_id} is {status} and cannot be {action}")]
+    InvalidStatus {
+        job_id: Id,
+        status: &'static str,
+        action: &'static str,
+    },
+}
+
+/// A job changed since the version an update of it expected
+///
+/// Another update, e.g. a reset racing a completion, got there first; the
+/// caller can read the job again and retry.
+#[derive(Debug, thiserror::Error)]
+#[error("Job {job_id} changed since version {expected}")]
+pub struct JobConflict {
+    pub job_id: Id,
+    pub expected: u64,
+}
+
+/// A job result has a score outside the bounds of the job's challenge, which
+/// rejects such results, see `ChallengeMetadata::score_bounds`
+#[derive(Debug, thiserror::Error)]
+#[error("Score '{name}' of job {job_id} is {score}, outside the bounds of its challenge")]
+pub struct ScoreOutOfBounds {
+    pub job_id: Id,
+    pub name: String,
+    pub score: Score,
+}
+
+/// A job result breaks the score policy of the job's challenge, see
+/// `ChallengeMetadata::score_policy`
+#[derive(Debug, thiserror::Error)]
+#[error("Result of job {job_id} breaks the score policy of its challenge")]
+pub struct ScorePolicyViolated {
+    pub job_id: Id,
+    pub violations: Vec<ScoreViolation>,
+}
+
+impl From<QuotaExceeded> for PlatformError {
+    fn from(err: QuotaExceeded) -> Self {
+        PlatformError::rate_limited(err)
+    }
+}
+
+impl From<TimeoutTooLong> for PlatformError {
+    fn from(err: TimeoutTooLong) -> Self {
+        PlatformError::invalid("timeout", err)
+    }
+}
+
+impl From<PayloadTooLarge> for PlatformError {
+    fn from(err: PayloadTooLarge) -> Self {
+        PlatformError::PayloadTooLarge {
+            reason: err.to_string(),
+        }
+    }
+}
+
+impl From<InvalidPayload> for PlatformError {
+    fn from(err: InvalidPayload) -> Self {
+        PlatformError::invalid(err.path, err.message)
+    }
+}
+
+impl From<CapacityExhausted> for PlatformError {
+    fn from(err: CapacityExhausted) -> Self {
+        PlatformError::rate_limited(err)
+    }
+}
+
+impl From<JobAdminError> for PlatformError {
+    fn from(err: JobAdminError) -> Self {
+        match err {
+            JobAdminError::NotFound(job_id) => PlatformError::not_found("job", job_id),
+            err => PlatformError::conflict(err),
+        }
+    }
+}
+
+impl From<JobConflict> for PlatformError {
+    fn from(err: JobConflict) -> Self {
+        PlatformError::conflict(err)
+    }
+}
+
+impl From<ScoreOutOfBounds> for PlatformError {
+    fn from(err: ScoreOutOfBounds) -> Self {
+        PlatformError::invalid(format!("scores/{}", err.name), &err)
+    }
+}
+
+impl From<ScorePolicyViolated> for PlatformError {
+    fn from(err: ScorePolicyViolated) -> Self {
+        PlatformError::Unprocessable {
+            fields: err
+                .violations
+                .iter()
+                .map(|violation| FieldError {
+                    field: violation.field(),
+                    message: violation.to_string(),
+                    rule: Some(violation.rule().to_string()),
+                })
+                .collect(),
         }
     }
 }
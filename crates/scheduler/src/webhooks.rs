@@ -0,0 +1,379 @@
+//! Webhook notifications of job terminal states
+//!
+//! Challenges may register a webhook URL, see
+//! `SchedulerConfig::challenge_webhooks`. When one of their jobs completes,
+//! fails or is dead-lettered, a `JobWebhookEvent` is POSTed to it as JSON.
+//!
+//! Every delivery attempt is signed with the platform key so that receivers
+//! can check it came from the platform: the hex-encoded signature, sent in
+//! `SIGNATURE_HEADER`, covers `{timestamp}.{body}` (see [`signed_payload`]),
+//! with the unix timestamp of the attempt sent in `TIMESTAMP_HEADER`.
+//! Receivers should drop deliveries whose timestamp is more than
+//! `WEBHOOK_TIMESTAMP_TOLERANCE_SECS` away from their clock, so that captured
+//! deliveries cannot be replayed later, and drop redelivered events by their
+//! `event_id`.
+
+use crate::service::SchedulerService;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use platform_api_models::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Header carrying the hex-encoded signature of the timestamp and body
+pub const SIGNATURE_HEADER: &str = "X-Platform-Signature";
+
+/// Header carrying the unix timestamp, in seconds, the delivery was signed at
+pub const TIMESTAMP_HEADER: &str = "X-Platform-Timestamp";
+
+/// How far from their clock receivers should accept delivery timestamps
+pub const WEBHOOK_TIMESTAMP_TOLERANCE_SECS: u64 = 300;
+
+/// Header carrying the event type, e.g. `job.completed`
+pub const EVENT_HEADER: &str = "X-Platform-Event";
+
+/// Default number of delivery attempts of an event
+pub const DEFAULT_WEBHOOK_MAX_ATTEMPTS: u32 = 5;
+
+/// Default delay before the first retry; it doubles after every attempt
+pub const DEFAULT_WEBHOOK_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Signs webhook bodies with the platform key
+pub trait WebhookSigner: Send + Sync {
+    fn sign(&self, payload: &[u8]) -> Vec<u8>;
+}
+
+/// What the signature of a delivery made at `timestamp` covers:
+/// `{timestamp}.{body}`
+pub fn signed_payload(timestamp: i64, body: &[u8]) -> Vec<u8> {
+    let mut payload = format!("{}.", timestamp).into_bytes();
+    payload.extend_from_slice(body);
+    payload
+}
+
+/// Event POSTed to a challenge webhook when a job reaches a terminal state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobWebhookEvent {
+    /// Unique per event, so that receivers can drop redelivered events
+    pub event_id: Uuid,
+    /// `job.completed`, `job.failed` or `job.dead_lettered`
+    pub event: String,
+    pub job_id: Id,
+    pub challenge_id: Id,
+    pub status: JobStatus,
+    pub validator_hotkey: Option<String>,
+    /// Reason of the failure, for failed and dead-lettered jobs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_category: Option<FailureCategory>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl JobWebhookEvent {
+    pub fn new(
+        job_id: Id,
+        challenge_id: Id,
+        status: JobStatus,
+        validator_hotkey: Option<String>,
+    ) -> Self {
+        Self {
+            event_id: Uuid::new_v4(),
+            event: format!("job.{}", status.as_str()),
+            job_id,
+            challenge_id,
+            status,
+            validator_hotkey,
+            reason: None,
+            failure_category: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Add the reason and category of a failure
+    pub fn with_failure(mut self, reason: &str, category: Option<FailureCategory>) -> Self {
+        self.reason = Some(reason.to_string());
+        self.failure_category = category;
+        self
+    }
+}
+
+/// Delivers signed webhook events, retrying with exponential backoff
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    signer: Arc<dyn WebhookSigner>,
+    max_attempts: u32,
+    initial_backoff: Duration,
+}
+
+impl WebhookDispatcher {
+    pub fn new(signer: Arc<dyn WebhookSigner>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            signer,
+            max_attempts: DEFAULT_WEBHOOK_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_WEBHOOK_BACKOFF,
+        }
+    }
+
+    /// Make at most `max_attempts` attempts, waiting `initial_backoff` before the first retry
+    pub fn with_retries(mut self, max_attempts: u32, initial_backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// POST `event` to `url` until it answers with a 2xx status
+    ///
+    /// Every attempt is signed afresh, so that retries stay within the
+    /// receiver's timestamp tolerance. Returns the number of attempts made.
+    pub async fn deliver(&self, url: &str, event: &JobWebhookEvent) -> Result<u32> {
+        let body = serde_json::to_vec(event)?;
+
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let timestamp = Utc::now().timestamp();
+            let signature = hex::encode(self.signer.sign(&signed_payload(timestamp, &body)));
+            let outcome = self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, &signature)
+                .header(EVENT_HEADER, &event.event)
+                .body(body.clone())
+                .send()
+                .await;
+
+            let error = match outcome {
+                Ok(response) if response.status().is_success() => {
+                    debug!(event_id = %event.event_id, attempt, "Delivered webhook event");
+                    return Ok(attempt);
+                }
+                Ok(response) => format!("receiver answered {}", response.status()),
+                Err(e) => e.to_string(),
+            };
+
+            if attempt >= self.max_attempts {
+                return Err(anyhow::anyhow!(
+                    "Webhook delivery to {} failed after {} attempts: {}",
+                    url,
+                    attempt,
+                    error
+                ));
+            }
+            debug!(event_id = %event.event_id, attempt, error = %error, "Retrying webhook delivery");
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+impl SchedulerService {
+    /// Send `event` to the webhook of its challenge, if it has one
+    ///
+    /// Delivery runs in the background so that job transitions never wait on
+    /// a receiver.
+    pub(crate) async fn notify_webhook(&self, event: JobWebhookEvent) {
+        let Some(dispatcher) = &self.webhooks else {
+            return;
+        };
+        let Some(url) = self
+            .config()
+            .await
+            .challenge_webhooks
+            .get(&event.challenge_id)
+            .cloned()
+        else {
+            return;
+        };
+
+        let dispatcher = dispatcher.clone();
+        tokio::spawn(async move {
+            if let Err(e) = dispatcher.deliver(&url, &event).await {
+                warn!(
+                    event_id = %event.event_id,
+                    job_id = %event.job_id,
+                    challenge_id = %event.challenge_id,
+                    "Dropped webhook event: {}",
+                    e
+                );
+            }
+        });
+    }
+}
+
+/// Parse a `<challenge_id>=<url>` comma-separated list of challenge webhooks
+pub fn parse_challenge_webhooks(spec: &str) -> Result<HashMap<Id, String>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (challenge_id, url) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected <challenge_id>=<url>, got '{}'", entry))?;
+            let url = reqwest::Url::parse(url.trim())?;
+            if !matches!(url.scheme(), "http" | "https") {
+                anyhow::bail!("Webhook URL must be http or https: {}", url);
+            }
+            Ok((Uuid::parse_str(challenge_id.trim())?, url.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CreateJobRequest, SchedulerConfig};
+    use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+    use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+    use tokio::sync::Mutex;
+
+    struct TestSigner(SigningKey);
+
+    impl WebhookSigner for TestSigner {
+        fn sign(&self, payload: &[u8]) -> Vec<u8> {
+            self.0.sign(payload).to_bytes().to_vec()
+        }
+    }
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, Vec<u8>)>>>;
+
+    /// Receiver that answers 500 to the first request and 200 afterwards
+    async fn receive(
+        State(received): State<Received>,
+        headers: HeaderMap,
+        body: axum::body::Bytes,
+    ) -> StatusCode {
+        let mut received = received.lock().await;
+        received.push((headers, body.to_vec()));
+        if received.len() == 1 {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completed_job_is_delivered_signed_after_retry() {
+        let received: Received = Arc::default();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(received.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let challenge_id = Uuid::new_v4();
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let config = SchedulerConfig {
+            challenge_webhooks: HashMap::from([(challenge_id, url)]),
+            ..SchedulerConfig::default()
+        };
+        let scheduler = SchedulerService::new(&config).unwrap().with_webhooks(
+            WebhookDispatcher::new(Arc::new(TestSigner(key.clone())))
+                .with_retries(3, Duration::from_millis(10)),
+        );
+
+        let job_id = scheduler
+            .create_job(CreateJobRequest {
+                challenge_id,
+                payload: serde_json::json!({}),
+                priority: None,
+                runtime: Some(RuntimeType::Docker),
+                timeout: None,
+                max_retries: None,
+                job_id: None,
+                depends_on: vec![],
+                deadline: None,
+                required_capabilities: None,
+                resources: None,
+            })
+            .await
+            .unwrap()
+            .id;
+        scheduler
+            .complete_job(
+                job_id,
+                SubmitResultRequest {
+                    job_id,
+                    result: EvalResult {
+                        job_id,
+                        submission_id: Uuid::new_v4(),
+                        scores: Default::default(),
+                        metrics: Default::default(),
+                        logs: vec![],
+                        error: None,
+                        execution_time: 0,
+                        resource_usage: ResourceUsage {
+                            cpu_time: 0,
+                            memory_peak: 0,
+                            disk_usage: 0,
+                            network_bytes: 0,
+                        },
+                        attestation_receipt: None,
+                        schema_version: EVAL_RESULT_SCHEMA_VERSION,
+                    },
+                    receipts: vec![],
+                    expected_version: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        let deliveries = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if received.lock().await.len() >= 2 {
+                    break received.lock().await.clone();
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Webhook was not retried");
+
+        // The retry carries the same signed body
+        assert_eq!(deliveries[0].1, deliveries[1].1);
+        let (headers, body) = &deliveries[1];
+        assert_eq!(headers[EVENT_HEADER], "job.completed");
+        let signature = hex::decode(headers[SIGNATURE_HEADER].to_str().unwrap()).unwrap();
+        let signature = Signature::from_slice(&signature).unwrap();
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        assert!(Utc::now().timestamp().abs_diff(timestamp) <= WEBHOOK_TIMESTAMP_TOLERANCE_SECS);
+        assert!(key
+            .verifying_key()
+            .verify(&signed_payload(timestamp, body), &signature)
+            .is_ok());
+        // The timestamp is covered, so it cannot be moved forward on replay
+        assert!(key
+            .verifying_key()
+            .verify(&signed_payload(timestamp + 600, body), &signature)
+            .is_err());
+        assert!(key.verifying_key().verify(body, &signature).is_err());
+
+        let event: JobWebhookEvent = serde_json::from_slice(body).unwrap();
+        assert_eq!(event.job_id, job_id);
+        assert_eq!(event.challenge_id, challenge_id);
+        assert_eq!(event.status, JobStatus::Completed);
+    }
+
+    #[test]
+    fn test_parse_challenge_webhooks() {
+        let id = Uuid::new_v4();
+        let webhooks =
+            parse_challenge_webhooks(&format!(" {}=https://example.com/hook ,", id)).unwrap();
+        assert_eq!(webhooks[&id], "https://example.com/hook");
+
+        assert!(parse_challenge_webhooks("not-a-uuid=https://example.com").is_err());
+        assert!(parse_challenge_webhooks(&format!("{}=ftp://example.com", id)).is_err());
+    }
+}
@@ -0,0 +1,7 @@
+-- Migration: Add failure category to jobs
+-- Created: 2026-10-16
+
+-- Distinguishes timeouts from execution errors, validator crashes, etc.
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS failure_category VARCHAR(50);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_failure_category ON jobs(failure_category) WHERE failure_category IS NOT NULL;
@@ -0,0 +1,25 @@
+-- Migration: Create platform_settings tables for runtime-tunable settings
+-- Created: 2026-10-16
+-- Purpose: Let operators tune whitelisted settings without restarting platform-api
+
+-- Table: platform_settings
+-- Current override for each setting; unset settings fall back to startup configuration
+CREATE TABLE IF NOT EXISTS platform_settings (
+    key VARCHAR(255) PRIMARY KEY,
+    value JSONB NOT NULL,
+    updated_by VARCHAR(255) NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Table: platform_settings_audit
+-- Who changed which setting, from what, to what, and when
+CREATE TABLE IF NOT EXISTS platform_settings_audit (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    key VARCHAR(255) NOT NULL,
+    old_value JSONB,
+    new_value JSONB NOT NULL,
+    changed_by VARCHAR(255) NOT NULL,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_platform_settings_audit_key ON platform_settings_audit(key, changed_at DESC);
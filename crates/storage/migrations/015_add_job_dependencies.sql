@@ -0,0 +1,8 @@
+-- Migration: Add job dependencies
+-- Created: 2026-10-16
+
+-- Jobs this job waits on; a job with unfinished dependencies stays 'blocked'
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS depends_on UUID[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_jobs_depends_on ON jobs USING GIN (depends_on);
+CREATE INDEX IF NOT EXISTS idx_jobs_blocked ON jobs(status) WHERE status = 'blocked';
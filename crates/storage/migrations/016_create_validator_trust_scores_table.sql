@@ -0,0 +1,19 @@
+-- Migration: Create validator_trust_scores table
+-- Created: 2026-10-16
+-- Purpose: Record composite validator trust scores used to weight job routing
+
+-- Table: validator_trust_scores
+-- One row per computation; the latest row per validator is the current score
+CREATE TABLE IF NOT EXISTS validator_trust_scores (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    validator_hotkey VARCHAR(255) NOT NULL,
+    score DOUBLE PRECISION NOT NULL,
+    success_rate DOUBLE PRECISION NOT NULL,
+    avg_execution_time_ms DOUBLE PRECISION,
+    attestation_failures_count BIGINT NOT NULL DEFAULT 0,
+    stake_share DOUBLE PRECISION NOT NULL DEFAULT 0,
+    window_days INTEGER NOT NULL,
+    computed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_validator_trust_scores_hotkey ON validator_trust_scores(validator_hotkey, computed_at DESC);
@@ -0,0 +1,7 @@
+-- Migration: Add owner to challenges
+-- Created: 2026-10-16
+
+-- Identity of the caller that created the challenge; existing challenges belong to the platform
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS owner VARCHAR(255) NOT NULL DEFAULT 'platform-system';
+
+CREATE INDEX IF NOT EXISTS idx_challenges_owner ON challenges(owner);
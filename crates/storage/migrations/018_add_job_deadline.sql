@@ -0,0 +1,8 @@
+-- Migration: Add job deadlines
+-- Created: 2026-10-16
+
+-- Time by which a job should be claimed; the scheduler ranks it higher as it nears
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS deadline TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_jobs_pending_deadline ON jobs(deadline)
+    WHERE status = 'pending' AND deadline IS NOT NULL;
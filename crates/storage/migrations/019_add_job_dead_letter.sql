@@ -0,0 +1,10 @@
+-- Migration: Add dead-lettered jobs
+-- Created: 2026-10-16
+
+-- Every failed attempt of a job, oldest first
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS retry_history JSONB NOT NULL DEFAULT '[]';
+-- Time the job exhausted its retries
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS dead_lettered_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_jobs_dead_lettered ON jobs(dead_lettered_at DESC)
+    WHERE status = 'dead_lettered';
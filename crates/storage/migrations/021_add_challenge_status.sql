@@ -0,0 +1,7 @@
+-- Migration: Add status to challenges
+-- Created: 2026-10-16
+
+-- Lifecycle status of the challenge; draft challenges are not run by validators
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS status VARCHAR(32) NOT NULL DEFAULT 'active';
+
+CREATE INDEX IF NOT EXISTS idx_challenges_status ON challenges(status);
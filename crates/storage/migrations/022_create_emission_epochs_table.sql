@@ -0,0 +1,15 @@
+-- Migration: Create emission epochs table
+-- Created: 2026-10-16
+
+-- Consecutive block ranges over which emissions are accounted and distributed
+CREATE TABLE IF NOT EXISTS emission_epochs (
+    epoch_number BIGINT PRIMARY KEY CHECK (epoch_number > 0),
+    start_block BIGINT NOT NULL CHECK (start_block >= 0),
+    end_block BIGINT NOT NULL,
+    total_emissions DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (total_emissions >= 0),
+    distributed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (end_block > start_block)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_emission_epochs_start_block ON emission_epochs(start_block);
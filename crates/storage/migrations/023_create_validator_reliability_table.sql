@@ -0,0 +1,12 @@
+-- Migration: Create validator_reliability table
+-- Created: 2026-10-16
+-- Purpose: Keep decayed counts of the jobs each validator completed and failed
+
+-- Counts are decayed to updated_at; they lose half their weight every
+-- scheduler reliability half-life so that old failures fade
+CREATE TABLE IF NOT EXISTS validator_reliability (
+    validator_hotkey VARCHAR(255) PRIMARY KEY,
+    completed_jobs DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (completed_jobs >= 0),
+    failed_jobs DOUBLE PRECISION NOT NULL DEFAULT 0 CHECK (failed_jobs >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
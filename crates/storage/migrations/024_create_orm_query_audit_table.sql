@@ -0,0 +1,22 @@
+-- Migration: Create orm_query_audit table
+-- Created: 2026-10-16
+-- Purpose: Audit the queries challenges execute through the ORM gateway
+
+-- One row per query or transaction, executed or refused. Filter values are
+-- never stored; the SQL text is kept for failed and slow queries only
+CREATE TABLE IF NOT EXISTS orm_query_audit (
+    id BIGSERIAL PRIMARY KEY,
+    challenge VARCHAR(255),
+    operation VARCHAR(32) NOT NULL,
+    table_name TEXT NOT NULL,
+    filter_summary TEXT,
+    row_count BIGINT,
+    duration_ms DOUBLE PRECISION NOT NULL,
+    outcome VARCHAR(16) NOT NULL CHECK (outcome IN ('success', 'error')),
+    error TEXT,
+    sql TEXT,
+    executed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_orm_query_audit_executed_at ON orm_query_audit(executed_at DESC);
+CREATE INDEX IF NOT EXISTS idx_orm_query_audit_challenge ON orm_query_audit(challenge, executed_at DESC);
@@ -0,0 +1,5 @@
+-- Migration: Add default job priority to challenges
+-- Created: 2026-10-16
+
+-- Priority given to the challenge's jobs submitted without one
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS default_job_priority VARCHAR(16) NOT NULL DEFAULT 'normal';
@@ -0,0 +1,19 @@
+-- Migration: Create validators table
+-- Created: 2026-10-16
+-- Purpose: Keep the capabilities validators declare through POST /validators/register
+
+-- One row per hotkey; registering again replaces the declared capabilities.
+-- preferred_challenges are routing hints, used even while the validator is
+-- not connected
+CREATE TABLE IF NOT EXISTS validators (
+    hotkey VARCHAR(255) PRIMARY KEY,
+    runtimes TEXT[] NOT NULL DEFAULT '{}',
+    gpu BOOLEAN NOT NULL DEFAULT FALSE,
+    memory_gb BIGINT NOT NULL CHECK (memory_gb >= 0),
+    cpu_cores INTEGER NOT NULL CHECK (cpu_cores >= 0),
+    preferred_challenges UUID[] NOT NULL DEFAULT '{}',
+    registered_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_validators_preferred_challenges ON validators USING GIN (preferred_challenges);
@@ -0,0 +1,5 @@
+-- Migration: Add job payload schema to challenges
+-- Created: 2026-10-16
+
+-- JSON Schema the challenge's job payloads must match; NULL accepts any payload
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS job_payload_schema JSONB;
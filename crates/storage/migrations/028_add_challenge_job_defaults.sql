@@ -0,0 +1,5 @@
+-- Migration: Add job defaults to challenges
+-- Created: 2026-10-16
+
+-- Runtime, timeout and max retries given to the challenge's jobs submitted without them
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS job_defaults JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,21 @@
+-- Migration: Create image builds table
+-- Created: 2026-10-16
+-- Purpose: Track docker image builds of challenges queued through POST /challenges/:id/build
+
+-- status goes from queued to building, then pushed or failed. log_tail holds
+-- the last lines of the build output as a JSON array of strings
+CREATE TABLE IF NOT EXISTS image_builds (
+    id UUID PRIMARY KEY,
+    challenge_id UUID NOT NULL REFERENCES challenges(id) ON DELETE CASCADE,
+    source JSONB NOT NULL,
+    status VARCHAR(16) NOT NULL CHECK (status IN ('queued', 'building', 'pushed', 'failed')),
+    image TEXT NOT NULL,
+    error TEXT,
+    log_tail JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_image_builds_challenge ON image_builds (challenge_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_image_builds_unfinished ON image_builds (status) WHERE status IN ('queued', 'building');
@@ -0,0 +1,16 @@
+-- Migration: Create image build cache table
+-- Created: 2026-10-16
+-- Purpose: Reuse the images of challenge sources that were already built
+
+-- source_digest is the commit a GitHub ref resolved to ('commit:<sha>') or the
+-- SHA-256 of an artifact tarball ('sha256:<hex>'). Entries are evicted least
+-- recently used first once their images add up to more than the cache size
+CREATE TABLE IF NOT EXISTS image_build_cache (
+    source_digest TEXT PRIMARY KEY,
+    image TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    last_used_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE image_builds ADD COLUMN IF NOT EXISTS source_digest TEXT;
+ALTER TABLE image_builds ADD COLUMN IF NOT EXISTS cache VARCHAR(8) CHECK (cache IN ('hit', 'miss'));
@@ -0,0 +1,7 @@
+-- Migration: Add job version
+-- Created: 2026-10-16
+-- Purpose: Detect concurrent changes of a job, e.g. a late completion racing a reset
+
+-- Incremented by every update of the job; completions, failures and claims of
+-- a specific job only apply if the job is still at the version they expect
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,15 @@
+-- Migration: Add resource requirements
+-- Created: 2026-10-16
+-- Purpose: Let challenges declare what their jobs need and validators what they have
+
+-- vcpu, memory_gb, gpu_count, gpu_type and disk_gb the challenge's jobs need
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS resource_requirements JSONB NOT NULL DEFAULT '{}';
+
+-- Inherited from the challenge when the job is submitted without them;
+-- only validators claiming with every required capability are given the job
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS required_capabilities TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS resources JSONB NOT NULL DEFAULT '{}';
+
+ALTER TABLE validators ADD COLUMN IF NOT EXISTS gpu_count INTEGER NOT NULL DEFAULT 0 CHECK (gpu_count >= 0);
+ALTER TABLE validators ADD COLUMN IF NOT EXISTS gpu_type TEXT;
+ALTER TABLE validators ADD COLUMN IF NOT EXISTS disk_gb BIGINT CHECK (disk_gb >= 0);
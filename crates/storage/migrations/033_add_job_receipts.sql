@@ -0,0 +1,6 @@
+-- Migration: Add job receipts
+-- Created: 2026-10-16
+-- Purpose: Keep the receipts validators submit with job results
+
+-- Replaced by each completion; the attestation receipt stays in result
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS receipts TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,7 @@
+-- Migration: Add challenge provenance
+-- Created: 2026-10-16
+-- Purpose: Record where challenges imported from GitHub were defined
+
+-- repo_url, commit_sha, manifest_path and imported_at of the last import;
+-- NULL for challenges created through the API
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS provenance JSONB;
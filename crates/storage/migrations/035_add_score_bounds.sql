@@ -0,0 +1,7 @@
+-- Migration: Add score bounds
+-- Created: 2026-10-16
+-- Purpose: Keep job result scores of a challenge within the range it expects
+
+-- min, max and out_of_range (Reject or Clamp) of the challenge's job scores;
+-- unset bounds are open
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS score_bounds JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,7 @@
+-- Migration: Add cancelled image builds
+-- Created: 2026-10-16
+-- Purpose: Record builds removed from the build queue before they started
+
+ALTER TABLE image_builds DROP CONSTRAINT IF EXISTS image_builds_status_check;
+ALTER TABLE image_builds ADD CONSTRAINT image_builds_status_check
+    CHECK (status IN ('queued', 'building', 'pushed', 'failed', 'cancelled'));
@@ -0,0 +1,10 @@
+-- Migration: Add metagraph fields to validators
+-- Created: 2026-10-16
+-- Purpose: Keep the uid and stake of registered validators from the metagraph sync
+
+-- Written by the metagraph sync for the neurons that changed since the
+-- previous sync, and on registration. NULL uid and stake: not in the
+-- metagraph. metagraph_block: block of the sync that last changed them
+ALTER TABLE validators ADD COLUMN IF NOT EXISTS uid INTEGER;
+ALTER TABLE validators ADD COLUMN IF NOT EXISTS stake DOUBLE PRECISION;
+ALTER TABLE validators ADD COLUMN IF NOT EXISTS metagraph_block BIGINT;
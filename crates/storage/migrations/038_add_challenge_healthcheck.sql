@@ -0,0 +1,9 @@
+-- Migration: Add challenge health checks
+-- Created: 2026-10-16
+-- Purpose: Let operators check a challenge's container responds before routing validators to it
+
+-- URL answering GET requests once the container is up, and the seconds a
+-- check waits for it; NULL URL: the challenge cannot be checked
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS healthcheck_url TEXT;
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS healthcheck_timeout_secs INTEGER NOT NULL DEFAULT 10
+    CHECK (healthcheck_timeout_secs > 0);
@@ -0,0 +1,8 @@
+-- Migration: Add score policies
+-- Created: 2026-10-16
+-- Purpose: Refuse job results with scores or metrics a challenge cannot aggregate
+
+-- default_range, ranges, require_finite, required_scores and max_metrics of
+-- the challenge's job results; unset fields take their defaults, scores within
+-- 0..=1 for the range
+ALTER TABLE challenges ADD COLUMN IF NOT EXISTS score_policy JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,13 @@
+-- Migration: Add job timeouts
+-- Created: 2026-10-16
+-- Purpose: Start the timeout of jobs when they are claimed rather than created
+
+-- Seconds a job may run once claimed; timeout_at is set from it on every claim
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS timeout_secs BIGINT CHECK (timeout_secs > 0);
+
+-- Jobs created before kept their timeout as a deadline counted from creation
+UPDATE jobs
+SET timeout_secs = GREATEST(CEIL(EXTRACT(EPOCH FROM (timeout_at - created_at))), 1)::BIGINT
+WHERE timeout_secs IS NULL AND timeout_at IS NOT NULL;
+
+UPDATE jobs SET timeout_at = NULL WHERE status IN ('blocked', 'pending');
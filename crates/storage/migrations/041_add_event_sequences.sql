@@ -0,0 +1,16 @@
+-- Migration: Add event sequences
+-- Created: 2026-10-16
+-- Purpose: Order job events and trust scores by insertion rather than timestamp
+
+-- Timestamps are those of the recording transaction, so that the events of
+-- one transition, such as a completion and the dependents it unblocks, tie.
+-- Existing rows are numbered in storage order, the order they were appended in.
+ALTER TABLE job_events ADD COLUMN IF NOT EXISTS seq BIGSERIAL;
+DROP INDEX IF EXISTS idx_job_events_job_id;
+CREATE INDEX IF NOT EXISTS idx_job_events_job_id_seq ON job_events(job_id, seq);
+
+-- Instances computing the score of a validator at once would tie likewise
+ALTER TABLE validator_trust_scores ADD COLUMN IF NOT EXISTS seq BIGSERIAL;
+DROP INDEX IF EXISTS idx_validator_trust_scores_hotkey;
+CREATE INDEX IF NOT EXISTS idx_validator_trust_scores_hotkey_seq
+    ON validator_trust_scores(validator_hotkey, seq DESC);
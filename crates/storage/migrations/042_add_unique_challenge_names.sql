@@ -0,0 +1,15 @@
+-- Migration: Add unique challenge names
+-- Created: 2026-10-16
+-- Purpose: Enforce unique challenge names, ignoring case, in the database
+
+-- Names were only checked before writes, so concurrent ones could share a
+-- name; all but the oldest challenge of a name get their ID appended to it
+UPDATE challenges c
+SET name = c.name || '-' || c.id::text
+WHERE EXISTS (
+    SELECT 1 FROM challenges other
+    WHERE LOWER(other.name) = LOWER(c.name)
+      AND (other.created_at, other.id) < (c.created_at, c.id)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS challenges_name_key ON challenges (LOWER(name));
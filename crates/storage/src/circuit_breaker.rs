@@ -0,0 +1,301 @@
+//! Circuit breaker for the database connection pool
+//!
+//! After `failure_threshold` consecutive connection failures the circuit
+//! opens, and requests fail with [`StorageError::DatabaseUnavailable`] at once
+//! instead of each waiting for the pool to time out. Once
+//! `recovery_window_secs` have passed, a single request goes through as a
+//! probe: the circuit closes if it reaches the database and opens again if
+//! not. Errors returned by the database itself, e.g. a violated constraint,
+//! show it is reachable and do not count as failures.
+
+use platform_api_models::PlatformError;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Error of a storage backend that is not a failure of the query itself
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StorageError {
+    /// The circuit breaker is open, the database was not contacted
+    #[error("Database unavailable")]
+    DatabaseUnavailable,
+}
+
+impl From<StorageError> for PlatformError {
+    fn from(err: StorageError) -> Self {
+        PlatformError::upstream("database", err)
+    }
+}
+
+/// State of a [`DbCircuitBreaker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests go to the database
+    Closed,
+    /// Requests fail without contacting the database
+    Open,
+    /// One request is probing whether the database is back
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive connection failures that open the circuit
+    pub failure_threshold: u32,
+    /// Time the circuit stays open before a request probes the database
+    pub recovery_window_secs: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            recovery_window_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Instant,
+}
+
+/// Circuit breaker guarding the requests of a storage backend
+#[derive(Debug)]
+pub struct DbCircuitBreaker {
+    config: CircuitBreakerConfig,
+    circuit: Mutex<Circuit>,
+}
+
+impl DbCircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            circuit: Mutex::new(Circuit {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.circuit.lock().unwrap().state
+    }
+
+    /// Run `request` unless the circuit is open, recording whether it
+    /// reached the database
+    ///
+    /// Fails with `StorageError::DatabaseUnavailable`, also available as a
+    /// retryable `PlatformError::Upstream`, without running `request` while
+    /// the circuit is open or another request is probing.
+    pub async fn call<T, F>(&self, request: F) -> anyhow::Result<T>
+    where
+        F: Future<Output = anyhow::Result<T>>,
+    {
+        let mut probe = self.acquire()?;
+        let result = request.await;
+        let reached = match &result {
+            Ok(_) => true,
+            Err(e) => !is_connection_error(e),
+        };
+        probe.finished = true;
+        self.record(reached);
+        result
+    }
+
+    fn acquire(&self) -> anyhow::Result<Probe<'_>> {
+        let mut circuit = self.circuit.lock().unwrap();
+        let probing = match circuit.state {
+            CircuitState::Closed => false,
+            CircuitState::Open
+                if circuit.opened_at.elapsed()
+                    >= Duration::from_secs(self.config.recovery_window_secs) =>
+            {
+                info!("Probing the database after the circuit breaker opened");
+                circuit.state = CircuitState::HalfOpen;
+                true
+            }
+            CircuitState::Open | CircuitState::HalfOpen => {
+                return Err(anyhow::Error::new(StorageError::DatabaseUnavailable)
+                    .context(PlatformError::from(StorageError::DatabaseUnavailable)));
+            }
+        };
+        Ok(Probe {
+            breaker: self,
+            probing,
+            finished: false,
+        })
+    }
+
+    fn record(&self, reached: bool) {
+        let mut circuit = self.circuit.lock().unwrap();
+        if reached {
+            if circuit.state != CircuitState::Closed {
+                info!("Database reachable again, closing the circuit breaker");
+            }
+            circuit.state = CircuitState::Closed;
+            circuit.consecutive_failures = 0;
+            return;
+        }
+
+        circuit.consecutive_failures += 1;
+        let reopen = circuit.state == CircuitState::HalfOpen;
+        if reopen || circuit.consecutive_failures >= self.config.failure_threshold {
+            if circuit.state == CircuitState::Closed {
+                warn!(
+                    failures = circuit.consecutive_failures,
+                    "Database unreachable, opening the circuit breaker"
+                );
+            }
+            circuit.state = CircuitState::Open;
+            circuit.opened_at = Instant::now();
+        }
+    }
+}
+
+/// A request let through by the breaker; a probe dropped before it finished
+/// counts as a failure so that another one can be made
+struct Probe<'a> {
+    breaker: &'a DbCircuitBreaker,
+    probing: bool,
+    finished: bool,
+}
+
+impl Drop for Probe<'_> {
+    fn drop(&mut self) {
+        if self.probing && !self.finished {
+            self.breaker.record(false);
+        }
+    }
+}
+
+/// Check if `err` comes from failing to reach the database rather than from
+/// the query
+fn is_connection_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<sqlx::Error>(),
+            Some(
+                sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+                    | sqlx::Error::Io(_)
+                    | sqlx::Error::Tls(_)
+            )
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn breaker(recovery_window_secs: u64) -> DbCircuitBreaker {
+        DbCircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            recovery_window_secs,
+        })
+    }
+
+    async fn unreachable(breaker: &DbCircuitBreaker, calls: &AtomicU32) -> anyhow::Result<()> {
+        breaker
+            .call(async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(sqlx::Error::PoolTimedOut.into())
+            })
+            .await
+    }
+
+    async fn reachable(breaker: &DbCircuitBreaker, calls: &AtomicU32) -> anyhow::Result<()> {
+        breaker
+            .call(async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_fails_fast() {
+        let breaker = breaker(3600);
+        let calls = AtomicU32::new(0);
+
+        unreachable(&breaker, &calls).await.unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        unreachable(&breaker, &calls).await.unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        let err = reachable(&breaker, &calls).await.unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            err.downcast_ref::<StorageError>(),
+            Some(&StorageError::DatabaseUnavailable)
+        );
+        let err = PlatformError::from(err);
+        assert_eq!(err.status_code(), 502);
+        assert!(err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_query_errors_keep_circuit_closed() {
+        let breaker = breaker(3600);
+        let calls = AtomicU32::new(0);
+
+        for _ in 0..3 {
+            breaker
+                .call(async { Err::<(), _>(sqlx::Error::RowNotFound.into()) })
+                .await
+                .unwrap_err();
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        // A success resets the count of consecutive failures
+        unreachable(&breaker, &calls).await.unwrap_err();
+        reachable(&breaker, &calls).await.unwrap();
+        unreachable(&breaker, &calls).await.unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_probe_closes_or_reopens_circuit() {
+        let breaker = breaker(0);
+        let calls = AtomicU32::new(0);
+        unreachable(&breaker, &calls).await.unwrap_err();
+        unreachable(&breaker, &calls).await.unwrap_err();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // A failed probe opens the circuit again at once
+        unreachable(&breaker, &calls).await.unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        reachable(&breaker, &calls).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_single_probe_at_a_time() {
+        let breaker = breaker(0);
+        let calls = AtomicU32::new(0);
+        unreachable(&breaker, &calls).await.unwrap_err();
+        unreachable(&breaker, &calls).await.unwrap_err();
+
+        let probe = breaker.acquire().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        reachable(&breaker, &calls).await.unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // An abandoned probe lets the next request probe again
+        drop(probe);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        reachable(&breaker, &calls).await.unwrap();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
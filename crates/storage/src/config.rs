@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::CircuitBreakerConfig;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     pub backend_type: String,
@@ -7,6 +9,9 @@ pub struct StorageConfig {
     pub s3_region: Option<String>,
     pub minio_endpoint: Option<String>,
     pub encryption_key: String,
+    /// Circuit breaker of the PostgreSQL backend
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
 }
 
 impl Default for StorageConfig {
@@ -17,6 +22,7 @@ impl Default for StorageConfig {
             s3_region: None,
             minio_endpoint: None,
             encryption_key: "disabled".to_string(),
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 }
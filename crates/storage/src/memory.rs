@@ -0,0 +1,932 @@
+//! In-memory storage backend, for local development and tests
+
+use super::{CreateBackupRequest, StorageBackend, StorageConfig, StoredChallenge};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use platform_api_models::*;
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::RwLock;
+use tracing::info;
+use uuid::Uuid;
+
+/// Storage backend keeping its tables in memory
+///
+/// Selected with `STORAGE_BACKEND=memory`, so the API boots without
+/// PostgreSQL; everything is lost on restart. It answers like
+/// [`crate::PostgresStorageBackend`]: each challenge has an emission
+/// schedule sharing its id, whose rate is the challenge's emission share.
+/// Challenges, VM compose configs and job events are only those inserted
+/// with [`MemoryStorageBackend::insert_challenge`],
+/// [`MemoryStorageBackend::insert_vm_compose_config`] and
+/// [`MemoryStorageBackend::insert_job_event`], so tests can run handlers
+/// against it without a database.
+pub struct MemoryStorageBackend {
+    config: StorageConfig,
+    subnet_config: RwLock<Option<SubnetConfig>>,
+    config_backups: RwLock<HashMap<Uuid, ConfigBackup>>,
+    /// Oldest change first
+    config_history: RwLock<Vec<ConfigChangeLog>>,
+    pools: RwLock<HashMap<Uuid, Pool>>,
+    nodes: RwLock<HashMap<Uuid, Node>>,
+    challenges: RwLock<HashMap<Uuid, StoredChallenge>>,
+    /// Emission share of challenges, by challenge id; unset is 0
+    emission_shares: RwLock<HashMap<Uuid, f64>>,
+    vm_compose_configs: RwLock<HashMap<String, VmComposeConfig>>,
+    /// Events of each job, oldest first
+    job_events: RwLock<HashMap<Uuid, Vec<JobEvent>>>,
+}
+
+impl MemoryStorageBackend {
+    pub fn new(config: &StorageConfig) -> Result<Self> {
+        Ok(Self {
+            config: config.clone(),
+            subnet_config: RwLock::new(None),
+            config_backups: RwLock::new(HashMap::new()),
+            config_history: RwLock::new(Vec::new()),
+            pools: RwLock::new(HashMap::new()),
+            nodes: RwLock::new(HashMap::new()),
+            challenges: RwLock::new(HashMap::new()),
+            emission_shares: RwLock::new(HashMap::new()),
+            vm_compose_configs: RwLock::new(HashMap::new()),
+            job_events: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Add or replace a challenge
+    pub async fn insert_challenge(&self, challenge: StoredChallenge) {
+        let mut challenges = self.challenges.write().await;
+        challenges.insert(challenge.metadata.id, challenge);
+    }
+
+    /// Add or replace the VM compose config of its `vm_type`
+    pub async fn insert_vm_compose_config(&self, config: VmComposeConfig) {
+        let mut configs = self.vm_compose_configs.write().await;
+        configs.insert(config.vm_type.clone(), config);
+    }
+
+    /// Record a status transition of a job
+    pub async fn insert_job_event(&self, event: JobEvent) {
+        let mut events = self.job_events.write().await;
+        events.entry(event.job_id).or_default().push(event);
+    }
+
+    /// Emission schedules of the challenges, newest challenge first
+    async fn emission_schedules(&self) -> Vec<EmissionSchedule> {
+        let challenges = self.challenges.read().await;
+        let shares = self.emission_shares.read().await;
+        let mut challenges: Vec<&StoredChallenge> = challenges.values().collect();
+        challenges.sort_by_key(|c| std::cmp::Reverse(c.metadata.created_at));
+        challenges
+            .into_iter()
+            .map(|c| emission_schedule(c, shares.get(&c.metadata.id).copied()))
+            .collect()
+    }
+
+    async fn set_emission_share(&self, challenge_id: Uuid, emission_rate: f64) -> Result<()> {
+        check_emission_rate(emission_rate)?;
+        if !self.challenges.read().await.contains_key(&challenge_id) {
+            return Err(PlatformError::not_found("challenge", challenge_id).into());
+        }
+        let mut shares = self.emission_shares.write().await;
+        shares.insert(challenge_id, emission_rate);
+        Ok(())
+    }
+
+    /// Replace the subnet config with `config`, logging the change
+    async fn replace_subnet_config(
+        &self,
+        config: SubnetConfig,
+        reason: Option<String>,
+    ) -> Result<SubnetConfig> {
+        let mut stored = self.subnet_config.write().await;
+        let change = ConfigChangeLog {
+            id: Uuid::new_v4(),
+            change_type: ConfigChangeType::FullConfigUpdated,
+            old_value: stored.as_ref().map(serde_json::to_value).transpose()?,
+            new_value: serde_json::to_value(&config)?,
+            changed_by: "system".to_string(),
+            timestamp: Utc::now(),
+            reason,
+        };
+        *stored = Some(config.clone());
+        self.config_history.write().await.push(change);
+        Ok(config)
+    }
+}
+
+/// Emission schedule of `challenge`, as the PostgreSQL backend derives it
+/// from the challenge's emission share
+fn emission_schedule(challenge: &StoredChallenge, share: Option<f64>) -> EmissionSchedule {
+    let share = share.unwrap_or(0.0);
+    EmissionSchedule {
+        id: challenge.metadata.id,
+        emission_type: EmissionType::Challenge,
+        challenge_id: Some(challenge.metadata.id),
+        start_time: challenge.metadata.created_at,
+        end_time: None,
+        emission_rate: share,
+        total_amount: share,
+        distributed_amount: 0.0,
+        status: EmissionStatus::Active,
+        distribution_curve: DistributionCurve::Linear,
+        epoch_secs: DEFAULT_EPOCH_BLOCKS * BLOCK_TIME_SECS,
+        created_at: challenge.metadata.created_at,
+        updated_at: challenge.metadata.updated_at,
+    }
+}
+
+fn check_emission_rate(emission_rate: f64) -> Result<()> {
+    if !(0.0..=1.0).contains(&emission_rate) {
+        return Err(PlatformError::invalid(
+            "emission_rate",
+            "Emission rate must be between 0.0 and 1.0",
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Items of page `page`, counted from 1, of `per_page` items
+fn paginate<T>(items: Vec<T>, page: u32, per_page: u32) -> Vec<T> {
+    let start = (page.saturating_sub(1) as usize).saturating_mul(per_page as usize);
+    items
+        .into_iter()
+        .skip(start)
+        .take(per_page as usize)
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl StorageBackend for MemoryStorageBackend {
+    async fn list_challenges(
+        &self,
+        page: u32,
+        per_page: u32,
+        status: Option<String>,
+        _visibility: Option<String>,
+        owner: Option<String>,
+    ) -> Result<ChallengeListResponse> {
+        let challenges = self.challenges.read().await;
+        let mut filtered: Vec<ChallengeMetadata> = challenges
+            .values()
+            .map(|c| c.metadata.clone())
+            .filter(|c| owner.as_ref().is_none_or(|owner| c.owner == *owner))
+            .filter(|c| {
+                status
+                    .as_deref()
+                    .is_none_or(|status| c.status.as_str() == status)
+            })
+            .collect();
+        filtered.sort_by_key(|c| std::cmp::Reverse(c.created_at));
+
+        Ok(ChallengeListResponse {
+            total: filtered.len() as u64,
+            challenges: paginate(filtered, page, per_page),
+            page,
+            per_page,
+        })
+    }
+
+    async fn get_challenge(&self, id: Uuid) -> Result<Option<StoredChallenge>> {
+        let challenges = self.challenges.read().await;
+        Ok(challenges.get(&id).cloned())
+    }
+
+    async fn get_challenge_emissions(&self, id: Uuid) -> Result<EmissionSchedule> {
+        let challenges = self.challenges.read().await;
+        let challenge = challenges
+            .get(&id)
+            .ok_or_else(|| PlatformError::not_found("challenge", id))?;
+        let share = self.emission_shares.read().await.get(&id).copied();
+        Ok(emission_schedule(challenge, share))
+    }
+
+    async fn get_subnet_config(&self) -> Result<SubnetConfig> {
+        let config = self.subnet_config.read().await;
+        config
+            .clone()
+            .ok_or_else(|| PlatformError::not_found("subnet config", "current").into())
+    }
+
+    async fn update_subnet_config(&self, config: SubnetConfig) -> Result<SubnetConfig> {
+        self.replace_subnet_config(config, None).await
+    }
+
+    async fn validate_config(
+        &self,
+        _config: &UpdateConfigRequest,
+    ) -> Result<ConfigValidationResult> {
+        Ok(ConfigValidationResult {
+            is_valid: true,
+            errors: vec![],
+            warnings: vec![],
+            suggestions: vec![],
+        })
+    }
+
+    async fn create_config_backup(&self, _request: CreateBackupRequest) -> Result<ConfigBackup> {
+        use sha2::{Digest, Sha256};
+
+        let config = self.get_subnet_config().await?;
+        let config_json = serde_json::to_string(&config)?;
+        let mut hasher = Sha256::new();
+        hasher.update(config_json.as_bytes());
+        let checksum = hex::encode(hasher.finalize());
+
+        let backup = ConfigBackup {
+            id: Uuid::new_v4(),
+            version: config.version,
+            config,
+            created_at: Utc::now(),
+            created_by: "system".to_string(),
+            checksum,
+        };
+        let mut backups = self.config_backups.write().await;
+        backups.insert(backup.id, backup.clone());
+        Ok(backup)
+    }
+
+    async fn restore_config(&self, request: RestoreConfigRequest) -> Result<()> {
+        if !request.confirm {
+            return Err(
+                PlatformError::invalid("confirm", "Restoring a backup must be confirmed").into(),
+            );
+        }
+        let backup = self.get_config_backup(request.backup_id).await?;
+        let version = match self.get_subnet_config().await {
+            Ok(current) => current.version + 1,
+            Err(_) => backup.config.version,
+        };
+        let config = SubnetConfig {
+            updated_at: Utc::now(),
+            version,
+            ..backup.config
+        };
+        self.replace_subnet_config(config, Some(request.reason))
+            .await?;
+        Ok(())
+    }
+
+    async fn list_config_backups(&self) -> Result<Vec<ConfigBackup>> {
+        let backups = self.config_backups.read().await;
+        let mut backups: Vec<ConfigBackup> = backups.values().cloned().collect();
+        backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+        Ok(backups)
+    }
+
+    async fn get_config_backup(&self, id: Uuid) -> Result<ConfigBackup> {
+        let backups = self.config_backups.read().await;
+        backups
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| PlatformError::not_found("config backup", id).into())
+    }
+
+    async fn get_config_history(&self) -> Result<Vec<ConfigChangeLog>> {
+        Ok(self.config_history.read().await.clone())
+    }
+
+    async fn list_emission_schedules(
+        &self,
+        status: Option<String>,
+        _emission_type: Option<String>,
+        challenge_id: Option<Uuid>,
+    ) -> Result<Vec<EmissionSchedule>> {
+        let status = match status.as_deref() {
+            Some("scheduled") => Some(EmissionStatus::Scheduled),
+            Some("active") => Some(EmissionStatus::Active),
+            Some("completed") => Some(EmissionStatus::Completed),
+            Some("paused") => Some(EmissionStatus::Paused),
+            Some("cancelled") => Some(EmissionStatus::Cancelled),
+            _ => None,
+        };
+        let mut schedules = self.emission_schedules().await;
+        schedules.retain(|s| {
+            challenge_id.is_none_or(|id| s.challenge_id == Some(id))
+                && status.as_ref().is_none_or(|status| s.status == *status)
+        });
+        Ok(schedules)
+    }
+
+    async fn get_emission_schedule(&self, id: Uuid) -> Result<EmissionSchedule> {
+        self.get_challenge_emissions(id).await
+    }
+
+    async fn create_emission_schedule(
+        &self,
+        request: CreateEmissionScheduleRequest,
+    ) -> Result<EmissionSchedule> {
+        let challenge_id = request.challenge_id.ok_or_else(|| {
+            PlatformError::invalid(
+                "challenge_id",
+                "challenge_id is required for emission schedules",
+            )
+        })?;
+        self.set_emission_share(challenge_id, request.emission_rate)
+            .await?;
+        self.get_challenge_emissions(challenge_id).await
+    }
+
+    async fn update_emission_schedule(
+        &self,
+        id: Uuid,
+        request: UpdateEmissionScheduleRequest,
+    ) -> Result<EmissionSchedule> {
+        if let Some(emission_rate) = request.emission_rate {
+            self.set_emission_share(id, emission_rate).await?;
+        }
+        self.get_challenge_emissions(id).await
+    }
+
+    async fn distribute_emission(
+        &self,
+        id: Uuid,
+        _request: DistributeEmissionRequest,
+    ) -> Result<()> {
+        self.get_emission_schedule(id).await?;
+        info!(
+            "Emission distribution requested for schedule {}, but distribution is handled automatically through weight aggregation",
+            id
+        );
+        Ok(())
+    }
+
+    async fn calculate_emission(
+        &self,
+        request: CalculateEmissionRequest,
+    ) -> Result<CalculateEmissionResponse> {
+        let schedules = match request.challenge_id {
+            Some(challenge_id) => vec![self.get_challenge_emissions(challenge_id).await?],
+            None => self.emission_schedules().await,
+        };
+        let breakdown: BTreeMap<String, f64> = schedules
+            .iter()
+            .filter_map(|s| {
+                s.challenge_id
+                    .map(|id| (format!("challenge_{}", id), s.emission_rate))
+            })
+            .collect();
+
+        Ok(CalculateEmissionResponse {
+            total_emission: schedules.iter().map(|s| s.emission_rate).sum(),
+            breakdown,
+            distributions: vec![],
+            metrics: EmissionMetrics {
+                participation_rate: 1.0,
+                quality_score: 1.0,
+                efficiency_score: 1.0,
+                fairness_score: 1.0,
+                sustainability_score: 1.0,
+            },
+        })
+    }
+
+    async fn get_emission_aggregate(
+        &self,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> Result<EmissionAggregate> {
+        let schedules = self.emission_schedules().await;
+        let total_emissions = schedules.iter().map(|s| s.emission_rate).sum();
+        let challenge_emissions = schedules
+            .iter()
+            .filter(|s| s.emission_type == EmissionType::Challenge)
+            .map(|s| s.emission_rate)
+            .sum();
+
+        Ok(EmissionAggregate {
+            period_start,
+            period_end,
+            total_emissions,
+            challenge_emissions,
+            validator_emissions: 0.0,
+            miner_emissions: 0.0,
+            owner_emissions: 0.0,
+            network_emissions: 0.0,
+            distributions: vec![],
+        })
+    }
+
+    async fn get_challenge_emission_metrics(&self, id: Uuid) -> Result<ChallengeEmissionMetrics> {
+        let schedule = self.get_challenge_emissions(id).await?;
+
+        Ok(ChallengeEmissionMetrics {
+            challenge_id: id,
+            total_emission: schedule.total_amount,
+            distributed_emission: schedule.distributed_amount,
+            pending_emission: schedule.total_amount - schedule.distributed_amount,
+            emission_rate: schedule.emission_rate,
+            participation_score: 1.0,
+            quality_score: 1.0,
+            efficiency_score: 1.0,
+            last_distribution: None,
+            next_distribution: None,
+        })
+    }
+
+    async fn get_validator_emission_metrics(
+        &self,
+        hotkey: &str,
+    ) -> Result<ValidatorEmissionMetrics> {
+        // Validators don't have direct emissions in this model
+        Ok(ValidatorEmissionMetrics {
+            validator_hotkey: hotkey.to_string(),
+            total_emission: 0.0,
+            distributed_emission: 0.0,
+            pending_emission: 0.0,
+            performance_score: 1.0,
+            uptime_score: 1.0,
+            accuracy_score: 1.0,
+            efficiency_score: 1.0,
+            last_distribution: None,
+            next_distribution: None,
+        })
+    }
+
+    async fn get_miner_emission_metrics(&self, hotkey: &str) -> Result<MinerEmissionMetrics> {
+        // Miners don't have direct emissions in this model
+        Ok(MinerEmissionMetrics {
+            miner_hotkey: hotkey.to_string(),
+            total_emission: 0.0,
+            distributed_emission: 0.0,
+            pending_emission: 0.0,
+            submission_score: 1.0,
+            quality_score: 1.0,
+            participation_score: 1.0,
+            innovation_score: 1.0,
+            last_distribution: None,
+            next_distribution: None,
+        })
+    }
+
+    async fn get_emission_report(
+        &self,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> Result<EmissionReport> {
+        let aggregate = self
+            .get_emission_aggregate(period_start, period_end)
+            .await?;
+        let schedule_count = self.challenges.read().await.len() as u32;
+
+        Ok(EmissionReport {
+            period_start,
+            period_end,
+            total_emissions: aggregate.total_emissions,
+            schedule_count,
+            distribution_count: 0,
+            recipient_count: 0,
+            avg_distribution_amount: 0.0,
+            top_recipients: vec![],
+            emission_trends: BTreeMap::new(),
+        })
+    }
+
+    // Pool implementations
+    async fn list_pools(
+        &self,
+        validator_hotkey: Option<&str>,
+        page: u32,
+        per_page: u32,
+    ) -> Result<PoolListResponse> {
+        let pools = self.pools.read().await;
+        let mut filtered: Vec<Pool> = pools.values().cloned().collect();
+
+        if let Some(hotkey) = validator_hotkey {
+            filtered.retain(|p| p.validator_hotkey == hotkey);
+        }
+        filtered.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+
+        Ok(PoolListResponse {
+            total: filtered.len() as u64,
+            pools: paginate(filtered, page, per_page),
+            page,
+            per_page,
+        })
+    }
+
+    async fn get_pool(&self, id: Uuid) -> Result<Pool> {
+        let pools = self.pools.read().await;
+        pools
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| PlatformError::not_found("pool", id).into())
+    }
+
+    async fn create_pool(
+        &self,
+        validator_hotkey: &str,
+        request: CreatePoolRequest,
+    ) -> Result<Pool> {
+        let mut pools = self.pools.write().await;
+        let pool = Pool {
+            id: Uuid::new_v4(),
+            validator_hotkey: validator_hotkey.to_string(),
+            name: request.name,
+            description: request.description,
+            autoscale_policy: request.autoscale_policy.unwrap_or_default(),
+            region: request.region,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        pools.insert(pool.id, pool.clone());
+        Ok(pool)
+    }
+
+    async fn update_pool(&self, id: Uuid, request: UpdatePoolRequest) -> Result<Pool> {
+        let mut pools = self.pools.write().await;
+        let pool = pools
+            .get_mut(&id)
+            .ok_or_else(|| PlatformError::not_found("pool", id))?;
+
+        if let Some(name) = request.name {
+            pool.name = name;
+        }
+        if let Some(description) = request.description {
+            pool.description = Some(description);
+        }
+        if let Some(autoscale_policy) = request.autoscale_policy {
+            pool.autoscale_policy = autoscale_policy;
+        }
+        if let Some(region) = request.region {
+            pool.region = Some(region);
+        }
+        pool.updated_at = Utc::now();
+
+        Ok(pool.clone())
+    }
+
+    async fn delete_pool(&self, id: Uuid) -> Result<()> {
+        let mut pools = self.pools.write().await;
+        pools
+            .remove(&id)
+            .ok_or_else(|| PlatformError::not_found("pool", id))?;
+        // Nodes go with their pool, as with the foreign key of the database
+        self.nodes.write().await.retain(|_, n| n.pool_id != id);
+        Ok(())
+    }
+
+    // Node implementations
+    async fn list_nodes(
+        &self,
+        pool_id: Option<Uuid>,
+        page: u32,
+        per_page: u32,
+    ) -> Result<NodeListResponse> {
+        let nodes = self.nodes.read().await;
+        let mut filtered: Vec<Node> = nodes.values().cloned().collect();
+
+        if let Some(pid) = pool_id {
+            filtered.retain(|n| n.pool_id == pid);
+        }
+        filtered.sort_by_key(|n| std::cmp::Reverse(n.created_at));
+
+        Ok(NodeListResponse {
+            total: filtered.len() as u64,
+            nodes: paginate(filtered, page, per_page),
+            page,
+            per_page,
+        })
+    }
+
+    async fn get_node(&self, id: Uuid) -> Result<Node> {
+        let nodes = self.nodes.read().await;
+        nodes
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| PlatformError::not_found("node", id).into())
+    }
+
+    async fn add_node(&self, pool_id: Uuid, request: AddNodeRequest) -> Result<Node> {
+        if !self.pools.read().await.contains_key(&pool_id) {
+            return Err(PlatformError::not_found("pool", pool_id).into());
+        }
+        let mut nodes = self.nodes.write().await;
+        let node = Node {
+            id: Uuid::new_v4(),
+            pool_id,
+            name: request.name,
+            vmm_url: request.vmm_url,
+            capacity: request.capacity,
+            health: NodeHealth::default(),
+            metadata: request.metadata.unwrap_or_default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        nodes.insert(node.id, node.clone());
+        Ok(node)
+    }
+
+    async fn update_node(&self, id: Uuid, request: UpdateNodeRequest) -> Result<Node> {
+        let mut nodes = self.nodes.write().await;
+        let node = nodes
+            .get_mut(&id)
+            .ok_or_else(|| PlatformError::not_found("node", id))?;
+
+        if let Some(name) = request.name {
+            node.name = name;
+        }
+        if let Some(vmm_url) = request.vmm_url {
+            node.vmm_url = vmm_url;
+        }
+        if let Some(capacity) = request.capacity {
+            node.capacity = capacity;
+        }
+        if let Some(metadata) = request.metadata {
+            node.metadata = metadata;
+        }
+        node.updated_at = Utc::now();
+
+        Ok(node.clone())
+    }
+
+    async fn delete_node(&self, id: Uuid) -> Result<()> {
+        let mut nodes = self.nodes.write().await;
+        nodes
+            .remove(&id)
+            .ok_or_else(|| PlatformError::not_found("node", id))?;
+        Ok(())
+    }
+
+    // Capacity implementation
+    async fn get_pool_capacity(&self, pool_id: Uuid) -> Result<PoolCapacitySummary> {
+        let nodes = self.nodes.read().await;
+        let pool_nodes: Vec<&Node> = nodes.values().filter(|n| n.pool_id == pool_id).collect();
+
+        let healthy_nodes = pool_nodes
+            .iter()
+            .filter(|n| matches!(n.health.status, HealthStatus::Healthy))
+            .count() as u32;
+
+        let total_cpu: u32 = pool_nodes.iter().map(|n| n.capacity.total_cpu).sum();
+        let available_cpu: u32 = pool_nodes.iter().map(|n| n.capacity.available_cpu).sum();
+        let total_memory_gb: u32 = pool_nodes.iter().map(|n| n.capacity.total_memory_gb).sum();
+        let available_memory_gb: u32 = pool_nodes
+            .iter()
+            .map(|n| n.capacity.available_memory_gb)
+            .sum();
+        let has_tdx = pool_nodes.iter().any(|n| n.capacity.has_tdx);
+        let gpu_count: u32 = pool_nodes.iter().map(|n| n.capacity.gpu_count).sum();
+
+        Ok(PoolCapacitySummary {
+            pool_id,
+            total_nodes: pool_nodes.len() as u32,
+            healthy_nodes,
+            total_cpu,
+            available_cpu,
+            total_memory_gb,
+            available_memory_gb,
+            has_tdx,
+            gpu_count,
+        })
+    }
+
+    async fn get_vm_compose_config(&self, vm_type: &str) -> Result<VmComposeConfig> {
+        let configs = self.vm_compose_configs.read().await;
+        configs
+            .get(vm_type)
+            .cloned()
+            .ok_or_else(|| PlatformError::not_found("VM compose config", vm_type).into())
+    }
+
+    async fn get_job_history(&self, job_id: Uuid) -> Result<Vec<JobEvent>> {
+        let events = self.job_events.read().await;
+        Ok(events.get(&job_id).cloned().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_storage_backend() {
+        let config = StorageConfig::default();
+        let backend = MemoryStorageBackend::new(&config).unwrap();
+        let result = backend
+            .list_challenges(1, 20, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(result.total, 0);
+    }
+
+    fn challenge(
+        name: &str,
+        owner: &str,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> StoredChallenge {
+        StoredChallenge {
+            metadata: ChallengeMetadata {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                description: String::new(),
+                version: "1.0.0".to_string(),
+                visibility: ChallengeVisibility::Public,
+                status: ChallengeStatus::Active,
+                owner: owner.to_string(),
+                created_at,
+                updated_at: created_at,
+                tags: vec![],
+                default_job_priority: JobPriority::Normal,
+                job_payload_schema: None,
+                job_defaults: JobDefaults::default(),
+                resources: Default::default(),
+                score_bounds: ScoreBounds::default(),
+                score_policy: ScorePolicy::default(),
+                healthcheck_url: None,
+                healthcheck_timeout_secs: DEFAULT_HEALTHCHECK_TIMEOUT_SECS,
+            },
+            compose_hash: format!("{}-hash", name),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_serves_inserted_challenges() {
+        let backend = MemoryStorageBackend::new(&StorageConfig::default()).unwrap();
+        let now = chrono::Utc::now();
+        let older = challenge("older", "alice", now - chrono::Duration::hours(1));
+        let newer = challenge("newer", "alice", now);
+        let other = challenge("other", "bob", now - chrono::Duration::hours(2));
+        for c in [&older, &newer, &other] {
+            backend.insert_challenge(c.clone()).await;
+        }
+
+        let all = backend
+            .list_challenges(1, 2, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(all.total, 3);
+        let names: Vec<_> = all.challenges.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["newer", "older"]);
+
+        let alice = backend
+            .list_challenges(1, 20, None, None, Some("alice".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(alice.total, 2);
+
+        // Pages past the end are empty, however far
+        let past = backend
+            .list_challenges(u32::MAX, u32::MAX, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(past.total, 3);
+        assert!(past.challenges.is_empty());
+
+        let found = backend
+            .get_challenge(other.metadata.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.compose_hash, "other-hash");
+        assert!(backend
+            .get_challenge(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_config_backups() {
+        let backend = MemoryStorageBackend::new(&StorageConfig::default()).unwrap();
+        let err = backend.get_subnet_config().await.unwrap_err();
+        assert_eq!(PlatformError::from(err).status_code(), 404);
+
+        let original = backend
+            .update_subnet_config(SubnetConfig::default())
+            .await
+            .unwrap();
+        let backup = backend
+            .create_config_backup(CreateBackupRequest {
+                reason: "before rake change".to_string(),
+                tags: None,
+            })
+            .await
+            .unwrap();
+        backend
+            .update_subnet_config(SubnetConfig {
+                rake: 0.2,
+                version: original.version + 1,
+                ..original.clone()
+            })
+            .await
+            .unwrap();
+
+        let unconfirmed = RestoreConfigRequest {
+            backup_id: backup.id,
+            reason: "rollback".to_string(),
+            confirm: false,
+        };
+        let err = backend.restore_config(unconfirmed).await.unwrap_err();
+        assert_eq!(PlatformError::from(err).status_code(), 400);
+
+        let restore = RestoreConfigRequest {
+            backup_id: backup.id,
+            reason: "rollback".to_string(),
+            confirm: true,
+        };
+        backend.restore_config(restore).await.unwrap();
+        let restored = backend.get_subnet_config().await.unwrap();
+        assert_eq!(restored.rake, original.rake);
+        assert_eq!(restored.version, original.version + 2);
+
+        assert_eq!(backend.list_config_backups().await.unwrap().len(), 1);
+        let history = backend.get_config_history().await.unwrap();
+        assert_eq!(history.len(), 3);
+        assert!(history[0].old_value.is_none());
+        assert_eq!(history[2].reason.as_deref(), Some("rollback"));
+
+        let err = backend.get_config_backup(Uuid::new_v4()).await.unwrap_err();
+        assert!(matches!(
+            PlatformError::from(err),
+            PlatformError::NotFound { resource, .. } if resource == "config backup"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_emission_schedules() {
+        let backend = MemoryStorageBackend::new(&StorageConfig::default()).unwrap();
+        let stored = challenge("scored", "alice", chrono::Utc::now());
+        let id = stored.metadata.id;
+        backend.insert_challenge(stored).await;
+
+        let schedule = backend.get_challenge_emissions(id).await.unwrap();
+        assert_eq!(schedule.id, id);
+        assert_eq!(schedule.emission_rate, 0.0);
+
+        let request = |emission_rate| CreateEmissionScheduleRequest {
+            emission_type: EmissionType::Challenge,
+            challenge_id: Some(id),
+            start_time: chrono::Utc::now(),
+            end_time: None,
+            emission_rate,
+            total_amount: 0.0,
+            distribution_curve: DistributionCurve::Linear,
+        };
+        let err = backend
+            .create_emission_schedule(request(1.5))
+            .await
+            .unwrap_err();
+        assert_eq!(PlatformError::from(err).status_code(), 400);
+        let schedule = backend
+            .create_emission_schedule(request(0.25))
+            .await
+            .unwrap();
+        assert_eq!(schedule.emission_rate, 0.25);
+
+        let schedules = backend
+            .list_emission_schedules(Some("active".to_string()), None, Some(id))
+            .await
+            .unwrap();
+        assert_eq!(schedules.len(), 1);
+        let calculated = backend
+            .calculate_emission(CalculateEmissionRequest {
+                challenge_id: None,
+                validator_hotkey: None,
+                miner_hotkey: None,
+                period_start: chrono::Utc::now(),
+                period_end: chrono::Utc::now(),
+                include_pending: false,
+            })
+            .await
+            .unwrap();
+        assert_eq!(calculated.total_emission, 0.25);
+
+        let err = backend
+            .get_emission_schedule(Uuid::new_v4())
+            .await
+            .unwrap_err();
+        assert_eq!(PlatformError::from(err).status_code(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_job_history() {
+        let backend = MemoryStorageBackend::new(&StorageConfig::default()).unwrap();
+        let job_id = Uuid::new_v4();
+        for (old_status, new_status) in [
+            (None, JobStatus::Pending),
+            (Some(JobStatus::Pending), JobStatus::Claimed),
+        ] {
+            backend
+                .insert_job_event(JobEvent {
+                    id: Uuid::new_v4(),
+                    job_id,
+                    old_status,
+                    new_status,
+                    actor: None,
+                    timestamp: chrono::Utc::now(),
+                    metadata: serde_json::json!({}),
+                })
+                .await;
+        }
+
+        let history = backend.get_job_history(job_id).await.unwrap();
+        let statuses: Vec<_> = history.iter().map(|e| e.new_status.clone()).collect();
+        assert_eq!(statuses, vec![JobStatus::Pending, JobStatus::Claimed]);
+        assert!(backend
+            .get_job_history(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,32 +1,111 @@
 //! Challenge and configuration operations
 
-use super::PostgresStorageBackend;
-use crate::CreateBackupRequest;
+use super::{rows::ChallengeMetadataRow, PostgresStorageBackend};
+use crate::{CreateBackupRequest, StoredChallenge};
 use anyhow::Result;
 use platform_api_models::*;
 use uuid::Uuid;
 
+/// Columns of `ChallengeMetadataRow`
+const CHALLENGE_METADATA_COLUMNS: &str = "id, name, compose_hash, version, description, owner, \
+    status, default_job_priority, job_payload_schema, job_defaults, resource_requirements, \
+    score_bounds, score_policy, healthcheck_url, healthcheck_timeout_secs, created_at, updated_at";
+
+impl From<ChallengeMetadataRow> for StoredChallenge {
+    fn from(row: ChallengeMetadataRow) -> Self {
+        Self {
+            metadata: ChallengeMetadata {
+                id: row.id,
+                name: row.name,
+                description: row.description.unwrap_or_default(),
+                version: row.version,
+                visibility: ChallengeVisibility::Public,
+                status: ChallengeStatus::from(row.status.as_str()),
+                owner: row.owner,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+                tags: vec![],
+                default_job_priority: JobPriority::from(row.default_job_priority.as_str()),
+                job_payload_schema: row.job_payload_schema,
+                job_defaults: serde_json::from_value(row.job_defaults).unwrap_or_default(),
+                resources: serde_json::from_value(row.resource_requirements).unwrap_or_default(),
+                score_bounds: serde_json::from_value(row.score_bounds).unwrap_or_default(),
+                score_policy: serde_json::from_value(row.score_policy).unwrap_or_default(),
+                healthcheck_url: row.healthcheck_url,
+                healthcheck_timeout_secs: row.healthcheck_timeout_secs as u32,
+            },
+            compose_hash: row.compose_hash,
+        }
+    }
+}
+
 impl PostgresStorageBackend {
-    /// List challenges (currently returns empty as challenges are managed in memory)
+    /// List challenges, newest first
     pub async fn list_challenges_impl(
         &self,
         page: u32,
         per_page: u32,
-        _status: Option<String>,
+        status: Option<String>,
         _visibility: Option<String>,
+        owner: Option<String>,
     ) -> Result<ChallengeListResponse> {
-        // For now, return empty list - challenges are managed in memory via challenge_registry
+        let offset = page.saturating_sub(1) as i64 * per_page as i64;
+
+        let total: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM challenges
+            WHERE ($1::TEXT IS NULL OR owner = $1)
+              AND ($2::TEXT IS NULL OR status = $2)
+            "#,
+        )
+        .persistent(false)
+        .bind(owner.as_deref())
+        .bind(status.as_deref())
+        .fetch_one(&self.pool)
+        .await?;
+
+        let rows = sqlx::query_as::<_, ChallengeMetadataRow>(&format!(
+            r#"
+            SELECT {}
+            FROM challenges
+            WHERE ($1::TEXT IS NULL OR owner = $1)
+              AND ($2::TEXT IS NULL OR status = $2)
+            ORDER BY created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            CHALLENGE_METADATA_COLUMNS
+        ))
+        .persistent(false)
+        .bind(owner.as_deref())
+        .bind(status.as_deref())
+        .bind(per_page as i64)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
         Ok(ChallengeListResponse {
-            challenges: vec![],
-            total: 0,
+            challenges: rows
+                .into_iter()
+                .map(|row| StoredChallenge::from(row).metadata)
+                .collect(),
+            total: total as u64,
             page,
             per_page,
         })
     }
 
-    /// Get challenge details
-    pub async fn get_challenge_impl(&self, _id: Uuid) -> Result<ChallengeDetailResponse> {
-        Err(anyhow::anyhow!("Challenge not found"))
+    /// Get a challenge, `None` if it does not exist
+    pub async fn get_challenge_impl(&self, id: Uuid) -> Result<Option<StoredChallenge>> {
+        let row = sqlx::query_as::<_, ChallengeMetadataRow>(&format!(
+            "SELECT {} FROM challenges WHERE id = $1",
+            CHALLENGE_METADATA_COLUMNS
+        ))
+        .persistent(false)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(StoredChallenge::from))
     }
 
     /// Get subnet configuration
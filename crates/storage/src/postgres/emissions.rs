@@ -31,6 +31,7 @@ impl PostgresStorageBackend {
             distributed_amount: 0.0,
             status: EmissionStatus::Active,
             distribution_curve: DistributionCurve::Linear,
+            epoch_secs: DEFAULT_EPOCH_BLOCKS * BLOCK_TIME_SECS,
             created_at: row.created_at,
             updated_at: row.updated_at,
         })
@@ -71,6 +72,7 @@ impl PostgresStorageBackend {
                 distributed_amount: 0.0,
                 status: EmissionStatus::Active,
                 distribution_curve: DistributionCurve::Linear,
+                epoch_secs: DEFAULT_EPOCH_BLOCKS * BLOCK_TIME_SECS,
                 created_at: row.created_at,
                 updated_at: row.updated_at,
             })
@@ -0,0 +1,36 @@
+//! Job history operations
+
+use super::{JobEventRow, PostgresStorageBackend};
+use anyhow::Result;
+use platform_api_models::*;
+use uuid::Uuid;
+
+impl PostgresStorageBackend {
+    /// Get the status transitions of a job, oldest first
+    pub async fn get_job_history_impl(&self, job_id: Uuid) -> Result<Vec<JobEvent>> {
+        let rows = sqlx::query_as::<_, JobEventRow>(
+            r#"
+            SELECT id, job_id, old_status, new_status, actor, timestamp, metadata
+            FROM job_events
+            WHERE job_id = $1
+            ORDER BY seq
+            "#,
+        )
+        .bind(job_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| JobEvent {
+                id: row.id,
+                job_id: row.job_id,
+                old_status: row.old_status.as_deref().map(JobStatus::from),
+                new_status: JobStatus::from(row.new_status.as_str()),
+                actor: row.actor,
+                timestamp: row.timestamp,
+                metadata: row.metadata,
+            })
+            .collect())
+    }
+}
@@ -2,13 +2,14 @@
 
 mod challenges;
 mod emissions;
+mod jobs;
 mod nodes;
 mod pools;
 mod rows;
 
 pub use rows::*;
 
-use super::StorageBackend;
+use super::{CircuitBreakerConfig, CircuitState, DbCircuitBreaker, StorageBackend};
 use anyhow::Result;
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use tracing::info;
@@ -16,6 +17,7 @@ use tracing::info;
 /// PostgreSQL storage backend
 pub struct PostgresStorageBackend {
     pool: PgPool,
+    breaker: DbCircuitBreaker,
 }
 
 impl PostgresStorageBackend {
@@ -64,7 +66,17 @@ impl PostgresStorageBackend {
             info!("✅ Database migrations completed");
         }
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            breaker: DbCircuitBreaker::new(CircuitBreakerConfig::default()),
+        })
+    }
+
+    /// Guard the backend's requests with a circuit breaker configured by
+    /// `config`
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.breaker = DbCircuitBreaker::new(config);
+        self
     }
 
     /// Get the underlying database connection pool
@@ -81,67 +93,72 @@ impl StorageBackend for PostgresStorageBackend {
         per_page: u32,
         status: Option<String>,
         visibility: Option<String>,
+        owner: Option<String>,
     ) -> Result<platform_api_models::ChallengeListResponse> {
-        self.list_challenges_impl(page, per_page, status, visibility)
+        self.breaker
+            .call(self.list_challenges_impl(page, per_page, status, visibility, owner))
             .await
     }
 
-    async fn get_challenge(
-        &self,
-        id: uuid::Uuid,
-    ) -> Result<platform_api_models::ChallengeDetailResponse> {
-        self.get_challenge_impl(id).await
+    async fn get_challenge(&self, id: uuid::Uuid) -> Result<Option<crate::StoredChallenge>> {
+        self.breaker.call(self.get_challenge_impl(id)).await
     }
 
     async fn get_challenge_emissions(
         &self,
         id: uuid::Uuid,
     ) -> Result<platform_api_models::EmissionSchedule> {
-        self.get_challenge_emissions_impl(id).await
+        self.breaker
+            .call(self.get_challenge_emissions_impl(id))
+            .await
     }
 
     async fn get_subnet_config(&self) -> Result<platform_api_models::SubnetConfig> {
-        self.get_subnet_config_impl().await
+        self.breaker.call(self.get_subnet_config_impl()).await
     }
 
     async fn update_subnet_config(
         &self,
         config: platform_api_models::SubnetConfig,
     ) -> Result<platform_api_models::SubnetConfig> {
-        self.update_subnet_config_impl(config).await
+        self.breaker
+            .call(self.update_subnet_config_impl(config))
+            .await
     }
 
     async fn validate_config(
         &self,
         config: &platform_api_models::UpdateConfigRequest,
     ) -> Result<platform_api_models::ConfigValidationResult> {
-        self.validate_config_impl(config).await
+        self.breaker.call(self.validate_config_impl(config)).await
     }
 
     async fn create_config_backup(
         &self,
         request: crate::CreateBackupRequest,
     ) -> Result<platform_api_models::ConfigBackup> {
-        self.create_config_backup_impl(request).await
+        self.breaker
+            .call(self.create_config_backup_impl(request))
+            .await
     }
 
     async fn restore_config(
         &self,
         request: platform_api_models::RestoreConfigRequest,
     ) -> Result<()> {
-        self.restore_config_impl(request).await
+        self.breaker.call(self.restore_config_impl(request)).await
     }
 
     async fn list_config_backups(&self) -> Result<Vec<platform_api_models::ConfigBackup>> {
-        self.list_config_backups_impl().await
+        self.breaker.call(self.list_config_backups_impl()).await
     }
 
     async fn get_config_backup(&self, id: uuid::Uuid) -> Result<platform_api_models::ConfigBackup> {
-        self.get_config_backup_impl(id).await
+        self.breaker.call(self.get_config_backup_impl(id)).await
     }
 
     async fn get_config_history(&self) -> Result<Vec<platform_api_models::ConfigChangeLog>> {
-        self.get_config_history_impl().await
+        self.breaker.call(self.get_config_history_impl()).await
     }
 
     async fn list_emission_schedules(
@@ -150,7 +167,8 @@ impl StorageBackend for PostgresStorageBackend {
         emission_type: Option<String>,
         challenge_id: Option<uuid::Uuid>,
     ) -> Result<Vec<platform_api_models::EmissionSchedule>> {
-        self.list_emission_schedules_impl(status, emission_type, challenge_id)
+        self.breaker
+            .call(self.list_emission_schedules_impl(status, emission_type, challenge_id))
             .await
     }
 
@@ -158,14 +176,16 @@ impl StorageBackend for PostgresStorageBackend {
         &self,
         id: uuid::Uuid,
     ) -> Result<platform_api_models::EmissionSchedule> {
-        self.get_emission_schedule_impl(id).await
+        self.breaker.call(self.get_emission_schedule_impl(id)).await
     }
 
     async fn create_emission_schedule(
         &self,
         request: platform_api_models::CreateEmissionScheduleRequest,
     ) -> Result<platform_api_models::EmissionSchedule> {
-        self.create_emission_schedule_impl(request).await
+        self.breaker
+            .call(self.create_emission_schedule_impl(request))
+            .await
     }
 
     async fn update_emission_schedule(
@@ -173,7 +193,9 @@ impl StorageBackend for PostgresStorageBackend {
         id: uuid::Uuid,
         request: platform_api_models::UpdateEmissionScheduleRequest,
     ) -> Result<platform_api_models::EmissionSchedule> {
-        self.update_emission_schedule_impl(id, request).await
+        self.breaker
+            .call(self.update_emission_schedule_impl(id, request))
+            .await
     }
 
     async fn distribute_emission(
@@ -181,14 +203,18 @@ impl StorageBackend for PostgresStorageBackend {
         id: uuid::Uuid,
         request: platform_api_models::DistributeEmissionRequest,
     ) -> Result<()> {
-        self.distribute_emission_impl(id, request).await
+        self.breaker
+            .call(self.distribute_emission_impl(id, request))
+            .await
     }
 
     async fn calculate_emission(
         &self,
         request: platform_api_models::CalculateEmissionRequest,
     ) -> Result<platform_api_models::CalculateEmissionResponse> {
-        self.calculate_emission_impl(request).await
+        self.breaker
+            .call(self.calculate_emission_impl(request))
+            .await
     }
 
     async fn get_emission_aggregate(
@@ -196,7 +222,8 @@ impl StorageBackend for PostgresStorageBackend {
         period_start: chrono::DateTime<chrono::Utc>,
         period_end: chrono::DateTime<chrono::Utc>,
     ) -> Result<platform_api_models::EmissionAggregate> {
-        self.get_emission_aggregate_impl(period_start, period_end)
+        self.breaker
+            .call(self.get_emission_aggregate_impl(period_start, period_end))
             .await
     }
 
@@ -204,21 +231,27 @@ impl StorageBackend for PostgresStorageBackend {
         &self,
         id: uuid::Uuid,
     ) -> Result<platform_api_models::ChallengeEmissionMetrics> {
-        self.get_challenge_emission_metrics_impl(id).await
+        self.breaker
+            .call(self.get_challenge_emission_metrics_impl(id))
+            .await
     }
 
     async fn get_validator_emission_metrics(
         &self,
         hotkey: &str,
     ) -> Result<platform_api_models::ValidatorEmissionMetrics> {
-        self.get_validator_emission_metrics_impl(hotkey).await
+        self.breaker
+            .call(self.get_validator_emission_metrics_impl(hotkey))
+            .await
     }
 
     async fn get_miner_emission_metrics(
         &self,
         hotkey: &str,
     ) -> Result<platform_api_models::MinerEmissionMetrics> {
-        self.get_miner_emission_metrics_impl(hotkey).await
+        self.breaker
+            .call(self.get_miner_emission_metrics_impl(hotkey))
+            .await
     }
 
     async fn get_emission_report(
@@ -226,7 +259,8 @@ impl StorageBackend for PostgresStorageBackend {
         period_start: chrono::DateTime<chrono::Utc>,
         period_end: chrono::DateTime<chrono::Utc>,
     ) -> Result<platform_api_models::EmissionReport> {
-        self.get_emission_report_impl(period_start, period_end)
+        self.breaker
+            .call(self.get_emission_report_impl(period_start, period_end))
             .await
     }
 
@@ -236,11 +270,13 @@ impl StorageBackend for PostgresStorageBackend {
         page: u32,
         per_page: u32,
     ) -> Result<platform_api_models::PoolListResponse> {
-        self.list_pools_impl(validator_hotkey, page, per_page).await
+        self.breaker
+            .call(self.list_pools_impl(validator_hotkey, page, per_page))
+            .await
     }
 
     async fn get_pool(&self, id: uuid::Uuid) -> Result<platform_api_models::Pool> {
-        self.get_pool_impl(id).await
+        self.breaker.call(self.get_pool_impl(id)).await
     }
 
     async fn create_pool(
@@ -248,7 +284,9 @@ impl StorageBackend for PostgresStorageBackend {
         validator_hotkey: &str,
         request: platform_api_models::CreatePoolRequest,
     ) -> Result<platform_api_models::Pool> {
-        self.create_pool_impl(validator_hotkey, request).await
+        self.breaker
+            .call(self.create_pool_impl(validator_hotkey, request))
+            .await
     }
 
     async fn update_pool(
@@ -256,11 +294,11 @@ impl StorageBackend for PostgresStorageBackend {
         id: uuid::Uuid,
         request: platform_api_models::UpdatePoolRequest,
     ) -> Result<platform_api_models::Pool> {
-        self.update_pool_impl(id, request).await
+        self.breaker.call(self.update_pool_impl(id, request)).await
     }
 
     async fn delete_pool(&self, id: uuid::Uuid) -> Result<()> {
-        self.delete_pool_impl(id).await
+        self.breaker.call(self.delete_pool_impl(id)).await
     }
 
     async fn list_nodes(
@@ -269,11 +307,13 @@ impl StorageBackend for PostgresStorageBackend {
         page: u32,
         per_page: u32,
     ) -> Result<platform_api_models::NodeListResponse> {
-        self.list_nodes_impl(pool_id, page, per_page).await
+        self.breaker
+            .call(self.list_nodes_impl(pool_id, page, per_page))
+            .await
     }
 
     async fn get_node(&self, id: uuid::Uuid) -> Result<platform_api_models::Node> {
-        self.get_node_impl(id).await
+        self.breaker.call(self.get_node_impl(id)).await
     }
 
     async fn add_node(
@@ -281,7 +321,9 @@ impl StorageBackend for PostgresStorageBackend {
         pool_id: uuid::Uuid,
         request: platform_api_models::AddNodeRequest,
     ) -> Result<platform_api_models::Node> {
-        self.add_node_impl(pool_id, request).await
+        self.breaker
+            .call(self.add_node_impl(pool_id, request))
+            .await
     }
 
     async fn update_node(
@@ -289,24 +331,39 @@ impl StorageBackend for PostgresStorageBackend {
         id: uuid::Uuid,
         request: platform_api_models::UpdateNodeRequest,
     ) -> Result<platform_api_models::Node> {
-        self.update_node_impl(id, request).await
+        self.breaker.call(self.update_node_impl(id, request)).await
     }
 
     async fn delete_node(&self, id: uuid::Uuid) -> Result<()> {
-        self.delete_node_impl(id).await
+        self.breaker.call(self.delete_node_impl(id)).await
     }
 
     async fn get_pool_capacity(
         &self,
         pool_id: uuid::Uuid,
     ) -> Result<platform_api_models::PoolCapacitySummary> {
-        self.get_pool_capacity_impl(pool_id).await
+        self.breaker
+            .call(self.get_pool_capacity_impl(pool_id))
+            .await
     }
 
     async fn get_vm_compose_config(
         &self,
         vm_type: &str,
     ) -> Result<platform_api_models::VmComposeConfig> {
-        self.get_vm_compose_config_impl(vm_type).await
+        self.breaker
+            .call(self.get_vm_compose_config_impl(vm_type))
+            .await
+    }
+
+    async fn get_job_history(
+        &self,
+        job_id: uuid::Uuid,
+    ) -> Result<Vec<platform_api_models::JobEvent>> {
+        self.breaker.call(self.get_job_history_impl(job_id)).await
+    }
+
+    fn circuit_state(&self) -> Option<CircuitState> {
+        Some(self.breaker.state())
     }
 }
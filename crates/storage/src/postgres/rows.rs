@@ -61,6 +61,28 @@ pub struct ChallengeRow {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Database row for the challenge columns of `ChallengeMetadata`
+#[derive(Debug, FromRow)]
+pub struct ChallengeMetadataRow {
+    pub id: Uuid,
+    pub name: String,
+    pub compose_hash: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub owner: String,
+    pub status: String,
+    pub default_job_priority: String,
+    pub job_payload_schema: Option<serde_json::Value>,
+    pub job_defaults: serde_json::Value,
+    pub resource_requirements: serde_json::Value,
+    pub score_bounds: serde_json::Value,
+    pub score_policy: serde_json::Value,
+    pub healthcheck_url: Option<String>,
+    pub healthcheck_timeout_secs: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 /// Database row for VM compose configs
 #[derive(Debug, FromRow)]
 pub struct VmComposeRow {
@@ -77,3 +99,15 @@ pub struct VmComposeRow {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+/// Database row for job_events table
+#[derive(Debug, FromRow)]
+pub struct JobEventRow {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub old_status: Option<String>,
+    pub new_status: String,
+    pub actor: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub metadata: serde_json::Value,
+}
@@ -3,7 +3,7 @@
 // Uses: Real PostgreSQL, Real Redis (fakeredis), Mock: Validator (mock HTTP server)
 
 use platform_api_scheduler::{SchedulerService, SchedulerConfig, CreateJobRequest};
-use platform_api_models::{JobStatus, JobPriority, RuntimeType, Id, ClaimJobRequest, SubmitResultRequest, EvalResult, ResourceUsage};
+use platform_api_models::{JobStatus, JobPriority, RuntimeType, Id, ClaimJobRequest, SubmitResultRequest, EvalResult, ResourceUsage, EVAL_RESULT_SCHEMA_VERSION};
 use sqlx::PgPool;
 use uuid::Uuid;
 use serde_json::json;
@@ -60,7 +60,7 @@ async fn test_full_job_flow() {
         challenge_id: Id::from(challenge_id),
         payload: json!({"test": "data"}),
         priority: Some(JobPriority::Normal),
-        runtime: RuntimeType::Docker,
+        runtime: Some(RuntimeType::Docker),
         timeout: Some(3600),
         max_retries: Some(3),
     };
@@ -106,6 +106,7 @@ async fn test_full_job_flow() {
             network_bytes: 512,
         },
         attestation_receipt: None,
+        schema_version: EVAL_RESULT_SCHEMA_VERSION,
     };
     
     let complete_request = SubmitResultRequest {
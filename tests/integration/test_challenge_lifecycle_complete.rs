@@ -77,7 +77,7 @@ services:
     assert_eq!(created_challenge.name, "test-challenge");
     
     // 2. Simulate validator connection
-    let validator_hotkey = "test_validator_hotkey";
+    let validator_hotkey = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
     let compose_hash = created_challenge.compose_hash.clone();
     
     // Add validator to active set
@@ -95,7 +95,7 @@ services:
             "job_name": "evaluate_agent"
         }),
         priority: Some(JobPriority::Normal),
-        runtime: RuntimeType::Docker,
+        runtime: Some(RuntimeType::Docker),
         timeout: Some(300),
         max_retries: Some(3),
     };
@@ -124,12 +124,12 @@ services:
     assert!(distribution_result.distributed);
     assert_eq!(distribution_result.validator_count, 1);
     assert_eq!(distribution_result.assigned_validators.len(), 1);
-    assert!(distribution_result.assigned_validators.contains(&validator_hotkey.to_string()));
+    assert!(distribution_result.assigned_validators.iter().any(|v| v == validator_hotkey));
     
     // 5. Simulate validator claiming the job
     let claimed_job = state.scheduler.claim_job(
         ClaimJobRequest {
-            validator_hotkey: Hotkey::from(validator_hotkey),
+            validator_hotkey: validator_hotkey.parse().unwrap(),
             challenge_id: Some(Id::from(created_challenge.id)),
             runtime: RuntimeType::Docker,
         }
@@ -137,7 +137,7 @@ services:
     
     assert_eq!(claimed_job.job.id, job.id);
     assert_eq!(claimed_job.job.status, JobStatus::Claimed);
-    assert_eq!(claimed_job.job.validator_hotkey, Some(Hotkey::from(validator_hotkey)));
+    assert_eq!(claimed_job.job.validator_hotkey, Some(validator_hotkey.to_string()));
     
     // 6. Simulate job execution and result submission
     let eval_result = EvalResult {
@@ -223,17 +223,17 @@ async fn test_job_failure_and_retry() {
         challenge_id: Id::from(challenge.id),
         payload: json!({"test": "retry"}),
         priority: Some(JobPriority::Normal),
-        runtime: RuntimeType::Docker,
+        runtime: Some(RuntimeType::Docker),
         timeout: Some(60),
         max_retries: Some(3),
     }).await.expect("Failed to create job");
     
     // Claim job
-    let validator_hotkey = "retry_validator";
+    let validator_hotkey = "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y";
     state.add_validator(&challenge.compose_hash, validator_hotkey).await;
     
     let claimed = state.scheduler.claim_job(ClaimJobRequest {
-        validator_hotkey: Hotkey::from(validator_hotkey),
+        validator_hotkey: validator_hotkey.parse().unwrap(),
         challenge_id: Some(Id::from(challenge.id)),
         runtime: RuntimeType::Docker,
     }).await.expect("Failed to claim job");
@@ -255,7 +255,7 @@ async fn test_job_failure_and_retry() {
     
     // Claim again
     let reclaimed = state.scheduler.claim_job(ClaimJobRequest {
-        validator_hotkey: Hotkey::from(validator_hotkey),
+        validator_hotkey: validator_hotkey.parse().unwrap(),
         challenge_id: Some(Id::from(challenge.id)),
         runtime: RuntimeType::Docker,
     }).await.expect("Failed to reclaim job");
@@ -329,7 +329,7 @@ async fn test_pool_and_node_management() {
     let state = create_test_state(pool.clone()).await;
     
     // Create a pool
-    let validator_hotkey = "pool_test_validator";
+    let validator_hotkey = "5DAAnrj7VHTznn2AWBemMuyBwZWs6FNFjdyVXUeYum3PTXFy";
     let create_pool_req = CreatePoolRequest {
         name: "test-pool".to_string(),
         description: Some("Test pool for integration testing".to_string()),
@@ -448,7 +448,7 @@ async fn test_websocket_job_distribution() {
             "params": {"complexity": "high"}
         }),
         priority: Some(JobPriority::High),
-        runtime: RuntimeType::Docker,
+        runtime: Some(RuntimeType::Docker),
         timeout: Some(600),
         max_retries: Some(1),
     }).await.expect("Failed to create job");
@@ -326,7 +326,7 @@ async fn test_job_priority_distribution() {
             challenge_id: Id::from(challenge_id),
             payload: json!({"job_num": i, "priority": format!("{:?}", priority)}),
             priority: Some(priority.clone()),
-            runtime: RuntimeType::Docker,
+            runtime: Some(RuntimeType::Docker),
             timeout: Some(300),
             max_retries: Some(1),
         }).await.expect("Failed to create job");
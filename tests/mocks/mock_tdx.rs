@@ -39,6 +39,7 @@ impl MockTdxVerifier {
                 verified_measurements: vec![],
                 policy: String::new(),
                 error: Some("Mock TDX verification failed".to_string()),
+                refresh_token: String::new(),
             });
         }
 
@@ -51,6 +52,7 @@ impl MockTdxVerifier {
                 verified_measurements: vec![],
                 policy: String::new(),
                 error: Some("Missing quote in attestation request".to_string()),
+                refresh_token: String::new(),
             });
         }
 
@@ -62,6 +64,7 @@ impl MockTdxVerifier {
             verified_measurements: request.measurements.clone(),
             policy: "mock-policy".to_string(),
             error: None,
+            refresh_token: "mock-refresh-token".to_string(),
         })
     }
 
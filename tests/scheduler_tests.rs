@@ -1,17 +1,21 @@
 // Unit tests for Scheduler Service
 // Uses real PostgreSQL with sqlx::test (fast, testable)
 
-use platform_api_scheduler::{SchedulerService, SchedulerConfig, CreateJobRequest};
+use platform_api_scheduler::{
+    SchedulerService, SchedulerConfig, CreateJobRequest, ChallengeJobQuota, JobSearch,
+    Scorer, ValidatorInfo,
+};
 use platform_api_models::{
     JobStatus, JobPriority, RuntimeType, Id, ClaimJobRequest, SubmitResultRequest, 
-    FailJobRequest, EvalResult, ResourceUsage, Hotkey
+    FailJobRequest, FailureCategory, EvalResult, ResourceUsage, Hotkey, JobMetadata,
+    ResourceRequirements, PlatformError, EVAL_RESULT_SCHEMA_VERSION,
 };
 use sqlx::PgPool;
 use uuid::Uuid;
 use serde_json::json;
 use std::sync::Arc;
 use std::path::PathBuf;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 // Helper to create test database pool
 async fn setup_test_db() -> PgPool {
@@ -38,6 +42,13 @@ async fn setup_test_db() -> PgPool {
     pool
 }
 
+// Helper to make a valid hotkey out of a readable name
+fn hotkey_of(name: &str) -> Hotkey {
+    let mut public_key = [0u8; 32];
+    public_key[..name.len()].copy_from_slice(name.as_bytes());
+    Hotkey::from_public_key(&public_key)
+}
+
 // Helper to cleanup test data
 async fn cleanup_test_data(pool: &PgPool) {
     sqlx::query("DELETE FROM job_test_results").execute(pool).await.ok();
@@ -59,9 +70,14 @@ async fn test_create_job() {
         challenge_id: Id::from(challenge_id),
         payload: json!({"test": "data"}),
         priority: Some(JobPriority::Normal),
-        runtime: RuntimeType::Docker,
+        runtime: Some(RuntimeType::Docker),
         timeout: Some(3600),
         max_retries: Some(3),
+        job_id: None,
+        depends_on: vec![],
+        deadline: None,
+        required_capabilities: None,
+        resources: None,
     };
     
     let job = scheduler.create_job(request).await
@@ -72,7 +88,9 @@ async fn test_create_job() {
     assert_eq!(job.runtime, RuntimeType::Docker);
     assert_eq!(job.retry_count, 0);
     assert_eq!(job.max_retries, 3);
-    assert!(job.timeout_at.is_some());
+    assert_eq!(job.timeout, Some(3600));
+    // The timeout runs from the claim
+    assert!(job.timeout_at.is_none());
     
     cleanup_test_data(&pool).await;
 }
@@ -91,9 +109,14 @@ async fn test_create_job_with_priority() {
         challenge_id: Id::from(challenge_id),
         payload: json!({"test": "data"}),
         priority: Some(JobPriority::High),
-        runtime: RuntimeType::Docker,
+        runtime: Some(RuntimeType::Docker),
         timeout: None,
         max_retries: None,
+        job_id: None,
+        depends_on: vec![],
+        deadline: None,
+        required_capabilities: None,
+        resources: None,
     };
     
     let job = scheduler.create_job(request).await
@@ -122,15 +145,20 @@ async fn test_list_jobs() {
             challenge_id: Id::from(challenge_id),
             payload: json!({"index": i}),
             priority: Some(JobPriority::Normal),
-            runtime: RuntimeType::Docker,
+            runtime: Some(RuntimeType::Docker),
             timeout: None,
             max_retries: None,
+            job_id: None,
+            depends_on: vec![],
+            deadline: None,
+            required_capabilities: None,
+            resources: None,
         };
         scheduler.create_job(request).await.expect("Failed to create job");
     }
     
     // List all jobs
-    let response = scheduler.list_jobs(1, 10, None, None).await
+    let response = scheduler.list_jobs(1, 10, None, None, &JobSearch::default()).await
         .expect("Failed to list jobs");
     
     assert_eq!(response.jobs.len(), 5);
@@ -139,7 +167,7 @@ async fn test_list_jobs() {
     assert_eq!(response.per_page, 10);
     
     // List jobs with pagination
-    let response = scheduler.list_jobs(1, 2, None, None).await
+    let response = scheduler.list_jobs(1, 2, None, None, &JobSearch::default()).await
         .expect("Failed to list jobs");
     
     assert_eq!(response.jobs.len(), 2);
@@ -165,15 +193,20 @@ async fn test_list_jobs_with_status_filter() {
             challenge_id: Id::from(challenge_id),
             payload: json!({}),
             priority: Some(JobPriority::Normal),
-            runtime: RuntimeType::Docker,
+            runtime: Some(RuntimeType::Docker),
             timeout: None,
             max_retries: None,
+            job_id: None,
+            depends_on: vec![],
+            deadline: None,
+            required_capabilities: None,
+            resources: None,
         };
         scheduler.create_job(request).await.expect("Failed to create job");
     }
     
     // List pending jobs
-    let response = scheduler.list_jobs(1, 10, Some("pending".to_string()), None).await
+    let response = scheduler.list_jobs(1, 10, Some("pending".to_string()), None, &JobSearch::default()).await
         .expect("Failed to list jobs");
     
     assert_eq!(response.jobs.len(), 3);
@@ -184,6 +217,95 @@ async fn test_list_jobs_with_status_filter() {
     cleanup_test_data(&pool).await;
 }
 
+#[tokio::test]
+async fn test_search_jobs() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+    
+    let challenge_id = Uuid::new_v4();
+    let payloads = [
+        json!({"miner_hotkey": "5Alice", "agent": "swe-agent"}),
+        json!({"miner_hotkey": "5Bob", "agent": "swe-agent"}),
+        json!({"miner_hotkey": "5Carol", "model": "100%_tuned"}),
+    ];
+    let mut job_ids = Vec::new();
+    for payload in payloads {
+        let mut request = batch_request(challenge_id, None);
+        request.payload = payload;
+        job_ids.push(scheduler.create_job(request).await.expect("Failed to create job").id);
+    }
+    
+    // Backdate the first job by a day
+    sqlx::query("UPDATE jobs SET created_at = NOW() - INTERVAL '1 day' WHERE id = $1")
+        .bind(job_ids[0])
+        .execute(&pool)
+        .await
+        .unwrap();
+    
+    let search = |search: &str| JobSearch {
+        search: Some(search.to_string()),
+        ..JobSearch::default()
+    };
+    let found = |response: platform_api_models::JobListResponse| {
+        let mut ids: Vec<Uuid> = response.jobs.iter().map(|j| j.id).collect();
+        ids.sort();
+        ids
+    };
+    let sorted = |mut ids: Vec<Uuid>| {
+        ids.sort();
+        ids
+    };
+    
+    // Matches payload values, case-insensitively
+    let response = scheduler.list_jobs(1, 10, None, None, &search("5bob")).await.unwrap();
+    assert_eq!(response.total, 1);
+    assert_eq!(found(response), vec![job_ids[1]]);
+    
+    // Matches payload keys
+    let response = scheduler.list_jobs(1, 10, None, None, &search("model")).await.unwrap();
+    assert_eq!(found(response), vec![job_ids[2]]);
+    
+    let response = scheduler.list_jobs(1, 10, None, None, &search("SWE-agent")).await.unwrap();
+    assert_eq!(found(response), sorted(vec![job_ids[0], job_ids[1]]));
+    
+    // LIKE wildcards in the search text are matched literally
+    let response = scheduler.list_jobs(1, 10, None, None, &search("0%_t")).await.unwrap();
+    assert_eq!(found(response), vec![job_ids[2]]);
+    let response = scheduler.list_jobs(1, 10, None, None, &search("5_o")).await.unwrap();
+    assert_eq!(response.total, 0);
+    
+    // Date range
+    let cutoff = chrono::Utc::now() - chrono::Duration::hours(1);
+    let recent = JobSearch {
+        created_after: Some(cutoff),
+        ..JobSearch::default()
+    };
+    let response = scheduler.list_jobs(1, 10, None, None, &recent).await.unwrap();
+    assert_eq!(response.total, 2);
+    assert_eq!(found(response), sorted(vec![job_ids[1], job_ids[2]]));
+    
+    let older = JobSearch {
+        created_before: Some(cutoff),
+        ..JobSearch::default()
+    };
+    let response = scheduler.list_jobs(1, 10, None, None, &older).await.unwrap();
+    assert_eq!(found(response), vec![job_ids[0]]);
+    
+    // Search and date range combined
+    let recent_agents = JobSearch {
+        search: Some("swe-agent".to_string()),
+        ..recent
+    };
+    let response = scheduler.list_jobs(1, 10, None, None, &recent_agents).await.unwrap();
+    assert_eq!(found(response), vec![job_ids[1]]);
+    
+    cleanup_test_data(&pool).await;
+}
+
 #[tokio::test]
 async fn test_claim_job() {
     let pool = setup_test_db().await;
@@ -198,16 +320,21 @@ async fn test_claim_job() {
         challenge_id: Id::from(challenge_id),
         payload: json!({}),
         priority: Some(JobPriority::Normal),
-        runtime: RuntimeType::Docker,
+        runtime: Some(RuntimeType::Docker),
         timeout: None,
         max_retries: None,
+        job_id: None,
+        depends_on: vec![],
+        deadline: None,
+        required_capabilities: None,
+        resources: None,
     };
     
     let job = scheduler.create_job(request).await
         .expect("Failed to create job");
     
     let claim_request = ClaimJobRequest {
-        validator_hotkey: Hotkey::from("test-validator".to_string()),
+        validator_hotkey: hotkey_of("test-validator"),
         runtime: RuntimeType::Docker,
         capabilities: vec![],
     };
@@ -235,9 +362,14 @@ async fn test_complete_job() {
         challenge_id: Id::from(challenge_id),
         payload: json!({}),
         priority: Some(JobPriority::Normal),
-        runtime: RuntimeType::Docker,
+        runtime: Some(RuntimeType::Docker),
         timeout: None,
         max_retries: None,
+        job_id: None,
+        depends_on: vec![],
+        deadline: None,
+        required_capabilities: None,
+        resources: None,
     };
     
     let job = scheduler.create_job(request).await
@@ -245,7 +377,7 @@ async fn test_complete_job() {
     
     // Claim the job first
     let claim_request = ClaimJobRequest {
-        validator_hotkey: Hotkey::from("test-validator".to_string()),
+        validator_hotkey: hotkey_of("test-validator"),
         runtime: RuntimeType::Docker,
         capabilities: vec![],
     };
@@ -275,12 +407,14 @@ async fn test_complete_job() {
             network_bytes: 512,
         },
         attestation_receipt: None,
+        schema_version: EVAL_RESULT_SCHEMA_VERSION,
     };
     
     let complete_request = SubmitResultRequest {
         job_id: job.id,
         result: eval_result,
         receipts: vec![],
+        expected_version: None,
     };
     
     scheduler.complete_job(job.id.into(), complete_request).await
@@ -309,9 +443,14 @@ async fn test_retry_logic() {
         challenge_id: Id::from(challenge_id),
         payload: json!({}),
         priority: Some(JobPriority::Normal),
-        runtime: RuntimeType::Docker,
+        runtime: Some(RuntimeType::Docker),
         timeout: None,
         max_retries: Some(2),
+        job_id: None,
+        depends_on: vec![],
+        deadline: None,
+        required_capabilities: None,
+        resources: None,
     };
     
     let job = scheduler.create_job(request).await
@@ -321,6 +460,8 @@ async fn test_retry_logic() {
     let fail_request = FailJobRequest {
         reason: "Test failure".to_string(),
         error_details: Some("Test error details".to_string()),
+        failure_category: Some(FailureCategory::ExecutionError),
+        expected_version: None,
     };
     
     scheduler.fail_job(job.id.into(), fail_request).await
@@ -335,3 +476,1642 @@ async fn test_retry_logic() {
     
     cleanup_test_data(&pool).await;
 }
+
+#[tokio::test]
+async fn test_failure_category_stats() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    let mut job_ids = Vec::new();
+    for _ in 0..3 {
+        let request = CreateJobRequest {
+            challenge_id: Id::from(challenge_id),
+            payload: json!({}),
+            priority: None,
+            runtime: Some(RuntimeType::Docker),
+            timeout: None,
+            max_retries: None,
+            job_id: None,
+            depends_on: vec![],
+            deadline: None,
+            required_capabilities: None,
+            resources: None,
+        };
+        let job = scheduler.create_job(request).await
+            .expect("Failed to create job");
+        job_ids.push(job.id);
+    }
+
+    let categories = [
+        Some(FailureCategory::Timeout),
+        Some(FailureCategory::Timeout),
+        Some(FailureCategory::ValidatorCrash),
+    ];
+    for (job_id, category) in job_ids.iter().zip(categories) {
+        scheduler.fail_job(*job_id, FailJobRequest {
+            reason: "Test failure".to_string(),
+            error_details: None,
+            failure_category: category,
+            expected_version: None,
+        }).await.expect("Failed to fail job");
+    }
+
+    let failed_job = scheduler.get_job(job_ids[2]).await
+        .expect("Failed to get job");
+    assert_eq!(failed_job.failure_category, Some(FailureCategory::ValidatorCrash));
+
+    let stats = scheduler.get_job_stats().await
+        .expect("Failed to get stats");
+    assert_eq!(stats.failures_by_category.get("timeout"), Some(&2));
+    assert_eq!(stats.failures_by_category.get("validator_crash"), Some(&1));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_enforce_timeouts_marks_timeout_category() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let request = CreateJobRequest {
+        challenge_id: Id::from(Uuid::new_v4()),
+        payload: json!({}),
+        priority: None,
+        runtime: Some(RuntimeType::Docker),
+        timeout: Some(60),
+        max_retries: None,
+        job_id: None,
+        depends_on: vec![],
+        deadline: None,
+        required_capabilities: None,
+        resources: None,
+    };
+    let job = scheduler.create_job(request).await
+        .expect("Failed to create job");
+
+    scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("test_validator"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job");
+
+    // Push the deadline into the past
+    sqlx::query("UPDATE jobs SET timeout_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+        .bind(job.id)
+        .execute(&pool)
+        .await
+        .expect("Failed to expire job");
+
+    let expired = scheduler.enforce_timeouts().await
+        .expect("Failed to enforce timeouts");
+    assert_eq!(expired, 1);
+
+    let timed_out = scheduler.get_job(job.id).await
+        .expect("Failed to get job");
+    assert_eq!(timed_out.status, JobStatus::Failed);
+    assert_eq!(timed_out.failure_category, Some(FailureCategory::Timeout));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_stale_claimed_jobs_requeued() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let request = || CreateJobRequest {
+        challenge_id: Id::from(Uuid::new_v4()),
+        payload: json!({}),
+        priority: None,
+        runtime: Some(RuntimeType::Docker),
+        timeout: Some(60),
+        max_retries: None,
+        job_id: None,
+        depends_on: vec![],
+        deadline: None,
+        required_capabilities: None,
+        resources: None,
+    };
+    let claim = || ClaimJobRequest {
+        validator_hotkey: hotkey_of("test_validator"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    };
+    let stale = scheduler.create_job(request()).await
+        .expect("Failed to create job");
+    scheduler.claim_job(claim()).await.expect("Failed to claim job");
+    let started = scheduler.create_job(request()).await
+        .expect("Failed to create job");
+    scheduler.claim_job(claim()).await.expect("Failed to claim job");
+    let exhausted = scheduler.create_job(CreateJobRequest {
+        max_retries: Some(0),
+        ..request()
+    }).await.expect("Failed to create job");
+    scheduler.claim_job(claim()).await.expect("Failed to claim job");
+
+    // Claimed before a restart, longer ago than the job timeout
+    sqlx::query("UPDATE jobs SET claimed_at = NOW() - INTERVAL '2 hours' WHERE id IN ($1, $2, $3)")
+        .bind(stale.id)
+        .bind(started.id)
+        .bind(exhausted.id)
+        .execute(&pool)
+        .await
+        .expect("Failed to age claims");
+    sqlx::query("UPDATE jobs SET status = 'running', started_at = NOW() WHERE id = $1")
+        .bind(started.id)
+        .execute(&pool)
+        .await
+        .expect("Failed to start job");
+
+    let requeued = scheduler.cleanup_stale_jobs().await
+        .expect("Failed to clean up stale jobs");
+    assert_eq!(requeued, 1);
+
+    let job = scheduler.get_job(stale.id).await.expect("Failed to get job");
+    assert_eq!(job.status, JobStatus::Pending);
+    assert_eq!(job.retry_count, 1);
+    assert_eq!(job.validator_hotkey, None);
+    assert_eq!(job.timeout_at, None);
+    assert_eq!(scheduler.get_job(started.id).await.expect("Failed to get job").status, JobStatus::Running);
+
+    // Jobs without retries left are dead-lettered instead
+    let job = scheduler.get_job(exhausted.id).await.expect("Failed to get job");
+    assert_eq!(job.status, JobStatus::DeadLettered);
+    assert_eq!(job.failure_category, Some(FailureCategory::Timeout));
+
+    let (actor,): (Option<String>,) = sqlx::query_as(
+        "SELECT actor FROM job_events WHERE job_id = $1 AND new_status = 'pending' ORDER BY seq DESC LIMIT 1",
+    )
+    .bind(stale.id)
+    .fetch_one(&pool)
+    .await
+    .expect("Failed to get job event");
+    assert_eq!(actor.as_deref(), Some("stale_job_cleanup"));
+
+    cleanup_test_data(&pool).await;
+}
+
+fn batch_request(challenge_id: Uuid, timeout: Option<u64>) -> CreateJobRequest {
+    CreateJobRequest {
+        challenge_id: Id::from(challenge_id),
+        payload: json!({"task": "eval"}),
+        priority: None,
+        runtime: Some(RuntimeType::Docker),
+        timeout,
+        max_retries: None,
+        job_id: None,
+        depends_on: vec![],
+        deadline: None,
+        required_capabilities: None,
+        resources: None,
+    }
+}
+
+async fn count_jobs(pool: &PgPool, challenge_id: Uuid) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE challenge_id = $1")
+        .bind(challenge_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to count jobs")
+}
+
+#[tokio::test]
+async fn test_create_jobs_batch() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    let requests = (0..5).map(|_| batch_request(challenge_id, Some(600))).collect();
+
+    let response = scheduler.create_jobs_batch(requests).await
+        .expect("Failed to create job batch");
+
+    assert!(response.created);
+    assert_eq!(response.results.len(), 5);
+    for result in &response.results {
+        let job_id = result.job_id.expect("Missing job id");
+        assert!(result.error.is_none());
+        let job = scheduler.get_job(job_id).await
+            .expect("Batch job not found");
+        assert_eq!(job.status, JobStatus::Pending);
+    }
+    assert_eq!(count_jobs(&pool, challenge_id).await, 5);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_create_jobs_batch_rolls_back_on_invalid_item() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    let requests = vec![
+        batch_request(challenge_id, Some(600)),
+        batch_request(challenge_id, Some(0)),
+        batch_request(challenge_id, Some(600)),
+    ];
+
+    let response = scheduler.create_jobs_batch(requests).await
+        .expect("Failed to process job batch");
+
+    assert!(!response.created);
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.results[0].index, 1);
+    assert!(response.results[0].job_id.is_none());
+    assert!(response.results[0].error.is_some());
+    assert_eq!(count_jobs(&pool, challenge_id).await, 0);
+
+    // Oversized batches are refused outright
+    let oversized = (0..config.max_batch_size + 1)
+        .map(|_| batch_request(challenge_id, None))
+        .collect();
+    assert!(scheduler.create_jobs_batch(oversized).await.is_err());
+    assert_eq!(count_jobs(&pool, challenge_id).await, 0);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_challenge_job_counts() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    for _ in 0..3 {
+        scheduler.create_job(batch_request(challenge_id, None)).await
+            .expect("Failed to create job");
+    }
+    // Jobs of other challenges are not counted
+    scheduler.create_job(batch_request(Uuid::new_v4(), None)).await
+        .expect("Failed to create job");
+
+    sqlx::query(
+        "UPDATE jobs SET status = 'running' WHERE id = (SELECT id FROM jobs WHERE challenge_id = $1 LIMIT 1)",
+    )
+    .bind(challenge_id)
+    .execute(&pool)
+    .await
+    .expect("Failed to start job");
+
+    let counts = scheduler.get_challenge_job_counts(challenge_id).await
+        .expect("Failed to count jobs");
+    assert_eq!(counts.pending, 2);
+    assert_eq!(counts.running, 1);
+    assert_eq!(counts.completed, 0);
+
+    cleanup_test_data(&pool).await;
+}
+
+fn empty_result(job_id: Id) -> SubmitResultRequest {
+    SubmitResultRequest {
+        job_id,
+        result: EvalResult {
+            job_id,
+            submission_id: Id::from(Uuid::new_v4()),
+            scores: BTreeMap::new(),
+            metrics: BTreeMap::new(),
+            logs: vec![],
+            error: None,
+            execution_time: 0,
+            resource_usage: ResourceUsage {
+                cpu_time: 0,
+                memory_peak: 0,
+                disk_usage: 0,
+                network_bytes: 0,
+            },
+            attestation_receipt: None,
+            schema_version: EVAL_RESULT_SCHEMA_VERSION,
+        },
+        receipts: vec![],
+        expected_version: None,
+    }
+}
+
+#[tokio::test]
+async fn test_dependent_job_unblocked_after_dependency_completes() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    let job_a = scheduler.create_job(batch_request(challenge_id, None)).await
+        .expect("Failed to create job A");
+    let job_b = scheduler.create_job(CreateJobRequest {
+        depends_on: vec![job_a.id],
+        ..batch_request(challenge_id, None)
+    }).await.expect("Failed to create job B");
+
+    assert_eq!(job_a.status, JobStatus::Pending);
+    assert_eq!(job_b.status, JobStatus::Blocked);
+    assert_eq!(job_b.depends_on, vec![job_a.id]);
+
+    // Only A can be claimed while B waits on it
+    let claimed = scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("test-validator"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job");
+    assert_eq!(claimed.job.id, job_a.id);
+
+    scheduler.complete_job(job_a.id, empty_result(job_a.id)).await
+        .expect("Failed to complete job A");
+
+    let job_b = scheduler.get_job(job_b.id).await
+        .expect("Failed to get job B");
+    assert_eq!(job_b.status, JobStatus::Pending);
+
+    // Recorded in one transaction, the completion and the release of B share
+    // a timestamp but keep their order
+    let events: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT job_id, new_status FROM job_events WHERE job_id = ANY($1) ORDER BY seq",
+    )
+    .bind(vec![job_a.id, job_b.id])
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to load job events");
+    assert_eq!(events[events.len() - 2..], [
+        (job_a.id, "completed".to_string()),
+        (job_b.id, "pending".to_string()),
+    ]);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_dependency_cycle_rejected() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    let (id_a, id_b, id_c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let requests = vec![
+        CreateJobRequest {
+            job_id: Some(id_a),
+            depends_on: vec![id_b],
+            ..batch_request(challenge_id, None)
+        },
+        CreateJobRequest {
+            job_id: Some(id_b),
+            depends_on: vec![id_a],
+            ..batch_request(challenge_id, None)
+        },
+        // Depends on the cycle without being part of it
+        CreateJobRequest {
+            job_id: Some(id_c),
+            depends_on: vec![id_a],
+            ..batch_request(challenge_id, None)
+        },
+    ];
+
+    let response = scheduler.create_jobs_batch(requests).await
+        .expect("Failed to process job batch");
+
+    assert!(!response.created);
+    let rejected: Vec<usize> = response.results.iter().map(|r| r.index).collect();
+    assert_eq!(rejected, vec![0, 1]);
+    assert_eq!(count_jobs(&pool, challenge_id).await, 0);
+
+    // A job cannot depend on itself
+    let self_dependent = CreateJobRequest {
+        job_id: Some(id_c),
+        depends_on: vec![id_c],
+        ..batch_request(challenge_id, None)
+    };
+    assert!(scheduler.create_job(self_dependent).await.is_err());
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_trusted_validator_routed_high_priority_first() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    sqlx::query("DELETE FROM validator_trust_scores").execute(&pool).await.ok();
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    let low = scheduler.create_job(CreateJobRequest {
+        priority: Some(JobPriority::Low),
+        ..batch_request(challenge_id, None)
+    }).await.expect("Failed to create low priority job");
+    let high = scheduler.create_job(CreateJobRequest {
+        priority: Some(JobPriority::High),
+        ..batch_request(challenge_id, None)
+    }).await.expect("Failed to create high priority job");
+
+    sqlx::query(
+        "INSERT INTO validator_trust_scores (validator_hotkey, score, success_rate, window_days) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(hotkey_of("trusted-validator").as_str())
+    .bind(0.9)
+    .bind(0.95)
+    .bind(7)
+    .execute(&pool)
+    .await
+    .expect("Failed to store trust score");
+
+    let claimed = scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("trusted-validator"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job");
+    assert_eq!(claimed.job.id, high.id);
+
+    // The remaining job goes to a validator without a score
+    let claimed = scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("new-validator"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job");
+    assert_eq!(claimed.job.id, low.id);
+
+    sqlx::query("DELETE FROM validator_trust_scores").execute(&pool).await.ok();
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_aged_low_priority_job_is_claimed_before_fresh_high_priority_jobs() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    let low = scheduler.create_job(CreateJobRequest {
+        priority: Some(JobPriority::Low),
+        ..batch_request(challenge_id, None)
+    }).await.expect("Failed to create low priority job");
+
+    // The low priority job has been pending for three aging intervals
+    sqlx::query("UPDATE jobs SET created_at = created_at - make_interval(secs => $1) WHERE id = $2")
+        .bind((3 * config.priority_aging_secs) as f64)
+        .bind(low.id)
+        .execute(&pool)
+        .await
+        .expect("Failed to age job");
+
+    for _ in 0..3 {
+        scheduler.create_job(CreateJobRequest {
+            priority: Some(JobPriority::High),
+            ..batch_request(challenge_id, None)
+        }).await.expect("Failed to create high priority job");
+    }
+
+    let aged = scheduler.get_job(low.id).await.expect("Failed to get job");
+    assert!(aged.effective_priority.expect("Pending job has an effective priority") > 2.0);
+
+    let claimed = scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("any-validator"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job");
+    assert_eq!(claimed.job.id, low.id);
+
+    // A job due now jumps ahead of the remaining high priority jobs
+    let due = scheduler.create_job(CreateJobRequest {
+        priority: Some(JobPriority::Low),
+        deadline: Some(chrono::Utc::now() + chrono::Duration::seconds(1)),
+        ..batch_request(challenge_id, None)
+    }).await.expect("Failed to create job with deadline");
+
+    let claimed = scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("any-validator"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job");
+    assert_eq!(claimed.job.id, due.id);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_exhausted_job_is_dead_lettered_and_requeued() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig {
+        retry_delay: 0,
+        ..SchedulerConfig::default()
+    };
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let job = scheduler.create_job(CreateJobRequest {
+        max_retries: Some(1),
+        ..batch_request(Uuid::new_v4(), None)
+    }).await.expect("Failed to create job");
+
+    let claim = || scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("flaky-validator"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    });
+    let fail = |reason: &str| FailJobRequest {
+        reason: reason.to_string(),
+        error_details: Some("exit code 137".to_string()),
+        failure_category: Some(FailureCategory::ResourceExhausted),
+        expected_version: None,
+    };
+
+    // First attempt fails and is retried
+    claim().await.expect("Failed to claim job");
+    scheduler.fail_job(job.id, fail("Out of memory")).await.expect("Failed to fail job");
+    assert_eq!(scheduler.get_job(job.id).await.unwrap().status, JobStatus::Failed);
+
+    assert_eq!(scheduler.retry_failed_jobs().await.expect("Failed to retry jobs"), 1);
+    let retried = scheduler.get_job(job.id).await.unwrap();
+    assert_eq!(retried.status, JobStatus::Pending);
+    assert_eq!(retried.retry_count, 1);
+    assert!(retried.validator_hotkey.is_none());
+
+    // The retry fails too, exhausting the retries
+    claim().await.expect("Failed to claim retried job");
+    scheduler.fail_job(job.id, fail("Out of memory again")).await.expect("Failed to fail job");
+    assert_eq!(scheduler.get_job(job.id).await.unwrap().status, JobStatus::DeadLettered);
+    assert_eq!(scheduler.retry_failed_jobs().await.expect("Failed to retry jobs"), 0);
+
+    let dead_lettered = scheduler.list_dead_lettered_jobs(1, 20).await
+        .expect("Failed to list dead-lettered jobs");
+    assert_eq!(dead_lettered.total, 1);
+    let entry = &dead_lettered.jobs[0];
+    assert_eq!(entry.job.id, job.id);
+    assert_eq!(entry.reason.as_deref(), Some("Out of memory again"));
+    assert!(entry.dead_lettered_at.is_some());
+    assert_eq!(entry.retry_history.len(), 2);
+    assert_eq!(entry.retry_history[0].attempt, 1);
+    assert_eq!(entry.retry_history[0].reason, "Out of memory");
+    assert_eq!(entry.retry_history[1].validator_hotkey.as_deref(), Some(hotkey_of("flaky-validator").as_str()));
+    assert_eq!(entry.retry_history[1].failure_category, Some(FailureCategory::ResourceExhausted));
+
+    // Requeuing resets the retries and makes the job claimable again
+    let requeued = scheduler.requeue_job(job.id, "root").await.expect("Failed to requeue job");
+    assert_eq!(requeued.status, JobStatus::Pending);
+    assert_eq!(requeued.retry_count, 0);
+    assert!(scheduler.requeue_job(job.id, "root").await.is_err());
+    assert_eq!(claim().await.expect("Failed to claim requeued job").job.id, job.id);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_operator_job_changes_apply_on_next_claim() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    let high = scheduler.create_job(CreateJobRequest {
+        priority: Some(JobPriority::High),
+        ..batch_request(challenge_id, None)
+    }).await.expect("Failed to create job");
+    let low = scheduler.create_job(CreateJobRequest {
+        priority: Some(JobPriority::Low),
+        ..batch_request(challenge_id, None)
+    }).await.expect("Failed to create job");
+    let claim = || scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("validator-a"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    });
+
+    // The bumped job is claimed ahead of the one that outranked it
+    let bumped = scheduler.set_job_priority(low.id, JobPriority::Critical, "root").await
+        .expect("Failed to reprioritize job");
+    assert_eq!(bumped.priority, JobPriority::Critical);
+    assert_eq!(claim().await.expect("Failed to claim job").job.id, low.id);
+
+    // A stuck claim is released and claimed again, with a new timeout
+    let reset = scheduler.reset_job(low.id, "root").await.expect("Failed to reset job");
+    assert_eq!(reset.status, JobStatus::Pending);
+    assert!(reset.validator_hotkey.is_none());
+    assert!(reset.timeout_at.is_none());
+    let reclaimed = claim().await.expect("Failed to claim reset job");
+    assert_eq!(reclaimed.job.id, low.id);
+    assert_eq!(
+        reclaimed.job.timeout_at,
+        Some(reclaimed.job.claimed_at.unwrap() + chrono::Duration::seconds(reclaimed.config.timeout as i64))
+    );
+
+    sqlx::query("UPDATE jobs SET retry_count = 2 WHERE id = $1")
+        .bind(high.id)
+        .execute(&pool)
+        .await
+        .expect("Failed to set retry count");
+    let cleared = scheduler.clear_job_retries(high.id, "root").await
+        .expect("Failed to clear retries");
+    assert_eq!(cleared.retry_count, 0);
+
+    assert!(matches!(
+        scheduler.reset_job(high.id, "root").await.unwrap_err(),
+        PlatformError::Conflict { .. }
+    ));
+    assert!(matches!(
+        scheduler.set_job_priority(Uuid::new_v4(), JobPriority::High, "root").await.unwrap_err(),
+        PlatformError::NotFound { resource, .. } if resource == "job"
+    ));
+
+    // Each change is recorded with the operator who made it
+    let events: Vec<(String, Option<String>, serde_json::Value)> = sqlx::query_as(
+        "SELECT new_status, actor, metadata FROM job_events WHERE actor = 'root' ORDER BY seq",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to load job events");
+    let actions: Vec<(&str, &str)> = events
+        .iter()
+        .map(|(status, _, metadata)| (status.as_str(), metadata["action"].as_str().unwrap()))
+        .collect();
+    assert_eq!(actions, vec![
+        ("pending", "set_priority"),
+        ("pending", "reset"),
+        ("pending", "clear_retries"),
+    ]);
+    assert_eq!(events[0].2["old_priority"], "low");
+    assert_eq!(events[2].2["old_retry_count"], 2);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_job_transitions_are_recorded() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig {
+        retry_delay: 0,
+        ..SchedulerConfig::default()
+    };
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let job = scheduler.create_job(batch_request(Uuid::new_v4(), None)).await
+        .expect("Failed to create job");
+    let claim = || scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("validator-a"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    });
+
+    claim().await.expect("Failed to claim job");
+    scheduler.fail_job(job.id, FailJobRequest {
+        reason: "Container crashed".to_string(),
+        error_details: None,
+        failure_category: Some(FailureCategory::ValidatorCrash),
+        expected_version: None,
+    }).await.expect("Failed to fail job");
+    scheduler.retry_failed_jobs().await.expect("Failed to retry jobs");
+    claim().await.expect("Failed to claim retried job");
+    scheduler.complete_job(job.id, empty_result(job.id)).await
+        .expect("Failed to complete job");
+
+    let events: Vec<(Option<String>, String, Option<String>, serde_json::Value)> = sqlx::query_as(
+        "SELECT old_status, new_status, actor, metadata FROM job_events WHERE job_id = $1 ORDER BY seq",
+    )
+    .bind(job.id)
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to load job events");
+
+    let transitions: Vec<(Option<&str>, &str, Option<&str>)> = events
+        .iter()
+        .map(|(old, new, actor, _)| (old.as_deref(), new.as_str(), actor.as_deref()))
+        .collect();
+    let validator = hotkey_of("validator-a");
+    assert_eq!(transitions, vec![
+        (Some("pending"), "claimed", Some(validator.as_str())),
+        (Some("claimed"), "failed", Some(validator.as_str())),
+        (Some("failed"), "pending", Some("scheduler")),
+        (Some("pending"), "claimed", Some(validator.as_str())),
+        (Some("claimed"), "completed", Some(validator.as_str())),
+    ]);
+    assert_eq!(events[1].3["reason"], "Container crashed");
+    assert_eq!(events[1].3["failure_category"], "validator_crash");
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_challenge_job_quota() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let flooding = Uuid::new_v4();
+    let other = Uuid::new_v4();
+    let config = SchedulerConfig {
+        challenge_quotas: HashMap::from([(flooding, ChallengeJobQuota {
+            max_pending: 3,
+            max_in_flight: 1,
+        })]),
+        ..SchedulerConfig::default()
+    };
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    for _ in 0..3 {
+        scheduler.create_job(batch_request(flooding, None)).await
+            .expect("Failed to create job within quota");
+    }
+    let err = scheduler.create_job(batch_request(flooding, None)).await.unwrap_err();
+    assert!(matches!(err, PlatformError::RateLimited { .. }));
+    assert!(err.to_string().contains("quota of 3 pending jobs"), "{}", err);
+    assert!(scheduler.create_jobs_batch(vec![batch_request(flooding, None)]).await.is_err());
+    assert_eq!(count_jobs(&pool, flooding).await, 3);
+
+    // Other challenges keep the default quota
+    scheduler.create_jobs_batch((0..5).map(|_| batch_request(other, None)).collect()).await
+        .expect("Failed to create batch for another challenge");
+    assert_eq!(count_jobs(&pool, other).await, 5);
+
+    // A single job of the flooding challenge may be in flight
+    let claim = || scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("validator-a"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    });
+    assert_eq!(claim().await.expect("Failed to claim job").job.challenge_id, flooding);
+    assert_eq!(claim().await.expect("Failed to claim job").job.challenge_id, other);
+
+    let usage = scheduler.challenge_job_usage(flooding).await.expect("Failed to get usage");
+    assert_eq!((usage.pending, usage.in_flight), (2, 1));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_concurrent_claims_respect_validator_capacity() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let challenge_id = Uuid::new_v4();
+    let scheduler = Arc::new(
+        SchedulerService::with_database(&SchedulerConfig::default(), Arc::new(pool.clone()))
+            .expect("Failed to create scheduler"),
+    );
+    scheduler.create_jobs_batch((0..10).map(|_| batch_request(challenge_id, None)).collect()).await
+        .expect("Failed to create jobs");
+    scheduler.report_validator_capacity(&hotkey_of("validator-a"), 3).await;
+
+    let claims: Vec<_> = (0..10)
+        .map(|_| {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move {
+                scheduler.claim_job(ClaimJobRequest {
+                    validator_hotkey: hotkey_of("validator-a"),
+                    runtime: RuntimeType::Docker,
+                    capabilities: vec![],
+                }).await
+            })
+        })
+        .collect();
+    let mut claimed = vec![];
+    for claim in claims {
+        match claim.await.expect("Claim task panicked") {
+            Ok(response) => claimed.push(response.job.id),
+            Err(e) => assert!(matches!(e, PlatformError::RateLimited { .. }), "{}", e),
+        }
+    }
+    assert_eq!(claimed.len(), 3);
+
+    // Completing a job releases its slot
+    scheduler.complete_job(claimed[0], empty_result(claimed[0])).await
+        .expect("Failed to complete job");
+    assert!(scheduler.get_next_job(hotkey_of("validator-a"), None).await
+        .expect("Failed to get next job")
+        .is_some());
+    assert!(scheduler.get_next_job(hotkey_of("validator-a"), None).await
+        .expect("Failed to get next job")
+        .is_none());
+
+    cleanup_test_data(&pool).await;
+}
+
+/// Prefers the jobs of validators with a clean record
+struct ReliableFirstScorer;
+
+impl Scorer for ReliableFirstScorer {
+    fn score(&self, _job: &JobMetadata, validator: &ValidatorInfo) -> f64 {
+        match &validator.performance {
+            Some(performance) if performance.failed_jobs == 0 => 10.0,
+            _ => 0.0,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_challenge_scorer_picks_among_candidates() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let (plain, scored) = (Uuid::new_v4(), Uuid::new_v4());
+    let config = SchedulerConfig {
+        challenge_scorers: HashMap::from([(Id::from(scored), "reliable".to_string())]),
+        ..SchedulerConfig::default()
+    };
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler")
+        .with_scorer("reliable", ReliableFirstScorer);
+
+    let critical = scheduler.create_job(CreateJobRequest {
+        priority: Some(JobPriority::Critical),
+        ..batch_request(plain, None)
+    }).await.expect("Failed to create job");
+    let low = scheduler.create_job(CreateJobRequest {
+        priority: Some(JobPriority::Low),
+        ..batch_request(scored, None)
+    }).await.expect("Failed to create job");
+
+    // The low-priority job is not first in the database ordering, but its
+    // challenge's scorer ranks it first for a validator without failures
+    let claimed = scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("validator-a"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job");
+    assert_eq!(claimed.job.id, low.id);
+
+    let claimed = scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("validator-b"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job");
+    assert_eq!(claimed.job.id, critical.id);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_validator_reliability_survives_restart() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    sqlx::query("DELETE FROM validator_reliability").execute(&pool).await.ok();
+
+    let config = SchedulerConfig {
+        retry_delay: 0,
+        ..SchedulerConfig::default()
+    };
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+    let claim = |hotkey: &str| scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of(hotkey),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    });
+
+    // Both attempts of the job time out on the flaky validator; the retry
+    // clears the job's validator, but the failures stay on record
+    let job = scheduler.create_job(batch_request(Uuid::new_v4(), None)).await
+        .expect("Failed to create job");
+    for _ in 0..2 {
+        claim("flaky-validator").await.expect("Failed to claim job");
+        scheduler.fail_job(job.id, FailJobRequest {
+            reason: "Job exceeded timeout".to_string(),
+            error_details: None,
+            failure_category: Some(FailureCategory::Timeout),
+            expected_version: None,
+        }).await.expect("Failed to fail job");
+        scheduler.retry_failed_jobs().await.expect("Failed to retry jobs");
+    }
+    claim("steady-validator").await.expect("Failed to claim job");
+    scheduler.complete_job(job.id, empty_result(job.id)).await
+        .expect("Failed to complete job");
+
+    // Reporting the result again does not count twice
+    scheduler.complete_job(job.id, empty_result(job.id)).await
+        .expect("Failed to complete job");
+
+    // A new scheduler, as after a restart, sees the same record
+    let restarted = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+    let flaky = restarted.validator_reliability(&hotkey_of("flaky-validator")).await
+        .expect("Failed to load reliability");
+    assert!((flaky.failed_jobs - 2.0).abs() < 1e-3);
+    assert_eq!(flaky.completed_jobs, 0.0);
+    let steady = restarted.validator_reliability(&hotkey_of("steady-validator")).await
+        .expect("Failed to load reliability");
+    assert!((steady.completed_jobs - 1.0).abs() < 1e-3);
+    assert!(steady.success_rate() > 0.5 && flaky.success_rate() < 0.5);
+
+    let info = restarted.validator_info(&hotkey_of("flaky-validator"), false).await
+        .expect("Failed to load validator info");
+    assert!((info.success_rate - flaky.success_rate()).abs() < 1e-6);
+    assert!(info.effective_trust() < info.trust);
+
+    sqlx::query("DELETE FROM validator_reliability").execute(&pool).await.ok();
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_job_inherits_challenge_default_priority() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO challenges (
+            id, name, compose_hash, compose_yaml, version, images, resources, ports, env,
+            emission_share, mechanism_id, created_at, updated_at, default_job_priority
+        )
+        VALUES ($1, $2, $2, '', '1.0.0', '{}', '{}', '[]', '{}', 0.0, 0, NOW(), NOW(), 'high')
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(format!("priority-test-{}", challenge_id))
+    .execute(&pool)
+    .await
+    .expect("Failed to insert challenge");
+
+    // Jobs without a priority get the challenge's, explicit priorities win
+    let inherited = scheduler.create_job(batch_request(challenge_id, None)).await
+        .expect("Failed to create job");
+    assert_eq!(inherited.priority, JobPriority::High);
+    let explicit = scheduler.create_job(CreateJobRequest {
+        priority: Some(JobPriority::Low),
+        ..batch_request(challenge_id, None)
+    }).await.expect("Failed to create job");
+    assert_eq!(explicit.priority, JobPriority::Low);
+    let stored = scheduler.get_job(inherited.id).await
+        .expect("Failed to get job");
+    assert_eq!(stored.priority, JobPriority::High);
+
+    // Batches and unknown challenges too
+    let batch = scheduler.create_jobs_batch(vec![
+        batch_request(challenge_id, None),
+        batch_request(Uuid::new_v4(), None),
+    ]).await.expect("Failed to create batch");
+    let mut priorities = Vec::new();
+    for result in &batch.results {
+        let job_id = result.job_id.expect("Batch job was not created");
+        priorities.push(scheduler.get_job(job_id).await.expect("Failed to get job").priority);
+    }
+    assert_eq!(priorities, vec![JobPriority::High, JobPriority::Normal]);
+
+    sqlx::query("DELETE FROM challenges WHERE id = $1")
+        .bind(challenge_id)
+        .execute(&pool)
+        .await
+        .ok();
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_job_inherits_challenge_job_defaults() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig {
+        retry_attempts: 4,
+        retry_delay: 0,
+        ..SchedulerConfig::default()
+    };
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO challenges (
+            id, name, compose_hash, compose_yaml, version, images, resources, ports, env,
+            emission_share, mechanism_id, created_at, updated_at, job_defaults
+        )
+        VALUES ($1, $2, $2, '', '1.0.0', '{}', '{}', '[]', '{}', 0.0, 0, NOW(), NOW(), $3)
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(format!("defaults-test-{}", challenge_id))
+    .bind(json!({"runtime": "Sgx", "timeout": 600, "max_retries": 7, "retry_delay": 3600}))
+    .execute(&pool)
+    .await
+    .expect("Failed to insert challenge");
+    let timeout_secs = |job: &JobMetadata| job.timeout.unwrap();
+
+    // Jobs submitted without parameters get the challenge's
+    let inherited = scheduler.create_job(CreateJobRequest {
+        runtime: None,
+        ..batch_request(challenge_id, None)
+    }).await.expect("Failed to create job");
+    assert_eq!(inherited.runtime, RuntimeType::Sgx);
+    assert_eq!(inherited.max_retries, 7);
+    assert_eq!(timeout_secs(&inherited), 600);
+    let stored = scheduler.get_job(inherited.id).await
+        .expect("Failed to get job");
+    assert_eq!(stored.runtime, RuntimeType::Sgx);
+    assert_eq!(stored.max_retries, 7);
+
+    // Explicit parameters win
+    let explicit = scheduler.create_job(CreateJobRequest {
+        max_retries: Some(1),
+        ..batch_request(challenge_id, Some(60))
+    }).await.expect("Failed to create job");
+    assert_eq!(explicit.runtime, RuntimeType::Docker);
+    assert_eq!(explicit.max_retries, 1);
+    assert_eq!(timeout_secs(&explicit), 60);
+
+    // Batches too; jobs of unknown challenges get the scheduler's defaults
+    let batch = scheduler.create_jobs_batch(vec![
+        CreateJobRequest {
+            runtime: None,
+            ..batch_request(challenge_id, None)
+        },
+        CreateJobRequest {
+            runtime: None,
+            ..batch_request(Uuid::new_v4(), None)
+        },
+    ]).await.expect("Failed to create batch");
+    let mut jobs = Vec::new();
+    for result in &batch.results {
+        let job_id = result.job_id.expect("Batch job was not created");
+        jobs.push(scheduler.get_job(job_id).await.expect("Failed to get job"));
+    }
+    assert_eq!(
+        jobs.iter().map(|job| (job.runtime.clone(), job.max_retries)).collect::<Vec<_>>(),
+        vec![(RuntimeType::Sgx, 7), (RuntimeType::Docker, 4)]
+    );
+    assert_eq!(timeout_secs(&jobs[0]), 600);
+
+    // Failed jobs wait for the retry delay of their challenge
+    for job_id in [inherited.id, jobs[1].id] {
+        scheduler.claim_specific_job(job_id, ClaimJobRequest {
+            validator_hotkey: hotkey_of("defaults-validator"),
+            runtime: RuntimeType::Docker,
+            capabilities: vec![],
+        }).await.expect("Failed to claim job");
+        scheduler.fail_job(job_id, FailJobRequest {
+            reason: "exit code 1".to_string(),
+            error_details: None,
+            failure_category: None,
+            expected_version: None,
+        }).await.expect("Failed to fail job");
+    }
+    assert_eq!(scheduler.retry_failed_jobs().await.expect("Failed to retry jobs"), 1);
+    assert_eq!(scheduler.get_job(inherited.id).await.unwrap().status, JobStatus::Failed);
+    assert_eq!(scheduler.get_job(jobs[1].id).await.unwrap().status, JobStatus::Pending);
+
+    sqlx::query("DELETE FROM challenges WHERE id = $1")
+        .bind(challenge_id)
+        .execute(&pool)
+        .await
+        .ok();
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_job_inherits_challenge_resource_requirements() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO challenges (
+            id, name, compose_hash, compose_yaml, version, images, resources, ports, env,
+            emission_share, mechanism_id, created_at, updated_at, resource_requirements
+        )
+        VALUES ($1, $2, $2, '', '1.0.0', '{}', '{}', '[]', '{}', 0.0, 0, NOW(), NOW(), $3)
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(format!("resources-test-{}", challenge_id))
+    .bind(json!({"vcpu": 8, "memory_gb": 32, "gpu_count": 2, "gpu_type": "H100"}))
+    .execute(&pool)
+    .await
+    .expect("Failed to insert challenge");
+    let claim = |hotkey: &str, capabilities: &[&str]| ClaimJobRequest {
+        validator_hotkey: hotkey_of(hotkey),
+        runtime: RuntimeType::Docker,
+        capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+    };
+
+    // Jobs submitted without requirements get the challenge's
+    let inherited = scheduler.create_job(batch_request(challenge_id, None)).await
+        .expect("Failed to create job");
+    assert_eq!(inherited.resources.vcpu, Some(8));
+    assert_eq!(inherited.resources.gpu_type.as_deref(), Some("H100"));
+    assert_eq!(inherited.required_capabilities, vec!["gpu", "gpu:h100"]);
+    let stored = scheduler.get_job(inherited.id).await.expect("Failed to get job");
+    assert_eq!(stored.resources, inherited.resources);
+    assert_eq!(stored.required_capabilities, inherited.required_capabilities);
+
+    // Explicit requirements win, and jobs of unknown challenges have none
+    let explicit = scheduler.create_job(CreateJobRequest {
+        required_capabilities: Some(vec![]),
+        resources: Some(ResourceRequirements {
+            vcpu: Some(2),
+            ..Default::default()
+        }),
+        ..batch_request(challenge_id, None)
+    }).await.expect("Failed to create job");
+    assert_eq!(explicit.resources.vcpu, Some(2));
+    assert_eq!(explicit.resources.gpu_count, None);
+    assert!(explicit.required_capabilities.is_empty());
+    let unknown = scheduler.create_job(batch_request(Uuid::new_v4(), None)).await
+        .expect("Failed to create job");
+    assert!(unknown.resources.is_empty());
+    assert!(unknown.required_capabilities.is_empty());
+
+    // Validators without the capabilities are not given the job
+    let first = scheduler.claim_job(claim("cpu-validator", &[])).await
+        .expect("Failed to claim job");
+    let second = scheduler.claim_job(claim("cpu-validator-2", &["gpu"])).await
+        .expect("Failed to claim job");
+    let mut claimed = vec![first.job.id, second.job.id];
+    claimed.sort();
+    let mut expected = vec![explicit.id, unknown.id];
+    expected.sort();
+    assert_eq!(claimed, expected);
+    assert!(scheduler.claim_job(claim("cpu-validator-3", &["gpu"])).await.is_err());
+    assert!(scheduler.claim_specific_job(inherited.id, claim("cpu-validator-3", &["gpu"])).await.is_err());
+
+    // Nor are validators that registered fewer resources than it requires
+    let small = hotkey_of("small-gpu-validator");
+    sqlx::query(
+        r#"
+        INSERT INTO validators (hotkey, gpu, memory_gb, cpu_cores, gpu_count, gpu_type)
+        VALUES ($1, TRUE, 16, 8, 2, 'h100')
+        "#,
+    )
+    .bind(small.as_str())
+    .execute(&pool)
+    .await
+    .expect("Failed to register validator");
+    assert!(scheduler
+        .claim_job(claim("small-gpu-validator", &["gpu", "gpu:h100"]))
+        .await
+        .is_err());
+    assert!(matches!(
+        scheduler
+            .claim_specific_job(
+                inherited.id,
+                claim("small-gpu-validator", &["gpu", "gpu:h100"])
+            )
+            .await,
+        Err(PlatformError::Conflict { .. })
+    ));
+    sqlx::query("DELETE FROM validators WHERE hotkey = $1")
+        .bind(small.as_str())
+        .execute(&pool)
+        .await
+        .expect("Failed to remove validator");
+
+    // Invalid requirements are refused
+    let err = scheduler
+        .create_job(CreateJobRequest {
+            resources: Some(ResourceRequirements {
+                memory_gb: Some(0),
+                ..Default::default()
+            }),
+            ..batch_request(challenge_id, None)
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PlatformError::Validation { .. }), "{}", err);
+
+    // A validator with them is, along with the resources to run it with
+    let gpu = scheduler.claim_job(claim("gpu-validator", &["gpu", "gpu:h100"])).await
+        .expect("Failed to claim job");
+    assert_eq!(gpu.job.id, inherited.id);
+    assert_eq!(gpu.config.resources.cpu_cores, 8);
+    assert_eq!(gpu.config.resources.memory_mb, 32 * 1024);
+
+    sqlx::query("DELETE FROM challenges WHERE id = $1")
+        .bind(challenge_id)
+        .execute(&pool)
+        .await
+        .ok();
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_job_payload_checked_against_challenge_schema() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let config = SchedulerConfig::default();
+    let scheduler = SchedulerService::with_database(&config, Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+
+    let challenge_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO challenges (
+            id, name, compose_hash, compose_yaml, version, images, resources, ports, env,
+            emission_share, mechanism_id, created_at, updated_at, job_payload_schema
+        )
+        VALUES ($1, $2, $2, '', '1.0.0', '{}', '{}', '[]', '{}', 0.0, 0, NOW(), NOW(), $3)
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(format!("schema-test-{}", challenge_id))
+    .bind(json!({
+        "type": "object",
+        "required": ["task"],
+        "properties": { "task": { "enum": ["eval", "train"] } }
+    }))
+    .execute(&pool)
+    .await
+    .expect("Failed to insert challenge");
+
+    scheduler.create_job(batch_request(challenge_id, None)).await
+        .expect("Matching payload was rejected");
+    let err = scheduler.create_job(CreateJobRequest {
+        payload: json!({"task": "deploy"}),
+        ..batch_request(challenge_id, None)
+    }).await.expect_err("Invalid payload was accepted");
+    assert!(matches!(
+        &err,
+        PlatformError::Validation { fields } if fields[0].field == "payload/task"
+    ), "{}", err);
+
+    // Challenges without a schema accept any payload
+    scheduler.create_job(CreateJobRequest {
+        payload: json!({"task": "deploy"}),
+        ..batch_request(Uuid::new_v4(), None)
+    }).await.expect("Payload of a challenge without schema was rejected");
+
+    // An invalid payload rejects the whole batch
+    let batch = scheduler.create_jobs_batch(vec![
+        batch_request(challenge_id, None),
+        CreateJobRequest {
+            payload: json!({"steps": []}),
+            ..batch_request(challenge_id, None)
+        },
+    ]).await.expect("Failed to create batch");
+    assert!(!batch.created);
+    assert_eq!(batch.results.len(), 1);
+    assert_eq!(batch.results[0].index, 1);
+    assert_eq!(
+        batch.results[0].error.as_deref(),
+        Some("Invalid job payload at payload/task: required property is missing")
+    );
+    assert_eq!(count_jobs(&pool, challenge_id).await, 1);
+
+    sqlx::query("DELETE FROM challenges WHERE id = $1")
+        .bind(challenge_id)
+        .execute(&pool)
+        .await
+        .ok();
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_conflicting_job_updates_apply_once() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let challenge_id = Uuid::new_v4();
+    let scheduler = Arc::new(
+        SchedulerService::with_database(&SchedulerConfig::default(), Arc::new(pool.clone()))
+            .expect("Failed to create scheduler"),
+    );
+    scheduler.create_jobs_batch(vec![batch_request(challenge_id, None)]).await
+        .expect("Failed to create job");
+    let claimed = scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("validator-a"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job").job;
+
+    let (job_id, version) = (claimed.id, claimed.version);
+
+    // The validator's result and a failure race, both expecting the claimed job
+    let complete = {
+        let scheduler = scheduler.clone();
+        let mut result = empty_result(job_id);
+        result.expected_version = Some(version);
+        tokio::spawn(async move { scheduler.complete_job(job_id, result).await })
+    };
+    let fail = {
+        let scheduler = scheduler.clone();
+        tokio::spawn(async move {
+            scheduler.fail_job(job_id, FailJobRequest {
+                reason: "Job cancelled".to_string(),
+                error_details: None,
+                failure_category: None,
+                expected_version: Some(version),
+            }).await
+        })
+    };
+    let completed = complete.await.expect("Complete task panicked");
+    let failed = fail.await.expect("Fail task panicked");
+
+    assert!(completed.is_ok() != failed.is_ok(), "Exactly one update must apply");
+    for err in [&completed, &failed].into_iter().filter_map(|r| r.as_ref().err()) {
+        assert!(matches!(err, PlatformError::Conflict { .. }), "{}", err);
+    }
+
+    let job = scheduler.get_job(job_id).await.expect("Failed to get job");
+    assert_eq!(job.version, version + 1);
+    let expected = if completed.is_ok() { JobStatus::Completed } else { JobStatus::Failed };
+    assert_eq!(job.status, expected);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_job_receipts_stored_on_completion() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let scheduler = SchedulerService::with_database(&SchedulerConfig::default(), Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+    scheduler.create_jobs_batch(vec![batch_request(Uuid::new_v4(), None)]).await
+        .expect("Failed to create job");
+    let job_id = scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("validator-a"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job").job.id;
+
+    let receipts = scheduler.get_job_receipts(job_id).await.expect("Failed to get receipts");
+    assert!(receipts.receipts.is_empty());
+    assert!(receipts.completed_at.is_none());
+
+    let mut result = empty_result(job_id);
+    result.receipts = vec!["receipt-1".to_string(), "receipt-2".to_string()];
+    result.result.attestation_receipt = Some("attestation-receipt".to_string());
+    scheduler.complete_job(job_id, result).await.expect("Failed to complete job");
+
+    let receipts = scheduler.get_job_receipts(job_id).await.expect("Failed to get receipts");
+    assert_eq!(receipts.job_id, job_id);
+    assert_eq!(receipts.validator_hotkey.as_deref(), Some(hotkey_of("validator-a").as_str()));
+    assert_eq!(receipts.receipts, vec!["receipt-1", "receipt-2"]);
+    assert_eq!(receipts.attestation_receipt.as_deref(), Some("attestation-receipt"));
+    assert!(receipts.completed_at.is_some());
+
+    assert!(scheduler.get_job_receipts(Uuid::new_v4()).await.is_err());
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_out_of_bounds_scores_rejected_or_clamped() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let challenge_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO challenges (
+            id, name, compose_hash, compose_yaml, version, images, resources, ports, env,
+            emission_share, mechanism_id, created_at, updated_at, score_bounds
+        )
+        VALUES ($1, $2, $2, '', '1.0.0', '{}', '{}', '[]', '{}', 0.0, 0, NOW(), NOW(), $3)
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(format!("bounds-test-{}", challenge_id))
+    .bind(json!({"min": 0.0, "max": 1.0}))
+    .execute(&pool)
+    .await
+    .expect("Failed to insert challenge");
+
+    let scheduler = SchedulerService::with_database(&SchedulerConfig::default(), Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+    let claim = || scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("validator-a"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    });
+    let scored = |job_id: Id, score: f64| {
+        let mut result = empty_result(job_id);
+        result.result.scores.insert("accuracy".to_string(), score);
+        result
+    };
+
+    // Results out of bounds are refused and the job stays claimed
+    let job = scheduler.create_job(batch_request(challenge_id, None)).await
+        .expect("Failed to create job");
+    claim().await.expect("Failed to claim job");
+    let err = scheduler.complete_job(job.id, scored(job.id, 1.5)).await
+        .expect_err("Out of bounds score accepted");
+    assert!(matches!(
+        &err,
+        PlatformError::Validation { fields } if fields[0].field == "scores/accuracy"
+    ), "{}", err);
+    assert!(err.to_string().contains("1.5"), "{}", err);
+    let claimed = scheduler.get_job(job.id).await.expect("Failed to get job");
+    assert_eq!(claimed.status, JobStatus::Claimed);
+
+    scheduler.complete_job(job.id, scored(job.id, 1.0)).await
+        .expect("Failed to complete job");
+
+    // Challenges that clamp store the clamped score and record the raw one
+    sqlx::query(r#"UPDATE challenges SET score_bounds = $2 WHERE id = $1"#)
+        .bind(challenge_id)
+        .bind(json!({"min": 0.0, "max": 1.0, "out_of_range": "Clamp"}))
+        .execute(&pool)
+        .await
+        .expect("Failed to update challenge");
+    let job = scheduler.create_job(batch_request(challenge_id, None)).await
+        .expect("Failed to create job");
+    claim().await.expect("Failed to claim job");
+    scheduler.complete_job(job.id, scored(job.id, 1e9)).await
+        .expect("Failed to complete job");
+
+    let (result,): (serde_json::Value,) = sqlx::query_as("SELECT result FROM jobs WHERE id = $1")
+        .bind(job.id)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to load job result");
+    assert_eq!(result["scores"]["accuracy"], json!(1.0));
+    let (details,): (serde_json::Value,) = sqlx::query_as(
+        "SELECT metadata FROM job_events WHERE job_id = $1 AND new_status = 'completed'",
+    )
+    .bind(job.id)
+    .fetch_one(&pool)
+    .await
+    .expect("Failed to load completion event");
+    assert_eq!(details["raw_scores"], json!({"accuracy": 1e9}));
+
+    sqlx::query("DELETE FROM challenges WHERE id = $1")
+        .bind(challenge_id)
+        .execute(&pool)
+        .await
+        .ok();
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_score_policy_violations_refused() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    let challenge_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO challenges (
+            id, name, compose_hash, compose_yaml, version, images, resources, ports, env,
+            emission_share, mechanism_id, created_at, updated_at, score_policy
+        )
+        VALUES ($1, $2, $2, '', '1.0.0', '{}', '{}', '[]', '{}', 0.0, 0, NOW(), NOW(), $3)
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(format!("policy-test-{}", challenge_id))
+    .bind(json!({"required_scores": ["accuracy"], "max_metrics": 1}))
+    .execute(&pool)
+    .await
+    .expect("Failed to insert challenge");
+
+    let scheduler = SchedulerService::with_database(&SchedulerConfig::default(), Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+    let job = scheduler.create_job(batch_request(challenge_id, None)).await
+        .expect("Failed to create job");
+    scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("validator-a"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    }).await.expect("Failed to claim job");
+
+    // Every violation is listed and the job stays claimed
+    let mut result = empty_result(job.id);
+    result.result.scores.insert("f1".to_string(), f64::NAN);
+    result.result.metrics.insert("latency_ms".to_string(), 12.0);
+    result.result.metrics.insert("tokens".to_string(), 3e4);
+    let err = scheduler.complete_job(job.id, result).await
+        .expect_err("Result breaking the score policy accepted");
+    assert_eq!(err.status_code(), 422);
+    let PlatformError::Unprocessable { fields } = &err else {
+        panic!("Unexpected error: {}", err);
+    };
+    let fields: Vec<(&str, Option<&str>)> = fields
+        .iter()
+        .map(|field| (field.field.as_str(), field.rule.as_deref()))
+        .collect();
+    assert_eq!(fields, vec![
+        ("scores/accuracy", Some("required_scores")),
+        ("scores/f1", Some("require_finite")),
+        ("metrics", Some("max_metrics")),
+    ]);
+    let claimed = scheduler.get_job(job.id).await.expect("Failed to get job");
+    assert_eq!(claimed.status, JobStatus::Claimed);
+
+    let mut result = empty_result(job.id);
+    result.result.scores.insert("accuracy".to_string(), 0.9);
+    scheduler.complete_job(job.id, result).await
+        .expect("Failed to complete job");
+
+    sqlx::query("DELETE FROM challenges WHERE id = $1")
+        .bind(challenge_id)
+        .execute(&pool)
+        .await
+        .ok();
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_wide_score_bounds_accepted_without_a_policy() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    // Bounds wider than 0..=1, and the default policy
+    let challenge_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO challenges (
+            id, name, compose_hash, compose_yaml, version, images, resources, ports, env,
+            emission_share, mechanism_id, created_at, updated_at, score_bounds
+        )
+        VALUES ($1, $2, $2, '', '1.0.0', '{}', '{}', '[]', '{}', 0.0, 0, NOW(), NOW(), $3)
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(format!("wide-bounds-test-{}", challenge_id))
+    .bind(json!({"min": 0.0, "max": 100.0}))
+    .execute(&pool)
+    .await
+    .expect("Failed to insert challenge");
+
+    let scheduler = SchedulerService::with_database(&SchedulerConfig::default(), Arc::new(pool.clone()))
+        .expect("Failed to create scheduler");
+    let claim = || scheduler.claim_job(ClaimJobRequest {
+        validator_hotkey: hotkey_of("validator-a"),
+        runtime: RuntimeType::Docker,
+        capabilities: vec![],
+    });
+    let scored = |job_id: Id, score: f64| {
+        let mut result = empty_result(job_id);
+        result.result.scores.insert("accuracy".to_string(), score);
+        result
+    };
+
+    // Scores within the bounds are accepted, those outside refused by them
+    let job = scheduler.create_job(batch_request(challenge_id, None)).await
+        .expect("Failed to create job");
+    claim().await.expect("Failed to claim job");
+    let err = scheduler.complete_job(job.id, scored(job.id, 150.0)).await
+        .expect_err("Out of bounds score accepted");
+    assert!(matches!(err, PlatformError::Validation { .. }), "{}", err);
+    scheduler.complete_job(job.id, scored(job.id, 42.0)).await
+        .expect("Score within the bounds refused");
+
+    // A default range the policy sets itself still applies
+    sqlx::query(r#"UPDATE challenges SET score_policy = $2 WHERE id = $1"#)
+        .bind(challenge_id)
+        .bind(json!({"default_range": {"min": 0.0, "max": 10.0}}))
+        .execute(&pool)
+        .await
+        .expect("Failed to update challenge");
+    let job = scheduler.create_job(batch_request(challenge_id, None)).await
+        .expect("Failed to create job");
+    claim().await.expect("Failed to claim job");
+    let err = scheduler.complete_job(job.id, scored(job.id, 42.0)).await
+        .expect_err("Score outside the policy's range accepted");
+    assert_eq!(err.status_code(), 422);
+
+    sqlx::query("DELETE FROM challenges WHERE id = $1")
+        .bind(challenge_id)
+        .execute(&pool)
+        .await
+        .ok();
+    cleanup_test_data(&pool).await;
+}
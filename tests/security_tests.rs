@@ -65,7 +65,7 @@ fn test_compose_hash_consistency() {
 fn test_security_key_derivation() {
     let compose_hash = "test-compose-hash-12345";
     
-    let security = PlatformSecurity::new_with_compose_hash(compose_hash)
+    let security = PlatformSecurity::new_with_compose_hash(compose_hash, b"test-key-derivation-secret")
         .expect("Failed to create PlatformSecurity");
     
     // Verify compose hash is stored
@@ -86,10 +86,10 @@ fn test_security_deterministic_keys() {
     // Same compose hash should produce same keys
     let compose_hash = "test-compose-hash-deterministic";
     
-    let security1 = PlatformSecurity::new_with_compose_hash(compose_hash)
+    let security1 = PlatformSecurity::new_with_compose_hash(compose_hash, b"test-key-derivation-secret")
         .expect("Failed to create PlatformSecurity");
     
-    let security2 = PlatformSecurity::new_with_compose_hash(compose_hash)
+    let security2 = PlatformSecurity::new_with_compose_hash(compose_hash, b"test-key-derivation-secret")
         .expect("Failed to create PlatformSecurity");
     
     // Same compose hash should produce same public key
@@ -110,7 +110,7 @@ fn test_security_deterministic_keys() {
 #[test]
 fn test_signed_header_creation() {
     let compose_hash = "test-compose-hash";
-    let security = PlatformSecurity::new_with_compose_hash(compose_hash)
+    let security = PlatformSecurity::new_with_compose_hash(compose_hash, b"test-key-derivation-secret")
         .expect("Failed to create PlatformSecurity");
     
     let timestamp = 1234567890;
@@ -121,7 +121,8 @@ fn test_signed_header_creation() {
     // Header should contain signature and message
     assert!(header.contains(':'));
     let parts: Vec<&str> = header.split(':').collect();
-    assert_eq!(parts.len(), 3); // signature:timestamp:nonce
+    assert_eq!(parts.len(), 4); // signature:key_id:timestamp:nonce
+    assert_eq!(parts[1], security.get_key_id());
     
     // Signature should be hex-encoded (128 chars for 64 bytes)
     assert_eq!(parts[0].len(), 128);